@@ -35,6 +35,9 @@ use either::Either;
 use fxhash::FxHashSet;
 
 use next_transform_font::next_font_loaders;
+use next_transform_react_remove_properties::remove_properties;
+use next_transform_remove_console::remove_console;
+use next_transform_server_actions::server_actions;
 use serde::Deserialize;
 use std::cell::RefCell;
 use std::rc::Rc;
@@ -54,14 +57,10 @@ pub mod disallow_re_export_all_in_page;
 pub mod next_dynamic;
 pub mod next_ssg;
 pub mod page_config;
-pub mod react_remove_properties;
 pub mod react_server_components;
 #[cfg(not(target_arch = "wasm32"))]
 pub mod relay;
-pub mod remove_console;
-pub mod server_actions;
 pub mod shake_exports;
-mod top_level_binding_collector;
 
 #[derive(Clone, Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -100,10 +99,10 @@ pub struct TransformOptions {
     pub styled_components: Option<next_binding::swc::custom_transform::styled_components::Config>,
 
     #[serde(default)]
-    pub remove_console: Option<remove_console::Config>,
+    pub remove_console: Option<next_transform_remove_console::Config>,
 
     #[serde(default)]
-    pub react_remove_properties: Option<react_remove_properties::Config>,
+    pub react_remove_properties: Option<next_transform_react_remove_properties::Config>,
 
     #[serde(default)]
     #[cfg(not(target_arch = "wasm32"))]
@@ -128,7 +127,7 @@ pub struct TransformOptions {
     pub font_loaders: Option<next_transform_font::Config>,
 
     #[serde(default)]
-    pub server_actions: Option<server_actions::Config>,
+    pub server_actions: Option<next_transform_server_actions::Config>,
 }
 
 pub fn custom_before_pass<'a, C: Comments + 'a>(
@@ -213,13 +212,11 @@ where
         ),
         relay_plugin,
         match &opts.remove_console {
-            Some(config) if config.truthy() =>
-                Either::Left(remove_console::remove_console(config.clone())),
+            Some(config) if config.truthy() => Either::Left(remove_console(config.clone())),
             _ => Either::Right(noop()),
         },
         match &opts.react_remove_properties {
-            Some(config) if config.truthy() =>
-                Either::Left(react_remove_properties::remove_properties(config.clone())),
+            Some(config) if config.truthy() => Either::Left(remove_properties(config.clone())),
             _ => Either::Right(noop()),
         },
         match &opts.shake_exports {
@@ -261,11 +258,7 @@ where
             None => Either::Right(noop()),
         },
         match &opts.server_actions {
-            Some(config) => Either::Left(server_actions::server_actions(
-                &file.name,
-                config.clone(),
-                comments,
-            )),
+            Some(config) => Either::Left(server_actions(&file.name, config.clone(), comments)),
             None => Either::Right(noop()),
         },
     )
@@ -1,22 +1,16 @@
 use next_binding::swc::{
     core::{
-        common::{chain, FileName, Mark},
+        common::FileName,
         ecma::{
             parser::{EsConfig, Syntax},
-            transforms::{
-                base::resolver,
-                testing::{test_fixture, FixtureTestConfig},
-            },
+            transforms::testing::{test_fixture, FixtureTestConfig},
         },
     },
     testing::fixture,
 };
 use next_swc::{
-    disallow_re_export_all_in_page::disallow_re_export_all_in_page,
-    next_dynamic::next_dynamic,
-    next_ssg::next_ssg,
-    react_server_components::server_components,
-    server_actions::{self, server_actions},
+    disallow_re_export_all_in_page::disallow_re_export_all_in_page, next_dynamic::next_dynamic,
+    next_ssg::next_ssg, react_server_components::server_components,
 };
 use next_transform_font::{next_font_loaders, Config as FontLoaderConfig};
 use std::path::PathBuf;
@@ -148,27 +142,3 @@ fn next_font_loaders_errors(input: PathBuf) {
         },
     );
 }
-
-#[fixture("tests/errors/server-actions/**/input.js")]
-fn react_server_actions_errors(input: PathBuf) {
-    let output = input.parent().unwrap().join("output.js");
-    test_fixture(
-        syntax(),
-        &|_tr| {
-            chain!(
-                resolver(Mark::new(), Mark::new(), false),
-                server_actions(
-                    &FileName::Real("/app/item.js".into()),
-                    server_actions::Config { is_server: true },
-                    _tr.comments.as_ref().clone(),
-                )
-            )
-        },
-        &input,
-        &output,
-        FixtureTestConfig {
-            allow_error: true,
-            ..Default::default()
-        },
-    );
-}
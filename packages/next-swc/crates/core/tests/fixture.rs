@@ -2,7 +2,6 @@ use next_binding::swc::{
     core::{
         common::{chain, comments::SingleThreadedComments, FileName, Mark},
         ecma::parser::{EsConfig, Syntax},
-        ecma::transforms::base::resolver,
         ecma::transforms::react::jsx,
         ecma::transforms::testing::{test, test_fixture},
     },
@@ -13,11 +12,8 @@ use next_swc::{
     next_dynamic::next_dynamic,
     next_ssg::next_ssg,
     page_config::page_config_test,
-    react_remove_properties::remove_properties,
     react_server_components::server_components,
     relay::{relay, Config as RelayConfig, RelayLanguageConfig},
-    remove_console::remove_console,
-    server_actions::{self, server_actions},
     shake_exports::{shake_exports, Config as ShakeExportsConfig},
 };
 use next_transform_font::{next_font_loaders, Config as FontLoaderConfig};
@@ -161,48 +157,6 @@ fn relay_no_artifact_dir_fixture(input: PathBuf) {
     );
 }
 
-#[fixture("tests/fixture/remove-console/**/input.js")]
-fn remove_console_fixture(input: PathBuf) {
-    let output = input.parent().unwrap().join("output.js");
-    test_fixture(
-        syntax(),
-        &|_tr| remove_console(next_swc::remove_console::Config::All(true)),
-        &input,
-        &output,
-        Default::default(),
-    );
-}
-
-#[fixture("tests/fixture/react-remove-properties/default/**/input.js")]
-fn react_remove_properties_default_fixture(input: PathBuf) {
-    let output = input.parent().unwrap().join("output.js");
-    test_fixture(
-        syntax(),
-        &|_tr| remove_properties(next_swc::react_remove_properties::Config::All(true)),
-        &input,
-        &output,
-        Default::default(),
-    );
-}
-
-#[fixture("tests/fixture/react-remove-properties/custom/**/input.js")]
-fn react_remove_properties_custom_fixture(input: PathBuf) {
-    let output = input.parent().unwrap().join("output.js");
-    test_fixture(
-        syntax(),
-        &|_tr| {
-            remove_properties(next_swc::react_remove_properties::Config::WithOptions(
-                next_swc::react_remove_properties::Options {
-                    properties: vec!["^data-custom$".into()],
-                },
-            ))
-        },
-        &input,
-        &output,
-        Default::default(),
-    );
-}
-
 #[fixture("tests/fixture/shake-exports/most-usecases/input.js")]
 fn shake_exports_fixture(input: PathBuf) {
     let output = input.parent().unwrap().join("output.js");
@@ -300,45 +254,3 @@ fn next_font_loaders_fixture(input: PathBuf) {
         Default::default(),
     );
 }
-
-#[fixture("tests/fixture/server-actions/server/**/input.js")]
-fn server_actions_server_fixture(input: PathBuf) {
-    let output = input.parent().unwrap().join("output.js");
-    test_fixture(
-        syntax(),
-        &|_tr| {
-            chain!(
-                resolver(Mark::new(), Mark::new(), false),
-                server_actions(
-                    &FileName::Real("/app/item.js".into()),
-                    server_actions::Config { is_server: true },
-                    _tr.comments.as_ref().clone(),
-                )
-            )
-        },
-        &input,
-        &output,
-        Default::default(),
-    );
-}
-
-#[fixture("tests/fixture/server-actions/client/**/input.js")]
-fn server_actions_client_fixture(input: PathBuf) {
-    let output = input.parent().unwrap().join("output.js");
-    test_fixture(
-        syntax(),
-        &|_tr| {
-            chain!(
-                resolver(Mark::new(), Mark::new(), false),
-                server_actions(
-                    &FileName::Real("/app/item.js".into()),
-                    server_actions::Config { is_server: false },
-                    _tr.comments.as_ref().clone(),
-                )
-            )
-        },
-        &input,
-        &output,
-        Default::default(),
-    );
-}
@@ -42,6 +42,9 @@ use next_binding::swc::core::{
     common::{comments::SingleThreadedComments, errors::ColorConfig, FileName, GLOBALS},
     ecma::transforms::base::pass::noop,
 };
+use next_binding::turbo::next_core::test_transform::{
+    test_transform_options, TestTransformOptions,
+};
 use next_swc::{custom_before_pass, TransformOptions};
 
 use crate::{complete_output, get_compiler, util::MapErr};
@@ -205,6 +208,22 @@ pub fn transform_sync(
     let output = task.compute()?;
     task.resolve(env, output)
 }
+
+/// Transforms a module for tests like `transform_sync`, with the options of
+/// `next/jest`, see [test_transform_options].
+#[napi]
+pub fn transform_for_test_sync(env: Env, src: String, options: Buffer) -> napi::Result<Object> {
+    let options: TestTransformOptions = serde_json::from_slice(&options)?;
+    let options = serde_json::to_vec(&test_transform_options(&options))?;
+
+    let mut task = TransformTask {
+        c: get_compiler(),
+        input: Input::Source { src },
+        options: options.into(),
+    };
+    let output = task.compute()?;
+    task.resolve(env, output)
+}
 #[test]
 fn test_deser() {
     const JSON_STR: &str = r#"{"jsc":{"parser":{"syntax":"ecmascript","dynamicImport":true,"jsx":true},"transform":{"react":{"runtime":"automatic","pragma":"React.createElement","pragmaFrag":"React.Fragment","throwIfNamespace":true,"development":false,"useBuiltins":true}},"target":"es5"},"filename":"/Users/timneutkens/projects/next.js/packages/next/dist/client/next.js","sourceMaps":false,"sourceFileName":"/Users/timneutkens/projects/next.js/packages/next/dist/client/next.js"}"#;
@@ -3,7 +3,14 @@ use std::convert::TryFrom;
 use crate::util::MapErr;
 use napi::bindgen_prelude::*;
 use next_binding::turbo::{
-    next_build::{next_build as turbo_next_build, NextBuildOptions},
+    next_build::{
+        next_build as turbo_next_build,
+        transform_config::{
+            export_transform_config as turbo_export_transform_config, TransformConfigOptions,
+        },
+        NextBuildOptions,
+    },
+    next_core::manifests::PreviewProps as NextPreviewProps,
     next_dev::{devserver_options::DevServerOptions, start_server},
 };
 
@@ -22,6 +29,22 @@ pub struct NextBuildContext {
     pub rewrites: Option<Rewrites>,
     pub original_rewrites: Option<Rewrites>,
     pub original_redirects: Option<Vec<Redirect>>,
+    /// Whether the config sets `output: 'standalone'`.
+    pub standalone: Option<bool>,
+    /// Whether the config sets `output: 'export'`.
+    pub static_export: Option<bool>,
+    pub build_id: Option<String>,
+    pub preview_props: Option<PreviewProps>,
+    /// The `experimental.turbo.memoryLimit` config, in MB.
+    pub memory_limit: Option<u32>,
+}
+
+#[napi(object, object_to_js = false)]
+#[derive(Debug)]
+pub struct PreviewProps {
+    pub preview_mode_id: String,
+    pub preview_mode_signing_key: String,
+    pub preview_mode_encryption_key: String,
 }
 
 #[napi(object, object_to_js = false)]
@@ -37,6 +60,8 @@ pub struct Rewrites {
 pub struct Rewrite {
     pub source: String,
     pub destination: String,
+    pub has: Option<Vec<RouteHas>>,
+    pub missing: Option<Vec<RouteHas>>,
 }
 
 #[napi(object, object_to_js = false)]
@@ -46,8 +71,8 @@ pub struct Redirect {
     pub destination: String,
     pub permanent: Option<bool>,
     pub status_code: Option<u32>,
-    pub has: Option<RouteHas>,
-    pub missing: Option<RouteHas>,
+    pub has: Option<Vec<RouteHas>>,
+    pub missing: Option<Vec<RouteHas>>,
 }
 
 #[derive(Debug)]
@@ -98,13 +123,32 @@ impl From<NextBuildContext> for NextBuildOptions {
     fn from(value: NextBuildContext) -> Self {
         Self {
             dir: value.dir,
-            memory_limit: None,
+            memory_limit: value.memory_limit.map(|limit| limit as usize),
             full_stats: None,
+            inputs_manifest: None,
+            dist_dir: None,
+            source_maps: None,
+            standalone: value.standalone,
+            static_export: value.static_export,
+            build_id: value.build_id,
+            preview_props: value.preview_props.map(|preview_props| NextPreviewProps {
+                preview_mode_id: preview_props.preview_mode_id,
+                preview_mode_signing_key: preview_props.preview_mode_signing_key,
+                preview_mode_encryption_key: preview_props.preview_mode_encryption_key,
+            }),
         }
     }
 }
 
+/// Returns the summary of the build as JSON, see `BuildSummary`.
+#[napi]
+pub async fn next_build(ctx: NextBuildContext) -> napi::Result<String> {
+    let summary = turbo_next_build(ctx.into()).await.convert_err()?;
+    Ok(serde_json::to_string(&summary)?)
+}
+
 #[napi]
-pub async fn next_build(ctx: NextBuildContext) -> napi::Result<()> {
-    turbo_next_build(ctx.into()).await.convert_err()
+pub async fn export_transform_config(options: Buffer) -> napi::Result<String> {
+    let options: TransformConfigOptions = serde_json::from_slice(&options)?;
+    turbo_export_transform_config(options).await.convert_err()
 }
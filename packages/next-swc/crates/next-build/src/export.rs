@@ -0,0 +1,82 @@
+use std::{fs, path::Path};
+
+use anyhow::{bail, Context, Result};
+use next_core::static_export::{ExportedPage, ExportedPagesVc};
+use turbo_tasks::TryJoinIterExt;
+use turbopack_core::issue::{IssueSeverity, IssueVc};
+
+/// The directory of the output of `output: 'export'`, relative to the project
+/// directory.
+pub const EXPORT_DIR: &str = "out";
+
+/// Fails the build when exporting the pages emitted errors, e.g. for routes
+/// which need a server, as the export would be missing them.
+pub async fn fail_on_export_errors(pages: ExportedPagesVc) -> Result<()> {
+    let captured = IssueVc::peek_issues_with_path(pages)
+        .await?
+        .strongly_consistent()
+        .await?;
+    let issues = captured
+        .iter_with_shortest_path()
+        .map(|(issue, path)| issue.into_plain(path))
+        .try_join()
+        .await?;
+    let errors = issues
+        .iter()
+        .filter(|issue| issue.severity <= IssueSeverity::Error)
+        .map(|issue| format!("{}\n{}", issue.title, issue.description))
+        .collect::<Vec<_>>();
+    if !errors.is_empty() {
+        bail!("Failed to export the app:\n\n{}", errors.join("\n\n"));
+    }
+    Ok(())
+}
+
+/// Writes the output of `output: 'export'` to [EXPORT_DIR]: the HTML of the
+/// pages, the `public` directory and the static assets of the build in
+/// `_next/static`, which is everything a static file server needs.
+pub fn write_export_output(
+    project_dir: &Path,
+    output_dir: &Path,
+    pages: &[ExportedPage],
+) -> Result<()> {
+    let export_dir = project_dir.join(EXPORT_DIR);
+    if export_dir.exists() {
+        fs::remove_dir_all(&export_dir).context("failed to remove the previous export")?;
+    }
+    fs::create_dir_all(&export_dir)?;
+
+    copy_dir(&project_dir.join("public"), &export_dir)?;
+    copy_dir(&output_dir.join("static"), &export_dir.join("_next/static"))?;
+
+    // Pages take precedence over public files with the same path, like when
+    // serving them.
+    for page in pages {
+        let target = export_dir.join(&page.file);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&target, &page.html)
+            .with_context(|| format!("failed to write {} of {}", page.file, page.pathname))?;
+    }
+    Ok(())
+}
+
+/// Copies the files of a directory and its subdirectories into another one.
+fn copy_dir(from: &Path, to: &Path) -> Result<()> {
+    if !from.exists() {
+        return Ok(());
+    }
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let target = to.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir(&entry.path(), &target)?;
+        } else {
+            fs::create_dir_all(to)?;
+            fs::copy(entry.path(), &target)
+                .with_context(|| format!("failed to copy {}", entry.path().display()))?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,100 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::summary::BUILD_SUMMARY;
+
+/// The file name of the integrity manifest in the output directory.
+pub const INTEGRITY_MANIFEST: &str = "integrity-manifest.json";
+
+/// Lists every file of a build output with its size and hash, so deployments
+/// only need to upload the files which changed since the last build, and can
+/// verify the uploaded files.
+#[derive(Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IntegrityManifest {
+    pub version: u32,
+    /// The files by their path relative to the output directory, with `/` as
+    /// separator.
+    pub files: BTreeMap<String, OutputFileEntry>,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OutputFileEntry {
+    /// The size in bytes.
+    pub size: u64,
+    /// The SHA-256 digest in the format of the `integrity` attribute of
+    /// subresource integrity, e.g. `sha256-47DEQpj8...`.
+    pub integrity: String,
+}
+
+/// Hashes the files in `output_dir` and writes the manifest to
+/// [INTEGRITY_MANIFEST] in it. This runs last, after everything else changing
+/// the output, e.g. stripping source maps.
+pub fn write_integrity_manifest(output_dir: &Path) -> Result<IntegrityManifest> {
+    let mut paths = Vec::new();
+    collect_files(output_dir, &mut paths)?;
+
+    let mut manifest = IntegrityManifest {
+        version: 1,
+        files: BTreeMap::new(),
+    };
+    for path in paths {
+        let relative = path
+            .strip_prefix(output_dir)?
+            .to_string_lossy()
+            .replace('\\', "/");
+        // The cache and the build summary aren't part of the output that is
+        // deployed.
+        if relative == INTEGRITY_MANIFEST
+            || relative == BUILD_SUMMARY
+            || relative.starts_with("cache/")
+        {
+            continue;
+        }
+        let content = fs::read(&path)
+            .with_context(|| format!("failed to read the output file {}", path.display()))?;
+        manifest.files.insert(
+            relative,
+            OutputFileEntry {
+                size: content.len() as u64,
+                integrity: format!("sha256-{}", STANDARD.encode(Sha256::digest(&content))),
+            },
+        );
+    }
+
+    if output_dir.exists() {
+        fs::write(
+            output_dir.join(INTEGRITY_MANIFEST),
+            serde_json::to_string_pretty(&manifest)?,
+        )
+        .context("failed to write the integrity manifest")?;
+    }
+    Ok(manifest)
+}
+
+/// Collects the files of a directory and its subdirectories.
+fn collect_files(dir: &Path, paths: &mut Vec<PathBuf>) -> Result<()> {
+    if !dir.exists() {
+        return Ok(());
+    }
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            collect_files(&path, paths)?;
+        } else if file_type.is_file() {
+            paths.push(path);
+        }
+    }
+    Ok(())
+}
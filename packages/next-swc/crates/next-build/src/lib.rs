@@ -1,5 +1,53 @@
-use turbo_tasks::{NothingVc, StatsType, TurboTasks, TurboTasksBackendApi};
+use std::{
+    collections::HashSet,
+    net::{Ipv4Addr, SocketAddr},
+    path::Path,
+    sync::{Arc, Mutex},
+    time::Instant,
+};
+
+use next_core::{
+    browserslist::browserslist_query,
+    entry_compilation::{compile_build_entries, write_build_entries},
+    env::load_env,
+    inputs_manifest::{get_build_inputs, verify_inputs_manifest, write_inputs_manifest},
+    manifests::{
+        count_routes, write_build_manifests, write_prerender_manifest, write_routes_manifests,
+        PreviewProps,
+    },
+    mode::NextMode,
+    next_config::load_next_config,
+    standalone::trace_server_files,
+    static_export::export_static_pages,
+};
+use turbo_tasks::{CompletionVc, NothingVc, StatsType, TurboTasks, TurboTasksBackendApi, Value};
+use turbo_tasks_fs::{DiskFileSystemVc, FileSystemVc};
 use turbo_tasks_memory::MemoryBackend;
+use turbopack::evaluate_context::node_build_environment;
+use turbopack_core::{asset::AssetsVc, environment::ServerAddr, issue::IssueVc};
+use turbopack_dev::DevChunkingContextVc;
+use turbopack_node::execution_context::ExecutionContextVc;
+
+use crate::{
+    export::{fail_on_export_errors, write_export_output},
+    integrity_manifest::write_integrity_manifest,
+    source_maps::{upload_source_maps, SourceMapUploadOptions},
+    standalone::write_standalone_output,
+    summary::{
+        collect_issues, count_issues, read_previous_integrity_manifest, write_build_summary,
+        BuildSummary,
+    },
+};
+
+pub mod export;
+pub mod integrity_manifest;
+pub mod source_maps;
+pub mod standalone;
+pub mod summary;
+pub mod transform_config;
+
+/// The output directory of a build, relative to the project directory.
+const DEFAULT_DIST_DIR: &str = ".next";
 
 pub fn register() {
     turbo_tasks::register();
@@ -10,9 +58,40 @@ pub struct NextBuildOptions {
     pub dir: Option<String>,
     pub memory_limit: Option<usize>,
     pub full_stats: Option<bool>,
+    pub inputs_manifest: Option<InputsManifestMode>,
+    /// The output directory, relative to `dir`. Defaults to `.next`.
+    pub dist_dir: Option<String>,
+    /// Uploads the emitted source maps after the build.
+    pub source_maps: Option<SourceMapUploadOptions>,
+    /// Writes the output of `output: 'standalone'`, see
+    /// [write_standalone_output].
+    pub standalone: Option<bool>,
+    /// Writes the output of `output: 'export'`, see [write_export_output].
+    pub static_export: Option<bool>,
+    /// The id of the build, which the data routes of prerendered pages
+    /// contain.
+    pub build_id: Option<String>,
+    /// The preview mode keys of the build. The prerender manifest is only
+    /// written with them and the `build_id`, see [write_prerender_manifest].
+    pub preview_props: Option<PreviewProps>,
 }
 
-pub async fn next_build(options: NextBuildOptions) -> anyhow::Result<()> {
+/// What to do with the inputs manifest of a build, which lists every file,
+/// env var and network resource the build consumed. The paths are relative to
+/// the project directory.
+#[derive(Clone)]
+pub enum InputsManifestMode {
+    /// Records the inputs manifest to the given path.
+    Record(String),
+    /// Fails the build when its inputs differ from the inputs manifest at the
+    /// given path.
+    Verify(String),
+}
+
+/// Builds the project, and returns the summary of the build which is written to
+/// [summary::BUILD_SUMMARY] in the output directory.
+pub async fn next_build(options: NextBuildOptions) -> anyhow::Result<BuildSummary> {
+    let start = Instant::now();
     register();
     let tt = TurboTasks::new(MemoryBackend::new(
         options.memory_limit.map_or(usize::MAX, |l| l * 1024 * 1024),
@@ -22,12 +101,240 @@ pub async fn next_build(options: NextBuildOptions) -> anyhow::Result<()> {
         _ => StatsType::Essential,
     };
     tt.set_stats_type(stats_type);
+    let dir = options.dir;
+    let output_dir = Path::new(dir.as_deref().unwrap_or("."))
+        .join(options.dist_dir.as_deref().unwrap_or(DEFAULT_DIST_DIR));
+    let inputs_manifest = options.inputs_manifest;
+    let standalone = options.standalone.unwrap_or(false);
+    let static_export = options.static_export.unwrap_or(false);
+    let build_id = options.build_id;
+    let preview_props = options.preview_props;
+    let task_output_dir = output_dir.clone();
+    // Read before the build overwrites it, to tell which output files changed.
+    let previous_integrity_manifest = read_previous_integrity_manifest(&output_dir);
+    let summary = Arc::new(Mutex::new(BuildSummary::new()));
+    let task_summary = summary.clone();
     let task = tt.spawn_root_task(move || {
+        let dir = dir.clone();
+        let summary = task_summary.clone();
+        let inputs_manifest = inputs_manifest.clone();
+        let output_dir = task_output_dir.clone();
+        let build_id = build_id.clone();
+        let preview_props = preview_props.clone();
         Box::pin(async move {
-            // run next build here
+            // The compilation of the entries and their modules, from which the
+            // inputs manifest is created.
+            let mut build = CompletionVc::new();
+            let mut roots = AssetsVc::cell(vec![]);
+            let mut issues = HashSet::new();
+
+            if let Some(dir) = dir.clone() {
+                let phase = Instant::now();
+                let fs: FileSystemVc =
+                    DiskFileSystemVc::new("project".to_string(), dir.clone()).into();
+                let project_path = fs.root();
+                let output_path = project_path.join(
+                    &output_dir
+                        .strip_prefix(Path::new(&dir))?
+                        .to_string_lossy()
+                        .replace('\\', "/"),
+                );
+                let build_output_root = output_path.join("build");
+                let chunking_context = DevChunkingContextVc::builder(
+                    project_path,
+                    build_output_root,
+                    build_output_root.join("chunks"),
+                    build_output_root.join("assets"),
+                    node_build_environment(),
+                )
+                .build();
+                let env = load_env(project_path);
+                let execution_context =
+                    ExecutionContextVc::new(project_path, chunking_context, env);
+                let next_config = load_next_config(execution_context.with_layer("next_config"));
+                // The pages are compiled and prerendered without a server, the
+                // address is only a placeholder.
+                let server_addr =
+                    ServerAddr::new(SocketAddr::new(Ipv4Addr::LOCALHOST.into(), 3000)).cell();
+
+                let browserslist_query =
+                    browserslist_query(project_path, Value::new(NextMode::Build)).await?;
+
+                build = write_build_entries(
+                    project_path,
+                    execution_context,
+                    env,
+                    server_addr,
+                    output_path,
+                    &browserslist_query,
+                    next_config,
+                );
+                build.await?;
+                collect_issues(IssueVc::peek_issues_with_path(build).await?, &mut issues).await?;
+                let entries = compile_build_entries(
+                    project_path,
+                    execution_context,
+                    env,
+                    server_addr,
+                    output_path,
+                    &browserslist_query,
+                    next_config,
+                )
+                .await?;
+                roots = entries.modules;
+                summary
+                    .lock()
+                    .unwrap()
+                    .add_phase("entries", phase.elapsed());
+
+                let phase = Instant::now();
+                let build_manifests = write_build_manifests(output_path, entries.manifest_entries);
+                build_manifests.await?;
+                collect_issues(
+                    IssueVc::peek_issues_with_path(build_manifests).await?,
+                    &mut issues,
+                )
+                .await?;
+
+                let routes_manifests =
+                    write_routes_manifests(project_path, output_path, next_config);
+                routes_manifests.await?;
+                collect_issues(
+                    IssueVc::peek_issues_with_path(routes_manifests).await?,
+                    &mut issues,
+                )
+                .await?;
+                let routes = count_routes(project_path, next_config).await?;
+                {
+                    let mut summary = summary.lock().unwrap();
+                    summary.routes = (*routes).clone();
+                    summary.add_phase("manifests", phase.elapsed());
+                }
+
+                if let (Some(build_id), Some(preview_props)) = (build_id, preview_props) {
+                    let phase = Instant::now();
+                    let prerender_manifest = write_prerender_manifest(
+                        project_path,
+                        execution_context,
+                        env,
+                        server_addr,
+                        output_path,
+                        next_config,
+                        &build_id,
+                        preview_props.cell(),
+                    );
+                    prerender_manifest.await?;
+                    collect_issues(
+                        IssueVc::peek_issues_with_path(prerender_manifest).await?,
+                        &mut issues,
+                    )
+                    .await?;
+                    summary
+                        .lock()
+                        .unwrap()
+                        .add_phase("prerender manifest", phase.elapsed());
+                }
+            }
+
+            if let (Some(dir), Some(mode)) = (dir.clone(), inputs_manifest) {
+                let phase = Instant::now();
+                let fs: FileSystemVc = DiskFileSystemVc::new("project".to_string(), dir).into();
+                let project_path = fs.root();
+                let inputs = get_build_inputs(project_path, build, roots);
+                match mode {
+                    InputsManifestMode::Record(path) => {
+                        write_inputs_manifest(project_path.join(&path), inputs).await?;
+                    }
+                    InputsManifestMode::Verify(path) => {
+                        verify_inputs_manifest(project_path.join(&path), inputs).await?;
+                    }
+                }
+                summary
+                    .lock()
+                    .unwrap()
+                    .add_phase("inputs manifest", phase.elapsed());
+            }
+
+            if let (Some(dir), true) = (dir.clone(), static_export) {
+                let phase = Instant::now();
+                let fs: FileSystemVc =
+                    DiskFileSystemVc::new("project".to_string(), dir.clone()).into();
+                let project_path = fs.root();
+                let project_dir = Path::new(&dir);
+                let build_output_root = project_path.join(
+                    &output_dir
+                        .strip_prefix(project_dir)?
+                        .join("build")
+                        .to_string_lossy()
+                        .replace('\\', "/"),
+                );
+                let env = load_env(project_path);
+                let chunking_context = DevChunkingContextVc::builder(
+                    project_path,
+                    build_output_root,
+                    build_output_root.join("chunks"),
+                    build_output_root.join("assets"),
+                    node_build_environment(),
+                )
+                .build();
+                let execution_context =
+                    ExecutionContextVc::new(project_path, chunking_context, env);
+                let next_config = load_next_config(execution_context.with_layer("next_config"));
+                // The pages are rendered without a server, like `next export`
+                // does, the address is only a placeholder.
+                let server_addr =
+                    ServerAddr::new(SocketAddr::new(Ipv4Addr::LOCALHOST.into(), 3000)).cell();
+                let pages = export_static_pages(
+                    project_path,
+                    execution_context,
+                    env,
+                    server_addr,
+                    next_config,
+                );
+                fail_on_export_errors(pages).await?;
+                collect_issues(IssueVc::peek_issues_with_path(pages).await?, &mut issues).await?;
+                write_export_output(project_dir, &output_dir, &pages.await?)?;
+                summary.lock().unwrap().add_phase("export", phase.elapsed());
+            }
+
+            if let (Some(dir), true) = (dir, standalone) {
+                let phase = Instant::now();
+                let fs: FileSystemVc =
+                    DiskFileSystemVc::new("project".to_string(), dir.clone()).into();
+                let project_path = fs.root();
+                let project_dir = Path::new(&dir);
+                let server_dir = output_dir.strip_prefix(project_dir)?.join("server");
+                let traced_files = trace_server_files(
+                    project_path,
+                    project_path.join(&server_dir.to_string_lossy().replace('\\', "/")),
+                )
+                .await?;
+                write_standalone_output(project_dir, &output_dir, &traced_files)?;
+                summary
+                    .lock()
+                    .unwrap()
+                    .add_phase("standalone", phase.elapsed());
+            }
+
+            let issues = count_issues(&issues).await?;
+            summary.lock().unwrap().issues = issues;
+
             Ok(NothingVc::new().into())
         })
     });
     tt.wait_task_completion(task, true).await?;
-    Ok(())
+
+    let mut summary = std::mem::take(&mut *summary.lock().unwrap());
+    if let Some(source_maps) = &options.source_maps {
+        let phase = Instant::now();
+        upload_source_maps(&output_dir, source_maps).await?;
+        summary.add_phase("source maps", phase.elapsed());
+    }
+    let phase = Instant::now();
+    let integrity_manifest = write_integrity_manifest(&output_dir)?;
+    summary.add_phase("integrity manifest", phase.elapsed());
+    summary.set_output(&integrity_manifest, &previous_integrity_manifest);
+    summary.duration_ms = start.elapsed().as_millis() as u64;
+    write_build_summary(&output_dir, &summary)?;
+    Ok(summary)
 }
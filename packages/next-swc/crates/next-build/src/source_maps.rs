@@ -0,0 +1,143 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use turbo_tasks_hash::hash_xxh3_hash64;
+
+/// The directory of the output which is served publicly, relative to the
+/// output directory.
+const PUBLIC_OUTPUT_DIR: &str = "static";
+
+/// A source map emitted by the build.
+pub struct EmittedSourceMap {
+    /// The path of the source map, relative to the output directory.
+    pub path: String,
+    /// The path of the generated file the source map belongs to, relative to
+    /// the output directory.
+    pub file: String,
+    pub content: Vec<u8>,
+}
+
+/// Uploads source maps to an error-reporting service, e.g. Sentry, so that
+/// errors of a release can be mapped back to the original source.
+#[async_trait]
+pub trait SourceMapUploader: Send + Sync {
+    async fn upload(&self, release: &str, source_maps: &[EmittedSourceMap]) -> Result<()>;
+}
+
+pub struct SourceMapUploadOptions {
+    pub uploader: Arc<dyn SourceMapUploader>,
+    /// Identifies the build in the error-reporting service. Defaults to a hash
+    /// of the source maps, so that an unchanged build gets the same release.
+    pub release: Option<String>,
+    /// Removes the source maps of the public output after the upload, so they
+    /// aren't served to browsers.
+    pub strip: bool,
+}
+
+/// Passes the source maps in `output_dir` to the uploader, and strips the
+/// public ones when requested. Nothing is stripped when the upload fails.
+pub async fn upload_source_maps(output_dir: &Path, options: &SourceMapUploadOptions) -> Result<()> {
+    let mut paths = Vec::new();
+    collect_source_maps(output_dir, &mut paths)?;
+    paths.sort();
+
+    let mut source_maps = Vec::new();
+    for path in &paths {
+        let relative = path
+            .strip_prefix(output_dir)?
+            .to_string_lossy()
+            .replace('\\', "/");
+        let file = relative.trim_end_matches(".map").to_string();
+        let content = fs::read(path)
+            .with_context(|| format!("failed to read the source map {}", path.display()))?;
+        source_maps.push(EmittedSourceMap {
+            path: relative,
+            file,
+            content,
+        });
+    }
+    if source_maps.is_empty() {
+        return Ok(());
+    }
+
+    let release = match &options.release {
+        Some(release) => release.clone(),
+        None => default_release(&source_maps),
+    };
+    options
+        .uploader
+        .upload(&release, &source_maps)
+        .await
+        .with_context(|| format!("failed to upload the source maps of release {release}"))?;
+
+    if options.strip {
+        for source_map in &source_maps {
+            if source_map
+                .path
+                .starts_with(&format!("{PUBLIC_OUTPUT_DIR}/"))
+            {
+                strip_source_map(output_dir, source_map)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn collect_source_maps(dir: &Path, paths: &mut Vec<PathBuf>) -> Result<()> {
+    if !dir.exists() {
+        return Ok(());
+    }
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        if entry.file_type()?.is_dir() {
+            collect_source_maps(&path, paths)?;
+        } else if path
+            .extension()
+            .map_or(false, |extension| extension == "map")
+        {
+            paths.push(path);
+        }
+    }
+    Ok(())
+}
+
+fn default_release(source_maps: &[EmittedSourceMap]) -> String {
+    let mut content = Vec::new();
+    for source_map in source_maps {
+        content.extend_from_slice(source_map.path.as_bytes());
+        content.push(0);
+        content.extend_from_slice(&source_map.content);
+        content.push(0);
+    }
+    format!("{:016x}", hash_xxh3_hash64(&content))
+}
+
+/// Deletes a source map, and removes the `sourceMappingURL` comment from its
+/// generated file, so browsers don't request the missing map.
+fn strip_source_map(output_dir: &Path, source_map: &EmittedSourceMap) -> Result<()> {
+    fs::remove_file(output_dir.join(&source_map.path))?;
+
+    let file = output_dir.join(&source_map.file);
+    let Ok(content) = fs::read_to_string(&file) else {
+        return Ok(());
+    };
+    let lines = content.lines().collect::<Vec<_>>();
+    let kept = lines
+        .iter()
+        .filter(|line| {
+            let line = line.trim();
+            !line.starts_with("//# sourceMappingURL=") && !line.starts_with("/*# sourceMappingURL=")
+        })
+        .copied()
+        .collect::<Vec<_>>();
+    if kept.len() != lines.len() {
+        fs::write(&file, kept.join("\n") + "\n")?;
+    }
+    Ok(())
+}
@@ -0,0 +1,181 @@
+use std::{
+    collections::BTreeSet,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use serde_json::Value as JsonValue;
+
+/// The directory of the standalone output, relative to the output directory.
+pub const STANDALONE_DIR: &str = "standalone";
+
+/// The manifest with the config of the server, written by `next build`.
+const REQUIRED_SERVER_FILES: &str = "required-server-files.json";
+
+/// Writes the output for `output: 'standalone'`: the files traced by
+/// [next_core::standalone::trace_server_files], relative to `project_dir`,
+/// the server output, and a `server.js` starting the server of Next.js, see
+/// [ServerJsOptions]. Only the files the server loads are copied from
+/// `node_modules`, with the `package.json` of their packages so Node.js
+/// resolves them the same way.
+///
+/// Like with webpack, `public` and the `static` output aren't copied, as they
+/// are usually served by a CDN. When they are copied next to it, `server.js`
+/// serves them.
+pub fn write_standalone_output(
+    project_dir: &Path,
+    output_dir: &Path,
+    traced_files: &[String],
+) -> Result<()> {
+    let standalone_dir = output_dir.join(STANDALONE_DIR);
+    if standalone_dir.exists() {
+        fs::remove_dir_all(&standalone_dir)
+            .context("failed to remove the previous standalone output")?;
+    }
+
+    let mut files = BTreeSet::new();
+    for file in traced_files {
+        files.insert(PathBuf::from(file));
+        if let Some(package_json) = package_json_of(file) {
+            if project_dir.join(&package_json).is_file() {
+                files.insert(package_json);
+            }
+        }
+    }
+    let output_relative = output_dir
+        .strip_prefix(project_dir)
+        .unwrap_or_else(|_| Path::new(".next"))
+        .to_path_buf();
+    collect_server_output(output_dir, &output_relative, &mut files)?;
+
+    for file in &files {
+        let target = standalone_dir.join(file);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::copy(project_dir.join(file), &target).with_context(|| {
+            format!("failed to copy {} to the standalone output", file.display())
+        })?;
+    }
+
+    let server_js = server_js(output_dir, &output_relative)?;
+    fs::write(standalone_dir.join("server.js"), server_js)
+        .context("failed to write the standalone server.js")?;
+    Ok(())
+}
+
+/// Returns the `package.json` of the package in `node_modules` a file belongs
+/// to, e.g. `node_modules/@scope/name/package.json`.
+fn package_json_of(file: &str) -> Option<PathBuf> {
+    let segments = file.split('/').collect::<Vec<_>>();
+    let index = segments
+        .iter()
+        .rposition(|segment| *segment == "node_modules")?;
+    let name_len = match segments.get(index + 1) {
+        Some(scope) if scope.starts_with('@') => 2,
+        Some(_) => 1,
+        None => return None,
+    };
+    let package = segments.get(..index + 1 + name_len)?;
+    // The path is the directory of the package.
+    if package.len() >= segments.len() {
+        return None;
+    }
+    Some(PathBuf::from(package.join("/")).join("package.json"))
+}
+
+/// Collects the files of the output the server needs, which are the server
+/// output and the manifests next to it.
+fn collect_server_output(
+    output_dir: &Path,
+    output_relative: &Path,
+    files: &mut BTreeSet<PathBuf>,
+) -> Result<()> {
+    if !output_dir.exists() {
+        return Ok(());
+    }
+    for entry in fs::read_dir(output_dir)? {
+        let entry = entry?;
+        if entry.file_type()?.is_file() {
+            files.insert(output_relative.join(entry.file_name()));
+        }
+    }
+    collect_files(
+        &output_dir.join("server"),
+        &output_relative.join("server"),
+        files,
+    )
+}
+
+fn collect_files(dir: &Path, relative: &Path, files: &mut BTreeSet<PathBuf>) -> Result<()> {
+    if !dir.exists() {
+        return Ok(());
+    }
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let relative = relative.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            collect_files(&entry.path(), &relative, files)?;
+        } else {
+            files.insert(relative);
+        }
+    }
+    Ok(())
+}
+
+/// The template of the `server.js` of the standalone output, see
+/// [ServerJsOptions].
+const SERVER_JS_TEMPLATE: &str = include_str!("standalone/server.js");
+
+/// The values [SERVER_JS_TEMPLATE] is rendered with. `PORT` and `HOSTNAME`
+/// override the port and hostname when starting the server.
+pub struct ServerJsOptions {
+    pub port: u16,
+    pub hostname: String,
+    /// Whether static files are served with gzip, like `compress` in
+    /// `next.config.js` does for Next.js.
+    pub compress: bool,
+    /// The config the server of Next.js is created with.
+    pub next_config: JsonValue,
+}
+
+impl ServerJsOptions {
+    pub fn render(&self) -> Result<String> {
+        Ok(SERVER_JS_TEMPLATE
+            .replace("__COMPRESS__", &self.compress.to_string())
+            .replace("__PORT__", &self.port.to_string())
+            .replace("__HOSTNAME__", &serde_json::to_string(&self.hostname)?)
+            // Last, as the config could contain any of the placeholders.
+            .replace(
+                "__NEXT_CONFIG__",
+                &serde_json::to_string(&self.next_config)?,
+            ))
+    }
+}
+
+/// Returns the `server.js` of the standalone output, with the config from
+/// [REQUIRED_SERVER_FILES] when `next build` wrote it.
+fn server_js(output_dir: &Path, output_relative: &Path) -> Result<String> {
+    let mut config = fs::read(output_dir.join(REQUIRED_SERVER_FILES))
+        .ok()
+        .and_then(|content| serde_json::from_slice::<JsonValue>(&content).ok())
+        .and_then(|manifest| manifest.get("config").cloned())
+        .filter(JsonValue::is_object)
+        .unwrap_or_else(|| JsonValue::Object(Default::default()));
+    config["distDir"] = JsonValue::String(format!(
+        "./{}",
+        output_relative.to_string_lossy().replace('\\', "/")
+    ));
+
+    ServerJsOptions {
+        port: 3000,
+        hostname: "localhost".to_string(),
+        compress: config
+            .get("compress")
+            .and_then(JsonValue::as_bool)
+            .unwrap_or(true),
+        next_config: config,
+    }
+    .render()
+}
@@ -0,0 +1,126 @@
+// The server of `output: 'standalone'`, rendered by `next build` with the
+// config of the app, see `ServerJsOptions`.
+const http = require('http')
+const fs = require('fs')
+const path = require('path')
+const zlib = require('zlib')
+const NextServer = require('next/dist/server/next-server').default
+
+process.env.NODE_ENV = 'production'
+process.chdir(__dirname)
+
+const nextConfig = __NEXT_CONFIG__
+const distDir = path.join(__dirname, nextConfig.distDir)
+const compress = __COMPRESS__
+const currentPort = parseInt(process.env.PORT, 10) || __PORT__
+const hostname = process.env.HOSTNAME || __HOSTNAME__
+
+// Make sure commands gracefully respect termination signals (e.g. from Docker)
+// Allow the graceful termination to be manually configurable
+if (!process.env.NEXT_MANUAL_SIG_HANDLE) {
+  process.on('SIGTERM', () => process.exit(0))
+  process.on('SIGINT', () => process.exit(0))
+}
+
+const CONTENT_TYPES = {
+  '.css': 'text/css; charset=utf-8',
+  '.gif': 'image/gif',
+  '.html': 'text/html; charset=utf-8',
+  '.ico': 'image/x-icon',
+  '.jpeg': 'image/jpeg',
+  '.jpg': 'image/jpeg',
+  '.js': 'application/javascript; charset=utf-8',
+  '.json': 'application/json; charset=utf-8',
+  '.map': 'application/json; charset=utf-8',
+  '.png': 'image/png',
+  '.svg': 'image/svg+xml',
+  '.txt': 'text/plain; charset=utf-8',
+  '.webp': 'image/webp',
+  '.woff': 'font/woff',
+  '.woff2': 'font/woff2',
+}
+const COMPRESSIBLE = new Set(['.css', '.html', '.js', '.json', '.map', '.svg', '.txt'])
+
+// The build output and `public` aren't part of the standalone output by
+// default, as they are usually served by a CDN. When they are copied next to
+// the server, they are served from here without going through Next.js.
+const staticDirs = [
+  [`${nextConfig.basePath || ''}/_next/static/`, path.join(distDir, 'static'), true],
+  [`${nextConfig.basePath || ''}/`, path.join(__dirname, 'public'), false],
+]
+
+function resolveStaticFile(pathname) {
+  for (const [prefix, dir, immutable] of staticDirs) {
+    if (!pathname.startsWith(prefix)) continue
+    const file = path.join(dir, pathname.slice(prefix.length))
+    // Requests can't escape the directory with `..`.
+    if (!file.startsWith(dir + path.sep)) continue
+    try {
+      if (fs.statSync(file).isFile()) return { file, immutable }
+    } catch {}
+  }
+  return null
+}
+
+function serveStaticFile(req, res, { file, immutable }) {
+  const ext = path.extname(file)
+  res.setHeader('Content-Type', CONTENT_TYPES[ext] || 'application/octet-stream')
+  res.setHeader(
+    'Cache-Control',
+    immutable ? 'public, max-age=31536000, immutable' : 'public, max-age=0'
+  )
+  let stream = fs.createReadStream(file)
+  if (
+    compress &&
+    COMPRESSIBLE.has(ext) &&
+    /\bgzip\b/.test(req.headers['accept-encoding'] || '')
+  ) {
+    res.setHeader('Content-Encoding', 'gzip')
+    res.setHeader('Vary', 'Accept-Encoding')
+    stream = stream.pipe(zlib.createGzip())
+  }
+  if (req.method === 'HEAD') {
+    res.end()
+    return
+  }
+  stream.pipe(res)
+}
+
+let handler
+
+const server = http.createServer(async (req, res) => {
+  try {
+    if (req.method === 'GET' || req.method === 'HEAD') {
+      const { pathname } = new URL(req.url, 'http://n')
+      const staticFile = resolveStaticFile(decodeURIComponent(pathname))
+      if (staticFile) {
+        serveStaticFile(req, res, staticFile)
+        return
+      }
+    }
+    // Everything else is routed by Next.js, from the manifests of the build.
+    await handler(req, res)
+  } catch (err) {
+    console.error(err)
+    res.statusCode = 500
+    res.end('internal server error')
+  }
+})
+
+server.listen(currentPort, (err) => {
+  if (err) {
+    console.error('Failed to start server', err)
+    process.exit(1)
+  }
+  const nextServer = new NextServer({
+    hostname,
+    port: currentPort,
+    dir: __dirname,
+    dev: false,
+    customServer: false,
+    conf: nextConfig,
+  })
+  handler = nextServer.getRequestHandler()
+
+  console.log('Listening on port', currentPort, 'url: http://' + hostname + ':' + currentPort)
+})
@@ -0,0 +1,156 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    fs,
+    path::Path,
+    time::Duration,
+};
+
+use anyhow::{Context, Result};
+use next_core::manifests::RouteCounts;
+use serde::Serialize;
+use turbopack_core::issue::{CapturedIssuesVc, IssueSeverity, IssueVc};
+
+use crate::integrity_manifest::{IntegrityManifest, INTEGRITY_MANIFEST};
+
+/// The file name of the build summary in the output directory.
+pub const BUILD_SUMMARY: &str = "build-summary.json";
+
+/// A machine-readable summary of a build, which CI can track over time without
+/// parsing the logs. It's written to [BUILD_SUMMARY] and returned to the
+/// bindings.
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BuildSummary {
+    pub version: u32,
+    pub duration_ms: u64,
+    /// The phases of the build which ran, in order.
+    pub phases: Vec<BuildPhase>,
+    pub routes: RouteCounts,
+    pub issues: IssueCounts,
+    pub output: OutputSummary,
+    pub cache: CacheSummary,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BuildPhase {
+    pub name: &'static str,
+    pub duration_ms: u64,
+}
+
+/// The issues reported by the build, which don't fail it.
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IssueCounts {
+    pub errors: usize,
+    pub warnings: usize,
+}
+
+/// The files of the output directory, except for the cache.
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OutputSummary {
+    pub files: usize,
+    pub bytes: u64,
+    /// The bytes by top-level directory of the output, e.g. `server` and
+    /// `static`. Files at the top level are counted under `.`.
+    pub bytes_by_directory: BTreeMap<String, u64>,
+}
+
+/// How much of the output the build reproduced. An output file is a hit when
+/// it's identical to the one of the previous build, according to the
+/// integrity manifests, so deployments don't need to upload it again.
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CacheSummary {
+    pub hits: usize,
+    pub misses: usize,
+    /// `hits / (hits + misses)`, or `0` for an empty output.
+    pub hit_rate: f64,
+}
+
+impl BuildSummary {
+    pub fn new() -> Self {
+        Self {
+            version: 1,
+            ..Default::default()
+        }
+    }
+
+    pub fn add_phase(&mut self, name: &'static str, duration: Duration) {
+        self.phases.push(BuildPhase {
+            name,
+            duration_ms: duration.as_millis() as u64,
+        });
+    }
+
+    /// Summarizes the output and compares it with the output of the previous
+    /// build.
+    pub fn set_output(&mut self, manifest: &IntegrityManifest, previous: &IntegrityManifest) {
+        let mut output = OutputSummary::default();
+        let mut cache = CacheSummary::default();
+        for (path, entry) in &manifest.files {
+            output.files += 1;
+            output.bytes += entry.size;
+            let directory = path.split_once('/').map_or(".", |(directory, _)| directory);
+            *output
+                .bytes_by_directory
+                .entry(directory.to_string())
+                .or_default() += entry.size;
+            if previous.files.get(path) == Some(entry) {
+                cache.hits += 1;
+            } else {
+                cache.misses += 1;
+            }
+        }
+        if output.files > 0 {
+            cache.hit_rate = cache.hits as f64 / output.files as f64;
+        }
+        self.output = output;
+        self.cache = cache;
+    }
+}
+
+/// Adds the issues captured from a part of the build. The same issue can be
+/// captured from several parts, it's only counted once.
+pub async fn collect_issues(
+    captured: CapturedIssuesVc,
+    issues: &mut HashSet<IssueVc>,
+) -> Result<()> {
+    let captured = captured.strongly_consistent().await?;
+    issues.extend(captured.iter_with_shortest_path().map(|(issue, _)| issue));
+    Ok(())
+}
+
+/// Counts the errors and warnings among the issues.
+pub async fn count_issues(issues: &HashSet<IssueVc>) -> Result<IssueCounts> {
+    let mut counts = IssueCounts::default();
+    for issue in issues {
+        let severity = *issue.severity().await?;
+        if severity <= IssueSeverity::Error {
+            counts.errors += 1;
+        } else if severity == IssueSeverity::Warning {
+            counts.warnings += 1;
+        }
+    }
+    Ok(counts)
+}
+
+/// Reads the integrity manifest of the previous build, if any.
+pub fn read_previous_integrity_manifest(output_dir: &Path) -> IntegrityManifest {
+    fs::read(output_dir.join(INTEGRITY_MANIFEST))
+        .ok()
+        .and_then(|content| serde_json::from_slice(&content).ok())
+        .unwrap_or_default()
+}
+
+pub fn write_build_summary(output_dir: &Path, summary: &BuildSummary) -> Result<()> {
+    if output_dir.exists() {
+        fs::write(
+            output_dir.join(BUILD_SUMMARY),
+            serde_json::to_string_pretty(summary)?,
+        )
+        .context("failed to write the build summary")?;
+    }
+    Ok(())
+}
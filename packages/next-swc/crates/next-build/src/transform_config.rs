@@ -0,0 +1,69 @@
+use anyhow::Result;
+use next_core::{
+    env::load_env,
+    mode::NextMode,
+    next_config::load_next_config,
+    transform_config::{get_transform_config, TransformConfigContext},
+};
+use serde::Deserialize;
+use turbo_tasks::{TurboTasks, Value};
+use turbo_tasks_fs::{DiskFileSystemVc, FileSystemVc};
+use turbo_tasks_memory::MemoryBackend;
+use turbopack::evaluate_context::node_build_environment;
+use turbopack_dev::DevChunkingContextVc;
+use turbopack_node::execution_context::ExecutionContextVc;
+
+use crate::DEFAULT_DIST_DIR;
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransformConfigOptions {
+    /// The project directory.
+    pub dir: String,
+    pub context: TransformConfigContext,
+    /// Exports the configuration of `next dev` instead of `next build`.
+    #[serde(default)]
+    pub dev: bool,
+}
+
+/// Returns the resolve and transform configuration of a context of the
+/// project as JSON, see [get_transform_config], for tools which bundle the
+/// modules of the app without Turbopack.
+pub async fn export_transform_config(options: TransformConfigOptions) -> Result<String> {
+    crate::register();
+    let TransformConfigOptions { dir, context, dev } = options;
+    let mode = if dev {
+        NextMode::Development
+    } else {
+        NextMode::Build
+    };
+    let tt = TurboTasks::new(MemoryBackend::default());
+    tt.run_once(async move {
+        let fs: FileSystemVc = DiskFileSystemVc::new("project".to_string(), dir).into();
+        let project_path = fs.root();
+        let env = load_env(project_path);
+        let build_output_root = project_path.join(&format!("{DEFAULT_DIST_DIR}/build"));
+        let chunking_context = DevChunkingContextVc::builder(
+            project_path,
+            build_output_root,
+            build_output_root.join("chunks"),
+            build_output_root.join("assets"),
+            node_build_environment(),
+        )
+        .build();
+        let execution_context = ExecutionContextVc::new(project_path, chunking_context, env);
+        let next_config = load_next_config(execution_context.with_layer("next_config"));
+        let config = get_transform_config(
+            project_path,
+            execution_context,
+            env,
+            Value::new(context),
+            Value::new(mode),
+            next_config,
+        )
+        .strongly_consistent()
+        .await?;
+        Ok(serde_json::to_string_pretty(&*config)?)
+    })
+    .await
+}
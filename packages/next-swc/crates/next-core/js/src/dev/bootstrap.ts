@@ -1,5 +1,5 @@
 import { initializeHMR } from "./client";
 
 initializeHMR({
-  assetPrefix: "",
+  assetPrefix: process.env.__NEXT_ASSET_PREFIX || "",
 });
@@ -7,13 +7,14 @@ type FileType =
   | "not-found"
   | "head";
 declare global {
-  // an array of all layouts and the page
-  const LAYOUT_INFO: ({
-    segment: string;
-    page?: { module: any; chunks: string[] };
-  } & {
-    [componentKey in FileType]?: { module: any; chunks: string[] };
-  })[];
+  // the tree of segments rendering the page
+  const LOADER_TREE: LoaderTree;
+  // the module of the page
+  const PAGE_MODULE: { module: any };
+  // the chunks of every component in the loader tree, by component key
+  const COMPONENT_CHUNKS: Record<string, string[]>;
+  // the `<link>`s to the icons of the app directory, added to the head
+  const METADATA_ICONS: Record<string, string>[];
   // array of chunks for the bootstrap script
   const BOOTSTRAP: string[];
   const IPC: Ipc<unknown, unknown>;
@@ -35,7 +36,23 @@ import { renderToHTMLOrFlight } from "next/dist/server/app-render";
 import { PassThrough } from "stream";
 import { ServerResponseShim } from "@vercel/turbopack-next/internal/http";
 import { headersFromEntries } from "@vercel/turbopack-next/internal/headers";
+import { injectFontPreloads } from "@vercel/turbopack-next/internal/font/preload";
+import { preloadLinkHeader } from "@vercel/turbopack-next/internal/preload";
+import {
+  Revalidate,
+  withIsrCache,
+} from "@vercel/turbopack-next/internal/isr-cache";
+import {
+  collectFetchTags,
+  installFetchCache,
+} from "@vercel/turbopack-next/internal/fetch-cache";
+import {
+  serverTimingHeader,
+  startEvaluationMetrics,
+} from "@vercel/turbopack-next/internal/evaluation-metrics";
 import { parse, ParsedUrlQuery } from "node:querystring";
+import * as instrumentation from "@vercel/turbopack-next/instrumentation";
+import { registerInstrumentation } from "@vercel/turbopack-next/internal/instrumentation";
 
 globalThis.__next_require__ = (data) => {
   const [, , , ssr_id] = JSON.parse(data);
@@ -45,6 +62,10 @@ globalThis.__next_chunk_load__ = () => Promise.resolve();
 
 process.env.__NEXT_NEW_LINK_BEHAVIOR = "true";
 
+// The page module is the layout entry, which exports the hooks Next.js uses to
+// patch `fetch`.
+installFetchCache(PAGE_MODULE as any);
+
 const ipc = IPC as Ipc<IpcIncomingMessage, IpcOutgoingMessage>;
 
 type IpcIncomingMessage = {
@@ -62,6 +83,8 @@ type IpcOutgoingMessage = {
 const MIME_TEXT_HTML_UTF8 = "text/html; charset=utf-8";
 
 (async () => {
+  await registerInstrumentation(instrumentation);
+
   while (true) {
     const msg = await ipc.recv();
 
@@ -77,16 +100,32 @@ const MIME_TEXT_HTML_UTF8 = "text/html; charset=utf-8";
       }
     }
 
-    const result = await runOperation(renderData);
-
-    if (result == null) {
-      throw new Error("no html returned");
-    }
+    const stopMetrics = startEvaluationMetrics();
+    const urlPath = new URL(renderData.url, "next://").pathname;
+    const result = await withIsrCache(
+      isrCacheKey(urlPath, renderData.rawHeaders),
+      [renderData.path, urlPath],
+      renderData.rawHeaders,
+      async () => {
+        const { result, tags } = await collectFetchTags(() =>
+          runOperation(renderData)
+        );
+        if (result == null) {
+          throw new Error("no html returned");
+        }
+        return {
+          response: { statusCode: 200, ...result },
+          revalidate: await segmentRevalidate(LOADER_TREE),
+          tags,
+        };
+      }
+    );
+    const metrics = stopMetrics();
 
     ipc.send({
       type: "response",
-      statusCode: 200,
       ...result,
+      headers: [...result.headers, serverTimingHeader("render", metrics)],
     });
   }
 })().catch((err) => {
@@ -107,29 +146,64 @@ type LoaderTree = [
   components: ComponentsType
 ];
 
-async function runOperation(renderData: RenderData) {
-  const layoutInfoChunks: Record<string, string[]> = {};
-  const pageItem = LAYOUT_INFO[LAYOUT_INFO.length - 1];
-  const pageModule = pageItem.page!.module;
-  let tree: LoaderTree = [
-    "",
-    {},
-    { page: [() => pageModule.module, "page.js"] },
-  ];
-  layoutInfoChunks["page"] = pageItem.page!.chunks;
-  for (let i = LAYOUT_INFO.length - 2; i >= 0; i--) {
-    const info = LAYOUT_INFO[i];
-    const components: ComponentsType = {};
-    for (const key of Object.keys(info)) {
-      if (key === "segment") {
+/**
+ * The key of a response in the ISR cache. Flight responses depend on the
+ * router state of the client, which is part of the key.
+ */
+function isrCacheKey(urlPath: string, rawHeaders: Array<[string, string]>) {
+  const header = (name: string) =>
+    rawHeaders.find(([key]) => key.toLowerCase() === name)?.[1];
+  if (header("rsc") == null) {
+    return urlPath;
+  }
+  return `${urlPath}.rsc?${new URLSearchParams({
+    tree: header("next-router-state-tree") ?? "",
+    prefetch: header("next-router-prefetch") ?? "",
+  })}`;
+}
+
+/**
+ * Returns how the page is revalidated according to the `revalidate` and
+ * `dynamic` exports of its layouts and itself, like `next build` prerenders
+ * it. Whether pages without these exports are static is only known when
+ * prerendering them, so they aren't cached.
+ */
+async function segmentRevalidate(tree: LoaderTree): Promise<Revalidate> {
+  let revalidate: Revalidate = undefined;
+  let current: LoaderTree | undefined = tree;
+  while (current != null) {
+    const [, parallelRoutes, components] = current;
+    for (const reference of [components.layout, components.page]) {
+      if (reference == null) {
         continue;
       }
-      const k = key as FileType;
-      components[k] = [() => info[k]!.module.module, `${k}${i}.js`];
-      layoutInfoChunks[`${k}${i}`] = info[k]!.chunks;
+      const mod = await reference[0]();
+      if (mod.dynamic === "force-dynamic" || mod.revalidate === 0) {
+        return undefined;
+      }
+      if (typeof mod.revalidate === "number") {
+        revalidate =
+          typeof revalidate === "number"
+            ? Math.min(revalidate, mod.revalidate)
+            : mod.revalidate;
+      } else if (
+        revalidate === undefined &&
+        (mod.revalidate === false ||
+          mod.dynamic === "force-static" ||
+          mod.dynamic === "error")
+      ) {
+        revalidate = false;
+      }
     }
-    tree = [info.segment, { children: tree }, components];
+    current = parallelRoutes.children;
   }
+  return revalidate;
+}
+
+async function runOperation(renderData: RenderData) {
+  const layoutInfoChunks = COMPONENT_CHUNKS;
+  const pageModule = PAGE_MODULE;
+  const tree = LOADER_TREE;
 
   const proxyMethodsForModule = (
     id: string
@@ -250,7 +324,7 @@ async function runOperation(renderData: RenderData) {
     serverCSSManifest,
     runtime: "nodejs",
     serverComponents: true,
-    assetPrefix: "",
+    assetPrefix: process.env.__NEXT_ASSET_PREFIX || "",
     pageConfig: pageModule.config,
     reactLoadableManifest: {},
   };
@@ -278,14 +352,42 @@ async function runOperation(renderData: RenderData) {
   } else {
     body = result.toUnchunkedString();
   }
+  const contentType = result.contentType() ?? MIME_TEXT_HTML_UTF8;
+  const headers: [string, string][] = [["Content-Type", contentType]];
+  if (contentType === MIME_TEXT_HTML_UTF8) {
+    body = injectFontPreloads(injectIconLinks(body));
+    const link = preloadLinkHeader(
+      renderOpt.assetPrefix ?? "",
+      Object.values(layoutInfoChunks).flat().concat(BOOTSTRAP)
+    );
+    if (link != null) {
+      headers.push(["Link", link]);
+    }
+  }
   return {
-    headers: [
-      ["Content-Type", result.contentType() ?? MIME_TEXT_HTML_UTF8],
-    ] as [string, string][],
+    headers,
     body,
   };
 }
 
+function injectIconLinks(html: string) {
+  const headEnd = html.indexOf("</head>");
+  if (headEnd === -1 || METADATA_ICONS.length === 0) {
+    return html;
+  }
+  const links = METADATA_ICONS.map(
+    (attributes) =>
+      `<link ${Object.entries(attributes)
+        .map(([name, value]) => `${name}="${escapeAttribute(value)}"`)
+        .join(" ")}/>`
+  ).join("");
+  return html.slice(0, headEnd) + links + html.slice(headEnd);
+}
+
+function escapeAttribute(value: string) {
+  return value.replace(/&/g, "&amp;").replace(/"/g, "&quot;");
+}
+
 // This utility is based on https://github.com/zertosh/htmlescape
 // License: https://github.com/zertosh/htmlescape/blob/0527ca7156a524d256101bb310a9f970f63078ad/LICENSE
 
@@ -11,7 +11,7 @@ import { HeadManagerContext } from "next/dist/shared/lib/head-manager-context";
 import { initializeHMR } from "@vercel/turbopack-next/dev/client";
 
 initializeHMR({
-  assetPrefix: "",
+  assetPrefix: process.env.__NEXT_ASSET_PREFIX || "",
 });
 
 window.next = {
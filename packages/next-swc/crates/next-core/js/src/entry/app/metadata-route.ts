@@ -0,0 +1,19 @@
+// @ts-expect-error METADATA_ROUTE is provided by rust
+import metadataRoute from "METADATA_ROUTE";
+
+// Dates (e.g. `lastModified` of a sitemap entry) are serialized as ISO strings.
+// Responses (e.g. an `ImageResponse` of an icon) are serialized with a base64
+// encoded body.
+export default async function loadMetadataRoute() {
+  const result =
+    typeof metadataRoute === "function"
+      ? await metadataRoute()
+      : metadataRoute;
+  if (result instanceof Response) {
+    return {
+      contentType: result.headers.get("content-type"),
+      body: Buffer.from(await result.arrayBuffer()).toString("base64"),
+    };
+  }
+  return result;
+}
@@ -1,39 +1,85 @@
 // IPC need to be the first import to allow it to catch errors happening during
 // the other imports
 import startHandler from "@vercel/turbopack-next/internal/api-server-handler";
-import { runEdgeFunction } from "@vercel/turbopack-next/internal/edge";
-
-import { join } from "path";
 
 import "next/dist/server/node-polyfill-fetch.js";
 
-import chunkGroup from "ROUTE_CHUNK_GROUP";
-
+import { AppRouteRouteHandler } from "next/dist/server/future/route-handlers/app-route-route-handler";
+import { RouteKind } from "next/dist/server/future/route-kind";
 import {
   NodeNextRequest,
   NodeNextResponse,
 } from "next/dist/server/base-http/node";
 
-startHandler(async ({ request, response, query, params, path }) => {
-  const edgeInfo = {
-    name: "edge",
-    paths: chunkGroup.map((chunk: string) =>
-      join(process.cwd(), ".next/server/app", chunk)
-    ),
-    wasm: [],
-    env: [],
-    assets: [],
-  };
-  await runEdgeFunction({
-    edgeInfo,
-    outputDir: "app",
-    req: new NodeNextRequest(request),
-    res: new NodeNextResponse(response),
-    query,
-    params,
-    path,
-    onWarning(warning) {
-      console.warn(warning);
+import { staticGenerationAsyncStorage } from "next/dist/client/components/static-generation-async-storage";
+import * as serverHooks from "next/dist/client/components/hooks-server-context";
+import { staticGenerationBailout } from "next/dist/client/components/static-generation-bailout";
+import * as headerHooks from "next/dist/client/components/headers";
+import { requestAsyncStorage } from "next/dist/client/components/request-async-storage";
+import { installFetchCache } from "@vercel/turbopack-next/internal/fetch-cache";
+
+// @ts-expect-error ROUTE is provided by rust
+import * as handlers from "ROUTE";
+
+declare const PAGE: string;
+// Whether `HEAD` requests run the `GET` handler, because the route doesn't
+// export `HEAD`. Provided by rust, which also answers `OPTIONS` by default.
+declare const HEAD_AS_GET: boolean;
+
+const handler = new AppRouteRouteHandler();
+handler.module = {
+  handlers: HEAD_AS_GET ? { ...handlers, HEAD: handlers.GET } : handlers,
+  resolvedPagePath: PAGE,
+  staticGenerationAsyncStorage,
+  serverHooks,
+  staticGenerationBailout,
+  headerHooks,
+  requestAsyncStorage,
+};
+installFetchCache(handler.module);
+
+startHandler(async ({ request, response, params }) => {
+  const result = await handler.handle(
+    {
+      definition: {
+        kind: RouteKind.APP_ROUTE,
+        page: "",
+        pathname: "",
+        bundlePath: "",
+        filename: PAGE,
+      },
+      params,
     },
+    new NodeNextRequest(request),
+    new NodeNextResponse(response),
+    { supportsDynamicHTML: true }
+  );
+
+  response.statusCode = result.status;
+  response.statusMessage = result.statusText;
+  result.headers.forEach((value, key) => {
+    // the append handling is special cased for `set-cookie`
+    if (key.toLowerCase() === "set-cookie") {
+      response.setHeader(key, value);
+    } else {
+      response.appendHeader(key, value);
+    }
   });
+
+  if (result.body) {
+    const reader = result.body.getReader();
+    try {
+      while (true) {
+        const { done, value } = await reader.read();
+        if (done) {
+          break;
+        }
+        response.write(value);
+      }
+    } finally {
+      response.end();
+    }
+  } else {
+    response.end();
+  }
 });
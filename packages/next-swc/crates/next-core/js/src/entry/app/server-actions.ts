@@ -0,0 +1,48 @@
+// IPC need to be the first import to allow it to catch errors happening during
+// the other imports
+import startHandler from "@vercel/turbopack-next/internal/api-server-handler";
+
+import "next/dist/server/node-polyfill-fetch.js";
+
+import { ApiError } from "next/dist/server/api-utils";
+import { parseBody } from "next/dist/server/api-utils/node";
+
+// Provided by the rust generate code
+declare global {
+  // loads the exports of the action modules, by action id
+  const SERVER_ACTIONS: Record<string, () => Promise<any>>;
+}
+
+const ACTION_HEADER = "next-action";
+
+// Actions are called like the app renderer of Next.js calls them, which is
+// what `callServer` of `next/dist/client/app-call-server` expects: the bound
+// arguments are sent as JSON, and the result is returned in a JSON array.
+// Errors are answered with their message.
+startHandler(async ({ request, response }) => {
+  const id = request.headers[ACTION_HEADER];
+  const loadAction = typeof id === "string" ? SERVER_ACTIONS[id] : undefined;
+  if (request.method !== "POST" || !loadAction) {
+    response.statusCode = 404;
+    response.end(`Server action ${JSON.stringify(id)} not found`);
+    return;
+  }
+
+  try {
+    const actionData = (await parseBody(request, "1mb")) || {};
+    const bound = actionData.bound || [];
+    const action = await loadAction();
+    const result = await action(...bound);
+    response.statusCode = 200;
+    response.setHeader("Content-Type", "application/json");
+    response.end(JSON.stringify([result]));
+  } catch (error) {
+    console.error(error);
+    response.statusCode = error instanceof ApiError ? error.statusCode : 500;
+    response.setHeader("Content-Type", "text/plain; charset=utf-8");
+    response.end(
+      (error instanceof Error ? error.message : undefined) ??
+        "Internal Server Error"
+    );
+  }
+});
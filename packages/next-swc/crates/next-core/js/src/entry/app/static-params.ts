@@ -0,0 +1,34 @@
+// Provided by the rust generate code
+declare global {
+  // the modules of the layouts and the page, from the root segment down
+  const SEGMENTS: any[];
+}
+
+import "next/dist/server/node-polyfill-fetch";
+
+type Params = Record<string, string | string[]>;
+
+// Calls `generateStaticParams` of every segment with the params of the parent
+// segments, like `next build` does.
+export default async function getStaticParams() {
+  let params: Params[] | null = null;
+  let dynamicParams = true;
+  for (const segment of SEGMENTS) {
+    if (typeof segment.generateStaticParams === "function") {
+      const segmentParams: Params[] = [];
+      for (const parentParams of params ?? [{}]) {
+        const result = await segment.generateStaticParams({
+          params: parentParams,
+        });
+        for (const params of result) {
+          segmentParams.push({ ...parentParams, ...params });
+        }
+      }
+      params = segmentParams;
+    }
+    if (typeof segment.dynamicParams === "boolean") {
+      dynamicParams = segment.dynamicParams;
+    }
+  }
+  return { params, dynamicParams };
+}
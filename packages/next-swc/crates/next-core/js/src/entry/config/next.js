@@ -30,6 +30,16 @@ const loadNextConfig = async (silent) => {
     );
   }
 
+  if (nextConfig.experimental?.turbo?.rules) {
+    ensureLoadersHaveSerializableOptions(
+      Object.fromEntries(
+        Object.entries(nextConfig.experimental.turbo.rules).map(
+          ([glob, rule]) => [glob, Array.isArray(rule) ? rule : rule.loaders]
+        )
+      )
+    );
+  }
+
   return nextConfig;
 };
 
@@ -2,6 +2,8 @@ declare const NAME: string;
 declare const PAGE: string;
 
 import { adapter, enhanceGlobals } from "next/dist/server/web/adapter";
+import * as instrumentation from "@vercel/turbopack-next/instrumentation";
+import { registerInstrumentation } from "@vercel/turbopack-next/internal/instrumentation";
 
 enhanceGlobals();
 
@@ -17,7 +19,8 @@ if (typeof handler !== "function") {
 // @ts-ignore
 globalThis._ENTRIES = {
   [`middleware_${NAME}`]: {
-    default: function (opts: any) {
+    default: async function (opts: any) {
+      await registerInstrumentation(instrumentation);
       return adapter({
         ...opts,
         page: `/${PAGE}`,
@@ -0,0 +1,196 @@
+import "next/dist/server/node-polyfill-fetch.js";
+import "@vercel/turbopack-next/internal/shims";
+
+import type { IncomingMessage, ServerResponse } from "node:http";
+
+import { renderToHTML, RenderOpts } from "next/dist/server/render";
+import { buildStaticPaths } from "next/dist/build/utils";
+import { getRouteMatcher } from "next/dist/shared/lib/router/utils/route-matcher";
+import { getRouteRegex } from "next/dist/shared/lib/router/utils/route-regex";
+import type { BuildManifest } from "next/dist/server/get-page-files";
+import type { ReactLoadableManifest } from "next/dist/server/load-components";
+
+import { ServerResponseShim } from "@vercel/turbopack-next/internal/http";
+import { injectFontPreloads } from "@vercel/turbopack-next/internal/font/preload";
+
+import App from "@vercel/turbopack-next/pages/_app";
+import Document from "@vercel/turbopack-next/pages/_document";
+
+// @ts-expect-error INNER is provided by rust
+import Component, * as otherExports from "INNER";
+
+type ExportedPage = {
+  pathname: string;
+  html: string;
+  /** The `revalidate` returned by `getStaticProps`, `false` without ISR. */
+  revalidate: number | false;
+};
+
+type ExportedPages = {
+  /** The `fallback` of `getStaticPaths`, `null` when the page isn't dynamic. */
+  fallback: boolean | "blocking" | null;
+  pages: ExportedPage[];
+  /** The paths for which `getStaticProps` returns `notFound: true`. */
+  notFound: string[];
+};
+
+/**
+ * Renders a page of the pages directory at build time, once for every path
+ * returned by its `getStaticPaths`, or once when it isn't dynamic. Paths for
+ * which `getStaticProps` returns `notFound: true` aren't rendered.
+ *
+ * A `fallback` other than `false` needs a server to render the other paths on
+ * demand, so it's only allowed with `allowFallback`, which `output: 'export'`
+ * doesn't set.
+ */
+export default async function exportPage(
+  page: string,
+  allowFallback: boolean
+): Promise<ExportedPages> {
+  let pathnames = [page];
+  let pageFallback: ExportedPages["fallback"] = null;
+  if ("getStaticPaths" in otherExports) {
+    const { paths, fallback } = await buildStaticPaths({
+      page,
+      getStaticPaths: otherExports.getStaticPaths,
+      // TODO(alexkirsz) Provide the correct next.config.js path.
+      configFileName: "next.config.js",
+    });
+    if (fallback !== false && !allowFallback) {
+      throw new Error(
+        `getStaticPaths of ${page} returns \`fallback: ${JSON.stringify(
+          fallback
+        )}\`, which needs a server. Use \`fallback: false\` with \`output: 'export'\`.`
+      );
+    }
+    pathnames = paths;
+    pageFallback = fallback;
+  }
+
+  const matchParams = getRouteMatcher(getRouteRegex(page));
+  const exported: ExportedPages = {
+    fallback: pageFallback,
+    pages: [],
+    notFound: [],
+  };
+  for (const pathname of pathnames) {
+    const rendered = await renderPage(
+      page,
+      pathname,
+      matchParams(pathname) || {}
+    );
+    if (rendered == null) {
+      exported.notFound.push(pathname);
+    } else {
+      exported.pages.push({ pathname, ...rendered });
+    }
+  }
+  return exported;
+}
+
+async function renderPage(
+  page: string,
+  pathname: string,
+  params: Record<string, string | string[]>
+): Promise<Omit<ExportedPage, "pathname"> | null> {
+  // The client chunks aren't part of the export yet, so the pages don't
+  // reference any.
+  const buildManifest: BuildManifest = {
+    pages: {
+      "/_app": [],
+      [page]: [],
+    },
+    devFiles: [],
+    ampDevFiles: [],
+    polyfillFiles: [],
+    lowPriorityFiles: [],
+    rootMainFiles: [],
+    ampFirstPages: [],
+  };
+
+  const renderOpts: RenderOpts = {
+    /* LoadComponentsReturnType */
+    Component,
+    App,
+    Document,
+    pageConfig: {},
+    buildManifest,
+    reactLoadableManifest: {} as ReactLoadableManifest,
+    ComponentMod: {
+      default: Component,
+      ...otherExports,
+    },
+    pathname: page,
+    buildId: "export",
+
+    /* RenderOptsPartial */
+    isDataReq: false,
+    runtimeConfig: {},
+    assetPrefix: process.env.__NEXT_ASSET_PREFIX || "",
+    canonicalBase: "",
+    previewProps: {
+      previewModeId: "",
+      previewModeEncryptionKey: "",
+      previewModeSigningKey: "",
+    },
+    params,
+    basePath: "",
+    nextExport: true,
+    resolvedUrl: pathname,
+    optimizeFonts: false,
+    optimizeCss: false,
+    nextScriptWorkers: false,
+    images: {
+      deviceSizes: [],
+      imageSizes: [],
+      loader: "default",
+      path: "",
+      loaderFile: "",
+      domains: [],
+      disableStaticImages: false,
+      minimumCacheTTL: 0,
+      formats: [],
+      dangerouslyAllowSVG: false,
+      contentSecurityPolicy: "",
+      remotePatterns: [],
+      unoptimized: true,
+    },
+  };
+  if ("getStaticProps" in otherExports) {
+    renderOpts.getStaticProps = otherExports.getStaticProps;
+  }
+
+  const req: IncomingMessage = {
+    url: pathname,
+    method: "GET",
+    headers: {},
+  } as any;
+  const res: ServerResponse = new ServerResponseShim(req) as any;
+  res.statusCode = page === "/404" ? 404 : 200;
+
+  const renderResult = await renderToHTML(
+    req,
+    res,
+    page,
+    { ...params },
+    renderOpts
+  );
+
+  const metadata = renderResult.metadata();
+  if (metadata.isNotFound) {
+    return null;
+  }
+  if (metadata.isRedirect) {
+    throw new Error(
+      `getStaticProps of ${page} returns a redirect for ${pathname}, which needs a server.`
+    );
+  }
+  if (renderResult.isNull()) {
+    throw new Error(`no render result returned for ${pathname}`);
+  }
+  return {
+    html: injectFontPreloads(renderResult.toUnchunkedString()),
+    revalidate:
+      typeof metadata.revalidate === "number" ? metadata.revalidate : false,
+  };
+}
@@ -25,7 +25,7 @@ subscribeToUpdate(
 );
 
 initializeHMR({
-  assetPrefix: "",
+  assetPrefix: process.env.__NEXT_ASSET_PREFIX || "",
 });
 
 const el = document.getElementById("__next")!;
@@ -0,0 +1,24 @@
+// @ts-expect-error HTML_POST_PROCESSOR is provided by rust
+import postProcessor from "HTML_POST_PROCESSOR";
+
+/**
+ * Runs a post-processor from `experimental.turbo.htmlPostProcessors` on the
+ * prerendered HTML of a page and returns the processed HTML.
+ */
+export default async function postProcessHtml(
+  html: string,
+  pathname: string
+): Promise<string> {
+  if (typeof postProcessor !== "function") {
+    throw new Error(
+      "An HTML post-processor needs to export a function as its default export"
+    );
+  }
+  const result = await postProcessor(html, { pathname });
+  if (typeof result !== "string") {
+    throw new Error(
+      `The HTML post-processor returned ${typeof result} instead of a string for ${pathname}`
+    );
+  }
+  return result;
+}
@@ -41,6 +41,36 @@ async function loadPageChunk(assetPrefix: string, chunkPath: string) {
   }
 }
 
+/**
+ * Moves the stylesheets of a page into the order of its chunks, which is the
+ * order they are imported in. Stylesheets shared with previously visited pages
+ * are already in the document, so the ones loaded for this page would
+ * otherwise apply in the order of navigation.
+ */
+function orderStylesheets(assetPrefix: string, chunkPaths: string[]) {
+  let previous: HTMLLinkElement | null = null;
+  for (const chunkPath of chunkPaths) {
+    if (!chunkPath.endsWith(".css")) {
+      continue;
+    }
+    const link = document.querySelector<HTMLLinkElement>(
+      `link[href=${JSON.stringify(assetPrefix + chunkPath)}]`
+    );
+    if (!link) {
+      continue;
+    }
+    // Only out of order stylesheets are moved, as moving a stylesheet applies
+    // it again.
+    if (
+      previous &&
+      previous.compareDocumentPosition(link) & Node.DOCUMENT_POSITION_PRECEDING
+    ) {
+      previous.after(link);
+    }
+    previous = link;
+  }
+}
+
 (async () => {
   console.debug("Initializing Next.js");
 
@@ -67,10 +97,15 @@ async function loadPageChunk(assetPrefix: string, chunkPath: string) {
   // for the page loader
   window.__turbopack_load_page_chunks__ = (page, paths) => {
     const chunkPromises = paths.map(loadPageChunk.bind(null, assetPrefix));
-
-    Promise.all(chunkPromises).catch((err) =>
-      console.error("failed to load chunks for page " + page, err)
-    );
+    // The stylesheets are inserted when their chunks start loading, so they
+    // can be ordered before they apply.
+    orderStylesheets(assetPrefix, paths);
+
+    Promise.all(chunkPromises)
+      .then(() => orderStylesheets(assetPrefix, paths))
+      .catch((err) =>
+        console.error("failed to load chunks for page " + page, err)
+      );
   };
 
   const pagePath = window.__NEXT_DATA__.page;
@@ -0,0 +1,122 @@
+import { createRequire } from "node:module";
+import { readFile } from "node:fs/promises";
+import { join } from "node:path";
+import { pathToFileURL } from "node:url";
+
+type PluginConfig =
+  | string
+  | [string | PluginObject, any]
+  | PluginObject
+  | Record<string, any>;
+
+type PluginObject = ((...args: any[]) => any) | { postcssPlugin: string };
+
+type PostCssConfig = {
+  plugins?: PluginConfig[] | Record<string, any>;
+};
+
+type Dependency = { type: "file"; path: string } | { type: "dir"; path: string };
+
+/**
+ * Runs the plugins of the PostCSS config on a stylesheet and returns the
+ * output, with the files it depends on, e.g. the `content` of Tailwind.
+ *
+ * The fingerprint of the dependencies is only passed so that the evaluation
+ * isn't cached across changes of the dependencies.
+ */
+export default async function transform(
+  content: string,
+  resourcePath: string,
+  configPath: string,
+  _dependenciesFingerprint: string
+): Promise<{ css: string; dependencies: Dependency[] }> {
+  // PostCSS is resolved from the project, like in webpack, and the plugins
+  // from the config file.
+  const projectRequire = createRequire(join(process.cwd(), "package.json"));
+  const configRequire = createRequire(configPath);
+  let postcss;
+  try {
+    postcss = projectRequire("postcss");
+  } catch {
+    postcss = projectRequire("next/dist/compiled/postcss");
+  }
+
+  const config = await loadConfig(configPath, configRequire);
+  const plugins = resolvePlugins(config.plugins ?? [], configRequire);
+  const result = await postcss(plugins).process(content, {
+    from: resourcePath,
+    to: resourcePath,
+    map: false,
+  });
+
+  const dependencies: Dependency[] = [{ type: "file", path: configPath }];
+  for (const message of result.messages) {
+    if (message.type === "dependency") {
+      dependencies.push({ type: "file", path: message.file });
+    } else if (message.type === "dir-dependency") {
+      dependencies.push({ type: "dir", path: message.dir });
+    }
+  }
+  return { css: result.css, dependencies };
+}
+
+async function loadConfig(
+  configPath: string,
+  configRequire: NodeRequire
+): Promise<PostCssConfig> {
+  if (configPath.endsWith(".json") || configPath.endsWith(".postcssrc")) {
+    return JSON.parse(await readFile(configPath, "utf-8"));
+  }
+  if (configPath.endsWith(".mjs")) {
+    const module = await import(
+      `${pathToFileURL(configPath).href}?t=${Date.now()}`
+    );
+    return module.default;
+  }
+  // The config is evaluated again when it changes.
+  delete configRequire.cache[configPath];
+  const config = configRequire(configPath);
+  return config.default ?? config;
+}
+
+/**
+ * Supports the array and object forms of `plugins`, with plugins given by
+ * name or as plugin objects. `false` options disable a plugin.
+ */
+function resolvePlugins(
+  plugins: PluginConfig[] | Record<string, any>,
+  configRequire: NodeRequire
+) {
+  const entries = Array.isArray(plugins) ? plugins : Object.entries(plugins);
+  return entries.flatMap((plugin) => {
+    if (typeof plugin === "string") {
+      return [loadPlugin(plugin, undefined, configRequire)];
+    }
+    if (Array.isArray(plugin)) {
+      const [nameOrPlugin, options] = plugin;
+      if (options === false) {
+        return [];
+      }
+      return typeof nameOrPlugin === "string"
+        ? [loadPlugin(nameOrPlugin, options, configRequire)]
+        : [nameOrPlugin];
+    }
+    return [plugin];
+  });
+}
+
+function loadPlugin(name: string, options: any, configRequire: NodeRequire) {
+  const module = configRequire(name);
+  const plugin = module.default ?? module;
+  return options === undefined || options === true || isEmpty(options)
+    ? plugin()
+    : plugin(options);
+}
+
+function isEmpty(options: any) {
+  return (
+    typeof options === "object" &&
+    options !== null &&
+    Object.keys(options).length === 0
+  );
+}
@@ -2,19 +2,33 @@ import type { Ipc } from "@vercel/turbopack-next/ipc/index";
 import type { IncomingMessage, ServerResponse } from "node:http";
 import { Buffer } from "node:buffer";
 import { createServer, makeRequest } from "@vercel/turbopack-next/ipc/server";
-import { toPairs } from "@vercel/turbopack-next/internal/headers";
+import {
+  entriesFromHeaders,
+  toPairs,
+} from "@vercel/turbopack-next/internal/headers";
+import { wasmBindingName } from "@vercel/turbopack-next/internal/edge-wasm";
+import {
+  EvaluationMetrics,
+  serverTimingHeader,
+  startEvaluationMetrics,
+} from "@vercel/turbopack-next/internal/evaluation-metrics";
 import { makeResolver } from "next/dist/server/lib/route-resolver";
 import loadConfig from "next/dist/server/config";
 import { PHASE_DEVELOPMENT_SERVER } from "next/dist/shared/lib/constants";
+import type { Rewrite } from "next/dist/lib/load-custom-routes";
+import { registerInstrumentation } from "@vercel/turbopack-next/internal/instrumentation";
 
 import "next/dist/server/node-polyfill-fetch.js";
 
 import middlewareChunkGroup from "MIDDLEWARE_CHUNK_GROUP";
 import middlewareConfig from "MIDDLEWARE_CONFIG";
+import * as instrumentation from "INSTRUMENTATION";
 
 type RouterRequest = {
   method: string;
   pathname: string;
+  // Header values are "latin1" strings, with a character for each byte, like
+  // Node.js represents them. The same goes for all headers sent back.
   rawHeaders: [string, string][];
   rawQuery: string;
 };
@@ -23,7 +37,7 @@ type RouteResult =
   | {
       type: "rewrite";
       url: string;
-      headers: Record<string, string>;
+      headers: Record<string, string | string[] | number>;
     }
   | {
       type: "none";
@@ -45,6 +59,7 @@ type MessageData =
       type: "rewrite";
       data: RewriteResponse;
     }
+  | { type: "middleware-body-too-large"; data: { limit: number } }
   | { type: "none" };
 
 type RewriteResponse = {
@@ -60,9 +75,11 @@ type MiddlewareHeadersResponse = {
 let resolveRouteMemo: Promise<
   (req: IncomingMessage, res: ServerResponse) => Promise<void>
 >;
+let resolveRouteMemoKey: string | undefined;
 
 async function getResolveRoute(
-  dir: string
+  dir: string,
+  interceptionRewrites: Rewrite[]
 ): ReturnType<
   typeof import("next/dist/server/lib/route-resolver").makeResolver
 > {
@@ -74,8 +91,29 @@ async function getResolveRoute(
     true
   );
 
+  // Intercepting routes are rewritten before the rewrites from the config, as
+  // they are part of the file system routes.
+  const configRewrites = nextConfig.rewrites;
+  nextConfig.rewrites = async () => {
+    const rewrites = (await configRewrites?.()) ?? [];
+    if (Array.isArray(rewrites)) {
+      return {
+        beforeFiles: interceptionRewrites,
+        afterFiles: rewrites,
+        fallback: [],
+      };
+    }
+    return {
+      ...rewrites,
+      beforeFiles: [...interceptionRewrites, ...(rewrites.beforeFiles ?? [])],
+    };
+  };
+
   return await makeResolver(dir, nextConfig, {
     files: middlewareChunkGroup.filter((f) => /\.[mc]?js$/.test(f)),
+    wasm: middlewareChunkGroup
+      .filter((f) => f.endsWith(".wasm"))
+      .map((f) => ({ name: wasmBindingName(f), filePath: f })),
     matcher: middlewareConfig.matcher,
   });
 }
@@ -83,10 +121,24 @@ async function getResolveRoute(
 export default async function route(
   ipc: Ipc<RouterRequest, IpcOutgoingMessage>,
   routerRequest: RouterRequest,
-  dir: string
+  dir: string,
+  interceptionRewrites: Rewrite[],
+  bodySizeLimit: number
 ) {
+  // The router process is kept alive between requests, `register` only runs
+  // for the first one.
+  await registerInstrumentation(instrumentation);
+
+  // The intercepting routes change with the app directory, while the router
+  // process is kept alive.
+  const memoKey = JSON.stringify(interceptionRewrites);
+  if (resolveRouteMemoKey !== memoKey) {
+    resolveRouteMemoKey = memoKey;
+    resolveRouteMemo = getResolveRoute(dir, interceptionRewrites);
+  }
+  const stopMetrics = startEvaluationMetrics();
   const [resolveRoute, server] = await Promise.all([
-    (resolveRouteMemo ??= getResolveRoute(dir)),
+    resolveRouteMemo,
     createServer(),
   ]);
 
@@ -119,15 +171,26 @@ export default async function route(
     // We again cannot block on the clientResponsePromise, because an error may
     // occur in the routePromise while we're waiting.
     const responsePromise = clientResponsePromise.then((c) =>
-      handleClientResponse(ipc, c)
+      handleClientResponse(ipc, c, bodySizeLimit)
+    );
+
+    // A middleware producing a runaway stream never finishes, so an aborted
+    // response doesn't wait for the route.
+    const abortedPromise = responsePromise.then((response) =>
+      response.type === "middleware-body-too-large"
+        ? [response]
+        : new Promise<never>(() => {})
     );
 
     // Now that both promises are in progress, we await both so that a
     // rejection in either will end the routing.
-    const [response] = await Promise.all([responsePromise, routePromise]);
+    const [response] = await Promise.race([
+      Promise.all([responsePromise, routePromise]),
+      abortedPromise,
+    ]);
 
     server.close();
-    return response;
+    return withServerTiming(response, stopMetrics());
   } catch (e) {
     // Server doesn't need to be closed, because the sendError will terminate
     // the process.
@@ -135,9 +198,30 @@ export default async function route(
   }
 }
 
+/**
+ * Adds the metrics of the routing, which includes running middleware, to the
+ * response headers.
+ */
+function withServerTiming(
+  response: MessageData,
+  metrics: EvaluationMetrics
+): MessageData {
+  const header = serverTimingHeader("middleware", metrics);
+  switch (response.type) {
+    case "rewrite":
+      response.data.headers.push(header);
+      break;
+    case "full-middleware":
+      response.data.headers.headers.push(header);
+      break;
+  }
+  return response;
+}
+
 async function handleClientResponse(
   _ipc: Ipc<RouterRequest, IpcOutgoingMessage>,
-  clientResponse: IncomingMessage
+  clientResponse: IncomingMessage,
+  bodySizeLimit: number
 ): Promise<MessageData> {
   if (clientResponse.headers["x-nextjs-route-result"] === "1") {
     clientResponse.setEncoding("utf8");
@@ -160,7 +244,7 @@ async function handleClientResponse(
           type: "rewrite",
           data: {
             url: data.url,
-            headers: Object.entries(data.headers),
+            headers: entriesFromHeaders(data.headers),
           },
         };
     }
@@ -188,7 +272,17 @@ async function handleClientResponse(
   // });
 
   const buffers = [];
+  let size = 0;
   for await (const chunk of clientResponse) {
+    size += (chunk as Buffer).length;
+    // Stops reading runaway streams, e.g. from a middleware writing in a loop.
+    if (size > bodySizeLimit) {
+      clientResponse.destroy();
+      return {
+        type: "middleware-body-too-large",
+        data: { limit: bodySizeLimit },
+      };
+    }
     buffers.push(chunk as Buffer);
   }
   return {
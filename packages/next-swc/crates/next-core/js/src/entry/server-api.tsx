@@ -12,6 +12,7 @@ import {
   NodeNextResponse,
 } from "next/dist/server/base-http/node";
 import { parse } from "node:querystring";
+import { revalidatePath } from "@vercel/turbopack-next/internal/cache";
 
 startHandler(({ request, response, query, params, path }) => {
   const parsedQuery = parse(query);
@@ -31,6 +32,13 @@ startHandler(({ request, response, query, params, path }) => {
       previewModeId: "",
       previewModeEncryptionKey: "",
       previewModeSigningKey: "",
+      // `res.revalidate()` revalidates the path in the ISR cache, which
+      // renders it again on its next request.
+      revalidate: async (revalidateReq, revalidateRes) => {
+        await revalidatePath(new URL(revalidateReq.url!, "next://").pathname);
+        revalidateRes.setHeader("x-nextjs-cache", "REVALIDATED");
+        revalidateRes.end();
+      },
     },
     false,
     true,
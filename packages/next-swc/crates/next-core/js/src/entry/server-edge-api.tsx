@@ -1,7 +1,10 @@
 // IPC need to be the first import to allow it to catch errors happening during
 // the other imports
 import startHandler from "@vercel/turbopack-next/internal/api-server-handler";
-import { runEdgeFunction } from "@vercel/turbopack-next/internal/edge";
+import {
+  edgeFunctionFiles,
+  runEdgeFunction,
+} from "@vercel/turbopack-next/internal/edge";
 
 import { join } from "path";
 
@@ -18,10 +21,7 @@ import {
 startHandler(async ({ request, response, query, params, path }) => {
   const edgeInfo = {
     name: "edge",
-    paths: chunkGroup.map((chunk: string) =>
-      join(process.cwd(), ".next/server/pages", chunk)
-    ),
-    wasm: [],
+    ...edgeFunctionFiles(chunkGroup, join(process.cwd(), ".next/server/pages")),
     env: Object.keys(process.env),
     assets: [],
   };
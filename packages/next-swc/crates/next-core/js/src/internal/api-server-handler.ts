@@ -8,7 +8,14 @@ import type { ClientRequest, IncomingMessage, Server } from "node:http";
 import type { ServerResponse } from "node:http";
 import { createServer, makeRequest } from "@vercel/turbopack-next/ipc/server";
 import { toPairs } from "@vercel/turbopack-next/internal/headers";
+import {
+  EvaluationMetrics,
+  serverTimingHeader,
+  startEvaluationMetrics,
+} from "@vercel/turbopack-next/internal/evaluation-metrics";
 import { Buffer } from "node:buffer";
+import * as instrumentation from "@vercel/turbopack-next/instrumentation";
+import { registerInstrumentation } from "@vercel/turbopack-next/internal/instrumentation";
 
 const ipc = IPC as Ipc<IpcIncomingMessage, IpcOutgoingMessage>;
 
@@ -55,10 +62,13 @@ type Operation = {
 
 export default function startHandler(handler: Handler): void {
   (async () => {
+    await registerInstrumentation(instrumentation);
+
     while (true) {
       let operationPromise: Promise<Operation> | null = null;
 
       const msg = await ipc.recv();
+      const stopMetrics = startEvaluationMetrics();
 
       switch (msg.type) {
         case "headers": {
@@ -95,7 +105,7 @@ export default function startHandler(handler: Handler): void {
       await Promise.all([
         endOperation(operation, body),
         operation.clientResponsePromise.then((clientResponse) =>
-          handleClientResponse(operation.server, clientResponse)
+          handleClientResponse(operation.server, clientResponse, stopMetrics)
         ),
       ]);
     }
@@ -135,12 +145,18 @@ export default function startHandler(handler: Handler): void {
 
   function handleClientResponse(
     server: Server,
-    clientResponse: IncomingMessage
+    clientResponse: IncomingMessage,
+    stopMetrics: () => EvaluationMetrics
   ) {
     const responseData: Buffer[] = [];
+    // The headers are sent before the body, so the metrics cover the handler
+    // until it starts responding.
     const responseHeaders: ResponseHeaders = {
       status: clientResponse.statusCode!,
-      headers: toPairs(clientResponse.rawHeaders),
+      headers: [
+        ...toPairs(clientResponse.rawHeaders),
+        serverTimingHeader("api", stopMetrics()),
+      ],
     };
 
     ipc.send({
@@ -0,0 +1,160 @@
+import { createHash } from "node:crypto";
+import {
+  mkdir,
+  open,
+  readFile,
+  rename,
+  stat,
+  unlink,
+  writeFile,
+} from "node:fs/promises";
+import { dirname, join, resolve } from "node:path";
+
+// The storage of the ISR cache and the fetch cache, and their on-demand
+// revalidation, see `next-cache.ts`.
+
+/** When every tag was last revalidated, in milliseconds since the epoch. */
+type TagsManifest = Record<string, number>;
+
+/**
+ * Whether a cache entry can be served, has to be revalidated in the
+ * background, or was revalidated on demand and has to be replaced before it's
+ * served.
+ */
+export type EntryState = "fresh" | "stale" | "revalidated";
+
+// The renderers of a pool run in separate processes, so the cache is shared
+// through the disk. It's in the `distDir`, which is relative to the project
+// directory, the working directory of the renderers.
+const CACHE_DIR = resolve(
+  process.cwd(),
+  process.env.__NEXT_CACHE_DIR || ".next/cache"
+);
+const TAGS_MANIFEST = join(CACHE_DIR, "tags-manifest.json");
+
+/** How long to wait before trying to take a lock again. */
+const LOCK_RETRY_MS = 10;
+/** A lock held for longer was left by a process which exited. */
+const LOCK_STALE_MS = 10_000;
+
+/** The background revalidations of this process, by entry path. */
+const revalidations = new Map<string, Promise<void>>();
+
+/**
+ * Revalidates the cached responses and fetches with a tag. They are replaced
+ * on their next use.
+ */
+export async function revalidateTag(tag: string): Promise<void> {
+  // Other processes update the manifest too, so it's read again under the
+  // lock and only the tag is changed.
+  await withLock(TAGS_MANIFEST, async () => {
+    const manifest = await readTagsManifest();
+    manifest[tag] = Math.max(manifest[tag] ?? 0, Date.now());
+    await writeJson(TAGS_MANIFEST, manifest);
+  });
+}
+
+/**
+ * Revalidates the cached responses of a path, see {@link revalidateTag}.
+ */
+export function revalidatePath(path: string): Promise<void> {
+  return revalidateTag(path);
+}
+
+/**
+ * Returns the state of an entry with tags, created at `createdAt`, which is
+ * revalidated after `revalidate` seconds, or only on demand when it's `false`.
+ */
+export async function entryState(
+  tags: string[],
+  createdAt: number,
+  revalidate: number | false
+): Promise<EntryState> {
+  const manifest = await readTagsManifest();
+  if (tags.some((tag) => (manifest[tag] ?? 0) >= createdAt)) {
+    return "revalidated";
+  }
+  if (revalidate !== false && Date.now() - createdAt >= revalidate * 1000) {
+    return "stale";
+  }
+  return "fresh";
+}
+
+/**
+ * Runs a revalidation of an entry in the background, unless one is already
+ * running. Failures are logged, and the stale entry keeps being served.
+ */
+export function revalidateInBackground(
+  path: string,
+  revalidate: () => Promise<unknown>
+) {
+  if (revalidations.has(path)) {
+    return;
+  }
+  const revalidation = revalidate()
+    .then(() => {})
+    .catch((err) => {
+      console.error(`Failed to revalidate ${path}:`, err);
+    })
+    .finally(() => revalidations.delete(path));
+  revalidations.set(path, revalidation);
+}
+
+/**
+ * Returns the path of a cache entry, e.g. `entryPath("fetch", key)`.
+ */
+export function entryPath(kind: string, key: string): string {
+  const hash = createHash("sha1").update(key).digest("hex");
+  return join(CACHE_DIR, kind, `${hash}.json`);
+}
+
+async function readTagsManifest(): Promise<TagsManifest> {
+  return (await readJson<TagsManifest>(TAGS_MANIFEST)) ?? {};
+}
+
+export async function readJson<T>(path: string): Promise<T | null> {
+  try {
+    return JSON.parse(await readFile(path, "utf-8"));
+  } catch {
+    // A missing or partially written file is like an empty cache.
+    return null;
+  }
+}
+
+/**
+ * Runs `fn` while holding the lock of `path`, a `.lock` file next to it which
+ * only one process can create at a time.
+ */
+async function withLock<T>(path: string, fn: () => Promise<T>): Promise<T> {
+  const lockPath = `${path}.lock`;
+  await mkdir(dirname(lockPath), { recursive: true });
+  for (;;) {
+    try {
+      await (await open(lockPath, "wx")).close();
+      break;
+    } catch (err) {
+      if ((err as NodeJS.ErrnoException).code !== "EEXIST") {
+        throw err;
+      }
+    }
+    const lock = await stat(lockPath).catch(() => null);
+    if (lock && Date.now() - lock.mtimeMs > LOCK_STALE_MS) {
+      await unlink(lockPath).catch(() => {});
+      continue;
+    }
+    await new Promise((done) => setTimeout(done, LOCK_RETRY_MS));
+  }
+  try {
+    return await fn();
+  } finally {
+    await unlink(lockPath).catch(() => {});
+  }
+}
+
+export async function writeJson(path: string, value: unknown): Promise<void> {
+  await mkdir(dirname(path), { recursive: true });
+  // Renamed into place, so other processes never read a partial file.
+  const tmpPath = `${path}.${process.pid}.tmp`;
+  await writeFile(tmpPath, JSON.stringify(value));
+  await rename(tmpPath, path);
+}
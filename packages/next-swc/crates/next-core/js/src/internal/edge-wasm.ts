@@ -0,0 +1,29 @@
+// Runtime support for `.wasm` imports in the edge runtime.
+//
+// The edge sandbox compiles the wasm files of an edge function before its
+// chunks are evaluated, and binds each module to a global named after the file.
+
+/**
+ * Returns the name of the global the wasm file at `path` is bound to.
+ */
+export function wasmBindingName(path: string): string {
+  const fileName = path.slice(path.lastIndexOf("/") + 1);
+  return `wasm_${fileName.replace(/[^a-zA-Z0-9_$]/g, "_")}`;
+}
+
+/**
+ * Returns the `WebAssembly.Module` the sandbox compiled for the wasm file at
+ * `url`.
+ */
+export function wasmBinding(url: URL): WebAssembly.Module {
+  const name = wasmBindingName(url.pathname);
+  const module = (globalThis as any)[name];
+  // The module is compiled outside of the sandbox, so `instanceof` can't be
+  // used to check it.
+  if (module == null) {
+    throw new Error(
+      `The WebAssembly module \`${name}\` was not provided to the Edge Runtime.`
+    );
+  }
+  return module;
+}
@@ -12,6 +12,31 @@ import { parse, ParsedUrlQuery } from "querystring";
 import type { Params } from "next/dist/shared/lib/router/utils/route-matcher";
 import { FetchEventResult } from "next/dist/server/web/types";
 import { getCloneableBody } from "next/dist/server/body-streams";
+import { wasmBindingName } from "./edge-wasm";
+
+/**
+ * Splits the files of an edge chunk group, relative to `dir`, into the scripts
+ * the sandbox evaluates and the wasm files it compiles and binds to globals.
+ */
+export function edgeFunctionFiles(
+  chunkGroup: string[],
+  dir: string
+): {
+  paths: string[];
+  wasm: { name: string; filePath: string }[];
+} {
+  return {
+    paths: chunkGroup
+      .filter((file) => /\.[mc]?js$/.test(file))
+      .map((file) => join(dir, file)),
+    wasm: chunkGroup
+      .filter((file) => file.endsWith(".wasm"))
+      .map((file) => ({
+        name: wasmBindingName(file),
+        filePath: join(dir, file),
+      })),
+  };
+}
 
 // This is an adapted version of a similar function in next-dev-server.
 // TODO exposes this method from next.js instead
@@ -28,7 +53,7 @@ export async function runEdgeFunction({
   edgeInfo: {
     name: string;
     paths: string[];
-    wasm: unknown[];
+    wasm: { name: string; filePath: string }[];
     env: unknown[];
     assets: unknown[];
   };
@@ -0,0 +1,4 @@
+// The module an alias to `false` in `experimental.turbo.resolveAlias` resolves
+// to.
+
+export default {};
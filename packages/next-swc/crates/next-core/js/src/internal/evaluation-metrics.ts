@@ -0,0 +1,57 @@
+/**
+ * The resources used by an evaluation in a worker, e.g. running middleware or
+ * rendering a page.
+ */
+export type EvaluationMetrics = {
+  durationMs: number;
+  cpuTimeMs: number;
+  peakRssBytes: number;
+};
+
+const RSS_SAMPLE_INTERVAL_MS = 10;
+
+/**
+ * Starts measuring an evaluation. Workers evaluate one request at a time, so
+ * the CPU time and memory of the process are the ones of the evaluation. The
+ * peak memory is sampled, so short spikes can be missed.
+ *
+ * Returns a function which stops measuring and returns the metrics.
+ */
+export function startEvaluationMetrics(): () => EvaluationMetrics {
+  const start = performance.now();
+  const startCpuUsage = process.cpuUsage();
+  let peakRssBytes = process.memoryUsage.rss();
+  const interval = setInterval(() => {
+    peakRssBytes = Math.max(peakRssBytes, process.memoryUsage.rss());
+  }, RSS_SAMPLE_INTERVAL_MS);
+  interval.unref();
+
+  return () => {
+    clearInterval(interval);
+    const cpuUsage = process.cpuUsage(startCpuUsage);
+    return {
+      durationMs: performance.now() - start,
+      cpuTimeMs: (cpuUsage.user + cpuUsage.system) / 1000,
+      peakRssBytes: Math.max(peakRssBytes, process.memoryUsage.rss()),
+    };
+  };
+}
+
+/**
+ * Formats the metrics of an evaluation as a `Server-Timing` header, which
+ * shows up in the network panel of browser devtools.
+ */
+export function serverTimingHeader(
+  name: string,
+  metrics: EvaluationMetrics
+): [string, string] {
+  const peakRssMb = (metrics.peakRssBytes / (1024 * 1024)).toFixed(1);
+  return [
+    "Server-Timing",
+    [
+      `${name};dur=${metrics.durationMs.toFixed(1)}`,
+      `${name}-cpu;dur=${metrics.cpuTimeMs.toFixed(1)}`,
+      `${name}-peak-rss;desc="${peakRssMb} MB"`,
+    ].join(", "),
+  ];
+}
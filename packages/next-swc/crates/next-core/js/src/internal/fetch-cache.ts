@@ -0,0 +1,157 @@
+import { AsyncLocalStorage } from "node:async_hooks";
+import { patchFetch } from "next/dist/server/lib/patch-fetch";
+import type { StaticGenerationAsyncStorage } from "next/dist/client/components/static-generation-async-storage";
+import type * as ServerHooks from "next/dist/client/components/hooks-server-context";
+import {
+  entryPath,
+  entryState,
+  readJson,
+  revalidateInBackground,
+  writeJson,
+} from "@vercel/turbopack-next/internal/cache";
+
+type CacheEntry = {
+  status: number;
+  statusText: string;
+  headers: Array<[string, string]>;
+  /** The body, base64 encoded. */
+  body: string;
+  revalidate: number | false;
+  /** The tags revalidating the entry, see `revalidateTag`. */
+  tags: string[];
+  /** When the response was fetched, in milliseconds since the epoch. */
+  fetchedAt: number;
+};
+
+/** The `next` options of `fetch` in server components and route handlers. */
+type NextFetchOptions = {
+  revalidate?: number | false;
+  tags?: string[];
+  internal?: boolean;
+};
+
+type FetchOptions = RequestInit & { next?: NextFetchOptions };
+
+/** The tags of the cached fetches of a render, see {@link collectFetchTags}. */
+const fetchTags = new AsyncLocalStorage<Set<string>>();
+
+/**
+ * Caches the responses of `fetch` on disk, like the fetch cache of
+ * `next start`. Responses are cached until they are revalidated with
+ * `revalidateTag` or `revalidatePath`, or after `next.revalidate` seconds, in
+ * which case the stale response is served while it's fetched again in the
+ * background.
+ *
+ * Like in Next.js, fetches aren't cached with `cache: "no-store"` or
+ * `next: { revalidate: 0 }`, for methods other than `GET` and `HEAD`, or with
+ * `authorization` or `cookie` headers, unless they opt into caching.
+ *
+ * Next.js patches `fetch` too, which drops the `next` options, so it's patched
+ * first and wrapped by the cache.
+ */
+export function installFetchCache(module: {
+  serverHooks: typeof ServerHooks;
+  staticGenerationAsyncStorage: StaticGenerationAsyncStorage;
+}) {
+  if ((globalThis.fetch as any).__nextFetchCache) {
+    return;
+  }
+  patchFetch(module);
+
+  const nextFetch = globalThis.fetch;
+  const cachedFetch = async (
+    input: RequestInfo | URL,
+    init?: FetchOptions
+  ): Promise<Response> => {
+    // Only reads the request, constructing a `Request` could consume its body.
+    const inputRequest = input instanceof Request ? input : null;
+    const method = (
+      init?.method ??
+      inputRequest?.method ??
+      "GET"
+    ).toUpperCase();
+    const url = inputRequest?.url ?? input.toString();
+    const headers = new Headers(init?.headers ?? inputRequest?.headers);
+    const next: NextFetchOptions = {
+      ...(input as { next?: NextFetchOptions }).next,
+      ...init?.next,
+    };
+    const cache = init?.cache ?? inputRequest?.cache;
+    const explicitRevalidate =
+      cache === "force-cache" ? false : next.revalidate;
+    const uncacheable =
+      next.internal ||
+      cache === "no-store" ||
+      cache === "no-cache" ||
+      explicitRevalidate === 0 ||
+      !["GET", "HEAD"].includes(method) ||
+      (explicitRevalidate === undefined &&
+        (headers.has("authorization") || headers.has("cookie")));
+    if (uncacheable) {
+      return nextFetch(input, init);
+    }
+
+    const revalidate = explicitRevalidate ?? false;
+    const tags = next.tags ?? [];
+    tags.forEach((tag) => fetchTags.getStore()?.add(tag));
+
+    const key = JSON.stringify([
+      method,
+      url,
+      Array.from(headers.entries()).sort(),
+    ]);
+    const path = entryPath("fetch", key);
+    const fetchEntry = async () => {
+      const fetchedAt = Date.now();
+      const response = await nextFetch(input, init);
+      if (!response.ok) {
+        // Errors aren't cached, so they are retried on the next fetch.
+        return response;
+      }
+      const body = Buffer.from(await response.clone().arrayBuffer());
+      const entry: CacheEntry = {
+        status: response.status,
+        statusText: response.statusText,
+        headers: Array.from(response.headers.entries()),
+        body: body.toString("base64"),
+        revalidate,
+        tags,
+        fetchedAt,
+      };
+      await writeJson(path, entry);
+      return response;
+    };
+
+    const entry = await readJson<CacheEntry>(path);
+    const state =
+      entry != null
+        ? await entryState(entry.tags, entry.fetchedAt, entry.revalidate)
+        : null;
+    if (entry == null || state === "revalidated") {
+      return fetchEntry();
+    }
+    if (state === "stale") {
+      revalidateInBackground(path, fetchEntry);
+    }
+    return new Response(Buffer.from(entry.body, "base64"), {
+      status: entry.status,
+      statusText: entry.statusText,
+      headers: entry.headers,
+    });
+  };
+
+  Object.assign(cachedFetch, nextFetch, { __nextFetchCache: true });
+  globalThis.fetch = cachedFetch as typeof fetch;
+}
+
+/**
+ * Runs a render and returns the tags of the cached fetches it made, which
+ * revalidate the rendered response too.
+ */
+export async function collectFetchTags<T>(
+  render: () => Promise<T>
+): Promise<{ result: T; tags: string[] }> {
+  const tags = new Set<string>();
+  const result = await fetchTags.run(tags, render);
+  return { result, tags: Array.from(tags) };
+}
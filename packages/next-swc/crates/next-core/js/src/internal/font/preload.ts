@@ -0,0 +1,40 @@
+/**
+ * The font files to preload, registered by the `next/font` modules when they
+ * are loaded. The server renderer of a page only loads the modules of that
+ * page, so these are the fonts the page uses.
+ */
+const fontPreloads = new Set<string>();
+
+export function registerFontPreloads(hrefs: string[]) {
+  for (const href of hrefs) {
+    fontPreloads.add(href);
+  }
+}
+
+export function getFontPreloads(): string[] {
+  return Array.from(fontPreloads);
+}
+
+/**
+ * Adds a `<link rel="preload">` for each registered font file to the head of
+ * an HTML document.
+ */
+export function injectFontPreloads(html: string): string {
+  const headEnd = html.indexOf("</head>");
+  if (headEnd === -1 || fontPreloads.size === 0) {
+    return html;
+  }
+  const links = Array.from(fontPreloads)
+    .map((href) => {
+      const type = `font/${href.slice(href.lastIndexOf(".") + 1)}`;
+      return `<link rel="preload" href="${escapeAttribute(
+        href
+      )}" as="font" type="${escapeAttribute(type)}" crossorigin=""/>`;
+    })
+    .join("");
+  return html.slice(0, headEnd) + links + html.slice(headEnd);
+}
+
+function escapeAttribute(value: string) {
+  return value.replace(/&/g, "&amp;").replace(/"/g, "&quot;");
+}
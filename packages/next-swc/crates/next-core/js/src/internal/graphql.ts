@@ -0,0 +1,101 @@
+// Runtime support for `.graphql`/`.gql` imports, mirroring the `#import`
+// comments and the named operation exports of `graphql-tag/loader`. The
+// documents themselves are parsed at build time.
+
+type DocumentNode = {
+  kind: "Document";
+  definitions: DefinitionNode[];
+  [key: string]: any;
+};
+
+type DefinitionNode = {
+  kind: string;
+  name?: { value: string };
+  [key: string]: any;
+};
+
+function collectFragmentSpreads(node: any, names: Set<string>) {
+  if (node == null || typeof node !== "object") {
+    return;
+  }
+  if (node.kind === "FragmentSpread") {
+    names.add(node.name.value);
+  }
+  for (const key of Object.keys(node)) {
+    if (key !== "loc") {
+      collectFragmentSpreads(node[key], names);
+    }
+  }
+}
+
+/**
+ * Returns `doc` with the fragments of the `imported` documents appended, each
+ * fragment once.
+ */
+export function mergeFragments(
+  doc: DocumentNode,
+  imported: DocumentNode[]
+): DocumentNode {
+  const names = new Set<string>();
+  for (const def of doc.definitions) {
+    if (def.kind === "FragmentDefinition" && def.name != null) {
+      names.add(def.name.value);
+    }
+  }
+  const definitions = [...doc.definitions];
+  for (const importedDoc of imported) {
+    for (const def of importedDoc.definitions) {
+      if (def.kind !== "FragmentDefinition" || def.name == null) {
+        continue;
+      }
+      if (!names.has(def.name.value)) {
+        names.add(def.name.value);
+        definitions.push(def);
+      }
+    }
+  }
+  return { ...doc, definitions };
+}
+
+/**
+ * Returns a document which only contains the operation `name` and the
+ * fragments it (transitively) depends on.
+ */
+export function extractOperation(
+  doc: DocumentNode,
+  name: string
+): DocumentNode {
+  const operation = doc.definitions.find(
+    (def) => def.kind === "OperationDefinition" && def.name?.value === name
+  );
+  if (operation == null) {
+    return doc;
+  }
+
+  const fragments = new Map<string, DefinitionNode>();
+  for (const def of doc.definitions) {
+    if (def.kind === "FragmentDefinition" && def.name != null) {
+      fragments.set(def.name.value, def);
+    }
+  }
+
+  const definitions = [operation];
+  const seen = new Set<string>();
+  const pending = new Set<string>();
+  collectFragmentSpreads(operation, pending);
+  while (pending.size > 0) {
+    const [fragmentName] = pending;
+    pending.delete(fragmentName);
+    if (seen.has(fragmentName)) {
+      continue;
+    }
+    seen.add(fragmentName);
+    const fragment = fragments.get(fragmentName);
+    if (fragment != null) {
+      definitions.push(fragment);
+      collectFragmentSpreads(fragment, pending);
+    }
+  }
+
+  return { ...doc, definitions };
+}
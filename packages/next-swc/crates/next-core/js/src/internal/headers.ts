@@ -20,6 +20,30 @@ export function headersFromEntries(
   return headers;
 }
 
+/**
+ * Converts a map of header names to values, like `OutgoingMessage.getHeaders`
+ * returns, to raw header entries. Each value of a multi-valued header, e.g.
+ * `set-cookie`, becomes a separate entry, in order.
+ */
+export function entriesFromHeaders(
+  headers: Record<string, string | string[] | number | undefined>
+): Array<[string, string]> {
+  const entries: Array<[string, string]> = [];
+  for (const [key, value] of Object.entries(headers)) {
+    if (value === undefined) {
+      continue;
+    }
+    if (Array.isArray(value)) {
+      for (const item of value) {
+        entries.push([key, item]);
+      }
+    } else {
+      entries.push([key, String(value)]);
+    }
+  }
+  return entries;
+}
+
 /**
  * Transforms an array of elements into an array of pairs of elements.
  *
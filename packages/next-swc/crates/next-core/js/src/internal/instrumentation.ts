@@ -0,0 +1,27 @@
+/** The exports of the `instrumentation.ts` file of a project. */
+export type Instrumentation = {
+  register?: () => unknown;
+};
+
+let registration: Promise<void> | null = null;
+
+/**
+ * Calls the `register` export of the instrumentation file of the project.
+ * Every server process calls this on startup, before it handles a request, and
+ * `register` only runs once per process even when this is called again.
+ *
+ * `process.env.NEXT_RUNTIME` is `nodejs` or `edge`, so `register` can import
+ * what the runtime supports.
+ */
+export function registerInstrumentation(
+  instrumentation: Instrumentation
+): Promise<void> {
+  if (registration == null) {
+    registration = (async () => {
+      if (typeof instrumentation.register === "function") {
+        await instrumentation.register();
+      }
+    })();
+  }
+  return registration;
+}
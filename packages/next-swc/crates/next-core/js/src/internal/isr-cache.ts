@@ -0,0 +1,142 @@
+import {
+  entryPath,
+  entryState,
+  readJson,
+  revalidateInBackground,
+  writeJson,
+} from "@vercel/turbopack-next/internal/cache";
+
+/**
+ * A rendered response, as the renderers send it to the dev server.
+ */
+export type CachedResponse = {
+  statusCode: number;
+  headers: Array<[string, string]>;
+  body: string;
+};
+
+/**
+ * The number of seconds after which a response is revalidated, `false` when
+ * it's only revalidated on demand, or `undefined` when it isn't cached.
+ */
+export type Revalidate = number | false | undefined;
+
+/**
+ * A rendered response, with the tags of the cached fetches it used, see
+ * `fetch-cache.ts`.
+ */
+type Rendered<T extends CachedResponse> = {
+  response: T;
+  revalidate: Revalidate;
+  tags?: string[];
+};
+
+type CacheEntry<T extends CachedResponse = CachedResponse> = {
+  response: T;
+  revalidate: number | false;
+  /** The tags revalidating the entry, see `revalidateTag`. */
+  tags: string[];
+  /** When the response was rendered, in milliseconds since the epoch. */
+  renderedAt: number;
+};
+
+type CacheStatus = "HIT" | "STALE" | "MISS" | "REVALIDATED";
+
+/**
+ * The header with which `res.revalidate()` of API routes requests an
+ * on-demand revalidation of a path.
+ */
+const PRERENDER_REVALIDATE_HEADER = "x-prerender-revalidate";
+
+/**
+ * Headers describing a single render, which must not be replayed with the
+ * cached response, e.g. the `Server-Timing` of the render metrics.
+ */
+const UNCACHED_HEADERS = new Set(["server-timing"]);
+
+/** Set with `experimental.turbo.isrCache`. */
+const enabled = process.env.__NEXT_ISR_CACHE === "true";
+
+/**
+ * Serves a rendered page from the ISR cache, like `next start` does.
+ *
+ * Fresh responses are served from the cache. Stale ones are served while the
+ * page is re-rendered in the background. Responses revalidated on demand with
+ * `revalidateTag` are rendered again before they are served, like requests
+ * with the `x-prerender-revalidate` header.
+ *
+ * `key` identifies the response, e.g. the pathname, and `tags` are the tags
+ * revalidating it with `revalidateTag`, in addition to the key and the tags
+ * returned by `render`. Only successful responses with a `revalidate` are
+ * cached.
+ */
+export async function withIsrCache<T extends CachedResponse>(
+  key: string,
+  tags: string[],
+  rawHeaders: Array<[string, string]>,
+  render: () => Promise<Rendered<T>>
+): Promise<T> {
+  if (!enabled) {
+    return (await render()).response;
+  }
+
+  const revalidateOnDemand = rawHeaders.some(
+    ([name]) => name.toLowerCase() === PRERENDER_REVALIDATE_HEADER
+  );
+  const entry = revalidateOnDemand
+    ? null
+    : await readJson<CacheEntry<T>>(entryPath("isr", key));
+  const state =
+    entry != null
+      ? await entryState(entry.tags, entry.renderedAt, entry.revalidate)
+      : null;
+  if (entry != null && state === "fresh") {
+    return withCacheStatus(entry.response, "HIT");
+  }
+  if (entry != null && state === "stale") {
+    revalidateInBackground(entryPath("isr", key), () =>
+      renderEntry(key, tags, render)
+    );
+    return withCacheStatus(entry.response, "STALE");
+  }
+
+  const response = await renderEntry(key, tags, render);
+  return withCacheStatus(response, revalidateOnDemand ? "REVALIDATED" : "MISS");
+}
+
+async function renderEntry<T extends CachedResponse>(
+  key: string,
+  tags: string[],
+  render: () => Promise<Rendered<T>>
+): Promise<T> {
+  const renderedAt = Date.now();
+  const { response, revalidate, tags: renderedTags = [] } = await render();
+  if (
+    response.statusCode === 200 &&
+    (revalidate === false || (revalidate != null && revalidate > 0))
+  ) {
+    const entry: CacheEntry<T> = {
+      response: {
+        ...response,
+        headers: response.headers.filter(
+          ([name]) => !UNCACHED_HEADERS.has(name.toLowerCase())
+        ),
+      },
+      revalidate,
+      tags: [key, ...tags, ...renderedTags],
+      renderedAt,
+    };
+    await writeJson(entryPath("isr", key), entry);
+  }
+  return response;
+}
+
+function withCacheStatus<T extends CachedResponse>(
+  response: T,
+  status: CacheStatus
+): T {
+  return {
+    ...response,
+    headers: [...response.headers, ["x-nextjs-cache", status]],
+  };
+}
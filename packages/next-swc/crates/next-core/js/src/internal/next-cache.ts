@@ -0,0 +1,8 @@
+/**
+ * `next/cache` of server code in the app directory, which revalidates cached
+ * pages and fetches on demand.
+ */
+export {
+  revalidatePath,
+  revalidateTag,
+} from "@vercel/turbopack-next/internal/cache";
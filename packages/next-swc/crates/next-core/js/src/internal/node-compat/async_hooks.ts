@@ -0,0 +1,6 @@
+// Edge-safe shim for the `async_hooks` Node.js module, backed by the
+// `AsyncLocalStorage` global of the edge runtime.
+
+export const AsyncLocalStorage = (globalThis as any).AsyncLocalStorage;
+
+export default { AsyncLocalStorage };
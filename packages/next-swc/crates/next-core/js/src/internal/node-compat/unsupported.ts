@@ -0,0 +1,23 @@
+// Stands in for Node.js modules which are not available in the edge runtime.
+// Importing the module succeeds, so code which only uses it behind a runtime
+// check keeps working, but any use of it throws.
+
+// Properties which are read when the module is imported, not when it is used.
+const INTEROP_PROPERTIES = new Set<string | symbol>(["__esModule", "then"]);
+
+export function unsupportedModule(name: string): any {
+  const fail = () => {
+    throw new Error(
+      `The Node.js module \`${name}\` is not supported in the Edge Runtime.`
+    );
+  };
+
+  return new Proxy(fail, {
+    get: (_target, prop) =>
+      typeof prop === "symbol" || INTEROP_PROPERTIES.has(prop)
+        ? undefined
+        : fail(),
+    apply: fail,
+    construct: fail,
+  });
+}
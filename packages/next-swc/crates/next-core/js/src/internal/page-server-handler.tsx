@@ -16,6 +16,18 @@ import type { ReactLoadableManifest } from "next/dist/server/load-components";
 
 import { ServerResponseShim } from "@vercel/turbopack-next/internal/http";
 import { headersFromEntries } from "@vercel/turbopack-next/internal/headers";
+import { injectFontPreloads } from "@vercel/turbopack-next/internal/font/preload";
+import { preloadLinkHeader } from "@vercel/turbopack-next/internal/preload";
+import {
+  Revalidate,
+  withIsrCache,
+} from "@vercel/turbopack-next/internal/isr-cache";
+import {
+  serverTimingHeader,
+  startEvaluationMetrics,
+} from "@vercel/turbopack-next/internal/evaluation-metrics";
+import * as instrumentation from "@vercel/turbopack-next/instrumentation";
+import { registerInstrumentation } from "@vercel/turbopack-next/internal/instrumentation";
 import type { Ipc } from "@vercel/turbopack-next/ipc/index";
 import type { RenderData } from "types/turbopack";
 import type { ChunkGroup } from "types/next";
@@ -28,14 +40,14 @@ type IpcIncomingMessage = {
   data: RenderData;
 };
 
-type IpcOutgoingMessage =
-  | {
-      type: "response";
-      statusCode: number;
-      headers: Array<[string, string]>;
-      body: string;
-    }
-  | { type: "rewrite"; path: string };
+type ResponseMessage = {
+  type: "response";
+  statusCode: number;
+  headers: Array<[string, string]>;
+  body: string;
+};
+
+type IpcOutgoingMessage = ResponseMessage | { type: "rewrite"; path: string };
 
 const MIME_APPLICATION_JAVASCRIPT = "application/javascript";
 const MIME_TEXT_HTML_UTF8 = "text/html; charset=utf-8";
@@ -56,6 +68,8 @@ export default function startHandler({
   chunkGroup?: ChunkGroup;
 }) {
   (async () => {
+    await registerInstrumentation(instrumentation);
+
     while (true) {
       const msg = await ipc.recv();
 
@@ -71,9 +85,19 @@ export default function startHandler({
         }
       }
 
+      const stopMetrics = startEvaluationMetrics();
       const res = await runOperation(renderData);
-
-      ipc.send(res);
+      const metrics = stopMetrics();
+      // The response can be the one stored in the ISR cache, so the header is
+      // added to a copy.
+      ipc.send(
+        res.type === "response"
+          ? {
+              ...res,
+              headers: [...res.headers, serverTimingHeader("render", metrics)],
+            }
+          : res
+      );
     }
   })().catch((err) => {
     ipc.sendError(err);
@@ -82,6 +106,19 @@ export default function startHandler({
   async function runOperation(
     renderData: RenderData
   ): Promise<IpcOutgoingMessage> {
+    // We provide a dummy base URL to the URL constructor so that it doesn't
+    // throw when we pass a relative URL.
+    const urlPath = new URL(renderData.url, "next://").pathname;
+    let resolvedPath = urlPath;
+    if (isDataReq) {
+      // we still want to match data requests so we remove the prefix and extension
+      // to get back the path the page would have
+      resolvedPath = resolvedPath.replace(
+        /^\/_next\/data\/development(.+).json$/,
+        "$1"
+      );
+    }
+
     if ("getStaticPaths" in otherExports) {
       const {
         paths: prerenderRoutes,
@@ -94,17 +131,6 @@ export default function startHandler({
         configFileName: "next.config.js",
       });
 
-      // We provide a dummy base URL to the URL constructor so that it doesn't
-      // throw when we pass a relative URL.
-      let resolvedPath = new URL(renderData.url, "next://").pathname;
-      if (isDataReq) {
-        // we still want to match data requests so we remove the prefix and extension
-        // to get back the path the page would have
-        resolvedPath = resolvedPath.replace(
-          /^\/_next\/data\/development(.+).json$/,
-          "$1"
-        );
-      }
       if (
         prerenderFallback === false &&
         // TODO(alexkirsz) Strip basePath.
@@ -114,6 +140,23 @@ export default function startHandler({
       }
     }
 
+    // Pages with `getStaticProps` are served from the ISR cache. The HTML and
+    // the data of a path are both tagged with it, so they are revalidated
+    // together.
+    if ("getStaticProps" in otherExports) {
+      return withIsrCache(
+        urlPath,
+        [renderData.path, resolvedPath],
+        renderData.rawHeaders,
+        () => renderPage(renderData)
+      );
+    }
+    return (await renderPage(renderData)).response;
+  }
+
+  async function renderPage(
+    renderData: RenderData
+  ): Promise<{ response: ResponseMessage; revalidate: Revalidate }> {
     // TODO(alexkirsz) This is missing *a lot* of data, but it's enough to get a
     // basic render working.
 
@@ -161,7 +204,7 @@ export default function startHandler({
       /* RenderOptsPartial */
       isDataReq,
       runtimeConfig: {},
-      assetPrefix: "",
+      assetPrefix: process.env.__NEXT_ASSET_PREFIX || "",
       canonicalBase: "",
       previewProps: {
         previewModeId: "",
@@ -240,9 +283,10 @@ export default function startHandler({
 
     // Set when `getStaticProps` returns `notFound: true`.
     const isNotFound = renderResult.metadata().isNotFound;
+    const revalidate = renderResult.metadata().revalidate;
 
     if (isNotFound) {
-      return createNotFoundResponse(isDataReq);
+      return { response: createNotFoundResponse(isDataReq), revalidate };
     }
 
     // Set when `getStaticProps` returns `redirect: { destination, permanent, statusCode }`.
@@ -274,10 +318,13 @@ export default function startHandler({
       }
 
       return {
-        type: "response",
-        statusCode,
-        headers,
-        body: redirect.destination,
+        response: {
+          type: "response",
+          statusCode,
+          headers,
+          body: redirect.destination,
+        },
+        revalidate,
       };
     }
 
@@ -285,11 +332,14 @@ export default function startHandler({
       // TODO(from next.js): change this to a different passing mechanism
       const pageData = renderResult.metadata().pageData;
       return {
-        type: "response",
-        statusCode,
-        headers: [["Content-Type", MIME_APPLICATION_JAVASCRIPT]],
-        // Page data is only returned if the page had getXxyProps.
-        body: JSON.stringify(pageData === undefined ? {} : pageData),
+        response: {
+          type: "response",
+          statusCode,
+          headers: [["Content-Type", MIME_APPLICATION_JAVASCRIPT]],
+          // Page data is only returned if the page had getXxyProps.
+          body: JSON.stringify(pageData === undefined ? {} : pageData),
+        },
+        revalidate,
       };
     }
 
@@ -297,23 +347,33 @@ export default function startHandler({
       throw new Error("no render result returned");
     }
 
-    const body = renderResult.toUnchunkedString();
-
-    // TODO: handle revalidate
-    // const sprRevalidate = renderResult.metadata().revalidate;
+    const contentType = renderResult.contentType() ?? MIME_TEXT_HTML_UTF8;
+    let body = renderResult.toUnchunkedString();
+    const headers: Array<[string, string]> = [["Content-Type", contentType]];
+    if (contentType === MIME_TEXT_HTML_UTF8) {
+      body = injectFontPreloads(body);
+      const link = preloadLinkHeader(
+        renderOpts.assetPrefix ?? "",
+        chunkGroup ?? []
+      );
+      if (link != null) {
+        headers.push(["Link", link]);
+      }
+    }
 
     return {
-      type: "response",
-      statusCode,
-      headers: [
-        ["Content-Type", renderResult.contentType() ?? MIME_TEXT_HTML_UTF8],
-      ],
-      body,
+      response: {
+        type: "response",
+        statusCode,
+        headers,
+        body,
+      },
+      revalidate,
     };
   }
 }
 
-function createNotFoundResponse(isDataReq: boolean): IpcOutgoingMessage {
+function createNotFoundResponse(isDataReq: boolean): ResponseMessage {
   if (isDataReq) {
     return {
       type: "response",
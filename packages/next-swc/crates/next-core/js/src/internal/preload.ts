@@ -0,0 +1,33 @@
+import { getFontPreloads } from "@vercel/turbopack-next/internal/font/preload";
+
+/**
+ * Returns the value of a `Link` header which preloads the chunks of a route
+ * and the fonts it uses, or `undefined` when there is nothing to preload.
+ * Servers supporting 103 Early Hints can send it before the response, so the
+ * browser fetches the chunks while the page is still rendering.
+ *
+ * The chunks are classic scripts rather than ES modules, so they are
+ * preloaded `as=script` instead of with `modulepreload`.
+ */
+export function preloadLinkHeader(
+  assetPrefix: string,
+  chunkPaths: string[]
+): string | undefined {
+  const links = new Set<string>();
+  for (const chunkPath of chunkPaths) {
+    const as = chunkPath.endsWith(".css")
+      ? "style"
+      : chunkPath.endsWith(".js")
+      ? "script"
+      : undefined;
+    if (as == null) {
+      continue;
+    }
+    const href = encodeURI(`${assetPrefix}/${chunkPath}`);
+    links.add(`<${href}>; rel=preload; as=${as}`);
+  }
+  for (const href of getFontPreloads()) {
+    links.add(`<${encodeURI(href)}>; rel=preload; as=font; crossorigin`);
+  }
+  return links.size > 0 ? Array.from(links).join(", ") : undefined;
+}
@@ -0,0 +1,14 @@
+// Loads the entry of a remote container in the browser, see
+// `remote-container.ts`.
+
+export function loadContainerScript(url: string): Promise<void> {
+  return new Promise((resolve, reject) => {
+    const script = document.createElement("script");
+    script.src = url;
+    script.async = true;
+    script.onload = () => resolve();
+    script.onerror = () =>
+      reject(new Error(`Failed to load remote container from ${url}`));
+    document.head.appendChild(script);
+  });
+}
@@ -0,0 +1,34 @@
+// Loads the entry of a remote container in the Node.js server, see
+// `remote-container.ts`.
+
+import { readFile } from "node:fs/promises";
+import { runInThisContext } from "node:vm";
+
+/**
+ * Evaluates the container entry in the global scope, like a script tag does
+ * in the browser. Containers served from `public` are read from `file`, as
+ * the server doesn't know the origin it's served from.
+ */
+export async function loadContainerScript(
+  url: string,
+  file: string | null
+): Promise<void> {
+  let code: string;
+  if (file != null) {
+    code = await readFile(file, "utf8");
+  } else {
+    if (!/^https?:\/\//.test(url)) {
+      throw new Error(
+        `Failed to load remote container from ${url} on the server: the URL must be absolute, or point to a file in \`public\``
+      );
+    }
+    const res = await fetch(url);
+    if (!res.ok) {
+      throw new Error(
+        `Failed to load remote container from ${url}: ${res.status} ${res.statusText}`
+      );
+    }
+    code = await res.text();
+  }
+  runInThisContext(code, { filename: url });
+}
@@ -0,0 +1,62 @@
+// Runtime support for consuming precompiled (module federation style) remote
+// containers configured via `experimental.turbo.remotes`.
+
+type ModuleFactory = () => any;
+
+interface RemoteContainer {
+  init(shareScope: Record<string, unknown>): void | Promise<void>;
+  get(module: string): ModuleFactory | Promise<ModuleFactory>;
+}
+
+const shareScope: Record<string, unknown> = {};
+const containers = new Map<string, Promise<RemoteContainer>>();
+const modules = new Map<string, Promise<any>>();
+
+async function initContainer(
+  global: string,
+  loadScript: () => Promise<void>
+): Promise<RemoteContainer> {
+  if ((globalThis as any)[global] == null) {
+    await loadScript();
+  }
+  const container: RemoteContainer | undefined = (globalThis as any)[global];
+  if (container == null) {
+    throw new Error(
+      `Remote container did not register itself as \`${global}\``
+    );
+  }
+  await container.init(shareScope);
+  return container;
+}
+
+async function getModule(
+  container: Promise<RemoteContainer>,
+  exposed: string
+): Promise<any> {
+  const factory = await (await container).get(exposed);
+  return factory();
+}
+
+/**
+ * Loads the module `exposed` from the remote container registered under
+ * `global`, evaluating the container entry with `loadScript` first if
+ * necessary. Containers and their modules are only loaded once.
+ */
+export function loadRemoteModule(
+  global: string,
+  exposed: string,
+  loadScript: () => Promise<void>
+): Promise<any> {
+  let container = containers.get(global);
+  if (container == null) {
+    container = initContainer(global, loadScript);
+    containers.set(global, container);
+  }
+  const key = `${global}\0${exposed}`;
+  let module = modules.get(key);
+  if (module == null) {
+    module = getModule(container, exposed);
+    modules.set(key, module);
+  }
+  return module;
+}
@@ -0,0 +1,32 @@
+// Runtime support for web workers bundled from
+// `new Worker(new URL("./worker", import.meta.url))`.
+
+/**
+ * Returns the arguments for the `Worker`/`SharedWorker` constructor: the URL of
+ * a script bootstrapping the worker chunks, and the worker options.
+ *
+ * `chunks` are the chunk paths of the worker entry, relative to `/_next/`.
+ * They are loaded from the `assetPrefix`, which is the `basePath` when no
+ * prefix is configured.
+ */
+export function workerArgs(
+  chunks: string[],
+  options?: string | WorkerOptions
+): [string, string | WorkerOptions | undefined] {
+  const assetPrefix = process.env.__NEXT_ASSET_PREFIX || "";
+  const urls = chunks.map(
+    (chunk) => new URL(`${assetPrefix}/_next/${chunk}`, location.href).href
+  );
+
+  // Module workers can't use `importScripts`, the chunks are imported in
+  // order instead. They only register their modules, so they run the same
+  // as modules and as classic scripts.
+  const specifiers = urls.map((url) => JSON.stringify(url));
+  const isModule = typeof options === "object" && options.type === "module";
+  const source = isModule
+    ? specifiers.map((specifier) => `import ${specifier};`).join("\n")
+    : `importScripts(${specifiers.join(", ")});`;
+  const blob = new Blob([source], { type: "text/javascript" });
+
+  return [URL.createObjectURL(blob), options];
+}
@@ -4,9 +4,12 @@ use std::{
     iter::once,
 };
 
-use anyhow::{anyhow, Result};
-use indexmap::indexmap;
-use turbo_tasks::{primitives::OptionStringVc, TryJoinIterExt, Value, ValueToString};
+use anyhow::{anyhow, bail, Context, Result};
+use indexmap::{indexmap, IndexMap};
+use turbo_tasks::{
+    primitives::{OptionStringVc, StringVc},
+    ReadRef, Value, ValueToString,
+};
 use turbo_tasks_env::{CustomProcessEnvVc, EnvMapVc, ProcessEnvVc};
 use turbo_tasks_fs::{rope::RopeBuilder, File, FileContent, FileSystemPathVc};
 use turbopack::{
@@ -15,6 +18,7 @@ use turbopack::{
     ModuleAssetContextVc,
 };
 use turbopack_core::{
+    asset::AssetVc,
     compile_time_info::CompileTimeInfoVc,
     context::{AssetContext, AssetContextVc},
     environment::{EnvironmentIntention, ServerAddrVc},
@@ -26,7 +30,8 @@ use turbopack_dev::DevChunkingContextVc;
 use turbopack_dev_server::{
     html::DevHtmlAssetVc,
     source::{
-        combined::CombinedContentSource, ContentSourceData, ContentSourceVc, NoContentSourceVc,
+        combined::CombinedContentSource, specificity::SpecificityVc, ContentSourceData,
+        ContentSourceVc, NoContentSourceVc,
     },
 };
 use turbopack_ecmascript::{
@@ -39,24 +44,34 @@ use turbopack_node::{
     render::{
         node_api_source::create_node_api_source, rendered_source::create_node_rendered_source,
     },
+    route_matcher::RouteMatcherVc,
     NodeEntry, NodeEntryVc, NodeRenderingEntry, NodeRenderingEntryVc,
 };
 
 use crate::{
-    app_render::{
-        next_layout_entry_transition::NextLayoutEntryTransition, LayoutSegment, LayoutSegmentsVc,
+    app_render::next_layout_entry_transition::NextLayoutEntryTransition,
+    app_structure::{
+        get_global_error_loader_tree, get_not_found_loader_tree, AppStructure, AppStructureItem,
+        AppStructureVc, Components, LoaderTree, LoaderTreeVc, OptionAppStructureVc,
     },
-    app_structure::{AppStructure, AppStructureItem, AppStructureVc, OptionAppStructureVc},
     embed_js::next_js_file,
     env::env_for_js,
     fallback::get_fallback_page,
+    flight_manifest::{
+        client_reference_manifest_asset, emit_manifest, server_reference_manifest_asset,
+    },
+    metadata_routes::{
+        find_metadata_routes, icon_links, MetadataRouteContentSource, MetadataRoutesVc,
+    },
+    mode::NextMode,
     next_client::{
         context::{
             get_client_assets_path, get_client_chunking_context, get_client_compile_time_info,
-            get_client_module_options_context, get_client_resolve_options_context,
+            get_client_module_options_context, get_client_resolve_options_context, get_client_root,
             get_client_runtime_entries, ClientContextType,
         },
         transition::NextClientTransition,
+        worker_transition::NextClientWorkerTransitionVc,
     },
     next_client_chunks::client_chunks_transition::NextClientChunksTransitionVc,
     next_client_component::{
@@ -68,12 +83,19 @@ use crate::{
         context::{get_edge_compile_time_info, get_edge_resolve_options_context},
         transition::NextEdgeTransition,
     },
-    next_route_matcher::NextParamsMatcherVc,
+    next_route_matcher::{
+        NextExactMatcherVc, NextFallbackMatcherVc, NextParamsMatcherVc, NextStaticParamsMatcherVc,
+    },
     next_server::context::{
         get_server_compile_time_info, get_server_module_options_context,
         get_server_resolve_options_context, ServerContextType,
     },
-    util::pathname_for_path,
+    next_shared::dependencies::get_dependencies_runtime_entry,
+    route_handlers::{resolve_route_handler_method, route_handler_methods, RouteHandlerMethod},
+    router::{INTERNAL_ERROR_PATHNAME, NOT_FOUND_PATHNAME},
+    server_actions::{get_app_server_actions, ServerActionsVc, SERVER_ACTION_PATHNAME},
+    static_params::get_static_params,
+    util::{parse_config_from_source, pathname_for_path, NextRuntime},
 };
 
 #[turbo_tasks::function]
@@ -85,6 +107,7 @@ async fn next_client_transition(
     env: ProcessEnvVc,
     client_compile_time_info: CompileTimeInfoVc,
     next_config: NextConfigVc,
+    mode: Value<NextMode>,
 ) -> Result<TransitionVc> {
     let ty = Value::new(ClientContextType::App { app_dir });
     let client_chunking_context = get_client_chunking_context(
@@ -92,22 +115,25 @@ async fn next_client_transition(
         server_root,
         client_compile_time_info.environment(),
         ty,
+        mode,
     );
     let client_module_options_context = get_client_module_options_context(
         project_path,
         execution_context,
         client_compile_time_info.environment(),
         ty,
+        mode,
         next_config,
     );
     let client_runtime_entries =
-        get_client_runtime_entries(project_path, env, ty, next_config, execution_context);
+        get_client_runtime_entries(project_path, env, ty, mode, next_config, execution_context);
     let client_resolve_options_context =
         get_client_resolve_options_context(project_path, ty, next_config, execution_context);
 
     Ok(NextClientTransition {
         is_app: true,
         client_chunking_context,
+        client_root: get_client_root(server_root, mode),
         client_module_options_context,
         client_resolve_options_context,
         client_compile_time_info,
@@ -125,6 +151,7 @@ fn next_ssr_client_module_transition(
     process_env: ProcessEnvVc,
     next_config: NextConfigVc,
     server_addr: ServerAddrVc,
+    mode: Value<NextMode>,
 ) -> TransitionVc {
     let ty = Value::new(ServerContextType::AppSSR { app_dir });
     NextSSRClientModuleTransition {
@@ -140,7 +167,13 @@ fn next_ssr_client_module_transition(
             next_config,
             execution_context,
         ),
-        ssr_environment: get_server_compile_time_info(ty, process_env, server_addr),
+        ssr_environment: get_server_compile_time_info(
+            ty,
+            process_env,
+            server_addr,
+            mode,
+            next_config,
+        ),
     }
     .cell()
     .into()
@@ -155,9 +188,11 @@ fn next_layout_entry_transition(
     process_env: ProcessEnvVc,
     next_config: NextConfigVc,
     server_addr: ServerAddrVc,
+    mode: Value<NextMode>,
 ) -> TransitionVc {
     let ty = Value::new(ServerContextType::AppRSC { app_dir });
-    let rsc_compile_time_info = get_server_compile_time_info(ty, process_env, server_addr);
+    let rsc_compile_time_info =
+        get_server_compile_time_info(ty, process_env, server_addr, mode, next_config);
     let rsc_resolve_options_context =
         get_server_resolve_options_context(project_path, ty, next_config, execution_context);
     let rsc_module_options_context =
@@ -182,11 +217,16 @@ fn next_route_transition(
     server_addr: ServerAddrVc,
     output_path: FileSystemPathVc,
     execution_context: ExecutionContextVc,
+    mode: Value<NextMode>,
 ) -> TransitionVc {
     let server_ty = Value::new(ServerContextType::AppRoute { app_dir });
 
-    let edge_compile_time_info =
-        get_edge_compile_time_info(server_addr, Value::new(EnvironmentIntention::Api));
+    let edge_compile_time_info = get_edge_compile_time_info(
+        server_addr,
+        Value::new(EnvironmentIntention::Api),
+        mode,
+        next_config,
+    );
 
     let edge_chunking_context = DevChunkingContextVc::builder(
         project_path,
@@ -226,6 +266,7 @@ fn app_context(
     next_config: NextConfigVc,
     server_addr: ServerAddrVc,
     output_path: FileSystemPathVc,
+    mode: Value<NextMode>,
 ) -> AssetContextVc {
     let next_server_to_client_transition = NextServerToClientTransition { ssr }.cell().into();
 
@@ -240,6 +281,7 @@ fn app_context(
             server_addr,
             output_path,
             execution_context,
+            mode,
         ),
     );
     transitions.insert(
@@ -252,6 +294,7 @@ fn app_context(
             env,
             next_config,
             server_addr,
+            mode,
         ),
     );
     transitions.insert(
@@ -268,6 +311,7 @@ fn app_context(
             env,
             client_compile_time_info,
             next_config,
+            mode,
         ),
     );
     let client_ty = Value::new(ClientContextType::App { app_dir });
@@ -277,12 +321,27 @@ fn app_context(
             project_path,
             execution_context,
             client_ty,
+            mode,
             server_root,
             client_compile_time_info,
             next_config,
         )
         .into(),
     );
+    transitions.insert(
+        "next-client-worker".to_string(),
+        NextClientWorkerTransitionVc::new(
+            project_path,
+            execution_context,
+            client_ty,
+            mode,
+            server_root,
+            env,
+            client_compile_time_info,
+            next_config,
+        )
+        .into(),
+    );
     transitions.insert(
         "next-ssr-client-module".to_string(),
         next_ssr_client_module_transition(
@@ -292,19 +351,121 @@ fn app_context(
             env,
             next_config,
             server_addr,
+            mode,
         ),
     );
 
     let ssr_ty = Value::new(ServerContextType::AppSSR { app_dir });
     ModuleAssetContextVc::new(
         TransitionsByNameVc::cell(transitions),
-        get_server_compile_time_info(ssr_ty, env, server_addr),
+        get_server_compile_time_info(ssr_ty, env, server_addr, mode, next_config),
         get_server_module_options_context(project_path, execution_context, ssr_ty, next_config),
         get_server_resolve_options_context(project_path, ssr_ty, next_config, execution_context),
     )
     .into()
 }
 
+/// Returns the server components context of the app directory, in which e.g.
+/// `generateStaticParams` is evaluated outside of the dev server.
+#[allow(clippy::too_many_arguments)]
+#[turbo_tasks::function]
+pub fn app_server_components_context(
+    project_path: FileSystemPathVc,
+    execution_context: ExecutionContextVc,
+    output_path: FileSystemPathVc,
+    server_root: FileSystemPathVc,
+    app_dir: FileSystemPathVc,
+    env: ProcessEnvVc,
+    browserslist_query: &str,
+    next_config: NextConfigVc,
+    server_addr: ServerAddrVc,
+) -> AssetContextVc {
+    let client_compile_time_info = get_client_compile_time_info(
+        browserslist_query,
+        env,
+        Value::new(NextMode::Development),
+        next_config,
+    );
+    app_context(
+        project_path,
+        execution_context,
+        server_root,
+        app_dir,
+        env,
+        client_compile_time_info,
+        false,
+        next_config,
+        server_addr,
+        output_path,
+        Value::new(NextMode::Development),
+    )
+}
+
+/// Returns the module rendering a page of the app directory in `next build`,
+/// `app_structure` being the structure of the whole app directory.
+///
+/// Its client chunks are written to `static/chunks` in `output_root`, and the
+/// manifests of the page to `server/app`, like the dev server writes them.
+#[allow(clippy::too_many_arguments)]
+#[turbo_tasks::function]
+pub async fn app_page_build_module(
+    project_path: FileSystemPathVc,
+    execution_context: ExecutionContextVc,
+    output_root: FileSystemPathVc,
+    app_structure: AppStructureVc,
+    env: ProcessEnvVc,
+    browserslist_query: &str,
+    next_config: NextConfigVc,
+    server_addr: ServerAddrVc,
+    loader_tree: LoaderTreeVc,
+    page: FileSystemPathVc,
+    url: FileSystemPathVc,
+) -> Result<EcmascriptModuleAssetVc> {
+    let app_dir = app_structure.directory();
+    let output_path = output_root.join("server/app");
+    let client_compile_time_info = get_client_compile_time_info(
+        browserslist_query,
+        env,
+        Value::new(NextMode::Build),
+        next_config,
+    );
+    // The client chunks are written to the output, which takes the place of
+    // the root of the dev server.
+    let context_for = |ssr| {
+        app_context(
+            project_path,
+            execution_context,
+            output_root,
+            app_dir,
+            env,
+            client_compile_time_info,
+            ssr,
+            next_config,
+            server_addr,
+            output_path,
+            Value::new(NextMode::Build),
+        )
+    };
+    let context = context_for(false);
+    let renderer = AppRenderer {
+        context_ssr: context_for(true),
+        context,
+        server_root: output_root,
+        loader_tree,
+        page_path: page,
+        project_path,
+        intermediate_output_path: output_path,
+        manifest_output_path: output_path.join(&url.await?.path),
+        metadata_routes: find_metadata_routes(OptionAppStructureVc::cell(Some(app_structure))),
+        server_actions: get_app_server_actions(
+            app_structure,
+            context.with_transition("next-layout-entry"),
+        ),
+    }
+    .cell();
+    Ok(renderer.entry(false).await?.module)
+}
+
 /// Create a content source serving the `app` or `src/app` directory as
 /// Next.js app folder.
 #[turbo_tasks::function]
@@ -319,12 +480,18 @@ pub async fn create_app_source(
     next_config: NextConfigVc,
     server_addr: ServerAddrVc,
 ) -> Result<ContentSourceVc> {
+    let metadata_routes = find_metadata_routes(app_structure);
     let Some(app_structure) = *app_structure.await? else {
         return Ok(NoContentSourceVc::new().into());
     };
     let app_dir = app_structure.directory();
 
-    let client_compile_time_info = get_client_compile_time_info(browserslist_query);
+    let client_compile_time_info = get_client_compile_time_info(
+        browserslist_query,
+        env,
+        Value::new(NextMode::Development),
+        next_config,
+    );
 
     let context_ssr = app_context(
         project_path,
@@ -337,6 +504,7 @@ pub async fn create_app_source(
         next_config,
         server_addr,
         output_path,
+        Value::new(NextMode::Development),
     );
     let context = app_context(
         project_path,
@@ -349,13 +517,16 @@ pub async fn create_app_source(
         next_config,
         server_addr,
         output_path,
+        Value::new(NextMode::Development),
     );
 
     let injected_env = env_for_js(EnvMapVc::empty().into(), false, next_config);
     let env = CustomProcessEnvVc::new(env, next_config.env()).as_process_env();
 
-    let server_runtime_entries =
-        vec![ProcessEnvAssetVc::new(project_path, injected_env).as_ecmascript_chunk_placeable()];
+    let server_runtime_entries = EcmascriptChunkPlaceablesVc::cell(vec![
+        ProcessEnvAssetVc::new(project_path, injected_env).as_ecmascript_chunk_placeable(),
+        get_dependencies_runtime_entry(project_path, context_ssr),
+    ]);
 
     let fallback_page = get_fallback_page(
         project_path,
@@ -366,6 +537,9 @@ pub async fn create_app_source(
         next_config,
     );
 
+    let server_actions =
+        get_app_server_actions(app_structure, context.with_transition("next-layout-entry"));
+
     let source = create_app_source_for_directory(
         app_structure,
         context_ssr,
@@ -373,11 +547,133 @@ pub async fn create_app_source(
         project_path,
         env,
         server_root,
-        EcmascriptChunkPlaceablesVc::cell(server_runtime_entries),
+        server_runtime_entries,
         fallback_page,
         output_path,
+        metadata_routes,
+        server_actions,
+        execution_context,
+    );
+    let metadata_source = MetadataRouteContentSource {
+        execution_context,
+        routes: metadata_routes,
+    }
+    .cell()
+    .into();
+    // Actions are called through a single endpoint, the router rewrites action
+    // requests to it.
+    let server_action_source = create_node_api_source(
+        project_path,
+        env,
+        SpecificityVc::exact(),
+        server_root,
+        NextExactMatcherVc::new(StringVc::cell(SERVER_ACTION_PATHNAME.to_string())).into(),
+        StringVc::cell(SERVER_ACTION_PATHNAME.to_string()),
+        AppServerActions {
+            context: context_ssr,
+            actions: server_actions,
+            server_root,
+            project_path,
+            intermediate_output_path: output_path,
+        }
+        .cell()
+        .into(),
+        server_runtime_entries,
     );
-    Ok(source)
+    let mut sources = vec![metadata_source, server_action_source, source];
+    // The root `not-found` file renders the pathnames which don't match any
+    // route, and the not found page the router rewrites to.
+    if let Some(loader_tree) =
+        *get_not_found_loader_tree(app_dir, next_config.page_extensions()).await?
+    {
+        let not_found = loader_tree
+            .await?
+            .parallel_routes
+            .get("children")
+            .context("the not found loader tree must render children")?
+            .await?
+            .components
+            .await?
+            .page
+            .context("the not found loader tree must render a page")?;
+        let not_found_sources: [(RouteMatcherVc, _); 2] = [
+            (
+                NextExactMatcherVc::new(StringVc::cell(NOT_FOUND_PATHNAME.to_string())).into(),
+                SpecificityVc::exact(),
+            ),
+            (
+                NextFallbackMatcherVc::new().into(),
+                SpecificityVc::not_found(),
+            ),
+        ];
+        for (route_matcher, specificity) in not_found_sources {
+            sources.push(create_node_rendered_source(
+                project_path,
+                env,
+                specificity,
+                server_root,
+                route_matcher,
+                StringVc::cell("_not-found".to_string()),
+                AppRenderer {
+                    context_ssr,
+                    context,
+                    server_root,
+                    loader_tree,
+                    page_path: not_found,
+                    project_path,
+                    intermediate_output_path: output_path,
+                    manifest_output_path: output_path.join("_not-found"),
+                    metadata_routes,
+                    server_actions,
+                }
+                .cell()
+                .into(),
+                server_runtime_entries,
+                fallback_page,
+            ));
+        }
+    }
+    // The `global-error` file renders router-level failures in place of the
+    // 500 page of the pages directory.
+    if let Some(loader_tree) =
+        *get_global_error_loader_tree(app_dir, next_config.page_extensions()).await?
+    {
+        let global_error = loader_tree
+            .await?
+            .parallel_routes
+            .get("children")
+            .context("the global error loader tree must render children")?
+            .await?
+            .components
+            .await?
+            .page
+            .context("the global error loader tree must render a page")?;
+        sources.push(create_node_rendered_source(
+            project_path,
+            env,
+            SpecificityVc::exact(),
+            server_root,
+            NextExactMatcherVc::new(StringVc::cell(INTERNAL_ERROR_PATHNAME.to_string())).into(),
+            StringVc::cell("_global-error".to_string()),
+            AppRenderer {
+                context_ssr,
+                context,
+                server_root,
+                loader_tree,
+                page_path: global_error,
+                project_path,
+                intermediate_output_path: output_path,
+                manifest_output_path: output_path.join("_global-error"),
+                metadata_routes,
+                server_actions,
+            }
+            .cell()
+            .into(),
+            server_runtime_entries,
+            fallback_page,
+        ));
+    }
+    Ok(CombinedContentSource { sources }.cell().into())
 }
 
 #[allow(clippy::too_many_arguments)]
@@ -392,6 +688,9 @@ async fn create_app_source_for_directory(
     runtime_entries: EcmascriptChunkPlaceablesVc,
     fallback_page: DevHtmlAssetVc,
     intermediate_output_path_root: FileSystemPathVc,
+    metadata_routes: MetadataRoutesVc,
+    server_actions: ServerActionsVc,
+    execution_context: ExecutionContextVc,
 ) -> Result<ContentSourceVc> {
     let AppStructure {
         item,
@@ -403,32 +702,44 @@ async fn create_app_source_for_directory(
     if let Some(item) = item {
         match *item.await? {
             AppStructureItem::Page {
-                segment,
                 url,
                 specificity,
                 page,
-                segments: layouts,
+                loader_tree,
+                ..
             } => {
-                let LayoutSegment { target, .. } = *segment.await?;
                 let pathname = pathname_for_path(server_root, url, false, false);
                 let params_matcher = NextParamsMatcherVc::new(pathname);
+                // Params not returned by `generateStaticParams` respond with a
+                // 404 when the page exports `dynamicParams = false`.
+                let route_matcher: RouteMatcherVc = if url.await?.path.contains('[') {
+                    NextStaticParamsMatcherVc::new(
+                        params_matcher.into(),
+                        get_static_params(context, execution_context, loader_tree),
+                    )
+                    .into()
+                } else {
+                    params_matcher.into()
+                };
 
                 sources.push(create_node_rendered_source(
                     project_path,
                     env,
                     specificity,
                     server_root,
-                    params_matcher.into(),
+                    route_matcher,
                     pathname,
                     AppRenderer {
                         context_ssr,
                         context,
                         server_root,
-                        layout_path: layouts,
+                        loader_tree,
                         page_path: page,
-                        target,
                         project_path,
                         intermediate_output_path: intermediate_output_path_root,
+                        manifest_output_path: intermediate_output_path_root.join(&url.await?.path),
+                        metadata_routes,
+                        server_actions,
                     }
                     .cell()
                     .into(),
@@ -494,6 +805,9 @@ async fn create_app_source_for_directory(
                     runtime_entries,
                     fallback_page,
                     intermediate_output_path_root,
+                    metadata_routes,
+                    server_actions,
+                    execution_context,
                 )
             }))
             .collect(),
@@ -508,11 +822,18 @@ struct AppRenderer {
     context_ssr: AssetContextVc,
     context: AssetContextVc,
     server_root: FileSystemPathVc,
-    layout_path: LayoutSegmentsVc,
+    loader_tree: LoaderTreeVc,
     page_path: FileSystemPathVc,
-    target: FileSystemPathVc,
     project_path: FileSystemPathVc,
     intermediate_output_path: FileSystemPathVc,
+    /// The directory of the page in the server output, where its manifests
+    /// are written.
+    manifest_output_path: FileSystemPathVc,
+    metadata_routes: MetadataRoutesVc,
+    /// The server actions of the app directory. Their manifest is written
+    /// with the manifests of the page, so the Next.js runtime can look up the
+    /// actions before the first one is called.
+    server_actions: ServerActionsVc,
 }
 
 #[turbo_tasks::value_impl]
@@ -520,88 +841,77 @@ impl AppRendererVc {
     #[turbo_tasks::function]
     async fn entry(self, is_rsc: bool) -> Result<NodeRenderingEntryVc> {
         let this = self.await?;
-        let layout_path = this.layout_path.await?;
         let page = this.page_path;
         let path = page.parent();
         let path_value = &*path.await?;
 
-        let layout_and_page = layout_path
-            .iter()
-            .copied()
-            .chain(std::iter::once(
-                LayoutSegment {
-                    files: HashMap::from([("page".to_string(), page)]),
-                    target: this.target,
-                }
-                .cell(),
-            ))
-            .try_join()
-            .await?;
-
-        let segments: Vec<_> = layout_and_page
-            .into_iter()
-            .fold(
-                (this.server_root, Vec::new()),
-                |(last_path, mut futures), segment| {
-                    (segment.target, {
-                        futures.push(async move {
-                            let target = &*segment.target.await?;
-                            let segment_path =
-                                last_path.await?.get_path_to(target).unwrap_or_default();
-                            let mut imports = BTreeMap::new();
-                            for (key, file) in segment.files.iter() {
-                                let file_str = file.to_string().await?;
-                                let identifier = magic_identifier::encode(&format!(
-                                    "imported namespace {}",
-                                    file_str
-                                ));
-                                let chunks_identifier = magic_identifier::encode(&format!(
-                                    "client chunks for {}",
-                                    file_str
-                                ));
-                                if let Some(p) = path_value.get_relative_path_to(&*file.await?) {
-                                    imports.insert(
-                                        key.to_string(),
-                                        (p, identifier, chunks_identifier),
-                                    );
-                                } else {
-                                    return Err(anyhow!(
-                                        "Unable to generate import as there
-                                is no relative path to the layout module {} from context
-                                path {}",
-                                        file_str,
-                                        path.to_string().await?
-                                    ));
-                                }
-                            }
-                            Ok((StringifyJs(segment_path).to_string(), imports))
-                        });
-                        futures
-                    })
-                },
-            )
-            .1
-            .into_iter()
-            .try_join()
-            .await?;
+        // Writes the client reference manifest of the page. Collecting the
+        // client references also reports modules which are used on the wrong
+        // side of a client boundary.
+        emit_manifest(client_reference_manifest_asset(
+            this.context.with_transition("next-layout-entry"),
+            this.project_path,
+            this.loader_tree,
+            this.manifest_output_path,
+        ))
+        .await?;
+        emit_manifest(server_reference_manifest_asset(
+            this.server_actions,
+            this.project_path,
+            this.intermediate_output_path.parent(),
+        ))
+        .await?;
+
+        // Flatten the loader tree, so the code for it can be generated without
+        // async recursion. Nodes are listed in depth-first order, the root
+        // first.
+        let mut nodes = Vec::new();
+        let mut queue = vec![this.loader_tree];
+        while let Some(tree) = queue.pop() {
+            let tree = tree.await?;
+            let components = tree.components.await?;
+            queue.extend(tree.parallel_routes.values().rev().copied());
+            nodes.push((tree, components));
+        }
+
+        // Component files are imported through the `next-layout-entry`
+        // transition, which also provides their client chunks.
+        let mut imports = BTreeMap::new();
+        for (_, components) in nodes.iter() {
+            for (_, file) in components.iter() {
+                let file_str = file.to_string().await?;
+                let Some(p) = path_value.get_relative_path_to(&*file.await?) else {
+                    return Err(anyhow!(
+                        "Unable to generate import as there is no relative path to the layout \
+                         module {} from context path {}",
+                        file_str,
+                        path.to_string().await?
+                    ));
+                };
+                imports.entry(p).or_insert_with(|| {
+                    (
+                        magic_identifier::encode(&format!("imported namespace {}", file_str)),
+                        magic_identifier::encode(&format!("client chunks for {}", file_str)),
+                    )
+                });
+            }
+        }
 
         // IPC need to be the first import to allow it to catch errors happening during
         // the other imports
         let mut result =
             RopeBuilder::from("import { IPC } from \"@vercel/turbopack-next/ipc/index\";\n");
 
-        for (_, imports) in segments.iter() {
-            for (p, identifier, chunks_identifier) in imports.values() {
-                result += r#"("TURBOPACK { transition: next-layout-entry; chunking-type: isolatedParallel }");
+        for (p, (identifier, chunks_identifier)) in imports.iter() {
+            result += r#"("TURBOPACK { transition: next-layout-entry; chunking-type: isolatedParallel }");
 "#;
-                writeln!(
-                    result,
-                    "import {}, {{ chunks as {} }} from {};\n",
-                    identifier,
-                    chunks_identifier,
-                    StringifyJs(p)
-                )?
-            }
+            writeln!(
+                result,
+                "import {}, {{ chunks as {} }} from {};\n",
+                identifier,
+                chunks_identifier,
+                StringifyJs(p)
+            )?
         }
         if let Some(page) = path_value.get_relative_path_to(&*page.await?) {
             writeln!(
@@ -613,19 +923,78 @@ import BOOTSTRAP from {};
             )?;
         }
 
-        result += "const LAYOUT_INFO = [";
-        for (segment_str_lit, imports) in segments.iter() {
-            writeln!(result, "  {{\n    segment: {segment_str_lit},")?;
-            for (key, (_, identifier, chunks_identifier)) in imports {
-                writeln!(
-                    result,
-                    "    {key}: {{ module: {identifier}, chunks: {chunks_identifier} }},",
-                    key = StringifyJs(key),
-                )?;
+        // Every component is registered under a unique key, e.g. `layout0`,
+        // which is used for its file name and to look up its chunks. The page
+        // of the route is registered as `page`, while pages of parallel routes
+        // get an index like any other component.
+        let page_value = page.await?;
+        let mut component_keys = Vec::with_capacity(nodes.len());
+        let mut component_chunks = Vec::new();
+        for (index, (_, components)) in nodes.iter().enumerate() {
+            let mut keys = Vec::new();
+            for (key, file) in components.iter() {
+                let file = file.await?;
+                let p = path_value.get_relative_path_to(&file).unwrap_or_default();
+                let (identifier, chunks_identifier) = &imports[&p];
+                let is_route_page = key == "page" && *file == *page_value;
+                let component_key = if is_route_page {
+                    "page".to_string()
+                } else {
+                    format!("{key}{index}")
+                };
+                if is_route_page {
+                    writeln!(result, "const PAGE_MODULE = {identifier};")?;
+                }
+                component_chunks.push((component_key.clone(), chunks_identifier.clone()));
+                keys.push((key, component_key, identifier.clone()));
             }
-            result += "  },";
+            component_keys.push(keys);
         }
-        result += "];\n\n";
+
+        fn write_tree(
+            result: &mut String,
+            nodes: &[(ReadRef<LoaderTree>, ReadRef<Components>)],
+            component_keys: &[Vec<(&str, String, String)>],
+            index: &mut usize,
+        ) {
+            let current = *index;
+            let (tree, _) = &nodes[current];
+            *index += 1;
+            result.push_str(&format!("[{}, {{", StringifyJs(&tree.segment)));
+            for key in tree.parallel_routes.keys() {
+                result.push_str(&format!(" {}: ", StringifyJs(key)));
+                write_tree(result, nodes, component_keys, index);
+                result.push(',');
+            }
+            result.push_str(" }, {");
+            for (key, component_key, identifier) in component_keys[current].iter() {
+                result.push_str(&format!(
+                    " {}: [() => {identifier}.module, {}],",
+                    StringifyJs(key),
+                    StringifyJs(&format!("{component_key}.js"))
+                ));
+            }
+            result.push_str(" }]");
+        }
+
+        let mut tree = String::new();
+        write_tree(&mut tree, &nodes, &component_keys, &mut 0);
+        writeln!(result, "const LOADER_TREE = {tree};")?;
+        writeln!(
+            result,
+            "const METADATA_ICONS = {};",
+            StringifyJs(&icon_links(this.metadata_routes).await?)
+        )?;
+
+        result += "const COMPONENT_CHUNKS = {\n";
+        for (component_key, chunks_identifier) in component_chunks.iter() {
+            writeln!(
+                result,
+                "  {}: {chunks_identifier},",
+                StringifyJs(component_key)
+            )?;
+        }
+        result += "};\n\n";
 
         let base_code = next_js_file("entry/app-renderer.tsx");
         if let FileContent::Content(base_file) = &*base_code.await? {
@@ -691,7 +1060,8 @@ impl NodeEntry for AppRenderer {
     }
 }
 
-/// The node.js renderer api routes in the app directory
+/// The route handlers in the app directory. They run in node.js, or in the
+/// edge runtime when the route exports `runtime = "edge"`.
 #[turbo_tasks::value]
 struct AppRoute {
     context: AssetContextVc,
@@ -707,10 +1077,6 @@ impl AppRouteVc {
     #[turbo_tasks::function]
     async fn entry(self) -> Result<NodeRenderingEntryVc> {
         let this = self.await?;
-        let virtual_asset = VirtualAssetVc::new(
-            this.entry_path.join("route.ts"),
-            next_js_file("entry/app/route.ts").into(),
-        );
 
         let chunking_context = DevChunkingContextVc::builder(
             this.project_path,
@@ -723,10 +1089,62 @@ impl AppRouteVc {
         .css_chunk_root_path(this.server_root.join("_next/static/chunks"))
         .build();
 
-        let entry = this.context.with_transition("next-route").process(
-            SourceAssetVc::new(this.entry_path).into(),
+        let source_asset: AssetVc = SourceAssetVc::new(this.entry_path).into();
+        let route = this.context.process(
+            source_asset,
             Value::new(ReferenceType::Entry(EntryReferenceSubType::AppRoute)),
         );
+        // Reports invalid exports as issues, the route still compiles and
+        // unsupported methods are answered with 405 at runtime.
+        let methods = route_handler_methods(route).await?;
+        let route_config = parse_config_from_source(route);
+        let (virtual_asset, inner_assets) = if route_config.await?.runtime == NextRuntime::Edge {
+            let entry = this.context.with_transition("next-route").process(
+                source_asset,
+                Value::new(ReferenceType::Entry(EntryReferenceSubType::AppRoute)),
+            );
+            (
+                VirtualAssetVc::new(
+                    this.entry_path.join("edge-route.ts"),
+                    next_js_file("entry/app/edge-route.ts").into(),
+                ),
+                indexmap! {
+                    "ROUTE_CHUNK_GROUP".to_string() => entry
+                },
+            )
+        } else {
+            let FileContent::Content(base) = &*next_js_file("entry/app/route.ts").await? else {
+                bail!("route entry code not found");
+            };
+            let page = this
+                .project_path
+                .await?
+                .get_path_to(&*this.entry_path.await?)
+                .ok_or_else(|| anyhow!("route is not in the project directory"))?
+                .to_string();
+            let head_as_get =
+                resolve_route_handler_method(&methods, "HEAD") == RouteHandlerMethod::HeadAsGet;
+            let mut content = RopeBuilder::from(
+                format!(
+                    "const PAGE = {};\nconst HEAD_AS_GET = {head_as_get};\n",
+                    StringifyJs(&page)
+                )
+                .into_bytes(),
+            );
+            content.concat(base.content());
+            (
+                VirtualAssetVc::new(
+                    this.entry_path.join("route.ts"),
+                    FileContent::Content(File::from(content.build()))
+                        .cell()
+                        .into(),
+                ),
+                indexmap! {
+                    "ROUTE".to_string() => route
+                },
+            )
+        };
+
         Ok(NodeRenderingEntry {
             module: EcmascriptModuleAssetVc::new_with_inner_assets(
                 virtual_asset.into(),
@@ -736,9 +1154,7 @@ impl AppRouteVc {
                     use_define_for_class_fields: false,
                 }]),
                 this.context.compile_time_info(),
-                InnerAssetsVc::cell(indexmap! {
-                    "ROUTE_CHUNK_GROUP".to_string() => entry
-                }),
+                InnerAssetsVc::cell(inner_assets),
             ),
             chunking_context,
             intermediate_output_path: this.intermediate_output_path,
@@ -756,3 +1172,109 @@ impl NodeEntry for AppRoute {
         self_vc.entry()
     }
 }
+
+/// The endpoint calling the server actions of the app directory.
+#[turbo_tasks::value]
+struct AppServerActions {
+    context: AssetContextVc,
+    actions: ServerActionsVc,
+    server_root: FileSystemPathVc,
+    project_path: FileSystemPathVc,
+    intermediate_output_path: FileSystemPathVc,
+}
+
+#[turbo_tasks::value_impl]
+impl AppServerActionsVc {
+    #[turbo_tasks::function]
+    async fn entry(self) -> Result<NodeRenderingEntryVc> {
+        let this = self.await?;
+
+        // The server reference manifest is written next to the output of the
+        // app directory.
+        emit_manifest(server_reference_manifest_asset(
+            this.actions,
+            this.project_path,
+            this.intermediate_output_path.parent(),
+        ))
+        .await?;
+
+        let chunking_context = DevChunkingContextVc::builder(
+            this.project_path,
+            this.intermediate_output_path,
+            this.intermediate_output_path.join("chunks"),
+            this.server_root.join("_next/static/assets"),
+            this.context.compile_time_info().environment(),
+        )
+        .layer("ssr")
+        .css_chunk_root_path(this.server_root.join("_next/static/chunks"))
+        .build();
+
+        let mut content = RopeBuilder::default();
+        let mut inner_assets = IndexMap::new();
+        let mut modules = HashMap::new();
+        let mut actions = Vec::new();
+        for &action in this.actions.await?.iter() {
+            let action = action.await?;
+            let next_index = modules.len();
+            let identifier = modules
+                .entry(action.module)
+                .or_insert_with(|| format!("ACTION_MODULE_{next_index}"))
+                .clone();
+            if !inner_assets.contains_key(&identifier) {
+                writeln!(
+                    content,
+                    "import * as {identifier} from {};",
+                    StringifyJs(&identifier)
+                )?;
+                inner_assets.insert(identifier.clone(), action.module);
+            }
+            actions.push(format!(
+                "  {}: async () => {identifier}[{}],",
+                StringifyJs(&action.id),
+                StringifyJs(&action.name)
+            ));
+        }
+        writeln!(
+            content,
+            "const SERVER_ACTIONS = {{\n{}\n}};\n",
+            actions.join("\n")
+        )?;
+        let FileContent::Content(base) = &*next_js_file("entry/app/server-actions.ts").await?
+        else {
+            bail!("server actions entry code not found");
+        };
+        content.concat(base.content());
+        let virtual_asset = VirtualAssetVc::new(
+            this.project_path.join("server-actions.ts"),
+            FileContent::Content(File::from(content.build()))
+                .cell()
+                .into(),
+        );
+
+        Ok(NodeRenderingEntry {
+            module: EcmascriptModuleAssetVc::new_with_inner_assets(
+                virtual_asset.into(),
+                this.context,
+                Value::new(EcmascriptModuleAssetType::Typescript),
+                EcmascriptInputTransformsVc::cell(vec![EcmascriptInputTransform::TypeScript {
+                    use_define_for_class_fields: false,
+                }]),
+                this.context.compile_time_info(),
+                InnerAssetsVc::cell(inner_assets),
+            ),
+            chunking_context,
+            intermediate_output_path: this.intermediate_output_path,
+            output_root: this.intermediate_output_path,
+        }
+        .cell())
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl NodeEntry for AppServerActions {
+    #[turbo_tasks::function]
+    fn entry(self_vc: AppServerActionsVc, _data: Value<ContentSourceData>) -> NodeRenderingEntryVc {
+        // Call without being keyed by data
+        self_vc.entry()
+    }
+}
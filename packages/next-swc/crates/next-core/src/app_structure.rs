@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 use anyhow::{bail, Result};
 use turbo_tasks::{
@@ -13,7 +13,7 @@ use turbopack_dev_server::source::specificity::SpecificityVc;
 
 use crate::{
     app_render::{LayoutSegment, LayoutSegmentVc, LayoutSegmentsVc},
-    next_config::NextConfigVc,
+    next_config::{NextConfigVc, Rewrite, Rewrites, RewritesVc, RouteHas},
 };
 
 /// A final route in the app directory.
@@ -25,6 +25,7 @@ pub enum AppStructureItem {
         url: FileSystemPathVc,
         specificity: SpecificityVc,
         page: FileSystemPathVc,
+        loader_tree: LoaderTreeVc,
     },
     Route {
         segment: LayoutSegmentVc,
@@ -46,6 +47,302 @@ impl AppStructureItemVc {
     }
 }
 
+/// The special files of a segment in the [LoaderTree].
+#[turbo_tasks::value]
+#[derive(Default, Clone, Debug)]
+pub struct Components {
+    pub page: Option<FileSystemPathVc>,
+    pub layout: Option<FileSystemPathVc>,
+    pub error: Option<FileSystemPathVc>,
+    pub loading: Option<FileSystemPathVc>,
+    pub template: Option<FileSystemPathVc>,
+    pub not_found: Option<FileSystemPathVc>,
+    pub head: Option<FileSystemPathVc>,
+}
+
+impl Components {
+    fn from_files(files: &HashMap<String, FileSystemPathVc>) -> Self {
+        Components {
+            page: files.get("page").copied(),
+            layout: files.get("layout").copied(),
+            error: files.get("error").copied(),
+            loading: files.get("loading").copied(),
+            template: files.get("template").copied(),
+            not_found: files.get("not-found").copied(),
+            head: files.get("head").copied(),
+        }
+    }
+
+    /// Returns the files of the components, keyed by the file type as used by
+    /// next.js, e.g. `not-found`.
+    pub fn iter(&self) -> impl Iterator<Item = (&'static str, FileSystemPathVc)> {
+        [
+            ("page", self.page),
+            ("layout", self.layout),
+            ("error", self.error),
+            ("loading", self.loading),
+            ("template", self.template),
+            ("not-found", self.not_found),
+            ("head", self.head),
+        ]
+        .into_iter()
+        .filter_map(|(key, file)| Some((key, file?)))
+    }
+}
+
+/// The tree of segments rendering a page, as expected by the next.js app
+/// renderer. Route groups are segments of their own, e.g. `(marketing)`.
+#[turbo_tasks::value]
+#[derive(Debug)]
+pub struct LoaderTree {
+    pub segment: String,
+    pub parallel_routes: BTreeMap<String, LoaderTreeVc>,
+    pub components: ComponentsVc,
+}
+
+#[turbo_tasks::value(transparent)]
+pub struct OptionLoaderTree(Option<LoaderTreeVc>);
+
+/// The files and subdirectories of a directory in the app directory, as far as
+/// they are relevant for the [LoaderTree].
+#[turbo_tasks::value]
+#[derive(Default)]
+struct DirectoryTree {
+    /// The special files of the directory, except for `page` and `default`.
+    components: Components,
+    page: Option<FileSystemPathVc>,
+    default: Option<FileSystemPathVc>,
+    /// The `global-error` file, which is only used at the root of the app
+    /// directory.
+    global_error: Option<FileSystemPathVc>,
+    /// The parallel route slots, keyed by their name without the `@`.
+    slots: BTreeMap<String, FileSystemPathVc>,
+    subdirectories: BTreeMap<String, FileSystemPathVc>,
+}
+
+#[turbo_tasks::function]
+async fn get_directory_tree(
+    dir: FileSystemPathVc,
+    page_extensions: StringsVc,
+) -> Result<DirectoryTreeVc> {
+    let DirectoryContent::Entries(entries) = &*dir.read_dir().await? else {
+        // Slots don't need to contain every directory of the route.
+        return Ok(DirectoryTree::default().cell());
+    };
+
+    let allowed_extensions = &*page_extensions.await?;
+    let mut tree = DirectoryTree::default();
+    let mut files = HashMap::new();
+    for (name, entry) in entries.iter() {
+        match *entry {
+            DirectoryEntry::File(file) => {
+                let Some((name, ext)) = name.rsplit_once('.') else {
+                    continue;
+                };
+                if !allowed_extensions.iter().any(|allowed| allowed == ext) {
+                    continue;
+                }
+                match name {
+                    "page" => tree.page = Some(file),
+                    "default" => tree.default = Some(file),
+                    "global-error" => tree.global_error = Some(file),
+                    _ => {
+                        files.insert(name.to_string(), file);
+                    }
+                }
+            }
+            DirectoryEntry::Directory(dir) => {
+                if let Some(slot) = name.strip_prefix('@') {
+                    tree.slots.insert(slot.to_string(), dir);
+                } else {
+                    tree.subdirectories.insert(name.clone(), dir);
+                }
+            }
+            _ => {}
+        }
+    }
+    tree.components = Components::from_files(&files);
+
+    Ok(tree.cell())
+}
+
+fn page_loader_tree(segment: &str, page: FileSystemPathVc) -> LoaderTreeVc {
+    LoaderTree {
+        segment: segment.to_string(),
+        parallel_routes: BTreeMap::new(),
+        components: Components {
+            page: Some(page),
+            ..Default::default()
+        }
+        .cell(),
+    }
+    .cell()
+}
+
+/// Builds the [LoaderTree] of `dir`, rendered as `segment`, down to the page
+/// at `path`, a `/` separated list of directory names relative to `dir`.
+/// Returns `None` when neither `dir` nor any of its slots contain a page at
+/// `path`.
+///
+/// Every `@slot` directory renders the same `path` in parallel to the
+/// `children`. When they don't contain a page for it, they render their
+/// `default` file instead.
+#[turbo_tasks::function]
+async fn directory_loader_tree(
+    dir: FileSystemPathVc,
+    segment: &str,
+    path: &str,
+    page_extensions: StringsVc,
+) -> Result<OptionLoaderTreeVc> {
+    let directory = get_directory_tree(dir, page_extensions).await?;
+
+    let children = if path.is_empty() {
+        directory.page.map(|page| page_loader_tree("", page))
+    } else {
+        let (name, rest) = path.split_once('/').unwrap_or((path, ""));
+        match directory.subdirectories.get(name) {
+            Some(&subdirectory) => {
+                *directory_loader_tree(subdirectory, name, rest, page_extensions).await?
+            }
+            None => None,
+        }
+    };
+
+    let mut parallel_routes = BTreeMap::new();
+    for (name, &slot) in directory.slots.iter() {
+        if let Some(tree) = *directory_loader_tree(slot, "", path, page_extensions).await? {
+            if let Some(&tree) = tree.await?.parallel_routes.get("children") {
+                parallel_routes.insert(name.clone(), tree);
+            }
+        }
+    }
+
+    if children.is_none() && parallel_routes.is_empty() {
+        return Ok(OptionLoaderTreeVc::cell(None));
+    }
+
+    // The children don't match for intercepting routes which only exist in a
+    // slot. Without a default they aren't rendered at all.
+    if let Some(children) = children.or_else(|| {
+        directory
+            .default
+            .map(|default| page_loader_tree("__DEFAULT__", default))
+    }) {
+        parallel_routes.insert("children".to_string(), children);
+    }
+
+    for (name, &slot) in directory.slots.iter() {
+        if parallel_routes.contains_key(name) {
+            continue;
+        }
+        if let Some(default) = get_directory_tree(slot, page_extensions).await?.default {
+            parallel_routes.insert(name.clone(), page_loader_tree("__DEFAULT__", default));
+        } else {
+            AppStructureIssue {
+                severity: IssueSeverity::Warning.into(),
+                path: slot,
+                message: StringVc::cell(format!(
+                    "The parallel route @{name} has no default.js, so it isn't rendered for \
+                     routes it doesn't match."
+                )),
+            }
+            .cell()
+            .as_issue()
+            .emit();
+        }
+    }
+
+    Ok(OptionLoaderTreeVc::cell(Some(
+        LoaderTree {
+            segment: segment.to_string(),
+            parallel_routes,
+            components: directory.components.clone().cell(),
+        }
+        .cell(),
+    )))
+}
+
+/// Builds the [LoaderTree] of a page in the app directory, including the
+/// parallel routes of all its layouts.
+#[turbo_tasks::function]
+pub async fn get_loader_tree(
+    app_dir: FileSystemPathVc,
+    page: FileSystemPathVc,
+    page_extensions: StringsVc,
+) -> Result<LoaderTreeVc> {
+    let app_dir_value = app_dir.await?;
+    let page_dir = page.parent().await?;
+    let Some(path) = app_dir_value.get_path_to(&page_dir) else {
+        bail!(
+            "{} is not in the app directory {}",
+            page.to_string().await?,
+            app_dir.to_string().await?
+        );
+    };
+    // Pages of intercepting routes can be in a slot, which is matched by
+    // rendering the path in all slots.
+    let path = path
+        .split('/')
+        .filter(|segment| !segment.starts_with('@'))
+        .collect::<Vec<_>>()
+        .join("/");
+    let Some(tree) = *directory_loader_tree(app_dir, "", &path, page_extensions).await? else {
+        bail!("{} is not a page", page.to_string().await?);
+    };
+    Ok(tree)
+}
+
+/// Builds the [LoaderTree] rendering the `not-found` file at the root of the
+/// app directory, in the root layout. Returns `None` when there's no root
+/// `not-found` file.
+#[turbo_tasks::function]
+pub async fn get_not_found_loader_tree(
+    app_dir: FileSystemPathVc,
+    page_extensions: StringsVc,
+) -> Result<OptionLoaderTreeVc> {
+    let directory = get_directory_tree(app_dir, page_extensions).await?;
+    let Some(not_found) = directory.components.not_found else {
+        return Ok(OptionLoaderTreeVc::cell(None));
+    };
+    Ok(OptionLoaderTreeVc::cell(Some(
+        LoaderTree {
+            segment: "".to_string(),
+            parallel_routes: BTreeMap::from([(
+                "children".to_string(),
+                page_loader_tree("__PAGE__", not_found),
+            )]),
+            components: directory.components.clone().cell(),
+        }
+        .cell(),
+    )))
+}
+
+/// Builds the [LoaderTree] rendering the `global-error` file at the root of
+/// the app directory. It renders its own `<html>` and `<body>`, so it's
+/// rendered without the root layout, which may be what failed. Returns `None`
+/// when there's no `global-error` file.
+#[turbo_tasks::function]
+pub async fn get_global_error_loader_tree(
+    app_dir: FileSystemPathVc,
+    page_extensions: StringsVc,
+) -> Result<OptionLoaderTreeVc> {
+    let directory = get_directory_tree(app_dir, page_extensions).await?;
+    let Some(global_error) = directory.global_error else {
+        return Ok(OptionLoaderTreeVc::cell(None));
+    };
+    Ok(OptionLoaderTreeVc::cell(Some(
+        LoaderTree {
+            segment: "".to_string(),
+            parallel_routes: BTreeMap::from([(
+                "children".to_string(),
+                page_loader_tree("__PAGE__", global_error),
+            )]),
+            components: Components::default().cell(),
+        }
+        .cell(),
+    )))
+}
+
 /// A (sub)directory in the app directory with all analyzed routes and folders.
 #[turbo_tasks::value]
 pub struct AppStructure {
@@ -131,6 +428,7 @@ pub fn get_app_structure(
     page_extensions: StringsVc,
 ) -> AppStructureVc {
     get_app_structure_for_directory(
+        app_dir,
         app_dir,
         true,
         SpecificityVc::exact(),
@@ -145,6 +443,7 @@ pub fn get_app_structure(
 #[allow(clippy::too_many_arguments)]
 #[turbo_tasks::function]
 async fn get_app_structure_for_directory(
+    app_dir: FileSystemPathVc,
     input_dir: FileSystemPathVc,
     root: bool,
     specificity: SpecificityVc,
@@ -253,12 +552,31 @@ async fn get_app_structure_for_directory(
     list.push(segment);
     layouts = LayoutSegmentsVc::cell(list);
 
-    let mut children = Vec::new();
+    let mut directories = Vec::new();
     for (name, entry) in entries.iter() {
-        let DirectoryEntry::Directory(dir) = entry else {
+        let &DirectoryEntry::Directory(dir) = entry else {
             continue;
         };
+        if name.starts_with('@') {
+            // Parallel routes are rendered as part of the routes of this
+            // directory. Only intercepting routes in them are routes of their
+            // own.
+            if let DirectoryContent::Entries(slot_entries) = &*dir.read_dir().await? {
+                for (name, entry) in slot_entries.iter() {
+                    if let &DirectoryEntry::Directory(dir) = entry {
+                        if interception_marker(name).is_some() {
+                            directories.push((name.clone(), dir));
+                        }
+                    }
+                }
+            }
+        } else {
+            directories.push((name.clone(), dir));
+        }
+    }
 
+    let mut children = Vec::new();
+    for (name, dir) in directories {
         let specificity = if name.starts_with("[[") || name.starts_with("[...") {
             specificity.with_catch_all(position)
         } else if name.starts_with('[') {
@@ -267,19 +585,20 @@ async fn get_app_structure_for_directory(
             specificity
         };
 
-        let new_target = target.join(name);
+        let new_target = target.join(&name);
         let (new_root, new_url, position) = if name.starts_with('(') && name.ends_with(')') {
             // This doesn't affect the url
             (root, url, position)
         } else {
             // This adds to the url
-            (false, url.join(name), position + 1)
+            (false, url.join(&name), position + 1)
         };
 
         children.push((
             name,
             get_app_structure_for_directory(
-                *dir,
+                app_dir,
+                dir,
                 new_root,
                 specificity,
                 position,
@@ -299,6 +618,7 @@ async fn get_app_structure_for_directory(
                 segments: layouts,
                 url,
                 specificity,
+                loader_tree: get_loader_tree(app_dir, page, page_extensions),
             }
             .cell()
         })
@@ -315,7 +635,7 @@ async fn get_app_structure_for_directory(
         });
 
     // Ensure deterministic order since read_dir is not deterministic
-    children.sort_by_key(|(k, _)| *k);
+    children.sort_by(|(a, _), (b, _)| a.cmp(b));
 
     Ok(AppStructure {
         item,
@@ -325,6 +645,163 @@ async fn get_app_structure_for_directory(
     .cell())
 }
 
+/// The markers of intercepting route segments, e.g. `(..)photo`, with the
+/// number of segments the intercepted route is above the intercepting route.
+/// `None` stands for the root of the app.
+const INTERCEPTION_MARKERS: [(&str, Option<usize>); 4] = [
+    ("(..)(..)", Some(2)),
+    ("(.)", Some(0)),
+    ("(..)", Some(1)),
+    ("(...)", None),
+];
+
+/// Returns the interception marker of a segment and the number of segments
+/// the intercepted route is above it, see [INTERCEPTION_MARKERS].
+fn interception_marker(segment: &str) -> Option<(&'static str, Option<usize>)> {
+    INTERCEPTION_MARKERS
+        .into_iter()
+        .find(|(marker, _)| segment.starts_with(marker))
+}
+
+/// Converts the segments of a route to a path-to-regexp pattern, e.g.
+/// `/photo/:id`.
+fn route_pattern(segments: &[&str]) -> String {
+    segments
+        .iter()
+        .map(|segment| {
+            if let Some(param) = segment
+                .strip_prefix("[[...")
+                .and_then(|s| s.strip_suffix("]]"))
+            {
+                format!("/:{param}*")
+            } else if let Some(param) = segment
+                .strip_prefix("[...")
+                .and_then(|s| s.strip_suffix(']'))
+            {
+                format!("/:{param}+")
+            } else if let Some(param) = segment.strip_prefix('[').and_then(|s| s.strip_suffix(']'))
+            {
+                format!("/:{param}")
+            } else {
+                // Interception markers must not be parsed as groups.
+                format!("/{}", segment.replace('(', "\\(").replace(')', "\\)"))
+            }
+        })
+        .collect()
+}
+
+/// Converts the segments of a route to a regex matching the route and all
+/// routes below it.
+fn route_prefix_regex(segments: &[&str]) -> String {
+    if segments.is_empty() {
+        return "/.*".to_string();
+    }
+    let mut regex = String::new();
+    for segment in segments {
+        regex.push('/');
+        if segment.starts_with("[[...") {
+            regex.push_str(".*");
+        } else if segment.starts_with("[...") {
+            regex.push_str(".+");
+        } else if segment.starts_with('[') {
+            regex.push_str("[^/]+");
+        } else {
+            regex.push_str(&regex::escape(segment));
+        }
+    }
+    regex.push_str("(/.*)?");
+    regex
+}
+
+/// Creates the rewrites for intercepting routes, e.g.
+/// `app/feed/@modal/(..)photo/[id]/page.js`.
+///
+/// The client router sends the url it navigates from in the `Next-Url`
+/// header. Navigations from within the intercepting route (`/feed`) to the
+/// intercepted route (`/photo/123`) are rewritten to the intercepting route
+/// (`/feed/(..)photo/123`), while direct loads still render the intercepted
+/// route.
+#[turbo_tasks::function]
+pub async fn get_interception_rewrites(
+    app_structure: OptionAppStructureVc,
+    server_root: FileSystemPathVc,
+) -> Result<RewritesVc> {
+    let mut rewrites = Vec::new();
+    let Some(app_structure) = *app_structure.await? else {
+        return Ok(Rewrites::default().cell());
+    };
+    let server_root = server_root.await?;
+
+    let mut queue = vec![app_structure];
+    while let Some(structure) = queue.pop() {
+        let structure = structure.await?;
+        queue.extend(structure.children.iter().copied());
+        let Some(item) = structure.item else {
+            continue;
+        };
+        let AppStructureItem::Page { url, page, .. } = *item.await? else {
+            continue;
+        };
+        let url = url.await?;
+        let Some(pathname) = server_root.get_path_to(&url) else {
+            continue;
+        };
+        let segments = pathname
+            .split('/')
+            .filter(|segment| !segment.is_empty())
+            .collect::<Vec<_>>();
+        let Some((index, marker, levels)) = segments.iter().enumerate().find_map(|(i, segment)| {
+            interception_marker(segment).map(|(marker, levels)| (i, marker, levels))
+        }) else {
+            continue;
+        };
+
+        let intercepting = &segments[..index];
+        let base = match levels {
+            Some(levels) if levels <= intercepting.len() => {
+                &intercepting[..intercepting.len() - levels]
+            }
+            Some(_) => {
+                AppStructureIssue {
+                    severity: IssueSeverity::Error.into(),
+                    path: page,
+                    message: StringVc::cell(format!(
+                        "The intercepting route {marker} in /{pathname} is above the root of the \
+                         app. Use (...) to intercept a route relative to the root."
+                    )),
+                }
+                .cell()
+                .as_issue()
+                .emit();
+                continue;
+            }
+            None => &[],
+        };
+        let mut intercepted = base.to_vec();
+        let intercepted_segment = &segments[index][marker.len()..];
+        intercepted.push(intercepted_segment);
+        intercepted.extend_from_slice(&segments[index + 1..]);
+
+        rewrites.push(Rewrite {
+            source: route_pattern(&intercepted),
+            destination: route_pattern(&segments),
+            base_path: None,
+            locale: None,
+            has: Some(vec![RouteHas::Header {
+                key: "next-url".to_string(),
+                value: Some(route_prefix_regex(intercepting)),
+            }]),
+            missing: None,
+        });
+    }
+
+    Ok(Rewrites {
+        before_files: rewrites,
+        ..Default::default()
+    }
+    .cell())
+}
+
 #[turbo_tasks::value(shared)]
 struct AppStructureIssue {
     pub severity: IssueSeverityVc,
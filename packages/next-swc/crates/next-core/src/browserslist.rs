@@ -0,0 +1,130 @@
+use anyhow::Result;
+use serde_json::Value as JsonValue;
+use turbo_tasks::{primitives::StringVc, Value};
+use turbo_tasks_fs::{FileContent, FileJsonContent, FileSystemPathVc};
+
+use crate::mode::NextMode;
+
+/// The browsers Next.js supports when the project has no browserslist config.
+pub const DEFAULT_BROWSERSLIST_QUERY: &str = "chrome 64, edge 79, firefox 67, opera 51, safari 12";
+
+/// Returns the browserslist query of the project, from its `.browserslistrc`
+/// or the `browserslist` field of its `package.json`, or
+/// [DEFAULT_BROWSERSLIST_QUERY] when it has neither.
+///
+/// Like browserslist, the section of the config named after the
+/// `NODE_ENV` of the mode is used, falling back to the queries outside of
+/// sections, or the `defaults` section.
+#[turbo_tasks::function]
+pub async fn browserslist_query(
+    project_path: FileSystemPathVc,
+    mode: Value<NextMode>,
+) -> Result<StringVc> {
+    let env = mode.node_env();
+
+    if let FileContent::Content(file) = &*project_path.join(".browserslistrc").read().await? {
+        if let Some(query) = parse_browserslistrc(&file.content().to_str()?, env) {
+            return Ok(StringVc::cell(query));
+        }
+    }
+
+    if let FileJsonContent::Content(package_json) =
+        &*project_path.join("package.json").read_json().await?
+    {
+        if let Some(query) = package_json_query(&package_json["browserslist"], env) {
+            return Ok(StringVc::cell(query));
+        }
+    }
+
+    Ok(StringVc::cell(DEFAULT_BROWSERSLIST_QUERY.to_string()))
+}
+
+/// Returns the query of a `.browserslistrc` for `env`, see
+/// [browserslist_query].
+fn parse_browserslistrc(content: &str, env: &str) -> Option<String> {
+    let mut section = "defaults".to_string();
+    let mut queries: Vec<(String, Vec<&str>)> = vec![];
+    for line in content.lines() {
+        let line = line.split('#').next().unwrap_or_default().trim();
+        if line.is_empty() {
+            continue;
+        }
+        if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            section = name.trim().to_string();
+            continue;
+        }
+        match queries.iter_mut().find(|(name, _)| *name == section) {
+            Some((_, section_queries)) => section_queries.push(line),
+            None => queries.push((section.clone(), vec![line])),
+        }
+    }
+    [env, "defaults"]
+        .iter()
+        .find_map(|name| {
+            // A section can be named after several environments.
+            queries
+                .iter()
+                .find(|(section, _)| section.split_whitespace().any(|s| s == *name))
+        })
+        .map(|(_, queries)| queries.join(", "))
+}
+
+/// Returns the query of the `browserslist` field of a `package.json` for
+/// `env`, see [browserslist_query].
+fn package_json_query(browserslist: &JsonValue, env: &str) -> Option<String> {
+    match browserslist {
+        JsonValue::String(query) => Some(query.clone()),
+        JsonValue::Array(queries) => {
+            let queries = queries
+                .iter()
+                .filter_map(|query| query.as_str())
+                .collect::<Vec<_>>();
+            (!queries.is_empty()).then(|| queries.join(", "))
+        }
+        JsonValue::Object(sections) => [env, "defaults"]
+            .iter()
+            .find_map(|name| package_json_query(sections.get(*name)?, env)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{package_json_query, parse_browserslistrc};
+
+    #[test]
+    fn browserslistrc() {
+        let content = "# Browsers\n> 0.5%\nlast 2 versions # supported\n\n[development]\nlast 1 \
+                       chrome version\n";
+        assert_eq!(
+            parse_browserslistrc(content, "production").as_deref(),
+            Some("> 0.5%, last 2 versions")
+        );
+        assert_eq!(
+            parse_browserslistrc(content, "development").as_deref(),
+            Some("last 1 chrome version")
+        );
+        assert_eq!(parse_browserslistrc("# empty\n", "production"), None);
+    }
+
+    #[test]
+    fn package_json() {
+        assert_eq!(
+            package_json_query(&json!(["> 1%", "not dead"]), "production").as_deref(),
+            Some("> 1%, not dead")
+        );
+        assert_eq!(
+            package_json_query(&json!("safari 14"), "production").as_deref(),
+            Some("safari 14")
+        );
+        let sections = json!({ "production": ["> 1%"], "development": ["last 1 chrome version"] });
+        assert_eq!(
+            package_json_query(&sections, "development").as_deref(),
+            Some("last 1 chrome version")
+        );
+        assert_eq!(package_json_query(&sections, "test"), None);
+        assert_eq!(package_json_query(&json!(null), "production"), None);
+    }
+}
@@ -0,0 +1,92 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+/// The file recording the progress of a build, relative to the dist
+/// directory.
+pub const BUILD_PROGRESS_FILE: &str = "build-progress.json";
+
+#[derive(Default, Serialize, Deserialize)]
+struct BuildProgressFile {
+    /// The fingerprint of every completed page, by pathname.
+    pages: BTreeMap<String, String>,
+}
+
+/// The pages a build has completed so far. It's written after every completed
+/// page, so a build which fails on one page can be re-run and skip the pages
+/// which already completed, unless their inputs have changed since.
+pub struct BuildProgress {
+    path: PathBuf,
+    pages: Mutex<BTreeMap<String, String>>,
+}
+
+impl BuildProgress {
+    /// Loads the progress of an earlier build which didn't finish. The progress
+    /// is discarded when `resume` is false or it can't be read.
+    pub fn load(path: PathBuf, resume: bool) -> Result<Self> {
+        let pages = if resume {
+            match fs::read(&path) {
+                Ok(content) => serde_json::from_slice::<BuildProgressFile>(&content)
+                    .map(|file| file.pages)
+                    .unwrap_or_default(),
+                Err(err) if err.kind() == ErrorKind::NotFound => BTreeMap::new(),
+                Err(err) => {
+                    return Err(err).with_context(|| {
+                        format!("failed to read the build progress {}", path.display())
+                    })
+                }
+            }
+        } else {
+            remove_file(&path)?;
+            BTreeMap::new()
+        };
+        Ok(Self {
+            path,
+            pages: Mutex::new(pages),
+        })
+    }
+
+    /// Returns whether the page was completed by an earlier build with the same
+    /// inputs.
+    pub fn is_completed(&self, pathname: &str, fingerprint: &str) -> bool {
+        self.pages.lock().unwrap().get(pathname).map(String::as_str) == Some(fingerprint)
+    }
+
+    /// Records a completed page. The progress is replaced atomically, so it
+    /// stays readable when the build is killed while writing it.
+    pub fn complete(&self, pathname: &str, fingerprint: &str) -> Result<()> {
+        let mut pages = self.pages.lock().unwrap();
+        pages.insert(pathname.to_string(), fingerprint.to_string());
+        let json = serde_json::to_vec_pretty(&BuildProgressFile {
+            pages: pages.clone(),
+        })?;
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let tmp_path = self.path.with_extension("json.tmp");
+        fs::write(&tmp_path, json)
+            .and_then(|_| fs::rename(&tmp_path, &self.path))
+            .with_context(|| format!("failed to write the build progress {}", self.path.display()))
+    }
+
+    /// Removes the progress once the build succeeded, so the next build starts
+    /// from scratch.
+    pub fn finish(self) -> Result<()> {
+        remove_file(&self.path)
+    }
+}
+
+fn remove_file(path: &Path) -> Result<()> {
+    match fs::remove_file(path) {
+        Err(err) if err.kind() != ErrorKind::NotFound => Err(err)
+            .with_context(|| format!("failed to remove the build progress {}", path.display())),
+        _ => Ok(()),
+    }
+}
@@ -0,0 +1,446 @@
+use std::net::IpAddr;
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use subtle::ConstantTimeEq;
+use turbo_tasks::primitives::StringVc;
+use turbo_tasks_fs::FileSystemPathVc;
+use turbopack_core::issue::{Issue, IssueSeverity, IssueSeverityVc};
+
+use crate::{
+    next_config::{BasicAuthConfig, BuiltinMiddlewareConfig},
+    request_validation::percent_decode,
+    router::{FullMiddlewareResponse, HeaderMap, MiddlewareHeadersResponse, RouterRequest},
+};
+
+/// A protection run by the router before user middleware. It either lets the
+/// request through with `None`, or responds to it.
+pub trait BuiltinMiddleware {
+    fn handle(&self, request: &RouterRequest) -> Option<FullMiddlewareResponse>;
+}
+
+/// Only lets through requests from the configured client IPs.
+pub struct IpAllowlist {
+    ranges: Vec<(IpAddr, u8)>,
+    trusted_proxy_hops: usize,
+}
+
+impl IpAllowlist {
+    fn allows(&self, ip: IpAddr) -> bool {
+        self.ranges
+            .iter()
+            .any(|&(range, prefix)| ip_in_range(ip, range, prefix))
+    }
+}
+
+impl BuiltinMiddleware for IpAllowlist {
+    fn handle(&self, request: &RouterRequest) -> Option<FullMiddlewareResponse> {
+        match client_ip(request, self.trusted_proxy_hops) {
+            Some(ip) if self.allows(ip) => None,
+            _ => Some(text_response(403, HeaderMap::new(), "Forbidden")),
+        }
+    }
+}
+
+/// Requires the credentials of HTTP basic authentication.
+pub struct BasicAuth {
+    expected: String,
+    challenge: String,
+}
+
+impl BasicAuth {
+    pub fn new(config: &BasicAuthConfig) -> Self {
+        BasicAuth {
+            expected: STANDARD.encode(format!("{}:{}", config.username, config.password)),
+            challenge: format!(
+                "Basic realm={}, charset=\"UTF-8\"",
+                serde_json::Value::from(config.realm.as_deref().unwrap_or("Next.js"))
+            ),
+        }
+    }
+}
+
+impl BuiltinMiddleware for BasicAuth {
+    fn handle(&self, request: &RouterRequest) -> Option<FullMiddlewareResponse> {
+        let authorized = header(request, "authorization")
+            .and_then(|value| value.split_once(' '))
+            .map_or(false, |(scheme, credentials)| {
+                // The comparison takes the same time for any credentials of the
+                // expected length, so they can't be guessed byte by byte.
+                scheme.eq_ignore_ascii_case("basic")
+                    && bool::from(
+                        credentials
+                            .trim()
+                            .as_bytes()
+                            .ct_eq(self.expected.as_bytes()),
+                    )
+            });
+        if authorized {
+            None
+        } else {
+            Some(text_response(
+                401,
+                HeaderMap::from_iter([("www-authenticate", self.challenge.clone())]),
+                "Unauthorized",
+            ))
+        }
+    }
+}
+
+/// The built-in middleware of a config, in the order it runs.
+pub struct BuiltinMiddlewareChain {
+    matcher: Option<Vec<String>>,
+    middleware: Vec<Box<dyn BuiltinMiddleware + Send + Sync>>,
+}
+
+impl BuiltinMiddlewareChain {
+    /// Returns the chain of a config, and the invalid entries of its IP
+    /// allowlist, which lock out all requests until they're fixed.
+    pub fn new(config: &BuiltinMiddlewareConfig) -> (Self, Vec<String>) {
+        let mut middleware: Vec<Box<dyn BuiltinMiddleware + Send + Sync>> = vec![];
+        let mut invalid = vec![];
+        if let Some(entries) = &config.ip_allowlist {
+            invalid.extend(
+                entries
+                    .iter()
+                    .filter(|entry| parse_ip_range(entry).is_none())
+                    .cloned(),
+            );
+            let ranges = entries
+                .iter()
+                .filter_map(|entry| parse_ip_range(entry))
+                .collect();
+            middleware.push(Box::new(IpAllowlist {
+                ranges,
+                trusted_proxy_hops: config.trusted_proxy_hops.unwrap_or(0),
+            }));
+        }
+        if let Some(basic_auth) = &config.basic_auth {
+            middleware.push(Box::new(BasicAuth::new(basic_auth)));
+        }
+        (
+            BuiltinMiddlewareChain {
+                matcher: config.matcher.clone(),
+                middleware,
+            },
+            invalid,
+        )
+    }
+
+    /// Runs the middleware matching the request until one of them responds.
+    pub fn handle(&self, request: &RouterRequest) -> Option<FullMiddlewareResponse> {
+        if let Some(matcher) = &self.matcher {
+            if !matcher
+                .iter()
+                .any(|prefix| matches_prefix(&request.pathname, prefix))
+            {
+                return None;
+            }
+        }
+        self.middleware
+            .iter()
+            .find_map(|middleware| middleware.handle(request))
+    }
+}
+
+/// Matches `/admin` against `/admin` and `/admin/users`, but not
+/// `/administration`. The pathname is decoded and normalized first, as the
+/// routes matching it are, so `/%61dmin` and `//admin` match too.
+fn matches_prefix(pathname: &str, prefix: &str) -> bool {
+    // Pathnames which can't be decoded are rejected before routing, the
+    // protection applies to them anyway.
+    let Some(pathname) = normalize_pathname(pathname) else {
+        return true;
+    };
+    let prefix = prefix.trim_end_matches('/');
+    match pathname.strip_prefix(prefix) {
+        Some(rest) => rest.is_empty() || rest.starts_with('/'),
+        None => false,
+    }
+}
+
+/// Percent-decodes a pathname, and removes empty, `.` and `..` segments.
+fn normalize_pathname(pathname: &str) -> Option<String> {
+    let decoded = String::from_utf8(percent_decode(pathname)?).ok()?;
+    let mut segments = vec![];
+    for segment in decoded.split(['/', '\\']) {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                segments.pop();
+            }
+            segment => segments.push(segment),
+        }
+    }
+    Some(format!("/{}", segments.join("/")))
+}
+
+fn header<'a>(request: &'a RouterRequest, name: &str) -> Option<&'a str> {
+    request
+        .raw_headers
+        .get(name)
+        .and_then(|value| value.to_str())
+}
+
+/// Without proxies in front of the server, the client IP is the peer address
+/// of the connection. Every proxy appends the address of its peer to
+/// `x-forwarded-for`, so the entry added by the outermost of the
+/// `trusted_proxy_hops` proxies is the client IP. Entries before it are sent
+/// by the client, and can be forged.
+fn client_ip(request: &RouterRequest, trusted_proxy_hops: usize) -> Option<IpAddr> {
+    let Some(hops) = trusted_proxy_hops.checked_sub(1) else {
+        return request.peer_addr;
+    };
+    // Repeated headers are a single list, in the order they were received.
+    let forwarded = request
+        .raw_headers
+        .get_all("x-forwarded-for")
+        .filter_map(|value| value.to_str())
+        .collect::<Vec<_>>()
+        .join(",");
+    forwarded
+        .rsplit(',')
+        .nth(hops)
+        .and_then(|ip| ip.trim().parse().ok())
+}
+
+fn parse_ip_range(entry: &str) -> Option<(IpAddr, u8)> {
+    let (ip, prefix) = match entry.split_once('/') {
+        Some((ip, prefix)) => (
+            ip.trim().parse::<IpAddr>().ok()?,
+            Some(prefix.parse().ok()?),
+        ),
+        None => (entry.trim().parse::<IpAddr>().ok()?, None),
+    };
+    let max_prefix = if ip.is_ipv4() { 32 } else { 128 };
+    let prefix = prefix.unwrap_or(max_prefix);
+    (prefix <= max_prefix).then_some((ip, prefix))
+}
+
+fn ip_in_range(ip: IpAddr, range: IpAddr, prefix: u8) -> bool {
+    fn masked(bits: u128, prefix: u8, width: u8) -> u128 {
+        if prefix == 0 {
+            0
+        } else {
+            bits >> (width - prefix)
+        }
+    }
+    match (ip, range) {
+        (IpAddr::V4(ip), IpAddr::V4(range)) => {
+            masked(u32::from(ip).into(), prefix, 32) == masked(u32::from(range).into(), prefix, 32)
+        }
+        (IpAddr::V6(ip), IpAddr::V6(range)) => {
+            masked(ip.into(), prefix, 128) == masked(range.into(), prefix, 128)
+        }
+        (IpAddr::V6(ip), IpAddr::V4(_)) => ip
+            .to_ipv4_mapped()
+            .map_or(false, |ip| ip_in_range(IpAddr::V4(ip), range, prefix)),
+        (IpAddr::V4(ip), IpAddr::V6(_)) => {
+            ip_in_range(IpAddr::V6(ip.to_ipv6_mapped()), range, prefix)
+        }
+    }
+}
+
+fn text_response(status_code: u16, mut headers: HeaderMap, body: &str) -> FullMiddlewareResponse {
+    headers.append("content-type", "text/plain; charset=utf-8");
+    FullMiddlewareResponse {
+        headers: MiddlewareHeadersResponse {
+            status_code,
+            headers,
+        },
+        body: body.as_bytes().to_vec(),
+    }
+}
+
+#[turbo_tasks::value(shared)]
+pub struct InvalidIpAllowlistIssue {
+    pub path: FileSystemPathVc,
+    pub entries: Vec<String>,
+}
+
+#[turbo_tasks::value_impl]
+impl Issue for InvalidIpAllowlistIssue {
+    #[turbo_tasks::function]
+    fn severity(&self) -> IssueSeverityVc {
+        IssueSeverity::Error.into()
+    }
+
+    #[turbo_tasks::function]
+    fn category(&self) -> StringVc {
+        StringVc::cell("config".to_string())
+    }
+
+    #[turbo_tasks::function]
+    fn context(&self) -> FileSystemPathVc {
+        self.path
+    }
+
+    #[turbo_tasks::function]
+    fn title(&self) -> StringVc {
+        StringVc::cell(
+            "Invalid entries in experimental.turbo.builtinMiddleware.ipAllowlist".to_string(),
+        )
+    }
+
+    #[turbo_tasks::function]
+    fn description(&self) -> StringVc {
+        StringVc::cell(format!(
+            "{} must be IP addresses or CIDR ranges, e.g. `10.0.0.0/8`. They don't allow any \
+             client.",
+            self.entries
+                .iter()
+                .map(|entry| format!("`{entry}`"))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::IpAddr;
+
+    use super::{
+        client_ip, ip_in_range, matches_prefix, parse_ip_range, BasicAuth, BuiltinMiddleware,
+    };
+    use crate::{next_config::BasicAuthConfig, router::RouterRequest};
+
+    fn ip(ip: &str) -> IpAddr {
+        ip.parse().unwrap()
+    }
+
+    fn request(headers: &[(&str, &str)]) -> RouterRequest {
+        RouterRequest {
+            method: "GET".to_string(),
+            pathname: "/".to_string(),
+            raw_query: String::new(),
+            raw_headers: headers.iter().copied().collect(),
+            peer_addr: Some(ip("10.0.0.2")),
+        }
+    }
+
+    fn in_range(address: &str, entry: &str) -> bool {
+        let (range, prefix) = parse_ip_range(entry).unwrap();
+        ip_in_range(ip(address), range, prefix)
+    }
+
+    #[test]
+    fn test_parses_ip_range() {
+        assert_eq!(parse_ip_range("10.0.0.1"), Some((ip("10.0.0.1"), 32)));
+        assert_eq!(parse_ip_range(" 10.0.0.0/8"), Some((ip("10.0.0.0"), 8)));
+        assert_eq!(parse_ip_range("0.0.0.0/0"), Some((ip("0.0.0.0"), 0)));
+        assert_eq!(parse_ip_range("::1"), Some((ip("::1"), 128)));
+        assert_eq!(parse_ip_range("fd00::/8"), Some((ip("fd00::"), 8)));
+        assert_eq!(parse_ip_range("10.0.0.0/33"), None);
+        assert_eq!(parse_ip_range("fd00::/129"), None);
+        assert_eq!(parse_ip_range("10.0.0.0/"), None);
+        assert_eq!(parse_ip_range("10.0.0.0/-1"), None);
+        assert_eq!(parse_ip_range("10.0.0"), None);
+        assert_eq!(parse_ip_range("example.com"), None);
+    }
+
+    #[test]
+    fn test_ip_in_range() {
+        assert!(in_range("10.0.0.1", "10.0.0.1"));
+        assert!(!in_range("10.0.0.2", "10.0.0.1"));
+        assert!(in_range("10.255.0.1", "10.0.0.0/8"));
+        assert!(!in_range("11.0.0.1", "10.0.0.0/8"));
+        assert!(in_range("192.168.1.130", "192.168.1.128/25"));
+        assert!(!in_range("192.168.1.127", "192.168.1.128/25"));
+        assert!(in_range("fd00::1", "fd00::/8"));
+        assert!(!in_range("fe80::1", "fd00::/8"));
+        assert!(!in_range("10.0.0.1", "::1"));
+        assert!(!in_range("::1", "10.0.0.1"));
+    }
+
+    #[test]
+    fn test_ip_in_range_with_prefix_0() {
+        assert!(in_range("1.2.3.4", "0.0.0.0/0"));
+        assert!(in_range("255.255.255.255", "10.0.0.0/0"));
+        assert!(in_range("2001:db8::1", "::/0"));
+        assert!(in_range("1.2.3.4", "fd00::/0"));
+    }
+
+    #[test]
+    fn test_ip_in_range_with_ipv4_mapped_addresses() {
+        assert!(in_range("::ffff:10.0.0.1", "10.0.0.0/8"));
+        assert!(!in_range("::ffff:11.0.0.1", "10.0.0.0/8"));
+        assert!(in_range("::ffff:1.2.3.4", "0.0.0.0/0"));
+        assert!(in_range("10.0.0.1", "::ffff:10.0.0.0/104"));
+        assert!(!in_range("11.0.0.1", "::ffff:10.0.0.0/104"));
+        assert!(in_range("::ffff:10.0.0.1", "::ffff:10.0.0.0/104"));
+        assert!(in_range("10.0.0.1", "::ffff:0:0/96"));
+        assert!(!in_range("10.0.0.1", "fd00::/8"));
+    }
+
+    #[test]
+    fn test_matches_prefix() {
+        assert!(matches_prefix("/admin", "/admin"));
+        assert!(matches_prefix("/admin/users", "/admin"));
+        assert!(matches_prefix("/admin/users", "/admin/"));
+        assert!(!matches_prefix("/administration", "/admin"));
+        assert!(!matches_prefix("/", "/admin"));
+        assert!(matches_prefix("/", "/"));
+        assert!(matches_prefix("/admin", "/"));
+    }
+
+    #[test]
+    fn test_matches_prefix_of_normalized_pathname() {
+        assert!(matches_prefix("/%61dmin", "/admin"));
+        assert!(matches_prefix("/%61dmin%2fusers", "/admin"));
+        assert!(matches_prefix("//admin", "/admin"));
+        assert!(matches_prefix("/./admin/", "/admin"));
+        assert!(matches_prefix("/public/../admin", "/admin"));
+        assert!(matches_prefix("/public\\..\\admin", "/admin"));
+        assert!(matches_prefix("/%zz", "/admin"));
+        assert!(!matches_prefix("/%61dministration", "/admin"));
+        assert!(!matches_prefix("/admin/..", "/admin"));
+    }
+
+    #[test]
+    fn test_client_ip() {
+        let forwarded = request(&[("x-forwarded-for", "6.6.6.6, 1.2.3.4, 10.0.0.1")]);
+        assert_eq!(client_ip(&forwarded, 1), Some(ip("10.0.0.1")));
+        assert_eq!(client_ip(&forwarded, 2), Some(ip("1.2.3.4")));
+        assert_eq!(client_ip(&forwarded, 4), None);
+        assert_eq!(client_ip(&forwarded, 0), Some(ip("10.0.0.2")));
+        let repeated = request(&[
+            ("x-forwarded-for", "6.6.6.6"),
+            ("x-forwarded-for", "1.2.3.4"),
+        ]);
+        assert_eq!(client_ip(&repeated, 1), Some(ip("1.2.3.4")));
+        assert_eq!(client_ip(&request(&[("x-real-ip", "1.2.3.4")]), 1), None);
+        assert_eq!(client_ip(&request(&[]), 0), Some(ip("10.0.0.2")));
+        let without_peer = RouterRequest {
+            peer_addr: None,
+            ..request(&[])
+        };
+        assert_eq!(client_ip(&without_peer, 0), None);
+    }
+
+    #[test]
+    fn test_basic_auth() {
+        let auth = BasicAuth::new(&BasicAuthConfig {
+            username: "user".to_string(),
+            password: "secret".to_string(),
+            realm: None,
+        });
+        // "user:secret"
+        for authorization in ["Basic dXNlcjpzZWNyZXQ=", "basic  dXNlcjpzZWNyZXQ= "] {
+            assert!(auth
+                .handle(&request(&[("authorization", authorization)]))
+                .is_none());
+        }
+        // "user:secreT", "user:secret2" and "user:secret" as a bearer token
+        for authorization in [
+            "Basic dXNlcjpzZWNyZVQ=",
+            "Basic dXNlcjpzZWNyZXQy",
+            "Bearer dXNlcjpzZWNyZXQ=",
+        ] {
+            let response = auth
+                .handle(&request(&[("authorization", authorization)]))
+                .unwrap();
+            assert_eq!(response.headers.status_code, 401);
+        }
+        assert!(auth.handle(&request(&[])).is_some());
+    }
+}
@@ -0,0 +1,511 @@
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+use indexmap::IndexSet;
+use serde::Serialize;
+use swc_core::ecma::ast::{
+    Decl, ExportSpecifier, ImportSpecifier, Module, ModuleDecl, ModuleExportName, Program,
+};
+use tracing::Instrument;
+use turbo_tasks::{
+    graph::{GraphTraversal, NonDeterministic},
+    primitives::{BoolVc, StringVc},
+    CompletionVc, Value, ValueToString,
+};
+use turbo_tasks_fs::{FileContent, FileSystemPathVc};
+use turbopack_core::{
+    asset::{Asset, AssetVc, AssetsVc},
+    context::{AssetContext, AssetContextVc},
+    issue::{Issue, IssueSeverity, IssueSeverityVc, IssueVc},
+    reference::all_referenced_assets,
+    reference_type::{EntryReferenceSubType, ReferenceType},
+    source_asset::SourceAssetVc,
+};
+use turbopack_ecmascript::{parse::ParseResult, EcmascriptModuleAssetVc};
+
+use crate::{app_structure::LoaderTreeVc, next_client_chunks::with_chunks::WithChunksAssetVc};
+
+/// Modules which can't be imported by server components. `client-only` is
+/// enforced by the import map, see
+/// [insert_environment_only_aliases](crate::next_shared::environment_only::insert_environment_only_aliases).
+const INVALID_SERVER_IMPORTS: [&str; 3] = ["react-dom/client", "react-dom/server", "next/router"];
+
+/// Modules which can't be imported by client components. `server-only` is
+/// enforced by the import map.
+const INVALID_CLIENT_IMPORTS: [&str; 1] = ["next/headers"];
+
+/// React APIs which rely on state or effects, so they only work in client
+/// components.
+const INVALID_SERVER_REACT_APIS: [&str; 14] = [
+    "Component",
+    "createContext",
+    "createFactory",
+    "PureComponent",
+    "useDeferredValue",
+    "useEffect",
+    "useImperativeHandle",
+    "useInsertionEffect",
+    "useLayoutEffect",
+    "useReducer",
+    "useRef",
+    "useState",
+    "useSyncExternalStore",
+    "useTransition",
+];
+
+const INVALID_SERVER_REACT_DOM_APIS: [&str; 3] =
+    ["findDOMNode", "flushSync", "unstable_batchedUpdates"];
+
+/// Exports of layouts and pages which are only read from server components.
+const SERVER_ONLY_EXPORTS: [&str; 2] = ["metadata", "generateMetadata"];
+
+/// Returns whether the directive prologue of a module contains `directive`,
+/// e.g. `"use client"`. Only comments and other directives may precede it.
+pub fn has_directive(source: &str, directive: &str) -> bool {
+    let mut rest = source.trim_start_matches('\u{feff}');
+    if rest.starts_with("#!") {
+        rest = rest.split_once('\n').map_or("", |(_, rest)| rest);
+    }
+    loop {
+        rest = rest.trim_start();
+        if let Some(comment) = rest.strip_prefix("//") {
+            rest = comment.split_once('\n').map_or("", |(_, rest)| rest);
+        } else if let Some(comment) = rest.strip_prefix("/*") {
+            rest = comment.split_once("*/").map_or("", |(_, rest)| rest);
+        } else if let Some(quote) = rest.chars().next().filter(|c| *c == '"' || *c == '\'') {
+            let Some((value, after)) = rest[1..].split_once(quote) else {
+                return false;
+            };
+            if value == directive {
+                return true;
+            }
+            rest = after.trim_start();
+            rest = rest.strip_prefix(';').unwrap_or(rest);
+        } else {
+            return false;
+        }
+    }
+}
+
+/// Returns whether the file is marked with `"use client"`.
+#[turbo_tasks::function]
+pub async fn is_client_module(path: FileSystemPathVc) -> Result<BoolVc> {
+    let FileContent::Content(file) = &*path.read().await? else {
+        return Ok(BoolVc::cell(false));
+    };
+    Ok(BoolVc::cell(
+        file.content()
+            .to_str()
+            .map_or(false, |source| has_directive(&source, "use client")),
+    ))
+}
+
+pub(crate) fn is_node_module(path: &str) -> bool {
+    path.split('/').any(|segment| segment == "node_modules")
+}
+
+/// Returns the ecmascript modules referenced by `module`, including itself.
+/// Client components are included, but the traversal doesn't continue into
+/// their dependencies, as those are compiled by a transition.
+pub(crate) async fn ecmascript_module_graph(module: AssetVc) -> Result<IndexSet<AssetVc>> {
+    async fn get_referenced_assets(asset: AssetVc) -> Result<Vec<AssetVc>> {
+        let mut assets = Vec::new();
+        for &referenced in all_referenced_assets(asset).await?.iter() {
+            if EcmascriptModuleAssetVc::resolve_from(referenced)
+                .await?
+                .is_some()
+            {
+                assets.push(referenced);
+            }
+        }
+        Ok(assets)
+    }
+
+    Ok(
+        GraphTraversal::<NonDeterministic<_>>::visit([module], get_referenced_assets)
+            .await
+            .completed()?
+            .into_iter()
+            .collect(),
+    )
+}
+
+/// Returns the server components graph of the layouts and pages of a
+/// [LoaderTree](crate::app_structure::LoaderTree), including packages in
+/// `node_modules`. `context` is the server components context of the app
+/// directory.
+#[turbo_tasks::function]
+pub async fn server_component_modules(
+    context: AssetContextVc,
+    loader_tree: LoaderTreeVc,
+) -> Result<AssetsVc> {
+    let loader_tree = loader_tree.await?;
+    let mut modules = IndexSet::new();
+    for (_, file) in loader_tree.components.await?.iter() {
+        let module = context.process(
+            SourceAssetVc::new(file).into(),
+            Value::new(ReferenceType::Entry(EntryReferenceSubType::AppPage)),
+        );
+        modules.extend(ecmascript_module_graph(module).await?);
+    }
+    for &child in loader_tree.parallel_routes.values() {
+        modules.extend(
+            server_component_modules(context, child)
+                .await?
+                .iter()
+                .copied(),
+        );
+    }
+    Ok(AssetsVc::cell(modules.into_iter().collect()))
+}
+
+/// The files marked with `"use client"` which are imported by server
+/// components. Their exports are rendered by client components.
+#[turbo_tasks::value(transparent)]
+pub struct ClientReferences(Vec<FileSystemPathVc>);
+
+/// Returns the client references of the layouts and pages of a
+/// [LoaderTree](crate::app_structure::LoaderTree), and reports issues for
+/// modules which are used on the wrong side of a client boundary.
+#[turbo_tasks::function]
+pub async fn get_client_references(
+    context: AssetContextVc,
+    loader_tree: LoaderTreeVc,
+) -> Result<ClientReferencesVc> {
+    let span = tracing::info_span!("collect client references");
+    collect_client_references(context, loader_tree)
+        .instrument(span)
+        .await
+}
+
+async fn collect_client_references(
+    context: AssetContextVc,
+    loader_tree: LoaderTreeVc,
+) -> Result<ClientReferencesVc> {
+    let mut references = Vec::new();
+    for &module in server_component_modules(context, loader_tree).await?.iter() {
+        let path = module.ident().path();
+        let is_node_module = is_node_module(&path.await?.path);
+        if *is_client_module(path).await? {
+            references.push(path);
+            if !is_node_module {
+                validate_client_graph(context, path).await?;
+            }
+        } else if !is_node_module {
+            validate_server_component(module).await?;
+        }
+    }
+    validate_error_files(loader_tree).await?;
+    Ok(ClientReferencesVc::cell(references))
+}
+
+/// Reports error boundaries which aren't client components, as they need to
+/// handle errors in the browser.
+#[turbo_tasks::function]
+async fn validate_error_files(loader_tree: LoaderTreeVc) -> Result<CompletionVc> {
+    let loader_tree = loader_tree.await?;
+    if let Some(error) = loader_tree.components.await?.error {
+        if !*is_client_module(error).await? {
+            ClientBoundaryIssue {
+                path: error,
+                title: StringVc::cell("Error boundary must be a Client Component".to_string()),
+                message: StringVc::cell(
+                    "error files handle errors in the browser, so they must be Client Components. \
+                     Add \"use client\" at the top of the file."
+                        .to_string(),
+                ),
+            }
+            .cell()
+            .as_issue()
+            .emit();
+        }
+    }
+    for &child in loader_tree.parallel_routes.values() {
+        validate_error_files(child).await?;
+    }
+    Ok(CompletionVc::new())
+}
+
+/// Returns the names of the modules imported by `module`, with the names
+/// imported from each.
+fn imports(module: &Module) -> Vec<(String, Vec<String>)> {
+    module
+        .body
+        .iter()
+        .filter_map(|item| match item.as_module_decl() {
+            Some(ModuleDecl::Import(import)) if !import.type_only => {
+                let names = import
+                    .specifiers
+                    .iter()
+                    .filter_map(|specifier| match specifier {
+                        ImportSpecifier::Named(named) if !named.is_type_only => {
+                            Some(match &named.imported {
+                                Some(ModuleExportName::Ident(ident)) => ident.sym.to_string(),
+                                Some(ModuleExportName::Str(str)) => str.value.to_string(),
+                                None => named.local.sym.to_string(),
+                            })
+                        }
+                        _ => None,
+                    })
+                    .collect();
+                Some((import.src.value.to_string(), names))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// Returns the names exported by `module`.
+fn exports(module: &Module) -> Vec<String> {
+    let mut exports = Vec::new();
+    for item in module.body.iter() {
+        match item.as_module_decl() {
+            Some(ModuleDecl::ExportDecl(export_decl)) => match &export_decl.decl {
+                Decl::Fn(fn_decl) => exports.push(fn_decl.ident.sym.to_string()),
+                Decl::Var(var_decl) => exports.extend(
+                    var_decl
+                        .decls
+                        .iter()
+                        .filter_map(|decl| decl.name.as_ident())
+                        .map(|ident| ident.sym.to_string()),
+                ),
+                _ => {}
+            },
+            Some(ModuleDecl::ExportNamed(named_export)) => {
+                for specifier in named_export.specifiers.iter() {
+                    if let ExportSpecifier::Named(named) = specifier {
+                        exports.push(match named.exported.as_ref().unwrap_or(&named.orig) {
+                            ModuleExportName::Ident(ident) => ident.sym.to_string(),
+                            ModuleExportName::Str(str) => str.value.to_string(),
+                        });
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    exports
+}
+
+/// Reports imports of a server component which only work in client
+/// components.
+#[turbo_tasks::function]
+async fn validate_server_component(module: AssetVc) -> Result<CompletionVc> {
+    let Some(ecmascript_asset) = EcmascriptModuleAssetVc::resolve_from(module).await? else {
+        return Ok(CompletionVc::new());
+    };
+    let ParseResult::Ok {
+        program: Program::Module(program),
+        ..
+    } = &*ecmascript_asset.parse().await?
+    else {
+        return Ok(CompletionVc::new());
+    };
+    let path = module.ident().path();
+    let emit = |message: String| {
+        ClientBoundaryIssue {
+            path,
+            title: StringVc::cell("Invalid import in a Server Component".to_string()),
+            message: StringVc::cell(message),
+        }
+        .cell()
+        .as_issue()
+        .emit();
+    };
+    for (source, names) in imports(program) {
+        if INVALID_SERVER_IMPORTS.contains(&source.as_str()) {
+            emit(format!(
+                "`{source}` can't be imported from a Server Component. Add \"use client\" at the \
+                 top of the file to make it a Client Component, or move the import into one."
+            ));
+        }
+        let invalid_apis: &[&str] = match source.as_str() {
+            "react" => &INVALID_SERVER_REACT_APIS,
+            "react-dom" => &INVALID_SERVER_REACT_DOM_APIS,
+            _ => &[],
+        };
+        for name in names {
+            if invalid_apis.contains(&name.as_str()) {
+                emit(format!(
+                    "`{name}` from `{source}` only works in Client Components. Components in the \
+                     app directory are Server Components by default, add \"use client\" at the \
+                     top of the file to make it a Client Component."
+                ));
+            }
+        }
+    }
+    Ok(CompletionVc::new())
+}
+
+/// Reports imports in the client components graph of a client reference
+/// which only work in server components, and exports of the client reference
+/// which are only read from server components.
+#[turbo_tasks::function]
+async fn validate_client_graph(
+    context: AssetContextVc,
+    boundary: FileSystemPathVc,
+) -> Result<CompletionVc> {
+    let module = context.with_transition("next-ssr-client-module").process(
+        SourceAssetVc::new(boundary).into(),
+        Value::new(ReferenceType::Entry(
+            EntryReferenceSubType::AppClientComponent,
+        )),
+    );
+    let boundary_value = boundary.await?;
+    let boundary_str = boundary.to_string().await?;
+    for module in ecmascript_module_graph(module).await? {
+        let path = module.ident().path();
+        let path_value = path.await?;
+        if is_node_module(&path_value.path) {
+            continue;
+        }
+        let Some(ecmascript_asset) = EcmascriptModuleAssetVc::resolve_from(module).await? else {
+            continue;
+        };
+        let ParseResult::Ok {
+            program: Program::Module(program),
+            ..
+        } = &*ecmascript_asset.parse().await?
+        else {
+            continue;
+        };
+
+        for (source, _) in imports(program) {
+            if INVALID_CLIENT_IMPORTS.contains(&source.as_str()) {
+                let message = if *path_value == *boundary_value {
+                    format!(
+                        "`{source}` can only be imported from Server Components, but this file is \
+                         marked with \"use client\"."
+                    )
+                } else {
+                    format!(
+                        "`{source}` can only be imported from Server Components, but this file is \
+                         imported by {boundary_str}, which is marked with \"use client\"."
+                    )
+                };
+                ClientBoundaryIssue {
+                    path,
+                    title: StringVc::cell("Invalid import in a Client Component".to_string()),
+                    message: StringVc::cell(message),
+                }
+                .cell()
+                .as_issue()
+                .emit();
+            }
+        }
+
+        let is_layout_or_page = matches!(
+            boundary_value.file_name().split('.').next(),
+            Some("layout" | "page")
+        );
+        if *path_value == *boundary_value && is_layout_or_page {
+            for name in exports(program) {
+                if SERVER_ONLY_EXPORTS.contains(&name.as_str()) {
+                    ClientBoundaryIssue {
+                        path,
+                        title: StringVc::cell("Invalid export of a Client Component".to_string()),
+                        message: StringVc::cell(format!(
+                            "`{name}` can't be exported from a file marked with \"use client\". \
+                             Move it into a Server Component, e.g. a layout without \"use \
+                             client\"."
+                        )),
+                    }
+                    .cell()
+                    .as_issue()
+                    .emit();
+                }
+            }
+        }
+    }
+    Ok(CompletionVc::new())
+}
+
+#[turbo_tasks::value(shared)]
+struct ClientBoundaryIssue {
+    path: FileSystemPathVc,
+    title: StringVc,
+    message: StringVc,
+}
+
+#[turbo_tasks::value_impl]
+impl Issue for ClientBoundaryIssue {
+    #[turbo_tasks::function]
+    fn severity(&self) -> IssueSeverityVc {
+        IssueSeverity::Error.into()
+    }
+
+    #[turbo_tasks::function]
+    fn title(&self) -> StringVc {
+        self.title
+    }
+
+    #[turbo_tasks::function]
+    fn category(&self) -> StringVc {
+        StringVc::cell("next app".to_string())
+    }
+
+    #[turbo_tasks::function]
+    fn context(&self) -> FileSystemPathVc {
+        self.path
+    }
+
+    #[turbo_tasks::function]
+    fn description(&self) -> StringVc {
+        self.message
+    }
+}
+
+/// Maps client references to the client chunks loading them, so the Next.js
+/// runtime can load client components referenced in a Flight payload.
+#[derive(Serialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ClientReferenceManifest {
+    /// The entries by client reference path, relative to the project.
+    pub client_modules: BTreeMap<String, ClientReferenceManifestEntry>,
+}
+
+#[derive(Serialize)]
+pub struct ClientReferenceManifestEntry {
+    /// The id of the module in the client chunks.
+    pub id: serde_json::Value,
+    /// The export, `*` for all exports of the module.
+    pub name: String,
+    /// The paths of the client chunks, relative to the server root.
+    pub chunks: Vec<String>,
+}
+
+/// Builds the [ClientReferenceManifest] of client references. `context` is
+/// the server components context of the app directory.
+pub async fn client_reference_manifest(
+    context: AssetContextVc,
+    project_path: FileSystemPathVc,
+    references: ClientReferencesVc,
+) -> Result<ClientReferenceManifest> {
+    let project_path = project_path.await?;
+    let mut manifest = ClientReferenceManifest::default();
+    for &path in references.await?.iter() {
+        let chunks = context.with_transition("next-client-chunks").process(
+            SourceAssetVc::new(path).into(),
+            Value::new(ReferenceType::Entry(
+                EntryReferenceSubType::AppClientComponent,
+            )),
+        );
+        let Some(chunks) = WithChunksAssetVc::resolve_from(chunks).await? else {
+            continue;
+        };
+        let client_chunks = chunks.client_chunks().await?;
+        let path_value = path.await?;
+        let key = project_path
+            .get_path_to(&path_value)
+            .map_or_else(|| path_value.path.clone(), str::to_string);
+        manifest.client_modules.insert(
+            key,
+            ClientReferenceManifestEntry {
+                id: serde_json::to_value(&*client_chunks.module_id.await?)?,
+                name: "*".to_string(),
+                chunks: client_chunks.chunks.clone(),
+            },
+        );
+    }
+    Ok(manifest)
+}
@@ -0,0 +1,365 @@
+use std::{
+    collections::BTreeSet,
+    future::Future,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+use anyhow::Result;
+use serde_json::{json, Value as JsonValue};
+use tokio::sync::{Notify, Semaphore};
+use turbo_tasks::{
+    graph::{GraphTraversal, NonDeterministic},
+    primitives::OptionStringVc,
+    CompletionVc, Value,
+};
+use turbo_tasks_env::ProcessEnvVc;
+use turbo_tasks_fs::FileSystemPathVc;
+use turbopack_core::{
+    asset::AssetVc,
+    context::AssetContext,
+    environment::ServerAddrVc,
+    reference::all_referenced_assets,
+    reference_type::{EntryReferenceSubType, ReferenceType},
+    source_asset::SourceAssetVc,
+};
+use turbopack_dev_server::source::{
+    query::QueryValue, ContentSource, ContentSourceContent, ContentSourceData,
+    ContentSourceDataFilter, ContentSourceDataVary, ContentSourceResultVc, NeededData, ProxyResult,
+};
+use turbopack_node::execution_context::ExecutionContextVc;
+
+use crate::{
+    app_structure::{AppStructureItem, OptionAppStructureVc},
+    mode::NextMode,
+    next_config::NextConfigVc,
+    next_server::context::ServerContextType,
+    pages_structure::{OptionPagesStructureVc, PagesStructureItem},
+    route_list::server_context,
+    util::pathname_for_path,
+};
+
+/// How soon a hinted route is needed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompilePriority {
+    /// The route is shown to the user, e.g. in a just opened browser tab.
+    Visible,
+    /// The route might be requested later, e.g. it's frequent in the access
+    /// logs.
+    Background,
+}
+
+/// Schedules the compilation of hinted routes. Visible routes are compiled
+/// right away. Background routes are compiled one at a time, and only while no
+/// visible route is compiling, so they don't compete with it for the CPU. A
+/// background route which already compiles isn't interrupted.
+pub struct CompileScheduler {
+    visible: AtomicUsize,
+    visible_done: Notify,
+    background: Semaphore,
+}
+
+impl Default for CompileScheduler {
+    fn default() -> Self {
+        Self {
+            visible: AtomicUsize::new(0),
+            visible_done: Notify::new(),
+            background: Semaphore::new(1),
+        }
+    }
+}
+
+impl CompileScheduler {
+    async fn schedule<T>(&self, priority: CompilePriority, compile: impl Future<Output = T>) -> T {
+        match priority {
+            CompilePriority::Visible => {
+                self.visible.fetch_add(1, Ordering::SeqCst);
+                let result = compile.await;
+                if self.visible.fetch_sub(1, Ordering::SeqCst) == 1 {
+                    self.visible_done.notify_waiters();
+                }
+                result
+            }
+            CompilePriority::Background => {
+                let _permit = self.background.acquire().await;
+                loop {
+                    // Created before checking, so it can't miss the notification.
+                    let visible_done = self.visible_done.notified();
+                    if self.visible.load(Ordering::SeqCst) == 0 {
+                        break;
+                    }
+                    visible_done.await;
+                }
+                compile.await
+            }
+        }
+    }
+}
+
+/// Compiles the server modules of routes on behalf of the server embedding
+/// the dev server, before they are requested:
+///
+/// `/__nextjs_compile_hints?route=/blog/hello&priority=visible`
+///
+/// `route` is a pathname, which is matched against the routes of the app, and
+/// `priority` is `visible` or `background` (the default), see
+/// [CompileScheduler]. The response is sent once the route is compiled, with
+/// the matching route. Hinting an already compiled route is cheap.
+#[turbo_tasks::value(serialization = "none", eq = "manual", cell = "new", into = "new")]
+pub struct CompileHintsContentSource {
+    pub project_path: FileSystemPathVc,
+    pub execution_context: ExecutionContextVc,
+    pub server_root: FileSystemPathVc,
+    pub env: ProcessEnvVc,
+    pub server_addr: ServerAddrVc,
+    pub next_config: NextConfigVc,
+    pub app_structure: OptionAppStructureVc,
+    pub pages_structure: OptionPagesStructureVc,
+    #[turbo_tasks(debug_ignore, trace_ignore)]
+    pub scheduler: Arc<CompileScheduler>,
+}
+
+#[turbo_tasks::value_impl]
+impl ContentSource for CompileHintsContentSource {
+    #[turbo_tasks::function]
+    async fn get(
+        self_vc: CompileHintsContentSourceVc,
+        path: &str,
+        data: Value<ContentSourceData>,
+    ) -> Result<ContentSourceResultVc> {
+        if !path.is_empty() {
+            return Ok(ContentSourceResultVc::not_found());
+        }
+        let this = self_vc.await?;
+
+        let Some(query) = &data.query else {
+            let queries = ["route".to_string(), "priority".to_string()]
+                .into_iter()
+                .collect::<BTreeSet<_>>();
+            return Ok(ContentSourceResultVc::need_data(Value::new(NeededData {
+                source: self_vc.into(),
+                path: path.to_string(),
+                vary: ContentSourceDataVary {
+                    query: Some(ContentSourceDataFilter::Subset(queries)),
+                    ..Default::default()
+                },
+            })));
+        };
+
+        let param = |name: &str| match query.get(name) {
+            Some(QueryValue::String(value)) => Some(value.as_str()),
+            _ => None,
+        };
+        let Some(pathname) = param("route").filter(|route| route.starts_with('/')) else {
+            return Ok(json_response(
+                400,
+                json!({ "error": r#""route" parameter must be a pathname"# }),
+            ));
+        };
+        let priority = match param("priority") {
+            Some("visible") => CompilePriority::Visible,
+            Some("background") | None => CompilePriority::Background,
+            Some(_) => {
+                return Ok(json_response(
+                    400,
+                    json!({ "error": r#""priority" parameter must be "visible" or "background""# }),
+                ))
+            }
+        };
+
+        let route = this
+            .scheduler
+            .schedule(priority, async {
+                compile_route(
+                    this.project_path,
+                    this.execution_context,
+                    this.server_root,
+                    this.env,
+                    this.server_addr,
+                    this.next_config,
+                    this.app_structure,
+                    this.pages_structure,
+                    pathname,
+                )
+                .await
+            })
+            .await?;
+        Ok(match &*route {
+            Some(route) => json_response(200, json!({ "route": route })),
+            None => json_response(
+                404,
+                json!({ "error": format!("no route matches {pathname}") }),
+            ),
+        })
+    }
+}
+
+fn json_response(status: u16, body: JsonValue) -> ContentSourceResultVc {
+    ContentSourceResultVc::exact(
+        ContentSourceContent::HttpProxy(
+            ProxyResult {
+                status,
+                headers: vec![
+                    (
+                        "content-type".to_string(),
+                        "application/json; charset=utf-8".to_string(),
+                    ),
+                    ("cache-control".to_string(), "no-store".to_string()),
+                ],
+                body: body.to_string().into(),
+            }
+            .cell(),
+        )
+        .cell()
+        .into(),
+    )
+}
+
+/// Compiles the server module of the route matching a pathname, and all
+/// modules it references. Returns the matching route, if any.
+#[allow(clippy::too_many_arguments)]
+#[turbo_tasks::function]
+async fn compile_route(
+    project_path: FileSystemPathVc,
+    execution_context: ExecutionContextVc,
+    server_root: FileSystemPathVc,
+    env: ProcessEnvVc,
+    server_addr: ServerAddrVc,
+    next_config: NextConfigVc,
+    app_structure: OptionAppStructureVc,
+    pages_structure: OptionPagesStructureVc,
+    pathname: &str,
+) -> Result<OptionStringVc> {
+    // The candidates with their server module, see `route_list`.
+    let mut candidates = vec![];
+
+    if let Some(pages_structure) = *pages_structure.await? {
+        let pages_dir = pages_structure.directory();
+        let context = server_context(
+            project_path,
+            execution_context,
+            Value::new(ServerContextType::Pages { pages_dir }),
+            env,
+            server_addr,
+            Value::new(NextMode::Development),
+            next_config,
+        );
+        let mut queue = vec![pages_structure];
+        while let Some(structure) = queue.pop() {
+            let structure = structure.await?;
+            queue.extend(structure.children.iter().copied());
+            for item in structure.items.iter() {
+                let (url, file, reference_type) = match *item.await? {
+                    PagesStructureItem::Page { url, page, .. } => {
+                        (url, page, EntryReferenceSubType::Page)
+                    }
+                    PagesStructureItem::Api { url, api, .. } => {
+                        (url, api, EntryReferenceSubType::PagesApi)
+                    }
+                };
+                let route = format!(
+                    "/{}",
+                    pathname_for_path(server_root, url, true, false).await?
+                );
+                candidates.push((route, context, file, reference_type));
+            }
+        }
+    }
+
+    if let Some(app_structure) = *app_structure.await? {
+        let app_dir = app_structure.directory();
+        let context = server_context(
+            project_path,
+            execution_context,
+            Value::new(ServerContextType::AppRoute { app_dir }),
+            env,
+            server_addr,
+            Value::new(NextMode::Development),
+            next_config,
+        );
+        let mut queue = vec![app_structure];
+        while let Some(structure) = queue.pop() {
+            let structure = structure.await?;
+            queue.extend(structure.children.iter().copied());
+            let Some(item) = structure.item else {
+                continue;
+            };
+            let (url, file, reference_type) = match *item.await? {
+                AppStructureItem::Page { url, page, .. } => {
+                    (url, page, EntryReferenceSubType::AppPage)
+                }
+                AppStructureItem::Route { url, route, .. } => {
+                    (url, route, EntryReferenceSubType::AppRoute)
+                }
+            };
+            let route = format!(
+                "/{}",
+                pathname_for_path(server_root, url, false, false).await?
+            );
+            candidates.push((route, context, file, reference_type));
+        }
+    }
+
+    // Static segments take precedence over dynamic ones, and those over
+    // catch-all ones, like when routing.
+    let Some((route, context, file, reference_type)) = candidates
+        .into_iter()
+        .filter_map(|candidate| {
+            route_match_rank(&candidate.0, pathname).map(|rank| (rank, candidate))
+        })
+        .min_by(|(a, _), (b, _)| a.cmp(b))
+        .map(|(_, candidate)| candidate)
+    else {
+        return Ok(OptionStringVc::cell(None));
+    };
+
+    let module = context.process(
+        SourceAssetVc::new(file).into(),
+        Value::new(ReferenceType::Entry(reference_type)),
+    );
+    compile_module_graph(module).await?;
+    Ok(OptionStringVc::cell(Some(route)))
+}
+
+/// Returns how specific a route like `/blog/[slug]` is when it matches a
+/// pathname, as the number of catch-all and dynamic segments, or `None` when
+/// it doesn't match.
+fn route_match_rank(route: &str, pathname: &str) -> Option<(usize, usize)> {
+    let route_segments: Vec<_> = route.split('/').filter(|s| !s.is_empty()).collect();
+    let segments: Vec<_> = pathname.split('/').filter(|s| !s.is_empty()).collect();
+    let mut dynamic = 0;
+    for (index, route_segment) in route_segments.iter().enumerate() {
+        if route_segment.starts_with("[[...") {
+            return Some((1, dynamic));
+        }
+        if route_segment.starts_with("[...") {
+            return (segments.len() > index).then_some((1, dynamic));
+        }
+        let segment = segments.get(index)?;
+        if route_segment.starts_with('[') {
+            dynamic += 1;
+        } else if route_segment != segment {
+            return None;
+        }
+    }
+    (segments.len() == route_segments.len()).then_some((0, dynamic))
+}
+
+/// Compiles a module and all modules it references, transitively.
+#[turbo_tasks::function]
+async fn compile_module_graph(module: AssetVc) -> Result<CompletionVc> {
+    async fn get_referenced_assets(asset: AssetVc) -> Result<Vec<AssetVc>> {
+        Ok(all_referenced_assets(asset)
+            .await?
+            .iter()
+            .copied()
+            .collect())
+    }
+
+    GraphTraversal::<NonDeterministic<_>>::visit([module], get_referenced_assets)
+        .await
+        .completed()?;
+    Ok(CompletionVc::new())
+}
@@ -0,0 +1,446 @@
+use std::future::Future;
+
+use anyhow::Result;
+use futures::{stream, StreamExt, TryStreamExt};
+use indexmap::{indexmap, IndexMap, IndexSet};
+use tracing::Instrument;
+use turbo_tasks::{
+    graph::{GraphTraversal, NonDeterministic},
+    primitives::OptionStringVc,
+    CompletionVc, CompletionsVc, Value, ValueToString,
+};
+use turbo_tasks_env::ProcessEnvVc;
+use turbo_tasks_fs::{FileSystem, FileSystemPathVc};
+use turbopack::ecmascript::EcmascriptModuleAssetVc;
+use turbopack_core::{
+    asset::{Asset, AssetVc, AssetsVc},
+    chunk::{ChunkGroupVc, ChunkingContextVc, ChunksVc},
+    context::{AssetContext, AssetContextVc},
+    environment::ServerAddrVc,
+    reference::all_referenced_assets,
+    reference_type::{EntryReferenceSubType, ReferenceType},
+    server_fs::ServerFileSystemVc,
+    source_asset::SourceAssetVc,
+};
+use turbopack_dev::DevChunkingContextVc;
+use turbopack_ecmascript::{
+    chunk::EcmascriptChunkPlaceablesVc, EcmascriptInputTransform, EcmascriptInputTransformsVc,
+    EcmascriptModuleAssetType, InnerAssetsVc,
+};
+use turbopack_node::execution_context::ExecutionContextVc;
+
+use crate::{
+    app_source::app_page_build_module,
+    app_structure::{find_app_structure, AppStructureItem},
+    embed_js::next_asset,
+    manifests::{BuildManifestEntries, BuildManifestEntriesVc},
+    mode::NextMode,
+    next_client::context::{
+        get_client_asset_context, get_client_build_chunking_context, get_client_compile_time_info,
+        get_client_runtime_entries, ClientContextType,
+    },
+    next_config::NextConfigVc,
+    next_server::context::ServerContextType,
+    pages_structure::{find_pages_structure, PagesStructureItem},
+    route_list::server_context,
+    util::pathname_for_path,
+};
+
+/// How the entries of the routes are compiled by `next build`, from
+/// `experimental.turbo.entryConcurrency` of the next.config.js.
+#[turbo_tasks::value(shared)]
+#[derive(Clone, Copy, Debug)]
+pub struct EntryCompilationOptions {
+    /// The maximum number of entries compiled at the same time.
+    pub concurrency: usize,
+}
+
+impl EntryCompilationOptions {
+    /// Defaults to one entry per CPU.
+    pub fn default_concurrency() -> usize {
+        std::thread::available_parallelism().map_or(1, |cpus| cpus.get())
+    }
+}
+
+/// Compiles the entries with at most `options.concurrency` compilations in
+/// flight, instead of waiting for every entry before starting the next one.
+/// The results are returned in the order of the entries, and the first error
+/// fails the compilation.
+pub async fn compile_entries<T, R, F, Fut>(
+    entries: Vec<T>,
+    options: &EntryCompilationOptions,
+    compile: F,
+) -> Result<Vec<R>>
+where
+    F: Fn(T) -> Fut,
+    Fut: Future<Output = Result<R>>,
+{
+    stream::iter(entries)
+        .map(compile)
+        .buffered(options.concurrency.max(1))
+        .try_collect()
+        .await
+}
+
+/// The compiled entries of the routes of a build.
+#[turbo_tasks::value(shared)]
+pub struct BuildEntries {
+    /// The modules of the entries, from which the module graph of the build is
+    /// traversed, e.g. for its inputs manifest.
+    pub modules: AssetsVc,
+    /// The client and server chunks of the entries.
+    pub chunks: AssetsVc,
+    /// The client chunks of the pages, for the build manifests.
+    pub manifest_entries: BuildManifestEntriesVc,
+}
+
+struct BuildEntry {
+    pathname: String,
+    /// The entry module of the server chunks.
+    module: AssetVc,
+    server_context: AssetContextVc,
+    /// The context of the client chunks and the page they hydrate, for the
+    /// pages of the pages directory.
+    client: Option<(ClientBuildContext, AssetVc)>,
+}
+
+/// Compiles the entries of the routes in `NextMode::Build`, at most
+/// `experimental.turbo.entryConcurrency` at a time:
+///
+/// * the pages of the pages directory to client chunks in `static/chunks`,
+///   hydrating the page, and to server chunks in `server`,
+/// * the pages of the app directory to server chunks in `server`, which
+///   reference the client chunks of their client components in `static/chunks`,
+///   and write their client reference manifests to `server/app`,
+/// * the API routes and the route handlers of the app directory to server
+///   chunks in `server`.
+///
+/// The client chunks use the chunking context of the build, which doesn't
+/// include HMR. The pinned turbopack has no chunking context optimizing the
+/// chunks for production yet.
+#[allow(clippy::too_many_arguments)]
+#[turbo_tasks::function]
+pub async fn compile_build_entries(
+    project_path: FileSystemPathVc,
+    execution_context: ExecutionContextVc,
+    env: ProcessEnvVc,
+    server_addr: ServerAddrVc,
+    output_root: FileSystemPathVc,
+    browserslist_query: &str,
+    next_config: NextConfigVc,
+) -> Result<BuildEntriesVc> {
+    let server_root = ServerFileSystemVc::new().as_file_system().root();
+    let server_context_for = |ty: ServerContextType| {
+        server_context(
+            project_path,
+            execution_context,
+            Value::new(ty),
+            env,
+            server_addr,
+            Value::new(NextMode::Build),
+            next_config,
+        )
+    };
+    let mut entries = Vec::new();
+
+    if let Some(pages_structure) =
+        *find_pages_structure(project_path, server_root, next_config).await?
+    {
+        let pages_dir = pages_structure.directory();
+        let server_context = server_context_for(ServerContextType::Pages { pages_dir });
+        let client = ClientBuildContext::new(
+            project_path,
+            execution_context,
+            env,
+            output_root,
+            browserslist_query,
+            Value::new(ClientContextType::Pages { pages_dir }),
+            next_config,
+        );
+        let mut queue = vec![pages_structure];
+        while let Some(structure) = queue.pop() {
+            let structure = structure.await?;
+            queue.extend(structure.children.iter().copied());
+            for item in structure.items.iter() {
+                let (url, file, reference_type, is_page) = match *item.await? {
+                    PagesStructureItem::Page { url, page, .. } => {
+                        (url, page, EntryReferenceSubType::Page, true)
+                    }
+                    PagesStructureItem::Api { url, api, .. } => {
+                        (url, api, EntryReferenceSubType::PagesApi, false)
+                    }
+                };
+                let source = SourceAssetVc::new(file).into();
+                entries.push(BuildEntry {
+                    pathname: format!(
+                        "/{}",
+                        pathname_for_path(server_root, url, true, false).await?
+                    ),
+                    module: server_context
+                        .process(source, Value::new(ReferenceType::Entry(reference_type))),
+                    server_context,
+                    client: is_page.then_some((client, source)),
+                });
+            }
+        }
+    }
+
+    if let Some(app_structure) = *find_app_structure(project_path, server_root, next_config).await?
+    {
+        let app_dir = app_structure.directory();
+        let server_context = server_context_for(ServerContextType::AppRoute { app_dir });
+        let mut queue = vec![app_structure];
+        while let Some(structure) = queue.pop() {
+            let structure = structure.await?;
+            queue.extend(structure.children.iter().copied());
+            let Some(item) = structure.item else {
+                continue;
+            };
+            let (url, module) = match *item.await? {
+                AppStructureItem::Page {
+                    url,
+                    page,
+                    loader_tree,
+                    ..
+                } => (
+                    url,
+                    app_page_build_module(
+                        project_path,
+                        execution_context,
+                        output_root,
+                        app_structure,
+                        env,
+                        browserslist_query,
+                        next_config,
+                        server_addr,
+                        loader_tree,
+                        page,
+                        url,
+                    )
+                    .into(),
+                ),
+                AppStructureItem::Route { url, route, .. } => (
+                    url,
+                    server_context.process(
+                        SourceAssetVc::new(route).into(),
+                        Value::new(ReferenceType::Entry(EntryReferenceSubType::AppRoute)),
+                    ),
+                ),
+            };
+            entries.push(BuildEntry {
+                pathname: format!(
+                    "/{}",
+                    pathname_for_path(server_root, url, false, false).await?
+                ),
+                module,
+                server_context,
+                client: None,
+            });
+        }
+    }
+
+    let server_chunking_context = |context: AssetContextVc| {
+        DevChunkingContextVc::builder(
+            project_path,
+            output_root.join("server"),
+            output_root.join("server/chunks"),
+            output_root.join("static/media"),
+            context.compile_time_info().environment(),
+        )
+        .build()
+    };
+    let server_chunking_context = &server_chunking_context;
+
+    let compiled = compile_entries(
+        entries,
+        &*next_config.entry_compilation_options().await?,
+        |entry| async move {
+            let module = entry.module;
+            let server_chunks = match EcmascriptModuleAssetVc::resolve_from(module).await? {
+                Some(module) => {
+                    let chunking_context = server_chunking_context(entry.server_context);
+                    Some(evaluated_chunks(module, chunking_context, None).await?)
+                }
+                // E.g. a page which isn't JavaScript can't be evaluated.
+                None => None,
+            };
+            let client_chunks = match entry.client {
+                Some((client, page)) => Some(client.page_chunks(page).await?),
+                None => None,
+            };
+            Ok((entry.pathname, module, server_chunks, client_chunks))
+        },
+    )
+    .await?;
+
+    let mut modules = Vec::new();
+    let mut chunks = IndexSet::new();
+    let mut pages = IndexMap::new();
+    for (pathname, module, server_chunks, client_chunks) in compiled {
+        modules.push(module);
+        for chunks_vc in server_chunks.iter().chain(client_chunks.iter()) {
+            chunks.extend(chunks_vc.await?.iter().map(|&chunk| AssetVc::from(chunk)));
+        }
+        if let Some(client_chunks) = client_chunks {
+            pages.insert(pathname, client_chunks);
+        }
+    }
+
+    Ok(BuildEntries {
+        modules: AssetsVc::cell(modules),
+        chunks: AssetsVc::cell(chunks.into_iter().collect()),
+        manifest_entries: BuildManifestEntries {
+            pages,
+            ..Default::default()
+        }
+        .cell(),
+    }
+    .cell())
+}
+
+/// Compiles the entries with [compile_build_entries] and writes their chunks,
+/// and the assets the chunks reference, to `output_root`.
+#[allow(clippy::too_many_arguments)]
+#[turbo_tasks::function]
+pub async fn write_build_entries(
+    project_path: FileSystemPathVc,
+    execution_context: ExecutionContextVc,
+    env: ProcessEnvVc,
+    server_addr: ServerAddrVc,
+    output_root: FileSystemPathVc,
+    browserslist_query: &str,
+    next_config: NextConfigVc,
+) -> Result<CompletionVc> {
+    let entries = compile_build_entries(
+        project_path,
+        execution_context,
+        env,
+        server_addr,
+        output_root,
+        browserslist_query,
+        next_config,
+    )
+    .await?;
+    let output_root = output_root.await?;
+
+    let assets = GraphTraversal::<NonDeterministic<_>>::visit(
+        entries.chunks.await?.iter().copied(),
+        get_referenced_assets,
+    )
+    .await
+    .completed()?;
+
+    let mut completions = Vec::new();
+    for asset in assets {
+        let path = asset.ident().path();
+        // Assets outside of the output, e.g. the source files, aren't written.
+        if output_root.get_path_to(&*path.await?).is_some() {
+            completions.push(asset.content().write(path));
+        }
+    }
+    Ok(CompletionsVc::all(completions))
+}
+
+async fn get_referenced_assets(asset: AssetVc) -> Result<Vec<AssetVc>> {
+    Ok(all_referenced_assets(asset).await?.clone_value())
+}
+
+/// The context in which the pages are compiled for the browser.
+#[derive(Clone, Copy)]
+struct ClientBuildContext {
+    context: AssetContextVc,
+    chunking_context: ChunkingContextVc,
+    runtime_entries: EcmascriptChunkPlaceablesVc,
+}
+
+impl ClientBuildContext {
+    fn new(
+        project_path: FileSystemPathVc,
+        execution_context: ExecutionContextVc,
+        env: ProcessEnvVc,
+        output_root: FileSystemPathVc,
+        browserslist_query: &str,
+        ty: Value<ClientContextType>,
+        next_config: NextConfigVc,
+    ) -> Self {
+        let compile_time_info = get_client_compile_time_info(
+            browserslist_query,
+            env,
+            Value::new(NextMode::Build),
+            next_config,
+        );
+        let context = get_client_asset_context(
+            project_path,
+            execution_context,
+            compile_time_info,
+            ty,
+            Value::new(NextMode::Build),
+            next_config,
+        );
+        ClientBuildContext {
+            context,
+            chunking_context: get_client_build_chunking_context(
+                project_path,
+                output_root,
+                compile_time_info.environment(),
+            ),
+            runtime_entries: get_client_runtime_entries(
+                project_path,
+                env,
+                ty,
+                Value::new(NextMode::Build),
+                next_config,
+                execution_context,
+            )
+            .resolve_entries(context),
+        }
+    }
+
+    /// Returns the chunks hydrating the page, like the `next-client`
+    /// transition of `next dev` creates them.
+    async fn page_chunks(&self, page: AssetVc) -> Result<ChunksVc> {
+        let entry = EcmascriptModuleAssetVc::new_with_inner_assets(
+            next_asset("entry/next-hydrate.tsx"),
+            self.context,
+            Value::new(EcmascriptModuleAssetType::Typescript),
+            EcmascriptInputTransformsVc::cell(vec![
+                EcmascriptInputTransform::TypeScript {
+                    use_define_for_class_fields: false,
+                },
+                EcmascriptInputTransform::React {
+                    refresh: false,
+                    import_source: OptionStringVc::cell(None),
+                    runtime: OptionStringVc::cell(None),
+                },
+            ]),
+            self.context.compile_time_info(),
+            InnerAssetsVc::cell(indexmap! {
+                "PAGE".to_string() => self.context.process(
+                    page,
+                    Value::new(ReferenceType::Entry(EntryReferenceSubType::Page)),
+                ),
+            }),
+        );
+        evaluated_chunks(entry, self.chunking_context, Some(self.runtime_entries)).await
+    }
+}
+
+/// Returns the chunks evaluating the module.
+async fn evaluated_chunks(
+    module: EcmascriptModuleAssetVc,
+    chunking_context: ChunkingContextVc,
+    runtime_entries: Option<EcmascriptChunkPlaceablesVc>,
+) -> Result<ChunksVc> {
+    let span = tracing::info_span!("chunking", entry = %module.ident().to_string().await?);
+    async move {
+        let chunks =
+            ChunkGroupVc::from_chunk(module.as_evaluated_chunk(chunking_context, runtime_entries))
+                .chunks();
+        // Awaiting the chunks compiles the entry, which bounds the number of
+        // entries compiled at the same time.
+        chunks.await?;
+        anyhow::Ok(chunks)
+    }
+    .instrument(span)
+    .await
+}
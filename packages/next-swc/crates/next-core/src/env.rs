@@ -1,13 +1,16 @@
 use anyhow::Result;
-use indexmap::indexmap;
+use indexmap::{indexmap, IndexMap};
 use turbo_tasks_env::{
     CommandLineProcessEnvVc, CustomProcessEnvVc, EnvMapVc, FilterProcessEnvVc, ProcessEnv,
     ProcessEnvVc,
 };
 use turbo_tasks_fs::FileSystemPathVc;
+use turbopack_core::compile_time_info::{
+    CompileTimeDefineValue, CompileTimeDefines, CompileTimeDefinesVc,
+};
 use turbopack_env::{EmbeddableProcessEnvVc, TryDotenvProcessEnvVc};
 
-use crate::next_config::NextConfigVc;
+use crate::{inputs_manifest::EnvBuildInput, next_config::NextConfigVc};
 
 /// Loads a series of dotenv files according to the precedence rules set by
 /// https://nextjs.org/docs/basic-features/environment-variables#environment-variable-load-order
@@ -93,9 +96,68 @@ pub async fn env_for_js(
         map.insert("__NEXT_STRICT_MODE_APP".to_string(), "true".to_string());
     }
 
+    // The prefix of the URLs of chunks and assets, e.g. a CDN. The renderers
+    // pass it to Next.js, which prefixes the `_next/` paths with it.
+    let asset_prefix = next_config.asset_prefix.trim_end_matches('/');
+    if !asset_prefix.is_empty() {
+        map.insert("__NEXT_ASSET_PREFIX".to_string(), asset_prefix.to_string());
+    }
+
+    // The renderers serve pages from the ISR cache, see `internal/isr-cache.ts`.
+    let isr_cache = next_config
+        .experimental
+        .turbo
+        .as_ref()
+        .and_then(|turbo| turbo.isr_cache)
+        .unwrap_or(false);
+    if !client && isr_cache {
+        map.insert("__NEXT_ISR_CACHE".to_string(), "true".to_string());
+    }
+
+    // The ISR cache and the fetch cache are stored in the `cache` directory of
+    // the `distDir`, relative to the project directory unless it's absolute.
+    if !client {
+        let dist_dir = next_config.dist_dir.trim_end_matches('/');
+        map.insert("__NEXT_CACHE_DIR".to_string(), format!("{dist_dir}/cache"));
+    }
+
     if !test_mode.is_empty() {
         map.insert("__NEXT_TEST_MODE".to_string(), "true".to_string());
     }
 
     Ok(CustomProcessEnvVc::new(env, EnvMapVc::cell(map)).into())
 }
+
+/// Returns compile-time defines which inline the `NEXT_PUBLIC_*` environment
+/// variables into client code, e.g. `process.env.NEXT_PUBLIC_API_URL`.
+///
+/// The variables are read from the env returned by [load_env], so editing a
+/// dotenv file invalidates the modules referencing them.
+#[turbo_tasks::function]
+pub async fn next_public_env_defines(env: ProcessEnvVc) -> Result<CompileTimeDefinesVc> {
+    let env = FilterProcessEnvVc::new(env, vec!["NEXT_PUBLIC_".to_string()])
+        .read_all()
+        .await?;
+
+    for (name, value) in env.iter() {
+        EnvBuildInput {
+            name: name.clone(),
+            value: Some(value.clone()),
+        }
+        .cell()
+        .as_build_input()
+        .emit();
+    }
+
+    let defines: IndexMap<_, _> = env
+        .iter()
+        .map(|(name, value)| {
+            (
+                vec!["process".to_string(), "env".to_string(), name.clone()],
+                CompileTimeDefineValue::String(value.clone()),
+            )
+        })
+        .collect();
+
+    Ok(CompileTimeDefines(defines).cell())
+}
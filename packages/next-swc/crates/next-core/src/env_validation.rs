@@ -0,0 +1,252 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use anyhow::Result;
+use swc_core::ecma::{
+    ast::{Expr, Lit, MemberExpr, MemberProp},
+    visit::{Visit, VisitWith},
+};
+use turbo_tasks::{
+    graph::{GraphTraversal, NonDeterministic},
+    primitives::{StringVc, StringsVc},
+};
+use turbo_tasks_env::{ProcessEnv, ProcessEnvVc};
+use turbo_tasks_fs::{FileContent, FileSystemPathVc};
+use turbopack_core::{
+    asset::{Asset, AssetVc},
+    issue::{Issue, IssueSeverity, IssueSeverityVc, IssueVc},
+    reference::all_referenced_assets,
+};
+use turbopack_ecmascript::{parse::ParseResult, EcmascriptModuleAssetVc};
+
+use crate::next_config::NextConfigVc;
+
+/// The file declaring required environment variables, one name per line.
+const REQUIRED_ENV_FILE: &str = ".env.required";
+
+/// Environment variables which are always set by Next.js.
+const BUILTIN_ENV_VARS: [&str; 3] = ["NODE_ENV", "NEXT_RUNTIME", "NEXT_PHASE"];
+
+/// Returns the environment variables declared as required, in
+/// `experimental.requiredEnv` of the next.config.js and in the
+/// `.env.required` file of the project.
+#[turbo_tasks::function]
+pub async fn required_env_vars(
+    project_path: FileSystemPathVc,
+    next_config: NextConfigVc,
+) -> Result<StringsVc> {
+    let mut names: BTreeSet<String> = next_config.required_env().await?.iter().cloned().collect();
+    if let FileContent::Content(file) = &*project_path.join(REQUIRED_ENV_FILE).read().await? {
+        let content = file.content().to_str()?;
+        for line in content.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            // Allows copying lines of an `.env` file.
+            let name = line.split_once('=').map_or(line, |(name, _)| name).trim();
+            names.insert(name.to_string());
+        }
+    }
+    Ok(StringsVc::cell(names.into_iter().collect()))
+}
+
+/// Returns the environment variables read with `process.env.NAME` in the
+/// module and all modules it references, except for packages in
+/// `node_modules`, which usually handle missing variables themselves.
+#[turbo_tasks::function]
+pub async fn referenced_env_vars(module: AssetVc) -> Result<StringsVc> {
+    async fn get_referenced_assets(asset: AssetVc) -> Result<Vec<AssetVc>> {
+        let mut assets = Vec::new();
+        for &referenced in all_referenced_assets(asset).await?.iter() {
+            let path = referenced.ident().path().await?;
+            if !path
+                .path
+                .split('/')
+                .any(|segment| segment == "node_modules")
+            {
+                assets.push(referenced);
+            }
+        }
+        Ok(assets)
+    }
+
+    let modules = GraphTraversal::<NonDeterministic<_>>::visit([module], get_referenced_assets)
+        .await
+        .completed()?;
+
+    let mut names = BTreeSet::new();
+    for module in modules {
+        names.extend(module_env_vars(module).await?.iter().cloned());
+    }
+    Ok(StringsVc::cell(names.into_iter().collect()))
+}
+
+#[turbo_tasks::function]
+async fn module_env_vars(module: AssetVc) -> Result<StringsVc> {
+    let Some(ecmascript_asset) = EcmascriptModuleAssetVc::resolve_from(module).await? else {
+        return Ok(StringsVc::cell(vec![]));
+    };
+    let ParseResult::Ok { program, .. } = &*ecmascript_asset.parse().await? else {
+        return Ok(StringsVc::cell(vec![]));
+    };
+    let mut visitor = EnvVarVisitor::default();
+    program.visit_with(&mut visitor);
+    Ok(StringsVc::cell(visitor.names.into_iter().collect()))
+}
+
+/// Collects `process.env.NAME` and `process.env["NAME"]` reads.
+#[derive(Default)]
+struct EnvVarVisitor {
+    names: BTreeSet<String>,
+}
+
+impl Visit for EnvVarVisitor {
+    fn visit_member_expr(&mut self, expr: &MemberExpr) {
+        if let Expr::Member(MemberExpr {
+            obj,
+            prop: MemberProp::Ident(env),
+            ..
+        }) = &*expr.obj
+        {
+            if matches!(&**obj, Expr::Ident(process) if &*process.sym == "process")
+                && &*env.sym == "env"
+            {
+                match &expr.prop {
+                    MemberProp::Ident(name) => {
+                        self.names.insert(name.sym.to_string());
+                    }
+                    MemberProp::Computed(computed) => {
+                        if let Expr::Lit(Lit::Str(name)) = &*computed.expr {
+                            self.names.insert(name.value.to_string());
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+        expr.visit_children_with(self);
+    }
+}
+
+/// Validates the environment variables read by the server code of routes,
+/// given as `(pathname, module)`. Emits an error for every variable which isn't
+/// set and would be `undefined` at runtime, and, when required variables are
+/// declared, a warning for every variable which isn't declared. Both list the
+/// affected routes.
+pub async fn validate_route_env_vars(
+    project_path: FileSystemPathVc,
+    routes: &[(String, AssetVc)],
+    required: StringsVc,
+    env: ProcessEnvVc,
+) -> Result<()> {
+    let required = required.await?;
+    let env = env.read_all().await?;
+
+    let mut usages: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for name in required.iter() {
+        usages.entry(name.clone()).or_default();
+    }
+    for (pathname, module) in routes {
+        for name in referenced_env_vars(*module).await?.iter() {
+            usages
+                .entry(name.clone())
+                .or_default()
+                .push(pathname.clone());
+        }
+    }
+
+    for (name, mut routes) in usages {
+        if BUILTIN_ENV_VARS.contains(&name.as_str()) {
+            continue;
+        }
+        routes.sort();
+        routes.dedup();
+        let declared = required.contains(&name);
+        let kind = if !env.contains_key(&name) {
+            EnvVarIssueKind::Missing { declared }
+        } else if !declared && !required.is_empty() {
+            EnvVarIssueKind::Undeclared
+        } else {
+            continue;
+        };
+        EnvVarIssue {
+            path: project_path,
+            name,
+            routes,
+            kind,
+        }
+        .cell()
+        .as_issue()
+        .emit();
+    }
+    Ok(())
+}
+
+#[turbo_tasks::value(serialization = "auto_for_input")]
+#[derive(Debug, Copy, Clone, Hash)]
+enum EnvVarIssueKind {
+    Missing { declared: bool },
+    Undeclared,
+}
+
+#[turbo_tasks::value(shared)]
+struct EnvVarIssue {
+    path: FileSystemPathVc,
+    name: String,
+    routes: Vec<String>,
+    kind: EnvVarIssueKind,
+}
+
+#[turbo_tasks::value_impl]
+impl Issue for EnvVarIssue {
+    #[turbo_tasks::function]
+    fn severity(&self) -> IssueSeverityVc {
+        match self.kind {
+            EnvVarIssueKind::Missing { .. } => IssueSeverity::Error.into(),
+            EnvVarIssueKind::Undeclared => IssueSeverity::Warning.into(),
+        }
+    }
+
+    #[turbo_tasks::function]
+    fn title(&self) -> StringVc {
+        StringVc::cell(match self.kind {
+            EnvVarIssueKind::Missing { .. } => {
+                format!("Environment variable {} is not set", self.name)
+            }
+            EnvVarIssueKind::Undeclared => {
+                format!("Environment variable {} is not declared", self.name)
+            }
+        })
+    }
+
+    #[turbo_tasks::function]
+    fn category(&self) -> StringVc {
+        StringVc::cell("env".to_string())
+    }
+
+    #[turbo_tasks::function]
+    fn context(&self) -> FileSystemPathVc {
+        self.path
+    }
+
+    #[turbo_tasks::function]
+    fn description(&self) -> StringVc {
+        let routes = if self.routes.is_empty() {
+            "It isn't read by any route.".to_string()
+        } else {
+            format!("It's read by {}.", self.routes.join(", "))
+        };
+        StringVc::cell(match self.kind {
+            EnvVarIssueKind::Missing { declared: true } => {
+                format!("It's declared as required. {routes}")
+            }
+            EnvVarIssueKind::Missing { declared: false } => {
+                format!("{routes} Those routes will read `undefined` at runtime unless it's set.")
+            }
+            EnvVarIssueKind::Undeclared => format!(
+                "{routes} Add it to {REQUIRED_ENV_FILE} or `experimental.requiredEnv` in the \
+                 next.config.js."
+            ),
+        })
+    }
+}
@@ -17,6 +17,7 @@ use turbopack_dev_server::html::DevHtmlAssetVc;
 use turbopack_node::execution_context::ExecutionContextVc;
 
 use crate::{
+    mode::NextMode,
     next_client::context::{
         get_client_chunking_context, get_client_module_options_context,
         get_client_resolve_options_context, get_client_runtime_entries, ClientContextType,
@@ -43,6 +44,7 @@ pub async fn get_fallback_page(
         execution_context,
         client_compile_time_info.environment(),
         ty,
+        Value::new(NextMode::Development),
         next_config,
     );
     let chunking_context = get_client_chunking_context(
@@ -50,11 +52,25 @@ pub async fn get_fallback_page(
         dev_server_root,
         client_compile_time_info.environment(),
         ty,
+        Value::new(NextMode::Development),
+    );
+    let entries = get_client_runtime_entries(
+        project_path,
+        env,
+        ty,
+        Value::new(NextMode::Development),
+        next_config,
+        execution_context,
     );
-    let entries = get_client_runtime_entries(project_path, env, ty, next_config, execution_context);
 
     let mut import_map = ImportMap::empty();
-    insert_next_shared_aliases(&mut import_map, project_path, execution_context).await?;
+    insert_next_shared_aliases(
+        &mut import_map,
+        project_path,
+        next_config,
+        execution_context,
+    )
+    .await?;
     insert_alias_option(
         &mut import_map,
         project_path,
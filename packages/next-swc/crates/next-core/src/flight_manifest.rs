@@ -0,0 +1,97 @@
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+use serde::Serialize;
+use turbo_tasks::CompletionVc;
+use turbo_tasks_fs::{File, FileSystemPathVc};
+use turbopack_core::{
+    asset::{Asset, AssetVc},
+    context::AssetContextVc,
+    virtual_asset::VirtualAssetVc,
+};
+
+use crate::{
+    app_structure::LoaderTreeVc,
+    client_references::{client_reference_manifest, get_client_references},
+    server_actions::{ServerActionsVc, SERVER_ACTION_PATHNAME},
+};
+
+/// The file name of the client reference manifest of a page, next to its
+/// server output.
+pub const CLIENT_REFERENCE_MANIFEST: &str = "page_client-reference-manifest.json";
+
+/// The file name of the server reference manifest of the app directory.
+pub const SERVER_REFERENCE_MANIFEST: &str = "server-reference-manifest.json";
+
+/// Maps the ids of server actions to the workers running them, so the Next.js
+/// runtime can call the actions referenced in a Flight payload.
+#[derive(Serialize, Default)]
+pub struct ServerReferenceManifest {
+    pub node: BTreeMap<String, ServerReferenceManifestEntry>,
+    pub edge: BTreeMap<String, ServerReferenceManifestEntry>,
+}
+
+#[derive(Serialize)]
+pub struct ServerReferenceManifestEntry {
+    /// The module exporting the action, relative to the project, by the
+    /// pathname of the worker running it.
+    pub workers: BTreeMap<String, String>,
+    /// The name of the export of the action.
+    pub name: String,
+}
+
+/// Returns the client reference manifest of a page as an output asset in
+/// `output_path`. `context` is the server components context of the app
+/// directory.
+#[turbo_tasks::function]
+pub async fn client_reference_manifest_asset(
+    context: AssetContextVc,
+    project_path: FileSystemPathVc,
+    loader_tree: LoaderTreeVc,
+    output_path: FileSystemPathVc,
+) -> Result<AssetVc> {
+    let references = get_client_references(context, loader_tree);
+    let manifest = client_reference_manifest(context, project_path, references).await?;
+    Ok(VirtualAssetVc::new(
+        output_path.join(CLIENT_REFERENCE_MANIFEST),
+        File::from(serde_json::to_string_pretty(&manifest)?).into(),
+    )
+    .into())
+}
+
+/// Returns the server reference manifest of the app directory as an output
+/// asset in `output_path`. All actions are run by the server action endpoint.
+#[turbo_tasks::function]
+pub async fn server_reference_manifest_asset(
+    actions: ServerActionsVc,
+    project_path: FileSystemPathVc,
+    output_path: FileSystemPathVc,
+) -> Result<AssetVc> {
+    let project_path = project_path.await?;
+    let mut manifest = ServerReferenceManifest::default();
+    for &action in actions.await?.iter() {
+        let action = action.await?;
+        let path = action.module.ident().path().await?;
+        let module = project_path
+            .get_path_to(&path)
+            .map_or_else(|| path.path.clone(), str::to_string);
+        manifest.node.insert(
+            action.id.clone(),
+            ServerReferenceManifestEntry {
+                workers: BTreeMap::from([(format!("/{SERVER_ACTION_PATHNAME}"), module)]),
+                name: action.name.clone(),
+            },
+        );
+    }
+    Ok(VirtualAssetVc::new(
+        output_path.join(SERVER_REFERENCE_MANIFEST),
+        File::from(serde_json::to_string_pretty(&manifest)?).into(),
+    )
+    .into())
+}
+
+/// Writes a manifest asset to its path.
+#[turbo_tasks::function]
+pub fn emit_manifest(asset: AssetVc) -> CompletionVc {
+    asset.content().write(asset.ident().path())
+}
@@ -0,0 +1,155 @@
+use anyhow::Result;
+use indexmap::IndexSet;
+use tracing::Instrument;
+use turbo_tasks::{
+    graph::{GraphTraversal, NonDeterministic},
+    primitives::StringVc,
+    CompletionVc,
+};
+use turbo_tasks_fs::FileSystemPathVc;
+use turbopack_core::{
+    asset::{Asset, AssetVc},
+    issue::{Issue, IssueSeverity, IssueSeverityVc, IssueVc},
+    reference::all_referenced_assets,
+};
+use turbopack_ecmascript::EcmascriptModuleAssetVc;
+
+fn is_node_module(path: &str) -> bool {
+    path.split('/').any(|segment| segment == "node_modules")
+}
+
+/// Returns whether a stylesheet applies to the whole document. CSS modules
+/// and their scoped stylesheets are local to the modules importing them.
+fn is_global_stylesheet(path: &str) -> bool {
+    path.ends_with(".css") && !path.ends_with(".module.css") && !path.ends_with(".scoped.css")
+}
+
+/// Returns the ecmascript modules of the project which are imported by
+/// `module`, including itself. Packages in `node_modules` can import global
+/// CSS, so the traversal doesn't continue into them.
+async fn project_module_graph(module: AssetVc) -> Result<IndexSet<AssetVc>> {
+    async fn get_referenced_modules(asset: AssetVc) -> Result<Vec<AssetVc>> {
+        let mut modules = Vec::new();
+        for &referenced in all_referenced_assets(asset).await?.iter() {
+            if is_node_module(&referenced.ident().path().await?.path) {
+                continue;
+            }
+            if EcmascriptModuleAssetVc::resolve_from(referenced)
+                .await?
+                .is_some()
+            {
+                modules.push(referenced);
+            }
+        }
+        Ok(modules)
+    }
+
+    Ok(
+        GraphTraversal::<NonDeterministic<_>>::visit([module], get_referenced_modules)
+            .await
+            .completed()?
+            .into_iter()
+            .collect(),
+    )
+}
+
+/// Reports global stylesheets imported by the modules of a page in the pages
+/// directory. Like in Next.js, they can only be imported from `_app`, as they
+/// apply to every page once loaded. The app directory allows them in any
+/// layout or page.
+#[turbo_tasks::function]
+pub async fn validate_global_css_imports(
+    project_path: FileSystemPathVc,
+    page: AssetVc,
+) -> Result<CompletionVc> {
+    let span = tracing::info_span!("process page modules");
+    validate_page_css_imports(project_path, page)
+        .instrument(span)
+        .await
+}
+
+async fn validate_page_css_imports(
+    project_path: FileSystemPathVc,
+    page: AssetVc,
+) -> Result<CompletionVc> {
+    for module in project_module_graph(page).await? {
+        validate_module_css_imports(project_path, module).await?;
+    }
+    Ok(CompletionVc::new())
+}
+
+/// Reports the global stylesheets imported by a single module, so modules
+/// shared by several pages are only reported once.
+#[turbo_tasks::function]
+async fn validate_module_css_imports(
+    project_path: FileSystemPathVc,
+    module: AssetVc,
+) -> Result<CompletionVc> {
+    let project = project_path.await?;
+    let importer = module.ident().path();
+    if project.get_path_to(&*importer.await?).is_none() {
+        return Ok(CompletionVc::new());
+    }
+    for &referenced in all_referenced_assets(module).await?.iter() {
+        let stylesheet = referenced.ident().path().await?;
+        if is_node_module(&stylesheet.path) || !is_global_stylesheet(&stylesheet.path) {
+            continue;
+        }
+        let Some(relative) = project.get_path_to(&stylesheet) else {
+            continue;
+        };
+        let module_name = format!(
+            "{}.module.css",
+            relative.strip_suffix(".css").unwrap_or(relative)
+        );
+        GlobalCssImportIssue {
+            path: importer,
+            message: StringVc::cell(format!(
+                "This file imports the global stylesheet `{relative}`, but global CSS applies to \
+                 every page, so it can only be imported from `pages/_app`. Move the import into \
+                 `pages/_app`, or rename the stylesheet to `{module_name}` to use a CSS Module \
+                 scoped to this component."
+            )),
+        }
+        .cell()
+        .as_issue()
+        .emit();
+    }
+    Ok(CompletionVc::new())
+}
+
+#[turbo_tasks::value(shared)]
+struct GlobalCssImportIssue {
+    path: FileSystemPathVc,
+    message: StringVc,
+}
+
+#[turbo_tasks::value_impl]
+impl Issue for GlobalCssImportIssue {
+    #[turbo_tasks::function]
+    fn severity(&self) -> IssueSeverityVc {
+        IssueSeverity::Error.into()
+    }
+
+    #[turbo_tasks::function]
+    fn title(&self) -> StringVc {
+        StringVc::cell(
+            "Global CSS cannot be imported from files other than your Custom <App>".to_string(),
+        )
+    }
+
+    #[turbo_tasks::function]
+    fn category(&self) -> StringVc {
+        StringVc::cell("next pages".to_string())
+    }
+
+    #[turbo_tasks::function]
+    fn context(&self) -> FileSystemPathVc {
+        self.path
+    }
+
+    #[turbo_tasks::function]
+    fn description(&self) -> StringVc {
+        self.message
+    }
+}
@@ -0,0 +1,103 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use anyhow::Result;
+use turbo_tasks::{get_invalidator, Value};
+use turbopack_dev_server::source::{
+    ContentSource, ContentSourceContent, ContentSourceData, ContentSourceResultVc, ProxyResult,
+};
+
+/// How stale the readiness reported by [HealthContentSource] can be.
+const READINESS_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Whether the server is compiling. It's updated by the server, which knows
+/// about the tasks in progress.
+#[derive(Default)]
+pub struct ServerStatus {
+    compiling: AtomicBool,
+}
+
+impl ServerStatus {
+    pub fn set_compiling(&self, compiling: bool) {
+        self.compiling.store(compiling, Ordering::Relaxed);
+    }
+
+    pub fn is_compiling(&self) -> bool {
+        self.compiling.load(Ordering::Relaxed)
+    }
+}
+
+/// Answers health checks of orchestrators without routing them through
+/// Next.js. The health check succeeds whenever the server is up, the
+/// readiness check only when it's not compiling.
+#[turbo_tasks::value(serialization = "none", eq = "manual", cell = "new", into = "new")]
+pub struct HealthContentSource {
+    #[turbo_tasks(debug_ignore, trace_ignore)]
+    status: Arc<ServerStatus>,
+    readiness: bool,
+}
+
+impl HealthContentSourceVc {
+    pub fn health() -> Self {
+        Self::cell(HealthContentSource {
+            status: Default::default(),
+            readiness: false,
+        })
+    }
+
+    pub fn readiness(status: Arc<ServerStatus>) -> Self {
+        Self::cell(HealthContentSource {
+            status,
+            readiness: true,
+        })
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl ContentSource for HealthContentSource {
+    #[turbo_tasks::function]
+    fn get(&self, path: &str, _data: Value<ContentSourceData>) -> ContentSourceResultVc {
+        if !path.is_empty() {
+            return ContentSourceResultVc::not_found();
+        }
+        let status = if self.readiness {
+            // The status isn't tracked by turbo-tasks, so the result is refreshed
+            // periodically instead.
+            let invalidator = get_invalidator();
+            tokio::spawn(async move {
+                tokio::time::sleep(READINESS_INTERVAL).await;
+                invalidator.invalidate();
+            });
+            if self.status.is_compiling() {
+                "compiling"
+            } else {
+                "ready"
+            }
+        } else {
+            "ok"
+        };
+        ContentSourceResultVc::exact(
+            ContentSourceContent::HttpProxy(
+                ProxyResult {
+                    status: if status == "compiling" { 503 } else { 200 },
+                    headers: vec![
+                        (
+                            "content-type".to_string(),
+                            "application/json; charset=utf-8".to_string(),
+                        ),
+                        ("cache-control".to_string(), "no-store".to_string()),
+                    ],
+                    body: format!(r#"{{"status":"{status}"}}"#).into(),
+                }
+                .cell(),
+            )
+            .cell()
+            .into(),
+        )
+    }
+}
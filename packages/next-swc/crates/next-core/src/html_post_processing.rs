@@ -0,0 +1,187 @@
+use anyhow::{bail, Result};
+use indexmap::indexmap;
+use tracing::Instrument;
+use turbo_tasks::{
+    primitives::{JsonValueVc, StringVc},
+    Value,
+};
+use turbo_tasks_fs::{json::parse_json_rope_with_source_context, FileSystemPathVc};
+use turbopack::evaluate_context::node_evaluate_asset_context;
+use turbopack_core::{
+    changed::any_content_changed, context::AssetContext, ident::AssetIdentVc,
+    source_asset::SourceAssetVc,
+};
+use turbopack_ecmascript::{
+    EcmascriptInputTransform, EcmascriptInputTransformsVc, EcmascriptModuleAssetType,
+    EcmascriptModuleAssetVc, InnerAssetsVc,
+};
+use turbopack_node::{
+    evaluate::{evaluate, JavaScriptValue},
+    execution_context::{ExecutionContext, ExecutionContextVc},
+};
+
+use crate::{
+    embed_js::next_asset, next_config::NextConfigVc, next_import_map::get_next_build_import_map,
+    static_generation::StaticPage,
+};
+
+/// A pass over the prerendered HTML of pages, e.g. to inline critical assets,
+/// inject analytics snippets or rewrite asset hosts.
+#[turbo_tasks::value_trait]
+pub trait HtmlPostProcessor {
+    /// Returns the processed HTML of the page at `pathname`.
+    fn process(&self, pathname: StringVc, html: StringVc) -> StringVc;
+}
+
+#[turbo_tasks::value(transparent)]
+pub struct HtmlPostProcessors(Vec<HtmlPostProcessorVc>);
+
+/// An HTML post-processor implemented in JavaScript. The module default
+/// exports a function receiving the HTML and `{ pathname }`, which returns the
+/// processed HTML or a promise of it.
+#[turbo_tasks::value(shared)]
+pub struct JsHtmlPostProcessor {
+    execution_context: ExecutionContextVc,
+    path: FileSystemPathVc,
+}
+
+#[turbo_tasks::value_impl]
+impl JsHtmlPostProcessorVc {
+    #[turbo_tasks::function]
+    pub fn new(execution_context: ExecutionContextVc, path: FileSystemPathVc) -> Self {
+        JsHtmlPostProcessor {
+            execution_context,
+            path,
+        }
+        .cell()
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl HtmlPostProcessor for JsHtmlPostProcessor {
+    #[turbo_tasks::function]
+    async fn process(&self, pathname: StringVc, html: StringVc) -> Result<StringVc> {
+        let span = tracing::info_span!("evaluate", layer = "html_post_processor");
+        evaluate_html_post_processor(self, pathname, html)
+            .instrument(span)
+            .await
+    }
+}
+
+async fn evaluate_html_post_processor(
+    this: &JsHtmlPostProcessor,
+    pathname: StringVc,
+    html: StringVc,
+) -> Result<StringVc> {
+    let ExecutionContext {
+        project_path,
+        chunking_context,
+        env,
+    } = *this.execution_context.await?;
+
+    let context =
+        node_evaluate_asset_context(project_path, Some(get_next_build_import_map()), None);
+    let transforms =
+        EcmascriptInputTransformsVc::cell(vec![EcmascriptInputTransform::TypeScript {
+            use_define_for_class_fields: false,
+        }]);
+    let processor_asset = EcmascriptModuleAssetVc::new(
+        SourceAssetVc::new(this.path).into(),
+        context,
+        Value::new(EcmascriptModuleAssetType::Typescript),
+        transforms,
+        context.compile_time_info(),
+    );
+    let entry = EcmascriptModuleAssetVc::new_with_inner_assets(
+        next_asset("entry/html-post-processor.ts"),
+        context,
+        Value::new(EcmascriptModuleAssetType::Typescript),
+        transforms,
+        context.compile_time_info(),
+        InnerAssetsVc::cell(indexmap! {
+            "HTML_POST_PROCESSOR".to_string() => processor_asset.into()
+        }),
+    );
+
+    let result = evaluate(
+        entry.into(),
+        project_path,
+        env,
+        AssetIdentVc::from_path(this.path),
+        context,
+        chunking_context.with_layer("html_post_processor"),
+        None,
+        vec![
+            JsonValueVc::cell(html.await?.as_str().into()),
+            JsonValueVc::cell(pathname.await?.as_str().into()),
+        ],
+        // This invalidates the execution when anything referenced by the
+        // post-processor changes
+        any_content_changed(processor_asset.into()),
+        /* debug */ false,
+    )
+    .await?;
+    let JavaScriptValue::Value(val) = &*result else {
+        bail!(
+            "Failed to run the HTML post-processor {} on {}",
+            this.path.await?.path,
+            pathname.await?
+        );
+    };
+    let html: String = parse_json_rope_with_source_context(val)?;
+    Ok(StringVc::cell(html))
+}
+
+/// Returns the HTML post-processors of `experimental.turbo.htmlPostProcessors`,
+/// in the order they run.
+#[turbo_tasks::function]
+pub async fn get_html_post_processors(
+    execution_context: ExecutionContextVc,
+    next_config: NextConfigVc,
+) -> Result<HtmlPostProcessorsVc> {
+    let project_path = execution_context.await?.project_path;
+    Ok(HtmlPostProcessorsVc::cell(
+        next_config
+            .html_post_processors()
+            .await?
+            .iter()
+            .map(|path| {
+                JsHtmlPostProcessorVc::new(execution_context, project_path.join(path)).into()
+            })
+            .collect(),
+    ))
+}
+
+/// Runs the post-processors over the HTML of a page, each one on the output of
+/// the previous one.
+pub async fn post_process_html(
+    processors: HtmlPostProcessorsVc,
+    pathname: &str,
+    html: String,
+) -> Result<String> {
+    let processors = processors.await?;
+    if processors.is_empty() {
+        return Ok(html);
+    }
+    let pathname = StringVc::cell(pathname.to_string());
+    let mut html = StringVc::cell(html);
+    for processor in processors.iter() {
+        html = processor.process(pathname, html);
+    }
+    Ok(html.await?.clone_value())
+}
+
+/// Runs the post-processors over the HTML of the pages returned by
+/// [generate_static_pages](crate::static_generation::generate_static_pages),
+/// before they are written to the output.
+pub async fn post_process_static_pages(
+    processors: HtmlPostProcessorsVc,
+    pages: Vec<StaticPage<String>>,
+) -> Result<Vec<StaticPage<String>>> {
+    let mut processed = Vec::with_capacity(pages.len());
+    for StaticPage { pathname, content } in pages {
+        let content = post_process_html(processors, &pathname, content).await?;
+        processed.push(StaticPage { pathname, content });
+    }
+    Ok(processed)
+}
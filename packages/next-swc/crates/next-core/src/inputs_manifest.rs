@@ -0,0 +1,256 @@
+use std::collections::BTreeMap;
+
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use turbo_tasks::{
+    graph::{GraphTraversal, NonDeterministic},
+    primitives::StringVc,
+    CollectiblesSource, CompletionVc,
+};
+use turbo_tasks_fs::{File, FileContent, FileContentVc, FileSystemPathVc};
+use turbo_tasks_hash::hash_xxh3_hash64;
+use turbopack_core::{
+    asset::{AssetVc, AssetsVc},
+    reference::all_referenced_assets,
+};
+
+use crate::next_shared::dependencies::LOCKFILES;
+
+/// Files which are read by the build without being referenced by a module.
+const CONFIG_FILES: [&str; 14] = [
+    "package.json",
+    "next.config.js",
+    "next.config.mjs",
+    "tsconfig.json",
+    "jsconfig.json",
+    ".babelrc",
+    ".babelrc.json",
+    "babel.config.js",
+    "postcss.config.js",
+    ".env",
+    ".env.local",
+    ".env.production",
+    ".env.production.local",
+    ".browserslistrc",
+];
+
+/// An input of a build which isn't a file, e.g. an environment variable or a
+/// network resource. Inputs are emitted as collectibles where they are read,
+/// and collected by [get_build_inputs].
+#[turbo_tasks::value_trait]
+pub trait BuildInput {
+    fn entry(&self) -> BuildInputEntryVc;
+}
+
+impl BuildInputVc {
+    pub fn emit(self) {
+        turbo_tasks::emit(self);
+    }
+}
+
+#[turbo_tasks::value(serialization = "auto_for_input")]
+#[derive(Debug, Copy, Clone, Hash)]
+pub enum BuildInputKind {
+    Env,
+    Network,
+}
+
+#[turbo_tasks::value(shared)]
+pub struct BuildInputEntry {
+    pub kind: BuildInputKind,
+    pub name: String,
+    /// The hash of the value, `None` when it isn't set.
+    pub hash: Option<String>,
+}
+
+/// An environment variable which is compiled into the output.
+#[turbo_tasks::value(shared)]
+pub struct EnvBuildInput {
+    pub name: String,
+    pub value: Option<String>,
+}
+
+#[turbo_tasks::value_impl]
+impl BuildInput for EnvBuildInput {
+    #[turbo_tasks::function]
+    fn entry(&self) -> BuildInputEntryVc {
+        BuildInputEntry {
+            kind: BuildInputKind::Env,
+            name: self.name.clone(),
+            hash: self.value.as_ref().map(|value| hash_hex(value.as_bytes())),
+        }
+        .cell()
+    }
+}
+
+/// A resource fetched over the network, e.g. a Google Fonts stylesheet.
+#[turbo_tasks::value(shared)]
+pub struct NetworkBuildInput {
+    pub url: StringVc,
+    pub content: StringVc,
+}
+
+#[turbo_tasks::value_impl]
+impl BuildInput for NetworkBuildInput {
+    #[turbo_tasks::function]
+    async fn entry(&self) -> Result<BuildInputEntryVc> {
+        Ok(BuildInputEntry {
+            kind: BuildInputKind::Network,
+            name: self.url.await?.clone_value(),
+            hash: Some(hash_hex(self.content.await?.as_bytes())),
+        }
+        .cell())
+    }
+}
+
+fn hash_hex(bytes: &[u8]) -> String {
+    format!("{:016x}", hash_xxh3_hash64(bytes))
+}
+
+/// Everything a build consumed, with a hash of its content. It's recorded for
+/// reproducible-build audits and a later build can be verified against it.
+#[turbo_tasks::value(serialization = "custom")]
+#[derive(Default, Serialize, Deserialize)]
+pub struct InputsManifest {
+    /// Files relative to the project directory.
+    pub files: BTreeMap<String, String>,
+    pub env: BTreeMap<String, Option<String>>,
+    pub network: BTreeMap<String, String>,
+}
+
+/// Collects the inputs of a build: the project files of all assets reachable
+/// from `roots`, well-known config files, and the [BuildInput]s emitted while
+/// computing `build`.
+#[turbo_tasks::function]
+pub async fn get_build_inputs(
+    project_path: FileSystemPathVc,
+    build: CompletionVc,
+    roots: AssetsVc,
+) -> Result<InputsManifestVc> {
+    let project_path_value = project_path.await?;
+    let mut manifest = InputsManifest::default();
+
+    async fn get_referenced_assets(asset: AssetVc) -> Result<Vec<AssetVc>> {
+        Ok(all_referenced_assets(asset).await?.clone_value())
+    }
+
+    let assets = GraphTraversal::<NonDeterministic<_>>::visit(
+        roots.await?.iter().copied(),
+        get_referenced_assets,
+    )
+    .await
+    .completed()?;
+
+    let config_files = CONFIG_FILES
+        .iter()
+        .chain(LOCKFILES.iter())
+        .map(|file| project_path.join(file));
+    let files = assets
+        .into_iter()
+        .map(|asset| asset.ident().path())
+        .chain(config_files);
+    for path in files {
+        let path_value = path.await?;
+        let Some(relative) = project_path_value.get_path_to(&path_value) else {
+            // Virtual or generated files are not inputs.
+            continue;
+        };
+        if let FileContent::Content(file) = &*path.read().await? {
+            manifest
+                .files
+                .insert(relative.to_string(), hash_hex(&file.content().to_bytes()?));
+        }
+    }
+
+    for input in build
+        .peek_collectibles::<BuildInputVc>()
+        .strongly_consistent()
+        .await?
+    {
+        let entry = input.entry().await?;
+        match entry.kind {
+            BuildInputKind::Env => {
+                manifest.env.insert(entry.name.clone(), entry.hash.clone());
+            }
+            BuildInputKind::Network => {
+                manifest
+                    .network
+                    .insert(entry.name.clone(), entry.hash.clone().unwrap_or_default());
+            }
+        }
+    }
+
+    Ok(manifest.cell())
+}
+
+/// Returns a hash of all inputs in the manifest, which changes when any of them
+/// changes.
+#[turbo_tasks::function]
+pub async fn fingerprint_inputs(manifest: InputsManifestVc) -> Result<StringVc> {
+    let json = serde_json::to_string(&*manifest.await?)?;
+    Ok(StringVc::cell(hash_hex(json.as_bytes())))
+}
+
+/// Writes the inputs manifest as JSON.
+#[turbo_tasks::function]
+pub async fn write_inputs_manifest(
+    path: FileSystemPathVc,
+    manifest: InputsManifestVc,
+) -> Result<CompletionVc> {
+    let json = serde_json::to_string_pretty(&*manifest.await?)?;
+    Ok(path.write(FileContentVc::from(File::from(json))))
+}
+
+/// Verifies the inputs of a build against a recorded inputs manifest, failing
+/// with a list of all differences.
+#[turbo_tasks::function]
+pub async fn verify_inputs_manifest(
+    path: FileSystemPathVc,
+    manifest: InputsManifestVc,
+) -> Result<CompletionVc> {
+    let FileContent::Content(file) = &*path.read().await? else {
+        bail!("The inputs manifest {} doesn't exist", path.await?.path);
+    };
+    let recorded: InputsManifest = serde_json::from_slice(&file.content().to_bytes()?)?;
+    let current = manifest.await?;
+
+    let mut differences = Vec::new();
+    diff_inputs("file", &recorded.files, &current.files, &mut differences);
+    diff_inputs("env", &recorded.env, &current.env, &mut differences);
+    diff_inputs(
+        "network resource",
+        &recorded.network,
+        &current.network,
+        &mut differences,
+    );
+    if !differences.is_empty() {
+        bail!(
+            "The build inputs differ from the inputs manifest {}:\n{}",
+            path.await?.path,
+            differences.join("\n")
+        );
+    }
+    Ok(CompletionVc::new())
+}
+
+fn diff_inputs<T: PartialEq>(
+    kind: &str,
+    recorded: &BTreeMap<String, T>,
+    current: &BTreeMap<String, T>,
+    differences: &mut Vec<String>,
+) {
+    for (name, hash) in recorded {
+        match current.get(name) {
+            None => differences.push(format!("- {kind} {name} is no longer used")),
+            Some(current) if current != hash => {
+                differences.push(format!("- {kind} {name} has changed"))
+            }
+            _ => {}
+        }
+    }
+    for name in current.keys() {
+        if !recorded.contains_key(name) {
+            differences.push(format!("- {kind} {name} is new"));
+        }
+    }
+}
@@ -0,0 +1,58 @@
+use anyhow::Result;
+use turbo_tasks::primitives::StringsVc;
+use turbo_tasks_fs::{FileSystemEntryType, FileSystemPathVc};
+use turbopack_core::resolve::options::ImportMap;
+
+use crate::{
+    embed_js::{next_js_fs, VIRTUAL_PACKAGE_NAME},
+    next_import_map::request_to_import_mapping,
+};
+
+#[turbo_tasks::value(transparent)]
+pub struct OptionInstrumentationFile(Option<FileSystemPathVc>);
+
+/// Finds the instrumentation file of the project, `instrumentation.ts` or
+/// `src/instrumentation.ts` with any of the page extensions. Its `register`
+/// export is called once when a server process starts, before it handles any
+/// request, see `internal/instrumentation.ts`.
+#[turbo_tasks::function]
+pub async fn find_instrumentation_file(
+    project_path: FileSystemPathVc,
+    page_extensions: StringsVc,
+) -> Result<OptionInstrumentationFileVc> {
+    for dir in ["", "src/"] {
+        for extension in page_extensions.await?.iter() {
+            let path = project_path.join(&format!("{dir}instrumentation.{extension}"));
+            if *path.get_type().await? == FileSystemEntryType::File {
+                return Ok(OptionInstrumentationFileVc::cell(Some(path)));
+            }
+        }
+    }
+    Ok(OptionInstrumentationFileVc::cell(None))
+}
+
+/// Maps `@vercel/turbopack-next/instrumentation` to the instrumentation file of
+/// the project, or to an empty module without one. The Node.js and edge
+/// server contexts compile it with their own options.
+pub(crate) async fn insert_instrumentation_alias(
+    import_map: &mut ImportMap,
+    project_path: FileSystemPathVc,
+    page_extensions: StringsVc,
+) -> Result<()> {
+    let instrumentation_file = find_instrumentation_file(project_path, page_extensions).await?;
+    let relative_path = match *instrumentation_file {
+        Some(file) => project_path
+            .await?
+            .get_path_to(&*file.await?)
+            .map(str::to_string),
+        None => None,
+    };
+    let mapping = match relative_path {
+        Some(relative_path) => {
+            request_to_import_mapping(project_path, &format!("./{relative_path}"))
+        }
+        None => request_to_import_mapping(next_js_fs().root(), "./internal/empty.ts"),
+    };
+    import_map.insert_exact_alias(format!("{VIRTUAL_PACKAGE_NAME}/instrumentation"), mapping);
+    Ok(())
+}
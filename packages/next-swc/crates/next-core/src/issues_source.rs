@@ -0,0 +1,138 @@
+use anyhow::Result;
+use mime::{APPLICATION_JSON, TEXT_HTML_UTF_8};
+use serde::Serialize;
+use turbo_tasks::{primitives::StringVc, TryJoinIterExt, Value};
+use turbo_tasks_fs::File;
+use turbopack_core::{
+    asset::AssetContentVc,
+    issue::{IssueVc, PlainIssueReadRef},
+};
+use turbopack_dev_server::source::{
+    ContentSource, ContentSourceContentVc, ContentSourceData, ContentSourceResultVc,
+    ContentSourceVc,
+};
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct IssueEntry<'a> {
+    severity: &'static str,
+    category: &'a str,
+    context: &'a str,
+    title: &'a str,
+    description: &'a str,
+    detail: &'a str,
+}
+
+impl<'a> From<&'a PlainIssueReadRef> for IssueEntry<'a> {
+    fn from(issue: &'a PlainIssueReadRef) -> Self {
+        IssueEntry {
+            severity: issue.severity.as_str(),
+            category: &issue.category,
+            context: &issue.context,
+            title: &issue.title,
+            description: &issue.description,
+            detail: &issue.detail,
+        }
+    }
+}
+
+/// A dev-only content source serving the issues of the app, so they can be
+/// inspected without the browser overlay, e.g. by headless test runners.
+///
+/// `.json` serves them as JSON and `.html` as a minimal HTML page. Both
+/// include the issues emitted while creating the content sources of all
+/// routes and while analyzing every route for the route list. Issues of
+/// chunks, which are only compiled on request, are reported with the request.
+#[turbo_tasks::value(shared)]
+pub struct IssuesContentSource {
+    pub source: ContentSourceVc,
+    pub route_list: StringVc,
+}
+
+impl IssuesContentSource {
+    async fn issues(&self) -> Result<Vec<PlainIssueReadRef>> {
+        let mut issues = Vec::new();
+        for captured in [
+            IssueVc::peek_issues_with_path(self.source).await?,
+            IssueVc::peek_issues_with_path(self.route_list).await?,
+        ] {
+            let captured = captured.strongly_consistent().await?;
+            issues.extend(
+                captured
+                    .iter_with_shortest_path()
+                    .map(|(issue, path)| issue.into_plain(path))
+                    .try_join()
+                    .await?,
+            );
+        }
+        issues.sort_by(|a, b| {
+            (a.severity, &a.context, &a.title).cmp(&(b.severity, &b.context, &b.title))
+        });
+        issues.dedup_by(|a, b| {
+            (a.severity, &a.context, &a.title, &a.description)
+                == (b.severity, &b.context, &b.title, &b.description)
+        });
+        Ok(issues)
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl ContentSource for IssuesContentSource {
+    #[turbo_tasks::function]
+    async fn get(
+        &self,
+        path: &str,
+        _data: Value<ContentSourceData>,
+    ) -> Result<ContentSourceResultVc> {
+        let file = match path {
+            ".json" => {
+                let issues = self.issues().await?;
+                let entries = issues.iter().map(IssueEntry::from).collect::<Vec<_>>();
+                File::from(serde_json::to_string_pretty(&entries)?)
+                    .with_content_type(APPLICATION_JSON)
+            }
+            ".html" => {
+                let issues = self.issues().await?;
+                File::from(issues_html(&issues)).with_content_type(TEXT_HTML_UTF_8)
+            }
+            _ => return Ok(ContentSourceResultVc::not_found()),
+        };
+        Ok(ContentSourceResultVc::exact(
+            ContentSourceContentVc::static_content(AssetContentVc::from(file).into()).into(),
+        ))
+    }
+}
+
+fn issues_html(issues: &[PlainIssueReadRef]) -> String {
+    let mut html = String::from(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta \
+         charset=\"utf-8\">\n<title>Issues</title>\n</head>\n<body>\n",
+    );
+    html.push_str(&format!("<h1>{} issue(s)</h1>\n", issues.len()));
+    for issue in issues {
+        let entry = IssueEntry::from(issue);
+        html.push_str(&format!(
+            "<section>\n<h2>[{}] {}</h2>\n<p><code>{}</code> ({})</p>\n",
+            entry.severity,
+            escape_html(entry.title),
+            escape_html(entry.context),
+            escape_html(entry.category),
+        ));
+        if !entry.description.is_empty() {
+            html.push_str(&format!("<p>{}</p>\n", escape_html(entry.description)));
+        }
+        if !entry.detail.is_empty() {
+            html.push_str(&format!("<pre>{}</pre>\n", escape_html(entry.detail)));
+        }
+        html.push_str("</section>\n");
+    }
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
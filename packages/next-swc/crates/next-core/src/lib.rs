@@ -6,10 +6,27 @@ mod app_render;
 mod app_source;
 pub mod app_structure;
 mod babel;
+pub mod browserslist;
+pub mod build_progress;
+pub mod builtin_middleware;
+pub mod client_references;
+pub mod compile_hints;
 mod embed_js;
+pub mod entry_compilation;
 pub mod env;
+pub mod env_validation;
 mod fallback;
+pub mod flight_manifest;
+mod global_css;
+pub mod health_source;
+pub mod html_post_processing;
+pub mod inputs_manifest;
+pub mod instrumentation;
+pub mod issues_source;
 pub mod manifest;
+pub mod manifests;
+pub mod metadata_routes;
+pub mod mode;
 mod next_build;
 pub mod next_client;
 mod next_client_chunks;
@@ -19,22 +36,39 @@ mod next_edge;
 mod next_font;
 pub mod next_image;
 mod next_import_map;
+mod next_remote_container;
 mod next_route_matcher;
 pub mod next_server;
 pub mod next_shared;
 mod page_loader;
 mod page_source;
 pub mod pages_structure;
+pub mod project_structure;
 pub mod react_refresh;
+pub mod request_validation;
+pub mod route_handlers;
+pub mod route_list;
+pub mod route_types;
 pub mod router;
+mod router_experiments;
 pub mod router_source;
 mod runtime;
+pub mod server_actions;
+mod service_worker_source;
+pub mod shutdown;
+pub mod standalone;
+pub mod static_export;
+pub mod static_generation;
+pub mod static_params;
+pub mod test_transform;
+pub mod transform_config;
 mod typescript;
 mod util;
 mod web_entry_source;
 
 pub use app_source::create_app_source;
 pub use page_source::create_page_source;
+pub use service_worker_source::create_service_worker_source;
 pub use turbopack_node::source_map;
 pub use web_entry_source::create_web_entry_source;
 
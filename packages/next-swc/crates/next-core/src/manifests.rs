@@ -0,0 +1,913 @@
+use std::collections::{BTreeMap, HashMap};
+
+use anyhow::Result;
+use indexmap::IndexMap;
+use serde::Serialize;
+use serde_json::{json, Value as JsonValue};
+use turbo_tasks::{CompletionVc, CompletionsVc, TryJoinIterExt, Value, ValueToString};
+use turbo_tasks_env::ProcessEnvVc;
+use turbo_tasks_fs::{File, FileContentVc, FileSystem, FileSystemPathVc};
+use turbopack_core::{
+    chunk::{Chunk, ChunksVc, ModuleIdVc},
+    context::AssetContext,
+    environment::ServerAddrVc,
+    reference_type::{EntryReferenceSubType, ReferenceType},
+    server_fs::ServerFileSystemVc,
+    source_asset::SourceAssetVc,
+};
+use turbopack_node::{execution_context::ExecutionContextVc, route_matcher::Param};
+
+use crate::{
+    app_source::app_server_components_context,
+    app_structure::{find_app_structure, AppStructureItem},
+    browserslist::browserslist_query,
+    entry_compilation::compile_entries,
+    mode::NextMode,
+    next_config::{I18NConfig, NextConfigVc, RedirectStatus, Rewrite, Rewrites},
+    next_server::context::ServerContextType,
+    pages_structure::{find_pages_structure, PagesStructureItem},
+    route_list::{has_export, server_context},
+    static_export::{export_page, StaticPathsFallback},
+    static_generation::{generate_static_pages, StaticPage},
+    static_params::get_static_params,
+    util::{
+        get_asset_path_from_route, parse_segment_config_from_source, path_to_regexp,
+        pathname_for_path, NextRevalidate, NextSegmentDynamic,
+    },
+};
+
+/// The manifest of the chunks of the pages of the pages directory.
+pub const BUILD_MANIFEST: &str = "build-manifest.json";
+/// The manifest of the chunks of the entries of the app directory.
+pub const APP_BUILD_MANIFEST: &str = "app-build-manifest.json";
+/// The manifest of the chunks of the modules imported with `next/dynamic`.
+pub const REACT_LOADABLE_MANIFEST: &str = "react-loadable-manifest.json";
+/// The manifest of the server entries of the pages of the pages directory.
+pub const PAGES_MANIFEST: &str = "server/pages-manifest.json";
+/// The manifest of the routes and the custom routes of `next.config.js`,
+/// which `next start` and deployment platforms route requests with.
+pub const ROUTES_MANIFEST: &str = "routes-manifest.json";
+/// The manifest of the prerendered routes with their revalidate intervals,
+/// with which the server of Next.js serves and revalidates them (ISR).
+pub const PRERENDER_MANIFEST: &str = "prerender-manifest.json";
+
+/// The chunks of the entries of a build, from which [write_build_manifests]
+/// creates the manifests the Next.js server loads to find the chunks of a
+/// route.
+#[turbo_tasks::value(shared)]
+#[derive(Default)]
+pub struct BuildManifestEntries {
+    /// The client chunks of the pages of the pages directory, by page, e.g.
+    /// `/_app` or `/blog/[slug]`.
+    pub pages: IndexMap<String, ChunksVc>,
+    /// The client chunks of the entries of the app directory, by entry name,
+    /// e.g. `app/blog/page`.
+    pub app_pages: IndexMap<String, ChunksVc>,
+    /// The chunks of the modules imported with `next/dynamic`, by the key the
+    /// `next/dynamic` transform gives them.
+    pub dynamic_imports: IndexMap<String, DynamicImportChunksVc>,
+}
+
+/// The chunks loading a module imported with `next/dynamic`.
+#[turbo_tasks::value(shared)]
+pub struct DynamicImportChunks {
+    pub module_id: ModuleIdVc,
+    pub chunks: ChunksVc,
+}
+
+#[derive(Serialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct BuildManifest {
+    polyfill_files: Vec<String>,
+    dev_files: Vec<String>,
+    amp_dev_files: Vec<String>,
+    low_priority_files: Vec<String>,
+    root_main_files: Vec<String>,
+    pages: BTreeMap<String, Vec<String>>,
+    amp_first_pages: Vec<String>,
+}
+
+#[derive(Serialize, Default)]
+struct AppBuildManifest {
+    pages: BTreeMap<String, Vec<String>>,
+}
+
+#[derive(Serialize)]
+struct ReactLoadableManifestEntry {
+    id: String,
+    files: Vec<String>,
+}
+
+/// Writes [BUILD_MANIFEST], [APP_BUILD_MANIFEST] and [REACT_LOADABLE_MANIFEST]
+/// to `output_root`, the `distDir` of the build. Like with webpack, the chunk
+/// paths are relative to it, e.g. `static/chunks/main.js`.
+#[turbo_tasks::function]
+pub async fn write_build_manifests(
+    output_root: FileSystemPathVc,
+    entries: BuildManifestEntriesVc,
+) -> Result<CompletionVc> {
+    let entries = entries.await?;
+
+    let build_manifest = BuildManifest {
+        pages: chunk_paths_by_name(output_root, &entries.pages).await?,
+        ..Default::default()
+    };
+    let app_build_manifest = AppBuildManifest {
+        pages: chunk_paths_by_name(output_root, &entries.app_pages).await?,
+    };
+    let mut react_loadable_manifest = BTreeMap::new();
+    for (key, dynamic_import) in entries.dynamic_imports.iter() {
+        let dynamic_import = dynamic_import.await?;
+        react_loadable_manifest.insert(
+            key.clone(),
+            ReactLoadableManifestEntry {
+                id: dynamic_import.module_id.to_string().await?.clone_value(),
+                files: chunk_paths(output_root, dynamic_import.chunks).await?,
+            },
+        );
+    }
+
+    Ok(CompletionsVc::all(vec![
+        write_json(output_root.join(BUILD_MANIFEST), &build_manifest)?,
+        write_json(output_root.join(APP_BUILD_MANIFEST), &app_build_manifest)?,
+        write_json(
+            output_root.join(REACT_LOADABLE_MANIFEST),
+            &react_loadable_manifest,
+        )?,
+    ]))
+}
+
+async fn chunk_paths_by_name(
+    output_root: FileSystemPathVc,
+    entries: &IndexMap<String, ChunksVc>,
+) -> Result<BTreeMap<String, Vec<String>>> {
+    let mut paths = BTreeMap::new();
+    for (name, chunks) in entries.iter() {
+        paths.insert(name.clone(), chunk_paths(output_root, *chunks).await?);
+    }
+    Ok(paths)
+}
+
+/// Returns the paths of the chunks relative to `output_root`. Chunks outside
+/// of it aren't loaded by the server, so they are left out.
+async fn chunk_paths(output_root: FileSystemPathVc, chunks: ChunksVc) -> Result<Vec<String>> {
+    let output_root = output_root.await?;
+    Ok(chunks
+        .await?
+        .iter()
+        .map(|chunk| chunk.path())
+        .try_join()
+        .await?
+        .iter()
+        .filter_map(|path| output_root.get_path_to(path).map(str::to_string))
+        .collect())
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct RoutesManifest {
+    version: u32,
+    pages404: bool,
+    base_path: String,
+    redirects: Vec<JsonValue>,
+    headers: Vec<JsonValue>,
+    rewrites: JsonValue,
+    static_routes: Vec<RouteRegex>,
+    dynamic_routes: Vec<RouteRegex>,
+    data_routes: Vec<JsonValue>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    i18n: Option<I18NConfig>,
+    rsc: JsonValue,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct RouteRegex {
+    page: String,
+    regex: String,
+    route_keys: IndexMap<String, String>,
+    named_regex: String,
+}
+
+/// Writes [PAGES_MANIFEST] and [ROUTES_MANIFEST] to `output_root` from the
+/// routes of the pages and app directories and the custom routes of
+/// `next.config.js`, like `next build` writes them with webpack.
+///
+/// The pages manifest lists where the server entry of every page is written
+/// to, e.g. `pages/blog/[slug].js`, relative to the `server` directory.
+#[turbo_tasks::function]
+pub async fn write_routes_manifests(
+    project_path: FileSystemPathVc,
+    output_root: FileSystemPathVc,
+    next_config: NextConfigVc,
+) -> Result<CompletionVc> {
+    let server_root = ServerFileSystemVc::new().as_file_system().root();
+    let mut pages_manifest = BTreeMap::new();
+    let mut routes = Vec::new();
+
+    if let Some(pages_structure) =
+        *find_pages_structure(project_path, server_root, next_config).await?
+    {
+        // The built-in ones of Next.js are used without the files.
+        for page in ["_app", "_document", "_error"] {
+            pages_manifest.insert(format!("/{page}"), format!("pages/{page}.js"));
+        }
+        let mut queue = vec![pages_structure];
+        while let Some(structure) = queue.pop() {
+            let structure = structure.await?;
+            queue.extend(structure.children.iter().copied());
+            for item in structure.items.iter() {
+                let url = match *item.await? {
+                    PagesStructureItem::Page { url, .. } | PagesStructureItem::Api { url, .. } => {
+                        url
+                    }
+                };
+                let pathname = pathname_for_path(server_root, url, true, false).await?;
+                pages_manifest.insert(
+                    format!("/{pathname}"),
+                    format!("pages/{}", get_asset_path_from_route(&pathname, ".js")),
+                );
+                routes.push(format!("/{pathname}"));
+            }
+        }
+    }
+
+    if let Some(app_structure) = *find_app_structure(project_path, server_root, next_config).await?
+    {
+        let mut queue = vec![app_structure];
+        while let Some(structure) = queue.pop() {
+            let structure = structure.await?;
+            queue.extend(structure.children.iter().copied());
+            let Some(item) = structure.item else {
+                continue;
+            };
+            let url = match *item.await? {
+                AppStructureItem::Page { url, .. } | AppStructureItem::Route { url, .. } => url,
+            };
+            routes.push(format!(
+                "/{}",
+                pathname_for_path(server_root, url, false, false).await?
+            ));
+        }
+    }
+
+    routes.sort_by_cached_key(|route| route_sort_key(route));
+    routes.dedup();
+    let mut static_routes = Vec::new();
+    let mut dynamic_routes = Vec::new();
+    for route in routes {
+        if route.contains('[') {
+            dynamic_routes.push(route_regex(route));
+        } else if !is_reserved_page(&route) {
+            static_routes.push(route_regex(route));
+        }
+    }
+
+    let config = next_config.await?;
+    let restricted_redirect_paths = [format!("{}/_next", config.base_path)];
+    let redirects = config
+        .redirects
+        .iter()
+        .map(|redirect| {
+            let mut route = custom_route(
+                redirect,
+                &redirect.source,
+                redirect.internal.unwrap_or(false),
+                Some(&restricted_redirect_paths),
+            )?;
+            route["statusCode"] = json!(match redirect.status {
+                RedirectStatus::StatusCode(status_code) => status_code as u16,
+                RedirectStatus::Permanent(true) => 308,
+                RedirectStatus::Permanent(false) => 307,
+            });
+            if let JsonValue::Object(route) = &mut route {
+                route.remove("permanent");
+            }
+            Ok(route)
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let headers = config
+        .headers
+        .iter()
+        .map(|header| custom_route(header, &header.source, false, None))
+        .collect::<Result<Vec<_>>>()?;
+    let custom_rewrites = |rewrites: &[Rewrite]| {
+        rewrites
+            .iter()
+            .map(|rewrite| custom_route(rewrite, &rewrite.source, false, None))
+            .collect::<Result<Vec<_>>>()
+    };
+    let Rewrites {
+        before_files,
+        after_files,
+        fallback,
+    } = &config.rewrites;
+    // Without rewrites before the files or fallback ones, they are listed as
+    // an array, like with webpack.
+    let rewrites = if before_files.is_empty() && fallback.is_empty() {
+        json!(custom_rewrites(after_files)?)
+    } else {
+        json!({
+            "beforeFiles": custom_rewrites(before_files)?,
+            "afterFiles": custom_rewrites(after_files)?,
+            "fallback": custom_rewrites(fallback)?,
+        })
+    };
+
+    let routes_manifest = RoutesManifest {
+        version: 3,
+        pages404: true,
+        base_path: config.base_path.clone(),
+        redirects,
+        headers,
+        rewrites,
+        static_routes,
+        dynamic_routes,
+        // webpack adds the data routes of the pages with `getStaticProps` or
+        // `getServerSideProps` after analyzing them, which isn't done here
+        // yet.
+        data_routes: vec![],
+        i18n: config.i18n.clone(),
+        rsc: json!({
+            "header": "RSC",
+            "varyHeader": "RSC, Next-Router-State-Tree, Next-Router-Prefetch",
+            "contentTypeHeader": "text/x-component",
+        }),
+    };
+
+    Ok(CompletionsVc::all(vec![
+        write_json(output_root.join(PAGES_MANIFEST), &pages_manifest)?,
+        write_json(output_root.join(ROUTES_MANIFEST), &routes_manifest)?,
+    ]))
+}
+
+/// The number of routes of every kind, for the summary of a build.
+#[turbo_tasks::value(shared)]
+#[derive(Debug, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RouteCounts {
+    pub pages: u32,
+    pub api_routes: u32,
+    pub app_pages: u32,
+    pub app_routes: u32,
+}
+
+/// Counts the routes of the pages and app directories.
+#[turbo_tasks::function]
+pub async fn count_routes(
+    project_path: FileSystemPathVc,
+    next_config: NextConfigVc,
+) -> Result<RouteCountsVc> {
+    let server_root = ServerFileSystemVc::new().as_file_system().root();
+    let mut counts = RouteCounts::default();
+
+    if let Some(pages_structure) =
+        *find_pages_structure(project_path, server_root, next_config).await?
+    {
+        let mut queue = vec![pages_structure];
+        while let Some(structure) = queue.pop() {
+            let structure = structure.await?;
+            queue.extend(structure.children.iter().copied());
+            for item in structure.items.iter() {
+                match *item.await? {
+                    PagesStructureItem::Page { .. } => counts.pages += 1,
+                    PagesStructureItem::Api { .. } => counts.api_routes += 1,
+                }
+            }
+        }
+    }
+
+    if let Some(app_structure) = *find_app_structure(project_path, server_root, next_config).await?
+    {
+        let mut queue = vec![app_structure];
+        while let Some(structure) = queue.pop() {
+            let structure = structure.await?;
+            queue.extend(structure.children.iter().copied());
+            let Some(item) = structure.item else {
+                continue;
+            };
+            match *item.await? {
+                AppStructureItem::Page { .. } => counts.app_pages += 1,
+                AppStructureItem::Route { .. } => counts.app_routes += 1,
+            }
+        }
+    }
+
+    Ok(counts.cell())
+}
+
+/// The key of a route sorting it like `getSortedRoutes` of Next.js: a route
+/// comes before the routes nested in it, and at every level static segments
+/// come before dynamic ones, which come before catch-all ones.
+fn route_sort_key(route: &str) -> Vec<(u8, String)> {
+    route
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+        .map(|segment| {
+            let rank = if segment.starts_with("[[...") {
+                3
+            } else if segment.starts_with("[...") {
+                2
+            } else if segment.starts_with('[') {
+                1
+            } else {
+                0
+            };
+            (rank, segment.to_string())
+        })
+        .collect()
+}
+
+/// Whether a page isn't routed like the others, like `isReservedPage` of
+/// Next.js.
+fn is_reserved_page(page: &str) -> bool {
+    ["/_app", "/_error", "/_document"].contains(&page)
+        || page == "/api"
+        || page.starts_with("/api/")
+}
+
+/// Returns the regular expressions matching a route, like `getNamedRouteRegex`
+/// of Next.js, e.g. `^/blog/([^/]+?)(?:/)?$` and
+/// `^/blog/(?<slug>[^/]+?)(?:/)?$` for `/blog/[slug]`.
+fn route_regex(page: String) -> RouteRegex {
+    let route = match page.strip_suffix('/') {
+        Some(route) if !route.is_empty() => route,
+        _ => &page,
+    };
+    let mut regex = String::new();
+    let mut named_regex = String::new();
+    let mut route_keys = IndexMap::new();
+    let mut safe_route_key = safe_route_keys();
+    for segment in route[1..].split('/') {
+        let Some(param) = segment.strip_prefix('[').and_then(|s| s.strip_suffix(']')) else {
+            let segment = format!("/{}", escape_string_regexp(segment));
+            regex.push_str(&segment);
+            named_regex.push_str(&segment);
+            continue;
+        };
+        let (key, optional) = match param.strip_prefix('[').and_then(|s| s.strip_suffix(']')) {
+            Some(key) => (key, true),
+            None => (param, false),
+        };
+        let (key, repeat) = match key.strip_prefix("...") {
+            Some(key) => (key, true),
+            None => (key, false),
+        };
+        // Named groups only allow word characters and can't start with a
+        // digit.
+        let mut group: String = key
+            .chars()
+            .filter(|c| c.is_ascii_alphanumeric() || *c == '_')
+            .collect();
+        if group.is_empty() || group.len() > 30 || group.starts_with(|c: char| c.is_ascii_digit()) {
+            group = safe_route_key();
+        }
+        let (pattern, named_pattern) = match (repeat, optional) {
+            (true, true) => ("(?:/(.+?))?", format!("(?:/(?<{group}>.+?))?")),
+            (true, false) => ("/(.+?)", format!("/(?<{group}>.+?)")),
+            (false, _) => ("/([^/]+?)", format!("/(?<{group}>[^/]+?)")),
+        };
+        regex.push_str(pattern);
+        named_regex.push_str(&named_pattern);
+        route_keys.insert(group, key.to_string());
+    }
+    RouteRegex {
+        page,
+        regex: format!("^{regex}(?:/)?$"),
+        route_keys,
+        named_regex: format!("^{named_regex}(?:/)?$"),
+    }
+}
+
+/// Returns a generator of the route keys used for parameters which aren't
+/// valid names of groups, like `buildGetSafeRouteKey` of Next.js.
+fn safe_route_keys() -> impl FnMut() -> String {
+    let mut char_code = b'a';
+    let mut length = 1;
+    move || {
+        let mut key = String::new();
+        for _ in 0..length {
+            key.push(char_code as char);
+            char_code += 1;
+            if char_code > b'z' {
+                length += 1;
+                char_code = b'a';
+            }
+        }
+        key
+    }
+}
+
+/// Escapes the special characters of regular expressions, like
+/// `escapeStringRegexp` of Next.js.
+fn escape_string_regexp(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if "|\\{}()[]^$+*?.-".contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Returns a custom route of `next.config.js` with the `regex` matching its
+/// source, like `buildCustomRoute` of `next build` does. Unless Next.js added
+/// the route, the regex also matches with a trailing slash and doesn't match
+/// the `restricted_paths`.
+fn custom_route<T: Serialize>(
+    route: &T,
+    source: &str,
+    internal: bool,
+    restricted_paths: Option<&[String]>,
+) -> Result<JsonValue> {
+    let mut regex = format!("^{}$", path_to_regexp(source, "/")?);
+    if !internal {
+        if let Some(restricted_paths) = restricted_paths {
+            let restricted_paths = restricted_paths
+                .iter()
+                .map(|path| path.replace('/', "\\/"))
+                .collect::<Vec<_>>()
+                .join("|");
+            regex = format!("^(?!{restricted_paths}){}", &regex[1..]);
+        }
+        regex.truncate(regex.len() - 1);
+        regex.push_str("(?:\\/)?$");
+    }
+    let mut route = serde_json::to_value(route)?;
+    route["regex"] = JsonValue::String(regex.replace("\\/", "/"));
+    Ok(route)
+}
+
+/// The keys with which the server of Next.js signs and encrypts the cookies
+/// of the preview mode, which are generated for every build.
+#[turbo_tasks::value(shared)]
+#[derive(Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PreviewProps {
+    pub preview_mode_id: String,
+    pub preview_mode_signing_key: String,
+    pub preview_mode_encryption_key: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PrerenderManifest {
+    version: u32,
+    routes: BTreeMap<String, PrerenderRoute>,
+    dynamic_routes: BTreeMap<String, PrerenderDynamicRoute>,
+    not_found_routes: Vec<String>,
+    preview: PreviewProps,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PrerenderRoute {
+    /// The number of seconds after which the route is revalidated, or `false`
+    /// when it isn't.
+    initial_revalidate_seconds: JsonValue,
+    /// The dynamic route the path was prerendered for.
+    src_route: Option<String>,
+    data_route: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PrerenderDynamicRoute {
+    route_regex: String,
+    data_route: String,
+    /// `false` when other paths respond with a 404, the HTML file of the
+    /// fallback version of the page, or `null` when other paths are rendered
+    /// on their first request.
+    fallback: JsonValue,
+    data_route_regex: String,
+}
+
+/// Writes [PRERENDER_MANIFEST] to `output_root`, like `next build` writes it
+/// with webpack, which enables ISR for the prerendered routes:
+///
+/// * Pages of the pages directory with `getStaticProps` are rendered for every
+///   path of their `getStaticPaths`, which yields the `revalidate` of every
+///   path, the paths which aren't found and the `fallback` of dynamic pages.
+/// * Pages of the app directory are prerendered when their segment config makes
+///   them static, i.e. with `dynamic = 'force-static'` or `'error'`, or a
+///   `revalidate` other than `0`. Dynamic ones are prerendered for the params
+///   of `generateStaticParams`. Whether other pages are static is only known
+///   when rendering them, so they aren't listed.
+///
+/// The rendered HTML isn't written to the output yet, only the manifest.
+#[allow(clippy::too_many_arguments)]
+#[turbo_tasks::function]
+pub async fn write_prerender_manifest(
+    project_path: FileSystemPathVc,
+    execution_context: ExecutionContextVc,
+    env: ProcessEnvVc,
+    server_addr: ServerAddrVc,
+    output_root: FileSystemPathVc,
+    next_config: NextConfigVc,
+    build_id: &str,
+    preview_props: PreviewPropsVc,
+) -> Result<CompletionVc> {
+    let server_root = ServerFileSystemVc::new().as_file_system().root();
+    let mut manifest = PrerenderManifest {
+        version: 4,
+        routes: BTreeMap::new(),
+        dynamic_routes: BTreeMap::new(),
+        not_found_routes: Vec::new(),
+        preview: preview_props.await?.clone_value(),
+    };
+
+    if let Some(pages_structure) =
+        *find_pages_structure(project_path, server_root, next_config).await?
+    {
+        let context = server_context(
+            project_path,
+            execution_context,
+            Value::new(ServerContextType::Pages {
+                pages_dir: pages_structure.directory(),
+            }),
+            env,
+            server_addr,
+            Value::new(NextMode::Build),
+            next_config,
+        );
+        let mut entries = Vec::new();
+        let mut queue = vec![pages_structure];
+        while let Some(structure) = queue.pop() {
+            let structure = structure.await?;
+            queue.extend(structure.children.iter().copied());
+            for item in structure.items.iter() {
+                if let PagesStructureItem::Page { url, page, .. } = *item.await? {
+                    entries.push((url, page));
+                }
+            }
+        }
+        // The modules of the pages with `getStaticProps`, by page.
+        let pages: HashMap<_, _> = compile_entries(
+            entries,
+            &*next_config.entry_compilation_options().await?,
+            |(url, page)| async move {
+                let module = context.process(
+                    SourceAssetVc::new(page).into(),
+                    Value::new(ReferenceType::Entry(EntryReferenceSubType::Page)),
+                );
+                if !has_export(module, "getStaticProps").await? {
+                    return Ok(None);
+                }
+                let page = format!(
+                    "/{}",
+                    pathname_for_path(server_root, url, true, false).await?
+                );
+                Ok(Some((page, module)))
+            },
+        )
+        .await?
+        .into_iter()
+        .flatten()
+        .collect();
+
+        let options = next_config.static_generation_options().await?;
+        let pages = &pages;
+        let rendered = generate_static_pages(
+            pages.keys().cloned().collect(),
+            &options,
+            |page| async move {
+                Ok(
+                    export_page(context, execution_context, pages[&page], &page, true)
+                        .await?
+                        .clone_value(),
+                )
+            },
+        )
+        .await?;
+        for StaticPage {
+            pathname: page,
+            content,
+        } in rendered
+        {
+            let is_dynamic = content.fallback.is_some();
+            for rendered_page in content.pages {
+                manifest.routes.insert(
+                    rendered_page.pathname.clone(),
+                    PrerenderRoute {
+                        initial_revalidate_seconds: revalidate_json(rendered_page.revalidate),
+                        src_route: is_dynamic.then(|| page.clone()),
+                        data_route: format!(
+                            "/_next/data/{build_id}{}.json",
+                            normalize_page_path(&rendered_page.pathname)
+                        ),
+                    },
+                );
+            }
+            manifest.not_found_routes.extend(content.not_found);
+            if let Some(fallback) = content.fallback {
+                let fallback = match fallback {
+                    StaticPathsFallback::NotFound => JsonValue::Bool(false),
+                    StaticPathsFallback::Page => {
+                        JsonValue::String(format!("{}.html", normalize_page_path(&page)))
+                    }
+                    StaticPathsFallback::Blocking => JsonValue::Null,
+                };
+                let data_route = format!("/_next/data/{build_id}{}", normalize_page_path(&page));
+                manifest.dynamic_routes.insert(
+                    page.clone(),
+                    PrerenderDynamicRoute {
+                        route_regex: route_regex(page).regex,
+                        data_route: format!("{data_route}.json"),
+                        fallback,
+                        data_route_regex: data_route_regex(data_route, ".json"),
+                    },
+                );
+            }
+        }
+    }
+
+    if let Some(app_structure) = *find_app_structure(project_path, server_root, next_config).await?
+    {
+        let app_dir = app_structure.directory();
+        let context = server_context(
+            project_path,
+            execution_context,
+            Value::new(ServerContextType::AppRoute { app_dir }),
+            env,
+            server_addr,
+            Value::new(NextMode::Build),
+            next_config,
+        );
+        let server_components_context = app_server_components_context(
+            project_path,
+            execution_context,
+            output_root,
+            server_root,
+            app_dir,
+            env,
+            &browserslist_query(project_path, Value::new(NextMode::Build)).await?,
+            next_config,
+            server_addr,
+        );
+        let mut entries = Vec::new();
+        let mut queue = vec![app_structure];
+        while let Some(structure) = queue.pop() {
+            let structure = structure.await?;
+            queue.extend(structure.children.iter().copied());
+            let Some(item) = structure.item else {
+                continue;
+            };
+            if let AppStructureItem::Page {
+                url,
+                page,
+                loader_tree,
+                ..
+            } = *item.await?
+            {
+                entries.push((url, page, loader_tree));
+            }
+        }
+        // The prerendered pages with their `revalidate`, and the static params
+        // of the dynamic ones.
+        let pages = compile_entries(
+            entries,
+            &*next_config.entry_compilation_options().await?,
+            |(url, page, loader_tree)| async move {
+                let module = context.process(
+                    SourceAssetVc::new(page).into(),
+                    Value::new(ReferenceType::Entry(EntryReferenceSubType::AppPage)),
+                );
+                let config = parse_segment_config_from_source(module).await?;
+                let revalidate = match (config.dynamic, config.revalidate) {
+                    (Some(NextSegmentDynamic::ForceDynamic), _)
+                    | (_, Some(NextRevalidate::After(0))) => return Ok(None),
+                    (_, Some(NextRevalidate::After(seconds))) => Some(seconds),
+                    (_, Some(NextRevalidate::Never))
+                    | (Some(NextSegmentDynamic::ForceStatic | NextSegmentDynamic::Error), _) => {
+                        None
+                    }
+                    _ => return Ok(None),
+                };
+                let page = format!(
+                    "/{}",
+                    pathname_for_path(server_root, url, false, false).await?
+                );
+                let static_params = if page.contains('[') {
+                    Some(
+                        get_static_params(
+                            server_components_context,
+                            execution_context,
+                            loader_tree,
+                        )
+                        .await?,
+                    )
+                } else {
+                    None
+                };
+                Ok(Some((page, revalidate, static_params)))
+            },
+        )
+        .await?;
+        for (page, revalidate, static_params) in pages.into_iter().flatten() {
+            let Some(static_params) = static_params else {
+                manifest.routes.insert(
+                    page.clone(),
+                    PrerenderRoute {
+                        initial_revalidate_seconds: revalidate_json(revalidate),
+                        src_route: None,
+                        data_route: format!("{}.rsc", normalize_page_path(&page)),
+                    },
+                );
+                continue;
+            };
+
+            for params in static_params.params.iter().flatten() {
+                let Some(pathname) = pathname_with_params(&page, params) else {
+                    continue;
+                };
+                manifest.routes.insert(
+                    pathname.clone(),
+                    PrerenderRoute {
+                        initial_revalidate_seconds: revalidate_json(revalidate),
+                        src_route: Some(page.clone()),
+                        data_route: format!("{}.rsc", normalize_page_path(&pathname)),
+                    },
+                );
+            }
+            let data_route = normalize_page_path(&page);
+            manifest.dynamic_routes.insert(
+                page.clone(),
+                PrerenderDynamicRoute {
+                    route_regex: route_regex(page).regex,
+                    data_route: format!("{data_route}.rsc"),
+                    fallback: if static_params.dynamic_params {
+                        JsonValue::Null
+                    } else {
+                        JsonValue::Bool(false)
+                    },
+                    data_route_regex: data_route_regex(data_route, ".rsc"),
+                },
+            );
+        }
+    }
+
+    manifest.not_found_routes.sort();
+    write_json(output_root.join(PRERENDER_MANIFEST), &manifest)
+}
+
+fn revalidate_json(revalidate: Option<u32>) -> JsonValue {
+    revalidate.map_or(JsonValue::Bool(false), JsonValue::from)
+}
+
+/// Normalizes a page for the path of a file, like `normalizePagePath` of
+/// Next.js, e.g. `/` to `/index`.
+fn normalize_page_path(page: &str) -> String {
+    format!(
+        "/{}",
+        get_asset_path_from_route(page.trim_start_matches('/'), "")
+    )
+}
+
+/// Returns the regular expression matching the paths of a data route, which
+/// end with `extension` instead of an optional trailing slash.
+fn data_route_regex(data_route: String, extension: &str) -> String {
+    let regex = route_regex(data_route).regex;
+    let regex = regex.strip_suffix("(?:/)?$").unwrap_or(&regex);
+    format!("{regex}{}$", escape_string_regexp(extension))
+}
+
+/// Returns the pathname of a dynamic route for params, e.g. `/blog/hello` for
+/// `/blog/[slug]` and `slug: "hello"`. Returns `None` when a param is missing.
+fn pathname_with_params(route: &str, params: &IndexMap<String, Param>) -> Option<String> {
+    let mut pathname = String::new();
+    for segment in route.split('/').filter(|segment| !segment.is_empty()) {
+        let Some(param) = segment.strip_prefix('[').and_then(|s| s.strip_suffix(']')) else {
+            pathname.push('/');
+            pathname.push_str(segment);
+            continue;
+        };
+        let (key, optional) = match param.strip_prefix('[').and_then(|s| s.strip_suffix(']')) {
+            Some(key) => (key, true),
+            None => (param, false),
+        };
+        let key = key.strip_prefix("...").unwrap_or(key);
+        match params.get(key) {
+            Some(Param::Single(value)) => {
+                pathname.push('/');
+                pathname.push_str(value);
+            }
+            Some(Param::Multi(values)) if !values.is_empty() => {
+                pathname.push('/');
+                pathname.push_str(&values.join("/"));
+            }
+            Some(Param::Multi(_)) | None if optional => {}
+            _ => return None,
+        }
+    }
+    if pathname.is_empty() {
+        pathname.push('/');
+    }
+    Some(pathname)
+}
+
+fn write_json<T: Serialize>(path: FileSystemPathVc, value: &T) -> Result<CompletionVc> {
+    let mut json = serde_json::to_string_pretty(value)?;
+    json.push('\n');
+    Ok(path.write(FileContentVc::from(File::from(json))))
+}
@@ -0,0 +1,560 @@
+use anyhow::{bail, Result};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use indexmap::indexmap;
+use mime::{Mime, TEXT_PLAIN_UTF_8};
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use tracing::Instrument;
+use turbo_tasks::{
+    primitives::{OptionStringVc, StringVc},
+    Value,
+};
+use turbo_tasks_fs::{
+    json::parse_json_rope_with_source_context, DirectoryContent, DirectoryEntry, File, FileContent,
+    FileContentVc, FileSystemPathVc,
+};
+use turbopack::evaluate_context::node_evaluate_asset_context;
+use turbopack_core::{
+    asset::AssetContentVc,
+    changed::any_content_changed,
+    context::AssetContext,
+    ident::AssetIdentVc,
+    issue::{Issue, IssueSeverity, IssueSeverityVc, IssueVc},
+    source_asset::SourceAssetVc,
+};
+use turbopack_dev_server::source::{
+    ContentSource, ContentSourceContentVc, ContentSourceData, ContentSourceResultVc,
+};
+use turbopack_ecmascript::{
+    EcmascriptInputTransform, EcmascriptInputTransformsVc, EcmascriptModuleAssetType,
+    EcmascriptModuleAssetVc, InnerAssetsVc,
+};
+use turbopack_node::{
+    evaluate::{evaluate, JavaScriptValue},
+    execution_context::{ExecutionContext, ExecutionContextVc},
+};
+
+use crate::{
+    app_structure::OptionAppStructureVc, embed_js::next_asset,
+    next_import_map::get_next_build_import_map,
+};
+
+/// The metadata file conventions which are served as routes.
+#[turbo_tasks::value(serialization = "auto_for_input")]
+#[derive(Debug, Copy, Clone, Hash)]
+pub enum MetadataRouteKind {
+    Sitemap,
+    Robots,
+    Manifest,
+    Favicon,
+    Icon,
+    AppleIcon,
+}
+
+impl MetadataRouteKind {
+    const ALL: [MetadataRouteKind; 6] = [
+        MetadataRouteKind::Sitemap,
+        MetadataRouteKind::Robots,
+        MetadataRouteKind::Manifest,
+        MetadataRouteKind::Favicon,
+        MetadataRouteKind::Icon,
+        MetadataRouteKind::AppleIcon,
+    ];
+
+    fn basename(self) -> &'static str {
+        match self {
+            MetadataRouteKind::Sitemap => "sitemap",
+            MetadataRouteKind::Robots => "robots",
+            MetadataRouteKind::Manifest => "manifest",
+            MetadataRouteKind::Favicon => "favicon",
+            MetadataRouteKind::Icon => "icon",
+            MetadataRouteKind::AppleIcon => "apple-icon",
+        }
+    }
+
+    fn static_extensions(self) -> &'static [&'static str] {
+        match self {
+            MetadataRouteKind::Sitemap => &["xml"],
+            MetadataRouteKind::Robots => &["txt"],
+            MetadataRouteKind::Manifest => &["json", "webmanifest"],
+            MetadataRouteKind::Favicon => &["ico"],
+            MetadataRouteKind::Icon => &["ico", "jpg", "jpeg", "png", "svg"],
+            MetadataRouteKind::AppleIcon => &["jpg", "jpeg", "png"],
+        }
+    }
+
+    /// The extensions of modules generating the route.
+    fn dynamic_extensions(self) -> &'static [&'static str] {
+        match self {
+            MetadataRouteKind::Sitemap
+            | MetadataRouteKind::Robots
+            | MetadataRouteKind::Manifest => &["js", "ts"],
+            MetadataRouteKind::Favicon => &[],
+            MetadataRouteKind::Icon | MetadataRouteKind::AppleIcon => &["js", "ts", "jsx", "tsx"],
+        }
+    }
+
+    /// The url of a route generated by a module.
+    fn dynamic_url(self) -> &'static str {
+        match self {
+            MetadataRouteKind::Sitemap => "sitemap.xml",
+            MetadataRouteKind::Robots => "robots.txt",
+            MetadataRouteKind::Manifest => "manifest.webmanifest",
+            MetadataRouteKind::Favicon => "favicon.ico",
+            MetadataRouteKind::Icon => "icon",
+            MetadataRouteKind::AppleIcon => "apple-icon",
+        }
+    }
+
+    /// The `rel` of the `<link>` to an icon route.
+    fn link_rel(self) -> Option<&'static str> {
+        match self {
+            MetadataRouteKind::Favicon | MetadataRouteKind::Icon => Some("icon"),
+            MetadataRouteKind::AppleIcon => Some("apple-touch-icon"),
+            _ => None,
+        }
+    }
+}
+
+/// Returns the content type of a static metadata file.
+fn static_content_type(url: &str) -> Mime {
+    match url.rsplit_once('.').map_or("", |(_, ext)| ext) {
+        "xml" => "application/xml".parse().unwrap(),
+        "txt" => TEXT_PLAIN_UTF_8,
+        "json" | "webmanifest" => "application/manifest+json".parse().unwrap(),
+        "ico" => "image/x-icon".parse().unwrap(),
+        "jpg" | "jpeg" => mime::IMAGE_JPEG,
+        "png" => mime::IMAGE_PNG,
+        "svg" => mime::IMAGE_SVG,
+        _ => mime::APPLICATION_OCTET_STREAM,
+    }
+}
+
+/// A metadata file in the root of the app directory, e.g. `app/robots.txt` or
+/// `app/sitemap.ts`.
+#[turbo_tasks::value(shared)]
+pub struct MetadataRoute {
+    pub kind: MetadataRouteKind,
+    /// The url without a leading `/`.
+    pub url: String,
+    pub path: FileSystemPathVc,
+    /// Whether the file is a module whose default export generates the
+    /// content.
+    pub dynamic: bool,
+}
+
+#[turbo_tasks::value(transparent)]
+pub struct MetadataRoutes(Vec<MetadataRouteVc>);
+
+#[turbo_tasks::value(transparent)]
+pub struct OptionMetadataRoute(Option<MetadataRouteVc>);
+
+#[turbo_tasks::value_impl]
+impl MetadataRoutesVc {
+    /// Returns the metadata route serving a pathname without a leading `/`,
+    /// if any.
+    #[turbo_tasks::function]
+    pub async fn find(self, pathname: &str) -> Result<OptionMetadataRouteVc> {
+        for &route in self.await?.iter() {
+            if route.await?.url == pathname {
+                return Ok(OptionMetadataRouteVc::cell(Some(route)));
+            }
+        }
+        Ok(OptionMetadataRouteVc::cell(None))
+    }
+}
+
+/// Finds the metadata files in the root of the app directory.
+#[turbo_tasks::function]
+pub async fn find_metadata_routes(app_structure: OptionAppStructureVc) -> Result<MetadataRoutesVc> {
+    let Some(app_structure) = *app_structure.await? else {
+        return Ok(MetadataRoutesVc::cell(vec![]));
+    };
+    let app_dir = app_structure.await?.directory;
+    let DirectoryContent::Entries(entries) = &*app_dir.read_dir().await? else {
+        return Ok(MetadataRoutesVc::cell(vec![]));
+    };
+
+    let mut routes = Vec::new();
+    for kind in MetadataRouteKind::ALL {
+        let mut static_file = None;
+        let mut dynamic_file = None;
+        for (name, entry) in entries.iter() {
+            let &DirectoryEntry::File(file) = entry else {
+                continue;
+            };
+            let Some((basename, ext)) = name.rsplit_once('.') else {
+                continue;
+            };
+            if basename != kind.basename() {
+                continue;
+            }
+            if kind.static_extensions().contains(&ext) {
+                static_file = Some((name.clone(), file));
+            } else if kind.dynamic_extensions().contains(&ext) {
+                dynamic_file = Some(file);
+            }
+        }
+
+        match (static_file, dynamic_file) {
+            (Some((name, file)), dynamic_file) => {
+                if let Some(dynamic_file) = dynamic_file {
+                    MetadataRouteIssue {
+                        severity: IssueSeverity::Warning.into(),
+                        path: dynamic_file,
+                        message: StringVc::cell(format!(
+                            "Both app/{name} and a {} module exist, the module is ignored.",
+                            kind.basename()
+                        )),
+                    }
+                    .cell()
+                    .as_issue()
+                    .emit();
+                }
+                routes.push(
+                    MetadataRoute {
+                        kind,
+                        url: name,
+                        path: file,
+                        dynamic: false,
+                    }
+                    .cell(),
+                );
+            }
+            (None, Some(file)) => routes.push(
+                MetadataRoute {
+                    kind,
+                    url: kind.dynamic_url().to_string(),
+                    path: file,
+                    dynamic: true,
+                }
+                .cell(),
+            ),
+            (None, None) => {}
+        }
+    }
+    Ok(MetadataRoutesVc::cell(routes))
+}
+
+/// Serves the metadata routes of the app directory. Dynamic routes are
+/// compiled for node.js and their default export is evaluated on request.
+#[turbo_tasks::value(shared)]
+pub struct MetadataRouteContentSource {
+    pub execution_context: ExecutionContextVc,
+    pub routes: MetadataRoutesVc,
+}
+
+#[turbo_tasks::value_impl]
+impl ContentSource for MetadataRouteContentSource {
+    #[turbo_tasks::function]
+    async fn get(
+        &self,
+        path: &str,
+        _data: Value<ContentSourceData>,
+    ) -> Result<ContentSourceResultVc> {
+        let Some(route) = *self.routes.find(path).await? else {
+            return Ok(ContentSourceResultVc::not_found());
+        };
+        let route_value = route.await?;
+        let content = if route_value.dynamic {
+            metadata_route_content(self.execution_context, route)
+        } else {
+            let FileContent::Content(file) = &*route_value.path.read().await? else {
+                return Ok(ContentSourceResultVc::not_found());
+            };
+            FileContent::Content(
+                File::from(file.content().clone())
+                    .with_content_type(static_content_type(&route_value.url)),
+            )
+            .cell()
+        };
+        Ok(ContentSourceResultVc::exact(
+            ContentSourceContentVc::static_content(AssetContentVc::from(content).into()).into(),
+        ))
+    }
+}
+
+/// Evaluates a dynamic metadata route and renders its result.
+#[turbo_tasks::function]
+async fn metadata_route_content(
+    execution_context: ExecutionContextVc,
+    route: MetadataRouteVc,
+) -> Result<FileContentVc> {
+    let span = tracing::info_span!("evaluate", layer = "metadata_route");
+    evaluate_metadata_route(execution_context, route)
+        .instrument(span)
+        .await
+}
+
+async fn evaluate_metadata_route(
+    execution_context: ExecutionContextVc,
+    route: MetadataRouteVc,
+) -> Result<FileContentVc> {
+    let ExecutionContext {
+        project_path,
+        chunking_context,
+        env,
+    } = *execution_context.await?;
+    let MetadataRoute { kind, path, .. } = *route.await?;
+
+    let context =
+        node_evaluate_asset_context(project_path, Some(get_next_build_import_map()), None);
+    // Icons are generated with JSX, e.g. using `ImageResponse`.
+    let transforms = EcmascriptInputTransformsVc::cell(vec![
+        EcmascriptInputTransform::React {
+            refresh: false,
+            import_source: OptionStringVc::cell(None),
+            runtime: OptionStringVc::cell(None),
+        },
+        EcmascriptInputTransform::TypeScript {
+            use_define_for_class_fields: false,
+        },
+    ]);
+    let route_asset = EcmascriptModuleAssetVc::new(
+        SourceAssetVc::new(path).into(),
+        context,
+        Value::new(EcmascriptModuleAssetType::Typescript),
+        transforms,
+        context.compile_time_info(),
+    );
+    let entry = EcmascriptModuleAssetVc::new_with_inner_assets(
+        next_asset("entry/app/metadata-route.ts"),
+        context,
+        Value::new(EcmascriptModuleAssetType::Typescript),
+        transforms,
+        context.compile_time_info(),
+        InnerAssetsVc::cell(indexmap! {
+            "METADATA_ROUTE".to_string() => route_asset.into()
+        }),
+    );
+
+    let result = evaluate(
+        entry.into(),
+        project_path,
+        env,
+        AssetIdentVc::from_path(path),
+        context,
+        chunking_context.with_layer("metadata_route"),
+        None,
+        vec![],
+        // This invalidates the execution when anything referenced by the route
+        // changes
+        any_content_changed(route_asset.into()),
+        /* debug */ false,
+    )
+    .await?;
+    let JavaScriptValue::Value(val) = &*result else {
+        bail!("Failed to evaluate the metadata route {}", path.await?.path);
+    };
+    let file = match kind {
+        MetadataRouteKind::Sitemap => {
+            let entries: Vec<SitemapEntry> = parse_json_rope_with_source_context(val)?;
+            File::from(render_sitemap(&entries))
+                .with_content_type("application/xml".parse().unwrap())
+        }
+        MetadataRouteKind::Robots => {
+            let robots: Robots = parse_json_rope_with_source_context(val)?;
+            File::from(render_robots(&robots)).with_content_type(TEXT_PLAIN_UTF_8)
+        }
+        MetadataRouteKind::Manifest => {
+            let manifest: JsonValue = parse_json_rope_with_source_context(val)?;
+            File::from(serde_json::to_string_pretty(&manifest)?)
+                .with_content_type("application/manifest+json".parse().unwrap())
+        }
+        MetadataRouteKind::Favicon | MetadataRouteKind::Icon | MetadataRouteKind::AppleIcon => {
+            let response: GeneratedResponse = parse_json_rope_with_source_context(val)?;
+            File::from(STANDARD.decode(&response.body)?).with_content_type(
+                response
+                    .content_type
+                    .as_deref()
+                    .unwrap_or("image/png")
+                    .parse()?,
+            )
+        }
+    };
+    Ok(FileContent::Content(file).cell())
+}
+
+/// A `Response` returned by a metadata route, e.g. an `ImageResponse`.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GeneratedResponse {
+    content_type: Option<String>,
+    /// The base64 encoded body.
+    body: String,
+}
+
+/// A `<link>` to an icon route, which is added to the head of every page.
+#[derive(Serialize)]
+pub struct IconLink {
+    pub rel: &'static str,
+    pub href: String,
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub content_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sizes: Option<&'static str>,
+}
+
+/// Returns the `<link>`s to the icon routes.
+pub async fn icon_links(routes: MetadataRoutesVc) -> Result<Vec<IconLink>> {
+    let mut links = Vec::new();
+    for &route in routes.await?.iter() {
+        let route = route.await?;
+        let Some(rel) = route.kind.link_rel() else {
+            continue;
+        };
+        links.push(IconLink {
+            rel,
+            href: format!("/{}", route.url),
+            content_type: (!route.dynamic).then(|| static_content_type(&route.url).to_string()),
+            sizes: matches!(route.kind, MetadataRouteKind::Favicon).then_some("any"),
+        });
+    }
+    Ok(links)
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SitemapEntry {
+    url: String,
+    last_modified: Option<String>,
+    change_frequency: Option<String>,
+    priority: Option<f64>,
+}
+
+fn render_sitemap(entries: &[SitemapEntry]) -> String {
+    let mut xml = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<urlset \
+         xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\n",
+    );
+    for entry in entries {
+        xml.push_str("<url>\n");
+        xml.push_str(&format!("<loc>{}</loc>\n", escape_xml(&entry.url)));
+        if let Some(last_modified) = &entry.last_modified {
+            xml.push_str(&format!(
+                "<lastmod>{}</lastmod>\n",
+                escape_xml(last_modified)
+            ));
+        }
+        if let Some(change_frequency) = &entry.change_frequency {
+            xml.push_str(&format!(
+                "<changefreq>{}</changefreq>\n",
+                escape_xml(change_frequency)
+            ));
+        }
+        if let Some(priority) = entry.priority {
+            xml.push_str(&format!("<priority>{priority}</priority>\n"));
+        }
+        xml.push_str("</url>\n");
+    }
+    xml.push_str("</urlset>\n");
+    xml
+}
+
+/// A value which can be given once or as a list, e.g. the user agents of a
+/// robots rule.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum OneOrMany<T> {
+    One(T),
+    Many(Vec<T>),
+}
+
+impl<T> OneOrMany<T> {
+    fn iter(&self) -> std::slice::Iter<'_, T> {
+        match self {
+            OneOrMany::One(value) => std::slice::from_ref(value).iter(),
+            OneOrMany::Many(values) => values.iter(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RobotsRule {
+    user_agent: Option<OneOrMany<String>>,
+    allow: Option<OneOrMany<String>>,
+    disallow: Option<OneOrMany<String>>,
+    crawl_delay: Option<f64>,
+}
+
+#[derive(Deserialize)]
+struct Robots {
+    rules: OneOrMany<RobotsRule>,
+    sitemap: Option<OneOrMany<String>>,
+    host: Option<String>,
+}
+
+fn render_robots(robots: &Robots) -> String {
+    let mut txt = String::new();
+    for rule in robots.rules.iter() {
+        match &rule.user_agent {
+            Some(user_agents) => {
+                for user_agent in user_agents.iter() {
+                    txt.push_str(&format!("User-Agent: {user_agent}\n"));
+                }
+            }
+            None => txt.push_str("User-Agent: *\n"),
+        }
+        for allow in rule.allow.iter().flat_map(OneOrMany::iter) {
+            txt.push_str(&format!("Allow: {allow}\n"));
+        }
+        for disallow in rule.disallow.iter().flat_map(OneOrMany::iter) {
+            txt.push_str(&format!("Disallow: {disallow}\n"));
+        }
+        if let Some(crawl_delay) = rule.crawl_delay {
+            txt.push_str(&format!("Crawl-delay: {crawl_delay}\n"));
+        }
+        txt.push('\n');
+    }
+    if let Some(host) = &robots.host {
+        txt.push_str(&format!("Host: {host}\n"));
+    }
+    for sitemap in robots.sitemap.iter().flat_map(OneOrMany::iter) {
+        txt.push_str(&format!("Sitemap: {sitemap}\n"));
+    }
+    txt
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+#[turbo_tasks::value(shared)]
+struct MetadataRouteIssue {
+    severity: IssueSeverityVc,
+    path: FileSystemPathVc,
+    message: StringVc,
+}
+
+#[turbo_tasks::value_impl]
+impl Issue for MetadataRouteIssue {
+    #[turbo_tasks::function]
+    fn severity(&self) -> IssueSeverityVc {
+        self.severity
+    }
+
+    #[turbo_tasks::function]
+    fn title(&self) -> StringVc {
+        StringVc::cell("Conflicting metadata route".to_string())
+    }
+
+    #[turbo_tasks::function]
+    fn category(&self) -> StringVc {
+        StringVc::cell("next app".to_string())
+    }
+
+    #[turbo_tasks::function]
+    fn context(&self) -> FileSystemPathVc {
+        self.path
+    }
+
+    #[turbo_tasks::function]
+    fn description(&self) -> StringVc {
+        self.message
+    }
+}
@@ -0,0 +1,27 @@
+/// The mode in which Next.js is running.
+#[turbo_tasks::value(serialization = "auto_for_input")]
+#[derive(Debug, Copy, Clone, Hash, PartialOrd, Ord)]
+pub enum NextMode {
+    /// `next dev`
+    Development,
+    /// `next build`
+    Build,
+    /// Tests, e.g. modules compiled for Jest.
+    Test,
+}
+
+impl NextMode {
+    /// Returns true if the mode is development.
+    pub fn is_development(&self) -> bool {
+        matches!(self, NextMode::Development)
+    }
+
+    /// Returns the value of `process.env.NODE_ENV` for this mode.
+    pub fn node_env(&self) -> &'static str {
+        match self {
+            NextMode::Development => "development",
+            NextMode::Build => "production",
+            NextMode::Test => "test",
+        }
+    }
+}
@@ -8,7 +8,7 @@ use turbo_tasks_fs::{FileSystem, FileSystemPathVc};
 use turbopack::{
     module_options::{
         module_options_context::{ModuleOptionsContext, ModuleOptionsContextVc},
-        JsxTransformOptions, PostCssTransformOptions, WebpackLoadersOptions,
+        JsxTransformOptions, WebpackLoadersOptions,
     },
     resolve_options_context::{ResolveOptionsContext, ResolveOptionsContextVc},
     transition::TransitionsByNameVc,
@@ -16,7 +16,6 @@ use turbopack::{
 };
 use turbopack_core::{
     chunk::ChunkingContextVc,
-    compile_time_defines,
     compile_time_info::{CompileTimeDefinesVc, CompileTimeInfo, CompileTimeInfoVc},
     context::AssetContextVc,
     environment::{BrowserEnvironment, EnvironmentIntention, EnvironmentVc, ExecutionEnvironment},
@@ -26,34 +25,62 @@ use turbopack_dev::DevChunkingContextVc;
 use turbopack_env::ProcessEnvAssetVc;
 use turbopack_node::execution_context::ExecutionContextVc;
 
-use super::transforms::get_next_client_transforms_rules;
+use super::transforms::{
+    get_next_client_custom_ecmascript_transforms, get_next_client_transforms_rules,
+};
 use crate::{
     babel::maybe_add_babel_loader,
     embed_js::next_js_fs,
-    env::env_for_js,
-    next_build::{get_external_next_compiled_package_mapping, get_postcss_package_mapping},
+    env::{env_for_js, next_public_env_defines},
+    mode::NextMode,
+    next_build::get_external_next_compiled_package_mapping,
     next_client::runtime_entry::{RuntimeEntriesVc, RuntimeEntry},
     next_config::NextConfigVc,
     next_import_map::{
         get_next_client_fallback_import_map, get_next_client_import_map,
         get_next_client_resolved_map,
     },
+    next_shared::{
+        css_minify::get_css_minify_module_rules,
+        css_modules::get_css_module_rules,
+        data_modules::get_data_module_rules,
+        defines::{next_config_defines, NextRuntime},
+        graphql::get_graphql_module_rules,
+        media::get_media_module_rules,
+        postcss::get_postcss_module_rules,
+        resolve::ImportQueryResolvePluginVc,
+        static_images::get_static_image_module_rules,
+        transforms::get_import_attributes_transform_rule,
+    },
     react_refresh::assert_can_resolve_react_refresh,
-    typescript::get_typescript_transform_options,
+    typescript::{get_decorators_transform_options, get_typescript_transform_options},
     util::foreign_code_context_condition,
 };
 
-pub fn next_client_defines() -> CompileTimeDefinesVc {
-    compile_time_defines!(
-        process.turbopack = true,
-        process.env.NODE_ENV = "development",
-        process.env.__NEXT_CLIENT_ROUTER_FILTER_ENABLED = false
-    )
-    .cell()
+#[turbo_tasks::function]
+pub async fn next_client_defines(
+    env: ProcessEnvVc,
+    mode: Value<NextMode>,
+    next_config: NextConfigVc,
+) -> Result<CompileTimeDefinesVc> {
+    let mut defines =
+        next_config_defines(NextRuntime::Browser, mode.into_value(), next_config).await?;
+    let public_env_defines = next_public_env_defines(env).await?;
+    defines.0.extend(
+        public_env_defines
+            .iter()
+            .map(|(name, value)| (name.clone(), value.clone())),
+    );
+    Ok(defines.cell())
 }
 
 #[turbo_tasks::function]
-pub fn get_client_compile_time_info(browserslist_query: &str) -> CompileTimeInfoVc {
+pub fn get_client_compile_time_info(
+    browserslist_query: &str,
+    env: ProcessEnvVc,
+    mode: Value<NextMode>,
+    next_config: NextConfigVc,
+) -> CompileTimeInfoVc {
     CompileTimeInfo {
         environment: EnvironmentVc::new(
             Value::new(ExecutionEnvironment::Browser(
@@ -67,7 +94,7 @@ pub fn get_client_compile_time_info(browserslist_query: &str) -> CompileTimeInfo
             )),
             Value::new(EnvironmentIntention::Client),
         ),
-        defines: next_client_defines(),
+        defines: next_client_defines(env, mode, next_config),
     }
     .cell()
 }
@@ -82,14 +109,33 @@ pub enum ClientContextType {
 }
 
 #[turbo_tasks::function]
-pub async fn get_client_resolve_options_context(
+pub fn get_client_resolve_options_context(
     project_path: FileSystemPathVc,
     ty: Value<ClientContextType>,
     next_config: NextConfigVc,
     execution_context: ExecutionContextVc,
+) -> ResolveOptionsContextVc {
+    client_resolve_options_context(project_path, ty, next_config, execution_context, true)
+}
+
+/// The client resolve options, with or without the aliases to the pre-bundled
+/// `experimental.turbo.optimizeDeps` packages. The modules of those packages
+/// are resolved without them.
+#[turbo_tasks::function]
+pub(crate) async fn client_resolve_options_context(
+    project_path: FileSystemPathVc,
+    ty: Value<ClientContextType>,
+    next_config: NextConfigVc,
+    execution_context: ExecutionContextVc,
+    optimize_deps: bool,
 ) -> Result<ResolveOptionsContextVc> {
-    let next_client_import_map =
-        get_next_client_import_map(project_path, ty, next_config, execution_context);
+    let next_client_import_map = get_next_client_import_map(
+        project_path,
+        ty,
+        next_config,
+        execution_context,
+        optimize_deps,
+    );
     let next_client_fallback_import_map = get_next_client_fallback_import_map(ty);
     let next_client_resolved_map = get_next_client_resolved_map(project_path, project_path);
     let module_options_context = ResolveOptionsContext {
@@ -100,6 +146,7 @@ pub async fn get_client_resolve_options_context(
         resolved_map: Some(next_client_resolved_map),
         browser: true,
         module: true,
+        plugins: vec![ImportQueryResolvePluginVc::new(project_path).into()],
         ..Default::default()
     };
     Ok(ResolveOptionsContext {
@@ -120,17 +167,33 @@ pub async fn get_client_module_options_context(
     execution_context: ExecutionContextVc,
     env: EnvironmentVc,
     ty: Value<ClientContextType>,
+    mode: Value<NextMode>,
     next_config: NextConfigVc,
 ) -> Result<ModuleOptionsContextVc> {
-    let custom_rules = get_next_client_transforms_rules(ty.into_value()).await?;
+    let mut custom_rules =
+        get_next_client_transforms_rules(next_config, ty.into_value(), mode.into_value()).await?;
+    custom_rules.push(get_import_attributes_transform_rule());
+    custom_rules.extend(get_postcss_module_rules(execution_context).await?);
+    custom_rules.extend(get_css_module_rules(project_path, next_config, execution_context).await?);
+    custom_rules.extend(get_data_module_rules(next_config).await?);
+    custom_rules.extend(get_graphql_module_rules(next_config).await?);
+    custom_rules.extend(get_media_module_rules(next_config).await?);
+    custom_rules.extend(get_static_image_module_rules(project_path, next_config).await?);
+    custom_rules.extend(
+        get_css_minify_module_rules(project_path, env, mode.into_value(), next_config).await?,
+    );
+    let custom_ecmascript_transforms =
+        get_next_client_custom_ecmascript_transforms(next_config, mode.into_value()).await?;
     let resolve_options_context =
         get_client_resolve_options_context(project_path, ty, next_config, execution_context);
-    let enable_react_refresh =
-        assert_can_resolve_react_refresh(project_path, resolve_options_context)
+    // React Refresh is only used by the HMR of `next dev`.
+    let enable_react_refresh = mode.is_development()
+        && assert_can_resolve_react_refresh(project_path, resolve_options_context)
             .await?
             .is_found();
 
     let tsconfig = get_typescript_transform_options(project_path);
+    let decorators_options = get_decorators_transform_options(project_path);
     let enable_webpack_loaders = {
         let options = &*next_config.webpack_loaders_options().await?;
         let loaders_options = WebpackLoadersOptions {
@@ -150,10 +213,12 @@ pub async fn get_client_module_options_context(
     let module_options_context = ModuleOptionsContext {
         preset_env_versions: Some(env),
         execution_context: Some(execution_context),
+        custom_rules: vec![get_import_attributes_transform_rule()],
         ..Default::default()
     };
 
     let module_options_context = ModuleOptionsContext {
+        custom_ecmascript_transforms,
         // We don't need to resolve React Refresh for each module. Instead,
         // we try resolve it once at the root and pass down a context to all
         // the modules.
@@ -168,12 +233,9 @@ pub async fn get_client_module_options_context(
         enable_react_refresh,
         enable_styled_components: true,
         enable_styled_jsx: true,
-        enable_postcss_transform: Some(PostCssTransformOptions {
-            postcss_package: Some(get_postcss_package_mapping(project_path)),
-            ..Default::default()
-        }),
         enable_webpack_loaders,
         enable_typescript_transform: Some(tsconfig),
+        decorators: Some(decorators_options),
         rules: vec![(
             foreign_code_context_condition(next_config).await?,
             module_options_context.clone().cell(),
@@ -186,12 +248,41 @@ pub async fn get_client_module_options_context(
     Ok(module_options_context)
 }
 
+/// Returns the module options of web workers. Workers are compiled like the
+/// other client code, except that they don't run in a window and don't load
+/// the React Refresh runtime.
+#[turbo_tasks::function]
+pub async fn get_client_worker_module_options_context(
+    project_path: FileSystemPathVc,
+    execution_context: ExecutionContextVc,
+    env: EnvironmentVc,
+    mode: Value<NextMode>,
+    next_config: NextConfigVc,
+) -> Result<ModuleOptionsContextVc> {
+    let module_options_context = get_client_module_options_context(
+        project_path,
+        execution_context,
+        env,
+        Value::new(ClientContextType::Other),
+        mode,
+        next_config,
+    )
+    .await?;
+
+    Ok(ModuleOptionsContext {
+        enable_react_refresh: false,
+        ..(*module_options_context).clone()
+    }
+    .cell())
+}
+
 #[turbo_tasks::function]
 pub fn get_client_asset_context(
     project_path: FileSystemPathVc,
     execution_context: ExecutionContextVc,
     compile_time_info: CompileTimeInfoVc,
     ty: Value<ClientContextType>,
+    mode: Value<NextMode>,
     next_config: NextConfigVc,
 ) -> AssetContextVc {
     let resolve_options_context =
@@ -201,6 +292,7 @@ pub fn get_client_asset_context(
         execution_context,
         compile_time_info.environment(),
         ty,
+        mode,
         next_config,
     );
 
@@ -215,13 +307,22 @@ pub fn get_client_asset_context(
     context
 }
 
+/// Returns the chunking context of the client chunks, which are served by the
+/// dev server with HMR.
+///
+/// In `next build`, `server_root` is the output directory instead, and the
+/// chunks are written to it with [get_client_build_chunking_context].
 #[turbo_tasks::function]
 pub fn get_client_chunking_context(
     project_path: FileSystemPathVc,
     server_root: FileSystemPathVc,
     environment: EnvironmentVc,
     ty: Value<ClientContextType>,
+    mode: Value<NextMode>,
 ) -> ChunkingContextVc {
+    if let NextMode::Build = mode.into_value() {
+        return get_client_build_chunking_context(project_path, server_root, environment);
+    }
     DevChunkingContextVc::builder(
         project_path,
         server_root,
@@ -238,6 +339,59 @@ pub fn get_client_chunking_context(
     .build()
 }
 
+/// Returns the chunking context of web workers. Their chunks are served with
+/// the other client chunks, but don't support HMR, which needs a window.
+#[turbo_tasks::function]
+pub fn get_client_worker_chunking_context(
+    project_path: FileSystemPathVc,
+    server_root: FileSystemPathVc,
+    environment: EnvironmentVc,
+    ty: Value<ClientContextType>,
+    mode: Value<NextMode>,
+) -> ChunkingContextVc {
+    if let NextMode::Build = mode.into_value() {
+        return get_client_build_chunking_context(project_path, server_root, environment);
+    }
+    DevChunkingContextVc::builder(
+        project_path,
+        server_root,
+        server_root.join("/_next/static/chunks"),
+        get_client_assets_path(server_root, ty),
+        environment,
+    )
+    .build()
+}
+
+/// Returns the chunking context of the client chunks of `next build`, which
+/// are written to `static/chunks` in `output_root`, like with webpack. Unlike
+/// [get_client_chunking_context], the chunks don't support HMR.
+#[turbo_tasks::function]
+pub fn get_client_build_chunking_context(
+    project_path: FileSystemPathVc,
+    output_root: FileSystemPathVc,
+    environment: EnvironmentVc,
+) -> ChunkingContextVc {
+    DevChunkingContextVc::builder(
+        project_path,
+        output_root,
+        output_root.join("static/chunks"),
+        output_root.join("static/media"),
+        environment,
+    )
+    .build()
+}
+
+/// Returns the directory the paths of the client chunks are relative to, as
+/// the Next.js runtime loads them: `_next` of the dev server, or the output
+/// directory of `next build`, see [get_client_chunking_context].
+#[turbo_tasks::function]
+pub fn get_client_root(server_root: FileSystemPathVc, mode: Value<NextMode>) -> FileSystemPathVc {
+    match mode.into_value() {
+        NextMode::Build => server_root,
+        NextMode::Development | NextMode::Test => server_root.join("_next"),
+    }
+}
+
 #[turbo_tasks::function]
 pub fn get_client_assets_path(
     server_root: FileSystemPathVc,
@@ -256,15 +410,19 @@ pub async fn get_client_runtime_entries(
     project_root: FileSystemPathVc,
     env: ProcessEnvVc,
     ty: Value<ClientContextType>,
+    mode: Value<NextMode>,
     next_config: NextConfigVc,
     execution_context: ExecutionContextVc,
 ) -> Result<RuntimeEntriesVc> {
     let resolve_options_context =
         get_client_resolve_options_context(project_root, ty, next_config, execution_context);
-    let enable_react_refresh =
+    let enable_react_refresh = if mode.is_development() {
         assert_can_resolve_react_refresh(project_root, resolve_options_context)
             .await?
-            .as_request();
+            .as_request()
+    } else {
+        None
+    };
 
     let mut runtime_entries = vec![RuntimeEntry::Ecmascript(
         ProcessEnvAssetVc::new(project_root, env_for_js(env, true, next_config)).into(),
@@ -291,3 +449,17 @@ pub async fn get_client_runtime_entries(
 
     Ok(RuntimeEntriesVc::cell(runtime_entries))
 }
+
+/// Returns the runtime entries of web workers: only the environment variables,
+/// as React Refresh and the dev bootstrap need a window.
+#[turbo_tasks::function]
+pub fn get_client_worker_runtime_entries(
+    project_root: FileSystemPathVc,
+    env: ProcessEnvVc,
+    next_config: NextConfigVc,
+) -> RuntimeEntriesVc {
+    RuntimeEntriesVc::cell(vec![RuntimeEntry::Ecmascript(
+        ProcessEnvAssetVc::new(project_root, env_for_js(env, true, next_config)).into(),
+    )
+    .cell()])
+}
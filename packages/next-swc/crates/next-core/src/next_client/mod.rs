@@ -1,4 +1,6 @@
 pub(crate) mod context;
+pub(crate) mod optimize_deps;
 pub(crate) mod runtime_entry;
 pub(crate) mod transforms;
 pub(crate) mod transition;
+pub(crate) mod worker_transition;
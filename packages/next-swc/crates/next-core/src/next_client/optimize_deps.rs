@@ -0,0 +1,333 @@
+use std::collections::{HashMap, VecDeque};
+
+use anyhow::Result;
+use indexmap::{IndexMap, IndexSet};
+use indoc::formatdoc;
+use swc_core::ecma::{
+    ast::{CallExpr, Callee, Expr, Lit, ModuleItem, Program},
+    visit::{Visit, VisitWith},
+};
+use turbo_tasks::Value;
+use turbo_tasks_fs::{File, FileContent, FileContentVc, FileJsonContent, FileSystemPathVc};
+use turbopack::{resolve_options, resolve_options_context::ResolveOptionsContextVc};
+use turbopack_core::{
+    asset::{Asset, AssetContent, AssetVc},
+    resolve::{
+        find_context_file,
+        options::{
+            ImportMap, ImportMapResult, ImportMapResultVc, ImportMapping, ImportMappingReplacement,
+            ImportMappingReplacementVc, ImportMappingVc,
+        },
+        package_json,
+        parse::RequestVc,
+        resolve, FindContextFileResult, ResolveResult,
+    },
+    virtual_asset::VirtualAssetVc,
+};
+use turbopack_ecmascript::{
+    parse::{parse, ParseResult},
+    utils::StringifyJs,
+    EcmascriptInputTransformsVc, EcmascriptModuleAssetType,
+};
+
+use crate::next_config::NextConfigVc;
+
+/// Where pre-bundled packages are cached, relative to the project directory.
+const CACHE_DIR: &str = ".next/cache/turbopack/deps";
+
+/// Maps the packages listed in `experimental.turbo.optimizeDeps` to a single
+/// module containing their whole CommonJS dependency tree, so the client
+/// doesn't process and load each file of the package separately in
+/// development.
+///
+/// The modules of the tree are resolved with `resolve_options_context`, which
+/// must be the client resolve options without these aliases.
+pub async fn insert_optimized_deps_aliases(
+    import_map: &mut ImportMap,
+    project_path: FileSystemPathVc,
+    resolve_options_context: ResolveOptionsContextVc,
+    next_config: NextConfigVc,
+) -> Result<()> {
+    for package in next_config.optimize_deps().await?.iter() {
+        import_map.insert_exact_alias(
+            package,
+            ImportMapping::Dynamic(
+                OptimizedDepReplacerVc::new(project_path, package.clone(), resolve_options_context)
+                    .into(),
+            )
+            .cell(),
+        );
+    }
+    Ok(())
+}
+
+#[turbo_tasks::value(shared)]
+pub(crate) struct OptimizedDepReplacer {
+    project_path: FileSystemPathVc,
+    package: String,
+    resolve_options_context: ResolveOptionsContextVc,
+}
+
+#[turbo_tasks::value_impl]
+impl OptimizedDepReplacerVc {
+    #[turbo_tasks::function]
+    pub fn new(
+        project_path: FileSystemPathVc,
+        package: String,
+        resolve_options_context: ResolveOptionsContextVc,
+    ) -> Self {
+        Self::cell(OptimizedDepReplacer {
+            project_path,
+            package,
+            resolve_options_context,
+        })
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl ImportMappingReplacement for OptimizedDepReplacer {
+    #[turbo_tasks::function]
+    fn replace(&self, _capture: &str) -> ImportMappingVc {
+        ImportMapping::Ignore.into()
+    }
+
+    #[turbo_tasks::function]
+    async fn result(&self, _request: RequestVc) -> Result<ImportMapResultVc> {
+        let content = prebundle(
+            self.project_path,
+            self.package.clone(),
+            self.resolve_options_context,
+        );
+        // Packages which can't be pre-bundled are resolved as usual.
+        if !matches!(&*content.await?, FileContent::Content(_)) {
+            return Ok(ImportMapResult::NoEntry.into());
+        }
+
+        // The module is placed in the package directory, so it's handled like
+        // the other files of the package.
+        let asset = VirtualAssetVc::new(
+            self.project_path.join(&format!(
+                "node_modules/{}/__turbopack_optimized__.js",
+                self.package
+            )),
+            content.into(),
+        );
+
+        Ok(ImportMapResult::Result(ResolveResult::asset(asset.into()).into()).into())
+    }
+}
+
+/// Bundles the CommonJS dependency tree of `package` into a single CommonJS
+/// module, or returns `FileContent::NotFound` if the package can't be
+/// pre-bundled, e.g. because a module of the tree is ESM.
+///
+/// The result is cached on disk, keyed by the package version, so it's only
+/// computed once across restarts of the dev server.
+#[turbo_tasks::function]
+async fn prebundle(
+    project_path: FileSystemPathVc,
+    package: String,
+    resolve_options_context: ResolveOptionsContextVc,
+) -> Result<FileContentVc> {
+    let Some(entry) = *resolve(
+        project_path,
+        RequestVc::parse_string(package.clone()),
+        resolve_options(project_path, resolve_options_context),
+    )
+    .first_asset()
+    .await?
+    else {
+        return Ok(FileContent::NotFound.cell());
+    };
+
+    let FindContextFileResult::Found(package_json, _) =
+        *find_context_file(entry.ident().path().parent(), package_json()).await?
+    else {
+        return Ok(FileContent::NotFound.cell());
+    };
+    let FileJsonContent::Content(package_json) = &*package_json.read_json().await? else {
+        return Ok(FileContent::NotFound.cell());
+    };
+    let version = package_json["version"].as_str().unwrap_or("0.0.0");
+
+    let cache_path = project_path.join(&format!(
+        "{CACHE_DIR}/{}@{version}.js",
+        package.replace('/', "+")
+    ));
+    if let FileContent::Content(_) = &*cache_path.read().await? {
+        return Ok(cache_path.read());
+    }
+
+    // Collect the modules of the dependency tree, the entry being the first.
+    let mut ids = HashMap::new();
+    let mut queue = VecDeque::from([entry]);
+    ids.insert(entry.ident().to_string().await?.clone_value(), 0);
+    let mut modules = vec![];
+    let mut externals = IndexSet::new();
+    while let Some(asset) = queue.pop_front() {
+        let AssetContent::File(file) = &*asset.content().await? else {
+            return Ok(FileContent::NotFound.cell());
+        };
+        let FileContent::Content(file) = &*file.await? else {
+            return Ok(FileContent::NotFound.cell());
+        };
+        let Ok(source) = file.content().to_str() else {
+            return Ok(FileContent::NotFound.cell());
+        };
+        let source = source.into_owned();
+        let path = asset.ident().path();
+        let path_value = path.await?;
+        let is_json = path_value.extension() == Some("json");
+
+        let mut requests = IndexMap::new();
+        if !is_json {
+            let Some(required) = required_requests(asset).await? else {
+                return Ok(FileContent::NotFound.cell());
+            };
+            // Resolved like any other client module, so the `browser` field,
+            // aliases and polyfills apply.
+            let resolve_options = resolve_options(path.parent(), resolve_options_context);
+            for request in required {
+                if requests.contains_key(&request) {
+                    continue;
+                }
+                let resolved = *resolve(
+                    path.parent(),
+                    RequestVc::parse_string(request.clone()),
+                    resolve_options,
+                )
+                .first_asset()
+                .await?;
+                match resolved {
+                    Some(resolved) => {
+                        let key = resolved.ident().to_string().await?.clone_value();
+                        let id = match ids.get(&key) {
+                            Some(id) => *id,
+                            None => {
+                                let id = ids.len();
+                                ids.insert(key, id);
+                                queue.push_back(resolved);
+                                id
+                            }
+                        };
+                        requests.insert(request, id);
+                    }
+                    // Unresolvable requests are left to the client module,
+                    // which reports them.
+                    None => {
+                        externals.insert(request);
+                    }
+                }
+            }
+        }
+
+        modules.push((path_value.path.clone(), is_json, source, requests));
+    }
+
+    let mut code = format!(
+        "// {package}@{version}, pre-bundled for development. See \
+         `experimental.turbo.optimizeDeps`.\n\nvar __turbopack_optimized_externals__ = {{\n"
+    );
+    for request in externals {
+        // Plain `require` calls, which are resolved and bundled as usual.
+        code.push_str(&format!(
+            "  {request}: function () {{ return require({request}); }},\n",
+            request = StringifyJs(&request)
+        ));
+    }
+    code.push_str("};\n\nvar __turbopack_optimized_modules__ = [\n");
+    for (path, is_json, source, requests) in modules {
+        let wrapped = if is_json {
+            format!("module.exports = {};", source.trim())
+        } else {
+            source
+        };
+        code.push_str(&format!("  [\n    // {path}\n"));
+        code.push_str(&format!(
+            "    function (module, exports, require) {{\n{wrapped}\n    }},\n"
+        ));
+        code.push_str(&format!("    {},\n  ],\n", StringifyJs(&requests)));
+    }
+    code.push_str(&formatdoc!(
+        r#"
+            ];
+
+            var __turbopack_optimized_cache__ = [];
+
+            function __turbopack_optimized_require__(id) {{
+              var cached = __turbopack_optimized_cache__[id];
+              if (cached) {{
+                return cached.exports;
+              }}
+              var module = (__turbopack_optimized_cache__[id] = {{ exports: {{}} }});
+              var definition = __turbopack_optimized_modules__[id];
+              definition[0].call(module.exports, module, module.exports, function (request) {{
+                var target = definition[1][request];
+                if (target !== undefined) {{
+                  return __turbopack_optimized_require__(target);
+                }}
+                var external = __turbopack_optimized_externals__[request];
+                if (external) {{
+                  return external();
+                }}
+                throw new Error("Cannot find module '" + request + "'");
+              }});
+              return module.exports;
+            }}
+
+            module.exports = __turbopack_optimized_require__(0);
+        "#
+    ));
+
+    let content = FileContent::Content(File::from(code));
+    cache_path.write(content.clone().cell());
+
+    Ok(content.cell())
+}
+
+/// Returns the requests of the static `require` calls of a CommonJS module, or
+/// `None` if the module can't be parsed or is ESM, which can't be wrapped in a
+/// function.
+async fn required_requests(asset: AssetVc) -> Result<Option<IndexSet<String>>> {
+    let ParseResult::Ok { program, .. } = &*parse(
+        asset,
+        Value::new(EcmascriptModuleAssetType::Ecmascript),
+        EcmascriptInputTransformsVc::cell(vec![]),
+    )
+    .await?
+    else {
+        return Ok(None);
+    };
+    if let Program::Module(module) = program {
+        if module
+            .body
+            .iter()
+            .any(|item| matches!(item, ModuleItem::ModuleDecl(_)))
+        {
+            return Ok(None);
+        }
+    }
+    let mut visitor = RequireVisitor::default();
+    program.visit_with(&mut visitor);
+    Ok(Some(visitor.requests))
+}
+
+#[derive(Default)]
+struct RequireVisitor {
+    requests: IndexSet<String>,
+}
+
+impl Visit for RequireVisitor {
+    fn visit_call_expr(&mut self, call: &CallExpr) {
+        if let (Callee::Expr(callee), [arg]) = (&call.callee, &call.args[..]) {
+            if let (Expr::Ident(ident), None, Expr::Lit(Lit::Str(request))) =
+                (&**callee, arg.spread, &*arg.expr)
+            {
+                if &*ident.sym == "require" {
+                    self.requests.insert(request.value.to_string());
+                }
+            }
+        }
+        call.visit_children_with(self);
+    }
+}
@@ -1,23 +1,45 @@
 use anyhow::Result;
 use next_transform_strip_page_exports::ExportFilter;
 use turbopack::module_options::ModuleRule;
+use turbopack_ecmascript::EcmascriptInputTransform;
 
 use crate::{
+    mode::NextMode,
     next_client::context::ClientContextType,
-    next_shared::transforms::{
-        get_next_dynamic_transform_rule, get_next_font_transform_rule,
-        get_next_pages_transforms_rule,
+    next_config::NextConfigVc,
+    next_shared::{
+        defines::NextRuntime,
+        transforms::{
+            get_next_dynamic_transform_rule, get_next_font_transform_rule,
+            get_next_pages_transforms_rule, get_next_worker_transform_rule,
+            get_react_remove_properties_transform, get_remove_console_transform_rule,
+            get_server_actions_transform_rule, get_top_level_await_transform_rule,
+            get_typeof_window_transform_rule,
+        },
     },
 };
 
 /// Returns a list of module rules which apply client-side, Next.js-specific
 /// transforms.
 pub async fn get_next_client_transforms_rules(
+    next_config: NextConfigVc,
     context_ty: ClientContextType,
+    mode: NextMode,
 ) -> Result<Vec<ModuleRule>> {
     let mut rules = vec![];
 
     rules.push(get_next_font_transform_rule());
+    // Other client contexts, e.g. the entries of `next dev` without the pages
+    // and app directories, aren't necessarily rendered in a window.
+    if matches!(
+        context_ty,
+        ClientContextType::Pages { .. }
+            | ClientContextType::App { .. }
+            | ClientContextType::Fallback
+    ) {
+        rules.push(get_typeof_window_transform_rule(NextRuntime::Browser));
+    }
+    rules.push(get_top_level_await_transform_rule(NextRuntime::Browser));
 
     let pages_dir = match context_ty {
         ClientContextType::Pages { pages_dir } => {
@@ -26,12 +48,55 @@ pub async fn get_next_client_transforms_rules(
             );
             Some(pages_dir)
         }
-        ClientContextType::App { .. } | ClientContextType::Fallback | ClientContextType::Other => {
+        ClientContextType::App { .. } => {
+            rules.push(get_server_actions_transform_rule(false));
             None
         }
+        ClientContextType::Fallback | ClientContextType::Other => None,
     };
 
-    rules.push(get_next_dynamic_transform_rule(true, false, false, pages_dir).await?);
+    // Workers are bundled through the `next-client-worker` transition, which
+    // is only available in the pages and app contexts.
+    if matches!(
+        context_ty,
+        ClientContextType::Pages { .. } | ClientContextType::App { .. }
+    ) {
+        rules.push(get_next_worker_transform_rule());
+    }
+
+    rules.push(
+        get_next_dynamic_transform_rule(mode.is_development(), false, false, pages_dir).await?,
+    );
+
+    if !mode.is_development() {
+        let compiler = &next_config.await?.compiler;
+        if let Some(remove_console) = compiler.as_ref().and_then(|c| c.remove_console.as_ref()) {
+            rules.extend(get_remove_console_transform_rule(remove_console));
+        }
+    }
 
     Ok(rules)
 }
+
+/// Returns a list of client-side, Next.js-specific transforms which need to run
+/// before the built-in transforms (e.g. JSX).
+pub async fn get_next_client_custom_ecmascript_transforms(
+    next_config: NextConfigVc,
+    mode: NextMode,
+) -> Result<Vec<EcmascriptInputTransform>> {
+    let mut transforms = vec![];
+
+    if !mode.is_development() {
+        let compiler = &next_config.await?.compiler;
+        if let Some(react_remove_properties) = compiler
+            .as_ref()
+            .and_then(|c| c.react_remove_properties.as_ref())
+        {
+            transforms.extend(get_react_remove_properties_transform(
+                react_remove_properties,
+            )?);
+        }
+    }
+
+    Ok(transforms)
+}
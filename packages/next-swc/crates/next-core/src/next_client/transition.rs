@@ -1,6 +1,7 @@
 use anyhow::Result;
 use indexmap::indexmap;
 use turbo_tasks::{primitives::OptionStringVc, Value};
+use turbo_tasks_fs::FileSystemPathVc;
 use turbopack::{
     ecmascript::chunk_group_files_asset::ChunkGroupFilesAsset,
     module_options::ModuleOptionsContextVc,
@@ -9,9 +10,7 @@ use turbopack::{
     ModuleAssetContextVc,
 };
 use turbopack_core::{
-    asset::AssetVc,
-    chunk::{ChunkingContext, ChunkingContextVc},
-    compile_time_info::CompileTimeInfoVc,
+    asset::AssetVc, chunk::ChunkingContextVc, compile_time_info::CompileTimeInfoVc,
     context::AssetContext,
 };
 use turbopack_ecmascript::{
@@ -34,6 +33,9 @@ pub struct NextClientTransition {
     pub client_module_options_context: ModuleOptionsContextVc,
     pub client_resolve_options_context: ResolveOptionsContextVc,
     pub client_chunking_context: ChunkingContextVc,
+    /// The directory the chunk paths are relative to, see
+    /// [get_client_root](super::context::get_client_root).
+    pub client_root: FileSystemPathVc,
     pub runtime_entries: RuntimeEntriesVc,
 }
 
@@ -101,7 +103,7 @@ impl Transition for NextClientTransition {
             asset: asset.into(),
             // This ensures that the chunk group files asset will strip out the _next prefix from
             // all chunk paths, which is what the Next.js renderer code expects.
-            client_root: self.client_chunking_context.output_root().join("_next"),
+            client_root: self.client_root,
             chunking_context: self.client_chunking_context,
             runtime_entries: Some(runtime_entries),
         };
@@ -0,0 +1,137 @@
+use anyhow::Result;
+use turbo_tasks::Value;
+use turbo_tasks_env::ProcessEnvVc;
+use turbo_tasks_fs::FileSystemPathVc;
+use turbopack::{
+    ecmascript::chunk_group_files_asset::ChunkGroupFilesAsset,
+    module_options::ModuleOptionsContextVc,
+    resolve_options_context::ResolveOptionsContextVc,
+    transition::{Transition, TransitionVc},
+    ModuleAssetContextVc,
+};
+use turbopack_core::{
+    asset::AssetVc,
+    chunk::{ChunkableAssetVc, ChunkingContextVc},
+    compile_time_info::CompileTimeInfoVc,
+};
+use turbopack_node::execution_context::ExecutionContextVc;
+
+use super::{
+    context::{
+        get_client_resolve_options_context, get_client_root, get_client_worker_chunking_context,
+        get_client_worker_module_options_context, get_client_worker_runtime_entries,
+        ClientContextType,
+    },
+    runtime_entry::RuntimeEntriesVc,
+};
+use crate::{mode::NextMode, next_config::NextConfigVc};
+
+/// Makes a transition into the entry of a web worker, as created by
+/// `new Worker(new URL("./worker", import.meta.url))`.
+///
+/// The worker entry is compiled in the browser environment as its own chunk
+/// group. It offers a module to the importer that exports an array of chunk
+/// urls, from which the worker is bootstrapped. Workers don't have a window,
+/// so their chunks have no HMR and their modules aren't compiled for one.
+#[turbo_tasks::value(shared)]
+pub struct NextClientWorkerTransition {
+    pub client_compile_time_info: CompileTimeInfoVc,
+    pub client_module_options_context: ModuleOptionsContextVc,
+    pub client_resolve_options_context: ResolveOptionsContextVc,
+    pub client_chunking_context: ChunkingContextVc,
+    /// The directory the chunk paths are relative to, see [get_client_root].
+    pub client_root: FileSystemPathVc,
+    pub runtime_entries: RuntimeEntriesVc,
+}
+
+#[turbo_tasks::value_impl]
+impl NextClientWorkerTransitionVc {
+    #[turbo_tasks::function]
+    pub fn new(
+        project_path: FileSystemPathVc,
+        execution_context: ExecutionContextVc,
+        ty: Value<ClientContextType>,
+        mode: Value<NextMode>,
+        server_root: FileSystemPathVc,
+        env: ProcessEnvVc,
+        client_compile_time_info: CompileTimeInfoVc,
+        next_config: NextConfigVc,
+    ) -> NextClientWorkerTransitionVc {
+        NextClientWorkerTransition {
+            client_chunking_context: get_client_worker_chunking_context(
+                project_path,
+                server_root,
+                client_compile_time_info.environment(),
+                ty,
+                mode,
+            ),
+            client_root: get_client_root(server_root, mode),
+            client_module_options_context: get_client_worker_module_options_context(
+                project_path,
+                execution_context,
+                client_compile_time_info.environment(),
+                mode,
+                next_config,
+            ),
+            client_resolve_options_context: get_client_resolve_options_context(
+                project_path,
+                ty,
+                next_config,
+                execution_context,
+            ),
+            client_compile_time_info,
+            runtime_entries: get_client_worker_runtime_entries(project_path, env, next_config),
+        }
+        .cell()
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl Transition for NextClientWorkerTransition {
+    #[turbo_tasks::function]
+    fn process_compile_time_info(
+        &self,
+        _compile_time_info: CompileTimeInfoVc,
+    ) -> CompileTimeInfoVc {
+        self.client_compile_time_info
+    }
+
+    #[turbo_tasks::function]
+    fn process_module_options_context(
+        &self,
+        _context: ModuleOptionsContextVc,
+    ) -> ModuleOptionsContextVc {
+        self.client_module_options_context
+    }
+
+    #[turbo_tasks::function]
+    fn process_resolve_options_context(
+        &self,
+        _context: ResolveOptionsContextVc,
+    ) -> ResolveOptionsContextVc {
+        self.client_resolve_options_context
+    }
+
+    #[turbo_tasks::function]
+    async fn process_module(
+        &self,
+        asset: AssetVc,
+        context: ModuleAssetContextVc,
+    ) -> Result<AssetVc> {
+        let Some(asset) = ChunkableAssetVc::resolve_from(asset).await? else {
+            return Ok(asset);
+        };
+        let runtime_entries = self.runtime_entries.resolve_entries(context.into());
+
+        Ok(ChunkGroupFilesAsset {
+            asset,
+            // Strip the _next prefix from all chunk paths, the worker bootstrap
+            // adds it back.
+            client_root: self.client_root,
+            chunking_context: self.client_chunking_context,
+            runtime_entries: Some(runtime_entries),
+        }
+        .cell()
+        .into())
+    }
+}
@@ -12,7 +12,7 @@ use turbopack_core::{
     chunk::{
         availability_info::AvailabilityInfo, Chunk, ChunkGroupReferenceVc, ChunkGroupVc, ChunkItem,
         ChunkItemVc, ChunkListReferenceVc, ChunkVc, ChunkableAsset, ChunkableAssetVc,
-        ChunkingContextVc,
+        ChunkingContextVc, ModuleIdVc,
     },
     ident::AssetIdentVc,
     reference::AssetReferencesVc,
@@ -93,8 +93,46 @@ impl EcmascriptChunkPlaceable for WithChunksAsset {
     }
 }
 
+/// The id of a client module and the chunks loading it.
+#[turbo_tasks::value(shared)]
+pub struct ClientChunks {
+    pub module_id: ModuleIdVc,
+    /// The paths of the chunks, relative to the server root.
+    pub chunks: Vec<String>,
+}
+
 #[turbo_tasks::value_impl]
 impl WithChunksAssetVc {
+    #[turbo_tasks::function]
+    pub async fn client_chunks(self) -> Result<ClientChunksVc> {
+        let this = self.await?;
+        let Some(chunking_context) =
+            EcmascriptChunkingContextVc::resolve_from(this.chunking_context).await?
+        else {
+            bail!("the chunking context is not an EcmascriptChunkingContextVc");
+        };
+        let server_root = this.server_root.await?;
+        let mut chunks = Vec::new();
+        for chunk_path in self
+            .chunk_group()
+            .chunks()
+            .await?
+            .iter()
+            .map(|c| c.path())
+            .try_join()
+            .await?
+        {
+            if let Some(path) = server_root.get_path_to(&chunk_path) {
+                chunks.push(path.to_string());
+            }
+        }
+        Ok(ClientChunks {
+            module_id: this.asset.as_chunk_item(chunking_context).id(),
+            chunks,
+        }
+        .cell())
+    }
+
     #[turbo_tasks::function]
     async fn chunk_group(self) -> Result<ChunkGroupVc> {
         let this = self.await?;
@@ -124,14 +162,8 @@ impl EcmascriptChunkItem for WithChunksChunkItem {
     #[turbo_tasks::function]
     async fn content(&self) -> Result<EcmascriptChunkItemContentVc> {
         let inner = self.inner.await?;
-        let Some(inner_chunking_context) = EcmascriptChunkingContextVc::resolve_from(inner.chunking_context).await? else {
-            bail!("the chunking context is not an EcmascriptChunkingContextVc");
-        };
-
         let group = self.inner.chunk_group();
-        let chunks = group.chunks().await?;
         let server_root = inner.server_root.await?;
-        let mut client_chunks = Vec::new();
 
         let chunk_list_path = group.chunk_list_path().await?;
         let chunk_list_path = if let Some(path) = server_root.get_path_to(&chunk_list_path) {
@@ -140,16 +172,8 @@ impl EcmascriptChunkItem for WithChunksChunkItem {
             bail!("could not get path to chunk list");
         };
 
-        for chunk_path in chunks.iter().map(|c| c.path()).try_join().await? {
-            if let Some(path) = server_root.get_path_to(&chunk_path) {
-                client_chunks.push(serde_json::Value::String(path.to_string()));
-            }
-        }
-        let module_id = &*inner
-            .asset
-            .as_chunk_item(inner_chunking_context)
-            .id()
-            .await?;
+        let client_chunks = self.inner.client_chunks().await?;
+        let module_id = &*client_chunks.module_id.await?;
         Ok(EcmascriptChunkItemContent {
             inner_code: formatdoc! {
                 r#"
@@ -162,7 +186,7 @@ impl EcmascriptChunkItem for WithChunksChunkItem {
                 "#,
                 StringifyJs(&module_id),
                 StringifyJs(&chunk_list_path),
-                StringifyJs(&client_chunks),
+                StringifyJs(&client_chunks.chunks),
             }
             .into(),
             ..Default::default()
@@ -1,5 +1,6 @@
 use anyhow::{Context, Result};
 use indoc::formatdoc;
+use tracing::Instrument;
 use turbo_tasks::{primitives::StringVc, Value, ValueToString, ValueToStringVc};
 use turbo_tasks_fs::FileSystemPathVc;
 use turbopack::ecmascript::chunk::{
@@ -112,56 +113,63 @@ impl EcmascriptChunkItem for WithClientChunksChunkItem {
 
     #[turbo_tasks::function]
     async fn content(&self) -> Result<EcmascriptChunkItemContentVc> {
-        let inner = self.inner.await?;
-        let group = ChunkGroupVc::from_asset(
-            inner.asset.into(),
-            self.context.into(),
-            Value::new(AvailabilityInfo::Root {
-                current_availability_root: inner.asset.into(),
-            }),
-        );
-        let chunks = group.chunks().await?;
-        let server_root = inner.server_root.await?;
-
-        let mut asset_paths = vec![];
-        for chunk in chunks.iter() {
-            for reference in chunk.references().await?.iter() {
-                let assets = &*reference.resolve_reference().primary_assets().await?;
-                for asset in assets.iter() {
-                    asset_paths.push(asset.ident().path().await?);
-                }
-            }
-
-            asset_paths.push(chunk.path().await?);
-        }
+        let span = tracing::info_span!("chunking", layer = "client_component");
+        client_chunks_content(self).instrument(span).await
+    }
+}
 
-        let mut client_chunks = Vec::new();
-        for asset_path in asset_paths {
-            if let Some(path) = server_root.get_path_to(&asset_path) {
-                client_chunks.push(path.to_string());
+async fn client_chunks_content(
+    this: &WithClientChunksChunkItem,
+) -> Result<EcmascriptChunkItemContentVc> {
+    let inner = this.inner.await?;
+    let group = ChunkGroupVc::from_asset(
+        inner.asset.into(),
+        this.context.into(),
+        Value::new(AvailabilityInfo::Root {
+            current_availability_root: inner.asset.into(),
+        }),
+    );
+    let chunks = group.chunks().await?;
+    let server_root = inner.server_root.await?;
+
+    let mut asset_paths = vec![];
+    for chunk in chunks.iter() {
+        for reference in chunk.references().await?.iter() {
+            let assets = &*reference.resolve_reference().primary_assets().await?;
+            for asset in assets.iter() {
+                asset_paths.push(asset.ident().path().await?);
             }
         }
 
-        let module_id = inner.asset.as_chunk_item(self.context).id().await?;
-        Ok(EcmascriptChunkItemContent {
-            inner_code: formatdoc!(
-                // We store the chunks in a binding, otherwise a new array would be created every
-                // time the export binding is read.
-                r#"
-                    __turbopack_esm__({{
-                        default: () => __turbopack_import__({}),
-                        chunks: () => chunks,
-                    }});
-                    const chunks = {:#};
-                "#,
-                StringifyJs(&module_id),
-                StringifyJs(&client_chunks),
-            )
-            .into(),
-            ..Default::default()
+        asset_paths.push(chunk.path().await?);
+    }
+
+    let mut client_chunks = Vec::new();
+    for asset_path in asset_paths {
+        if let Some(path) = server_root.get_path_to(&asset_path) {
+            client_chunks.push(path.to_string());
         }
-        .cell())
     }
+
+    let module_id = inner.asset.as_chunk_item(this.context).id().await?;
+    Ok(EcmascriptChunkItemContent {
+        inner_code: formatdoc!(
+            // We store the chunks in a binding, otherwise a new array would be created every
+            // time the export binding is read.
+            r#"
+                __turbopack_esm__({{
+                    default: () => __turbopack_import__({}),
+                    chunks: () => chunks,
+                }});
+                const chunks = {:#};
+            "#,
+            StringifyJs(&module_id),
+            StringifyJs(&client_chunks),
+        )
+        .into(),
+        ..Default::default()
+    }
+    .cell())
 }
 
 #[turbo_tasks::value_impl]
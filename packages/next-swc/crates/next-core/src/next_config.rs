@@ -2,8 +2,9 @@ use anyhow::Result;
 use indexmap::IndexMap;
 use serde::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
+use tracing::Instrument;
 use turbo_tasks::{
-    primitives::{BoolVc, StringsVc},
+    primitives::{BoolVc, OptionStringVc, StringVc, StringsVc},
     trace::TraceRawVcs,
     CompletionVc, Value,
 };
@@ -14,9 +15,10 @@ use turbopack_core::{
     asset::Asset,
     changed::any_content_changed,
     chunk::ChunkingContext,
+    compile_time_info::{CompileTimeDefineValue, CompileTimeDefines, CompileTimeDefinesVc},
     context::AssetContext,
     ident::AssetIdentVc,
-    issue::IssueContextExt,
+    issue::{Issue, IssueContextExt, IssueSeverity, IssueSeverityVc, IssueVc},
     reference_type::{EntryReferenceSubType, ReferenceType},
     resolve::{
         find_context_file,
@@ -34,7 +36,15 @@ use turbopack_node::{
     transforms::webpack::{WebpackLoaderConfigItems, WebpackLoaderConfigItemsVc},
 };
 
-use crate::embed_js::next_asset;
+use crate::{
+    embed_js::{next_asset, VIRTUAL_PACKAGE_NAME},
+    entry_compilation::{EntryCompilationOptions, EntryCompilationOptionsVc},
+    mode::NextMode,
+    static_generation::{StaticGenerationOptions, StaticGenerationOptionsVc},
+};
+
+/// The default of `staticPageGenerationTimeout`, in seconds.
+const DEFAULT_STATIC_PAGE_GENERATION_TIMEOUT: f64 = 60.0;
 
 #[turbo_tasks::value(serialization = "custom", eq = "manual")]
 #[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
@@ -43,25 +53,29 @@ pub struct NextConfig {
     pub config_file: Option<String>,
     pub config_file_name: String,
 
+    pub asset_prefix: String,
+    pub compiler: Option<CompilerConfig>,
+    pub dist_dir: String,
     pub env: IndexMap<String, String>,
     pub experimental: ExperimentalConfig,
+    pub headers: Vec<Header>,
+    pub i18n: Option<I18NConfig>,
     pub images: ImageConfig,
     pub page_extensions: Vec<String>,
     pub react_strict_mode: Option<bool>,
+    pub redirects: Vec<Redirect>,
     pub rewrites: Rewrites,
+    pub static_page_generation_timeout: f64,
     pub transpile_packages: Option<Vec<String>>,
 
     // unsupported
     cross_origin: Option<String>,
-    compiler: Option<CompilerConfig>,
     amp: AmpConfig,
     analytics_id: String,
-    asset_prefix: String,
     base_path: String,
     clean_dist_dir: bool,
     compress: bool,
     dev_indicators: DevIndicatorsConfig,
-    dist_dir: String,
     eslint: EslintConfig,
     exclude_default_moment_locales: bool,
     // this can be a function in js land
@@ -69,9 +83,8 @@ pub struct NextConfig {
     // this is a function in js land
     generate_build_id: Option<serde_json::Value>,
     generate_etags: bool,
-    headers: Vec<Header>,
     http_agent_options: HttpAgentConfig,
-    i18n: Option<I18NConfig>,
+    modularize_imports: Option<serde_json::Value>,
     on_demand_entries: OnDemandEntriesConfig,
     optimize_fonts: bool,
     output: Option<OutputType>,
@@ -79,10 +92,8 @@ pub struct NextConfig {
     powered_by_header: bool,
     production_browser_source_maps: bool,
     public_runtime_config: IndexMap<String, serde_json::Value>,
-    redirects: Vec<Redirect>,
     sass_options: IndexMap<String, serde_json::Value>,
     server_runtime_config: IndexMap<String, serde_json::Value>,
-    static_page_generation_timeout: f64,
     swc_minify: bool,
     target: Option<String>,
     trailing_slash: bool,
@@ -136,26 +147,31 @@ struct HttpAgentConfig {
 
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize, TraceRawVcs)]
 #[serde(rename_all = "camelCase")]
-struct DomainLocale {
-    default_locale: String,
-    domain: String,
-    http: Option<bool>,
-    locales: Option<Vec<String>>,
+pub struct DomainLocale {
+    pub default_locale: String,
+    pub domain: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub http: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub locales: Option<Vec<String>>,
 }
 
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize, TraceRawVcs)]
 #[serde(rename_all = "camelCase")]
-struct I18NConfig {
-    default_locale: String,
-    domains: Option<Vec<DomainLocale>>,
-    locale_detection: Option<bool>,
-    locales: Vec<String>,
+pub struct I18NConfig {
+    pub default_locale: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub domains: Option<Vec<DomainLocale>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub locale_detection: Option<bool>,
+    pub locales: Vec<String>,
 }
 
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize, TraceRawVcs)]
 #[serde(rename_all = "kebab-case")]
 enum OutputType {
     Standalone,
+    Export,
 }
 
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize, TraceRawVcs)]
@@ -223,6 +239,9 @@ pub struct Redirect {
     pub has: Option<Vec<RouteHas>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub missing: Option<Vec<RouteHas>>,
+    /// Whether Next.js added the redirect itself, e.g. for `trailingSlash`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub internal: Option<bool>,
 
     #[serde(flatten)]
     pub status: RedirectStatus,
@@ -337,14 +356,282 @@ pub enum RemotePatternProtocal {
 #[serde(rename_all = "camelCase")]
 pub struct ExperimentalTurboConfig {
     pub loaders: Option<IndexMap<String, WebpackLoaderConfigItems>>,
+    /// Webpack loaders to run on files matching a glob, e.g. `*.svg`.
+    pub rules: Option<IndexMap<String, RuleConfigItem>>,
     pub resolve_alias: Option<IndexMap<String, JsonValue>>,
+    /// Remote containers to consume at runtime, keyed by the specifier prefix
+    /// they are imported with. Values are either the URL of the container
+    /// entry, or `global@url` when the container registers itself under a
+    /// different global name.
+    pub remotes: Option<IndexMap<String, String>>,
+    /// Data file formats to import as JSON modules, keyed by file extension.
+    /// Extends (and overrides) the default `.yaml`, `.yml` and `.toml`
+    /// handling.
+    pub data_modules: Option<IndexMap<String, DataModuleFormat>>,
+    /// The service worker entry, relative to the project directory, e.g.
+    /// `app/sw.ts`. It's served at the root of the app, e.g. `/sw.js`.
+    pub service_worker: Option<String>,
+    /// Audio and video files smaller than this many bytes are inlined as data
+    /// URLs. Defaults to 8 KiB.
+    pub media_inline_limit: Option<u64>,
+    /// How Node.js builtins are handled in the edge runtime. Defaults to
+    /// `shims`.
+    pub edge_node_compat: Option<EdgeNodeCompat>,
+    /// CommonJS packages to pre-bundle into a single module for the client in
+    /// development.
+    pub optimize_deps: Option<Vec<String>>,
+    /// Modules, relative to the project directory, which post-process the
+    /// prerendered HTML of pages, in the order they run.
+    pub html_post_processors: Option<Vec<String>>,
+    /// Experiments bucketing the requests of a pathname between rewrite
+    /// variants, which are applied by the router without running middleware.
+    pub experiments: Option<Vec<RouteExperiment>>,
+    /// Protections run by the router before user middleware, so they don't
+    /// need a `middleware.ts`.
+    pub builtin_middleware: Option<BuiltinMiddlewareConfig>,
+    /// Health and readiness endpoints answered by the server itself.
+    pub health_check: Option<HealthCheckConfig>,
+    /// The maximum size in bytes of a response body produced by middleware.
+    /// Larger responses are aborted with a 500. Defaults to 10 MiB.
+    pub middleware_body_size_limit: Option<u64>,
+    /// Generates `PageProps` and `RouteContext` types with the params of each
+    /// route in the app directory to `.next/types/app`.
+    pub route_types: Option<bool>,
+    /// Customizes the scoped class names of `.module.css` files.
+    pub css_modules: Option<CssModulesConfig>,
+    /// Compile-time defines of each mode, applied to client, server and edge
+    /// code.
+    pub defines: Option<ModeDefinesConfig>,
+    /// Caches the pages with `getStaticProps` or a `revalidate` in development
+    /// like `next start` does, serving them until they are revalidated.
+    pub isr_cache: Option<bool>,
+    /// The maximum number of route entries `next build` compiles at the same
+    /// time. Defaults to the number of CPUs.
+    pub entry_concurrency: Option<usize>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, TraceRawVcs)]
+#[serde(untagged)]
+pub enum RuleConfigItem {
+    Loaders(WebpackLoaderConfigItems),
+    Options {
+        loaders: WebpackLoaderConfigItems,
+        /// The file the loaders output should be processed as, e.g. `*.js`.
+        #[serde(rename = "as")]
+        rename_as: Option<String>,
+    },
+}
+
+impl RuleConfigItem {
+    fn loaders(&self) -> &WebpackLoaderConfigItems {
+        match self {
+            RuleConfigItem::Loaders(loaders) | RuleConfigItem::Options { loaders, .. } => loaders,
+        }
+    }
+
+    /// Returns the file extension the rule's glob matches, or why the rule
+    /// can't run. Webpack loaders match files by extension and their output
+    /// is processed as JavaScript.
+    fn extension<'a>(&self, glob: &'a str) -> Result<&'a str, String> {
+        let extension = glob
+            .strip_prefix("**/")
+            .unwrap_or(glob)
+            .strip_prefix('*')
+            .filter(|ext| ext.starts_with('.') && !ext.contains(['*', '/', '{', '[', '?']))
+            .ok_or_else(|| {
+                format!(
+                    "The glob `{glob}` is not supported. Only globs matching a file extension \
+                     (`*.svg`) are supported."
+                )
+            })?;
+        match self {
+            RuleConfigItem::Options {
+                rename_as: Some(rename_as),
+                ..
+            } if rename_as != "*.js" => Err(format!(
+                "The rule for `{glob}` can't be processed as `{rename_as}`. Only JavaScript \
+                 (`*.js`) output is supported."
+            )),
+            _ => Ok(extension),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize, TraceRawVcs)]
+#[serde(rename_all = "lowercase")]
+pub enum EdgeNodeCompat {
+    /// `buffer`, `async_hooks` and `util` are mapped to edge-safe shims, other
+    /// builtins are reported as unsupported.
+    #[default]
+    Shims,
+    /// All builtins are reported as unsupported.
+    Strict,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, TraceRawVcs)]
+#[serde(rename_all = "camelCase")]
+pub struct RouteExperiment {
+    /// The name of the experiment, which is part of the bucketing hash, so
+    /// experiments bucket requests independently.
+    pub name: String,
+    /// The pathname of the requests the experiment applies to.
+    pub source: String,
+    pub bucket_by: ExperimentBucketBy,
+    pub variants: Vec<ExperimentVariant>,
+}
+
+/// The part of a request which decides its bucket. Requests without it aren't
+/// bucketed.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, TraceRawVcs)]
+#[serde(tag = "type", content = "key", rename_all = "lowercase")]
+pub enum ExperimentBucketBy {
+    Cookie(String),
+    Header(String),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, TraceRawVcs)]
+#[serde(rename_all = "camelCase")]
+pub struct ExperimentVariant {
+    pub name: String,
+    /// The url the requests of the variant are rewritten to.
+    pub destination: String,
+    /// The share of requests of the variant, relative to the other variants.
+    /// Defaults to 1.
+    pub weight: Option<u32>,
+}
+
+#[turbo_tasks::value(shared)]
+#[derive(Clone, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct BuiltinMiddlewareConfig {
+    /// Pathname prefixes the protections apply to. Defaults to all requests.
+    pub matcher: Option<Vec<String>>,
+    /// Client IP addresses or CIDR ranges allowed to make requests. The client
+    /// IP is the peer address of the connection, or is read from the
+    /// `x-forwarded-for` header behind proxies, see `trusted_proxy_hops`.
+    pub ip_allowlist: Option<Vec<String>>,
+    /// The number of proxies in front of the server that append the address
+    /// of their peer to `x-forwarded-for`. The client IP is the entry added by
+    /// the outermost of them, counted from the right, as entries further left
+    /// are sent by the client. Defaults to 0, the peer address of the
+    /// connection being the client IP.
+    pub trusted_proxy_hops: Option<usize>,
+    pub basic_auth: Option<BasicAuthConfig>,
+}
+
+#[turbo_tasks::value(shared)]
+#[derive(Clone, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct HealthCheckConfig {
+    /// The pathname of the health check. Defaults to `/healthz`.
+    pub path: Option<String>,
+    /// The pathname of the readiness check, which fails while the server is
+    /// compiling. Defaults to `/readyz`.
+    pub readiness_path: Option<String>,
+}
+
+#[turbo_tasks::value(transparent)]
+pub struct OptionHealthCheckConfig(Option<HealthCheckConfig>);
+
+/// Compile-time defines, keyed by the expression they replace, e.g.
+/// `process.env.API_URL` or `__DEV__`.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize, TraceRawVcs)]
+pub struct ModeDefinesConfig {
+    /// Applied in `next dev`.
+    pub development: Option<IndexMap<String, DefineValue>>,
+    /// Applied in `next build`.
+    pub production: Option<IndexMap<String, DefineValue>>,
+    /// Applied to modules compiled for tests, e.g. by the Jest transform.
+    pub test: Option<IndexMap<String, DefineValue>>,
+}
+
+impl ModeDefinesConfig {
+    /// Returns the defines of a mode, keyed by the segments of the expression
+    /// they replace.
+    pub fn values(&self, mode: NextMode) -> IndexMap<Vec<String>, DefineValue> {
+        let defines = match mode {
+            NextMode::Development => self.development.as_ref(),
+            NextMode::Build => self.production.as_ref(),
+            NextMode::Test => self.test.as_ref(),
+        };
+        defines
+            .into_iter()
+            .flatten()
+            .map(|(expression, value)| {
+                (
+                    expression.split('.').map(ToOwned::to_owned).collect(),
+                    value.clone(),
+                )
+            })
+            .collect()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, TraceRawVcs)]
+#[serde(untagged)]
+pub enum DefineValue {
+    Bool(bool),
+    String(String),
+}
+
+impl From<DefineValue> for CompileTimeDefineValue {
+    fn from(value: DefineValue) -> Self {
+        match value {
+            DefineValue::Bool(value) => CompileTimeDefineValue::Bool(value),
+            DefineValue::String(value) => CompileTimeDefineValue::String(value),
+        }
+    }
+}
+
+#[turbo_tasks::value(shared)]
+#[derive(Clone, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct CssModulesConfig {
+    /// The pattern of the scoped class names of CSS modules, with the
+    /// `[name]`, `[folder]`, `[local]` and `[hash]` placeholders. Defaults to
+    /// the pattern of the webpack config of Next.js, e.g.
+    /// `Button_primary__Xk3Ab`.
+    pub local_ident_name: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, TraceRawVcs)]
+#[serde(rename_all = "camelCase")]
+pub struct BasicAuthConfig {
+    pub username: String,
+    pub password: String,
+    /// The realm of the `WWW-Authenticate` challenge. Defaults to `Next.js`.
+    pub realm: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, TraceRawVcs)]
+#[serde(rename_all = "lowercase")]
+pub enum DataModuleFormat {
+    Json,
+    Yaml,
+    Toml,
+}
+
+impl DataModuleFormat {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DataModuleFormat::Json => "JSON",
+            DataModuleFormat::Yaml => "YAML",
+            DataModuleFormat::Toml => "TOML",
+        }
+    }
 }
 
 #[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize, TraceRawVcs)]
 #[serde(rename_all = "camelCase")]
 pub struct ExperimentalConfig {
     pub app_dir: Option<bool>,
+    pub cpus: Option<f64>,
     pub server_components_external_packages: Option<Vec<String>>,
+    /// Environment variables which must be set for the server code to work.
+    pub required_env: Option<Vec<String>>,
+    /// Fetches the remote images rendered with next/image to validate their
+    /// size.
+    pub probe_remote_images: Option<bool>,
     pub turbo: Option<ExperimentalTurboConfig>,
 
     // unsupported
@@ -352,7 +639,6 @@ pub struct ExperimentalConfig {
     adjust_font_fallbacks_with_size_adjust: Option<bool>,
     allow_middleware_response_body: Option<bool>,
     amp: Option<serde_json::Value>,
-    cpus: Option<f64>,
     cra_compat: Option<bool>,
     disable_optimized_loading: Option<bool>,
     disable_postcss_preset_env: Option<bool>,
@@ -411,9 +697,14 @@ enum MiddlewarePrefetchType {
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize, TraceRawVcs)]
 #[serde(rename_all = "camelCase")]
 pub struct CompilerConfig {
-    pub react_remove_properties: Option<bool>,
+    pub css_minify: Option<bool>,
+    pub react_remove_properties: Option<ReactRemoveProperties>,
     pub relay: Option<RelayConfig>,
     pub remove_console: Option<RemoveConsoleConfig>,
+
+    // unsupported
+    emotion: Option<serde_json::Value>,
+    styled_components: Option<serde_json::Value>,
 }
 
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize, TraceRawVcs)]
@@ -446,6 +737,40 @@ pub enum RemoveConsoleConfig {
     Config { exclude: Option<Vec<String>> },
 }
 
+/// A precompiled remote container (module federation style) which is loaded
+/// at runtime.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, TraceRawVcs)]
+pub struct RemoteContainer {
+    /// The global name the container registers itself under.
+    pub global: String,
+    /// The URL of the container entry script.
+    pub url: String,
+}
+
+impl RemoteContainer {
+    fn from_config(name: &str, value: &str) -> Self {
+        match value.split_once('@') {
+            Some((global, url)) if !global.contains(['/', ':']) => RemoteContainer {
+                global: global.to_string(),
+                url: url.to_string(),
+            },
+            _ => RemoteContainer {
+                global: name.to_string(),
+                url: value.to_string(),
+            },
+        }
+    }
+}
+
+#[turbo_tasks::value(transparent)]
+pub struct RemoteContainers(IndexMap<String, RemoteContainer>);
+
+#[turbo_tasks::value(transparent)]
+pub struct DataModuleFormats(IndexMap<String, DataModuleFormat>);
+
+#[turbo_tasks::value(transparent)]
+pub struct RouteExperiments(Vec<RouteExperiment>);
+
 #[derive(Default)]
 #[turbo_tasks::value(transparent)]
 pub struct WebpackExtensionToLoaders(IndexMap<String, WebpackLoaderConfigItemsVc>);
@@ -491,11 +816,65 @@ impl NextConfigVc {
         Ok(StringsVc::cell(self.await?.page_extensions.clone()))
     }
 
+    #[turbo_tasks::function]
+    pub async fn required_env(self) -> Result<StringsVc> {
+        Ok(StringsVc::cell(
+            self.await?
+                .experimental
+                .required_env
+                .clone()
+                .unwrap_or_default(),
+        ))
+    }
+
+    #[turbo_tasks::function]
+    pub async fn probe_remote_images(self) -> Result<BoolVc> {
+        Ok(BoolVc::cell(
+            self.await?
+                .experimental
+                .probe_remote_images
+                .unwrap_or(false),
+        ))
+    }
+
     #[turbo_tasks::function]
     pub async fn rewrites(self) -> Result<RewritesVc> {
         Ok(self.await?.rewrites.clone().cell())
     }
 
+    #[turbo_tasks::function]
+    pub async fn static_generation_options(self) -> Result<StaticGenerationOptionsVc> {
+        let this = self.await?;
+        Ok(StaticGenerationOptions {
+            concurrency: this
+                .experimental
+                .cpus
+                .map_or_else(StaticGenerationOptions::default_concurrency, |cpus| {
+                    cpus as usize
+                }),
+            timeout_secs: if this.static_page_generation_timeout > 0.0 {
+                this.static_page_generation_timeout
+            } else {
+                DEFAULT_STATIC_PAGE_GENERATION_TIMEOUT
+            },
+        }
+        .cell())
+    }
+
+    #[turbo_tasks::function]
+    pub async fn entry_compilation_options(self) -> Result<EntryCompilationOptionsVc> {
+        Ok(EntryCompilationOptions {
+            concurrency: self
+                .await?
+                .experimental
+                .turbo
+                .as_ref()
+                .and_then(|t| t.entry_concurrency)
+                .unwrap_or_else(EntryCompilationOptions::default_concurrency),
+        }
+        .cell())
+    }
+
     #[turbo_tasks::function]
     pub async fn transpile_packages(self) -> Result<StringsVc> {
         Ok(StringsVc::cell(
@@ -506,16 +885,26 @@ impl NextConfigVc {
     #[turbo_tasks::function]
     pub async fn webpack_loaders_options(self) -> Result<WebpackExtensionToLoadersVc> {
         let this = self.await?;
-        let Some(turbo_loaders) = this.experimental.turbo.as_ref().and_then(|t| t.loaders.as_ref()) else {
+        let Some(turbo) = this.experimental.turbo.as_ref() else {
             return Ok(WebpackExtensionToLoadersVc::cell(IndexMap::new()));
         };
         let mut extension_to_loaders = IndexMap::new();
-        for (ext, loaders) in turbo_loaders {
+        for (ext, loaders) in turbo.loaders.iter().flatten() {
             extension_to_loaders.insert(
                 ext.clone(),
                 WebpackLoaderConfigItemsVc::cell(loaders.0.clone()),
             );
         }
+        // Rules matching a file extension run like `loaders`. The other rules
+        // are reported when the config is loaded.
+        for (glob, rule) in turbo.rules.iter().flatten() {
+            if let Ok(ext) = rule.extension(glob) {
+                extension_to_loaders.insert(
+                    ext.to_string(),
+                    WebpackLoaderConfigItemsVc::cell(rule.loaders().0.clone()),
+                );
+            }
+        }
         Ok(WebpackExtensionToLoaders(extension_to_loaders).cell())
     }
 
@@ -525,9 +914,187 @@ impl NextConfigVc {
         let Some(resolve_alias) = this.experimental.turbo.as_ref().and_then(|t| t.resolve_alias.as_ref()) else {
             return Ok(ResolveAliasMapVc::cell(ResolveAliasMap::default()));
         };
-        let alias_map: ResolveAliasMap = resolve_alias.try_into()?;
+        let resolve_alias: IndexMap<String, JsonValue> = resolve_alias
+            .iter()
+            .map(|(alias, value)| (alias.clone(), replace_false_aliases(value)))
+            .collect();
+        let alias_map: ResolveAliasMap = (&resolve_alias).try_into()?;
         Ok(alias_map.cell())
     }
+
+    #[turbo_tasks::function]
+    pub async fn data_module_formats(self) -> Result<DataModuleFormatsVc> {
+        let this = self.await?;
+        let mut formats: IndexMap<String, DataModuleFormat> = [
+            (".yaml".to_string(), DataModuleFormat::Yaml),
+            (".yml".to_string(), DataModuleFormat::Yaml),
+            (".toml".to_string(), DataModuleFormat::Toml),
+        ]
+        .into_iter()
+        .collect();
+        if let Some(data_modules) = this
+            .experimental
+            .turbo
+            .as_ref()
+            .and_then(|t| t.data_modules.as_ref())
+        {
+            formats.extend(data_modules.clone());
+        }
+        Ok(DataModuleFormatsVc::cell(formats))
+    }
+
+    #[turbo_tasks::function]
+    pub async fn service_worker_entry(self) -> Result<OptionStringVc> {
+        Ok(OptionStringVc::cell(
+            self.await?
+                .experimental
+                .turbo
+                .as_ref()
+                .and_then(|t| t.service_worker.clone()),
+        ))
+    }
+
+    #[turbo_tasks::function]
+    pub async fn html_post_processors(self) -> Result<StringsVc> {
+        Ok(StringsVc::cell(
+            self.await?
+                .experimental
+                .turbo
+                .as_ref()
+                .and_then(|t| t.html_post_processors.clone())
+                .unwrap_or_default(),
+        ))
+    }
+
+    #[turbo_tasks::function]
+    pub async fn route_experiments(self) -> Result<RouteExperimentsVc> {
+        Ok(RouteExperimentsVc::cell(
+            self.await?
+                .experimental
+                .turbo
+                .as_ref()
+                .and_then(|t| t.experiments.clone())
+                .unwrap_or_default(),
+        ))
+    }
+
+    #[turbo_tasks::function]
+    pub async fn builtin_middleware(self) -> Result<BuiltinMiddlewareConfigVc> {
+        Ok(self
+            .await?
+            .experimental
+            .turbo
+            .as_ref()
+            .and_then(|t| t.builtin_middleware.clone())
+            .unwrap_or_default()
+            .cell())
+    }
+
+    #[turbo_tasks::function]
+    pub async fn health_check(self) -> Result<OptionHealthCheckConfigVc> {
+        Ok(OptionHealthCheckConfigVc::cell(
+            self.await?
+                .experimental
+                .turbo
+                .as_ref()
+                .and_then(|t| t.health_check.clone()),
+        ))
+    }
+
+    #[turbo_tasks::function]
+    pub async fn route_types(self) -> Result<BoolVc> {
+        Ok(BoolVc::cell(
+            self.await?
+                .experimental
+                .turbo
+                .as_ref()
+                .and_then(|t| t.route_types)
+                .unwrap_or(false),
+        ))
+    }
+
+    #[turbo_tasks::function]
+    pub async fn trailing_slash(self) -> Result<BoolVc> {
+        Ok(BoolVc::cell(self.await?.trailing_slash))
+    }
+
+    #[turbo_tasks::function]
+    pub async fn base_path(self) -> Result<StringVc> {
+        Ok(StringVc::cell(self.await?.base_path.clone()))
+    }
+
+    /// Whether the stylesheets of production builds are minified, which is
+    /// the default.
+    #[turbo_tasks::function]
+    pub async fn css_minify(self) -> Result<BoolVc> {
+        Ok(BoolVc::cell(
+            self.await?
+                .compiler
+                .as_ref()
+                .and_then(|c| c.css_minify)
+                .unwrap_or(true),
+        ))
+    }
+
+    #[turbo_tasks::function]
+    pub async fn css_modules_local_ident_name(self) -> Result<OptionStringVc> {
+        Ok(OptionStringVc::cell(
+            self.await?
+                .experimental
+                .turbo
+                .as_ref()
+                .and_then(|t| t.css_modules.as_ref())
+                .and_then(|c| c.local_ident_name.clone()),
+        ))
+    }
+
+    /// The defines of `experimental.turbo.defines` for a mode.
+    #[turbo_tasks::function]
+    pub async fn defines(self, mode: Value<NextMode>) -> Result<CompileTimeDefinesVc> {
+        let this = self.await?;
+        let Some(defines) = this
+            .experimental
+            .turbo
+            .as_ref()
+            .and_then(|t| t.defines.as_ref())
+        else {
+            return Ok(CompileTimeDefines(IndexMap::new()).cell());
+        };
+        Ok(CompileTimeDefines(
+            defines
+                .values(mode.into_value())
+                .into_iter()
+                .map(|(name, value)| (name, value.into()))
+                .collect(),
+        )
+        .cell())
+    }
+
+    #[turbo_tasks::function]
+    pub async fn optimize_deps(self) -> Result<StringsVc> {
+        Ok(StringsVc::cell(
+            self.await?
+                .experimental
+                .turbo
+                .as_ref()
+                .and_then(|t| t.optimize_deps.clone())
+                .unwrap_or_default(),
+        ))
+    }
+
+    #[turbo_tasks::function]
+    pub async fn remote_containers(self) -> Result<RemoteContainersVc> {
+        let this = self.await?;
+        let Some(remotes) = this.experimental.turbo.as_ref().and_then(|t| t.remotes.as_ref()) else {
+            return Ok(RemoteContainersVc::cell(IndexMap::new()));
+        };
+        Ok(RemoteContainersVc::cell(
+            remotes
+                .iter()
+                .map(|(name, value)| (name.clone(), RemoteContainer::from_config(name, value)))
+                .collect(),
+        ))
+    }
 }
 
 fn next_configs() -> StringsVc {
@@ -556,6 +1123,16 @@ pub async fn load_next_config(execution_context: ExecutionContextVc) -> Result<N
 pub async fn load_next_config_internal(
     execution_context: ExecutionContextVc,
     config_file: Option<FileSystemPathVc>,
+) -> Result<NextConfigVc> {
+    let span = tracing::info_span!("evaluate", layer = "next_config");
+    evaluate_next_config(execution_context, config_file)
+        .instrument(span)
+        .await
+}
+
+async fn evaluate_next_config(
+    execution_context: ExecutionContextVc,
+    config_file: Option<FileSystemPathVc>,
 ) -> Result<NextConfigVc> {
     let ExecutionContext {
         project_path,
@@ -605,6 +1182,10 @@ pub async fn load_next_config_internal(
     match &*config_value {
         JavaScriptValue::Value(val) => {
             let next_config: NextConfig = parse_json_rope_with_source_context(val)?;
+            if let Some(config_file) = config_file {
+                emit_ignored_options_issues(&next_config, config_file);
+                emit_webpack_rules_issues(&next_config, config_file);
+            }
             let next_config = next_config.cell();
 
             Ok(next_config)
@@ -616,6 +1197,199 @@ pub async fn load_next_config_internal(
     }
 }
 
+/// Returns whether an option which is unset, `null` or `false` by default has
+/// been set.
+fn is_set(value: &Option<JsonValue>) -> bool {
+    !matches!(value, None | Some(JsonValue::Null | JsonValue::Bool(false)))
+}
+
+impl NextConfig {
+    /// Returns the options which are set, but have no effect with Turbopack, as
+    /// `(group, option, hint)`.
+    fn ignored_options(&self) -> Vec<(&'static str, &'static str, Option<&'static str>)> {
+        let mut options = Vec::new();
+
+        if is_set(&self.webpack) {
+            options.push((
+                "webpack",
+                "webpack",
+                Some(
+                    "Configure webpack loaders with `experimental.turbo.rules` and aliases with \
+                     `experimental.turbo.resolveAlias` instead.",
+                ),
+            ));
+        }
+
+        if let Some(compiler) = &self.compiler {
+            if compiler.relay.is_some() {
+                options.push((
+                    "compiler",
+                    "compiler.relay",
+                    Some("Add `babel-plugin-relay` to a .babelrc instead."),
+                ));
+            }
+            if is_set(&compiler.styled_components) {
+                options.push((
+                    "compiler",
+                    "compiler.styledComponents",
+                    Some("Add `babel-plugin-styled-components` to a .babelrc instead."),
+                ));
+            }
+            if is_set(&compiler.emotion) {
+                options.push((
+                    "compiler",
+                    "compiler.emotion",
+                    Some("Add `@emotion/babel-plugin` to a .babelrc instead."),
+                ));
+            }
+        }
+
+        let experimental = &self.experimental;
+        if is_set(&experimental.swc_plugins) {
+            options.push((
+                "experimental",
+                "experimental.swcPlugins",
+                Some("Use the equivalent Babel plugins in a .babelrc instead."),
+            ));
+        }
+        if is_set(&experimental.mdx_rs) {
+            options.push((
+                "experimental",
+                "experimental.mdxRs",
+                Some("Add `@mdx-js/loader` to `experimental.turbo.rules` instead."),
+            ));
+        }
+        if is_set(&experimental.url_imports) {
+            options.push((
+                "experimental",
+                "experimental.urlImports",
+                Some("Install the imported modules as dependencies instead."),
+            ));
+        }
+        if is_set(&experimental.optimize_css) {
+            options.push(("experimental", "experimental.optimizeCss", None));
+        }
+        if is_set(&experimental.sri) {
+            options.push(("experimental", "experimental.sri", None));
+        }
+        if !matches!(
+            experimental.esm_externals,
+            None | Some(JsonValue::Bool(true))
+        ) {
+            options.push(("experimental", "experimental.esmExternals", None));
+        }
+
+        if is_set(&self.modularize_imports) || is_set(&experimental.modularize_imports) {
+            options.push((
+                "options",
+                "modularizeImports",
+                Some("Import from the modules directly instead."),
+            ));
+        }
+        if !self.sass_options.is_empty() {
+            options.push(("options", "sassOptions", None));
+        }
+
+        options
+    }
+}
+
+/// Emits an issue for every group of options which are set in the config, but
+/// are ignored by Turbopack.
+fn emit_ignored_options_issues(next_config: &NextConfig, config_file: FileSystemPathVc) {
+    let mut groups: IndexMap<&str, Vec<(String, Option<String>)>> = IndexMap::new();
+    for (group, option, hint) in next_config.ignored_options() {
+        groups
+            .entry(group)
+            .or_default()
+            .push((option.to_string(), hint.map(ToString::to_string)));
+    }
+    for (group, options) in groups {
+        IgnoredConfigOptionsIssue {
+            path: config_file,
+            group: group.to_string(),
+            options,
+        }
+        .cell()
+        .as_issue()
+        .emit();
+    }
+}
+
+/// Emits an issue for every `experimental.turbo.rules` rule which can't run.
+fn emit_webpack_rules_issues(next_config: &NextConfig, config_file: FileSystemPathVc) {
+    let Some(rules) = next_config.experimental.turbo.as_ref().and_then(|t| t.rules.as_ref()) else {
+        return;
+    };
+    for (glob, rule) in rules {
+        if let Err(detail) = rule.extension(glob) {
+            WebpackRulesConfigIssue {
+                path: config_file,
+                detail,
+            }
+            .cell()
+            .as_issue()
+            .emit();
+        }
+    }
+}
+
+/// Options of the next.js config which are set, but have no effect with
+/// Turbopack.
+#[turbo_tasks::value(shared)]
+struct IgnoredConfigOptionsIssue {
+    path: FileSystemPathVc,
+    group: String,
+    /// The ignored options and how to migrate them.
+    options: Vec<(String, Option<String>)>,
+}
+
+#[turbo_tasks::value_impl]
+impl Issue for IgnoredConfigOptionsIssue {
+    #[turbo_tasks::function]
+    fn severity(&self) -> IssueSeverityVc {
+        IssueSeverity::Warning.into()
+    }
+
+    #[turbo_tasks::function]
+    fn title(&self) -> StringVc {
+        StringVc::cell(match self.group.as_str() {
+            "webpack" => "The webpack config is ignored by Turbopack".to_string(),
+            "compiler" => "Compiler options are not supported by Turbopack yet".to_string(),
+            "experimental" => "Experimental options are not supported by Turbopack yet".to_string(),
+            _ => "Config options are not supported by Turbopack yet".to_string(),
+        })
+    }
+
+    #[turbo_tasks::function]
+    fn category(&self) -> StringVc {
+        StringVc::cell("config".to_string())
+    }
+
+    #[turbo_tasks::function]
+    fn context(&self) -> FileSystemPathVc {
+        self.path
+    }
+
+    #[turbo_tasks::function]
+    fn description(&self) -> StringVc {
+        StringVc::cell("The following options have no effect:".to_string())
+    }
+
+    #[turbo_tasks::function]
+    fn detail(&self) -> StringVc {
+        let mut detail = String::new();
+        for (option, hint) in self.options.iter() {
+            detail.push_str(&format!("- `{option}`"));
+            if let Some(hint) = hint {
+                detail.push_str(&format!(": {hint}"));
+            }
+            detail.push('\n');
+        }
+        StringVc::cell(detail)
+    }
+}
+
 #[turbo_tasks::function]
 pub async fn has_next_config(context: FileSystemPathVc) -> Result<BoolVc> {
     Ok(BoolVc::cell(!matches!(
@@ -623,3 +1397,60 @@ pub async fn has_next_config(context: FileSystemPathVc) -> Result<BoolVc> {
         FindContextFileResult::NotFound(_)
     )))
 }
+
+/// Like webpack's `resolve.alias`, an alias to `false` (possibly for a single
+/// condition, e.g. `{ browser: false }`) resolves to an empty module.
+fn replace_false_aliases(value: &JsonValue) -> JsonValue {
+    match value {
+        JsonValue::Bool(false) => {
+            JsonValue::String(format!("{VIRTUAL_PACKAGE_NAME}/internal/empty"))
+        }
+        JsonValue::Object(conditions) => JsonValue::Object(
+            conditions
+                .iter()
+                .map(|(condition, value)| (condition.clone(), replace_false_aliases(value)))
+                .collect(),
+        ),
+        _ => value.clone(),
+    }
+}
+
+/// A rule of `experimental.turbo.rules` which is skipped.
+#[turbo_tasks::value(shared)]
+struct WebpackRulesConfigIssue {
+    path: FileSystemPathVc,
+    detail: String,
+}
+
+#[turbo_tasks::value_impl]
+impl Issue for WebpackRulesConfigIssue {
+    #[turbo_tasks::function]
+    fn severity(&self) -> IssueSeverityVc {
+        IssueSeverity::Warning.into()
+    }
+
+    #[turbo_tasks::function]
+    fn title(&self) -> StringVc {
+        StringVc::cell("Unsupported rule in experimental.turbo.rules".to_string())
+    }
+
+    #[turbo_tasks::function]
+    fn category(&self) -> StringVc {
+        StringVc::cell("config".to_string())
+    }
+
+    #[turbo_tasks::function]
+    fn context(&self) -> FileSystemPathVc {
+        self.path
+    }
+
+    #[turbo_tasks::function]
+    fn description(&self) -> StringVc {
+        StringVc::cell("The rule has been skipped.".to_string())
+    }
+
+    #[turbo_tasks::function]
+    fn detail(&self) -> StringVc {
+        StringVc::cell(self.detail.clone())
+    }
+}
@@ -3,7 +3,6 @@ use turbo_tasks::Value;
 use turbo_tasks_fs::FileSystemPathVc;
 use turbopack::resolve_options_context::{ResolveOptionsContext, ResolveOptionsContextVc};
 use turbopack_core::{
-    compile_time_defines,
     compile_time_info::{CompileTimeDefinesVc, CompileTimeInfo, CompileTimeInfoVc},
     environment::{
         EdgeWorkerEnvironment, EnvironmentIntention, EnvironmentVc, ExecutionEnvironment,
@@ -13,23 +12,35 @@ use turbopack_core::{
 use turbopack_node::execution_context::ExecutionContextVc;
 
 use crate::{
-    next_config::NextConfigVc, next_import_map::get_next_edge_import_map,
-    next_server::context::ServerContextType, util::foreign_code_context_condition,
+    mode::NextMode,
+    next_config::NextConfigVc,
+    next_import_map::{get_next_edge_fallback_import_map, get_next_edge_import_map},
+    next_server::context::ServerContextType,
+    next_shared::{
+        defines::{next_config_defines, NextRuntime},
+        resolve::ImportQueryResolvePluginVc,
+    },
+    util::foreign_code_context_condition,
 };
 
-pub fn next_edge_defines() -> CompileTimeDefinesVc {
-    compile_time_defines!(
-        process.turbopack = true,
-        process.env.NODE_ENV = "development",
-        process.env.__NEXT_CLIENT_ROUTER_FILTER_ENABLED = false
+#[turbo_tasks::function]
+pub async fn next_edge_defines(
+    mode: Value<NextMode>,
+    next_config: NextConfigVc,
+) -> Result<CompileTimeDefinesVc> {
+    Ok(
+        next_config_defines(NextRuntime::Edge, mode.into_value(), next_config)
+            .await?
+            .cell(),
     )
-    .cell()
 }
 
 #[turbo_tasks::function]
 pub fn get_edge_compile_time_info(
     server_addr: ServerAddrVc,
     intention: Value<EnvironmentIntention>,
+    mode: Value<NextMode>,
+    next_config: NextConfigVc,
 ) -> CompileTimeInfoVc {
     CompileTimeInfo {
         environment: EnvironmentVc::new(
@@ -38,7 +49,7 @@ pub fn get_edge_compile_time_info(
             )),
             intention,
         ),
-        defines: next_edge_defines(),
+        defines: next_edge_defines(mode, next_config),
     }
     .cell()
 }
@@ -57,7 +68,9 @@ pub async fn get_edge_resolve_options_context(
         enable_node_modules: Some(project_path.root().resolve().await?),
         custom_conditions: vec!["worker".to_string(), "development".to_string()],
         import_map: Some(next_edge_import_map),
+        fallback_import_map: Some(get_next_edge_fallback_import_map(project_path, next_config)),
         module: true,
+        plugins: vec![ImportQueryResolvePluginVc::new(project_path).into()],
         ..Default::default()
     };
 
@@ -0,0 +1,212 @@
+use anyhow::{bail, Result};
+use indexmap::{IndexMap, IndexSet};
+use serde::Serialize;
+use turbo_tasks_fs::{File, FileContent, FileSystemPathVc};
+use turbopack_core::{
+    asset::{Asset, AssetContent, AssetContentVc, AssetVc},
+    ident::AssetIdentVc,
+    reference::{all_referenced_assets, AssetReferencesVc},
+};
+
+use crate::{
+    next_config::NextConfigVc,
+    util::{path_to_regexp, NextSourceConfigVc},
+};
+
+/// The path of the middleware manifest, relative to the output root.
+pub const MIDDLEWARE_MANIFEST: &str = "server/middleware-manifest.json";
+
+/// The `middleware-manifest.json` of the middleware, in the format `next
+/// build` writes it, which the server of Next.js and hosting adapters read to
+/// run the middleware in an edge runtime.
+///
+/// The files of the manifest are relative to `output_root`, like they are
+/// relative to the `distDir` with webpack.
+#[turbo_tasks::value(shared)]
+pub struct MiddlewareManifestAsset {
+    pub output_root: FileSystemPathVc,
+    /// The chunk group the [super::transition::NextEdgeTransition] created
+    /// for the middleware, or `None` without middleware.
+    pub chunk_group: Option<AssetVc>,
+    pub config: NextSourceConfigVc,
+    pub next_config: NextConfigVc,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct MiddlewareManifest {
+    version: u32,
+    middleware: IndexMap<String, EdgeFunctionDefinition>,
+    functions: IndexMap<String, EdgeFunctionDefinition>,
+    sorted_middleware: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct EdgeFunctionDefinition {
+    env: IndexMap<String, String>,
+    files: Vec<String>,
+    name: String,
+    page: String,
+    matchers: Vec<MiddlewareMatcher>,
+    wasm: Vec<AssetBinding>,
+    assets: Vec<AssetBinding>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct MiddlewareMatcher {
+    regexp: String,
+    original_source: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AssetBinding {
+    name: String,
+    file_path: String,
+}
+
+#[turbo_tasks::value_impl]
+impl Asset for MiddlewareManifestAsset {
+    #[turbo_tasks::function]
+    fn ident(&self) -> AssetIdentVc {
+        AssetIdentVc::from_path(self.output_root.join(MIDDLEWARE_MANIFEST))
+    }
+
+    #[turbo_tasks::function]
+    async fn content(&self) -> Result<AssetContentVc> {
+        let mut manifest = MiddlewareManifest {
+            version: 2,
+            middleware: IndexMap::new(),
+            functions: IndexMap::new(),
+            sorted_middleware: vec![],
+        };
+
+        if let Some(chunk_group) = self.chunk_group {
+            let mut files = vec![];
+            let mut wasm = vec![];
+            let mut assets = vec![];
+            for path in output_files(self.output_root, chunk_group).await? {
+                let file_name = path.rsplit('/').next().unwrap_or(&path);
+                if path.ends_with(".js") || path.ends_with(".mjs") || path.ends_with(".cjs") {
+                    files.push(path);
+                } else if path.ends_with(".wasm") {
+                    wasm.push(AssetBinding {
+                        name: wasm_binding_name(file_name),
+                        file_path: path,
+                    });
+                } else if !path.ends_with(".map") {
+                    assets.push(AssetBinding {
+                        name: file_name.to_string(),
+                        file_path: path,
+                    });
+                }
+            }
+
+            let base_path = self.next_config.base_path().await?;
+            let matchers = match &self.config.await?.matcher {
+                Some(sources) => sources
+                    .iter()
+                    .map(|source| {
+                        Ok(MiddlewareMatcher {
+                            regexp: matcher_regexp(&base_path, source)?,
+                            original_source: source.clone(),
+                        })
+                    })
+                    .collect::<Result<Vec<_>>>()?,
+                // Without a matcher, the middleware runs for every request.
+                None => vec![MiddlewareMatcher {
+                    regexp: ".*".to_string(),
+                    original_source: "/:path*".to_string(),
+                }],
+            };
+
+            manifest.middleware.insert(
+                "/".to_string(),
+                EdgeFunctionDefinition {
+                    env: self.next_config.env().await?.clone_value(),
+                    files,
+                    name: "middleware".to_string(),
+                    page: "/".to_string(),
+                    matchers,
+                    wasm,
+                    assets,
+                },
+            );
+            manifest.sorted_middleware.push("/".to_string());
+        }
+
+        let mut json = serde_json::to_string_pretty(&manifest)?;
+        json.push('\n');
+        Ok(AssetContent::File(FileContent::Content(File::from(json)).cell()).cell())
+    }
+
+    #[turbo_tasks::function]
+    fn references(&self) -> AssetReferencesVc {
+        AssetReferencesVc::empty()
+    }
+}
+
+/// Returns the paths of the output assets referenced by a chunk group, which
+/// are in `output_root`, relative to it. The chunks are in the order they are
+/// referenced, which is the order they are evaluated in.
+async fn output_files(output_root: FileSystemPathVc, chunk_group: AssetVc) -> Result<Vec<String>> {
+    let output_root = output_root.await?;
+    let mut files = IndexSet::new();
+    let mut stack = vec![chunk_group];
+    let mut visited = IndexSet::new();
+    while let Some(asset) = stack.pop() {
+        if !visited.insert(asset) {
+            continue;
+        }
+        let path = asset.ident().path().await?;
+        if let Some(path) = output_root.get_path_to(&path) {
+            files.insert(path.to_string());
+        } else if asset != chunk_group {
+            // Only the output of the chunk group is part of the middleware.
+            continue;
+        }
+        stack.extend(all_referenced_assets(asset).await?.iter().rev().copied());
+    }
+    Ok(files.into_iter().collect())
+}
+
+/// The name of the global the edge runtime binds a wasm file to, like
+/// `wasmBindingName` in `internal/edge-wasm.ts`.
+fn wasm_binding_name(file_name: &str) -> String {
+    let name: String = file_name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '_' || c == '$' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!("wasm_{name}")
+}
+
+/// Returns the regular expression of a middleware matcher, like
+/// `getMiddlewareMatchers` of Next.js does. The matcher also matches the
+/// `_next/data` requests of the pages it matches.
+fn matcher_regexp(base_path: &str, source: &str) -> Result<String> {
+    if !source.starts_with('/') {
+        bail!("middleware matcher {source} must start with /");
+    }
+    let is_root = source == "/";
+    let source = format!(
+        "/:nextData(_next/data/[^/]{{1,}})?{base_path}{}{}",
+        if is_root && !base_path.is_empty() {
+            ""
+        } else {
+            source
+        },
+        if is_root {
+            "(/?index|/?index\\.json)?"
+        } else {
+            "(.json)?"
+        }
+    );
+    Ok(format!("^{}[\\/#\\?]?$", path_to_regexp(&source, "/#?")?))
+}
@@ -1,2 +1,5 @@
 pub mod context;
+pub mod middleware_manifest;
+pub(crate) mod node_compat;
 pub mod transition;
+pub(crate) mod wasm;
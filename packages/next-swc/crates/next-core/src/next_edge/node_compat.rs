@@ -0,0 +1,212 @@
+use anyhow::Result;
+use indoc::formatdoc;
+use turbo_tasks::primitives::StringVc;
+use turbo_tasks_fs::{FileContent, FileSystemPathVc};
+use turbopack_core::{
+    issue::{Issue, IssueSeverity, IssueSeverityVc, IssueVc},
+    resolve::{
+        options::{
+            ImportMapResult, ImportMapResultVc, ImportMapping, ImportMappingReplacement,
+            ImportMappingReplacementVc, ImportMappingVc,
+        },
+        parse::RequestVc,
+        ResolveResult,
+    },
+    virtual_asset::VirtualAssetVc,
+};
+
+use crate::{embed_js::next_js_file_path, next_config::EdgeNodeCompat};
+
+/// Node.js builtins which are available in the edge runtime through edge-safe
+/// shims, with the request of their shim.
+const EDGE_NODE_BUILTIN_SHIMS: [(&str, &str); 3] = [
+    (
+        "async_hooks",
+        "@vercel/turbopack-next/internal/node-compat/async_hooks",
+    ),
+    ("buffer", "next/dist/compiled/buffer"),
+    ("util", "next/dist/compiled/util"),
+];
+
+/// The Node.js builtin modules, see `require("module").builtinModules`.
+const NODE_BUILTINS: [&str; 42] = [
+    "assert",
+    "async_hooks",
+    "buffer",
+    "child_process",
+    "cluster",
+    "console",
+    "constants",
+    "crypto",
+    "dgram",
+    "diagnostics_channel",
+    "dns",
+    "domain",
+    "events",
+    "fs",
+    "http",
+    "http2",
+    "https",
+    "inspector",
+    "module",
+    "net",
+    "os",
+    "path",
+    "perf_hooks",
+    "process",
+    "punycode",
+    "querystring",
+    "readline",
+    "repl",
+    "stream",
+    "string_decoder",
+    "sys",
+    "timers",
+    "tls",
+    "trace_events",
+    "tty",
+    "url",
+    "util",
+    "v8",
+    "vm",
+    "wasi",
+    "worker_threads",
+    "zlib",
+];
+
+impl EdgeNodeCompat {
+    /// Returns the builtins which are shimmed in this mode.
+    pub(crate) fn shims(&self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            EdgeNodeCompat::Shims => &EDGE_NODE_BUILTIN_SHIMS,
+            EdgeNodeCompat::Strict => &[],
+        }
+    }
+
+    /// Returns the builtins which are reported as unsupported in this mode.
+    pub(crate) fn unsupported_builtins(&self) -> impl Iterator<Item = &'static str> {
+        let shims = self.shims();
+        NODE_BUILTINS
+            .into_iter()
+            .filter(move |builtin| !shims.iter().any(|(shimmed, _)| shimmed == builtin))
+    }
+}
+
+/// Replaces a Node.js builtin which is not available in the edge runtime.
+///
+/// Instead of failing inside the middleware sandbox at runtime, an issue is
+/// reported for the import. The issue is emitted while resolving the import,
+/// so its processing path is the import trace of the builtin. The builtin is
+/// replaced by a module which throws when it is used.
+#[turbo_tasks::value(shared)]
+pub(crate) struct EdgeNodeBuiltinReplacer {
+    project_path: FileSystemPathVc,
+    builtin: String,
+    strict: bool,
+}
+
+#[turbo_tasks::value_impl]
+impl EdgeNodeBuiltinReplacerVc {
+    #[turbo_tasks::function]
+    pub fn new(project_path: FileSystemPathVc, builtin: String, strict: bool) -> Self {
+        Self::cell(EdgeNodeBuiltinReplacer {
+            project_path,
+            builtin,
+            strict,
+        })
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl ImportMappingReplacement for EdgeNodeBuiltinReplacer {
+    #[turbo_tasks::function]
+    fn replace(&self, _capture: &str) -> ImportMappingVc {
+        ImportMapping::Ignore.into()
+    }
+
+    #[turbo_tasks::function]
+    async fn result(&self, _request: RequestVc) -> Result<ImportMapResultVc> {
+        EdgeNodeBuiltinIssue {
+            path: self.project_path,
+            builtin: self.builtin.clone(),
+            strict: self.strict,
+        }
+        .cell()
+        .as_issue()
+        .emit();
+
+        let js_asset = VirtualAssetVc::new(
+            next_js_file_path("internal/node-compat/unsupported")
+                .join(&format!("{}.js", self.builtin)),
+            FileContent::Content(
+                formatdoc!(
+                    r#"
+                        const {{ unsupportedModule }} = require("@vercel/turbopack-next/internal/node-compat/unsupported");
+
+                        module.exports = unsupportedModule({});
+                    "#,
+                    serde_json::to_string(&self.builtin)?,
+                )
+                .into(),
+            )
+            .into(),
+        );
+
+        Ok(ImportMapResult::Result(ResolveResult::asset(js_asset.into()).into()).into())
+    }
+}
+
+#[turbo_tasks::value(shared)]
+pub(crate) struct EdgeNodeBuiltinIssue {
+    path: FileSystemPathVc,
+    builtin: String,
+    strict: bool,
+}
+
+#[turbo_tasks::value_impl]
+impl Issue for EdgeNodeBuiltinIssue {
+    #[turbo_tasks::function]
+    fn severity(&self) -> IssueSeverityVc {
+        IssueSeverity::Error.into()
+    }
+
+    #[turbo_tasks::function]
+    fn category(&self) -> StringVc {
+        StringVc::cell("resolve".to_string())
+    }
+
+    #[turbo_tasks::function]
+    fn context(&self) -> FileSystemPathVc {
+        self.path
+    }
+
+    #[turbo_tasks::function]
+    fn title(&self) -> StringVc {
+        StringVc::cell(format!(
+            "The Node.js module `{}` is not supported in the Edge Runtime",
+            self.builtin
+        ))
+    }
+
+    #[turbo_tasks::function]
+    fn description(&self) -> StringVc {
+        let supported = if self.strict {
+            "Node.js modules are not available in the Edge Runtime, as \
+             `experimental.turbo.edgeNodeCompat` is set to `strict`."
+                .to_string()
+        } else {
+            format!(
+                "Only {} are available in the Edge Runtime.",
+                EDGE_NODE_BUILTIN_SHIMS
+                    .iter()
+                    .map(|(builtin, _)| format!("`{builtin}`"))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )
+        };
+        StringVc::cell(format!(
+            "{supported} Use the Node.js runtime for code which depends on `{}`.",
+            self.builtin
+        ))
+    }
+}
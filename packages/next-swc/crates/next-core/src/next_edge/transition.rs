@@ -20,6 +20,8 @@ use turbopack_ecmascript::{
     EcmascriptInputTransformsVc, EcmascriptModuleAssetType, EcmascriptModuleAssetVc, InnerAssetsVc,
 };
 
+use super::wasm::get_edge_module_options_context;
+
 #[turbo_tasks::value(shared)]
 pub struct NextEdgeTransition {
     pub edge_compile_time_info: CompileTimeInfoVc,
@@ -47,7 +49,7 @@ impl Transition for NextEdgeTransition {
         &self,
         context: ModuleOptionsContextVc,
     ) -> ModuleOptionsContextVc {
-        self.edge_module_options_context.unwrap_or(context)
+        get_edge_module_options_context(self.edge_module_options_context.unwrap_or(context))
     }
 
     #[turbo_tasks::function]
@@ -0,0 +1,104 @@
+use anyhow::Result;
+use indoc::formatdoc;
+use turbo_tasks_fs::{File, FileContent};
+use turbopack::module_options::{
+    ModuleOptionsContextVc, ModuleRule, ModuleRuleCondition, ModuleRuleEffect, ModuleType,
+};
+use turbopack_core::{
+    asset::{Asset, AssetContent, AssetContentVc, AssetVc},
+    ident::AssetIdentVc,
+    reference::AssetReferencesVc,
+    reference_type::{ReferenceType, UrlReferenceSubType},
+    source_transform::{SourceTransform, SourceTransformsVc},
+};
+use turbopack_ecmascript::{utils::StringifyJs, EcmascriptInputTransformsVc};
+
+/// Adds the `.wasm` module rule to the module options of an edge context.
+#[turbo_tasks::function]
+pub async fn get_edge_module_options_context(
+    context: ModuleOptionsContextVc,
+) -> Result<ModuleOptionsContextVc> {
+    let mut context = (*context.await?).clone();
+    context.custom_rules.push(get_edge_wasm_module_rule());
+    Ok(context.cell())
+}
+
+/// Returns a rule which turns `.wasm` imports, e.g. `import wasm from
+/// "./add.wasm?module"`, into the compiled `WebAssembly.Module`.
+///
+/// The edge runtime can't compile WebAssembly from code, so the wasm file is
+/// emitted as an output asset next to the edge chunks. The sandbox compiles it
+/// before the chunks are evaluated, and binds it to a global named after the
+/// file, which the generated module exports.
+fn get_edge_wasm_module_rule() -> ModuleRule {
+    ModuleRule::new(
+        ModuleRuleCondition::all(vec![
+            // The `new URL(...)` reference emits the wasm file, see
+            // `EdgeWasmModuleAsset`.
+            ModuleRuleCondition::not(ModuleRuleCondition::ReferenceType(ReferenceType::Url(
+                UrlReferenceSubType::Undefined,
+            ))),
+            ModuleRuleCondition::ResourcePathEndsWith(".wasm".to_string()),
+        ]),
+        vec![
+            ModuleRuleEffect::ModuleType(ModuleType::Ecmascript(
+                EcmascriptInputTransformsVc::cell(vec![]),
+            )),
+            ModuleRuleEffect::SourceTransforms(SourceTransformsVc::cell(vec![
+                EdgeWasmTransformVc::new().into(),
+            ])),
+        ],
+    )
+}
+
+/// Converts a wasm file to an ES module exporting its `WebAssembly.Module`.
+#[turbo_tasks::value]
+struct EdgeWasmTransform;
+
+#[turbo_tasks::value_impl]
+impl EdgeWasmTransformVc {
+    #[turbo_tasks::function]
+    fn new() -> Self {
+        EdgeWasmTransform.cell()
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl SourceTransform for EdgeWasmTransform {
+    #[turbo_tasks::function]
+    fn transform(&self, source: AssetVc) -> AssetVc {
+        EdgeWasmModuleAsset { source }.cell().into()
+    }
+}
+
+#[turbo_tasks::value]
+struct EdgeWasmModuleAsset {
+    source: AssetVc,
+}
+
+#[turbo_tasks::value_impl]
+impl Asset for EdgeWasmModuleAsset {
+    #[turbo_tasks::function]
+    fn ident(&self) -> AssetIdentVc {
+        self.source.ident()
+    }
+
+    #[turbo_tasks::function]
+    async fn content(&self) -> Result<AssetContentVc> {
+        let file_name = self.source.ident().path().await?.file_name().to_string();
+        let code = formatdoc!(
+            r#"
+                import {{ wasmBinding }} from "@vercel/turbopack-next/internal/edge-wasm";
+
+                export default wasmBinding(new URL({}, import.meta.url));
+            "#,
+            StringifyJs(&format!("./{file_name}"))
+        );
+        Ok(AssetContent::File(FileContent::Content(File::from(code)).cell()).cell())
+    }
+
+    #[turbo_tasks::function]
+    fn references(&self) -> AssetReferencesVc {
+        AssetReferencesVc::empty()
+    }
+}
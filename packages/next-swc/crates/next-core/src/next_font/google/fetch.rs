@@ -0,0 +1,86 @@
+use anyhow::Result;
+use turbo_tasks::primitives::{OptionStringVc, StringVc};
+use turbo_tasks_fetch::fetch;
+use turbo_tasks_fs::{File, FileContent, FileContentVc, FileSystemPathVc};
+use turbo_tasks_hash::hash_xxh3_hash64;
+use turbopack_core::issue::{IssueSeverity, IssueVc};
+
+use crate::next_font::issue::NextFontIssue;
+
+/// Where stylesheets and font files from Google Fonts are cached, relative to
+/// the project directory.
+const CACHE_DIR: &str = ".next/cache/google-fonts";
+
+/// Google Fonts serves the stylesheet for the formats the browser supports.
+/// This one gets `woff2` files, which all supported browsers can load.
+const USER_AGENT: &str = "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36 \
+                          (KHTML, like Gecko) Chrome/104.0.0.0 Safari/537.36";
+
+/// Fetches a stylesheet or font file from Google Fonts. A copy cached by an
+/// earlier build is preferred, so builds work offline once they have
+/// succeeded online. When neither is available, a warning is emitted at
+/// `issue_context` and `NotFound` is returned, so pages still render with the
+/// fallback font.
+#[turbo_tasks::function]
+pub(super) async fn fetch_from_google_fonts(
+    project_path: FileSystemPathVc,
+    url: StringVc,
+    issue_context: FileSystemPathVc,
+) -> Result<FileContentVc> {
+    let cache_path = project_path.join(&format!("{CACHE_DIR}/{}", cache_file_name(&url.await?)));
+    if let FileContent::Content(_) = &*cache_path.read().await? {
+        return Ok(cache_path.read());
+    }
+
+    let response = fetch(url, OptionStringVc::cell(Some(USER_AGENT.to_string()))).await?;
+    match &*response {
+        Ok(response) => {
+            let response = response.await?;
+            if response.status != 200 {
+                NextFontIssue {
+                    path: issue_context,
+                    title: StringVc::cell(format!(
+                        "Failed to download `{}` from Google Fonts",
+                        url.await?
+                    )),
+                    description: StringVc::cell(format!(
+                        "Google Fonts responded with status {}. The fallback font is used instead.",
+                        response.status
+                    )),
+                    severity: IssueSeverity::Warning.into(),
+                }
+                .cell()
+                .as_issue()
+                .emit();
+                return Ok(FileContent::NotFound.cell());
+            }
+            let body = response.body.await?;
+            let content = FileContent::Content(File::from(body.0.clone()));
+            cache_path.write(content.clone().cell());
+            Ok(content.cell())
+        }
+        Err(err) => {
+            // Inform the user of the failure to retrieve the resource, but don't
+            // propagate this error. We don't want e.g. offline connections to prevent
+            // page renders during development.
+            //
+            // TODO(WEB-293): Fail production builds (not dev) in this case
+            err.to_issue(IssueSeverity::Warning.into(), issue_context)
+                .as_issue()
+                .emit();
+            Ok(FileContent::NotFound.cell())
+        }
+    }
+}
+
+/// The name of a cached resource: a hash of its URL, with the extension of the
+/// URL, or `.css` for stylesheets.
+pub(super) fn cache_file_name(url: &str) -> String {
+    let path = url.split(['?', '#']).next().unwrap_or_default();
+    let file_name = path.rsplit('/').next().unwrap_or_default();
+    let extension = match file_name.rsplit_once('.') {
+        Some((_, extension)) if !extension.is_empty() => extension,
+        _ => "css",
+    };
+    format!("{:016x}.{extension}", hash_xxh3_hash64(url.as_bytes()))
+}
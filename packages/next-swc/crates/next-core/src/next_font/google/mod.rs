@@ -2,11 +2,12 @@ use anyhow::{bail, Context, Result};
 use indexmap::IndexMap;
 use indoc::formatdoc;
 use turbo_tasks::{
-    primitives::{OptionStringVc, StringVc, U32Vc},
+    primitives::{OptionStringVc, StringVc, StringsVc, U32Vc},
     Value,
 };
 use turbo_tasks_fs::{json::parse_json_with_source_context, FileContent, FileSystemPathVc};
 use turbopack_core::{
+    issue::{IssueSeverity, IssueVc},
     resolve::{
         options::{
             ImportMapResult, ImportMapResultVc, ImportMapping, ImportMappingReplacement,
@@ -18,16 +19,19 @@ use turbopack_core::{
     },
     virtual_asset::VirtualAssetVc,
 };
+use turbopack_ecmascript::utils::StringifyJs;
 use turbopack_node::execution_context::ExecutionContextVc;
 
 use self::{
+    fetch::{cache_file_name, fetch_from_google_fonts},
     font_fallback::get_font_fallback,
     options::{options_from_request, FontDataEntry, FontWeights, NextFontGoogleOptionsVc},
     stylesheet::build_stylesheet,
-    util::{get_font_axes, get_stylesheet_url},
+    util::{get_font_axes, get_stylesheet_url, get_subset_font_files, with_text_param},
 };
 use super::{
     font_fallback::{FontFallback, FontFallbackVc},
+    issue::NextFontIssue,
     util::{
         get_request_hash, get_request_id, get_scoped_font_family, FontCssProperties,
         FontCssPropertiesVc, FontFamilyType,
@@ -35,6 +39,7 @@ use super::{
 };
 use crate::{embed_js::next_js_file_path, util::load_next_json};
 
+pub mod fetch;
 pub mod font_fallback;
 pub mod options;
 pub mod request;
@@ -43,6 +48,10 @@ pub mod util;
 
 pub const GOOGLE_FONTS_STYLESHEET_URL: &str = "https://fonts.googleapis.com/css2";
 
+/// The request the stylesheets use for self-hosted font files, with the URL of
+/// the font file on Google Fonts as `url` query.
+pub const GOOGLE_FONTS_FONT_FILE_REQUEST: &str = "@vercel/turbopack-next/internal/font/google/font";
+
 #[turbo_tasks::value(transparent)]
 struct FontData(IndexMap<String, FontDataEntry>);
 
@@ -83,13 +92,34 @@ impl ImportMappingReplacement for NextFontGoogleReplacer {
         let request_hash = get_request_hash(*query_vc);
         let fallback = get_font_fallback(self.project_path, options, request_hash);
         let properties = get_font_css_properties(options, fallback, request_hash).await?;
+        let js_path = next_js_file_path("internal/font/google").join(&format!(
+            "{}.js",
+            get_request_id(options.font_family(), request_hash).await?
+        ));
+        let preloads = get_font_preloads(self.project_path, options, font_data, js_path).await?;
+        let preload_code = if preloads.is_empty() {
+            String::new()
+        } else {
+            format!(
+                "import {{ registerFontPreloads }} from \
+                 \"@vercel/turbopack-next/internal/font/preload\";\nregisterFontPreloads([{}]);\n",
+                preloads
+                    .iter()
+                    .map(|request| format!(
+                        "new URL({}, import.meta.url).pathname",
+                        StringifyJs(request)
+                    ))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )
+        };
         let js_asset = VirtualAssetVc::new(
-                next_js_file_path("internal/font/google")
-                    .join(&format!("{}.js", get_request_id(options.font_family(), request_hash).await?)),
+                js_path,
                 FileContent::Content(
                     formatdoc!(
                         r#"
                             import cssModule from "@vercel/turbopack-next/internal/font/google/cssmodule.module.css?{}";
+                            {}
                             const fontData = {{
                                 className: cssModule.className,
                                 style: {{
@@ -106,6 +136,7 @@ impl ImportMappingReplacement for NextFontGoogleReplacer {
                         "#,
                         // Pass along whichever options we received to the css handler
                         qstring::QString::new(query.as_ref().unwrap().iter().collect()),
+                        preload_code,
                         properties.font_family.await?,
                         properties
                             .weight
@@ -188,44 +219,37 @@ impl ImportMappingReplacement for NextFontGoogleCssModuleReplacer {
 
         #[cfg(not(feature = "__internal_nextjs_integration_test"))]
         let stylesheet_str = {
-            use turbo_tasks_fetch::fetch;
-            use turbopack_core::issue::IssueSeverity;
-
-            let stylesheet_res = fetch(
-                stylesheet_url,
-                OptionStringVc::cell(Some(
-                    "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36 (KHTML, \
-                     like Gecko) Chrome/104.0.0.0 Safari/537.36"
-                        .to_owned(),
-                )),
-            )
-            .await?;
+            use crate::inputs_manifest::NetworkBuildInput;
 
+            let stylesheet_res =
+                fetch_from_google_fonts(self.project_path, stylesheet_url, css_virtual_path)
+                    .await?;
             match &*stylesheet_res {
-                Ok(r) => Some(r.await?.body.to_string()),
-                Err(err) => {
-                    // Inform the user of the failure to retreive the stylesheet, but don't
-                    // propagate this error. We don't want e.g. offline connections to prevent page
-                    // renders during development. During production builds, however, this error
-                    // should propagate.
-                    //
-                    // TODO(WEB-283): Use fallback in dev in this case
-                    // TODO(WEB-293): Fail production builds (not dev) in this case
-                    err.to_issue(IssueSeverity::Warning.into(), css_virtual_path)
-                        .as_issue()
-                        .emit();
-
-                    None
+                FileContent::Content(file) => {
+                    let content = StringVc::cell(file.content().to_str()?.into_owned());
+                    NetworkBuildInput {
+                        url: stylesheet_url,
+                        content,
+                    }
+                    .cell()
+                    .as_build_input()
+                    .emit();
+                    Some(content)
                 }
+                FileContent::NotFound => None,
             }
         };
 
         let stylesheet = match stylesheet_str {
-            Some(s) => Some(
-                update_google_stylesheet(s, options, scoped_font_family)
-                    .await?
-                    .clone_value(),
-            ),
+            Some(s) => {
+                let stylesheet = update_google_stylesheet(s, options, scoped_font_family);
+                // The mocked stylesheets of integration tests refer to fonts which can't be
+                // downloaded.
+                #[cfg(not(feature = "__internal_nextjs_integration_test"))]
+                let stylesheet =
+                    self_host_font_files(stylesheet, self.project_path, css_virtual_path);
+                Some(stylesheet.await?.clone_value())
+            }
             None => None,
         };
 
@@ -248,6 +272,58 @@ impl ImportMappingReplacement for NextFontGoogleCssModuleReplacer {
     }
 }
 
+/// Resolves the font files referenced by the stylesheets of
+/// `next/font/google` to the downloaded copies, so they are self-hosted.
+#[turbo_tasks::value(shared)]
+pub struct NextFontGoogleFontFileReplacer {
+    project_path: FileSystemPathVc,
+}
+
+#[turbo_tasks::value_impl]
+impl NextFontGoogleFontFileReplacerVc {
+    #[turbo_tasks::function]
+    pub fn new(project_path: FileSystemPathVc) -> Self {
+        Self::cell(NextFontGoogleFontFileReplacer { project_path })
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl ImportMappingReplacement for NextFontGoogleFontFileReplacer {
+    #[turbo_tasks::function]
+    fn replace(&self, _capture: &str) -> ImportMappingVc {
+        ImportMapping::Ignore.into()
+    }
+
+    #[turbo_tasks::function]
+    async fn result(&self, request: RequestVc) -> Result<ImportMapResultVc> {
+        let request = &*request.await?;
+        let Request::Module {
+            module: _,
+            path: _,
+            query: query_vc,
+        } = request else {
+            return Ok(ImportMapResult::NoEntry.into());
+        };
+        let query = &*query_vc.await?;
+        let Some(url) = query.as_ref().and_then(|query| query.get("url")) else {
+            return Ok(ImportMapResult::NoEntry.into());
+        };
+
+        let font_directory = next_js_file_path("internal/font/google");
+        let content = fetch_from_google_fonts(
+            self.project_path,
+            StringVc::cell(url.clone()),
+            font_directory,
+        );
+        if let FileContent::NotFound = &*content.await? {
+            return Ok(ImportMapResult::NoEntry.into());
+        }
+        let font_asset = VirtualAssetVc::new(font_directory.join(&cache_file_name(&url)), content);
+
+        Ok(ImportMapResult::Result(ResolveResult::asset(font_asset.into()).into()).into())
+    }
+}
+
 #[turbo_tasks::function]
 async fn load_font_data(project_root: FileSystemPathVc) -> Result<FontDataVc> {
     let data: FontData = load_next_json(
@@ -273,6 +349,106 @@ async fn update_google_stylesheet(
     )))
 }
 
+/// Replaces the URLs of font files in a stylesheet with requests resolved by
+/// [NextFontGoogleFontFileReplacer]. Fonts which can't be downloaded keep
+/// their URL on Google Fonts.
+#[cfg(not(feature = "__internal_nextjs_integration_test"))]
+#[turbo_tasks::function]
+async fn self_host_font_files(
+    stylesheet: StringVc,
+    project_path: FileSystemPathVc,
+    issue_context: FileSystemPathVc,
+) -> Result<StringVc> {
+    use indexmap::IndexSet;
+    use once_cell::sync::Lazy;
+    use regex::Regex;
+
+    static FONT_URL: Lazy<Regex> =
+        Lazy::new(|| Regex::new(r"url\((https://fonts\.gstatic\.com/[^)\s]+)\)").unwrap());
+
+    let mut stylesheet = stylesheet.await?.clone_value();
+    let urls = FONT_URL
+        .captures_iter(&stylesheet)
+        .map(|captures| captures[1].to_string())
+        .collect::<IndexSet<_>>();
+    for url in urls {
+        let content =
+            fetch_from_google_fonts(project_path, StringVc::cell(url.clone()), issue_context);
+        if let FileContent::NotFound = &*content.await? {
+            continue;
+        }
+        stylesheet = stylesheet.replace(
+            &format!("url({url})"),
+            &format!("url({})", font_file_request(&url)),
+        );
+    }
+    Ok(StringVc::cell(stylesheet))
+}
+
+/// The request of a self-hosted font file, see
+/// [NextFontGoogleFontFileReplacer].
+fn font_file_request(url: &str) -> String {
+    format!(
+        "{GOOGLE_FONTS_FONT_FILE_REQUEST}?{}",
+        qstring::QString::new(vec![("url", url)])
+    )
+}
+
+/// Returns the requests of the self-hosted font files to preload: the files of
+/// the `subsets` of the font, like the webpack font loader of Next.js.
+#[turbo_tasks::function]
+async fn get_font_preloads(
+    project_path: FileSystemPathVc,
+    options: NextFontGoogleOptionsVc,
+    font_data: FontDataVc,
+    issue_context: FileSystemPathVc,
+) -> Result<StringsVc> {
+    let options_value = options.await?;
+    // The mocked stylesheets of integration tests refer to fonts which can't be
+    // downloaded.
+    if !options_value.preload || cfg!(feature = "__internal_nextjs_integration_test") {
+        return Ok(StringsVc::cell(vec![]));
+    }
+    let Some(subsets) = &options_value.subsets else {
+        NextFontIssue {
+            path: issue_context,
+            title: StringVc::cell(format!(
+                "Preloading `{}` requires its subsets",
+                options_value.font_family
+            )),
+            description: StringVc::cell(
+                "Specify the subsets to preload, e.g. `subsets: ['latin']`, or disable preloading \
+                 with `preload: false`."
+                    .to_string(),
+            ),
+            severity: IssueSeverity::Warning.into(),
+        }
+        .cell()
+        .as_issue()
+        .emit();
+        return Ok(StringsVc::cell(vec![]));
+    };
+
+    let stylesheet = fetch_from_google_fonts(
+        project_path,
+        get_stylesheet_url_from_options(options, font_data),
+        issue_context,
+    )
+    .await?;
+    let FileContent::Content(stylesheet) = &*stylesheet else {
+        return Ok(StringsVc::cell(vec![]));
+    };
+    let mut requests = Vec::new();
+    for url in get_subset_font_files(&stylesheet.content().to_str()?, subsets) {
+        let content =
+            fetch_from_google_fonts(project_path, StringVc::cell(url.clone()), issue_context);
+        if let FileContent::Content(_) = &*content.await? {
+            requests.push(font_file_request(&url));
+        }
+    }
+    Ok(StringsVc::cell(requests))
+}
+
 #[turbo_tasks::function]
 async fn get_stylesheet_url_from_options(
     options: NextFontGoogleOptionsVc,
@@ -291,7 +467,7 @@ async fn get_stylesheet_url_from_options(
     }
 
     let options = options.await?;
-    Ok(StringVc::cell(get_stylesheet_url(
+    let stylesheet_url = get_stylesheet_url(
         css_url.as_deref().unwrap_or(GOOGLE_FONTS_STYLESHEET_URL),
         &options.font_family,
         &get_font_axes(
@@ -302,7 +478,12 @@ async fn get_stylesheet_url_from_options(
             &options.selected_variable_axes,
         )?,
         &options.display,
-    )?))
+    )?;
+    Ok(StringVc::cell(match &options.text {
+        // Google Fonts subsets the font files to the glyphs of `text`.
+        Some(text) => with_text_param(stylesheet_url, text),
+        None => stylesheet_url,
+    }))
 }
 
 #[turbo_tasks::function]
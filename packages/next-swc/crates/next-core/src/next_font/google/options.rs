@@ -3,7 +3,10 @@ use indexmap::{indexset, IndexMap, IndexSet};
 use serde::{Deserialize, Serialize};
 use turbo_tasks::{primitives::StringVc, trace::TraceRawVcs, Value};
 
-use super::request::{NextFontRequest, OneOrManyStrings};
+use super::{
+    request::{NextFontRequest, OneOrManyStrings},
+    util::get_subset_text,
+};
 
 const ALLOWED_DISPLAY_VALUES: &[&str] = &["auto", "block", "swap", "fallback", "optional"];
 
@@ -25,6 +28,10 @@ pub(super) struct NextFontGoogleOptions {
     /// the font family when used.
     pub variable: Option<String>,
     pub subsets: Option<Vec<String>>,
+    /// The characters the font is used for, deduplicated and sorted. When set,
+    /// only the glyphs for these characters are requested, which results in
+    /// much smaller font files for e.g. numeric or display-only fonts.
+    pub text: Option<String>,
 }
 
 #[turbo_tasks::value_impl]
@@ -191,6 +198,10 @@ pub(super) fn options_from_request(
         adjust_font_fallback: argument.map(|a| a.adjust_font_fallback).unwrap_or(true),
         variable: argument.and_then(|a| a.variable.clone()),
         subsets: argument.and_then(|a| a.subsets.clone()),
+        text: argument
+            .and_then(|a| a.text.as_deref())
+            .map(get_subset_text)
+            .filter(|text| !text.is_empty()),
     })
 }
 
@@ -273,6 +284,7 @@ mod tests {
                 adjust_font_fallback: true,
                 variable: None,
                 subsets: None,
+                text: None,
             },
         );
 
@@ -23,6 +23,7 @@ pub(super) struct NextFontRequestArguments {
     #[serde(default = "default_adjust_font_fallback")]
     pub adjust_font_fallback: bool,
     pub variable: Option<String>,
+    pub text: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
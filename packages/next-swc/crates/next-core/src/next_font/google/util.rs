@@ -2,6 +2,8 @@ use std::cmp::Ordering;
 
 use anyhow::{anyhow, bail, Context, Result};
 use indexmap::{indexset, IndexSet};
+use once_cell::sync::Lazy;
+use regex::Regex;
 
 use super::options::{FontData, FontWeights};
 
@@ -227,6 +229,56 @@ pub(super) fn get_stylesheet_url(
     }
 }
 
+/// Returns the distinct characters of `text`, sorted, so fonts used for the
+/// same characters share their stylesheet regardless of their order.
+/// Whitespace is dropped as it has no glyphs.
+pub(super) fn get_subset_text(text: &str) -> String {
+    let mut chars = text
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect::<Vec<char>>();
+    chars.sort_unstable();
+    chars.dedup();
+    chars.into_iter().collect()
+}
+
+/// Appends the `text` param to a stylesheet url, restricting the font files
+/// it references to the glyphs of the given characters.
+pub(super) fn with_text_param(stylesheet_url: String, text: &str) -> String {
+    let mut url = stylesheet_url;
+    url.push_str("&text=");
+    for byte in text.bytes() {
+        if byte.is_ascii_alphanumeric() || b"-_.~".contains(&byte) {
+            url.push(byte as char);
+        } else {
+            url.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    url
+}
+
+/// Returns the URLs of the font files of the given subsets in a stylesheet
+/// from Google Fonts. Each `@font-face` rule is preceded by a comment naming
+/// its subset, e.g. `/* latin */`, and restricted to the subset with
+/// `unicode-range`, so browsers only download the files of the subsets a page
+/// renders.
+pub(super) fn get_subset_font_files(stylesheet: &str, subsets: &[String]) -> Vec<String> {
+    static FONT_FACE: Lazy<Regex> =
+        Lazy::new(|| Regex::new(r"/\*\s*([\w-]+)\s*\*/\s*@font-face\s*\{([^}]*)\}").unwrap());
+    static FONT_URL: Lazy<Regex> = Lazy::new(|| Regex::new(r"url\(([^)\s]+)\)").unwrap());
+
+    let mut files = IndexSet::new();
+    for font_face in FONT_FACE.captures_iter(stylesheet) {
+        if !subsets.iter().any(|subset| subset == &font_face[1]) {
+            continue;
+        }
+        for url in FONT_URL.captures_iter(&font_face[2]) {
+            files.insert(url[1].to_string());
+        }
+    }
+    files.into_iter().collect()
+}
+
 #[cfg(test)]
 mod tests {
     use anyhow::Result;
@@ -236,7 +288,10 @@ mod tests {
     use super::get_font_axes;
     use crate::next_font::google::{
         options::{FontData, FontWeights},
-        util::{get_stylesheet_url, FontAxes, FontStyle},
+        util::{
+            get_stylesheet_url, get_subset_font_files, get_subset_text, with_text_param, FontAxes,
+            FontStyle,
+        },
         GOOGLE_FONTS_STYLESHEET_URL,
     };
 
@@ -548,4 +603,55 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_subset_text_dedupes_and_sorts() {
+        assert_eq!(get_subset_text("1 2 3 2 1,0"), ",0123");
+        assert_eq!(get_subset_text("  "), "");
+    }
+
+    #[test]
+    fn test_stylesheet_url_with_text() -> Result<()> {
+        assert_eq!(
+            with_text_param(
+                get_stylesheet_url(
+                    GOOGLE_FONTS_STYLESHEET_URL,
+                    "Roboto Mono",
+                    &FontAxes {
+                        wght: indexset! {"500".to_owned()},
+                        ital: indexset! {},
+                        variable_axes: None
+                    },
+                    "swap"
+                )?,
+                &get_subset_text("€ 9,99"),
+            ),
+            "https://fonts.googleapis.com/css2?family=Roboto+Mono:wght@500&display=swap&text=%2C9%E2%82%AC"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_subset_font_files() {
+        let stylesheet = r#"
+/* cyrillic */
+@font-face {
+  font-family: 'Inter';
+  src: url(https://fonts.gstatic.com/s/inter/v12/cyrillic.woff2) format('woff2');
+  unicode-range: U+0301, U+0400-045F, U+0490-0491, U+04B0-04B1, U+2116;
+}
+/* latin */
+@font-face {
+  font-family: 'Inter';
+  src: url(https://fonts.gstatic.com/s/inter/v12/latin.woff2) format('woff2');
+  unicode-range: U+0000-00FF, U+0131, U+0152-0153, U+02BB-02BC, U+02C6, U+02DA, U+02DC;
+}
+"#;
+        assert_eq!(
+            get_subset_font_files(stylesheet, &["latin".to_owned()]),
+            vec!["https://fonts.gstatic.com/s/inter/v12/latin.woff2".to_owned()]
+        );
+        assert!(get_subset_font_files(stylesheet, &["greek".to_owned()]).is_empty());
+    }
 }
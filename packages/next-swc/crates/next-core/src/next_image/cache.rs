@@ -0,0 +1,316 @@
+use std::{
+    collections::HashMap,
+    fs,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::UNIX_EPOCH,
+};
+
+use anyhow::{Context, Result};
+use turbo_tasks_hash::hash_xxh3_hash64;
+
+/// The default size limit of the image cache, in bytes.
+pub const DEFAULT_IMAGE_CACHE_MAX_SIZE: u64 = 256 * 1024 * 1024;
+
+/// Identifies an optimized image.
+pub struct ImageCacheKey<'a> {
+    /// The url of the source image.
+    pub url: &'a str,
+    /// The content of the source image, so the cached image is invalidated
+    /// when it changes.
+    pub source: &'a [u8],
+    pub width: u32,
+    pub quality: u8,
+    /// The file extension of the output format, e.g. `webp`.
+    pub extension: &'a str,
+}
+
+impl ImageCacheKey<'_> {
+    /// Identifies the variant of the image, independent of the content of the
+    /// source image.
+    fn variant(&self) -> String {
+        let variant = format!(
+            "{}\0{}\0{}\0{}",
+            self.url, self.width, self.quality, self.extension
+        );
+        format!("{:016x}", hash_xxh3_hash64(variant.as_bytes()))
+    }
+
+    fn source_hash(&self) -> String {
+        format!("{:016x}", hash_xxh3_hash64(self.source))
+    }
+}
+
+struct ImageCacheEntry {
+    source_hash: String,
+    file_name: String,
+    size: u64,
+    last_used: u64,
+}
+
+#[derive(Default)]
+struct ImageCacheState {
+    /// The cached images by variant. Only the image of the latest source is
+    /// kept for every variant.
+    entries: HashMap<String, ImageCacheEntry>,
+    size: u64,
+    /// Increases with every access, to order the entries by recency.
+    clock: u64,
+}
+
+/// Caches optimized images on disk, so they aren't re-encoded after a
+/// restart. When the cache grows beyond its size limit, the least recently
+/// used images are removed.
+///
+/// Files are named `{variant}-{source hash}.{extension}`, so the cache can be
+/// restored from the directory. Entries restored that way are ordered by their
+/// modification time.
+pub struct ImageCache {
+    dir: PathBuf,
+    max_size: u64,
+    state: Mutex<ImageCacheState>,
+}
+
+impl ImageCache {
+    /// Opens the cache in `dir`, e.g. `.next/cache/images`, restoring the
+    /// images cached by earlier runs.
+    pub fn new(dir: PathBuf, max_size: u64) -> Result<Self> {
+        fs::create_dir_all(&dir)
+            .with_context(|| format!("failed to create the image cache {}", dir.display()))?;
+
+        let mut files = Vec::new();
+        for entry in fs::read_dir(&dir)? {
+            let entry = entry?;
+            let metadata = entry.metadata()?;
+            if !metadata.is_file() {
+                continue;
+            }
+            let Ok(file_name) = entry.file_name().into_string() else {
+                continue;
+            };
+            let modified = metadata
+                .modified()
+                .ok()
+                .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+                .unwrap_or_default();
+            files.push((modified, file_name, metadata.len()));
+        }
+        files.sort();
+
+        let mut state = ImageCacheState::default();
+        for (_, file_name, size) in files {
+            let Some((variant, source_hash)) = file_name
+                .split_once('.')
+                .and_then(|(stem, _)| stem.split_once('-'))
+            else {
+                continue;
+            };
+            let (variant, source_hash) = (variant.to_string(), source_hash.to_string());
+            state.clock += 1;
+            let entry = ImageCacheEntry {
+                source_hash,
+                file_name,
+                size,
+                last_used: state.clock,
+            };
+            state.size += size;
+            if let Some(replaced) = state.entries.insert(variant, entry) {
+                state.size -= replaced.size;
+                remove_file(&dir.join(replaced.file_name))?;
+            }
+        }
+
+        let cache = Self {
+            dir,
+            max_size,
+            state: Mutex::new(state),
+        };
+        cache.evict(&mut cache.state.lock().unwrap())?;
+        Ok(cache)
+    }
+
+    /// Returns the cached image, unless it isn't cached or was cached for a
+    /// different source image.
+    pub fn get(&self, key: &ImageCacheKey) -> Result<Option<Vec<u8>>> {
+        let mut state = self.state.lock().unwrap();
+        state.clock += 1;
+        let clock = state.clock;
+        let Some(entry) = state.entries.get_mut(&key.variant()) else {
+            return Ok(None);
+        };
+        if entry.source_hash != key.source_hash() {
+            return Ok(None);
+        }
+        entry.last_used = clock;
+        match fs::read(self.dir.join(&entry.file_name)) {
+            Ok(content) => Ok(Some(content)),
+            // The file was removed by someone else.
+            Err(err) if err.kind() == ErrorKind::NotFound => {
+                let size = entry.size;
+                state.entries.remove(&key.variant());
+                state.size -= size;
+                Ok(None)
+            }
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Caches an optimized image, replacing the image cached for an earlier
+    /// version of the source image.
+    pub fn insert(&self, key: &ImageCacheKey, content: &[u8]) -> Result<()> {
+        let size = content.len() as u64;
+        if size > self.max_size {
+            return Ok(());
+        }
+        let variant = key.variant();
+        let source_hash = key.source_hash();
+        let file_name = format!("{variant}-{source_hash}.{}", key.extension);
+        fs::write(self.dir.join(&file_name), content)
+            .with_context(|| format!("failed to write {file_name} to the image cache"))?;
+
+        let mut state = self.state.lock().unwrap();
+        state.clock += 1;
+        let entry = ImageCacheEntry {
+            source_hash,
+            file_name: file_name.clone(),
+            size,
+            last_used: state.clock,
+        };
+        state.size += size;
+        if let Some(replaced) = state.entries.insert(variant, entry) {
+            state.size -= replaced.size;
+            if replaced.file_name != file_name {
+                remove_file(&self.dir.join(replaced.file_name))?;
+            }
+        }
+        self.evict(&mut state)
+    }
+
+    /// Removes the least recently used images until the cache fits its size
+    /// limit.
+    fn evict(&self, state: &mut ImageCacheState) -> Result<()> {
+        while state.size > self.max_size {
+            let Some(variant) = state
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(variant, _)| variant.clone())
+            else {
+                break;
+            };
+            let entry = state.entries.remove(&variant).unwrap();
+            state.size -= entry.size;
+            remove_file(&self.dir.join(entry.file_name))?;
+        }
+        Ok(())
+    }
+}
+
+fn remove_file(path: &Path) -> Result<()> {
+    match fs::remove_file(path) {
+        Err(err) if err.kind() != ErrorKind::NotFound => Err(err)
+            .with_context(|| format!("failed to remove {} from the image cache", path.display())),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use tempfile::TempDir;
+
+    use super::{ImageCache, ImageCacheKey};
+
+    fn key<'a>(url: &'a str, source: &'a [u8]) -> ImageCacheKey<'a> {
+        ImageCacheKey {
+            url,
+            source,
+            width: 640,
+            quality: 75,
+            extension: "webp",
+        }
+    }
+
+    fn file_count(dir: &TempDir) -> usize {
+        fs::read_dir(dir.path()).unwrap().count()
+    }
+
+    #[test]
+    fn test_restore() {
+        let dir = TempDir::new().unwrap();
+        let cache = ImageCache::new(dir.path().to_path_buf(), 1024).unwrap();
+        cache.insert(&key("/a.png", b"a"), b"optimized a").unwrap();
+        drop(cache);
+
+        let cache = ImageCache::new(dir.path().to_path_buf(), 1024).unwrap();
+        assert_eq!(
+            cache.get(&key("/a.png", b"a")).unwrap().as_deref(),
+            Some(&b"optimized a"[..])
+        );
+        assert_eq!(cache.get(&key("/b.png", b"b")).unwrap(), None);
+    }
+
+    #[test]
+    fn test_eviction_order() {
+        let dir = TempDir::new().unwrap();
+        let cache = ImageCache::new(dir.path().to_path_buf(), 10).unwrap();
+        cache.insert(&key("/a.png", b"a"), b"aaaa").unwrap();
+        cache.insert(&key("/b.png", b"b"), b"bbbb").unwrap();
+        // Using `a` makes `b` the least recently used image.
+        assert!(cache.get(&key("/a.png", b"a")).unwrap().is_some());
+        cache.insert(&key("/c.png", b"c"), b"cccc").unwrap();
+
+        assert!(cache.get(&key("/a.png", b"a")).unwrap().is_some());
+        assert_eq!(cache.get(&key("/b.png", b"b")).unwrap(), None);
+        assert!(cache.get(&key("/c.png", b"c")).unwrap().is_some());
+        assert_eq!(file_count(&dir), 2);
+    }
+
+    #[test]
+    fn test_images_larger_than_the_cache() {
+        let dir = TempDir::new().unwrap();
+        let cache = ImageCache::new(dir.path().to_path_buf(), 4).unwrap();
+        cache.insert(&key("/a.png", b"a"), b"aaaaa").unwrap();
+
+        assert_eq!(cache.get(&key("/a.png", b"a")).unwrap(), None);
+        assert_eq!(file_count(&dir), 0);
+    }
+
+    #[test]
+    fn test_replace_when_the_source_changes() {
+        let dir = TempDir::new().unwrap();
+        let cache = ImageCache::new(dir.path().to_path_buf(), 1024).unwrap();
+        cache
+            .insert(&key("/a.png", b"v1"), b"optimized v1")
+            .unwrap();
+        cache
+            .insert(&key("/a.png", b"v2"), b"optimized v2")
+            .unwrap();
+
+        assert_eq!(cache.get(&key("/a.png", b"v1")).unwrap(), None);
+        assert_eq!(
+            cache.get(&key("/a.png", b"v2")).unwrap().as_deref(),
+            Some(&b"optimized v2"[..])
+        );
+        assert_eq!(file_count(&dir), 1);
+    }
+
+    #[test]
+    fn test_file_missing_on_disk() {
+        let dir = TempDir::new().unwrap();
+        let cache = ImageCache::new(dir.path().to_path_buf(), 8).unwrap();
+        cache.insert(&key("/a.png", b"a"), b"aaaa").unwrap();
+        for entry in fs::read_dir(dir.path()).unwrap() {
+            fs::remove_file(entry.unwrap().path()).unwrap();
+        }
+
+        assert_eq!(cache.get(&key("/a.png", b"a")).unwrap(), None);
+        // The missing image no longer counts towards the size limit.
+        cache.insert(&key("/b.png", b"b"), b"bbbb").unwrap();
+        cache.insert(&key("/c.png", b"c"), b"cccc").unwrap();
+        assert!(cache.get(&key("/b.png", b"b")).unwrap().is_some());
+        assert!(cache.get(&key("/c.png", b"c")).unwrap().is_some());
+    }
+}
@@ -1,26 +1,65 @@
-use std::collections::BTreeSet;
+use std::{collections::BTreeSet, sync::Arc};
 
 use anyhow::Result;
-use turbo_tasks::{primitives::StringVc, Value};
-use turbopack_core::introspect::{Introspectable, IntrospectableVc};
+use turbo_tasks::{
+    primitives::{OptionStringVc, StringVc},
+    Value,
+};
+use turbo_tasks_fetch::fetch;
+use turbo_tasks_fs::FileSystemPathVc;
+use turbopack_core::{
+    environment::ServerAddrVc,
+    introspect::{Introspectable, IntrospectableVc},
+    issue::IssueVc,
+};
 use turbopack_dev_server::source::{
     query::QueryValue, ContentSource, ContentSourceContent, ContentSourceData,
-    ContentSourceDataFilter, ContentSourceDataVary, ContentSourceResultVc, ContentSourceVc,
-    NeededData, ProxyResult,
+    ContentSourceDataFilter, ContentSourceDataVary, ContentSourceResultVc, NeededData, ProxyResult,
 };
 
+use self::{
+    cache::{ImageCache, ImageCacheKey},
+    optimizer::{
+        can_optimize, etag, negotiate_format, optimize, validate_params, InvalidImageParams,
+    },
+    probe::image_format,
+    remote_patterns::RemoteImageNotAllowedIssue,
+};
+use crate::next_config::NextConfigVc;
+
+pub mod cache;
+pub mod optimizer;
+pub mod probe;
+pub mod remote_patterns;
+
 /// Serves, resizes, optimizes, and re-encodes images to be used with
 /// next/image.
-#[turbo_tasks::value(shared)]
+///
+/// Local images are fetched from the dev server on every request, so changes
+/// to them show up. Re-encoding is skipped when the image cache has an image
+/// for the same source content.
+#[turbo_tasks::value(serialization = "none", eq = "manual", cell = "new", into = "new")]
 pub struct NextImageContentSource {
-    asset_source: ContentSourceVc,
+    project_path: FileSystemPathVc,
+    next_config: NextConfigVc,
+    server_addr: ServerAddrVc,
+    #[turbo_tasks(debug_ignore, trace_ignore)]
+    cache: Option<Arc<ImageCache>>,
 }
 
-#[turbo_tasks::value_impl]
 impl NextImageContentSourceVc {
-    #[turbo_tasks::function]
-    pub fn new(asset_source: ContentSourceVc) -> NextImageContentSourceVc {
-        NextImageContentSource { asset_source }.cell()
+    pub fn new(
+        project_path: FileSystemPathVc,
+        next_config: NextConfigVc,
+        server_addr: ServerAddrVc,
+        cache: Option<Arc<ImageCache>>,
+    ) -> NextImageContentSourceVc {
+        Self::cell(NextImageContentSource {
+            project_path,
+            next_config,
+            server_addr,
+            cache,
+        })
     }
 }
 
@@ -34,56 +73,205 @@ impl ContentSource for NextImageContentSource {
     ) -> Result<ContentSourceResultVc> {
         let this = self_vc.await?;
 
-        let query = match &data.query {
-            None => {
-                let queries = [
-                    "url".to_string(),
-                    // TODO: support q and w queries.
-                ]
+        let (Some(query), Some(raw_headers)) = (&data.query, &data.raw_headers) else {
+            let queries = ["url".to_string(), "w".to_string(), "q".to_string()]
                 .iter()
                 .cloned()
                 .collect::<BTreeSet<_>>();
 
-                return Ok(ContentSourceResultVc::need_data(Value::new(NeededData {
-                    source: self_vc.into(),
-                    path: path.to_string(),
-                    vary: ContentSourceDataVary {
-                        url: true,
-                        query: Some(ContentSourceDataFilter::Subset(queries)),
-                        ..Default::default()
-                    },
-                })));
+            return Ok(ContentSourceResultVc::need_data(Value::new(NeededData {
+                source: self_vc.into(),
+                path: path.to_string(),
+                vary: ContentSourceDataVary {
+                    url: true,
+                    query: Some(ContentSourceDataFilter::Subset(queries)),
+                    // For the `accept` header.
+                    raw_headers: true,
+                    // Local images are fetched again on every request.
+                    cache_buster: true,
+                    ..Default::default()
+                },
+            })));
+        };
+
+        let param = |name: &str| match query.get(name) {
+            Some(QueryValue::String(value)) => Some(value.as_str()),
+            _ => None,
+        };
+        let config = this.next_config.image_config().await?;
+        let params = match validate_params(&config, param("url"), param("w"), param("q")) {
+            Ok(params) => params,
+            Err(invalid) => {
+                if let InvalidImageParams::NotAllowed { url, hostname } = &invalid {
+                    RemoteImageNotAllowedIssue {
+                        path: this.project_path.join("next.config.js"),
+                        url: url.clone(),
+                        hostname: hostname.clone(),
+                    }
+                    .cell()
+                    .as_issue()
+                    .emit();
+                }
+                return Ok(text_response(400, invalid.message()));
             }
-            Some(query) => query,
         };
 
-        let url = match query.get("url") {
-            Some(QueryValue::String(s)) => s,
-            _ => return Ok(ContentSourceResultVc::not_found()),
+        let source_url = if params.is_local() {
+            // The cache buster is part of the url, as fetches are cached for
+            // the session.
+            let separator = if params.url.contains('?') { '&' } else { '?' };
+            format!(
+                "{}{}{separator}__nextImageCacheBuster={}",
+                this.server_addr.await?.to_string()?.trim_end_matches('/'),
+                params.url,
+                data.cache_buster
+            )
+        } else {
+            params.url.clone()
         };
+        let response = fetch(StringVc::cell(source_url), OptionStringVc::cell(None)).await?;
+        let response = match &*response {
+            Ok(response) => response.await?,
+            Err(_) => {
+                return Ok(text_response(
+                    502,
+                    r#""url" parameter is valid but upstream response is invalid"#,
+                ))
+            }
+        };
+        if response.status != 200 {
+            return Ok(text_response(
+                if response.status == 404 { 404 } else { 502 },
+                r#""url" parameter is valid but upstream response is invalid"#,
+            ));
+        }
+        let source = response.body.await?;
+        let source = &*source.0;
 
-        // TODO: consume the assets, resize and reduce quality, re-encode into next-gen
-        // formats.
-        if let Some(path) = url.strip_prefix('/') {
-            return Ok(this.asset_source.get(path, Default::default()));
+        let Some((content_type, _)) = image_format(source) else {
+            return Ok(text_response(
+                400,
+                "The requested resource isn't a valid image.",
+            ));
+        };
+        if content_type == "image/svg+xml" && !config.dangerously_allow_svg {
+            return Ok(text_response(
+                400,
+                r#""url" parameter is valid but image type is not allowed"#,
+            ));
         }
 
-        // TODO: This should be downloaded by the server, and resized, etc.
-        Ok(ContentSourceResultVc::exact(
-            ContentSourceContent::HttpProxy(
-                ProxyResult {
-                    status: 302,
-                    headers: vec![("Location".to_string(), url.clone())],
-                    body: "".into(),
+        let accept = raw_headers
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case("accept"))
+            .map(|(_, value)| value.as_str());
+        let format = negotiate_format(&config, accept, content_type)
+            .filter(|_| can_optimize(content_type, source));
+        let Some(format) = format else {
+            // Images which can't be optimized are served as they are.
+            return Ok(image_response(
+                &config.content_security_policy,
+                config.minimum_cache_ttl,
+                content_type,
+                source.to_vec(),
+                None,
+            ));
+        };
+
+        let key = ImageCacheKey {
+            url: &params.url,
+            source,
+            width: params.width,
+            quality: params.quality,
+            extension: format.extension(),
+        };
+        let cached = match &this.cache {
+            Some(cache) => cache.get(&key)?,
+            None => None,
+        };
+        let (content, cache_status) = match cached {
+            Some(content) => (content, "HIT"),
+            None => {
+                let (width, quality) = (params.width, params.quality);
+                let owned_source = source.to_vec();
+                let content = tokio::task::spawn_blocking(move || {
+                    optimize(&owned_source, width, quality, format)
+                })
+                .await??;
+                if let Some(cache) = &this.cache {
+                    cache.insert(&key, &content)?;
                 }
-                .cell(),
-            )
-            .cell()
-            .into(),
+                (content, "MISS")
+            }
+        };
+        Ok(image_response(
+            &config.content_security_policy,
+            config.minimum_cache_ttl,
+            format.content_type(),
+            content,
+            Some(cache_status),
         ))
     }
 }
 
+fn image_response(
+    content_security_policy: &str,
+    max_age: u32,
+    content_type: &str,
+    content: Vec<u8>,
+    cache_status: Option<&str>,
+) -> ContentSourceResultVc {
+    let mut headers = vec![
+        ("content-type".to_string(), content_type.to_string()),
+        (
+            "cache-control".to_string(),
+            format!("public, max-age={max_age}, must-revalidate"),
+        ),
+        ("vary".to_string(), "Accept".to_string()),
+        ("etag".to_string(), etag(&content)),
+    ];
+    if !content_security_policy.is_empty() {
+        headers.push((
+            "content-security-policy".to_string(),
+            content_security_policy.to_string(),
+        ));
+    }
+    if let Some(cache_status) = cache_status {
+        headers.push(("x-nextjs-cache".to_string(), cache_status.to_string()));
+    }
+    proxy_response(200, headers, content)
+}
+
+fn text_response(status: u16, message: &str) -> ContentSourceResultVc {
+    proxy_response(
+        status,
+        vec![(
+            "content-type".to_string(),
+            "text/plain; charset=utf-8".to_string(),
+        )],
+        message.as_bytes().to_vec(),
+    )
+}
+
+fn proxy_response(
+    status: u16,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+) -> ContentSourceResultVc {
+    ContentSourceResultVc::exact(
+        ContentSourceContent::HttpProxy(
+            ProxyResult {
+                status,
+                headers,
+                body: body.into(),
+            }
+            .cell(),
+        )
+        .cell()
+        .into(),
+    )
+}
+
 #[turbo_tasks::value_impl]
 impl Introspectable for NextImageContentSource {
     #[turbo_tasks::function]
@@ -93,6 +281,6 @@ impl Introspectable for NextImageContentSource {
 
     #[turbo_tasks::function]
     fn details(&self) -> StringVc {
-        StringVc::cell("suports dynamic serving of any statically imported image".to_string())
+        StringVc::cell("resizes and re-encodes local and remote images for next/image".to_string())
     }
 }
@@ -0,0 +1,390 @@
+use std::collections::{BTreeMap, HashSet};
+
+use anyhow::Result;
+use swc_core::ecma::{
+    ast::{
+        CallExpr, Expr, ImportDecl, ImportSpecifier, KeyValueProp, Lit, Program, Prop, PropName,
+        PropOrSpread,
+    },
+    atoms::JsWord,
+    visit::{Visit, VisitWith},
+};
+use turbo_tasks::{
+    graph::{GraphTraversal, NonDeterministic},
+    primitives::{OptionStringVc, StringVc},
+};
+use turbo_tasks_fetch::fetch;
+use turbo_tasks_fs::FileSystemPathVc;
+use turbopack_core::{
+    asset::{Asset, AssetVc},
+    issue::{Issue, IssueSeverity, IssueSeverityVc, IssueVc},
+    reference::all_referenced_assets,
+};
+use turbopack_ecmascript::{parse::ParseResult, EcmascriptModuleAssetVc};
+
+/// Remote images larger than this are reported, as optimizing them is slow
+/// and they are rarely intended.
+pub const REMOTE_IMAGE_MAX_SIZE: u64 = 10 * 1024 * 1024;
+
+/// The intrinsic size and format of a remote image.
+#[turbo_tasks::value(shared)]
+#[derive(Debug)]
+pub struct RemoteImage {
+    pub content_type: String,
+    /// The size of the image in pixels. `None` for formats without an
+    /// intrinsic size, like SVG.
+    pub dimensions: Option<(u32, u32)>,
+    /// The size of the image in bytes.
+    pub size: u64,
+}
+
+#[turbo_tasks::value(transparent)]
+pub struct OptionRemoteImage(Option<RemoteImageVc>);
+
+/// Fetches a remote image to read its format and size. Failures are reported
+/// as issues on `context`. The result is cached for the session, so every
+/// image is only fetched once.
+#[turbo_tasks::function]
+pub async fn probe_remote_image(
+    url: StringVc,
+    context: FileSystemPathVc,
+) -> Result<OptionRemoteImageVc> {
+    let url_value = url.await?;
+    let emit = |severity: IssueSeverity, message: String| {
+        RemoteImageIssue {
+            severity: severity.into(),
+            path: context,
+            message: StringVc::cell(message),
+        }
+        .cell()
+        .as_issue()
+        .emit();
+    };
+
+    let response = fetch(url, OptionStringVc::cell(None)).await?;
+    let response = match &*response {
+        Ok(response) => response.await?,
+        Err(err) => {
+            err.to_issue(IssueSeverity::Warning.into(), context)
+                .as_issue()
+                .emit();
+            return Ok(OptionRemoteImageVc::cell(None));
+        }
+    };
+    if response.status != 200 {
+        emit(
+            IssueSeverity::Warning,
+            format!(
+                "The remote image {url_value} responded with status {}.",
+                response.status
+            ),
+        );
+        return Ok(OptionRemoteImageVc::cell(None));
+    }
+
+    let body = response.body.await?;
+    let size = body.0.len() as u64;
+    if size > REMOTE_IMAGE_MAX_SIZE {
+        emit(
+            IssueSeverity::Warning,
+            format!(
+                "The remote image {url_value} is {size} bytes, more than the limit of \
+                 {REMOTE_IMAGE_MAX_SIZE} bytes. Serve a smaller version of it, optimizing it on \
+                 every request is slow."
+            ),
+        );
+    }
+    let Some((content_type, dimensions)) = image_format(&body.0) else {
+        emit(
+            IssueSeverity::Warning,
+            format!("The remote image {url_value} isn't a supported image format."),
+        );
+        return Ok(OptionRemoteImageVc::cell(None));
+    };
+    Ok(OptionRemoteImageVc::cell(Some(
+        RemoteImage {
+            content_type: content_type.to_string(),
+            dimensions,
+            size,
+        }
+        .cell(),
+    )))
+}
+
+/// Detects the format of an image from its content, and reads its dimensions
+/// from the header.
+pub(crate) fn image_format(bytes: &[u8]) -> Option<(&'static str, Option<(u32, u32)>)> {
+    let be_u16 = |offset: usize| -> Option<u32> {
+        Some(u16::from_be_bytes(bytes.get(offset..offset + 2)?.try_into().ok()?) as u32)
+    };
+    let le_u16 = |offset: usize| -> Option<u32> {
+        Some(u16::from_le_bytes(bytes.get(offset..offset + 2)?.try_into().ok()?) as u32)
+    };
+    let be_u32 = |offset: usize| -> Option<u32> {
+        Some(u32::from_be_bytes(
+            bytes.get(offset..offset + 4)?.try_into().ok()?,
+        ))
+    };
+    let le_u24 = |offset: usize| -> Option<u32> {
+        let bytes = bytes.get(offset..offset + 3)?;
+        Some(bytes[0] as u32 | (bytes[1] as u32) << 8 | (bytes[2] as u32) << 16)
+    };
+
+    if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+        return Some(("image/png", be_u32(16).zip(be_u32(20))));
+    }
+    if bytes.starts_with(b"GIF87a") || bytes.starts_with(b"GIF89a") {
+        return Some(("image/gif", le_u16(6).zip(le_u16(8))));
+    }
+    if bytes.starts_with(b"RIFF") && bytes.get(8..12) == Some(b"WEBP") {
+        let dimensions = match bytes.get(12..16)? {
+            b"VP8 " => le_u16(26)
+                .zip(le_u16(28))
+                .map(|(w, h)| (w & 0x3fff, h & 0x3fff)),
+            b"VP8L" => {
+                let bits = u32::from_le_bytes(bytes.get(21..25)?.try_into().ok()?);
+                Some(((bits & 0x3fff) + 1, (bits >> 14 & 0x3fff) + 1))
+            }
+            b"VP8X" => le_u24(24).zip(le_u24(27)).map(|(w, h)| (w + 1, h + 1)),
+            _ => None,
+        };
+        return Some(("image/webp", dimensions));
+    }
+    if bytes.get(4..12) == Some(b"ftypavif") {
+        return Some(("image/avif", None));
+    }
+    if bytes.starts_with(b"\xff\xd8") {
+        // Walks the segments until a start of frame segment.
+        let mut offset = 2;
+        while let (Some(&0xff), Some(&marker)) = (bytes.get(offset), bytes.get(offset + 1)) {
+            let length = be_u16(offset + 2)? as usize;
+            if matches!(marker, 0xc0..=0xcf) && !matches!(marker, 0xc4 | 0xc8 | 0xcc) {
+                return Some(("image/jpeg", be_u16(offset + 7).zip(be_u16(offset + 5))));
+            }
+            offset += 2 + length;
+        }
+        return Some(("image/jpeg", None));
+    }
+    let start = String::from_utf8_lossy(&bytes[..bytes.len().min(1024)]);
+    if start.contains("<svg") {
+        return Some(("image/svg+xml", None));
+    }
+    None
+}
+
+/// A remote image rendered with `next/image`, e.g.
+/// `<Image src="https://example.com/a.png" width={100} height={50} />`.
+#[derive(Debug, PartialEq, Eq, Hash)]
+struct RemoteImageUsage {
+    src: String,
+    width: Option<u32>,
+    height: Option<u32>,
+    fill: bool,
+}
+
+/// Collects `next/image` elements with a literal remote `src`. JSX is already
+/// compiled when modules are analyzed, so the elements are calls like
+/// `jsx(Image, { src: "..." })` or `React.createElement(Image, { ... })`.
+#[derive(Default)]
+struct RemoteImageVisitor {
+    image_components: HashSet<JsWord>,
+    usages: Vec<RemoteImageUsage>,
+}
+
+impl Visit for RemoteImageVisitor {
+    fn visit_import_decl(&mut self, import: &ImportDecl) {
+        if matches!(&*import.src.value, "next/image" | "next/legacy/image") {
+            for specifier in import.specifiers.iter() {
+                if let ImportSpecifier::Default(default) = specifier {
+                    self.image_components.insert(default.local.sym.clone());
+                }
+            }
+        }
+    }
+
+    fn visit_call_expr(&mut self, call: &CallExpr) {
+        call.visit_children_with(self);
+        let [component, props, ..] = &call.args[..] else {
+            return;
+        };
+        let (Expr::Ident(component), Expr::Object(props)) = (&*component.expr, &*props.expr) else {
+            return;
+        };
+        if !self.image_components.contains(&component.sym) {
+            return;
+        }
+
+        let mut attrs = BTreeMap::new();
+        for prop in props.props.iter() {
+            let PropOrSpread::Prop(prop) = prop else {
+                // Spread props may set any of the props.
+                return;
+            };
+            let Prop::KeyValue(KeyValueProp { key, value }) = &**prop else {
+                continue;
+            };
+            let name = match key {
+                PropName::Ident(ident) => ident.sym.to_string(),
+                PropName::Str(str) => str.value.to_string(),
+                _ => continue,
+            };
+            let value = match value.as_lit() {
+                Some(Lit::Str(str)) => Some(str.value.to_string()),
+                Some(Lit::Num(num)) => Some(num.value.to_string()),
+                Some(Lit::Bool(bool)) => Some(bool.value.to_string()),
+                _ => None,
+            };
+            attrs.insert(name, value);
+        }
+
+        let Some(Some(src)) = attrs.get("src") else {
+            return;
+        };
+        if !src.starts_with("https://") && !src.starts_with("http://") {
+            return;
+        }
+        let number = |name: &str| {
+            attrs
+                .get(name)
+                .and_then(|value| value.as_ref()?.parse::<f64>().ok())
+                .map(|value| value as u32)
+        };
+        self.usages.push(RemoteImageUsage {
+            src: src.clone(),
+            width: number("width"),
+            height: number("height"),
+            fill: matches!(attrs.get("fill"), Some(Some(value)) if value == "true"),
+        });
+    }
+}
+
+fn remote_image_usages(program: &Program) -> Vec<RemoteImageUsage> {
+    let mut visitor = RemoteImageVisitor::default();
+    program.visit_with(&mut visitor);
+    visitor.usages
+}
+
+/// Probes the remote images rendered with `next/image` by the modules and the
+/// modules they reference, except for packages in `node_modules`, and reports
+/// images which can't be loaded or don't match the size they're rendered
+/// with.
+pub async fn validate_remote_images(modules: &[AssetVc]) -> Result<()> {
+    async fn get_referenced_assets(asset: AssetVc) -> Result<Vec<AssetVc>> {
+        let mut assets = Vec::new();
+        for &referenced in all_referenced_assets(asset).await?.iter() {
+            let path = referenced.ident().path().await?;
+            if !path
+                .path
+                .split('/')
+                .any(|segment| segment == "node_modules")
+            {
+                assets.push(referenced);
+            }
+        }
+        Ok(assets)
+    }
+
+    let modules = GraphTraversal::<NonDeterministic<_>>::visit(
+        modules.iter().copied(),
+        get_referenced_assets,
+    )
+    .await
+    .completed()?;
+
+    let mut seen = HashSet::new();
+    for module in modules {
+        let Some(ecmascript_asset) = EcmascriptModuleAssetVc::resolve_from(module).await? else {
+            continue;
+        };
+        let ParseResult::Ok { program, .. } = &*ecmascript_asset.parse().await? else {
+            continue;
+        };
+        let path = module.ident().path();
+        for usage in remote_image_usages(program) {
+            if !seen.insert((path.resolve().await?, usage.src.clone())) {
+                continue;
+            }
+            validate_remote_image_usage(&usage, path).await?;
+        }
+    }
+    Ok(())
+}
+
+async fn validate_remote_image_usage(
+    usage: &RemoteImageUsage,
+    path: FileSystemPathVc,
+) -> Result<()> {
+    let image = probe_remote_image(StringVc::cell(usage.src.clone()), path).await?;
+    let Some(image) = *image else {
+        return Ok(());
+    };
+    let Some((width, height)) = image.await?.dimensions else {
+        return Ok(());
+    };
+    let message = match (usage.width, usage.height) {
+        _ if usage.fill => return Ok(()),
+        (Some(usage_width), Some(usage_height)) => {
+            // Allows for rounding of the rendered size.
+            let aspect_ratio = width as f64 / height as f64;
+            let usage_aspect_ratio = usage_width as f64 / usage_height.max(1) as f64;
+            if (aspect_ratio - usage_aspect_ratio).abs() / aspect_ratio < 0.02 {
+                return Ok(());
+            }
+            format!(
+                "The remote image {} is {width}x{height}, but it's rendered at \
+                 {usage_width}x{usage_height}, so it will be distorted. Use a width and height \
+                 with the same aspect ratio.",
+                usage.src
+            )
+        }
+        _ => format!(
+            "The remote image {} is rendered without a width and height. Its intrinsic size is \
+             {width}x{height}, set `width={{{width}}} height={{{height}}}` or use `fill`.",
+            usage.src
+        ),
+    };
+    RemoteImageIssue {
+        severity: IssueSeverity::Warning.into(),
+        path,
+        message: StringVc::cell(message),
+    }
+    .cell()
+    .as_issue()
+    .emit();
+    Ok(())
+}
+
+#[turbo_tasks::value(shared)]
+struct RemoteImageIssue {
+    severity: IssueSeverityVc,
+    path: FileSystemPathVc,
+    message: StringVc,
+}
+
+#[turbo_tasks::value_impl]
+impl Issue for RemoteImageIssue {
+    #[turbo_tasks::function]
+    fn severity(&self) -> IssueSeverityVc {
+        self.severity
+    }
+
+    #[turbo_tasks::function]
+    fn title(&self) -> StringVc {
+        StringVc::cell("Invalid remote image".to_string())
+    }
+
+    #[turbo_tasks::function]
+    fn category(&self) -> StringVc {
+        StringVc::cell("next/image".to_string())
+    }
+
+    #[turbo_tasks::function]
+    fn context(&self) -> FileSystemPathVc {
+        self.path
+    }
+
+    #[turbo_tasks::function]
+    fn description(&self) -> StringVc {
+        self.message
+    }
+}
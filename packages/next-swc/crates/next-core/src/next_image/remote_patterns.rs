@@ -0,0 +1,168 @@
+use regex::Regex;
+use turbo_tasks::primitives::StringVc;
+use turbo_tasks_fs::FileSystemPathVc;
+use turbopack_core::issue::{Issue, IssueSeverity, IssueSeverityVc};
+
+use super::optimizer::RemoteUrl;
+use crate::next_config::{ImageConfig, RemotePattern};
+
+/// Whether a remote image can be optimized, because its hostname is listed in
+/// `images.domains` or it matches one of `images.remotePatterns`.
+pub fn is_remote_allowed(config: &ImageConfig, url: &RemoteUrl) -> bool {
+    config.domains.iter().any(|domain| *domain == url.hostname)
+        || config
+            .remote_patterns
+            .iter()
+            .any(|pattern| matches_remote_pattern(pattern, url))
+}
+
+/// Matches a URL against a remote pattern, like `matchRemotePattern` of
+/// Next.js. Omitted parts of the pattern match anything, except for the
+/// hostname, which is required. An empty `port` only matches URLs without a
+/// port.
+pub fn matches_remote_pattern(pattern: &RemotePattern, url: &RemoteUrl) -> bool {
+    if let Some(protocol) = &pattern.protocol {
+        if *protocol != url.protocol {
+            return false;
+        }
+    }
+    if let Some(port) = &pattern.port {
+        if *port != url.port.as_deref().unwrap_or_default() {
+            return false;
+        }
+    }
+    if !glob_match(&pattern.hostname, &url.hostname, '.') {
+        return false;
+    }
+    match &pattern.pathname {
+        Some(pathname) => glob_match(pathname, &url.pathname, '/'),
+        None => true,
+    }
+}
+
+/// Matches `text` against a glob where `*` matches within a segment, and `**`
+/// matches any number of segments, e.g. `*.example.com` matches
+/// `img.example.com` but not `a.img.example.com`.
+fn glob_match(glob: &str, text: &str, separator: char) -> bool {
+    let separator = regex::escape(&separator.to_string());
+    let mut pattern = String::from("^");
+    let mut rest = glob;
+    while !rest.is_empty() {
+        if let Some(after) = rest.strip_prefix("**") {
+            pattern.push_str(".*");
+            rest = after;
+        } else if let Some(after) = rest.strip_prefix('*') {
+            pattern.push_str(&format!("[^{separator}]*"));
+            rest = after;
+        } else {
+            let end = rest.find('*').unwrap_or(rest.len());
+            pattern.push_str(&regex::escape(&rest[..end]));
+            rest = &rest[end..];
+        }
+    }
+    pattern.push('$');
+    Regex::new(&pattern).map_or(false, |regex| regex.is_match(text))
+}
+
+#[turbo_tasks::value(shared)]
+pub struct RemoteImageNotAllowedIssue {
+    pub path: FileSystemPathVc,
+    pub url: String,
+    pub hostname: String,
+}
+
+#[turbo_tasks::value_impl]
+impl Issue for RemoteImageNotAllowedIssue {
+    #[turbo_tasks::function]
+    fn severity(&self) -> IssueSeverityVc {
+        IssueSeverity::Error.into()
+    }
+
+    #[turbo_tasks::function]
+    fn category(&self) -> StringVc {
+        StringVc::cell("image".to_string())
+    }
+
+    #[turbo_tasks::function]
+    fn context(&self) -> FileSystemPathVc {
+        self.path
+    }
+
+    #[turbo_tasks::function]
+    fn title(&self) -> StringVc {
+        StringVc::cell(format!(
+            "Invalid src prop ({}) on `next/image`, hostname \"{}\" is not configured under \
+             images in your `next.config.js`",
+            self.url, self.hostname
+        ))
+    }
+
+    #[turbo_tasks::function]
+    fn description(&self) -> StringVc {
+        StringVc::cell(
+            "Remote images are only optimized when their hostname is listed in `images.domains`, \
+             or their URL matches one of `images.remotePatterns`. See more info: \
+             https://nextjs.org/docs/messages/next-image-unconfigured-host"
+                .to_string(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{glob_match, matches_remote_pattern};
+    use crate::{
+        next_config::{RemotePattern, RemotePatternProtocal},
+        next_image::optimizer::parse_remote_url,
+    };
+
+    #[test]
+    fn test_glob_match_hostnames() {
+        assert!(glob_match("example.com", "example.com", '.'));
+        assert!(!glob_match("example.com", "img.example.com", '.'));
+        // Dots are matched literally.
+        assert!(!glob_match("example.com", "exampleXcom", '.'));
+
+        assert!(glob_match("*.example.com", "img.example.com", '.'));
+        assert!(!glob_match("*.example.com", "a.img.example.com", '.'));
+        assert!(!glob_match("*.example.com", "example.com", '.'));
+
+        assert!(glob_match("**.example.com", "img.example.com", '.'));
+        assert!(glob_match("**.example.com", "a.img.example.com", '.'));
+        assert!(!glob_match("**.example.com", "example.com", '.'));
+        assert!(!glob_match(
+            "**.example.com",
+            "img.example.com.evil.com",
+            '.'
+        ));
+    }
+
+    #[test]
+    fn test_glob_match_pathnames() {
+        assert!(glob_match("/images/*", "/images/a.png", '/'));
+        assert!(!glob_match("/images/*", "/images/a/b.png", '/'));
+        assert!(glob_match("/images/**", "/images/a/b.png", '/'));
+        assert!(!glob_match("/images/**", "/other/a.png", '/'));
+        // Regex syntax in the glob is matched literally.
+        assert!(glob_match("/a+b(1).png", "/a+b(1).png", '/'));
+        assert!(!glob_match("/a+b(1).png", "/aab1.png", '/'));
+    }
+
+    #[test]
+    fn test_matches_remote_pattern() {
+        let pattern = RemotePattern {
+            protocol: Some(RemotePatternProtocal::Https),
+            hostname: "**.example.com".to_string(),
+            port: Some(String::new()),
+            pathname: Some("/images/**".to_string()),
+        };
+        let matches = |url| matches_remote_pattern(&pattern, &parse_remote_url(url).unwrap());
+
+        assert!(matches("https://cdn.example.com/images/a.png"));
+        assert!(!matches("http://cdn.example.com/images/a.png"));
+        // An empty port only matches URLs without a port.
+        assert!(!matches("https://cdn.example.com:8080/images/a.png"));
+        assert!(!matches("https://cdn.example.com/other/a.png"));
+        assert!(!matches("https://example.org/images/a.png"));
+    }
+}
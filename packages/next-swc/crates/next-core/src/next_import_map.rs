@@ -20,10 +20,24 @@ use turbopack_node::execution_context::ExecutionContextVc;
 
 use crate::{
     embed_js::{next_js_fs, VIRTUAL_PACKAGE_NAME},
-    next_client::context::ClientContextType,
-    next_config::NextConfigVc,
-    next_font::google::{NextFontGoogleCssModuleReplacerVc, NextFontGoogleReplacerVc},
+    instrumentation::insert_instrumentation_alias,
+    next_client::{
+        context::{client_resolve_options_context, ClientContextType},
+        optimize_deps::insert_optimized_deps_aliases,
+    },
+    next_config::{EdgeNodeCompat, NextConfigVc},
+    next_edge::node_compat::EdgeNodeBuiltinReplacerVc,
+    next_font::google::{
+        NextFontGoogleCssModuleReplacerVc, NextFontGoogleFontFileReplacerVc,
+        NextFontGoogleReplacerVc, GOOGLE_FONTS_FONT_FILE_REQUEST,
+    },
+    next_remote_container::insert_remote_container_aliases,
     next_server::context::ServerContextType,
+    next_shared::{
+        css_modules::{NextCssModuleStylesheetReplacerVc, CSS_MODULE_STYLESHEET_REQUEST},
+        environment_only::{insert_environment_only_aliases, ModuleEnvironment},
+    },
+    pages_structure::get_pages_special_files,
 };
 
 /// Computes the Next-specific client import map.
@@ -33,10 +47,17 @@ pub async fn get_next_client_import_map(
     ty: Value<ClientContextType>,
     next_config: NextConfigVc,
     execution_context: ExecutionContextVc,
+    optimize_deps: bool,
 ) -> Result<ImportMapVc> {
     let mut import_map = ImportMap::empty();
 
-    insert_next_shared_aliases(&mut import_map, project_path, execution_context).await?;
+    insert_next_shared_aliases(
+        &mut import_map,
+        project_path,
+        next_config,
+        execution_context,
+    )
+    .await?;
 
     insert_alias_option(
         &mut import_map,
@@ -46,32 +67,36 @@ pub async fn get_next_client_import_map(
     )
     .await?;
 
+    insert_remote_container_aliases(&mut import_map, project_path, next_config, false).await?;
+
+    if optimize_deps {
+        insert_optimized_deps_aliases(
+            &mut import_map,
+            project_path,
+            client_resolve_options_context(
+                project_path,
+                Value::new(*ty),
+                next_config,
+                execution_context,
+                false,
+            ),
+            next_config,
+        )
+        .await?;
+    }
+
+    insert_environment_only_aliases(
+        &mut import_map,
+        project_path,
+        ModuleEnvironment::from_client(&ty),
+    );
+
     match ty.into_value() {
         ClientContextType::Pages { pages_dir } => {
-            insert_alias_to_alternatives(
-                &mut import_map,
-                format!("{VIRTUAL_PACKAGE_NAME}/pages/_app"),
-                vec![
-                    request_to_import_mapping(pages_dir, "./_app"),
-                    request_to_import_mapping(pages_dir, "next/app"),
-                ],
-            );
-            insert_alias_to_alternatives(
-                &mut import_map,
-                format!("{VIRTUAL_PACKAGE_NAME}/pages/_document"),
-                vec![
-                    request_to_import_mapping(pages_dir, "./_document"),
-                    request_to_import_mapping(pages_dir, "next/document"),
-                ],
-            );
-            insert_alias_to_alternatives(
-                &mut import_map,
-                format!("{VIRTUAL_PACKAGE_NAME}/internal/_error"),
-                vec![
-                    request_to_import_mapping(pages_dir, "./_error"),
-                    request_to_import_mapping(pages_dir, "next/error"),
-                ],
-            );
+            insert_pages_special_aliases(&mut import_map, pages_dir, next_config, |request| {
+                request_to_import_mapping(pages_dir, request)
+            })
+            .await?;
         }
         ClientContextType::App { app_dir } => {
             import_map.insert_exact_alias(
@@ -172,16 +197,27 @@ pub async fn get_next_server_import_map(
 ) -> Result<ImportMapVc> {
     let mut import_map = ImportMap::empty();
 
-    insert_next_shared_aliases(&mut import_map, project_path, execution_context).await?;
+    insert_next_shared_aliases(
+        &mut import_map,
+        project_path,
+        next_config,
+        execution_context,
+    )
+    .await?;
 
     insert_alias_option(
         &mut import_map,
         project_path,
         next_config.resolve_alias_options(),
-        [],
+        ["server"],
     )
     .await?;
 
+    insert_instrumentation_alias(&mut import_map, project_path, next_config.page_extensions())
+        .await?;
+
+    insert_remote_container_aliases(&mut import_map, project_path, next_config, true).await?;
+
     import_map.insert_exact_alias(
         "@opentelemetry/api",
         // TODO(WEB-625) this actually need to prefer the local version of @opentelemetry/api
@@ -190,7 +226,12 @@ pub async fn get_next_server_import_map(
 
     let ty = ty.into_value();
 
-    insert_next_server_special_aliases(&mut import_map, ty).await?;
+    insert_next_server_special_aliases(&mut import_map, ty, next_config).await?;
+    insert_environment_only_aliases(
+        &mut import_map,
+        project_path,
+        ModuleEnvironment::from_server(&ty),
+    );
     let external = ImportMapping::External(None).cell();
 
     match ty {
@@ -230,23 +271,107 @@ pub async fn get_next_edge_import_map(
 ) -> Result<ImportMapVc> {
     let mut import_map = ImportMap::empty();
 
-    insert_next_shared_aliases(&mut import_map, project_path, execution_context).await?;
+    insert_next_shared_aliases(
+        &mut import_map,
+        project_path,
+        next_config,
+        execution_context,
+    )
+    .await?;
 
     insert_alias_option(
         &mut import_map,
         project_path,
         next_config.resolve_alias_options(),
-        [],
+        ["server"],
     )
     .await?;
 
+    insert_instrumentation_alias(&mut import_map, project_path, next_config.page_extensions())
+        .await?;
+
     let ty = ty.into_value();
 
-    insert_next_server_special_aliases(&mut import_map, ty).await?;
+    insert_next_server_special_aliases(&mut import_map, ty, next_config).await?;
+    insert_environment_only_aliases(
+        &mut import_map,
+        project_path,
+        ModuleEnvironment::from_server(&ty),
+    );
+
+    insert_edge_node_compat_aliases(&mut import_map, project_path, next_config).await?;
 
     Ok(import_map.cell())
 }
 
+/// Computes the Next-specific edge-side fallback import map, which reports
+/// Node.js builtins which are not available in the edge runtime.
+#[turbo_tasks::function]
+pub async fn get_next_edge_fallback_import_map(
+    project_path: FileSystemPathVc,
+    next_config: NextConfigVc,
+) -> Result<ImportMapVc> {
+    let mut import_map = ImportMap::empty();
+
+    // Bare builtin requests are only reported when they don't resolve, as some
+    // of them are also the names of browser-compatible npm packages.
+    let compat = edge_node_compat(next_config).await?;
+    for builtin in compat.unsupported_builtins() {
+        let mapping = edge_node_builtin_mapping(project_path, builtin, compat);
+        import_map.insert_exact_alias(builtin, mapping);
+        import_map.insert_wildcard_alias(format!("{builtin}/"), mapping);
+    }
+
+    Ok(import_map.cell())
+}
+
+/// Maps the Node.js builtins which are available in the edge runtime to their
+/// shims, and reports the other `node:` requests.
+async fn insert_edge_node_compat_aliases(
+    import_map: &mut ImportMap,
+    project_path: FileSystemPathVc,
+    next_config: NextConfigVc,
+) -> Result<()> {
+    let compat = edge_node_compat(next_config).await?;
+    for (builtin, shim) in compat.shims() {
+        let mapping = request_to_import_mapping(project_path, shim);
+        import_map.insert_exact_alias(*builtin, mapping);
+        import_map.insert_exact_alias(format!("node:{builtin}"), mapping);
+    }
+    for builtin in compat.unsupported_builtins() {
+        let mapping = edge_node_builtin_mapping(project_path, builtin, compat);
+        import_map.insert_exact_alias(format!("node:{builtin}"), mapping);
+        import_map.insert_wildcard_alias(format!("node:{builtin}/"), mapping);
+    }
+    Ok(())
+}
+
+async fn edge_node_compat(next_config: NextConfigVc) -> Result<EdgeNodeCompat> {
+    Ok(next_config
+        .await?
+        .experimental
+        .turbo
+        .as_ref()
+        .and_then(|turbo| turbo.edge_node_compat)
+        .unwrap_or_default())
+}
+
+fn edge_node_builtin_mapping(
+    project_path: FileSystemPathVc,
+    builtin: &str,
+    compat: EdgeNodeCompat,
+) -> ImportMappingVc {
+    ImportMapping::Dynamic(
+        EdgeNodeBuiltinReplacerVc::new(
+            project_path,
+            builtin.to_string(),
+            compat == EdgeNodeCompat::Strict,
+        )
+        .into(),
+    )
+    .cell()
+}
+
 pub fn get_next_client_resolved_map(
     context: FileSystemPathVc,
     root: FileSystemPathVc,
@@ -298,33 +423,17 @@ static NEXT_ALIASES: [(&str, &str); 23] = [
 pub async fn insert_next_server_special_aliases(
     import_map: &mut ImportMap,
     ty: ServerContextType,
+    next_config: NextConfigVc,
 ) -> Result<()> {
     match ty {
         ServerContextType::Pages { pages_dir } => {
-            insert_alias_to_alternatives(
+            insert_pages_special_aliases(
                 import_map,
-                format!("{VIRTUAL_PACKAGE_NAME}/pages/_app"),
-                vec![
-                    request_to_import_mapping(pages_dir, "./_app"),
-                    external_request_to_import_mapping("next/app"),
-                ],
-            );
-            insert_alias_to_alternatives(
-                import_map,
-                format!("{VIRTUAL_PACKAGE_NAME}/pages/_document"),
-                vec![
-                    request_to_import_mapping(pages_dir, "./_document"),
-                    external_request_to_import_mapping("next/document"),
-                ],
-            );
-            insert_alias_to_alternatives(
-                import_map,
-                format!("{VIRTUAL_PACKAGE_NAME}/internal/_error"),
-                vec![
-                    request_to_import_mapping(pages_dir, "./_error"),
-                    external_request_to_import_mapping("next/error"),
-                ],
-            );
+                pages_dir,
+                next_config,
+                external_request_to_import_mapping,
+            )
+            .await?;
         }
         ServerContextType::PagesData { .. } => {}
         ServerContextType::AppSSR { app_dir }
@@ -349,6 +458,12 @@ pub async fn insert_next_server_special_aliases(
                 "react-dom/",
                 request_to_import_mapping(app_dir, "next/dist/compiled/react-dom/*"),
             );
+            // Revalidates the fetch cache of the server runtime, see
+            // `internal/fetch-cache.ts`.
+            import_map.insert_exact_alias(
+                "next/cache",
+                request_to_import_mapping(next_js_fs().root(), "./internal/next-cache.ts"),
+            );
         }
         ServerContextType::Middleware => {}
     }
@@ -358,6 +473,7 @@ pub async fn insert_next_server_special_aliases(
 pub async fn insert_next_shared_aliases(
     import_map: &mut ImportMap,
     project_path: FileSystemPathVc,
+    next_config: NextConfigVc,
     execution_context: ExecutionContextVc,
 ) -> Result<()> {
     let package_root = next_js_fs().root();
@@ -395,6 +511,20 @@ pub async fn insert_next_shared_aliases(
         .into(),
     );
 
+    import_map.insert_alias(
+        AliasPattern::exact(GOOGLE_FONTS_FONT_FILE_REQUEST),
+        ImportMapping::Dynamic(NextFontGoogleFontFileReplacerVc::new(project_path).into()).into(),
+    );
+
+    import_map.insert_alias(
+        AliasPattern::exact(CSS_MODULE_STYLESHEET_REQUEST),
+        ImportMapping::Dynamic(
+            NextCssModuleStylesheetReplacerVc::new(project_path, next_config, execution_context)
+                .into(),
+        )
+        .into(),
+    );
+
     import_map.insert_singleton_alias("@swc/helpers", get_next_package(project_path));
     import_map.insert_singleton_alias("styled-jsx", get_next_package(project_path));
     import_map.insert_singleton_alias("next", project_path);
@@ -481,12 +611,27 @@ fn export_value_to_import_mapping(
 }
 
 /// Inserts an alias to an alternative of import mappings into an import map.
-fn insert_alias_to_alternatives<'a>(
+/// Maps `_app`, `_document` and `_error` to the files of the pages directory,
+/// or to the built-in ones of Next.js, which are mapped with `builtin`.
+async fn insert_pages_special_aliases(
     import_map: &mut ImportMap,
-    alias: impl Into<String> + 'a,
-    alternatives: Vec<ImportMappingVc>,
-) {
-    import_map.insert_exact_alias(alias, ImportMapping::Alternatives(alternatives).into());
+    pages_dir: FileSystemPathVc,
+    next_config: NextConfigVc,
+    builtin: impl Fn(&str) -> ImportMappingVc,
+) -> Result<()> {
+    let special_files = get_pages_special_files(pages_dir, next_config.page_extensions()).await?;
+    for (alias, file, builtin_request) in [
+        ("pages/_app", &special_files.app, "next/app"),
+        ("pages/_document", &special_files.document, "next/document"),
+        ("internal/_error", &special_files.error, "next/error"),
+    ] {
+        let mapping = match file {
+            Some(file) => request_to_import_mapping(pages_dir, &format!("./{file}")),
+            None => builtin(builtin_request),
+        };
+        import_map.insert_exact_alias(format!("{VIRTUAL_PACKAGE_NAME}/{alias}"), mapping);
+    }
+    Ok(())
 }
 
 /// Inserts an alias to an import mapping into an import map.
@@ -499,7 +644,10 @@ fn insert_package_alias(import_map: &mut ImportMap, prefix: &str, package_root:
 
 /// Creates a direct import mapping to the result of resolving a request
 /// in a context.
-fn request_to_import_mapping(context_path: FileSystemPathVc, request: &str) -> ImportMappingVc {
+pub(crate) fn request_to_import_mapping(
+    context_path: FileSystemPathVc,
+    request: &str,
+) -> ImportMappingVc {
     ImportMapping::PrimaryAlternative(request.to_string(), Some(context_path)).cell()
 }
 
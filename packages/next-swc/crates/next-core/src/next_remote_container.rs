@@ -0,0 +1,162 @@
+use anyhow::Result;
+use indoc::formatdoc;
+use turbo_tasks_fs::{to_sys_path, FileContent, FileSystemPathVc};
+use turbopack_core::{
+    resolve::{
+        options::{
+            ImportMap, ImportMapResult, ImportMapResultVc, ImportMapping, ImportMappingReplacement,
+            ImportMappingReplacementVc, ImportMappingVc,
+        },
+        parse::{Request, RequestVc},
+        pattern::Pattern,
+        ResolveResult,
+    },
+    virtual_asset::VirtualAssetVc,
+};
+
+use crate::{embed_js::next_js_file_path, next_config::NextConfigVc};
+
+/// Maps `name` and `name/*` requests of every configured remote container to
+/// a module which loads the exposed module from the container at runtime,
+/// with the loader of the browser, or of the Node.js server if `server` is
+/// true.
+///
+/// Like the modules of a webpack container, the exposed modules are only
+/// available asynchronously: `await import("name/path")` resolves to the
+/// exposed module.
+pub async fn insert_remote_container_aliases(
+    import_map: &mut ImportMap,
+    project_path: FileSystemPathVc,
+    next_config: NextConfigVc,
+    server: bool,
+) -> Result<()> {
+    for (name, container) in next_config.remote_containers().await?.iter() {
+        let mapping = ImportMapping::Dynamic(
+            NextRemoteContainerReplacerVc::new(
+                name.clone(),
+                container.global.clone(),
+                container.url.clone(),
+                project_path,
+                server,
+            )
+            .into(),
+        )
+        .cell();
+        import_map.insert_exact_alias(name, mapping);
+        import_map.insert_wildcard_alias(format!("{name}/"), mapping);
+    }
+    Ok(())
+}
+
+#[turbo_tasks::value(shared)]
+pub(crate) struct NextRemoteContainerReplacer {
+    name: String,
+    global: String,
+    url: String,
+    project_path: FileSystemPathVc,
+    server: bool,
+}
+
+#[turbo_tasks::value_impl]
+impl NextRemoteContainerReplacerVc {
+    #[turbo_tasks::function]
+    pub fn new(
+        name: String,
+        global: String,
+        url: String,
+        project_path: FileSystemPathVc,
+        server: bool,
+    ) -> Self {
+        Self::cell(NextRemoteContainerReplacer {
+            name,
+            global,
+            url,
+            project_path,
+            server,
+        })
+    }
+}
+
+impl NextRemoteContainerReplacer {
+    /// Returns the path on disk of a container served from the `public`
+    /// directory, which the server reads instead of fetching it, as it doesn't
+    /// know the origin it's served from.
+    async fn public_file(&self) -> Result<Option<String>> {
+        let Some(path) = self
+            .url
+            .strip_prefix('/')
+            .filter(|path| !path.starts_with('/'))
+        else {
+            return Ok(None);
+        };
+        let path = path.split(['?', '#']).next().unwrap_or_default();
+        let file = self.project_path.join("public").join(path);
+        if !matches!(&*file.read().await?, FileContent::Content(_)) {
+            return Ok(None);
+        }
+        Ok(to_sys_path(file)
+            .await?
+            .map(|path| path.to_string_lossy().into_owned()))
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl ImportMappingReplacement for NextRemoteContainerReplacer {
+    #[turbo_tasks::function]
+    fn replace(&self, _capture: &str) -> ImportMappingVc {
+        ImportMapping::Ignore.into()
+    }
+
+    #[turbo_tasks::function]
+    async fn result(&self, request: RequestVc) -> Result<ImportMapResultVc> {
+        let request = &*request.await?;
+        let Request::Module {
+            module,
+            path: Pattern::Constant(path),
+            query: _,
+        } = request
+        else {
+            return Ok(ImportMapResult::NoEntry.into());
+        };
+        if module != &self.name {
+            return Ok(ImportMapResult::NoEntry.into());
+        }
+
+        // Containers expose their modules as `./path`, the container root is
+        // exposed as `.`.
+        let exposed = format!(".{path}");
+        let (side, file) = if self.server {
+            ("server", self.public_file().await?)
+        } else {
+            ("client", None)
+        };
+        // The module is a thenable, so awaiting its namespace resolves to the
+        // exposed module, which is only loaded once it's imported.
+        let js_asset = VirtualAssetVc::new(
+            next_js_file_path("internal/remote-container")
+                .join(&format!("{side}/{}{}.js", self.name, path)),
+            FileContent::Content(
+                formatdoc!(
+                    r#"
+                        import {{ loadRemoteModule }} from "@vercel/turbopack-next/internal/remote-container";
+                        import {{ loadContainerScript }} from "@vercel/turbopack-next/internal/remote-container-{side}";
+
+                        export function then(resolve, reject) {{
+                          return loadRemoteModule({global}, {exposed}, () =>
+                            loadContainerScript({url}, {file})
+                          ).then(resolve, reject);
+                        }}
+                    "#,
+                    global = serde_json::to_string(&self.global)?,
+                    exposed = serde_json::to_string(&exposed)?,
+                    url = serde_json::to_string(&self.url)?,
+                    file = serde_json::to_string(&file)?,
+                )
+                .into(),
+            )
+            .into(),
+        );
+
+        Ok(ImportMapResult::Result(ResolveResult::asset(js_asset.into()).into()).into())
+    }
+}
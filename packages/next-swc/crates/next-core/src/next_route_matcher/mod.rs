@@ -7,6 +7,7 @@ use self::{
     path_regex::{PathRegex, PathRegexBuilder},
     prefix_suffix::PrefixSuffixMatcher,
 };
+use crate::static_params::StaticParamsVc;
 
 mod all;
 mod path_regex;
@@ -73,6 +74,45 @@ impl RouteMatcher for NextParamsMatcher {
     }
 }
 
+/// A route matcher that only matches the params of a page returned by
+/// `generateStaticParams`, when the page doesn't allow other params.
+#[turbo_tasks::value]
+pub(crate) struct NextStaticParamsMatcher {
+    matcher: RouteMatcherVc,
+    static_params: StaticParamsVc,
+}
+
+#[turbo_tasks::value_impl]
+impl NextStaticParamsMatcherVc {
+    #[turbo_tasks::function]
+    pub fn new(matcher: RouteMatcherVc, static_params: StaticParamsVc) -> Self {
+        Self::cell(NextStaticParamsMatcher {
+            matcher,
+            static_params,
+        })
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl RouteMatcher for NextStaticParamsMatcher {
+    #[turbo_tasks::function]
+    async fn matches(&self, path: &str) -> Result<BoolVc> {
+        Ok(BoolVc::cell(match &*self.matcher.params(path).await? {
+            Some(params) => self.static_params.await?.allows(params),
+            None => false,
+        }))
+    }
+
+    #[turbo_tasks::function]
+    async fn params(&self, path: &str) -> Result<ParamsVc> {
+        let params = self.matcher.params(path);
+        Ok(match &*params.await? {
+            Some(value) if !self.static_params.await?.allows(value) => ParamsVc::cell(None),
+            _ => params,
+        })
+    }
+}
+
 /// A route matcher that strips a prefix and a suffix from a path before
 /// matching it against a route regex.
 #[turbo_tasks::value]
@@ -5,12 +5,11 @@ use turbo_tasks_fs::FileSystemPathVc;
 use turbopack::{
     module_options::{
         JsxTransformOptions, JsxTransformOptionsVc, ModuleOptionsContext, ModuleOptionsContextVc,
-        PostCssTransformOptions, WebpackLoadersOptions,
+        WebpackLoadersOptions,
     },
     resolve_options_context::{ResolveOptionsContext, ResolveOptionsContextVc},
 };
 use turbopack_core::{
-    compile_time_defines,
     compile_time_info::{CompileTimeDefinesVc, CompileTimeInfo, CompileTimeInfoVc},
     environment::{
         EnvironmentIntention, EnvironmentVc, ExecutionEnvironment, NodeJsEnvironmentVc,
@@ -25,10 +24,22 @@ use super::{
 };
 use crate::{
     babel::maybe_add_babel_loader,
-    next_build::{get_external_next_compiled_package_mapping, get_postcss_package_mapping},
+    mode::NextMode,
+    next_build::get_external_next_compiled_package_mapping,
     next_config::NextConfigVc,
     next_import_map::get_next_server_import_map,
-    typescript::get_typescript_transform_options,
+    next_shared::{
+        css_modules::get_css_module_rules,
+        data_modules::get_data_module_rules,
+        defines::{next_config_defines, NextRuntime},
+        graphql::get_graphql_module_rules,
+        media::get_media_module_rules,
+        postcss::get_postcss_module_rules,
+        resolve::ImportQueryResolvePluginVc,
+        static_images::get_static_image_module_rules,
+        transforms::get_import_attributes_transform_rule,
+    },
+    typescript::{get_decorators_transform_options, get_typescript_transform_options},
     util::foreign_code_context_condition,
 };
 
@@ -54,6 +65,7 @@ pub async fn get_server_resolve_options_context(
         get_next_server_import_map(project_path, ty, next_config, execution_context);
     let foreign_code_context_condition = foreign_code_context_condition(next_config).await?;
     let root_dir = project_path.root().resolve().await?;
+    let import_query_plugin = ImportQueryResolvePluginVc::new(project_path);
 
     Ok(match ty.into_value() {
         ServerContextType::Pages { .. } | ServerContextType::PagesData { .. } => {
@@ -69,7 +81,10 @@ pub async fn get_server_resolve_options_context(
                 module: true,
                 custom_conditions: vec!["development".to_string()],
                 import_map: Some(next_server_import_map),
-                plugins: vec![external_cjs_modules_plugin.into()],
+                plugins: vec![
+                    external_cjs_modules_plugin.into(),
+                    import_query_plugin.into(),
+                ],
                 ..Default::default()
             };
             ResolveOptionsContext {
@@ -90,6 +105,7 @@ pub async fn get_server_resolve_options_context(
                 module: true,
                 custom_conditions: vec!["development".to_string()],
                 import_map: Some(next_server_import_map),
+                plugins: vec![import_query_plugin.into()],
                 ..Default::default()
             };
             ResolveOptionsContext {
@@ -110,6 +126,7 @@ pub async fn get_server_resolve_options_context(
                 module: true,
                 custom_conditions: vec!["development".to_string(), "react-server".to_string()],
                 import_map: Some(next_server_import_map),
+                plugins: vec![import_query_plugin.into()],
                 ..Default::default()
             };
             ResolveOptionsContext {
@@ -128,6 +145,7 @@ pub async fn get_server_resolve_options_context(
                 module: true,
                 custom_conditions: vec!["development".to_string()],
                 import_map: Some(next_server_import_map),
+                plugins: vec![import_query_plugin.into()],
                 ..Default::default()
             };
             ResolveOptionsContext {
@@ -146,6 +164,7 @@ pub async fn get_server_resolve_options_context(
                 enable_node_externals: true,
                 module: true,
                 custom_conditions: vec!["development".to_string()],
+                plugins: vec![import_query_plugin.into()],
                 ..Default::default()
             };
             ResolveOptionsContext {
@@ -162,13 +181,16 @@ pub async fn get_server_resolve_options_context(
     .cell())
 }
 
-pub fn next_server_defines() -> CompileTimeDefinesVc {
-    compile_time_defines!(
-        process.turbopack = true,
-        process.env.NODE_ENV = "development",
-        process.env.__NEXT_CLIENT_ROUTER_FILTER_ENABLED = false
+#[turbo_tasks::function]
+pub async fn next_server_defines(
+    mode: Value<NextMode>,
+    next_config: NextConfigVc,
+) -> Result<CompileTimeDefinesVc> {
+    Ok(
+        next_config_defines(NextRuntime::NodeJs, mode.into_value(), next_config)
+            .await?
+            .cell(),
     )
-    .cell()
 }
 
 #[turbo_tasks::function]
@@ -176,6 +198,8 @@ pub fn get_server_compile_time_info(
     ty: Value<ServerContextType>,
     process_env: ProcessEnvVc,
     server_addr: ServerAddrVc,
+    mode: Value<NextMode>,
+    next_config: NextConfigVc,
 ) -> CompileTimeInfoVc {
     CompileTimeInfo {
         environment: EnvironmentVc::new(
@@ -194,7 +218,7 @@ pub fn get_server_compile_time_info(
                 ServerContextType::Middleware => Value::new(EnvironmentIntention::Middleware),
             },
         ),
-        defines: next_server_defines(),
+        defines: next_server_defines(mode, next_config),
     }
     .cell()
 }
@@ -206,12 +230,15 @@ pub async fn get_server_module_options_context(
     ty: Value<ServerContextType>,
     next_config: NextConfigVc,
 ) -> Result<ModuleOptionsContextVc> {
-    let custom_rules = get_next_server_transforms_rules(ty.into_value()).await?;
+    let mut custom_rules = get_next_server_transforms_rules(ty.into_value()).await?;
+    custom_rules.push(get_import_attributes_transform_rule());
+    custom_rules.extend(get_postcss_module_rules(execution_context).await?);
+    custom_rules.extend(get_css_module_rules(project_path, next_config, execution_context).await?);
+    custom_rules.extend(get_data_module_rules(next_config).await?);
+    custom_rules.extend(get_graphql_module_rules(next_config).await?);
+    custom_rules.extend(get_media_module_rules(next_config).await?);
+    custom_rules.extend(get_static_image_module_rules(project_path, next_config).await?);
     let foreign_code_context_condition = foreign_code_context_condition(next_config).await?;
-    let enable_postcss_transform = Some(PostCssTransformOptions {
-        postcss_package: Some(get_postcss_package_mapping(project_path)),
-        ..Default::default()
-    });
 
     let enable_webpack_loaders = {
         let options = &*next_config.webpack_loaders_options().await?;
@@ -230,19 +257,21 @@ pub async fn get_server_module_options_context(
     };
 
     let tsconfig = get_typescript_transform_options(project_path);
+    let decorators_options = get_decorators_transform_options(project_path);
 
     let module_options_context = match ty.into_value() {
         ServerContextType::Pages { .. } | ServerContextType::PagesData { .. } => {
             let module_options_context = ModuleOptionsContext {
                 execution_context: Some(execution_context),
+                custom_rules: vec![get_import_attributes_transform_rule()],
                 ..Default::default()
             };
             ModuleOptionsContext {
                 enable_jsx: Some(get_jsx_transform_options()),
                 enable_styled_jsx: true,
-                enable_postcss_transform,
                 enable_webpack_loaders,
                 enable_typescript_transform: Some(tsconfig),
+                decorators: Some(decorators_options),
                 rules: vec![(
                     foreign_code_context_condition,
                     module_options_context.clone().cell(),
@@ -254,14 +283,15 @@ pub async fn get_server_module_options_context(
         ServerContextType::AppSSR { .. } => {
             let module_options_context = ModuleOptionsContext {
                 execution_context: Some(execution_context),
+                custom_rules: vec![get_import_attributes_transform_rule()],
                 ..Default::default()
             };
             ModuleOptionsContext {
                 enable_jsx: Some(get_jsx_transform_options()),
                 enable_styled_jsx: true,
-                enable_postcss_transform,
                 enable_webpack_loaders,
                 enable_typescript_transform: Some(tsconfig),
+                decorators: Some(decorators_options),
                 rules: vec![(
                     foreign_code_context_condition,
                     module_options_context.clone().cell(),
@@ -276,13 +306,14 @@ pub async fn get_server_module_options_context(
                     StringVc::cell("server-to-client".to_string()),
                 )],
                 execution_context: Some(execution_context),
+                custom_rules: vec![get_import_attributes_transform_rule()],
                 ..Default::default()
             };
             ModuleOptionsContext {
                 enable_jsx: Some(get_jsx_transform_options()),
-                enable_postcss_transform,
                 enable_webpack_loaders,
                 enable_typescript_transform: Some(tsconfig),
+                decorators: Some(decorators_options),
                 rules: vec![(
                     foreign_code_context_condition,
                     module_options_context.clone().cell(),
@@ -294,12 +325,13 @@ pub async fn get_server_module_options_context(
         ServerContextType::AppRoute { .. } => {
             let module_options_context = ModuleOptionsContext {
                 execution_context: Some(execution_context),
+                custom_rules: vec![get_import_attributes_transform_rule()],
                 ..Default::default()
             };
             ModuleOptionsContext {
-                enable_postcss_transform,
                 enable_webpack_loaders,
                 enable_typescript_transform: Some(tsconfig),
+                decorators: Some(decorators_options),
                 rules: vec![(
                     foreign_code_context_condition,
                     module_options_context.clone().cell(),
@@ -311,14 +343,15 @@ pub async fn get_server_module_options_context(
         ServerContextType::Middleware => {
             let module_options_context = ModuleOptionsContext {
                 execution_context: Some(execution_context),
+                custom_rules: vec![get_import_attributes_transform_rule()],
                 ..Default::default()
             };
             ModuleOptionsContext {
                 enable_jsx: Some(get_jsx_transform_options()),
                 enable_styled_jsx: true,
-                enable_postcss_transform,
                 enable_webpack_loaders,
                 enable_typescript_transform: Some(tsconfig),
+                decorators: Some(decorators_options),
                 rules: vec![(
                     foreign_code_context_condition,
                     module_options_context.clone().cell(),
@@ -4,9 +4,13 @@ use turbopack::module_options::ModuleRule;
 
 use crate::{
     next_server::context::ServerContextType,
-    next_shared::transforms::{
-        get_next_dynamic_transform_rule, get_next_font_transform_rule,
-        get_next_pages_transforms_rule,
+    next_shared::{
+        defines::NextRuntime,
+        transforms::{
+            get_next_dynamic_transform_rule, get_next_font_transform_rule,
+            get_next_pages_transforms_rule, get_server_actions_transform_rule,
+            get_top_level_await_transform_rule, get_typeof_window_transform_rule,
+        },
     },
 };
 
@@ -15,7 +19,13 @@ use crate::{
 pub async fn get_next_server_transforms_rules(
     context_ty: ServerContextType,
 ) -> Result<Vec<ModuleRule>> {
-    let mut rules = vec![get_next_font_transform_rule()];
+    let mut rules = vec![
+        get_next_font_transform_rule(),
+        // The edge runtime shares the server module options, `typeof window`
+        // is `"undefined"` there as well.
+        get_typeof_window_transform_rule(NextRuntime::NodeJs),
+        get_top_level_await_transform_rule(NextRuntime::NodeJs),
+    ];
 
     let (is_server_components, pages_dir) = match context_ty {
         ServerContextType::Pages { pages_dir } => (false, Some(pages_dir)),
@@ -25,8 +35,14 @@ pub async fn get_next_server_transforms_rules(
             );
             (false, Some(pages_dir))
         }
-        ServerContextType::AppSSR { .. } => (false, None),
-        ServerContextType::AppRSC { .. } => (true, None),
+        ServerContextType::AppSSR { .. } => {
+            rules.push(get_server_actions_transform_rule(false));
+            (false, None)
+        }
+        ServerContextType::AppRSC { .. } => {
+            rules.push(get_server_actions_transform_rule(true));
+            (true, None)
+        }
         ServerContextType::AppRoute { .. } => (false, None),
         ServerContextType::Middleware { .. } => (false, None),
     };
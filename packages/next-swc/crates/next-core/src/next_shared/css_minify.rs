@@ -0,0 +1,255 @@
+use anyhow::Result;
+use lightningcss::{
+    stylesheet::{MinifyOptions, ParserOptions, PrinterOptions, StyleSheet},
+    targets::{Browsers, Targets},
+};
+use swc_core::ecma::preset_env::Version;
+use turbo_tasks::{primitives::StringVc, Value};
+use turbo_tasks_fs::{File, FileContent, FileSystemPathVc};
+use turbopack::module_options::{ModuleRule, ModuleRuleCondition, ModuleRuleEffect};
+use turbopack_core::{
+    asset::{Asset, AssetContent, AssetContentVc, AssetVc},
+    environment::{BrowserEnvironment, EnvironmentIntention, EnvironmentVc, ExecutionEnvironment},
+    ident::AssetIdentVc,
+    issue::{Issue, IssueSeverity, IssueSeverityVc, IssueVc},
+    reference::AssetReferencesVc,
+    source_transform::{SourceTransform, SourceTransformsVc},
+};
+
+use super::transform_cache::TransformCache;
+use crate::{browserslist::browserslist_query, mode::NextMode, next_config::NextConfigVc};
+
+/// Returns the module rule minifying stylesheets in production builds, unless
+/// `compiler.cssMinify` is `false`. Syntax the browsers of the environment
+/// don't support is lowered and vendor prefixes are added, like the
+/// `CssMinimizerPlugin` of the webpack config of Next.js.
+///
+/// The rule must come after the rules transforming the source of stylesheets,
+/// e.g. PostCSS. CSS modules are minified as their scoped stylesheets. The
+/// minified stylesheets are cached on disk across builds.
+pub async fn get_css_minify_module_rules(
+    project_path: FileSystemPathVc,
+    env: EnvironmentVc,
+    mode: NextMode,
+    next_config: NextConfigVc,
+) -> Result<Vec<ModuleRule>> {
+    if mode.is_development() || !*next_config.css_minify().await? {
+        return Ok(vec![]);
+    }
+    Ok(vec![ModuleRule::new(
+        ModuleRuleCondition::all(vec![
+            ModuleRuleCondition::ResourcePathEndsWith(".css".to_string()),
+            ModuleRuleCondition::not(ModuleRuleCondition::ResourcePathEndsWith(
+                ".module.css".to_string(),
+            )),
+        ]),
+        vec![ModuleRuleEffect::SourceTransforms(
+            SourceTransformsVc::cell(vec![CssMinifyTransformVc::new(project_path, env).into()]),
+        )],
+    )])
+}
+
+/// Returns the browsers targeted by the environment, or the browsers of the
+/// browserslist config of the project when it doesn't target any browser,
+/// e.g. for stylesheets imported on the server.
+async fn browser_targets(project_path: FileSystemPathVc, env: EnvironmentVc) -> Result<Browsers> {
+    let browsers = runtime_browsers(env).await?;
+    if browsers != Browsers::default() {
+        return Ok(browsers);
+    }
+    let query = browserslist_query(project_path, Value::new(NextMode::Build)).await?;
+    runtime_browsers(EnvironmentVc::new(
+        Value::new(ExecutionEnvironment::Browser(
+            BrowserEnvironment {
+                dom: true,
+                web_worker: false,
+                service_worker: false,
+                browserslist_query: query.clone_value(),
+            }
+            .into(),
+        )),
+        Value::new(EnvironmentIntention::Client),
+    ))
+    .await
+}
+
+async fn runtime_browsers(env: EnvironmentVc) -> Result<Browsers> {
+    // lightningcss encodes versions as `major << 16 | minor << 8 | patch`.
+    fn encode(version: Option<Version>) -> Option<u32> {
+        version.map(|v| (v.major << 16) | (v.minor << 8) | v.patch)
+    }
+
+    let versions = &env.runtime_versions().await?.0;
+    let browsers = Browsers {
+        android: encode(versions.android),
+        chrome: encode(versions.chrome),
+        edge: encode(versions.edge),
+        firefox: encode(versions.firefox),
+        ie: encode(versions.ie),
+        ios_saf: encode(versions.ios),
+        opera: encode(versions.opera),
+        safari: encode(versions.safari),
+        samsung: encode(versions.samsung),
+    };
+    Ok(browsers)
+}
+
+/// Minifies a stylesheet for the browsers of an environment, see
+/// [get_css_minify_module_rules].
+#[turbo_tasks::value]
+struct CssMinifyTransform {
+    project_path: FileSystemPathVc,
+    env: EnvironmentVc,
+}
+
+#[turbo_tasks::value_impl]
+impl CssMinifyTransformVc {
+    #[turbo_tasks::function]
+    fn new(project_path: FileSystemPathVc, env: EnvironmentVc) -> Self {
+        CssMinifyTransform { project_path, env }.cell()
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl SourceTransform for CssMinifyTransform {
+    #[turbo_tasks::function]
+    fn transform(&self, source: AssetVc) -> AssetVc {
+        CssMinifiedAsset {
+            source,
+            project_path: self.project_path,
+            env: self.env,
+        }
+        .cell()
+        .into()
+    }
+}
+
+#[turbo_tasks::value]
+struct CssMinifiedAsset {
+    source: AssetVc,
+    project_path: FileSystemPathVc,
+    env: EnvironmentVc,
+}
+
+#[turbo_tasks::value_impl]
+impl Asset for CssMinifiedAsset {
+    #[turbo_tasks::function]
+    fn ident(&self) -> AssetIdentVc {
+        self.source.ident()
+    }
+
+    #[turbo_tasks::function]
+    async fn content(&self) -> Result<AssetContentVc> {
+        let FileContent::Content(file) = &*self.source.content().file_content().await? else {
+            return Ok(self.source.content());
+        };
+        let path = self.source.ident().path();
+        let css = file.content().to_str()?;
+        let browsers = browser_targets(self.project_path, self.env).await?;
+
+        let cache = TransformCache::new(
+            self.project_path,
+            "css-minify",
+            &[format!("{browsers:?}").as_bytes(), css.as_bytes()],
+        )
+        .await?;
+        if let Some(code) = cache.get_output().await? {
+            return Ok(AssetContent::File(FileContent::Content(File::from(code)).cell()).cell());
+        }
+
+        let targets = Targets::from(browsers);
+
+        // The errors borrow the source, so they are converted to strings
+        // right away.
+        let minified = StyleSheet::parse(
+            &css,
+            ParserOptions {
+                filename: path.await?.path.clone(),
+                ..Default::default()
+            },
+        )
+        .map_err(|err| err.to_string())
+        .and_then(|mut stylesheet| {
+            stylesheet
+                .minify(MinifyOptions {
+                    targets,
+                    ..Default::default()
+                })
+                .map_err(|err| err.to_string())?;
+            stylesheet
+                .to_css(PrinterOptions {
+                    minify: true,
+                    targets,
+                    ..Default::default()
+                })
+                .map_err(|err| err.to_string())
+        });
+
+        match minified {
+            Ok(result) => {
+                cache.set_output(result.code.clone()).await?;
+                Ok(AssetContent::File(FileContent::Content(File::from(result.code)).cell()).cell())
+            }
+            Err(detail) => {
+                // The stylesheet is still served, just not minified.
+                CssMinifyIssue {
+                    path,
+                    detail: StringVc::cell(detail),
+                }
+                .cell()
+                .as_issue()
+                .emit();
+                Ok(self.source.content())
+            }
+        }
+    }
+
+    #[turbo_tasks::function]
+    fn references(&self) -> AssetReferencesVc {
+        AssetReferencesVc::empty()
+    }
+}
+
+#[turbo_tasks::value(shared)]
+struct CssMinifyIssue {
+    path: FileSystemPathVc,
+    detail: StringVc,
+}
+
+#[turbo_tasks::value_impl]
+impl Issue for CssMinifyIssue {
+    #[turbo_tasks::function]
+    fn severity(&self) -> IssueSeverityVc {
+        IssueSeverity::Warning.into()
+    }
+
+    #[turbo_tasks::function]
+    fn title(&self) -> StringVc {
+        StringVc::cell("Unable to minify stylesheet".to_string())
+    }
+
+    #[turbo_tasks::function]
+    fn category(&self) -> StringVc {
+        StringVc::cell("css".to_string())
+    }
+
+    #[turbo_tasks::function]
+    fn context(&self) -> FileSystemPathVc {
+        self.path
+    }
+
+    #[turbo_tasks::function]
+    fn description(&self) -> StringVc {
+        StringVc::cell(
+            "The stylesheet could not be parsed by the CSS minifier, so it is included \
+             unminified. Set `compiler.cssMinify` to `false` in next.config.js to skip \
+             minification."
+                .to_string(),
+        )
+    }
+
+    #[turbo_tasks::function]
+    fn detail(&self) -> StringVc {
+        self.detail
+    }
+}
@@ -0,0 +1,576 @@
+use anyhow::Result;
+use indexmap::IndexMap;
+use turbo_tasks::primitives::OptionStringVc;
+use turbo_tasks_fs::{File, FileContent, FileSystemPathVc};
+use turbo_tasks_hash::hash_xxh3_hash64;
+use turbopack::module_options::{ModuleRule, ModuleRuleCondition, ModuleRuleEffect, ModuleType};
+use turbopack_core::{
+    asset::{Asset, AssetContent, AssetContentVc, AssetVc},
+    ident::AssetIdentVc,
+    reference::AssetReferencesVc,
+    resolve::{
+        options::{
+            ImportMapResult, ImportMapResultVc, ImportMapping, ImportMappingReplacement,
+            ImportMappingReplacementVc, ImportMappingVc,
+        },
+        parse::{Request, RequestVc},
+        ResolveResult,
+    },
+    source_asset::SourceAssetVc,
+    source_transform::{SourceTransform, SourceTransformsVc},
+    virtual_asset::VirtualAssetVc,
+};
+use turbopack_ecmascript::EcmascriptInputTransformsVc;
+use turbopack_node::execution_context::ExecutionContextVc;
+
+use super::postcss::{postcss_transform, PostCssTransformVc};
+use crate::{embed_js::next_js_file_path, next_config::NextConfigVc};
+
+/// The request the modules of `.module.css` files import their scoped
+/// stylesheet with, with the path of the file relative to the project
+/// directory as `path` query.
+pub const CSS_MODULE_STYLESHEET_REQUEST: &str =
+    "@vercel/turbopack-next/internal/css-module/stylesheet.css";
+
+/// The characters of the hash in scoped class names, like the `base64` digest
+/// of the webpack `loader-utils`.
+const HASH_ALPHABET: &[u8; 64] =
+    b"0123456789abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ-_";
+
+const HASH_LENGTH: usize = 5;
+
+/// Returns the module rule for `.module.css` files. Their class names are
+/// scoped in the format of the webpack config of Next.js, e.g.
+/// `Button_primary__Xk3Ab`, or with `experimental.turbo.cssModules.
+/// localIdentName`. The name only depends on the path of the file and the
+/// class, so the server and client contexts produce the same markup.
+///
+/// The module exports the scoped class names and imports the scoped
+/// stylesheet, see [NextCssModuleStylesheetReplacer]. PostCSS runs before the
+/// class names are scoped.
+pub async fn get_css_module_rules(
+    project_path: FileSystemPathVc,
+    next_config: NextConfigVc,
+    execution_context: ExecutionContextVc,
+) -> Result<Vec<ModuleRule>> {
+    if next_config
+        .webpack_loaders_options()
+        .await?
+        .contains_key(".module.css")
+    {
+        return Ok(vec![]);
+    }
+    Ok(vec![ModuleRule::new(
+        ModuleRuleCondition::all(vec![
+            ModuleRuleCondition::ResourcePathEndsWith(".module.css".to_string()),
+            // The CSS modules of `next/font` are generated with their own class names.
+            ModuleRuleCondition::not(ModuleRuleCondition::ResourcePathInExactDirectory(
+                next_js_file_path("internal/font/google").await?,
+            )),
+        ]),
+        vec![
+            ModuleRuleEffect::ModuleType(ModuleType::Ecmascript(
+                EcmascriptInputTransformsVc::cell(vec![]),
+            )),
+            ModuleRuleEffect::SourceTransforms(SourceTransformsVc::cell(vec![
+                PostCssTransformVc::new(execution_context).into(),
+                CssModuleTransformVc::new(project_path, next_config.css_modules_local_ident_name())
+                    .into(),
+            ])),
+        ],
+    )])
+}
+
+/// Converts a `.module.css` file to an ES module exporting its scoped class
+/// names.
+#[turbo_tasks::value]
+struct CssModuleTransform {
+    project_path: FileSystemPathVc,
+    local_ident_name: OptionStringVc,
+}
+
+#[turbo_tasks::value_impl]
+impl CssModuleTransformVc {
+    #[turbo_tasks::function]
+    fn new(project_path: FileSystemPathVc, local_ident_name: OptionStringVc) -> Self {
+        CssModuleTransform {
+            project_path,
+            local_ident_name,
+        }
+        .cell()
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl SourceTransform for CssModuleTransform {
+    #[turbo_tasks::function]
+    fn transform(&self, source: AssetVc) -> AssetVc {
+        CssModuleAsset {
+            source,
+            project_path: self.project_path,
+            local_ident_name: self.local_ident_name,
+        }
+        .cell()
+        .into()
+    }
+}
+
+#[turbo_tasks::value]
+struct CssModuleAsset {
+    source: AssetVc,
+    project_path: FileSystemPathVc,
+    local_ident_name: OptionStringVc,
+}
+
+#[turbo_tasks::value_impl]
+impl Asset for CssModuleAsset {
+    #[turbo_tasks::function]
+    fn ident(&self) -> AssetIdentVc {
+        self.source.ident()
+    }
+
+    #[turbo_tasks::function]
+    async fn content(&self) -> Result<AssetContentVc> {
+        let content = &*self.source.content().file_content().await?;
+        let FileContent::Content(file) = content else {
+            return Ok(AssetContent::File(FileContent::NotFound.cell()).cell());
+        };
+        let project_path = self.project_path.await?;
+        let path = self.source.ident().path().await?;
+        let Some(relative_path) = project_path.get_path_to(&path) else {
+            return Ok(AssetContent::File(FileContent::NotFound.cell()).cell());
+        };
+        let local_ident_name = self.local_ident_name.await?;
+        let (_, class_names) = scope_css(&file.content().to_str()?, |local| {
+            scoped_class_name(local_ident_name.as_deref(), relative_path, local)
+        });
+
+        let code = format!(
+            "import {};\nexport default {};\n",
+            serde_json::to_string(&format!(
+                "{CSS_MODULE_STYLESHEET_REQUEST}?{}",
+                qstring::QString::new(vec![("path", relative_path)])
+            ))?,
+            serde_json::to_string(&class_names)?
+        );
+        Ok(AssetContent::File(FileContent::Content(File::from(code)).cell()).cell())
+    }
+
+    #[turbo_tasks::function]
+    fn references(&self) -> AssetReferencesVc {
+        AssetReferencesVc::empty()
+    }
+}
+
+/// Resolves the scoped stylesheet of a `.module.css` file, which is placed
+/// next to it so that relative `url()`s keep working.
+#[turbo_tasks::value(shared)]
+pub struct NextCssModuleStylesheetReplacer {
+    project_path: FileSystemPathVc,
+    next_config: NextConfigVc,
+    execution_context: ExecutionContextVc,
+}
+
+#[turbo_tasks::value_impl]
+impl NextCssModuleStylesheetReplacerVc {
+    #[turbo_tasks::function]
+    pub fn new(
+        project_path: FileSystemPathVc,
+        next_config: NextConfigVc,
+        execution_context: ExecutionContextVc,
+    ) -> Self {
+        Self::cell(NextCssModuleStylesheetReplacer {
+            project_path,
+            next_config,
+            execution_context,
+        })
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl ImportMappingReplacement for NextCssModuleStylesheetReplacer {
+    #[turbo_tasks::function]
+    fn replace(&self, _capture: &str) -> ImportMappingVc {
+        ImportMapping::Ignore.into()
+    }
+
+    #[turbo_tasks::function]
+    async fn result(&self, request: RequestVc) -> Result<ImportMapResultVc> {
+        let request = &*request.await?;
+        let Request::Module {
+            module: _,
+            path: _,
+            query: query_vc,
+        } = request else {
+            return Ok(ImportMapResult::NoEntry.into());
+        };
+        let query = &*query_vc.await?;
+        let Some(relative_path) = query.as_ref().and_then(|query| query.get("path")) else {
+            return Ok(ImportMapResult::NoEntry.into());
+        };
+
+        let path = self.project_path.join(relative_path);
+        let source = postcss_transform(self.execution_context, SourceAssetVc::new(path).into());
+        let FileContent::Content(file) = &*source.content().file_content().await? else {
+            return Ok(ImportMapResult::NoEntry.into());
+        };
+        let local_ident_name = self.next_config.css_modules_local_ident_name().await?;
+        let (stylesheet, _) = scope_css(&file.content().to_str()?, |local| {
+            scoped_class_name(local_ident_name.as_deref(), relative_path, local)
+        });
+
+        let file_name = path.await?.file_name().to_string();
+        let css_asset = VirtualAssetVc::new(
+            path.parent().join(&format!(
+                "{}.scoped.css",
+                file_name.trim_end_matches(".css")
+            )),
+            FileContent::Content(File::from(stylesheet)).into(),
+        );
+
+        Ok(ImportMapResult::Result(ResolveResult::asset(css_asset.into()).into()).into())
+    }
+}
+
+/// Returns the scoped name of the class `local` of the CSS module at
+/// `relative_path`, like `getCssModuleLocalIdent` of Next.js: the file name,
+/// or the folder name for `index.module.css`, the class and a hash of both.
+pub(crate) fn scoped_class_name(
+    local_ident_name: Option<&str>,
+    relative_path: &str,
+    local: &str,
+) -> String {
+    let (folder, file_name) = match relative_path.rsplit_once('/') {
+        Some((directory, file_name)) => {
+            (directory.rsplit('/').next().unwrap_or(directory), file_name)
+        }
+        None => ("", relative_path),
+    };
+    let name = file_name
+        .rsplit_once('.')
+        .map_or(file_name, |(name, _)| name);
+    let is_index_module = name == "index.module" && folder != "pages";
+    let pattern = local_ident_name.unwrap_or(if is_index_module {
+        "[folder]_[local]__[hash]"
+    } else {
+        "[name]_[local]__[hash]"
+    });
+
+    let scoped = pattern
+        .replace("[name]", name)
+        .replace("[folder]", folder)
+        .replace("[local]", local)
+        .replace("[hash]", &class_name_hash(relative_path, local))
+        .replacen(".module_", "_", 1);
+    let scoped = scoped
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect::<String>();
+    // Class names can't start with a digit, two dashes, or a dash and a digit.
+    let mut chars = scoped.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), _) if c.is_ascii_digit() => format!("__{scoped}"),
+        (Some('-'), Some(c)) if c == '-' || c.is_ascii_digit() => format!("__{scoped}"),
+        _ => scoped,
+    }
+}
+
+fn class_name_hash(relative_path: &str, local: &str) -> String {
+    let mut hash = hash_xxh3_hash64(format!("{relative_path}{local}").as_bytes());
+    let mut digits = Vec::with_capacity(HASH_LENGTH);
+    for _ in 0..HASH_LENGTH {
+        digits.push(HASH_ALPHABET[(hash % 64) as usize]);
+        hash /= 64;
+    }
+    digits.reverse();
+    String::from_utf8(digits).unwrap_or_default()
+}
+
+/// Replaces the local class names in the selectors of a CSS module with the
+/// names returned by `scope`. Classes in `:global(...)`, or after `:global` in
+/// a selector, are left as is. Returns the stylesheet and the scoped name of
+/// each local class.
+fn scope_css(source: &str, scope: impl Fn(&str) -> String) -> (String, IndexMap<String, String>) {
+    let mut output = String::with_capacity(source.len());
+    let mut class_names = IndexMap::new();
+    // Whether each open block contains rules, like the top level and `@media`,
+    // rather than declarations.
+    let mut blocks = vec![true];
+    let mut prelude = String::new();
+
+    let mut chars = source.chars().peekable();
+    while let Some(c) = chars.next() {
+        let in_rules = *blocks.last().unwrap_or(&true);
+        let target = if in_rules { &mut prelude } else { &mut output };
+        match c {
+            '/' if chars.peek() == Some(&'*') => {
+                target.push(c);
+                let mut previous = ' ';
+                for c in chars.by_ref() {
+                    target.push(c);
+                    if previous == '*' && c == '/' {
+                        break;
+                    }
+                    previous = c;
+                }
+            }
+            '"' | '\'' => {
+                target.push(c);
+                copy_string(c, &mut chars, target);
+            }
+            '{' => {
+                if in_rules {
+                    let trimmed = prelude.trim_start();
+                    if trimmed.starts_with('@') {
+                        blocks.push(is_conditional_at_rule(trimmed));
+                        output.push_str(&prelude);
+                    } else {
+                        blocks.push(false);
+                        output.push_str(&scope_selector(&prelude, &scope, &mut class_names));
+                    }
+                    prelude.clear();
+                } else {
+                    blocks.push(false);
+                }
+                output.push(c);
+            }
+            '}' => {
+                output.push_str(&prelude);
+                prelude.clear();
+                if blocks.len() > 1 {
+                    blocks.pop();
+                }
+                output.push(c);
+            }
+            ';' if in_rules => {
+                // Statements like `@import`.
+                output.push_str(&prelude);
+                prelude.clear();
+                output.push(c);
+            }
+            _ => target.push(c),
+        }
+    }
+    output.push_str(&prelude);
+    (output, class_names)
+}
+
+fn copy_string(quote: char, chars: &mut impl Iterator<Item = char>, target: &mut String) {
+    let mut escaped = false;
+    for c in chars {
+        target.push(c);
+        if escaped {
+            escaped = false;
+        } else if c == '\\' {
+            escaped = true;
+        } else if c == quote || c == '\n' {
+            break;
+        }
+    }
+}
+
+/// At-rules whose block contains rules rather than declarations.
+fn is_conditional_at_rule(prelude: &str) -> bool {
+    let name = prelude[1..]
+        .split(|c: char| !c.is_ascii_alphanumeric() && c != '-')
+        .next()
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    matches!(
+        name.as_str(),
+        "media" | "supports" | "layer" | "container" | "document" | "scope"
+    )
+}
+
+fn scope_selector(
+    selector: &str,
+    scope: &impl Fn(&str) -> String,
+    class_names: &mut IndexMap<String, String>,
+) -> String {
+    let mut output = String::with_capacity(selector.len());
+    // Whether the classes are global, after a `:global` without parentheses
+    // until the end of the selector.
+    let mut global = false;
+    let mut chars = selector.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '/' if chars.peek() == Some(&'*') => {
+                output.push(c);
+                let mut previous = ' ';
+                for c in chars.by_ref() {
+                    output.push(c);
+                    if previous == '*' && c == '/' {
+                        break;
+                    }
+                    previous = c;
+                }
+            }
+            '"' | '\'' => {
+                output.push(c);
+                copy_string(c, &mut chars, &mut output);
+            }
+            ',' => {
+                global = false;
+                output.push(c);
+            }
+            ':' => {
+                let mut pseudo = String::new();
+                while let Some(&c) = chars.peek() {
+                    if c.is_ascii_alphanumeric() || c == '-' {
+                        pseudo.push(c);
+                        chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                if (pseudo == "global" || pseudo == "local") && chars.peek() == Some(&'(') {
+                    chars.next();
+                    let mut inner = String::new();
+                    let mut depth = 1;
+                    for c in chars.by_ref() {
+                        match c {
+                            '(' => depth += 1,
+                            ')' => {
+                                depth -= 1;
+                                if depth == 0 {
+                                    break;
+                                }
+                            }
+                            _ => {}
+                        }
+                        inner.push(c);
+                    }
+                    if pseudo == "global" {
+                        output.push_str(&inner);
+                    } else {
+                        output.push_str(&scope_selector(&inner, scope, class_names));
+                    }
+                } else if pseudo == "global" || pseudo == "local" {
+                    global = pseudo == "global";
+                    while chars.peek().map_or(false, |c| c.is_whitespace()) {
+                        chars.next();
+                    }
+                } else {
+                    output.push(c);
+                    output.push_str(&pseudo);
+                }
+            }
+            '.' => {
+                let mut local = String::new();
+                while let Some(&c) = chars.peek() {
+                    let valid = c.is_alphanumeric() || c == '-' || c == '_' || !c.is_ascii();
+                    // Class names can't start with a digit, e.g. `.5em` isn't one.
+                    if !valid || local.is_empty() && c.is_ascii_digit() {
+                        break;
+                    }
+                    local.push(c);
+                    chars.next();
+                }
+                output.push('.');
+                if local.is_empty() || global {
+                    output.push_str(&local);
+                    continue;
+                }
+                let scoped = class_names
+                    .entry(local.clone())
+                    .or_insert_with(|| scope(&local));
+                output.push_str(scoped);
+            }
+            _ => output.push(c),
+        }
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use indexmap::IndexMap;
+
+    use super::scope_css;
+
+    fn scope(source: &str) -> (String, IndexMap<String, String>) {
+        scope_css(source, |local| format!("x_{local}"))
+    }
+
+    fn class_names(names: &[&str]) -> IndexMap<String, String> {
+        names
+            .iter()
+            .map(|name| (name.to_string(), format!("x_{name}")))
+            .collect()
+    }
+
+    #[test]
+    fn test_scope_classes() {
+        assert_eq!(
+            scope(".a { color: red; }\n.b.c:hover, div > .b {}"),
+            (
+                ".x_a { color: red; }\n.x_b.x_c:hover, div > .x_b {}".to_string(),
+                class_names(&["a", "b", "c"])
+            )
+        );
+    }
+
+    #[test]
+    fn test_scope_comments() {
+        assert_eq!(
+            scope("/* .c { } */\n.a /* .d */ { /* } */ color: red }"),
+            (
+                "/* .c { } */\n.x_a /* .d */ { /* } */ color: red }".to_string(),
+                class_names(&["a"])
+            )
+        );
+    }
+
+    #[test]
+    fn test_scope_strings() {
+        assert_eq!(
+            scope(".a::after { content: \"} .e {\"; }\n.b[title='.c'] {}"),
+            (
+                ".x_a::after { content: \"} .e {\"; }\n.x_b[title='.c'] {}".to_string(),
+                class_names(&["a", "b"])
+            )
+        );
+    }
+
+    #[test]
+    fn test_scope_nested_at_rules() {
+        assert_eq!(
+            scope(
+                "@import \"a.css\";\n@media (min-width: 1.5em) {\n  @supports (display: grid) {\n    \
+                 .a { width: 1.5em }\n  }\n}\n@keyframes b { from { opacity: 0 } }\n.c {}"
+            ),
+            (
+                "@import \"a.css\";\n@media (min-width: 1.5em) {\n  @supports (display: grid) \
+                 {\n    .x_a { width: 1.5em }\n  }\n}\n@keyframes b { from { opacity: 0 } \
+                 }\n.x_c {}"
+                    .to_string(),
+                class_names(&["a", "c"])
+            )
+        );
+    }
+
+    #[test]
+    fn test_scope_global() {
+        assert_eq!(
+            scope(":global(.a) .b, .c :global(.d .e) {}"),
+            (
+                ".a .x_b, .x_c .d .e {}".to_string(),
+                class_names(&["b", "c"])
+            )
+        );
+        assert_eq!(
+            scope(":global .a .b, .c :local(.d) {}"),
+            (".a .b, .x_c .x_d {}".to_string(), class_names(&["c", "d"]))
+        );
+        assert_eq!(
+            scope(".a :global .b {}"),
+            (".x_a .b {}".to_string(), class_names(&["a"]))
+        );
+    }
+}
@@ -0,0 +1,159 @@
+use anyhow::Result;
+use turbo_tasks::primitives::StringVc;
+use turbo_tasks_fs::{File, FileContent, FileSystemPathVc};
+use turbopack::module_options::{ModuleRule, ModuleRuleCondition, ModuleRuleEffect, ModuleType};
+use turbopack_core::{
+    asset::{Asset, AssetContent, AssetContentVc, AssetVc},
+    ident::AssetIdentVc,
+    issue::{Issue, IssueSeverity, IssueSeverityVc, IssueVc},
+    reference::AssetReferencesVc,
+    source_transform::{SourceTransform, SourceTransformsVc},
+};
+
+use crate::next_config::{DataModuleFormat, NextConfigVc};
+
+/// Returns the module rules which allow importing data files (e.g. `.yaml`,
+/// `.toml`) as JSON modules, as configured by
+/// `experimental.turbo.dataModules`.
+///
+/// Extensions which are handled by webpack loaders are skipped.
+pub async fn get_data_module_rules(next_config: NextConfigVc) -> Result<Vec<ModuleRule>> {
+    let webpack_loaders = next_config.webpack_loaders_options().await?;
+    let mut rules = vec![];
+
+    for (extension, format) in next_config.data_module_formats().await?.iter() {
+        if webpack_loaders.contains_key(extension) {
+            continue;
+        }
+        let mut effects = vec![ModuleRuleEffect::ModuleType(ModuleType::Json)];
+        if *format != DataModuleFormat::Json {
+            let transform = DataToJsonTransform { format: *format }.cell().into();
+            effects.push(ModuleRuleEffect::SourceTransforms(
+                SourceTransformsVc::cell(vec![transform]),
+            ));
+        }
+        rules.push(ModuleRule::new(
+            ModuleRuleCondition::ResourcePathEndsWith(extension.clone()),
+            effects,
+        ));
+    }
+
+    Ok(rules)
+}
+
+/// Converts a data file to JSON, so it can be processed as a JSON module.
+#[turbo_tasks::value]
+struct DataToJsonTransform {
+    format: DataModuleFormat,
+}
+
+#[turbo_tasks::value_impl]
+impl SourceTransform for DataToJsonTransform {
+    #[turbo_tasks::function]
+    fn transform(self_vc: DataToJsonTransformVc, source: AssetVc) -> AssetVc {
+        DataJsonAsset {
+            source,
+            transform: self_vc,
+        }
+        .cell()
+        .into()
+    }
+}
+
+#[turbo_tasks::value]
+struct DataJsonAsset {
+    source: AssetVc,
+    transform: DataToJsonTransformVc,
+}
+
+#[turbo_tasks::value_impl]
+impl Asset for DataJsonAsset {
+    #[turbo_tasks::function]
+    fn ident(&self) -> AssetIdentVc {
+        self.source.ident()
+    }
+
+    #[turbo_tasks::function]
+    async fn content(&self) -> Result<AssetContentVc> {
+        let format = self.transform.await?.format;
+        let content = &*self.source.content().file_content().await?;
+        let FileContent::Content(file) = content else {
+            return Ok(AssetContent::File(FileContent::NotFound.cell()).cell());
+        };
+
+        let text = file.content().to_str()?;
+        let value: Result<serde_json::Value, String> = match format {
+            DataModuleFormat::Json => serde_json::from_str(&text).map_err(|e| e.to_string()),
+            DataModuleFormat::Yaml => serde_yaml::from_str(&text).map_err(|e| e.to_string()),
+            DataModuleFormat::Toml => toml::from_str(&text).map_err(|e| e.to_string()),
+        };
+        let value = match value {
+            Ok(value) => value,
+            Err(err) => {
+                DataModuleParsingIssue {
+                    path: self.source.ident().path(),
+                    format: StringVc::cell(format.as_str().to_string()),
+                    detail: StringVc::cell(err),
+                }
+                .cell()
+                .as_issue()
+                .emit();
+                serde_json::Value::Null
+            }
+        };
+
+        Ok(AssetContent::File(
+            FileContent::Content(File::from(serde_json::to_string(&value)?)).cell(),
+        )
+        .cell())
+    }
+
+    #[turbo_tasks::function]
+    fn references(&self) -> AssetReferencesVc {
+        AssetReferencesVc::empty()
+    }
+}
+
+/// An issue that occurred while parsing a data file imported as a module.
+#[turbo_tasks::value(shared)]
+pub struct DataModuleParsingIssue {
+    path: FileSystemPathVc,
+    format: StringVc,
+    detail: StringVc,
+}
+
+#[turbo_tasks::value_impl]
+impl Issue for DataModuleParsingIssue {
+    #[turbo_tasks::function]
+    fn severity(&self) -> IssueSeverityVc {
+        IssueSeverity::Error.into()
+    }
+
+    #[turbo_tasks::function]
+    async fn title(&self) -> Result<StringVc> {
+        Ok(StringVc::cell(format!(
+            "Unable to parse {} file",
+            self.format.await?
+        )))
+    }
+
+    #[turbo_tasks::function]
+    fn category(&self) -> StringVc {
+        StringVc::cell("parsing".to_string())
+    }
+
+    #[turbo_tasks::function]
+    fn context(&self) -> FileSystemPathVc {
+        self.path
+    }
+
+    #[turbo_tasks::function]
+    fn description(&self) -> StringVc {
+        StringVc::cell("The imported data file could not be converted to JSON.".to_string())
+    }
+
+    #[turbo_tasks::function]
+    fn detail(&self) -> StringVc {
+        self.detail
+    }
+}
@@ -0,0 +1,116 @@
+use anyhow::Result;
+use indexmap::IndexMap;
+use turbo_tasks::Value;
+use turbopack_core::compile_time_info::CompileTimeDefines;
+
+use crate::{
+    mode::NextMode,
+    next_config::{DefineValue, NextConfigVc},
+};
+
+/// The JavaScript runtime a Next.js context compiles for.
+#[turbo_tasks::value(serialization = "auto_for_input")]
+#[derive(Debug, Copy, Clone, Hash, PartialOrd, Ord)]
+pub enum NextRuntime {
+    /// Client code running in the browser.
+    Browser,
+    /// Server code running in Node.js.
+    NodeJs,
+    /// Server code running in the edge runtime.
+    Edge,
+}
+
+impl NextRuntime {
+    /// Returns the value of `process.env.NEXT_RUNTIME`, or `None` in the
+    /// browser, where it is not set.
+    pub fn next_runtime_env(&self) -> Option<&'static str> {
+        match self {
+            NextRuntime::Browser => None,
+            NextRuntime::NodeJs => Some("nodejs"),
+            NextRuntime::Edge => Some("edge"),
+        }
+    }
+
+    /// Returns the value of `typeof window`.
+    pub fn typeof_window(&self) -> &'static str {
+        match self {
+            NextRuntime::Browser => "object",
+            NextRuntime::NodeJs | NextRuntime::Edge => "undefined",
+        }
+    }
+}
+
+/// Returns the compile-time defines shared by all Next.js contexts.
+///
+/// `process.env.NODE_ENV` and `process.env.NEXT_RUNTIME` are replaced with
+/// string literals, so branches on them are eliminated when the condition is
+/// constant.
+pub fn next_defines(runtime: NextRuntime, mode: NextMode) -> CompileTimeDefines {
+    CompileTimeDefines(
+        next_define_values(runtime, mode)
+            .into_iter()
+            .map(|(name, value)| (name, value.into()))
+            .collect(),
+    )
+}
+
+/// Returns the values of [next_defines], keyed by the segments of the
+/// expression they replace. They are shared with the transform of modules for
+/// tests, see [crate::test_transform].
+pub fn next_define_values(
+    runtime: NextRuntime,
+    mode: NextMode,
+) -> IndexMap<Vec<String>, DefineValue> {
+    let mut defines = IndexMap::new();
+    defines.insert(
+        vec!["process".to_string(), "turbopack".to_string()],
+        DefineValue::Bool(true),
+    );
+    defines.insert(
+        vec![
+            "process".to_string(),
+            "env".to_string(),
+            "__NEXT_CLIENT_ROUTER_FILTER_ENABLED".to_string(),
+        ],
+        DefineValue::Bool(false),
+    );
+    defines.insert(
+        vec![
+            "process".to_string(),
+            "env".to_string(),
+            "NODE_ENV".to_string(),
+        ],
+        DefineValue::String(mode.node_env().to_string()),
+    );
+    if let Some(next_runtime) = runtime.next_runtime_env() {
+        defines.insert(
+            vec![
+                "process".to_string(),
+                "env".to_string(),
+                "NEXT_RUNTIME".to_string(),
+            ],
+            DefineValue::String(next_runtime.to_string()),
+        );
+    }
+    defines
+}
+
+/// Returns the defines of [next_defines] with the ones configured for `mode` in
+/// `experimental.turbo.defines`. The defines of Next.js take precedence, so
+/// `process.env.NODE_ENV` always matches the mode.
+pub async fn next_config_defines(
+    runtime: NextRuntime,
+    mode: NextMode,
+    next_config: NextConfigVc,
+) -> Result<CompileTimeDefines> {
+    let mut defines = CompileTimeDefines(
+        next_config
+            .defines(Value::new(mode))
+            .await?
+            .iter()
+            .map(|(name, value)| (name.clone(), value.clone()))
+            .collect(),
+    );
+    defines.0.extend(next_defines(runtime, mode).0);
+    Ok(defines)
+}
@@ -0,0 +1,91 @@
+use anyhow::Result;
+use turbo_tasks::{primitives::U64Vc, CompletionVc, Value};
+use turbo_tasks_fs::{File, FileContent, FileSystemPathVc};
+use turbo_tasks_hash::hash_xxh3_hash64;
+use turbopack_core::{
+    context::{AssetContext, AssetContextVc},
+    virtual_asset::VirtualAssetVc,
+};
+use turbopack_ecmascript::{
+    chunk::EcmascriptChunkPlaceableVc, EcmascriptInputTransformsVc, EcmascriptModuleAssetType,
+    EcmascriptModuleAssetVc,
+};
+
+/// Lockfiles of the supported package managers.
+pub(crate) const LOCKFILES: [&str; 5] = [
+    "package-lock.json",
+    "npm-shrinkwrap.json",
+    "yarn.lock",
+    "pnpm-lock.yaml",
+    "bun.lockb",
+];
+
+/// Files the package managers update in `node_modules` on every install,
+/// which also catches installs that don't change the lockfile, e.g. `npm link`.
+const NODE_MODULES_MARKERS: [&str; 4] = [
+    "node_modules/.package-lock.json",
+    "node_modules/.yarn-state.yml",
+    "node_modules/.yarn-integrity",
+    "node_modules/.modules.yaml",
+];
+
+/// Computes a fingerprint of the installed dependencies from the lockfiles
+/// and `node_modules` markers in the project directory and its parent
+/// directories, so lockfiles at the root of a monorepo are picked up too.
+#[turbo_tasks::function]
+pub async fn dependencies_fingerprint(project_path: FileSystemPathVc) -> Result<U64Vc> {
+    let mut contents = vec![];
+    let mut dir = project_path;
+    loop {
+        for file in LOCKFILES.iter().chain(NODE_MODULES_MARKERS.iter()) {
+            let path = dir.join(file);
+            if let FileContent::Content(content) = &*path.read().await? {
+                contents.push((path.await?.path.clone(), content.content().to_bytes()?));
+            }
+        }
+        if dir.await?.is_root() {
+            break;
+        }
+        dir = dir.parent().resolve().await?;
+    }
+    Ok(U64Vc::cell(hash_xxh3_hash64(contents)))
+}
+
+/// Returns a completion that changes whenever dependencies are installed,
+/// updated or removed.
+#[turbo_tasks::function]
+pub async fn dependencies_changed(project_path: FileSystemPathVc) -> Result<CompletionVc> {
+    dependencies_fingerprint(project_path).await?;
+    Ok(CompletionVc::new())
+}
+
+/// Creates a runtime entry for node.js rendering which changes whenever the
+/// installed dependencies change.
+///
+/// Packages which are not bundled are loaded by node.js itself and stay in its
+/// module cache. Changing the rendering chunks restarts the node.js processes,
+/// so a dependency install doesn't leave stale versions of those packages
+/// around until the dev server is restarted.
+#[turbo_tasks::function]
+pub async fn get_dependencies_runtime_entry(
+    project_path: FileSystemPathVc,
+    context: AssetContextVc,
+) -> Result<EcmascriptChunkPlaceableVc> {
+    let fingerprint = *dependencies_fingerprint(project_path).await?;
+    let asset = VirtualAssetVc::new(
+        project_path.join("__turbopack_next_dependencies__.js"),
+        FileContent::Content(File::from(format!(
+            "\"dependencies {fingerprint:016x}\";\n"
+        )))
+        .cell()
+        .into(),
+    );
+    Ok(EcmascriptModuleAssetVc::new(
+        asset.into(),
+        context,
+        Value::new(EcmascriptModuleAssetType::Ecmascript),
+        EcmascriptInputTransformsVc::cell(vec![]),
+        context.compile_time_info(),
+    )
+    .as_ecmascript_chunk_placeable())
+}
@@ -0,0 +1,222 @@
+use anyhow::Result;
+use indoc::formatdoc;
+use turbo_tasks::primitives::StringVc;
+use turbo_tasks_fs::{FileContent, FileSystemPathVc};
+use turbopack_core::{
+    issue::{Issue, IssueSeverity, IssueSeverityVc, IssueVc},
+    resolve::{
+        options::{
+            ImportMap, ImportMapResult, ImportMapResultVc, ImportMapping, ImportMappingReplacement,
+            ImportMappingReplacementVc, ImportMappingVc,
+        },
+        parse::RequestVc,
+        ResolveResult,
+    },
+    virtual_asset::VirtualAssetVc,
+};
+
+use crate::{
+    embed_js::{next_js_file_path, VIRTUAL_PACKAGE_NAME},
+    next_client::context::ClientContextType,
+    next_import_map::request_to_import_mapping,
+    next_server::context::ServerContextType,
+};
+
+/// The marker package for modules which only work on the server.
+const SERVER_ONLY: &str = "server-only";
+
+/// The marker package for modules which only work on the client.
+const CLIENT_ONLY: &str = "client-only";
+
+/// Where the modules resolved with an import map run, deciding which of the
+/// `server-only` and `client-only` marker packages they can import.
+#[derive(Clone, Copy)]
+pub(crate) enum ModuleEnvironment {
+    /// The browser, for pages and client components.
+    Client,
+    /// Client components of the app directory, rendered on the server.
+    ClientComponents,
+    /// Server components and route handlers of the app directory.
+    ServerComponents,
+    /// The server side of pages, which can use both marker packages.
+    PagesServer,
+    Middleware,
+}
+
+impl ModuleEnvironment {
+    pub(crate) fn from_client(ty: &ClientContextType) -> Self {
+        match ty {
+            ClientContextType::App { .. } => ModuleEnvironment::ClientComponents,
+            ClientContextType::Pages { .. }
+            | ClientContextType::Fallback
+            | ClientContextType::Other => ModuleEnvironment::Client,
+        }
+    }
+
+    pub(crate) fn from_server(ty: &ServerContextType) -> Self {
+        match ty {
+            ServerContextType::Pages { .. } | ServerContextType::PagesData { .. } => {
+                ModuleEnvironment::PagesServer
+            }
+            ServerContextType::AppSSR { .. } => ModuleEnvironment::ClientComponents,
+            ServerContextType::AppRSC { .. } | ServerContextType::AppRoute { .. } => {
+                ModuleEnvironment::ServerComponents
+            }
+            ServerContextType::Middleware => ModuleEnvironment::Middleware,
+        }
+    }
+
+    /// Returns how the modules are referred to in issues, or `None` when the
+    /// marker package can be imported in this environment.
+    fn forbidden(&self, package: &str) -> Option<&'static str> {
+        match (self, package) {
+            (ModuleEnvironment::Client, SERVER_ONLY) => Some("client code"),
+            (ModuleEnvironment::ClientComponents, SERVER_ONLY) => Some("a Client Component"),
+            (ModuleEnvironment::ServerComponents, CLIENT_ONLY) => Some("a Server Component"),
+            (ModuleEnvironment::Middleware, CLIENT_ONLY) => Some("middleware"),
+            _ => None,
+        }
+    }
+}
+
+/// Maps the `server-only` and `client-only` marker packages.
+///
+/// Where a marker package can be imported, it resolves to an empty module, so
+/// it doesn't need to be installed. Elsewhere, an issue is reported for the
+/// import, see [EnvironmentOnlyReplacer].
+pub(crate) fn insert_environment_only_aliases(
+    import_map: &mut ImportMap,
+    project_path: FileSystemPathVc,
+    environment: ModuleEnvironment,
+) {
+    for package in [SERVER_ONLY, CLIENT_ONLY] {
+        let mapping = match environment.forbidden(package) {
+            Some(environment) => ImportMapping::Dynamic(
+                EnvironmentOnlyReplacerVc::new(
+                    project_path,
+                    package.to_string(),
+                    environment.to_string(),
+                )
+                .into(),
+            )
+            .cell(),
+            None => request_to_import_mapping(
+                project_path,
+                &format!("{VIRTUAL_PACKAGE_NAME}/internal/empty"),
+            ),
+        };
+        import_map.insert_exact_alias(package, mapping);
+    }
+}
+
+/// Replaces a marker package imported in an environment it forbids.
+///
+/// The issue is emitted while resolving the import, so its processing path is
+/// the import trace from the entry to the module importing the marker package.
+/// The marker package is replaced by a module which throws, like the package
+/// itself does when it's imported in the wrong environment.
+#[turbo_tasks::value(shared)]
+pub(crate) struct EnvironmentOnlyReplacer {
+    project_path: FileSystemPathVc,
+    package: String,
+    environment: String,
+}
+
+#[turbo_tasks::value_impl]
+impl EnvironmentOnlyReplacerVc {
+    #[turbo_tasks::function]
+    pub fn new(project_path: FileSystemPathVc, package: String, environment: String) -> Self {
+        Self::cell(EnvironmentOnlyReplacer {
+            project_path,
+            package,
+            environment,
+        })
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl ImportMappingReplacement for EnvironmentOnlyReplacer {
+    #[turbo_tasks::function]
+    fn replace(&self, _capture: &str) -> ImportMappingVc {
+        ImportMapping::Ignore.into()
+    }
+
+    #[turbo_tasks::function]
+    async fn result(&self, _request: RequestVc) -> Result<ImportMapResultVc> {
+        EnvironmentOnlyIssue {
+            path: self.project_path,
+            package: self.package.clone(),
+            environment: self.environment.clone(),
+        }
+        .cell()
+        .as_issue()
+        .emit();
+
+        let js_asset = VirtualAssetVc::new(
+            next_js_file_path("internal/environment-only").join(&format!("{}.js", self.package)),
+            FileContent::Content(
+                formatdoc!(
+                    r#"
+                        throw new Error({});
+                    "#,
+                    serde_json::to_string(&format!(
+                        "`{}` can't be imported from {}.",
+                        self.package, self.environment
+                    ))?,
+                )
+                .into(),
+            )
+            .into(),
+        );
+
+        Ok(ImportMapResult::Result(ResolveResult::asset(js_asset.into()).into()).into())
+    }
+}
+
+#[turbo_tasks::value(shared)]
+pub(crate) struct EnvironmentOnlyIssue {
+    path: FileSystemPathVc,
+    package: String,
+    environment: String,
+}
+
+#[turbo_tasks::value_impl]
+impl Issue for EnvironmentOnlyIssue {
+    #[turbo_tasks::function]
+    fn severity(&self) -> IssueSeverityVc {
+        IssueSeverity::Error.into()
+    }
+
+    #[turbo_tasks::function]
+    fn category(&self) -> StringVc {
+        StringVc::cell("resolve".to_string())
+    }
+
+    #[turbo_tasks::function]
+    fn context(&self) -> FileSystemPathVc {
+        self.path
+    }
+
+    #[turbo_tasks::function]
+    fn title(&self) -> StringVc {
+        StringVc::cell(format!(
+            "`{}` can't be imported from {}",
+            self.package, self.environment
+        ))
+    }
+
+    #[turbo_tasks::function]
+    fn description(&self) -> StringVc {
+        let allowed = if self.package == SERVER_ONLY {
+            "It marks a module which only works on the server, e.g. in Server Components, Route \
+             Handlers, API routes or `getServerSideProps`."
+        } else {
+            "It marks a module which only works on the client, e.g. in Client Components or the \
+             browser. Add \"use client\" at the top of a component to make it a Client Component."
+        };
+        StringVc::cell(format!(
+            "{allowed} The import trace shows which module imports `{}`.",
+            self.package
+        ))
+    }
+}
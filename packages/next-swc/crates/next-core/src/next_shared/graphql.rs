@@ -0,0 +1,555 @@
+use std::fmt::Write;
+
+use anyhow::Result;
+use graphql_parser::query::{
+    parse_query, Definition, Directive, FragmentDefinition, OperationDefinition, Selection,
+    SelectionSet, Type, TypeCondition, Value, VariableDefinition,
+};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde_json::{json, Value as JsonValue};
+use turbo_tasks::primitives::StringVc;
+use turbo_tasks_fs::{File, FileContent, FileSystemPathVc};
+use turbopack::module_options::{ModuleRule, ModuleRuleCondition, ModuleRuleEffect, ModuleType};
+use turbopack_core::{
+    asset::{Asset, AssetContent, AssetContentVc, AssetVc},
+    ident::AssetIdentVc,
+    issue::{Issue, IssueSeverity, IssueSeverityVc, IssueVc},
+    reference::AssetReferencesVc,
+    source_transform::{SourceTransform, SourceTransformsVc},
+};
+use turbopack_ecmascript::{utils::StringifyJs, EcmascriptInputTransformsVc};
+
+use crate::next_config::NextConfigVc;
+
+const GRAPHQL_EXTENSIONS: [&str; 2] = [".graphql", ".gql"];
+
+/// Returns the module rules which allow importing GraphQL documents, with the
+/// same semantics as `graphql-tag/loader`: the default export is the parsed
+/// document, and every named operation is exported by its name. Unlike the
+/// loader, the documents are parsed at build time, so `graphql-tag` isn't
+/// needed.
+///
+/// Extensions which are handled by webpack loaders are skipped.
+pub async fn get_graphql_module_rules(next_config: NextConfigVc) -> Result<Vec<ModuleRule>> {
+    let webpack_loaders = next_config.webpack_loaders_options().await?;
+    let mut rules = vec![];
+
+    for extension in GRAPHQL_EXTENSIONS {
+        if webpack_loaders.contains_key(extension) {
+            continue;
+        }
+        rules.push(ModuleRule::new(
+            ModuleRuleCondition::ResourcePathEndsWith(extension.to_string()),
+            vec![
+                ModuleRuleEffect::ModuleType(ModuleType::Ecmascript(
+                    EcmascriptInputTransformsVc::cell(vec![]),
+                )),
+                ModuleRuleEffect::SourceTransforms(SourceTransformsVc::cell(vec![
+                    GraphQlTransformVc::new().into(),
+                ])),
+            ],
+        ));
+    }
+
+    Ok(rules)
+}
+
+/// Converts a GraphQL document to an ES module.
+#[turbo_tasks::value]
+struct GraphQlTransform;
+
+#[turbo_tasks::value_impl]
+impl GraphQlTransformVc {
+    #[turbo_tasks::function]
+    fn new() -> Self {
+        GraphQlTransform.cell()
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl SourceTransform for GraphQlTransform {
+    #[turbo_tasks::function]
+    fn transform(&self, source: AssetVc) -> AssetVc {
+        GraphQlModuleAsset { source }.cell().into()
+    }
+}
+
+#[turbo_tasks::value]
+struct GraphQlModuleAsset {
+    source: AssetVc,
+}
+
+#[turbo_tasks::value_impl]
+impl Asset for GraphQlModuleAsset {
+    #[turbo_tasks::function]
+    fn ident(&self) -> AssetIdentVc {
+        self.source.ident()
+    }
+
+    #[turbo_tasks::function]
+    async fn content(&self) -> Result<AssetContentVc> {
+        let content = &*self.source.content().file_content().await?;
+        let FileContent::Content(file) = content else {
+            return Ok(AssetContent::File(FileContent::NotFound.cell()).cell());
+        };
+
+        let code = match graphql_to_module(&file.content().to_str()?)? {
+            Ok(code) => code,
+            Err(err) => {
+                GraphQlParsingIssue {
+                    path: self.source.ident().path(),
+                    detail: StringVc::cell(err),
+                }
+                .cell()
+                .as_issue()
+                .emit();
+                "throw new Error(\"Unable to parse GraphQL document\");\n".to_string()
+            }
+        };
+        Ok(AssetContent::File(FileContent::Content(File::from(code)).cell()).cell())
+    }
+
+    #[turbo_tasks::function]
+    fn references(&self) -> AssetReferencesVc {
+        AssetReferencesVc::empty()
+    }
+}
+
+/// An issue that occurred while parsing an imported GraphQL document.
+#[turbo_tasks::value(shared)]
+pub struct GraphQlParsingIssue {
+    path: FileSystemPathVc,
+    detail: StringVc,
+}
+
+#[turbo_tasks::value_impl]
+impl Issue for GraphQlParsingIssue {
+    #[turbo_tasks::function]
+    fn severity(&self) -> IssueSeverityVc {
+        IssueSeverity::Error.into()
+    }
+
+    #[turbo_tasks::function]
+    fn title(&self) -> StringVc {
+        StringVc::cell("Unable to parse GraphQL document".to_string())
+    }
+
+    #[turbo_tasks::function]
+    fn category(&self) -> StringVc {
+        StringVc::cell("parsing".to_string())
+    }
+
+    #[turbo_tasks::function]
+    fn context(&self) -> FileSystemPathVc {
+        self.path
+    }
+
+    #[turbo_tasks::function]
+    fn description(&self) -> StringVc {
+        StringVc::cell(
+            "Only executable documents, made of operations and fragments, can be imported."
+                .to_string(),
+        )
+    }
+
+    #[turbo_tasks::function]
+    fn detail(&self) -> StringVc {
+        self.detail
+    }
+}
+
+/// `graphql-tag/loader` only recognizes `#import` comments at the start of a
+/// line.
+static IMPORT_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"^#\s*import\s+["']([^"']+)["']"#).unwrap());
+
+/// Generates the module code for a GraphQL document, or returns the parse
+/// error of the document.
+///
+/// The default export is the document in the AST format of graphql-js, which
+/// `graphql-tag` returns. `#import` comments are turned into imports whose
+/// fragments are merged into the document.
+fn graphql_to_module(source: &str) -> Result<Result<String, String>> {
+    let document = match parse_query::<String>(source) {
+        Ok(document) => document,
+        Err(err) => return Ok(Err(err.to_string())),
+    };
+
+    let imports: Vec<&str> = source
+        .lines()
+        .filter_map(|line| IMPORT_RE.captures(line))
+        .filter_map(|captures| captures.get(1))
+        .map(|request| request.as_str())
+        .collect();
+
+    let document_json = json!({
+        "kind": "Document",
+        "definitions": document.definitions.iter().map(definition).collect::<Vec<_>>(),
+        "loc": {
+            "start": 0,
+            "end": source.len(),
+            "source": {
+                "body": source,
+                "name": "GraphQL request",
+                "locationOffset": { "line": 1, "column": 1 },
+            },
+        },
+    });
+
+    let mut code = String::new();
+    writeln!(
+        code,
+        "import {{ extractOperation, mergeFragments }} from \
+         \"@vercel/turbopack-next/internal/graphql\";"
+    )?;
+    for (i, request) in imports.iter().enumerate() {
+        writeln!(code, "import __import{i} from {};", StringifyJs(request))?;
+    }
+    write!(
+        code,
+        "\nconst __document = mergeFragments({}, [",
+        StringifyJs(&document_json)
+    )?;
+    for i in 0..imports.len() {
+        write!(code, "{}__import{i}", if i == 0 { "" } else { ", " })?;
+    }
+    writeln!(code, "]);\n\nexport default __document;")?;
+
+    // The operations are exported with export specifiers, which accept any
+    // name but `default`, so they don't collide with the local bindings.
+    let mut names = Vec::new();
+    for definition in document.definitions.iter() {
+        let Definition::Operation(operation) = definition else {
+            continue;
+        };
+        let name = match operation {
+            OperationDefinition::Query(query) => query.name.as_deref(),
+            OperationDefinition::Mutation(mutation) => mutation.name.as_deref(),
+            OperationDefinition::Subscription(subscription) => subscription.name.as_deref(),
+            OperationDefinition::SelectionSet(_) => None,
+        };
+        let Some(name) = name else {
+            continue;
+        };
+        if name == "default" || names.contains(&name) {
+            continue;
+        }
+        writeln!(
+            code,
+            "const __operation{i} = extractOperation(__document, {});\nexport {{ __operation{i} \
+             as {name} }};",
+            StringifyJs(name),
+            i = names.len(),
+        )?;
+        names.push(name);
+    }
+
+    Ok(Ok(code))
+}
+
+fn name(value: &str) -> JsonValue {
+    json!({ "kind": "Name", "value": value })
+}
+
+fn definition(definition: &Definition<'_, String>) -> JsonValue {
+    match definition {
+        Definition::Operation(operation) => {
+            let (kind, name, variables, directives, selection_set) = match operation {
+                OperationDefinition::SelectionSet(selection_set) => {
+                    ("query", None, &[][..], &[][..], selection_set)
+                }
+                OperationDefinition::Query(query) => (
+                    "query",
+                    query.name.as_deref(),
+                    &query.variable_definitions[..],
+                    &query.directives[..],
+                    &query.selection_set,
+                ),
+                OperationDefinition::Mutation(mutation) => (
+                    "mutation",
+                    mutation.name.as_deref(),
+                    &mutation.variable_definitions[..],
+                    &mutation.directives[..],
+                    &mutation.selection_set,
+                ),
+                OperationDefinition::Subscription(subscription) => (
+                    "subscription",
+                    subscription.name.as_deref(),
+                    &subscription.variable_definitions[..],
+                    &subscription.directives[..],
+                    &subscription.selection_set,
+                ),
+            };
+            let mut json = json!({
+                "kind": "OperationDefinition",
+                "operation": kind,
+                "variableDefinitions": variables.iter().map(variable_definition).collect::<Vec<_>>(),
+                "directives": directives.iter().map(directive).collect::<Vec<_>>(),
+                "selectionSet": self::selection_set(selection_set),
+            });
+            if let Some(name) = name {
+                json["name"] = self::name(name);
+            }
+            json
+        }
+        Definition::Fragment(FragmentDefinition {
+            name,
+            type_condition: TypeCondition::On(type_condition),
+            directives,
+            selection_set,
+            ..
+        }) => json!({
+            "kind": "FragmentDefinition",
+            "name": self::name(name),
+            "typeCondition": named_type(type_condition),
+            "directives": directives.iter().map(directive).collect::<Vec<_>>(),
+            "selectionSet": self::selection_set(selection_set),
+        }),
+    }
+}
+
+fn variable_definition(definition: &VariableDefinition<'_, String>) -> JsonValue {
+    let mut json = json!({
+        "kind": "VariableDefinition",
+        "variable": { "kind": "Variable", "name": name(&definition.name) },
+        "type": ty(&definition.var_type),
+        "directives": [],
+    });
+    if let Some(default_value) = &definition.default_value {
+        json["defaultValue"] = value(default_value);
+    }
+    json
+}
+
+fn named_type(type_name: &str) -> JsonValue {
+    json!({ "kind": "NamedType", "name": name(type_name) })
+}
+
+fn ty(ty: &Type<'_, String>) -> JsonValue {
+    match ty {
+        Type::NamedType(type_name) => named_type(type_name),
+        Type::ListType(ty) => json!({ "kind": "ListType", "type": self::ty(ty) }),
+        Type::NonNullType(ty) => json!({ "kind": "NonNullType", "type": self::ty(ty) }),
+    }
+}
+
+fn directive(directive: &Directive<'_, String>) -> JsonValue {
+    json!({
+        "kind": "Directive",
+        "name": name(&directive.name),
+        "arguments": arguments(&directive.arguments),
+    })
+}
+
+fn arguments(arguments: &[(String, Value<'_, String>)]) -> Vec<JsonValue> {
+    arguments
+        .iter()
+        .map(|(argument, argument_value)| {
+            json!({ "kind": "Argument", "name": name(argument), "value": value(argument_value) })
+        })
+        .collect()
+}
+
+fn selection_set(selection_set: &SelectionSet<'_, String>) -> JsonValue {
+    json!({
+        "kind": "SelectionSet",
+        "selections": selection_set.items.iter().map(selection).collect::<Vec<_>>(),
+    })
+}
+
+fn selection(selection: &Selection<'_, String>) -> JsonValue {
+    match selection {
+        Selection::Field(field) => {
+            let mut json = json!({
+                "kind": "Field",
+                "name": name(&field.name),
+                "arguments": arguments(&field.arguments),
+                "directives": field.directives.iter().map(directive).collect::<Vec<_>>(),
+            });
+            if let Some(alias) = &field.alias {
+                json["alias"] = name(alias);
+            }
+            // Leaf fields have no selection set.
+            if !field.selection_set.items.is_empty() {
+                json["selectionSet"] = selection_set(&field.selection_set);
+            }
+            json
+        }
+        Selection::FragmentSpread(spread) => json!({
+            "kind": "FragmentSpread",
+            "name": name(&spread.fragment_name),
+            "directives": spread.directives.iter().map(directive).collect::<Vec<_>>(),
+        }),
+        Selection::InlineFragment(fragment) => {
+            let mut json = json!({
+                "kind": "InlineFragment",
+                "directives": fragment.directives.iter().map(directive).collect::<Vec<_>>(),
+                "selectionSet": selection_set(&fragment.selection_set),
+            });
+            if let Some(TypeCondition::On(type_condition)) = &fragment.type_condition {
+                json["typeCondition"] = named_type(type_condition);
+            }
+            json
+        }
+    }
+}
+
+fn value(value: &Value<'_, String>) -> JsonValue {
+    match value {
+        Value::Variable(variable) => json!({ "kind": "Variable", "name": name(variable) }),
+        Value::Int(int) => json!({
+            "kind": "IntValue",
+            "value": int.as_i64().map(|int| int.to_string()),
+        }),
+        Value::Float(float) => json!({ "kind": "FloatValue", "value": float.to_string() }),
+        Value::String(string) => json!({ "kind": "StringValue", "value": string, "block": false }),
+        Value::Boolean(boolean) => json!({ "kind": "BooleanValue", "value": boolean }),
+        Value::Null => json!({ "kind": "NullValue" }),
+        Value::Enum(enum_value) => json!({ "kind": "EnumValue", "value": enum_value }),
+        Value::List(values) => json!({
+            "kind": "ListValue",
+            "values": values.iter().map(self::value).collect::<Vec<_>>(),
+        }),
+        Value::Object(fields) => json!({
+            "kind": "ObjectValue",
+            "fields": fields
+                .iter()
+                .map(|(field, field_value)| {
+                    json!({
+                        "kind": "ObjectField",
+                        "name": name(field),
+                        "value": self::value(field_value),
+                    })
+                })
+                .collect::<Vec<_>>(),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use graphql_parser::query::parse_query;
+    use serde_json::json;
+
+    use super::{definition, graphql_to_module};
+
+    fn module(source: &str) -> String {
+        graphql_to_module(source).unwrap().unwrap()
+    }
+
+    #[test]
+    fn exports_named_operations() {
+        let code = module(
+            "query GetUser($id: ID!) { user(id: $id) { ...UserFields } }\nmutation doc { logout \
+             }\nsubscription delete { deleted }\n{ anonymous }\nfragment UserFields on User { id \
+             name }\n",
+        );
+        assert!(code.contains("export { __operation0 as GetUser };"));
+        // Names of local bindings and reserved words are valid export names.
+        assert!(code.contains("export { __operation1 as doc };"));
+        assert!(code.contains("export { __operation2 as delete };"));
+        assert!(!code.contains("__operation3"));
+        assert!(code.contains("export default __document;"));
+    }
+
+    #[test]
+    fn skips_operations_named_default() {
+        let code = module("query default { a }\nquery Other { b }\nquery Other { c }\n");
+        assert!(!code.contains("as default }"));
+        assert!(code.contains("export { __operation0 as Other };"));
+        assert!(!code.contains("__operation1"));
+    }
+
+    #[test]
+    fn ignores_comments_and_strings() {
+        let code = module(
+            "# query Commented { a }\nquery Real { field(arg: \"query InString { b }\") }\n",
+        );
+        assert!(code.contains("as Real }"));
+        assert!(!code.contains("Commented }"));
+        assert!(!code.contains("InString }"));
+    }
+
+    #[test]
+    fn converts_to_graphql_js_ast() {
+        let document =
+            parse_query::<String>("query Q($n: [Int!] = [1]) @live { a: b(x: {y: ENUM}) { c } }")
+                .unwrap();
+        let name = |value: &str| json!({ "kind": "Name", "value": value });
+        assert_eq!(
+            definition(&document.definitions[0]),
+            json!({
+                "kind": "OperationDefinition",
+                "operation": "query",
+                "name": name("Q"),
+                "variableDefinitions": [{
+                    "kind": "VariableDefinition",
+                    "variable": { "kind": "Variable", "name": name("n") },
+                    "type": {
+                        "kind": "ListType",
+                        "type": {
+                            "kind": "NonNullType",
+                            "type": { "kind": "NamedType", "name": name("Int") },
+                        },
+                    },
+                    "defaultValue": {
+                        "kind": "ListValue",
+                        "values": [{ "kind": "IntValue", "value": "1" }],
+                    },
+                    "directives": [],
+                }],
+                "directives": [{ "kind": "Directive", "name": name("live"), "arguments": [] }],
+                "selectionSet": {
+                    "kind": "SelectionSet",
+                    "selections": [{
+                        "kind": "Field",
+                        "alias": name("a"),
+                        "name": name("b"),
+                        "arguments": [{
+                            "kind": "Argument",
+                            "name": name("x"),
+                            "value": {
+                                "kind": "ObjectValue",
+                                "fields": [{
+                                    "kind": "ObjectField",
+                                    "name": name("y"),
+                                    "value": { "kind": "EnumValue", "value": "ENUM" },
+                                }],
+                            },
+                        }],
+                        "directives": [],
+                        "selectionSet": {
+                            "kind": "SelectionSet",
+                            "selections": [{
+                                "kind": "Field",
+                                "name": name("c"),
+                                "arguments": [],
+                                "directives": [],
+                            }],
+                        },
+                    }],
+                },
+            })
+        );
+    }
+
+    #[test]
+    fn imports_fragments() {
+        let code = module(
+            "#import \"./fragments.graphql\"\n  #import \"./indented.graphql\"\nquery Q { ...F }\n",
+        );
+        assert!(code.contains("import __import0 from \"./fragments.graphql\";"));
+        assert!(!code.contains("indented"));
+        assert!(code.contains("[__import0]);"));
+    }
+
+    #[test]
+    fn reports_invalid_documents() {
+        assert!(graphql_to_module("query { a").unwrap().is_err());
+        // Schema definitions aren't executable.
+        assert!(graphql_to_module("type Query { a: String }")
+            .unwrap()
+            .is_err());
+    }
+}
@@ -0,0 +1,128 @@
+use swc_core::{
+    common::errors::HANDLER,
+    ecma::{
+        ast::{
+            ExportAll, Expr, ImportDecl, KeyValueProp, Lit, Module, ModuleDecl, ModuleItem,
+            NamedExport, ObjectLit, Prop, PropName, PropOrSpread, Str,
+        },
+        visit::{noop_visit_mut_type, VisitMut},
+    },
+};
+
+/// Removes the import attributes of static imports and re-exports, e.g.
+/// `import data from "./data.json" assert { type: "json" }`.
+///
+/// The module type is determined by the resolved file instead, so only
+/// `type: "json"` attributes on `.json` files are supported. Other attributes
+/// are reported and ignored.
+pub struct ImportAttributes;
+
+impl VisitMut for ImportAttributes {
+    noop_visit_mut_type!();
+
+    fn visit_mut_module(&mut self, module: &mut Module) {
+        // Import attributes are only allowed on the declarations at the top
+        // level of a module.
+        for item in module.body.iter_mut() {
+            let ModuleItem::ModuleDecl(decl) = item else {
+                continue;
+            };
+            let (src, asserts) = match decl {
+                ModuleDecl::Import(ImportDecl { src, asserts, .. })
+                | ModuleDecl::ExportAll(ExportAll { src, asserts, .. })
+                | ModuleDecl::ExportNamed(NamedExport {
+                    src: Some(src),
+                    asserts,
+                    ..
+                }) => (src, asserts),
+                _ => continue,
+            };
+            let Some(attributes) = asserts.take() else {
+                continue;
+            };
+            if !is_json_import(src, &attributes) {
+                HANDLER.with(|handler| {
+                    handler
+                        .struct_span_warn(
+                            attributes.span,
+                            "Only `type: \"json\"` attributes on `.json` files are supported. The \
+                             attributes of this import are ignored, the imported module is \
+                             processed based on its file extension instead.",
+                        )
+                        .emit()
+                });
+            }
+        }
+    }
+}
+
+/// Returns true if the attributes have `type: "json"` and the specifier is a
+/// `.json` file.
+fn is_json_import(src: &Str, attributes: &ObjectLit) -> bool {
+    let ty = attributes.props.iter().find_map(|prop| {
+        let PropOrSpread::Prop(prop) = prop else {
+            return None;
+        };
+        let Prop::KeyValue(KeyValueProp { key, value }) = &**prop else {
+            return None;
+        };
+        let key = match key {
+            PropName::Ident(ident) => &*ident.sym,
+            PropName::Str(str) => &*str.value,
+            _ => return None,
+        };
+        match &**value {
+            Expr::Lit(Lit::Str(value)) if key == "type" => Some(&*value.value),
+            _ => None,
+        }
+    });
+    ty == Some("json") && src.value.ends_with(".json")
+}
+
+#[cfg(test)]
+mod tests {
+    use swc_core::ecma::{
+        parser::{EsConfig, Syntax},
+        transforms::testing::test,
+        visit::as_folder,
+    };
+
+    use super::ImportAttributes;
+
+    fn syntax() -> Syntax {
+        Syntax::Es(EsConfig {
+            import_assertions: true,
+            ..Default::default()
+        })
+    }
+
+    test!(
+        syntax(),
+        |_| as_folder(ImportAttributes),
+        json_import,
+        r#"
+        import data from "./data.json" assert { type: "json" };
+        export { default as config } from "./config.json" assert { type: "json" };
+        export * from "./other.json" assert { "type": "json" };
+        "#,
+        r#"
+        import data from "./data.json";
+        export { default as config } from "./config.json";
+        export * from "./other.json";
+        "#
+    );
+
+    test!(
+        syntax(),
+        |_| as_folder(ImportAttributes),
+        unsupported_attributes,
+        r#"
+        import styles from "./styles.css" assert { type: "css" };
+        import data from "./data.txt" assert { type: "json" };
+        "#,
+        r#"
+        import styles from "./styles.css";
+        import data from "./data.txt";
+        "#
+    );
+}
@@ -0,0 +1,147 @@
+use anyhow::Result;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use turbo_tasks_fs::{File, FileContent};
+use turbopack::module_options::{ModuleRule, ModuleRuleCondition, ModuleRuleEffect, ModuleType};
+use turbopack_core::{
+    asset::{Asset, AssetContent, AssetContentVc, AssetVc},
+    ident::AssetIdentVc,
+    reference::AssetReferencesVc,
+    reference_type::{ReferenceType, UrlReferenceSubType},
+    source_transform::{SourceTransform, SourceTransformsVc},
+};
+use turbopack_ecmascript::EcmascriptInputTransformsVc;
+
+use crate::next_config::NextConfigVc;
+
+/// Audio and video file extensions, with their MIME type.
+const MEDIA_TYPES: [(&str, &str); 10] = [
+    (".mp4", "video/mp4"),
+    (".webm", "video/webm"),
+    (".ogv", "video/ogg"),
+    (".mov", "video/quicktime"),
+    (".mp3", "audio/mpeg"),
+    (".wav", "audio/wav"),
+    (".ogg", "audio/ogg"),
+    (".oga", "audio/ogg"),
+    (".flac", "audio/flac"),
+    (".m4a", "audio/mp4"),
+];
+
+/// Files smaller than this are inlined as data URLs by default. Matches the
+/// default of webpack's `asset` module type.
+const DEFAULT_INLINE_LIMIT: u64 = 8 * 1024;
+
+/// Returns the module rules for audio and video files. Importing them returns
+/// their URL, which is a data URL when the file is smaller than
+/// `experimental.turbo.mediaInlineLimit` and the URL of the emitted file
+/// otherwise.
+///
+/// Extensions which are handled by webpack loaders are skipped.
+pub async fn get_media_module_rules(next_config: NextConfigVc) -> Result<Vec<ModuleRule>> {
+    let inline_limit = next_config
+        .await?
+        .experimental
+        .turbo
+        .as_ref()
+        .and_then(|turbo| turbo.media_inline_limit)
+        .unwrap_or(DEFAULT_INLINE_LIMIT);
+    let webpack_loaders = next_config.webpack_loaders_options().await?;
+    let mut rules = vec![];
+
+    for (extension, mime_type) in MEDIA_TYPES {
+        if webpack_loaders.contains_key(extension) {
+            continue;
+        }
+        rules.push(ModuleRule::new(
+            ModuleRuleCondition::all(vec![
+                // `new URL(...)` references emit the file, see `MediaModuleAsset`.
+                ModuleRuleCondition::not(ModuleRuleCondition::ReferenceType(ReferenceType::Url(
+                    UrlReferenceSubType::Undefined,
+                ))),
+                ModuleRuleCondition::ResourcePathEndsWith(extension.to_string()),
+            ]),
+            vec![
+                ModuleRuleEffect::ModuleType(ModuleType::Ecmascript(
+                    EcmascriptInputTransformsVc::cell(vec![]),
+                )),
+                ModuleRuleEffect::SourceTransforms(SourceTransformsVc::cell(vec![
+                    MediaTransform {
+                        mime_type: mime_type.to_string(),
+                        inline_limit,
+                    }
+                    .cell()
+                    .into(),
+                ])),
+            ],
+        ));
+    }
+
+    Ok(rules)
+}
+
+/// Converts a media file to an ES module exporting its URL.
+#[turbo_tasks::value]
+struct MediaTransform {
+    mime_type: String,
+    inline_limit: u64,
+}
+
+#[turbo_tasks::value_impl]
+impl SourceTransform for MediaTransform {
+    #[turbo_tasks::function]
+    fn transform(self_vc: MediaTransformVc, source: AssetVc) -> AssetVc {
+        MediaModuleAsset {
+            source,
+            transform: self_vc,
+        }
+        .cell()
+        .into()
+    }
+}
+
+#[turbo_tasks::value]
+struct MediaModuleAsset {
+    source: AssetVc,
+    transform: MediaTransformVc,
+}
+
+#[turbo_tasks::value_impl]
+impl Asset for MediaModuleAsset {
+    #[turbo_tasks::function]
+    fn ident(&self) -> AssetIdentVc {
+        self.source.ident()
+    }
+
+    #[turbo_tasks::function]
+    async fn content(&self) -> Result<AssetContentVc> {
+        let transform = self.transform.await?;
+        let content = &*self.source.content().file_content().await?;
+        let FileContent::Content(file) = content else {
+            return Ok(AssetContent::File(FileContent::NotFound.cell()).cell());
+        };
+
+        let bytes = file.content().to_bytes()?;
+        let url = if (bytes.len() as u64) < transform.inline_limit {
+            serde_json::to_string(&format!(
+                "data:{};base64,{}",
+                transform.mime_type,
+                STANDARD.encode(&bytes)
+            ))?
+        } else {
+            // The `new URL` reference emits the file as a static asset.
+            let file_name = self.source.ident().path().await?.file_name().to_string();
+            format!(
+                "new URL({}, import.meta.url).href",
+                serde_json::to_string(&format!("./{file_name}"))?
+            )
+        };
+
+        let code = format!("export default {url};\n");
+        Ok(AssetContent::File(FileContent::Content(File::from(code)).cell()).cell())
+    }
+
+    #[turbo_tasks::function]
+    fn references(&self) -> AssetReferencesVc {
+        AssetReferencesVc::empty()
+    }
+}
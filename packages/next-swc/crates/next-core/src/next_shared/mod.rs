@@ -1 +1,17 @@
+pub(crate) mod css_minify;
+pub(crate) mod css_modules;
+pub(crate) mod data_modules;
+pub(crate) mod defines;
+pub(crate) mod dependencies;
+pub(crate) mod environment_only;
+pub(crate) mod graphql;
+pub(crate) mod import_attributes;
+pub(crate) mod media;
+pub(crate) mod postcss;
+pub(crate) mod resolve;
+pub(crate) mod static_images;
+pub(crate) mod top_level_await;
+pub(crate) mod transform_cache;
 pub(crate) mod transforms;
+pub(crate) mod typeof_window;
+pub(crate) mod worker;
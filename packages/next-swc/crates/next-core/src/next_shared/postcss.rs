@@ -0,0 +1,234 @@
+use anyhow::Result;
+use serde::Deserialize;
+use tracing::Instrument;
+use turbo_tasks::{
+    primitives::{JsonValueVc, StringsVc},
+    CompletionVc, Value,
+};
+use turbo_tasks_fs::{json::parse_json_rope_with_source_context, to_sys_path, File, FileContent};
+use turbopack::{
+    evaluate_context::node_evaluate_asset_context,
+    module_options::{ModuleRule, ModuleRuleCondition, ModuleRuleEffect},
+};
+use turbopack_core::{
+    asset::{Asset, AssetContent, AssetContentVc, AssetVc},
+    chunk::ChunkingContext,
+    context::AssetContext,
+    ident::AssetIdentVc,
+    reference::AssetReferencesVc,
+    resolve::{find_context_file, FindContextFileResult},
+    source_transform::{SourceTransform, SourceTransformsVc},
+};
+use turbopack_ecmascript::{
+    EcmascriptInputTransform, EcmascriptInputTransformsVc, EcmascriptModuleAssetType,
+    EcmascriptModuleAssetVc,
+};
+use turbopack_node::{
+    evaluate::{evaluate, JavaScriptValue},
+    execution_context::{ExecutionContext, ExecutionContextVc},
+};
+
+use super::transform_cache::{TransformCache, TransformCacheLookup, TransformDependency};
+use crate::{
+    embed_js::{next_asset, next_js_file_path},
+    next_import_map::get_next_build_import_map,
+};
+
+/// The PostCSS config files, in the order they are looked up in the directory
+/// of a stylesheet and its ancestors.
+const POSTCSS_CONFIG_FILES: [&str; 5] = [
+    "postcss.config.js",
+    "postcss.config.cjs",
+    "postcss.config.mjs",
+    ".postcssrc.json",
+    ".postcssrc",
+];
+
+#[turbo_tasks::function]
+fn postcss_configs() -> StringsVc {
+    StringsVc::cell(
+        POSTCSS_CONFIG_FILES
+            .into_iter()
+            .map(ToOwned::to_owned)
+            .collect(),
+    )
+}
+
+/// Returns the module rule running the plugins of the PostCSS config of the
+/// project on `.css` files, e.g. Tailwind and autoprefixer. CSS modules run
+/// the transform themselves before their class names are scoped, see
+/// [postcss_transform].
+pub async fn get_postcss_module_rules(
+    execution_context: ExecutionContextVc,
+) -> Result<Vec<ModuleRule>> {
+    Ok(vec![ModuleRule::new(
+        ModuleRuleCondition::all(vec![
+            ModuleRuleCondition::ResourcePathEndsWith(".css".to_string()),
+            ModuleRuleCondition::not(ModuleRuleCondition::ResourcePathEndsWith(
+                ".module.css".to_string(),
+            )),
+            // The stylesheets of `next/font` are generated.
+            ModuleRuleCondition::not(ModuleRuleCondition::ResourcePathInExactDirectory(
+                next_js_file_path("internal/font/google").await?,
+            )),
+        ]),
+        vec![ModuleRuleEffect::SourceTransforms(
+            SourceTransformsVc::cell(vec![PostCssTransformVc::new(execution_context).into()]),
+        )],
+    )])
+}
+
+/// Runs the PostCSS plugins on a stylesheet, or returns it as is when there's
+/// no PostCSS config.
+#[turbo_tasks::function]
+pub fn postcss_transform(execution_context: ExecutionContextVc, source: AssetVc) -> AssetVc {
+    PostCssProcessedAsset {
+        source,
+        execution_context,
+    }
+    .cell()
+    .into()
+}
+
+/// Runs the PostCSS plugins on a stylesheet, see [postcss_transform].
+#[turbo_tasks::value]
+pub struct PostCssTransform {
+    execution_context: ExecutionContextVc,
+}
+
+#[turbo_tasks::value_impl]
+impl PostCssTransformVc {
+    #[turbo_tasks::function]
+    pub fn new(execution_context: ExecutionContextVc) -> Self {
+        PostCssTransform { execution_context }.cell()
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl SourceTransform for PostCssTransform {
+    #[turbo_tasks::function]
+    fn transform(&self, source: AssetVc) -> AssetVc {
+        postcss_transform(self.execution_context, source)
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PostCssResult {
+    css: String,
+    dependencies: Vec<TransformDependency>,
+}
+
+#[turbo_tasks::value]
+struct PostCssProcessedAsset {
+    source: AssetVc,
+    execution_context: ExecutionContextVc,
+}
+
+#[turbo_tasks::value_impl]
+impl Asset for PostCssProcessedAsset {
+    #[turbo_tasks::function]
+    fn ident(&self) -> AssetIdentVc {
+        self.source.ident()
+    }
+
+    #[turbo_tasks::function]
+    async fn content(&self) -> Result<AssetContentVc> {
+        let span = tracing::info_span!("evaluate", layer = "postcss");
+        process_postcss(self).instrument(span).await
+    }
+
+    #[turbo_tasks::function]
+    fn references(&self) -> AssetReferencesVc {
+        AssetReferencesVc::empty()
+    }
+}
+
+async fn process_postcss(this: &PostCssProcessedAsset) -> Result<AssetContentVc> {
+    let ExecutionContext {
+        project_path,
+        chunking_context,
+        env,
+    } = *this.execution_context.await?;
+
+    let source_path = this.source.ident().path();
+    let FindContextFileResult::Found(config_path, _) =
+        &*find_context_file(source_path.parent(), postcss_configs()).await?
+    else {
+        return Ok(this.source.content());
+    };
+    let FileContent::Content(file) = &*this.source.content().file_content().await? else {
+        return Ok(this.source.content());
+    };
+    let FileContent::Content(config) = &*config_path.read().await? else {
+        return Ok(this.source.content());
+    };
+    let (Some(resource_path), Some(config_sys_path)) = (
+        to_sys_path(source_path).await?,
+        to_sys_path(*config_path).await?,
+    ) else {
+        return Ok(this.source.content());
+    };
+    let css = file.content().to_str()?;
+
+    let resource_path = resource_path.to_string_lossy().to_string();
+    let cache = TransformCache::new(
+        project_path,
+        "postcss",
+        &[
+            resource_path.as_bytes(),
+            &*config.content().to_bytes()?,
+            css.as_bytes(),
+        ],
+    )
+    .await?;
+
+    // The fingerprint is passed to PostCSS, so the evaluation runs again
+    // when a dependency changes.
+    let fingerprint = match cache.get().await? {
+        TransformCacheLookup::Hit(css) => return Ok(css_content(css)),
+        TransformCacheLookup::Miss { fingerprint } => fingerprint,
+    };
+
+    let context =
+        node_evaluate_asset_context(project_path, Some(get_next_build_import_map()), None);
+    let postcss_runner = EcmascriptModuleAssetVc::new(
+        next_asset("entry/postcss.ts"),
+        context,
+        Value::new(EcmascriptModuleAssetType::Typescript),
+        EcmascriptInputTransformsVc::cell(vec![EcmascriptInputTransform::TypeScript {
+            use_define_for_class_fields: false,
+        }]),
+        context.compile_time_info(),
+    );
+    let result = evaluate(
+        postcss_runner.into(),
+        project_path,
+        env,
+        this.source.ident(),
+        context,
+        chunking_context.with_layer("postcss"),
+        None,
+        vec![
+            JsonValueVc::cell(css.into()),
+            JsonValueVc::cell(resource_path.into()),
+            JsonValueVc::cell(config_sys_path.to_string_lossy().into()),
+            JsonValueVc::cell(fingerprint.into()),
+        ],
+        CompletionVc::immutable(),
+        /* debug */ false,
+    )
+    .await?;
+
+    let JavaScriptValue::Value(val) = &*result else {
+        // The error has been reported as an issue by `evaluate`.
+        return Ok(AssetContent::File(FileContent::NotFound.cell()).cell());
+    };
+    let PostCssResult { css, dependencies } = parse_json_rope_with_source_context(val)?;
+    cache.set(css.clone(), dependencies).await?;
+    Ok(css_content(css))
+}
+
+fn css_content(css: String) -> AssetContentVc {
+    AssetContent::File(FileContent::Content(File::from(css)).cell()).cell()
+}
@@ -0,0 +1,172 @@
+use std::borrow::Cow;
+
+use anyhow::Result;
+use turbo_tasks::primitives::StringVc;
+use turbo_tasks_fs::{glob::GlobVc, File, FileContent, FileSystemPathVc};
+use turbopack_core::{
+    issue::{Issue, IssueSeverity, IssueSeverityVc, IssueVc},
+    resolve::{
+        parse::{Request, RequestVc},
+        plugin::{ResolvePlugin, ResolvePluginConditionVc, ResolvePluginVc},
+        ResolveResult, ResolveResultOptionVc,
+    },
+    virtual_asset::VirtualAssetVc,
+};
+
+/// A resolve plugin which handles the `?raw` and `?url` import queries:
+///
+/// * `import text from "./file?raw"` imports the file contents as a string.
+/// * `import url from "./file?url"` emits the file as an asset and imports its
+///   URL.
+///
+/// Both are handled by a module next to the file, so the `new URL` of a `?url`
+/// module references the file like any other `new URL` does, which emits it.
+#[turbo_tasks::value]
+pub(crate) struct ImportQueryResolvePlugin {
+    root: FileSystemPathVc,
+}
+
+#[turbo_tasks::value_impl]
+impl ImportQueryResolvePluginVc {
+    #[turbo_tasks::function]
+    pub fn new(root: FileSystemPathVc) -> Self {
+        ImportQueryResolvePlugin { root }.cell()
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl ResolvePlugin for ImportQueryResolvePlugin {
+    #[turbo_tasks::function]
+    fn after_resolve_condition(&self) -> ResolvePluginConditionVc {
+        ResolvePluginConditionVc::new(self.root.root(), GlobVc::new("**"))
+    }
+
+    #[turbo_tasks::function]
+    async fn after_resolve(
+        &self,
+        fs_path: FileSystemPathVc,
+        _context: FileSystemPathVc,
+        request: RequestVc,
+    ) -> Result<ResolveResultOptionVc> {
+        let query = match &*request.await? {
+            Request::Relative { query, .. } | Request::Module { query, .. } => *query,
+            _ => return Ok(ResolveResultOptionVc::none()),
+        };
+        let Some(query) = &*query.await? else {
+            return Ok(ResolveResultOptionVc::none());
+        };
+
+        let file_name = fs_path.await?.file_name().to_string();
+        let (kind, code) = if query.contains_key("raw") {
+            let content = &*fs_path.read().await?;
+            let FileContent::Content(file) = content else {
+                return Ok(ResolveResultOptionVc::none());
+            };
+            let (code, is_binary) = raw_module_code(&file.content().to_bytes()?)?;
+            if is_binary {
+                BinaryRawImportIssue { path: fs_path }
+                    .cell()
+                    .as_issue()
+                    .emit();
+            }
+            ("raw", code)
+        } else if query.contains_key("url") {
+            ("url", url_module_code(&file_name)?)
+        } else {
+            return Ok(ResolveResultOptionVc::none());
+        };
+
+        let asset = VirtualAssetVc::new(
+            fs_path.parent().join(&format!("{file_name}?{kind}.js")),
+            FileContent::Content(File::from(code)).into(),
+        );
+
+        Ok(ResolveResultOptionVc::some(
+            ResolveResult::asset(asset.into()).cell(),
+        ))
+    }
+}
+
+/// Returns the code of a `?raw` module exporting `content` as a string, and
+/// whether `content` isn't valid UTF-8, in which case the invalid sequences
+/// are replaced with U+FFFD.
+fn raw_module_code(content: &[u8]) -> Result<(String, bool)> {
+    let text = String::from_utf8_lossy(content);
+    let is_binary = matches!(text, Cow::Owned(_));
+    let code = format!("export default {};\n", serde_json::to_string(&*text)?);
+    Ok((code, is_binary))
+}
+
+/// Returns the code of a `?url` module for the file named `file_name` in the
+/// same directory.
+fn url_module_code(file_name: &str) -> Result<String> {
+    Ok(format!(
+        "export default new URL({}, import.meta.url).href;\n",
+        serde_json::to_string(&format!("./{file_name}"))?
+    ))
+}
+
+/// An issue for a `?raw` import of a file which isn't UTF-8 text.
+#[turbo_tasks::value(shared)]
+pub struct BinaryRawImportIssue {
+    path: FileSystemPathVc,
+}
+
+#[turbo_tasks::value_impl]
+impl Issue for BinaryRawImportIssue {
+    #[turbo_tasks::function]
+    fn severity(&self) -> IssueSeverityVc {
+        IssueSeverity::Warning.into()
+    }
+
+    #[turbo_tasks::function]
+    fn title(&self) -> StringVc {
+        StringVc::cell("Imported `?raw` file is not valid UTF-8".to_string())
+    }
+
+    #[turbo_tasks::function]
+    fn category(&self) -> StringVc {
+        StringVc::cell("resolve".to_string())
+    }
+
+    #[turbo_tasks::function]
+    fn context(&self) -> FileSystemPathVc {
+        self.path
+    }
+
+    #[turbo_tasks::function]
+    fn description(&self) -> StringVc {
+        StringVc::cell(
+            "`?raw` imports the file as a string, so its invalid UTF-8 sequences were replaced \
+             with U+FFFD. Import binary files with `?url` and fetch them instead."
+                .to_string(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{raw_module_code, url_module_code};
+
+    #[test]
+    fn raw_text() {
+        let (code, is_binary) = raw_module_code("line \"one\"\nline two".as_bytes()).unwrap();
+        assert_eq!(code, "export default \"line \\\"one\\\"\\nline two\";\n");
+        assert!(!is_binary);
+    }
+
+    #[test]
+    fn raw_binary() {
+        let (code, is_binary) = raw_module_code(&[0x61, 0xff, 0xfe, 0x62]).unwrap();
+        assert_eq!(code, "export default \"a\u{fffd}\u{fffd}b\";\n");
+        assert!(is_binary);
+    }
+
+    #[test]
+    fn url() {
+        assert_eq!(
+            url_module_code("image.png").unwrap(),
+            "export default new URL(\"./image.png\", import.meta.url).href;\n"
+        );
+    }
+}
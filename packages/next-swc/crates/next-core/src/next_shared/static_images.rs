@@ -0,0 +1,238 @@
+use std::io::Cursor;
+
+use anyhow::Result;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use image::{imageops::FilterType, ImageOutputFormat};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde_json::json;
+use turbo_tasks_fs::{File, FileContent, FileSystemPathVc};
+use turbopack::module_options::{ModuleRule, ModuleRuleCondition, ModuleRuleEffect, ModuleType};
+use turbopack_core::{
+    asset::{Asset, AssetContent, AssetContentVc, AssetVc},
+    ident::AssetIdentVc,
+    reference::AssetReferencesVc,
+    reference_type::{ReferenceType, UrlReferenceSubType},
+    source_transform::{SourceTransform, SourceTransformsVc},
+};
+use turbopack_ecmascript::EcmascriptInputTransformsVc;
+
+use super::transform_cache::TransformCache;
+use crate::{next_config::NextConfigVc, next_image::probe::image_format};
+
+const STATIC_IMAGE_EXTENSIONS: [&str; 7] =
+    [".png", ".jpg", ".jpeg", ".gif", ".webp", ".avif", ".svg"];
+
+/// The size of the longer side of blur placeholders, like in the webpack image
+/// loader of Next.js.
+const BLUR_SIZE: u32 = 8;
+
+/// Returns the module rules for static image imports. Importing an image
+/// returns an object with its `src`, `width` and `height`, which can be passed
+/// to `next/image` as is, and a tiny version of it as `blurDataURL` for
+/// `placeholder="blur"`.
+///
+/// The rules are disabled by `images.disableStaticImages`, and extensions
+/// which are handled by webpack loaders are skipped. The modules are cached on
+/// disk across restarts, as decoding images for the placeholders is slow.
+pub async fn get_static_image_module_rules(
+    project_path: FileSystemPathVc,
+    next_config: NextConfigVc,
+) -> Result<Vec<ModuleRule>> {
+    if next_config.await?.images.disable_static_images {
+        return Ok(vec![]);
+    }
+    let webpack_loaders = next_config.webpack_loaders_options().await?;
+    let mut rules = vec![];
+
+    for extension in STATIC_IMAGE_EXTENSIONS {
+        if webpack_loaders.contains_key(extension) {
+            continue;
+        }
+        rules.push(ModuleRule::new(
+            ModuleRuleCondition::all(vec![
+                // `new URL(...)` references emit the file, see `StaticImageModuleAsset`.
+                ModuleRuleCondition::not(ModuleRuleCondition::ReferenceType(ReferenceType::Url(
+                    UrlReferenceSubType::Undefined,
+                ))),
+                ModuleRuleCondition::ResourcePathEndsWith(extension.to_string()),
+            ]),
+            vec![
+                ModuleRuleEffect::ModuleType(ModuleType::Ecmascript(
+                    EcmascriptInputTransformsVc::cell(vec![]),
+                )),
+                ModuleRuleEffect::SourceTransforms(SourceTransformsVc::cell(vec![
+                    StaticImageTransformVc::new(project_path).into(),
+                ])),
+            ],
+        ));
+    }
+
+    Ok(rules)
+}
+
+/// Converts an image to an ES module exporting its metadata.
+#[turbo_tasks::value]
+struct StaticImageTransform {
+    project_path: FileSystemPathVc,
+}
+
+#[turbo_tasks::value_impl]
+impl StaticImageTransformVc {
+    #[turbo_tasks::function]
+    fn new(project_path: FileSystemPathVc) -> Self {
+        StaticImageTransform { project_path }.cell()
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl SourceTransform for StaticImageTransform {
+    #[turbo_tasks::function]
+    fn transform(&self, source: AssetVc) -> AssetVc {
+        StaticImageModuleAsset {
+            source,
+            project_path: self.project_path,
+        }
+        .cell()
+        .into()
+    }
+}
+
+#[turbo_tasks::value]
+struct StaticImageModuleAsset {
+    source: AssetVc,
+    project_path: FileSystemPathVc,
+}
+
+#[turbo_tasks::value_impl]
+impl Asset for StaticImageModuleAsset {
+    #[turbo_tasks::function]
+    fn ident(&self) -> AssetIdentVc {
+        self.source.ident()
+    }
+
+    #[turbo_tasks::function]
+    async fn content(&self) -> Result<AssetContentVc> {
+        let content = &*self.source.content().file_content().await?;
+        let FileContent::Content(file) = content else {
+            return Ok(AssetContent::File(FileContent::NotFound.cell()).cell());
+        };
+        let bytes = file.content().to_bytes()?;
+        let file_name = self.source.ident().path().await?.file_name().to_string();
+
+        let cache = TransformCache::new(
+            self.project_path,
+            "static-images",
+            &[file_name.as_bytes(), &*bytes],
+        )
+        .await?;
+        if let Some(code) = cache.get_output().await? {
+            return Ok(AssetContent::File(FileContent::Content(File::from(code)).cell()).cell());
+        }
+
+        let mut metadata = serde_json::Map::new();
+        let format = image_format(&bytes);
+        let dimensions = match format {
+            Some(("image/svg+xml", _)) => svg_dimensions(&bytes),
+            Some((_, dimensions)) => dimensions,
+            None => None,
+        };
+        if let Some((width, height)) = dimensions {
+            metadata.insert("width".to_string(), json!(width));
+            metadata.insert("height".to_string(), json!(height));
+            if let Some((content_type, _)) = format {
+                if let Some(placeholder) = blur_placeholder(&bytes, content_type, width, height) {
+                    metadata.insert("blurDataURL".to_string(), json!(placeholder.data_url));
+                    metadata.insert("blurWidth".to_string(), json!(placeholder.width));
+                    metadata.insert("blurHeight".to_string(), json!(placeholder.height));
+                }
+            }
+        }
+
+        // The `new URL` reference emits the file as a static asset. `next/image`
+        // expects a path on the same origin, not an absolute URL.
+        let code = format!(
+            "export default {{\n  src: new URL({}, import.meta.url).pathname,\n  ...{}\n}};\n",
+            serde_json::to_string(&format!("./{file_name}"))?,
+            serde_json::Value::Object(metadata)
+        );
+        cache.set_output(code.clone()).await?;
+        Ok(AssetContent::File(FileContent::Content(File::from(code)).cell()).cell())
+    }
+
+    #[turbo_tasks::function]
+    fn references(&self) -> AssetReferencesVc {
+        AssetReferencesVc::empty()
+    }
+}
+
+struct BlurPlaceholder {
+    data_url: String,
+    width: u32,
+    height: u32,
+}
+
+/// Downscales an image so that its longer side is [BLUR_SIZE] pixels. The
+/// browser blurs it when scaling it back up. Only formats which can be decoded
+/// get a placeholder, and animated images don't.
+fn blur_placeholder(
+    bytes: &[u8],
+    content_type: &str,
+    width: u32,
+    height: u32,
+) -> Option<BlurPlaceholder> {
+    if !matches!(content_type, "image/png" | "image/jpeg" | "image/webp") || width == 0 {
+        return None;
+    }
+    let (blur_width, blur_height) = if width >= height {
+        let height = (height as f64 / width as f64 * BLUR_SIZE as f64).round() as u32;
+        (BLUR_SIZE, height.max(1))
+    } else {
+        let width = (width as f64 / height as f64 * BLUR_SIZE as f64).round() as u32;
+        (width.max(1), BLUR_SIZE)
+    };
+
+    let image = image::load_from_memory(bytes).ok()?;
+    let thumbnail = image.resize_exact(blur_width, blur_height, FilterType::Triangle);
+    let mut png = Vec::new();
+    thumbnail
+        .write_to(&mut Cursor::new(&mut png), ImageOutputFormat::Png)
+        .ok()?;
+    Some(BlurPlaceholder {
+        data_url: format!("data:image/png;base64,{}", STANDARD.encode(png)),
+        width: blur_width,
+        height: blur_height,
+    })
+}
+
+/// Reads the size of an SVG from the `width` and `height` attributes of its
+/// root element, or from its `viewBox`.
+fn svg_dimensions(bytes: &[u8]) -> Option<(u32, u32)> {
+    static SVG_TAG: Lazy<Regex> = Lazy::new(|| Regex::new(r"<svg\b[^>]*>").unwrap());
+    static ATTRIBUTE: Lazy<Regex> =
+        Lazy::new(|| Regex::new(r#"\b(width|height|viewBox)\s*=\s*["']([^"']*)["']"#).unwrap());
+
+    let source = String::from_utf8_lossy(bytes);
+    let tag = SVG_TAG.find(&source)?.as_str();
+    let (mut width, mut height, mut view_box) = (None, None, None);
+    for captures in ATTRIBUTE.captures_iter(tag) {
+        let value = &captures[2];
+        match &captures[1] {
+            // Relative sizes like `100%` don't tell the size of the image.
+            "width" => width = value.trim_end_matches("px").parse::<f64>().ok(),
+            "height" => height = value.trim_end_matches("px").parse::<f64>().ok(),
+            _ => {
+                let values = value
+                    .split(|c: char| c.is_whitespace() || c == ',')
+                    .filter(|value| !value.is_empty())
+                    .filter_map(|value| value.parse::<f64>().ok())
+                    .collect::<Vec<_>>();
+                if let [_, _, width, height] = values[..] {
+                    view_box = Some((width, height));
+                }
+            }
+        }
+    }
+    let (width, height) = width.zip(height).or(view_box)?;
+    Some((width.round() as u32, height.round() as u32))
+}
@@ -0,0 +1,72 @@
+use swc_core::{
+    common::{errors::HANDLER, Span},
+    ecma::{
+        ast::{ArrowExpr, AwaitExpr, Class, ForOfStmt, Function, Module},
+        visit::{noop_visit_mut_type, VisitMut, VisitMutWith},
+    },
+};
+
+/// Reports `await` expressions and `for await` loops at the top level of a
+/// module.
+///
+/// Modules are evaluated synchronously by the turbopack runtime, so a
+/// top-level `await` would only fail once the chunk is evaluated, with a
+/// syntax error pointing into the generated chunk. Reporting it while the
+/// module is transformed points at the original source instead.
+pub struct TopLevelAwait {
+    message: &'static str,
+    span: Option<Span>,
+}
+
+impl TopLevelAwait {
+    pub fn new(message: &'static str) -> Self {
+        Self {
+            message,
+            span: None,
+        }
+    }
+
+    fn found(&mut self, span: Span) {
+        if self.span.is_none() {
+            self.span = Some(span);
+        }
+    }
+}
+
+impl VisitMut for TopLevelAwait {
+    noop_visit_mut_type!();
+
+    fn visit_mut_module(&mut self, module: &mut Module) {
+        module.visit_mut_children_with(self);
+
+        // Only the first occurrence is reported, the others would repeat the
+        // same error.
+        if let Some(span) = self.span {
+            HANDLER.with(|handler| handler.struct_span_err(span, self.message).emit());
+        }
+    }
+
+    fn visit_mut_await_expr(&mut self, await_expr: &mut AwaitExpr) {
+        self.found(await_expr.span);
+    }
+
+    fn visit_mut_for_of_stmt(&mut self, for_of_stmt: &mut ForOfStmt) {
+        if for_of_stmt.is_await {
+            self.found(for_of_stmt.span);
+        } else {
+            for_of_stmt.visit_mut_children_with(self);
+        }
+    }
+
+    // `await` is only at the top level when it's not inside of a function.
+    fn visit_mut_function(&mut self, _function: &mut Function) {}
+
+    fn visit_mut_arrow_expr(&mut self, _arrow_expr: &mut ArrowExpr) {}
+
+    fn visit_mut_class(&mut self, class: &mut Class) {
+        // The heritage and decorators of a class are evaluated with the
+        // class declaration, its members are not.
+        class.decorators.visit_mut_with(self);
+        class.super_class.visit_mut_with(self);
+    }
+}
@@ -0,0 +1,226 @@
+use std::path::{Component, Path};
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use turbo_tasks::primitives::StringVc;
+use turbo_tasks_fs::{
+    to_sys_path, DirectoryContent, DirectoryEntry, File, FileContent, FileSystemPathVc,
+};
+use turbo_tasks_hash::hash_xxh3_hash64;
+
+use super::dependencies::dependencies_fingerprint;
+
+/// Where the outputs of module transforms are cached, relative to the project
+/// directory, e.g. the outputs of PostCSS in `.next/cache/turbopack/postcss`.
+const CACHE_DIR: &str = ".next/cache/turbopack";
+
+/// Changes when the format of the cache entries changes, so entries written by
+/// other versions are ignored.
+const CACHE_VERSION: u32 = 1;
+
+/// A file or directory the output of a transform depends on, besides its
+/// source, e.g. the files matching the `content` globs of Tailwind.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub(crate) enum TransformDependency {
+    File { path: String },
+    Dir { path: String },
+}
+
+/// The cached output of a transform. It's valid while the fingerprint of its
+/// dependencies is unchanged.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TransformCacheEntry {
+    output: String,
+    dependencies: Vec<TransformDependency>,
+    fingerprint: String,
+}
+
+pub(crate) enum TransformCacheLookup {
+    Hit(String),
+    /// The fingerprint of the dependencies of the stale entry, or an empty
+    /// string without an entry. It's passed to the transform, so it runs
+    /// again when a dependency changes.
+    Miss {
+        fingerprint: String,
+    },
+}
+
+/// Caches the outputs of a kind of transform on disk, so they are reused
+/// after a restart of the dev server. Entries are keyed by a hash of what the
+/// output is computed from, e.g. the source and the config of the transform,
+/// and of the installed packages, so upgrading a PostCSS plugin invalidates
+/// them.
+///
+/// Only the slow transforms run by Next.js are cached: PostCSS, the
+/// minification of stylesheets and the placeholders of static images. The
+/// module graph, the SWC transforms, webpack loaders and chunks are computed
+/// by Turbopack in memory, and are computed again after a restart.
+pub(crate) struct TransformCache {
+    project_path: FileSystemPathVc,
+    entry_path: FileSystemPathVc,
+}
+
+impl TransformCache {
+    pub async fn new(project_path: FileSystemPathVc, kind: &str, key: &[&[u8]]) -> Result<Self> {
+        let mut hashed = Vec::new();
+        hashed.extend_from_slice(&CACHE_VERSION.to_le_bytes());
+        hashed.extend_from_slice(&dependencies_fingerprint(project_path).await?.to_le_bytes());
+        for part in key {
+            hashed.extend_from_slice(part);
+            hashed.push(0);
+        }
+        let entry_path = project_path.join(&format!(
+            "{CACHE_DIR}/{kind}/{:016x}.json",
+            hash_xxh3_hash64(&hashed)
+        ));
+        Ok(Self {
+            project_path,
+            entry_path,
+        })
+    }
+
+    /// Returns the cached output, unless a dependency changed since it was
+    /// cached.
+    pub async fn get(&self) -> Result<TransformCacheLookup> {
+        if let FileContent::Content(cached) = &*self.entry_path.read().await? {
+            if let Ok(entry) =
+                serde_json::from_str::<TransformCacheEntry>(&cached.content().to_str()?)
+            {
+                let fingerprint = fingerprint(self.project_path, &entry.dependencies).await?;
+                return Ok(match fingerprint {
+                    Some(fingerprint) if fingerprint == entry.fingerprint => {
+                        TransformCacheLookup::Hit(entry.output)
+                    }
+                    fingerprint => TransformCacheLookup::Miss {
+                        fingerprint: fingerprint.unwrap_or_default(),
+                    },
+                });
+            }
+        }
+        Ok(TransformCacheLookup::Miss {
+            fingerprint: String::new(),
+        })
+    }
+
+    /// Caches the output of a transform. Outputs depending on files which
+    /// can't be tracked, outside of the filesystem of the project, aren't
+    /// cached, as changes to them wouldn't invalidate the entry.
+    pub async fn set(&self, output: String, dependencies: Vec<TransformDependency>) -> Result<()> {
+        let Some(fingerprint) = fingerprint(self.project_path, &dependencies).await? else {
+            return Ok(());
+        };
+        let entry = TransformCacheEntry {
+            fingerprint,
+            output,
+            dependencies,
+        };
+        self.entry_path
+            .write(FileContent::Content(File::from(serde_json::to_string(&entry)?)).cell());
+        Ok(())
+    }
+
+    /// Returns the cached output of a transform which only depends on what
+    /// the cache is keyed by.
+    pub async fn get_output(&self) -> Result<Option<String>> {
+        Ok(match self.get().await? {
+            TransformCacheLookup::Hit(output) => Some(output),
+            TransformCacheLookup::Miss { .. } => None,
+        })
+    }
+
+    /// Caches the output of a transform which only depends on what the cache
+    /// is keyed by.
+    pub async fn set_output(&self, output: String) -> Result<()> {
+        self.set(output, vec![]).await
+    }
+}
+
+/// Hashes the contents of the dependencies. Reading them makes the output
+/// depend on them, e.g. a change to a file matching the `content` of Tailwind
+/// rebuilds the stylesheets using Tailwind.
+///
+/// Dependencies are resolved from the root of the filesystem of the project,
+/// which is the root of the workspace in monorepos, so sibling packages are
+/// tracked. Returns `None` if a dependency is outside of it.
+async fn fingerprint(
+    project_path: FileSystemPathVc,
+    dependencies: &[TransformDependency],
+) -> Result<Option<String>> {
+    if dependencies.is_empty() {
+        return Ok(Some(format!("{:016x}", hash_xxh3_hash64(b""))));
+    }
+    let root = project_path.root();
+    let Some(root_sys_path) = to_sys_path(root).await? else {
+        return Ok(None);
+    };
+    let mut hashes = Vec::new();
+    for dependency in dependencies {
+        let (TransformDependency::File { path } | TransformDependency::Dir { path }) = dependency;
+        let Some(path) = fs_path(root, &root_sys_path, path) else {
+            return Ok(None);
+        };
+        let hash = match dependency {
+            TransformDependency::File { .. } => file_fingerprint(path),
+            TransformDependency::Dir { .. } => directory_fingerprint(path),
+        };
+        hashes.push(hash.await?.clone_value());
+    }
+    Ok(Some(format!(
+        "{:016x}",
+        hash_xxh3_hash64(hashes.join(",").as_bytes())
+    )))
+}
+
+/// Converts an absolute path on disk to a path of the filesystem with the
+/// `root`, or returns `None` if it's outside of it.
+fn fs_path(root: FileSystemPathVc, root_sys_path: &Path, path: &str) -> Option<FileSystemPathVc> {
+    let relative = Path::new(path).strip_prefix(root_sys_path).ok()?;
+    let mut segments = vec![];
+    for component in relative.components() {
+        match component {
+            Component::Normal(segment) => segments.push(segment.to_str()?),
+            Component::CurDir => {}
+            _ => return None,
+        }
+    }
+    Some(root.join(&segments.join("/")))
+}
+
+#[turbo_tasks::function]
+async fn file_fingerprint(path: FileSystemPathVc) -> Result<StringVc> {
+    Ok(StringVc::cell(match &*path.read().await? {
+        FileContent::Content(file) => {
+            format!("{:016x}", hash_xxh3_hash64(&*file.content().to_bytes()?))
+        }
+        FileContent::NotFound => String::new(),
+    }))
+}
+
+/// Hashes the files of a directory and its subdirectories. Dependencies are
+/// tracked by directory, without the globs of their files, and
+/// `node_modules` and hidden directories are skipped.
+#[turbo_tasks::function]
+async fn directory_fingerprint(dir: FileSystemPathVc) -> Result<StringVc> {
+    let DirectoryContent::Entries(entries) = &*dir.read_dir().await? else {
+        return Ok(StringVc::cell(String::new()));
+    };
+    let mut entries = entries.iter().collect::<Vec<_>>();
+    entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+    let mut hashes = Vec::new();
+    for (name, entry) in entries {
+        let hash = match entry {
+            DirectoryEntry::File(file) => file_fingerprint(*file),
+            DirectoryEntry::Directory(dir) if name != "node_modules" && !name.starts_with('.') => {
+                directory_fingerprint(*dir)
+            }
+            _ => continue,
+        };
+        hashes.push(format!("{name}:{}", hash.await?.as_str()));
+    }
+    Ok(StringVc::cell(format!(
+        "{:016x}",
+        hash_xxh3_hash64(hashes.join(",").as_bytes())
+    )))
+}
@@ -1,8 +1,17 @@
 use std::path::PathBuf;
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use next_transform_dynamic::{next_dynamic, NextDynamicMode};
+use next_transform_react_remove_properties::{
+    remove_properties, Config as ReactRemovePropertiesTransformConfig,
+    Options as ReactRemovePropertiesOptions,
+};
+use next_transform_remove_console::{
+    remove_console, Config as RemoveConsoleTransformConfig, Options as RemoveConsoleOptions,
+};
+use next_transform_server_actions::{server_actions, Config as ServerActionsConfig};
 use next_transform_strip_page_exports::{next_transform_strip_page_exports, ExportFilter};
+use regex::Regex;
 use swc_core::{
     common::{util::take::Take, FileName},
     ecma::{
@@ -19,6 +28,12 @@ use turbopack_ecmascript::{
     TransformContext,
 };
 
+use super::{
+    defines::NextRuntime, import_attributes::ImportAttributes,
+    top_level_await::TopLevelAwait, typeof_window::TypeofWindow, worker::WorkerConstructor,
+};
+use crate::next_config::{ReactRemoveProperties, RemoveConsoleConfig};
+
 /// Returns a rule which applies the Next.js page export stripping transform.
 pub async fn get_next_pages_transforms_rule(
     pages_dir: FileSystemPathVc,
@@ -119,6 +134,40 @@ impl CustomTransformer for NextJsDynamic {
     }
 }
 
+/// Returns a rule which applies the Server Actions transform. On the server,
+/// it annotates the actions with their ids and exports inline actions. On the
+/// client, it replaces the actions of `"use server"` files with stubs.
+pub fn get_server_actions_transform_rule(is_server: bool) -> ModuleRule {
+    let transformer =
+        EcmascriptInputTransform::Custom(CustomTransformVc::cell(box NextJsServerActions {
+            is_server,
+        }));
+    ModuleRule::new(
+        module_rule_match_js_no_url(),
+        vec![ModuleRuleEffect::AddEcmascriptTransforms(
+            EcmascriptInputTransformsVc::cell(vec![transformer]),
+        )],
+    )
+}
+
+#[derive(Debug)]
+struct NextJsServerActions {
+    is_server: bool,
+}
+
+impl CustomTransformer for NextJsServerActions {
+    fn transform(&self, program: &mut Program, ctx: &TransformContext<'_>) -> Option<Program> {
+        let module_program = unwrap_module_program(program);
+        Some(module_program.fold_with(&mut server_actions(
+            &FileName::Real(ctx.file_path_str.into()),
+            ServerActionsConfig {
+                is_server: self.is_server,
+            },
+            ctx.comments.clone(),
+        )))
+    }
+}
+
 /// Returns a rule which applies the Next.js font transform.
 pub fn get_next_font_transform_rule() -> ModuleRule {
     #[allow(unused_mut)] // This is mutated when next-font-local is enabled
@@ -157,7 +206,203 @@ impl CustomTransformer for NextJsFont {
     }
 }
 
-fn module_rule_match_js_no_url() -> ModuleRuleCondition {
+/// Returns a rule which applies the `compiler.removeConsole` transform, or
+/// `None` if it is disabled.
+pub fn get_remove_console_transform_rule(config: &RemoveConsoleConfig) -> Option<ModuleRule> {
+    let exclude = match config {
+        RemoveConsoleConfig::Boolean(false) => return None,
+        RemoveConsoleConfig::Boolean(true) => vec![],
+        RemoveConsoleConfig::Config { exclude } => exclude
+            .iter()
+            .flatten()
+            .map(|method| method.as_str().into())
+            .collect(),
+    };
+
+    let transformer =
+        EcmascriptInputTransform::Custom(CustomTransformVc::cell(box NextJsRemoveConsole {
+            exclude,
+        }));
+    Some(ModuleRule::new(
+        module_rule_match_js_no_url(),
+        vec![ModuleRuleEffect::AddEcmascriptTransforms(
+            EcmascriptInputTransformsVc::cell(vec![transformer]),
+        )],
+    ))
+}
+
+#[derive(Debug)]
+struct NextJsRemoveConsole {
+    exclude: Vec<JsWord>,
+}
+
+impl CustomTransformer for NextJsRemoveConsole {
+    fn transform(&self, program: &mut Program, _ctx: &TransformContext<'_>) -> Option<Program> {
+        let module_program = unwrap_module_program(program);
+        let config = RemoveConsoleTransformConfig::WithOptions(RemoveConsoleOptions {
+            exclude: self.exclude.clone(),
+        });
+        Some(module_program.fold_with(&mut remove_console(config)))
+    }
+}
+
+/// Returns a rule which bundles the entries of `new Worker(new URL("./worker",
+/// import.meta.url))` and `SharedWorker` constructors through the
+/// `next-client-worker` transition.
+pub fn get_next_worker_transform_rule() -> ModuleRule {
+    let transformer = EcmascriptInputTransform::Custom(CustomTransformVc::cell(box NextJsWorker));
+    ModuleRule::new(
+        module_rule_match_js_no_url(),
+        vec![ModuleRuleEffect::AddEcmascriptTransforms(
+            EcmascriptInputTransformsVc::cell(vec![transformer]),
+        )],
+    )
+}
+
+#[derive(Debug)]
+struct NextJsWorker;
+
+impl CustomTransformer for NextJsWorker {
+    fn transform(&self, program: &mut Program, ctx: &TransformContext<'_>) -> Option<Program> {
+        let mut module_program = unwrap_module_program(program);
+        module_program.visit_mut_with(&mut WorkerConstructor::new(ctx.unresolved_mark));
+        Some(module_program)
+    }
+}
+
+/// Returns a rule which replaces `typeof window` with its value in the given
+/// runtime.
+pub fn get_typeof_window_transform_rule(runtime: NextRuntime) -> ModuleRule {
+    let transformer =
+        EcmascriptInputTransform::Custom(CustomTransformVc::cell(box NextJsTypeofWindow {
+            value: runtime.typeof_window(),
+        }));
+    ModuleRule::new(
+        module_rule_match_js_no_url(),
+        vec![ModuleRuleEffect::AddEcmascriptTransforms(
+            EcmascriptInputTransformsVc::cell(vec![transformer]),
+        )],
+    )
+}
+
+#[derive(Debug)]
+struct NextJsTypeofWindow {
+    value: &'static str,
+}
+
+impl CustomTransformer for NextJsTypeofWindow {
+    fn transform(&self, program: &mut Program, ctx: &TransformContext<'_>) -> Option<Program> {
+        program.visit_mut_with(&mut TypeofWindow::new(ctx.unresolved_mark, self.value));
+        None
+    }
+}
+
+/// Returns a rule which reports top-level `await` in modules compiled for the
+/// given runtime, which doesn't support it.
+pub fn get_top_level_await_transform_rule(runtime: NextRuntime) -> ModuleRule {
+    let message = match runtime {
+        NextRuntime::Browser => {
+            "Top-level await is not supported in client components and pages. Move the `await` \
+             into an async function, or load the module with `import()`."
+        }
+        NextRuntime::NodeJs | NextRuntime::Edge => {
+            "Top-level await is not supported in server modules. Move the `await` into an async \
+             function, e.g. the component or route handler which needs the value."
+        }
+    };
+    let transformer =
+        EcmascriptInputTransform::Custom(CustomTransformVc::cell(box NextJsTopLevelAwait {
+            message,
+        }));
+    ModuleRule::new(
+        module_rule_match_js_no_url(),
+        vec![ModuleRuleEffect::AddEcmascriptTransforms(
+            EcmascriptInputTransformsVc::cell(vec![transformer]),
+        )],
+    )
+}
+
+#[derive(Debug)]
+struct NextJsTopLevelAwait {
+    message: &'static str,
+}
+
+impl CustomTransformer for NextJsTopLevelAwait {
+    fn transform(&self, program: &mut Program, _ctx: &TransformContext<'_>) -> Option<Program> {
+        program.visit_mut_with(&mut TopLevelAwait::new(self.message));
+        None
+    }
+}
+
+/// Returns a rule which removes the import attributes of static imports, e.g.
+/// `import data from "./data.json" assert { type: "json" }`, and reports the
+/// unsupported ones.
+pub fn get_import_attributes_transform_rule() -> ModuleRule {
+    let transformer =
+        EcmascriptInputTransform::Custom(CustomTransformVc::cell(box NextJsImportAttributes));
+    ModuleRule::new(
+        module_rule_match_js_no_url(),
+        vec![ModuleRuleEffect::AddEcmascriptTransforms(
+            EcmascriptInputTransformsVc::cell(vec![transformer]),
+        )],
+    )
+}
+
+#[derive(Debug)]
+struct NextJsImportAttributes;
+
+impl CustomTransformer for NextJsImportAttributes {
+    fn transform(&self, program: &mut Program, _ctx: &TransformContext<'_>) -> Option<Program> {
+        program.visit_mut_with(&mut ImportAttributes);
+        None
+    }
+}
+
+/// Returns the `compiler.reactRemoveProperties` transform, or `None` if it is
+/// disabled.
+///
+/// This has to run before JSX is transformed, so it is returned as a plain
+/// transform instead of a module rule.
+pub fn get_react_remove_properties_transform(
+    config: &ReactRemoveProperties,
+) -> Result<Option<EcmascriptInputTransform>> {
+    let properties = match config {
+        ReactRemoveProperties::Boolean(false) => return Ok(None),
+        ReactRemoveProperties::Boolean(true) => vec![],
+        ReactRemoveProperties::Config { properties } => properties.clone().unwrap_or_default(),
+    };
+
+    // The transform panics on invalid patterns, they're reported here instead.
+    for pattern in properties.iter() {
+        Regex::new(pattern).with_context(|| {
+            format!("invalid regex `{pattern}` in compiler.reactRemoveProperties")
+        })?;
+    }
+
+    let transformer =
+        EcmascriptInputTransform::Custom(CustomTransformVc::cell(box NextJsReactRemoveProperties {
+            properties,
+        }));
+    Ok(Some(transformer))
+}
+
+#[derive(Debug)]
+struct NextJsReactRemoveProperties {
+    properties: Vec<String>,
+}
+
+impl CustomTransformer for NextJsReactRemoveProperties {
+    fn transform(&self, program: &mut Program, _ctx: &TransformContext<'_>) -> Option<Program> {
+        let module_program = unwrap_module_program(program);
+        let config =
+            ReactRemovePropertiesTransformConfig::WithOptions(ReactRemovePropertiesOptions {
+                properties: self.properties.clone(),
+            });
+        Some(module_program.fold_with(&mut remove_properties(config)))
+    }
+}
+
+pub(crate) fn module_rule_match_js_no_url() -> ModuleRuleCondition {
     ModuleRuleCondition::all(vec![
         ModuleRuleCondition::not(ModuleRuleCondition::ReferenceType(ReferenceType::Url(
             UrlReferenceSubType::Undefined,
@@ -0,0 +1,54 @@
+use swc_core::{
+    common::Mark,
+    ecma::{
+        ast::{Expr, Lit, Str, UnaryExpr, UnaryOp},
+        visit::{noop_visit_mut_type, VisitMut, VisitMutWith},
+    },
+};
+
+/// Replaces `typeof window` with its value in the target runtime, so branches
+/// on it are eliminated when the condition is constant.
+///
+/// Only the global `window` is replaced, local bindings are left untouched.
+pub struct TypeofWindow {
+    unresolved_mark: Mark,
+    value: &'static str,
+}
+
+impl TypeofWindow {
+    pub fn new(unresolved_mark: Mark, value: &'static str) -> Self {
+        Self {
+            unresolved_mark,
+            value,
+        }
+    }
+}
+
+impl VisitMut for TypeofWindow {
+    noop_visit_mut_type!();
+
+    fn visit_mut_expr(&mut self, expr: &mut Expr) {
+        expr.visit_mut_children_with(self);
+
+        let Expr::Unary(UnaryExpr {
+            op: UnaryOp::TypeOf,
+            arg,
+            span,
+        }) = expr
+        else {
+            return;
+        };
+        if !matches!(&**arg, Expr::Ident(ident)
+            if &*ident.sym == "window" && ident.span.ctxt.outer() == self.unresolved_mark)
+        {
+            return;
+        }
+
+        let span = *span;
+        *expr = Expr::Lit(Lit::Str(Str {
+            span,
+            value: self.value.into(),
+            raw: None,
+        }));
+    }
+}
@@ -0,0 +1,163 @@
+use swc_core::{
+    common::{Mark, DUMMY_SP},
+    ecma::{
+        ast::{
+            CallExpr, Callee, Expr, ExprOrSpread, ExprStmt, Ident, ImportDecl,
+            ImportDefaultSpecifier, ImportNamedSpecifier, ImportSpecifier, Lit, MetaPropKind,
+            Module, ModuleDecl, ModuleItem, NewExpr, Stmt, Str,
+        },
+        atoms::JsWord,
+        visit::{noop_visit_mut_type, VisitMut, VisitMutWith},
+    },
+};
+
+const WORKER_HELPERS_MODULE: &str = "@vercel/turbopack-next/internal/worker";
+const WORKER_ARGS_IDENT: &str = "__turbopack_next_worker_args__";
+
+/// Rewrites `new Worker(new URL("./worker", import.meta.url))` (and the same
+/// for `SharedWorker`) so the worker entry is compiled as its own chunk group
+/// through the `next-client-worker` transition.
+///
+/// Only the global `Worker` and `SharedWorker` constructors are rewritten, and
+/// only when the URL is a string literal relative to `import.meta.url`.
+pub struct WorkerConstructor {
+    unresolved_mark: Mark,
+    workers: Vec<(Ident, JsWord)>,
+}
+
+impl WorkerConstructor {
+    pub fn new(unresolved_mark: Mark) -> Self {
+        Self {
+            unresolved_mark,
+            workers: vec![],
+        }
+    }
+
+    fn is_global(&self, expr: &Expr, names: &[&str]) -> bool {
+        matches!(expr, Expr::Ident(ident)
+            if names.contains(&&*ident.sym) && ident.span.ctxt.outer() == self.unresolved_mark)
+    }
+
+    /// Returns the specifier of `new URL("<specifier>", import.meta.url)`.
+    fn worker_specifier(&self, arg: &ExprOrSpread) -> Option<JsWord> {
+        if arg.spread.is_some() {
+            return None;
+        }
+        let Expr::New(new_url) = &*arg.expr else {
+            return None;
+        };
+        if !self.is_global(&new_url.callee, &["URL"]) {
+            return None;
+        }
+        let Some(args) = &new_url.args else {
+            return None;
+        };
+        let [specifier, base] = &args[..] else {
+            return None;
+        };
+        if specifier.spread.is_some() || base.spread.is_some() {
+            return None;
+        }
+        let Expr::Member(member) = &*base.expr else {
+            return None;
+        };
+        match (&*member.obj, member.prop.as_ident()) {
+            (Expr::MetaProp(meta), Some(prop))
+                if meta.kind == MetaPropKind::ImportMeta && &*prop.sym == "url" => {}
+            _ => return None,
+        }
+        match &*specifier.expr {
+            Expr::Lit(Lit::Str(Str { value, .. })) => Some(value.clone()),
+            _ => None,
+        }
+    }
+}
+
+impl VisitMut for WorkerConstructor {
+    noop_visit_mut_type!();
+
+    fn visit_mut_new_expr(&mut self, new_expr: &mut NewExpr) {
+        new_expr.visit_mut_children_with(self);
+
+        if !self.is_global(&new_expr.callee, &["Worker", "SharedWorker"]) {
+            return;
+        }
+        let Some(args) = &mut new_expr.args else {
+            return;
+        };
+        let Some(specifier) = args.first().and_then(|arg| self.worker_specifier(arg)) else {
+            return;
+        };
+
+        let chunks_ident = Ident::new(
+            format!("__turbopack_next_worker_{}__", self.workers.len()).into(),
+            DUMMY_SP,
+        );
+        self.workers.push((chunks_ident.clone(), specifier));
+
+        // `new Worker(...workerArgs(chunks, options))`: the helper returns the
+        // URL of a script bootstrapping the worker chunks, together with the
+        // options the worker must be created with.
+        let mut helper_args = vec![ExprOrSpread {
+            spread: None,
+            expr: Box::new(Expr::Ident(chunks_ident)),
+        }];
+        helper_args.extend(args.drain(..).nth(1));
+        *args = vec![ExprOrSpread {
+            spread: Some(DUMMY_SP),
+            expr: Box::new(Expr::Call(CallExpr {
+                span: DUMMY_SP,
+                callee: Callee::Expr(Box::new(Expr::Ident(Ident::new(
+                    WORKER_ARGS_IDENT.into(),
+                    DUMMY_SP,
+                )))),
+                args: helper_args,
+                type_args: None,
+            })),
+        }];
+    }
+
+    fn visit_mut_module(&mut self, module: &mut Module) {
+        module.visit_mut_children_with(self);
+
+        if self.workers.is_empty() {
+            return;
+        }
+
+        let mut items = vec![ModuleItem::ModuleDecl(ModuleDecl::Import(ImportDecl {
+            span: DUMMY_SP,
+            specifiers: vec![ImportSpecifier::Named(ImportNamedSpecifier {
+                span: DUMMY_SP,
+                local: Ident::new(WORKER_ARGS_IDENT.into(), DUMMY_SP),
+                imported: Some(Ident::new("workerArgs".into(), DUMMY_SP).into()),
+                is_type_only: false,
+            })],
+            src: Box::new(WORKER_HELPERS_MODULE.into()),
+            type_only: false,
+            asserts: None,
+        }))];
+        for (chunks_ident, specifier) in self.workers.drain(..) {
+            // The `next-client-worker` transition returns the chunks needed to
+            // run the worker entry.
+            items.push(ModuleItem::Stmt(Stmt::Expr(ExprStmt {
+                span: DUMMY_SP,
+                expr: Box::new(Expr::Lit(Lit::Str(
+                    "TURBOPACK { transition: next-client-worker }".into(),
+                ))),
+            })));
+            items.push(ModuleItem::ModuleDecl(ModuleDecl::Import(ImportDecl {
+                span: DUMMY_SP,
+                specifiers: vec![ImportSpecifier::Default(ImportDefaultSpecifier {
+                    span: DUMMY_SP,
+                    local: chunks_ident,
+                })],
+                src: Box::new(specifier.into()),
+                type_only: false,
+                asserts: None,
+            })));
+        }
+
+        items.append(&mut module.body);
+        module.body = items;
+    }
+}
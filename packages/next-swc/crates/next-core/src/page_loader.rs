@@ -2,6 +2,7 @@ use std::io::Write;
 
 use anyhow::{bail, Result};
 use indexmap::indexmap;
+use tracing::Instrument;
 use turbo_tasks::{primitives::StringVc, TryJoinIterExt, Value};
 use turbo_tasks_fs::{rope::RopeBuilder, File, FileContent, FileSystemPathVc};
 use turbopack_core::{
@@ -78,30 +79,38 @@ impl PageLoaderAssetVc {
 
     #[turbo_tasks::function]
     async fn get_page_chunks(self) -> Result<ChunksVc> {
-        let this = &*self.await?;
-
-        let loader_entry_asset = self.get_loader_entry_asset();
-
-        let asset = EcmascriptModuleAssetVc::new_with_inner_assets(
-            loader_entry_asset,
-            this.client_context,
-            Value::new(EcmascriptModuleAssetType::Typescript),
-            EcmascriptInputTransformsVc::cell(vec![EcmascriptInputTransform::TypeScript {
-                use_define_for_class_fields: false,
-            }]),
-            this.client_context.compile_time_info(),
-            InnerAssetsVc::cell(indexmap! {
-                "PAGE".to_string() => this.client_context.process(this.entry_asset, Value::new(ReferenceType::Entry(EntryReferenceSubType::Page)))
-            }),
-        );
-
-        let chunk_group =
-            ChunkGroupVc::from_chunk(asset.as_evaluated_chunk(this.client_chunking_context, None));
-
-        Ok(chunk_group.chunks())
+        let span = tracing::info_span!("chunking", page = %self.await?.pathname.await?);
+        page_chunks(self).instrument(span).await
     }
 }
 
+async fn page_chunks(self_vc: PageLoaderAssetVc) -> Result<ChunksVc> {
+    let this = &*self_vc.await?;
+
+    let loader_entry_asset = self_vc.get_loader_entry_asset();
+
+    let asset = EcmascriptModuleAssetVc::new_with_inner_assets(
+        loader_entry_asset,
+        this.client_context,
+        Value::new(EcmascriptModuleAssetType::Typescript),
+        EcmascriptInputTransformsVc::cell(vec![EcmascriptInputTransform::TypeScript {
+            use_define_for_class_fields: false,
+        }]),
+        this.client_context.compile_time_info(),
+        InnerAssetsVc::cell(indexmap! {
+            "PAGE".to_string() => this.client_context.process(this.entry_asset, Value::new(ReferenceType::Entry(EntryReferenceSubType::Page)))
+        }),
+    );
+
+    let chunk_group =
+        ChunkGroupVc::from_chunk(asset.as_evaluated_chunk(this.client_chunking_context, None));
+
+    // Awaited here, so the chunking of the page is part of its span.
+    let chunks = chunk_group.chunks();
+    chunks.await?;
+    Ok(chunks)
+}
+
 #[turbo_tasks::value_impl]
 impl Asset for PageLoaderAsset {
     #[turbo_tasks::function]
@@ -7,7 +7,7 @@ use turbo_tasks::{
     Value,
 };
 use turbo_tasks_env::{CustomProcessEnvVc, EnvMapVc, ProcessEnvVc};
-use turbo_tasks_fs::{FileContent, FileSystemPathVc};
+use turbo_tasks_fs::FileSystemPathVc;
 use turbopack::{transition::TransitionsByNameVc, ModuleAssetContextVc};
 use turbopack_core::{
     asset::AssetVc,
@@ -45,13 +45,16 @@ use crate::{
     embed_js::{next_asset, next_js_file},
     env::env_for_js,
     fallback::get_fallback_page,
+    global_css::validate_global_css_imports,
+    mode::NextMode,
     next_client::{
         context::{
             get_client_assets_path, get_client_chunking_context, get_client_compile_time_info,
-            get_client_module_options_context, get_client_resolve_options_context,
+            get_client_module_options_context, get_client_resolve_options_context, get_client_root,
             get_client_runtime_entries, ClientContextType,
         },
         transition::NextClientTransition,
+        worker_transition::NextClientWorkerTransitionVc,
     },
     next_client_chunks::client_chunks_transition::NextClientChunksTransitionVc,
     next_config::NextConfigVc,
@@ -67,10 +70,13 @@ use crate::{
         get_server_compile_time_info, get_server_module_options_context,
         get_server_resolve_options_context, ServerContextType,
     },
+    next_shared::dependencies::get_dependencies_runtime_entry,
     page_loader::create_page_loader,
     pages_structure::{
-        OptionPagesStructureVc, PagesStructure, PagesStructureItem, PagesStructureVc,
+        get_pages_special_files, OptionPagesStructureVc, PagesStructure, PagesStructureItem,
+        PagesStructureVc,
     },
+    router::{INTERNAL_ERROR_PATHNAME, NOT_FOUND_PATHNAME},
     util::{parse_config_from_source, pathname_for_path, NextRuntime},
 };
 
@@ -97,12 +103,18 @@ pub async fn create_page_source(
     let server_ty = Value::new(ServerContextType::Pages { pages_dir });
     let server_data_ty = Value::new(ServerContextType::PagesData { pages_dir });
 
-    let client_compile_time_info = get_client_compile_time_info(browserslist_query);
+    let client_compile_time_info = get_client_compile_time_info(
+        browserslist_query,
+        env,
+        Value::new(NextMode::Development),
+        next_config,
+    );
     let client_module_options_context = get_client_module_options_context(
         project_path,
         execution_context,
         client_compile_time_info.environment(),
         client_ty,
+        Value::new(NextMode::Development),
         next_config,
     );
     let client_resolve_options_context =
@@ -113,14 +125,22 @@ pub async fn create_page_source(
         server_root,
         client_compile_time_info.environment(),
         client_ty,
+        Value::new(NextMode::Development),
     );
 
-    let client_runtime_entries =
-        get_client_runtime_entries(project_path, env, client_ty, next_config, execution_context);
+    let client_runtime_entries = get_client_runtime_entries(
+        project_path,
+        env,
+        client_ty,
+        Value::new(NextMode::Development),
+        next_config,
+        execution_context,
+    );
 
     let next_client_transition = NextClientTransition {
         is_app: false,
         client_chunking_context,
+        client_root: get_client_root(server_root, Value::new(NextMode::Development)),
         client_module_options_context,
         client_resolve_options_context,
         client_compile_time_info,
@@ -129,8 +149,24 @@ pub async fn create_page_source(
     .cell()
     .into();
 
-    let edge_compile_time_info =
-        get_edge_compile_time_info(server_addr, Value::new(EnvironmentIntention::Api));
+    let next_client_worker_transition = NextClientWorkerTransitionVc::new(
+        project_path,
+        execution_context,
+        client_ty,
+        Value::new(NextMode::Development),
+        server_root,
+        env,
+        client_compile_time_info,
+        next_config,
+    )
+    .into();
+
+    let edge_compile_time_info = get_edge_compile_time_info(
+        server_addr,
+        Value::new(EnvironmentIntention::Api),
+        Value::new(NextMode::Development),
+        next_config,
+    );
 
     let edge_chunking_context = DevChunkingContextVc::builder(
         project_path,
@@ -159,7 +195,13 @@ pub async fn create_page_source(
     .cell()
     .into();
 
-    let server_compile_time_info = get_server_compile_time_info(server_ty, env, server_addr);
+    let server_compile_time_info = get_server_compile_time_info(
+        server_ty,
+        env,
+        server_addr,
+        Value::new(NextMode::Development),
+        next_config,
+    );
     let server_resolve_options_context =
         get_server_resolve_options_context(project_path, server_ty, next_config, execution_context);
 
@@ -177,12 +219,17 @@ pub async fn create_page_source(
         [
             ("next-edge".to_string(), next_edge_transition),
             ("next-client".to_string(), next_client_transition),
+            (
+                "next-client-worker".to_string(),
+                next_client_worker_transition,
+            ),
             (
                 "next-client-chunks".to_string(),
                 NextClientChunksTransitionVc::new(
                     project_path,
                     execution_context,
                     client_ty,
+                    Value::new(NextMode::Development),
                     server_root,
                     client_compile_time_info,
                     next_config,
@@ -219,8 +266,10 @@ pub async fn create_page_source(
     let injected_env = env_for_js(EnvMapVc::empty().into(), false, next_config);
     let env = CustomProcessEnvVc::new(env, next_config.env()).as_process_env();
 
-    let server_runtime_entries =
-        vec![ProcessEnvAssetVc::new(project_path, injected_env).as_ecmascript_chunk_placeable()];
+    let server_runtime_entries = vec![
+        ProcessEnvAssetVc::new(project_path, injected_env).as_ecmascript_chunk_placeable(),
+        get_dependencies_runtime_entry(project_path, server_context),
+    ];
     let server_runtime_entries = EcmascriptChunkPlaceablesVc::cell(server_runtime_entries);
 
     let fallback_page = get_fallback_page(
@@ -233,7 +282,7 @@ pub async fn create_page_source(
     );
 
     let page_extensions = next_config.page_extensions();
-    let force_not_found_source = create_not_found_page_source(
+    let force_not_found_source = create_error_page_source(
         project_path,
         env,
         server_context,
@@ -245,9 +294,10 @@ pub async fn create_page_source(
         server_root,
         output_path.join("force_not_found"),
         SpecificityVc::exact(),
-        NextExactMatcherVc::new(StringVc::cell("_next/404".to_string())).into(),
+        NextExactMatcherVc::new(StringVc::cell(NOT_FOUND_PATHNAME.to_string())).into(),
+        404,
     );
-    let fallback_not_found_source = create_not_found_page_source(
+    let fallback_not_found_source = create_error_page_source(
         project_path,
         env,
         server_context,
@@ -260,6 +310,22 @@ pub async fn create_page_source(
         output_path.join("fallback_not_found"),
         SpecificityVc::not_found(),
         NextFallbackMatcherVc::new().into(),
+        404,
+    );
+    let force_internal_error_source = create_error_page_source(
+        project_path,
+        env,
+        server_context,
+        client_context,
+        pages_dir,
+        page_extensions,
+        server_runtime_entries,
+        fallback_page,
+        server_root,
+        output_path.join("force_internal_error"),
+        SpecificityVc::exact(),
+        NextExactMatcherVc::new(StringVc::cell(INTERNAL_ERROR_PATHNAME.to_string())).into(),
+        500,
     );
     let page_source = create_page_source_for_directory(
         pages_structure,
@@ -281,6 +347,8 @@ pub async fn create_page_source(
         sources: vec![
             // Match _next/404 first to ensure rewrites work properly.
             force_not_found_source.issue_context(pages_dir, "Next.js pages directory not found"),
+            force_internal_error_source
+                .issue_context(pages_dir, "Next.js pages directory internal error"),
             page_source,
             fallback_source
                 .as_content_source()
@@ -344,6 +412,7 @@ async fn create_page_source_for_file(
         server_root,
         client_context.compile_time_info().environment(),
         Value::new(ClientContextType::Pages { pages_dir }),
+        Value::new(NextMode::Development),
     );
 
     let pathname = pathname_for_path(server_root, server_path, true, false);
@@ -358,6 +427,7 @@ async fn create_page_source_for_file(
             route_matcher.into(),
             pathname,
             SsrEntry {
+                project_path,
                 context: server_context,
                 entry_asset: page_asset,
                 ty: SsrType::AutoApi,
@@ -375,6 +445,7 @@ async fn create_page_source_for_file(
             NextPrefixSuffixParamsMatcherVc::new(data_pathname, "_next/data/development/", ".json");
 
         let ssr_entry = SsrEntry {
+            project_path,
             context: server_context,
             entry_asset: page_asset,
             ty: SsrType::Html,
@@ -386,6 +457,7 @@ async fn create_page_source_for_file(
         .into();
 
         let ssr_data_entry = SsrEntry {
+            project_path,
             context: server_data_context,
             entry_asset: page_asset,
             ty: SsrType::Data,
@@ -431,23 +503,10 @@ async fn create_page_source_for_file(
     })
 }
 
-async fn get_not_found_page(
-    pages_dir: FileSystemPathVc,
-    page_extensions: StringsVc,
-) -> Result<Option<AssetVc>> {
-    for ext in page_extensions.await?.iter() {
-        let not_found_path = pages_dir.join(&format!("404.{ext}"));
-        let content = not_found_path.read();
-        if let FileContent::Content(_) = &*content.await? {
-            return Ok(Some(SourceAssetVc::new(not_found_path).into()));
-        }
-    }
-    Ok(None)
-}
-
-/// Handles a single page file in the pages directory
+/// Renders the custom error page of a status code, `404` or `500`, or the
+/// built-in error page when the pages directory doesn't have one.
 #[turbo_tasks::function]
-async fn create_not_found_page_source(
+async fn create_error_page_source(
     project_path: FileSystemPathVc,
     env: ProcessEnvVc,
     server_context: AssetContextVc,
@@ -460,6 +519,7 @@ async fn create_not_found_page_source(
     intermediate_output_path: FileSystemPathVc,
     specificity: SpecificityVc,
     route_matcher: RouteMatcherVc,
+    status_code: u16,
 ) -> Result<ContentSourceVc> {
     let server_chunking_context = DevChunkingContextVc::builder(
         project_path,
@@ -478,21 +538,29 @@ async fn create_not_found_page_source(
         server_root,
         client_context.compile_time_info().environment(),
         Value::new(ClientContextType::Pages { pages_dir }),
+        Value::new(NextMode::Development),
     );
 
-    let (page_asset, pathname) =
-        if let Some(not_found_page_asset) = get_not_found_page(pages_dir, page_extensions).await? {
-            // If a 404 page is defined, the pathname should be 404.
-            (not_found_page_asset, StringVc::cell("404".to_string()))
-        } else {
-            (
-                // The error page asset must be within the context path so it can depend on the
-                // Next.js module.
-                next_asset("entry/error.tsx"),
-                // If no 404 page is defined, the pathname should be _error.
-                StringVc::cell("_error".to_string()),
-            )
-        };
+    let special_files = get_pages_special_files(pages_dir, page_extensions).await?;
+    let error_page = match status_code {
+        404 => &special_files.not_found,
+        _ => &special_files.internal_error,
+    };
+    let (page_asset, pathname): (AssetVc, _) = if let Some(error_page) = error_page {
+        // If a custom error page is defined, the pathname is its status code.
+        (
+            SourceAssetVc::new(pages_dir.join(error_page)).into(),
+            StringVc::cell(status_code.to_string()),
+        )
+    } else {
+        (
+            // The error page asset must be within the context path so it can depend on the
+            // Next.js module.
+            next_asset("entry/error.tsx"),
+            // If no custom error page is defined, the pathname should be _error.
+            StringVc::cell("_error".to_string()),
+        )
+    };
 
     let entry_asset = server_context.process(
         page_asset,
@@ -500,6 +568,7 @@ async fn create_not_found_page_source(
     );
 
     let ssr_entry = SsrEntry {
+        project_path,
         context: server_context,
         entry_asset,
         ty: SsrType::Html,
@@ -642,6 +711,7 @@ enum SsrType {
 /// The node.js renderer for SSR of pages.
 #[turbo_tasks::value]
 struct SsrEntry {
+    project_path: FileSystemPathVc,
     context: AssetContextVc,
     entry_asset: AssetVc,
     ty: SsrType,
@@ -697,6 +767,7 @@ impl SsrEntryVc {
                 },
             ),
             SsrType::Html => {
+                validate_global_css_imports(this.project_path, entry_asset_page).await?;
                 let entry_asset_client_chunk_group =
                     this.context.with_transition("next-client").process(
                         this.entry_asset,
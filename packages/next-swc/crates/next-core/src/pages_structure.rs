@@ -106,6 +106,54 @@ impl OptionPagesStructureVc {
     }
 }
 
+/// The files at the root of the pages directory which customize all pages.
+/// `None` when the project doesn't have the file, so the built-in one of
+/// Next.js is used.
+#[turbo_tasks::value]
+#[derive(Default)]
+pub struct PagesSpecialFiles {
+    /// The file name of `_app`, e.g. `_app.tsx`.
+    pub app: Option<String>,
+    pub document: Option<String>,
+    pub error: Option<String>,
+    /// The custom `404` page, which is a route too.
+    pub not_found: Option<String>,
+    /// The custom `500` page, which is a route too.
+    pub internal_error: Option<String>,
+}
+
+/// Basenames of the [PagesSpecialFiles] which aren't routes.
+const SPECIAL_PAGES: [&str; 3] = ["_app", "_document", "_error"];
+
+/// Finds the [PagesSpecialFiles] of a pages directory. It's invalidated when
+/// they are added or removed, which updates the pages using them.
+#[turbo_tasks::function]
+pub async fn get_pages_special_files(
+    pages_dir: FileSystemPathVc,
+    page_extensions: StringsVc,
+) -> Result<PagesSpecialFilesVc> {
+    let mut special_files = PagesSpecialFiles::default();
+    let DirectoryContent::Entries(entries) = &*pages_dir.read_dir().await? else {
+        return Ok(special_files.cell());
+    };
+    // The first page extension takes precedence, like for routes.
+    for extension in page_extensions.await?.iter().rev() {
+        for (basename, file) in [
+            ("_app", &mut special_files.app),
+            ("_document", &mut special_files.document),
+            ("_error", &mut special_files.error),
+            ("404", &mut special_files.not_found),
+            ("500", &mut special_files.internal_error),
+        ] {
+            let name = format!("{basename}.{extension}");
+            if let Some(DirectoryEntry::File(_)) = entries.get(&name) {
+                *file = Some(name);
+            }
+        }
+    }
+    Ok(special_files.cell())
+}
+
 /// Finds and returns the [PagesStructure] of the pages directory if existing.
 #[turbo_tasks::function]
 pub async fn find_pages_structure(
@@ -178,6 +226,10 @@ async fn get_pages_structure_for_directory(
             match entry {
                 DirectoryEntry::File(file) => {
                     if let Some((basename, extension)) = name.rsplit_once('.') {
+                        if position == 0 && SPECIAL_PAGES.contains(&basename) {
+                            // Customizes all pages, see [PagesSpecialFiles].
+                            continue;
+                        }
                         if page_extensions_raw
                             .iter()
                             .any(|allowed| allowed == extension)
@@ -0,0 +1,273 @@
+use anyhow::Result;
+use turbo_tasks::{
+    primitives::{StringVc, StringsVc},
+    CompletionVc,
+};
+use turbo_tasks_fs::{DirectoryContent, DirectoryEntry, FileSystemEntryType, FileSystemPathVc};
+use turbopack_core::issue::{Issue, IssueSeverity, IssueSeverityVc, IssueVc};
+
+use crate::next_config::NextConfigVc;
+
+/// Characters which can't be part of a URL path without being encoded, so a
+/// route with them in its name can't be requested as written.
+const INVALID_ROUTE_CHARS: [char; 13] = [
+    '#', '?', '%', '\\', '"', '<', '>', '|', '^', '`', '{', '}', ' ',
+];
+
+/// Validates the structure of the project before its routes are compiled, and
+/// emits an issue for every kind of problem found, listing all the affected
+/// directories and files:
+///
+/// - neither a `pages` nor an `app` directory exists,
+/// - both `app` and `src/app` (or `pages` and `src/pages`) exist, in which case
+///   the one in `src` is ignored,
+/// - routes have names which can't be requested, like `about us` or `[slug`.
+#[turbo_tasks::function]
+pub async fn validate_project_structure(
+    project_path: FileSystemPathVc,
+    next_config: NextConfigVc,
+) -> Result<CompletionVc> {
+    let app_dir_enabled = *next_config.app_dir().await?;
+    let mut route_dirs = Vec::new();
+    let mut conflicts = Vec::new();
+    for (name, enabled) in [("pages", true), ("app", app_dir_enabled)] {
+        if !enabled {
+            continue;
+        }
+        let dir = project_path.join(name);
+        let src_dir = project_path.join(&format!("src/{name}"));
+        let exists = is_directory(dir).await?;
+        let src_exists = is_directory(src_dir).await?;
+        if exists && src_exists {
+            conflicts.push(name.to_string());
+        }
+        if exists {
+            route_dirs.push((dir, name == "app"));
+        } else if src_exists {
+            route_dirs.push((src_dir, name == "app"));
+        }
+    }
+
+    if route_dirs.is_empty() {
+        ProjectStructureIssue {
+            path: project_path,
+            problem: ProjectStructureProblem::NoRouteDirectory { app_dir_enabled },
+        }
+        .cell()
+        .as_issue()
+        .emit();
+    }
+
+    if !conflicts.is_empty() {
+        ProjectStructureIssue {
+            path: project_path,
+            problem: ProjectStructureProblem::ConflictingSrcDirectories { names: conflicts },
+        }
+        .cell()
+        .as_issue()
+        .emit();
+    }
+
+    let mut invalid = Vec::new();
+    for (dir, is_app_dir) in route_dirs {
+        invalid.extend(
+            invalid_route_names(project_path, dir, is_app_dir)
+                .await?
+                .iter()
+                .cloned(),
+        );
+    }
+    if !invalid.is_empty() {
+        ProjectStructureIssue {
+            path: project_path,
+            problem: ProjectStructureProblem::InvalidRouteNames { paths: invalid },
+        }
+        .cell()
+        .as_issue()
+        .emit();
+    }
+
+    Ok(CompletionVc::new())
+}
+
+async fn is_directory(path: FileSystemPathVc) -> Result<bool> {
+    Ok(*path.get_type().await? == FileSystemEntryType::Directory)
+}
+
+/// Returns the paths, relative to the project, of the directories and files in
+/// a routes directory whose names can't be part of a URL.
+#[turbo_tasks::function]
+async fn invalid_route_names(
+    project_path: FileSystemPathVc,
+    dir: FileSystemPathVc,
+    is_app_dir: bool,
+) -> Result<StringsVc> {
+    let DirectoryContent::Entries(entries) = &*dir.read_dir().await? else {
+        return Ok(StringsVc::cell(vec![]));
+    };
+    let project = project_path.await?;
+    let mut invalid = Vec::new();
+    let mut entries = entries.iter().collect::<Vec<_>>();
+    entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+    for (name, entry) in entries {
+        let (path, segment, directory) = match *entry {
+            DirectoryEntry::File(path) => {
+                let segment = name
+                    .rsplit_once('.')
+                    .map_or(name.as_str(), |(stem, _)| stem);
+                (path, segment, None)
+            }
+            DirectoryEntry::Directory(path) => {
+                // Private folders of the app directory aren't routes.
+                if (is_app_dir && name.starts_with('_')) || name == "node_modules" {
+                    continue;
+                }
+                (path, name.as_str(), Some(path))
+            }
+            _ => continue,
+        };
+        if !is_valid_segment(segment) {
+            if let Some(relative) = project.get_path_to(&*path.await?) {
+                invalid.push(relative.to_string());
+            }
+        }
+        if let Some(directory) = directory {
+            invalid.extend(
+                invalid_route_names(project_path, directory, is_app_dir)
+                    .await?
+                    .iter()
+                    .cloned(),
+            );
+        }
+    }
+    Ok(StringsVc::cell(invalid))
+}
+
+/// Whether a directory or file name can be a segment of a route: it doesn't
+/// contain characters which need to be encoded in a URL, and brackets only
+/// appear around the name of a dynamic segment, like `[slug]`, `[...slug]` or
+/// `[[...slug]]`.
+fn is_valid_segment(segment: &str) -> bool {
+    if segment.contains(INVALID_ROUTE_CHARS) {
+        return false;
+    }
+    if !segment.contains(['[', ']']) {
+        return true;
+    }
+    let param = segment
+        .strip_prefix("[[...")
+        .and_then(|s| s.strip_suffix("]]"))
+        .or_else(|| {
+            segment
+                .strip_prefix("[...")
+                .and_then(|s| s.strip_suffix(']'))
+        })
+        .or_else(|| segment.strip_prefix('[').and_then(|s| s.strip_suffix(']')));
+    matches!(param, Some(param) if !param.is_empty() && !param.contains(['[', ']', '.']))
+}
+
+#[turbo_tasks::value(shared)]
+#[derive(Debug, Clone)]
+enum ProjectStructureProblem {
+    NoRouteDirectory {
+        app_dir_enabled: bool,
+    },
+    /// The names of the directories which exist both in the project and in
+    /// `src`.
+    ConflictingSrcDirectories {
+        names: Vec<String>,
+    },
+    InvalidRouteNames {
+        paths: Vec<String>,
+    },
+}
+
+#[turbo_tasks::value(shared)]
+struct ProjectStructureIssue {
+    path: FileSystemPathVc,
+    problem: ProjectStructureProblem,
+}
+
+#[turbo_tasks::value_impl]
+impl Issue for ProjectStructureIssue {
+    #[turbo_tasks::function]
+    fn severity(&self) -> IssueSeverityVc {
+        match self.problem {
+            ProjectStructureProblem::ConflictingSrcDirectories { .. } => {
+                IssueSeverity::Warning.into()
+            }
+            _ => IssueSeverity::Error.into(),
+        }
+    }
+
+    #[turbo_tasks::function]
+    fn category(&self) -> StringVc {
+        StringVc::cell("project structure".to_string())
+    }
+
+    #[turbo_tasks::function]
+    fn context(&self) -> FileSystemPathVc {
+        self.path
+    }
+
+    #[turbo_tasks::function]
+    fn title(&self) -> StringVc {
+        StringVc::cell(
+            match self.problem {
+                ProjectStructureProblem::NoRouteDirectory {
+                    app_dir_enabled: true,
+                } => "Couldn't find a `pages` or `app` directory",
+                ProjectStructureProblem::NoRouteDirectory {
+                    app_dir_enabled: false,
+                } => "Couldn't find a `pages` directory",
+                ProjectStructureProblem::ConflictingSrcDirectories { .. } => {
+                    "Route directories exist both in the project and in `src`"
+                }
+                ProjectStructureProblem::InvalidRouteNames { .. } => {
+                    "Routes have names which can't be part of a URL"
+                }
+            }
+            .to_string(),
+        )
+    }
+
+    #[turbo_tasks::function]
+    fn description(&self) -> StringVc {
+        StringVc::cell(match &self.problem {
+            ProjectStructureProblem::NoRouteDirectory {
+                app_dir_enabled: true,
+            } => "Create a `pages` or `app` directory in the project, or in its `src` directory, \
+                  with the routes of the app."
+                .to_string(),
+            ProjectStructureProblem::NoRouteDirectory {
+                app_dir_enabled: false,
+            } => "Create a `pages` directory in the project, or in its `src` directory, with the \
+                  routes of the app. To use an `app` directory, enable `experimental.appDir` in \
+                  the next.config.js."
+                .to_string(),
+            ProjectStructureProblem::ConflictingSrcDirectories { names } => format!(
+                "The directories in `src` are ignored, move their routes or remove them:\n\n{}",
+                names
+                    .iter()
+                    .map(|name| format!("- {name} and src/{name}"))
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            ),
+            ProjectStructureProblem::InvalidRouteNames { paths } => format!(
+                "Rename these directories and files, their names contain characters which need to \
+                 be encoded in a URL ({}), or brackets which don't enclose a dynamic segment like \
+                 `[slug]`, `[...slug]` or `[[...slug]]`:\n\n{}",
+                INVALID_ROUTE_CHARS
+                    .iter()
+                    .map(|c| format!("`{c}`"))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                paths
+                    .iter()
+                    .map(|path| format!("- {path}"))
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            ),
+        })
+    }
+}
@@ -0,0 +1,178 @@
+use crate::router::{FullMiddlewareResponse, HeaderMap, MiddlewareHeadersResponse, RouterRequest};
+
+/// The maximum length in bytes of the pathname and query of a request. Longer
+/// URLs are answered with `414 URI Too Long`.
+pub const MAX_URL_LENGTH: usize = 8 * 1024;
+
+/// Why a request URL is rejected before it is routed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InvalidRequestUrl {
+    TooLong,
+    InvalidPercentEncoding,
+    NullByte,
+    ControlCharacter,
+}
+
+impl InvalidRequestUrl {
+    pub fn status_code(self) -> u16 {
+        match self {
+            InvalidRequestUrl::TooLong => 414,
+            _ => 400,
+        }
+    }
+
+    pub fn message(self) -> &'static str {
+        match self {
+            InvalidRequestUrl::TooLong => "URI Too Long",
+            InvalidRequestUrl::InvalidPercentEncoding => {
+                "Bad Request: the URL contains an invalid percent-encoding"
+            }
+            InvalidRequestUrl::NullByte => "Bad Request: the URL contains a null byte",
+            InvalidRequestUrl::ControlCharacter => {
+                "Bad Request: the URL contains a control character"
+            }
+        }
+    }
+}
+
+/// Checks the pathname and query of a request, which are still
+/// percent-encoded. The pathname must decode to UTF-8 without null bytes, as
+/// Next.js decodes it to match routes. Query values are decoded by the page,
+/// so only raw null bytes and control characters are rejected there.
+pub fn validate_request_url(pathname: &str, raw_query: &str) -> Result<(), InvalidRequestUrl> {
+    if pathname.len() + raw_query.len() > MAX_URL_LENGTH {
+        return Err(InvalidRequestUrl::TooLong);
+    }
+    for part in [pathname, raw_query] {
+        if part.contains('\0') {
+            return Err(InvalidRequestUrl::NullByte);
+        }
+        if part.chars().any(char::is_control) {
+            return Err(InvalidRequestUrl::ControlCharacter);
+        }
+    }
+    let decoded = percent_decode(pathname).ok_or(InvalidRequestUrl::InvalidPercentEncoding)?;
+    if decoded.contains(&0) {
+        return Err(InvalidRequestUrl::NullByte);
+    }
+    if std::str::from_utf8(&decoded).is_err() {
+        return Err(InvalidRequestUrl::InvalidPercentEncoding);
+    }
+    Ok(())
+}
+
+/// Returns the response to a request with an invalid URL, see
+/// [validate_request_url].
+pub fn invalid_request_url_response(request: &RouterRequest) -> Option<FullMiddlewareResponse> {
+    let error = validate_request_url(&request.pathname, &request.raw_query).err()?;
+    Some(FullMiddlewareResponse {
+        headers: MiddlewareHeadersResponse {
+            status_code: error.status_code(),
+            headers: HeaderMap::from_iter([
+                ("content-type", "text/plain; charset=utf-8"),
+                ("connection", "close"),
+            ]),
+        },
+        body: error.message().as_bytes().to_vec(),
+    })
+}
+
+/// Decodes `%XX` escapes, or returns `None` when an escape isn't followed by
+/// two hex digits.
+pub(crate) fn percent_decode(value: &str) -> Option<Vec<u8>> {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = bytes.get(i + 1..i + 3)?;
+            if !hex.iter().all(u8::is_ascii_hexdigit) {
+                return None;
+            }
+            decoded.push(u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok()?);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+    Some(decoded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{percent_decode, validate_request_url, InvalidRequestUrl, MAX_URL_LENGTH};
+
+    #[test]
+    fn test_valid_urls() {
+        assert_eq!(validate_request_url("/", ""), Ok(()));
+        assert_eq!(validate_request_url("/blog/hello-world", "page=2"), Ok(()));
+        assert_eq!(validate_request_url("/caf%C3%A9", ""), Ok(()));
+        // Query values are decoded by the page, not by the router.
+        assert_eq!(validate_request_url("/search", "q=100%"), Ok(()));
+    }
+
+    #[test]
+    fn test_too_long() {
+        let pathname = format!("/{}", "a".repeat(MAX_URL_LENGTH - 3));
+        assert_eq!(validate_request_url(&pathname, "a="), Ok(()));
+        assert_eq!(
+            validate_request_url(&pathname, "ab="),
+            Err(InvalidRequestUrl::TooLong)
+        );
+    }
+
+    #[test]
+    fn test_invalid_percent_encoding() {
+        for pathname in ["/%", "/%4", "/%zz", "/%C3%28", "/%FF"] {
+            assert_eq!(
+                validate_request_url(pathname, ""),
+                Err(InvalidRequestUrl::InvalidPercentEncoding),
+                "{pathname}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_null_bytes() {
+        assert_eq!(
+            validate_request_url("/a%00b", ""),
+            Err(InvalidRequestUrl::NullByte)
+        );
+        assert_eq!(
+            validate_request_url("/a\0b", ""),
+            Err(InvalidRequestUrl::NullByte)
+        );
+        assert_eq!(
+            validate_request_url("/", "a=\0"),
+            Err(InvalidRequestUrl::NullByte)
+        );
+    }
+
+    #[test]
+    fn test_control_characters() {
+        assert_eq!(
+            validate_request_url("/a\nb", ""),
+            Err(InvalidRequestUrl::ControlCharacter)
+        );
+        assert_eq!(
+            validate_request_url("/", "a=\r\n"),
+            Err(InvalidRequestUrl::ControlCharacter)
+        );
+    }
+
+    #[test]
+    fn test_status_codes() {
+        assert_eq!(InvalidRequestUrl::TooLong.status_code(), 414);
+        assert_eq!(InvalidRequestUrl::InvalidPercentEncoding.status_code(), 400);
+        assert_eq!(InvalidRequestUrl::NullByte.status_code(), 400);
+        assert_eq!(InvalidRequestUrl::ControlCharacter.status_code(), 400);
+    }
+
+    #[test]
+    fn test_percent_decode() {
+        assert_eq!(percent_decode("a%20b%2fc").as_deref(), Some(&b"a b/c"[..]));
+        assert_eq!(percent_decode("%"), None);
+        assert_eq!(percent_decode("%g0"), None);
+    }
+}
@@ -0,0 +1,259 @@
+use anyhow::Result;
+use swc_core::ecma::ast::{Decl, ExportSpecifier, ModuleDecl, ModuleExportName, Program};
+use turbo_tasks::primitives::{StringVc, StringsVc};
+use turbo_tasks_fs::FileSystemPathVc;
+use turbopack_core::{
+    asset::{Asset, AssetVc},
+    issue::{Issue, IssueSeverity, IssueSeverityVc, IssueVc},
+};
+use turbopack_ecmascript::{parse::ParseResult, EcmascriptModuleAssetVc};
+use turbopack_node::route_matcher::RouteMatcher;
+
+use crate::{
+    app_structure::{AppStructureItem, OptionAppStructureVc},
+    next_route_matcher::NextParamsMatcherVc,
+    util::pathname_for_path,
+};
+
+/// The HTTP methods a route handler can export.
+pub const ROUTE_HANDLER_METHODS: [&str; 7] =
+    ["GET", "HEAD", "OPTIONS", "POST", "PUT", "DELETE", "PATCH"];
+
+/// A route handler, i.e. an `app/**/route.{js,ts}` file.
+#[turbo_tasks::value(shared)]
+pub struct RouteHandler {
+    /// The pathname of the route, e.g. `api/users/[id]`.
+    pub pathname: StringVc,
+    pub route: FileSystemPathVc,
+}
+
+#[turbo_tasks::value(transparent)]
+pub struct RouteHandlers(Vec<RouteHandlerVc>);
+
+/// Discovers all route handlers in the app directory.
+#[turbo_tasks::function]
+pub async fn get_route_handlers(
+    app_structure: OptionAppStructureVc,
+    server_root: FileSystemPathVc,
+) -> Result<RouteHandlersVc> {
+    let mut route_handlers = Vec::new();
+    if let Some(app_structure) = *app_structure.await? {
+        let mut queue = vec![app_structure];
+        while let Some(structure) = queue.pop() {
+            let structure = structure.await?;
+            queue.extend(structure.children.iter().copied());
+            let Some(item) = structure.item else {
+                continue;
+            };
+            if let AppStructureItem::Route { url, route, .. } = *item.await? {
+                route_handlers.push(
+                    RouteHandler {
+                        pathname: pathname_for_path(server_root, url, false, false),
+                        route,
+                    }
+                    .cell(),
+                );
+            }
+        }
+    }
+    Ok(RouteHandlersVc::cell(route_handlers))
+}
+
+#[turbo_tasks::value_impl]
+impl RouteHandlersVc {
+    /// Returns the route handler matching a pathname without a leading `/`,
+    /// if any.
+    #[turbo_tasks::function]
+    pub async fn find(self, pathname: &str) -> Result<OptionRouteHandlerVc> {
+        for &route_handler in self.await?.iter() {
+            let matcher = NextParamsMatcherVc::new(route_handler.await?.pathname);
+            if *matcher.matches(pathname).await? {
+                return Ok(OptionRouteHandlerVc::cell(Some(route_handler)));
+            }
+        }
+        Ok(OptionRouteHandlerVc::cell(None))
+    }
+}
+
+#[turbo_tasks::value(transparent)]
+pub struct OptionRouteHandler(Option<RouteHandlerVc>);
+
+/// Returns the HTTP methods exported by a route handler module, and reports
+/// issues for exports which look like mistakes, e.g. a default export or a
+/// lowercase `get`.
+#[turbo_tasks::function]
+pub async fn route_handler_methods(module_asset: AssetVc) -> Result<StringsVc> {
+    let Some(ecmascript_asset) = EcmascriptModuleAssetVc::resolve_from(module_asset).await? else {
+        return Ok(StringsVc::cell(vec![]));
+    };
+    let ParseResult::Ok {
+        program: Program::Module(module),
+        ..
+    } = &*ecmascript_asset.parse().await?
+    else {
+        return Ok(StringsVc::cell(vec![]));
+    };
+
+    let mut exports = Vec::new();
+    let mut has_default_export = false;
+    for item in module.body.iter() {
+        match item.as_module_decl() {
+            Some(ModuleDecl::ExportDecl(export_decl)) => match &export_decl.decl {
+                Decl::Fn(fn_decl) => exports.push(fn_decl.ident.sym.to_string()),
+                Decl::Var(var_decl) => exports.extend(
+                    var_decl
+                        .decls
+                        .iter()
+                        .filter_map(|decl| decl.name.as_ident())
+                        .map(|ident| ident.sym.to_string()),
+                ),
+                _ => {}
+            },
+            Some(ModuleDecl::ExportNamed(named_export)) => {
+                for specifier in named_export.specifiers.iter() {
+                    if let ExportSpecifier::Named(named) = specifier {
+                        let name = match named.exported.as_ref().unwrap_or(&named.orig) {
+                            ModuleExportName::Ident(ident) => ident.sym.to_string(),
+                            ModuleExportName::Str(str) => str.value.to_string(),
+                        };
+                        if name == "default" {
+                            has_default_export = true;
+                        } else {
+                            exports.push(name);
+                        }
+                    }
+                }
+            }
+            Some(ModuleDecl::ExportDefaultDecl(_) | ModuleDecl::ExportDefaultExpr(_)) => {
+                has_default_export = true;
+            }
+            _ => {}
+        }
+    }
+
+    let path = module_asset.ident().path();
+    let emit = |severity: IssueSeverity, message: String| {
+        RouteHandlerIssue {
+            severity: severity.into(),
+            path,
+            message: StringVc::cell(message),
+        }
+        .cell()
+        .as_issue()
+        .emit();
+    };
+
+    if has_default_export {
+        emit(
+            IssueSeverity::Error,
+            "Route handlers don't support default exports. Export a named function for each HTTP \
+             method instead, e.g. `export async function GET() {}`."
+                .to_string(),
+        );
+    }
+
+    let mut methods = Vec::new();
+    for export in exports {
+        if ROUTE_HANDLER_METHODS.contains(&export.as_str()) {
+            methods.push(export);
+        } else if ROUTE_HANDLER_METHODS.contains(&export.to_uppercase().as_str()) {
+            emit(
+                IssueSeverity::Warning,
+                format!(
+                    "Detected the lowercase method `{export}`. HTTP methods must be exported in \
+                     uppercase, did you mean `{}`?",
+                    export.to_uppercase()
+                ),
+            );
+        }
+    }
+
+    if methods.is_empty() {
+        emit(
+            IssueSeverity::Warning,
+            format!(
+                "The route handler doesn't export any HTTP method, so every request will fail \
+                 with 405 Method Not Allowed. Export at least one of {}.",
+                ROUTE_HANDLER_METHODS.join(", ")
+            ),
+        );
+    }
+
+    Ok(StringsVc::cell(methods))
+}
+
+/// How a route handler answers a request method.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RouteHandlerMethod {
+    /// The route handler exports the method.
+    Exported,
+    /// `HEAD` runs the `GET` handler, and the server drops the body.
+    HeadAsGet,
+    /// `OPTIONS` is answered with the allowed methods, see
+    /// [route_handler_allowed_methods].
+    DefaultOptions,
+    /// The route handler answers with 405 Method Not Allowed.
+    NotAllowed,
+}
+
+/// Returns how a route handler exporting `methods` answers `method`, like
+/// Next.js implements `HEAD` and `OPTIONS` for route handlers which don't
+/// export them.
+pub fn resolve_route_handler_method(methods: &[String], method: &str) -> RouteHandlerMethod {
+    let exports = |method: &str| methods.iter().any(|exported| exported == method);
+    if exports(method) {
+        RouteHandlerMethod::Exported
+    } else if method == "HEAD" && exports("GET") {
+        RouteHandlerMethod::HeadAsGet
+    } else if method == "OPTIONS" {
+        RouteHandlerMethod::DefaultOptions
+    } else {
+        RouteHandlerMethod::NotAllowed
+    }
+}
+
+/// The methods a route handler exporting `methods` allows, including the
+/// implicit `HEAD` and `OPTIONS`, for the `Allow` header.
+pub fn route_handler_allowed_methods(methods: &[String]) -> Vec<&'static str> {
+    ROUTE_HANDLER_METHODS
+        .into_iter()
+        .filter(|&method| {
+            resolve_route_handler_method(methods, method) != RouteHandlerMethod::NotAllowed
+        })
+        .collect()
+}
+
+#[turbo_tasks::value(shared)]
+struct RouteHandlerIssue {
+    severity: IssueSeverityVc,
+    path: FileSystemPathVc,
+    message: StringVc,
+}
+
+#[turbo_tasks::value_impl]
+impl Issue for RouteHandlerIssue {
+    #[turbo_tasks::function]
+    fn severity(&self) -> IssueSeverityVc {
+        self.severity
+    }
+
+    #[turbo_tasks::function]
+    fn title(&self) -> StringVc {
+        StringVc::cell("Invalid route handler exports".to_string())
+    }
+
+    #[turbo_tasks::function]
+    fn category(&self) -> StringVc {
+        StringVc::cell("next app".to_string())
+    }
+
+    #[turbo_tasks::function]
+    fn context(&self) -> FileSystemPathVc {
+        self.path
+    }
+
+    #[turbo_tasks::function]
+    fn description(&self) -> StringVc {
+        self.message
+    }
+}
@@ -0,0 +1,364 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+use mime::APPLICATION_JSON;
+use serde::Serialize;
+use serde_json::json;
+use swc_core::ecma::ast::{Decl, ExportSpecifier, ModuleDecl, ModuleExportName, Program};
+use turbo_tasks::{primitives::StringVc, Value};
+use turbo_tasks_env::{CustomProcessEnvVc, ProcessEnvVc};
+use turbo_tasks_fs::{File, FileSystemPathVc};
+use turbopack::{transition::TransitionsByNameVc, ModuleAssetContextVc};
+use turbopack_core::{
+    asset::{AssetContentVc, AssetVc},
+    context::{AssetContext, AssetContextVc},
+    environment::ServerAddrVc,
+    reference_type::{EntryReferenceSubType, ReferenceType},
+    source_asset::SourceAssetVc,
+};
+use turbopack_dev_server::source::{
+    ContentSource, ContentSourceContentVc, ContentSourceData, ContentSourceResultVc,
+};
+use turbopack_ecmascript::{parse::ParseResult, EcmascriptModuleAssetVc};
+use turbopack_node::execution_context::ExecutionContextVc;
+
+use crate::{
+    app_structure::{AppStructureItem, OptionAppStructureVc},
+    env_validation::{required_env_vars, validate_route_env_vars},
+    mode::NextMode,
+    next_config::NextConfigVc,
+    next_image::probe::validate_remote_images,
+    next_server::context::{
+        get_server_compile_time_info, get_server_module_options_context,
+        get_server_resolve_options_context, ServerContextType,
+    },
+    pages_structure::{OptionPagesStructureVc, PagesStructureItem},
+    util::{
+        parse_config_from_source, parse_segment_config_from_source, pathname_for_path,
+        NextRevalidate, NextRuntime, NextSegmentConfig, NextSegmentDynamic,
+    },
+};
+
+#[derive(Serialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "kebab-case")]
+enum RouteKind {
+    Page,
+    Api,
+    AppPage,
+    AppRoute,
+}
+
+/// How a route is rendered, as far as it can be determined at compile time.
+#[derive(Serialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "kebab-case")]
+enum RouteRendering {
+    /// Rendered once at build time.
+    Static,
+    /// Rendered at build time and revalidated after the given number of
+    /// seconds.
+    Isr,
+    /// Rendered on every request.
+    Dynamic,
+    /// Depends on the usage of dynamic functions and data fetching, which is
+    /// only known at runtime.
+    Auto,
+}
+
+#[derive(Serialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "camelCase")]
+struct RouteListEntry {
+    pathname: String,
+    kind: RouteKind,
+    runtime: &'static str,
+    rendering: RouteRendering,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    revalidate: Option<u32>,
+    file: String,
+}
+
+/// Creates a stable JSON description of all routes of the app, with their
+/// runtime and whether they are rendered statically or dynamically. It's
+/// intended to be diffed between commits, e.g. on CI, to catch a page
+/// unexpectedly switching from static to dynamic rendering.
+#[allow(clippy::too_many_arguments)]
+#[turbo_tasks::function]
+pub async fn route_list(
+    project_path: FileSystemPathVc,
+    execution_context: ExecutionContextVc,
+    server_root: FileSystemPathVc,
+    env: ProcessEnvVc,
+    server_addr: ServerAddrVc,
+    next_config: NextConfigVc,
+    app_structure: OptionAppStructureVc,
+    pages_structure: OptionPagesStructureVc,
+) -> Result<StringVc> {
+    let project_path_value = project_path.await?;
+    let file_name = |path: FileSystemPathVc| {
+        let project_path_value = project_path_value.clone();
+        async move {
+            let path = path.await?;
+            Ok::<_, anyhow::Error>(
+                project_path_value
+                    .get_path_to(&path)
+                    .map_or_else(|| path.path.clone(), |path| path.to_string()),
+            )
+        }
+    };
+    let mut routes = vec![];
+    // The server module of every route, for validating its env var and remote
+    // image usage.
+    let mut route_modules = vec![];
+
+    if let Some(pages_structure) = *pages_structure.await? {
+        let pages_dir = pages_structure.directory();
+        let context = server_context(
+            project_path,
+            execution_context,
+            Value::new(ServerContextType::Pages { pages_dir }),
+            env,
+            server_addr,
+            Value::new(NextMode::Development),
+            next_config,
+        );
+        let mut queue = vec![pages_structure];
+        while let Some(structure) = queue.pop() {
+            let structure = structure.await?;
+            queue.extend(structure.children.iter().copied());
+            for item in structure.items.iter() {
+                let (url, file, kind) = match *item.await? {
+                    PagesStructureItem::Page { url, page, .. } => (url, page, RouteKind::Page),
+                    PagesStructureItem::Api { url, api, .. } => (url, api, RouteKind::Api),
+                };
+                let reference_type = if kind == RouteKind::Api {
+                    EntryReferenceSubType::PagesApi
+                } else {
+                    EntryReferenceSubType::Page
+                };
+                let module = context.process(
+                    SourceAssetVc::new(file).into(),
+                    Value::new(ReferenceType::Entry(reference_type)),
+                );
+                let runtime = parse_config_from_source(module).await?.runtime;
+                let rendering =
+                    if kind == RouteKind::Api || has_export(module, "getServerSideProps").await? {
+                        RouteRendering::Dynamic
+                    } else {
+                        RouteRendering::Static
+                    };
+                let pathname = format!(
+                    "/{}",
+                    pathname_for_path(server_root, url, true, false).await?
+                );
+                route_modules.push((pathname.clone(), module));
+                routes.push(RouteListEntry {
+                    pathname,
+                    kind,
+                    runtime: runtime_name(runtime),
+                    rendering,
+                    revalidate: None,
+                    file: file_name(file).await?,
+                });
+            }
+        }
+    }
+
+    if let Some(app_structure) = *app_structure.await? {
+        let app_dir = app_structure.directory();
+        let context = server_context(
+            project_path,
+            execution_context,
+            Value::new(ServerContextType::AppRoute { app_dir }),
+            env,
+            server_addr,
+            Value::new(NextMode::Development),
+            next_config,
+        );
+        let mut queue = vec![app_structure];
+        while let Some(structure) = queue.pop() {
+            let structure = structure.await?;
+            queue.extend(structure.children.iter().copied());
+            let Some(item) = structure.item else {
+                continue;
+            };
+            let (url, file, kind, reference_type) = match *item.await? {
+                AppStructureItem::Page { url, page, .. } => (
+                    url,
+                    page,
+                    RouteKind::AppPage,
+                    EntryReferenceSubType::AppPage,
+                ),
+                AppStructureItem::Route { url, route, .. } => (
+                    url,
+                    route,
+                    RouteKind::AppRoute,
+                    EntryReferenceSubType::AppRoute,
+                ),
+            };
+            let module = context.process(
+                SourceAssetVc::new(file).into(),
+                Value::new(ReferenceType::Entry(reference_type)),
+            );
+            let config = parse_segment_config_from_source(module).await?;
+            let (rendering, revalidate) = segment_rendering(&config, &kind);
+            let pathname = format!(
+                "/{}",
+                pathname_for_path(server_root, url, false, false).await?
+            );
+            route_modules.push((pathname.clone(), module));
+            routes.push(RouteListEntry {
+                pathname,
+                kind,
+                runtime: runtime_name(config.runtime.unwrap_or_default()),
+                rendering,
+                revalidate,
+                file: file_name(file).await?,
+            });
+        }
+    }
+
+    validate_route_env_vars(
+        project_path,
+        &route_modules,
+        required_env_vars(project_path, next_config),
+        CustomProcessEnvVc::new(env, next_config.env()).as_process_env(),
+    )
+    .await?;
+
+    if *next_config.probe_remote_images().await? {
+        let modules: Vec<_> = route_modules.iter().map(|&(_, module)| module).collect();
+        validate_remote_images(&modules).await?;
+    }
+
+    routes.sort();
+
+    let mut json = serde_json::to_string_pretty(&json!({ "routes": routes }))?;
+    json.push('\n');
+    Ok(StringVc::cell(json))
+}
+
+#[turbo_tasks::function]
+pub(crate) fn server_context(
+    project_path: FileSystemPathVc,
+    execution_context: ExecutionContextVc,
+    ty: Value<ServerContextType>,
+    env: ProcessEnvVc,
+    server_addr: ServerAddrVc,
+    mode: Value<NextMode>,
+    next_config: NextConfigVc,
+) -> AssetContextVc {
+    ModuleAssetContextVc::new(
+        TransitionsByNameVc::cell(HashMap::new()),
+        get_server_compile_time_info(ty, env, server_addr, mode, next_config),
+        get_server_module_options_context(project_path, execution_context, ty, next_config),
+        get_server_resolve_options_context(project_path, ty, next_config, execution_context),
+    )
+    .into()
+}
+
+fn runtime_name(runtime: NextRuntime) -> &'static str {
+    match runtime {
+        NextRuntime::NodeJs => "nodejs",
+        NextRuntime::Edge => "edge",
+    }
+}
+
+fn segment_rendering(
+    config: &NextSegmentConfig,
+    kind: &RouteKind,
+) -> (RouteRendering, Option<u32>) {
+    match (config.dynamic, config.revalidate) {
+        (Some(NextSegmentDynamic::ForceDynamic), _) | (_, Some(NextRevalidate::After(0))) => {
+            (RouteRendering::Dynamic, None)
+        }
+        (_, Some(NextRevalidate::After(seconds))) => (RouteRendering::Isr, Some(seconds)),
+        (Some(NextSegmentDynamic::ForceStatic | NextSegmentDynamic::Error), _) => {
+            (RouteRendering::Static, None)
+        }
+        // Route handlers are only rendered statically when opted in.
+        _ if *kind == RouteKind::AppRoute => (RouteRendering::Dynamic, None),
+        _ => (RouteRendering::Auto, None),
+    }
+}
+
+/// Returns whether the module has a named export with the given name.
+pub(crate) async fn has_export(module_asset: AssetVc, name: &str) -> Result<bool> {
+    let Some(ecmascript_asset) = EcmascriptModuleAssetVc::resolve_from(module_asset).await? else {
+        return Ok(false);
+    };
+    let ParseResult::Ok {
+        program: Program::Module(module),
+        ..
+    } = &*ecmascript_asset.parse().await?
+    else {
+        return Ok(false);
+    };
+    let is_name = |export_name: &ModuleExportName| match export_name {
+        ModuleExportName::Ident(ident) => &*ident.sym == name,
+        ModuleExportName::Str(str) => &*str.value == name,
+    };
+    Ok(module.body.iter().any(|item| match item.as_module_decl() {
+        Some(ModuleDecl::ExportDecl(export_decl)) => match &export_decl.decl {
+            Decl::Fn(fn_decl) => &*fn_decl.ident.sym == name,
+            Decl::Var(var_decl) => var_decl.decls.iter().any(|decl| {
+                decl.name
+                    .as_ident()
+                    .map_or(false, |ident| &*ident.sym == name)
+            }),
+            _ => false,
+        },
+        Some(ModuleDecl::ExportNamed(named_export)) => {
+            named_export
+                .specifiers
+                .iter()
+                .any(|specifier| match specifier {
+                    ExportSpecifier::Named(named) => {
+                        is_name(named.exported.as_ref().unwrap_or(&named.orig))
+                    }
+                    _ => false,
+                })
+        }
+        _ => false,
+    }))
+}
+
+/// A content source serving the JSON created by [route_list].
+#[turbo_tasks::value(shared)]
+pub struct RouteListContentSource {
+    pub project_path: FileSystemPathVc,
+    pub execution_context: ExecutionContextVc,
+    pub server_root: FileSystemPathVc,
+    pub env: ProcessEnvVc,
+    pub server_addr: ServerAddrVc,
+    pub next_config: NextConfigVc,
+    pub app_structure: OptionAppStructureVc,
+    pub pages_structure: OptionPagesStructureVc,
+}
+
+#[turbo_tasks::value_impl]
+impl ContentSource for RouteListContentSource {
+    #[turbo_tasks::function]
+    async fn get(
+        &self,
+        path: &str,
+        _data: Value<ContentSourceData>,
+    ) -> Result<ContentSourceResultVc> {
+        if !path.is_empty() {
+            return Ok(ContentSourceResultVc::not_found());
+        }
+        let json = route_list(
+            self.project_path,
+            self.execution_context,
+            self.server_root,
+            self.env,
+            self.server_addr,
+            self.next_config,
+            self.app_structure,
+            self.pages_structure,
+        )
+        .await?;
+        let file = File::from(json.as_str()).with_content_type(APPLICATION_JSON);
+        Ok(ContentSourceResultVc::exact(
+            ContentSourceContentVc::static_content(AssetContentVc::from(file).into()).into(),
+        ))
+    }
+}
@@ -0,0 +1,163 @@
+use anyhow::Result;
+use indoc::formatdoc;
+use turbo_tasks::CompletionVc;
+use turbo_tasks_fs::{File, FileContentVc, FileSystemPathVc};
+
+use crate::{
+    app_structure::{
+        AppStructure, AppStructureItem, AppStructureItemVc, AppStructureVc, OptionAppStructureVc,
+    },
+    next_config::NextConfigVc,
+};
+
+/// Where the route types are generated, relative to the project directory.
+const TYPES_DIR: &str = ".next/types/app";
+
+/// Writes a module with the `PageProps` or `RouteContext` of each route in the
+/// app directory, mirroring the app directory in `.next/types/app`, e.g.
+/// `.next/types/app/blog/[slug]/page.ts`. Only the types of changed routes are
+/// written again.
+#[turbo_tasks::function]
+pub async fn write_route_types(
+    project_path: FileSystemPathVc,
+    app_structure: OptionAppStructureVc,
+    next_config: NextConfigVc,
+) -> Result<CompletionVc> {
+    if !*next_config.route_types().await? {
+        return Ok(CompletionVc::new());
+    }
+    let Some(app_structure) = *app_structure.await? else {
+        return Ok(CompletionVc::new());
+    };
+    write_route_types_for_directory(
+        project_path.join(TYPES_DIR),
+        app_structure.directory(),
+        app_structure,
+    )
+    .await?;
+    Ok(CompletionVc::new())
+}
+
+#[turbo_tasks::function]
+async fn write_route_types_for_directory(
+    types_dir: FileSystemPathVc,
+    app_dir: FileSystemPathVc,
+    app_structure: AppStructureVc,
+) -> Result<CompletionVc> {
+    let AppStructure {
+        item, ref children, ..
+    } = *app_structure.await?;
+    if let Some(item) = item {
+        write_route_type(types_dir, app_dir, item).await?;
+    }
+    for &child in children.iter() {
+        write_route_types_for_directory(types_dir, app_dir, child).await?;
+    }
+    Ok(CompletionVc::new())
+}
+
+#[turbo_tasks::function]
+async fn write_route_type(
+    types_dir: FileSystemPathVc,
+    app_dir: FileSystemPathVc,
+    item: AppStructureItemVc,
+) -> Result<CompletionVc> {
+    let (file, is_page) = match *item.await? {
+        AppStructureItem::Page { page, .. } => (page, true),
+        AppStructureItem::Route { route, .. } => (route, false),
+    };
+    let app_dir = app_dir.await?;
+    let Some(relative) = app_dir.get_path_to(&*file.await?).map(str::to_string) else {
+        return Ok(CompletionVc::new());
+    };
+    let (directory, _) = relative.rsplit_once('/').unwrap_or(("", &relative));
+
+    let params = params_type(directory);
+    let props = if is_page {
+        formatdoc!(
+            r#"
+                export type SearchParams = {{ [key: string]: string | string[] | undefined }};
+
+                export interface PageProps {{
+                  params: Params;
+                  searchParams: SearchParams;
+                }}
+            "#
+        )
+    } else {
+        formatdoc!(
+            r#"
+                export interface RouteContext {{
+                  params: Params;
+                }}
+            "#
+        )
+    };
+    let content = format!(
+        "// Generated from {}/{relative}, do not edit.\n\nexport type Params = \
+         {params};\n\n{props}",
+        app_dir.file_name()
+    );
+
+    let name = if is_page { "page.ts" } else { "route.ts" };
+    let path = if directory.is_empty() {
+        types_dir.join(name)
+    } else {
+        types_dir.join(&format!("{directory}/{name}"))
+    };
+    Ok(path.write(FileContentVc::from(File::from(content))))
+}
+
+/// Returns the type of the params of a route in `directory`, relative to the
+/// app directory. Route groups and parallel route slots don't add params.
+fn params_type(directory: &str) -> String {
+    let params = directory
+        .split('/')
+        .filter_map(param_property)
+        .collect::<Vec<_>>();
+    if params.is_empty() {
+        return "{}".to_string();
+    }
+    format!("{{\n  {}\n}}", params.join("\n  "))
+}
+
+/// Returns the property of a dynamic segment, e.g. `slug: string;` for
+/// `[slug]`.
+fn param_property(segment: &str) -> Option<String> {
+    let segment = strip_interception_markers(segment);
+    let name = segment.strip_prefix('[')?.strip_suffix(']')?;
+    if let Some(name) = name
+        .strip_prefix("[...")
+        .and_then(|name| name.strip_suffix(']'))
+    {
+        Some(format!("{}?: string[];", property_name(name)))
+    } else if let Some(name) = name.strip_prefix("...") {
+        Some(format!("{}: string[];", property_name(name)))
+    } else {
+        Some(format!("{}: string;", property_name(name)))
+    }
+}
+
+/// Intercepting routes like `(..)photo/[id]` have the params of the
+/// intercepted route.
+fn strip_interception_markers(segment: &str) -> &str {
+    let mut segment = segment;
+    while let Some(rest) = ["(...)", "(..)", "(.)"]
+        .iter()
+        .find_map(|marker| segment.strip_prefix(marker))
+    {
+        segment = rest;
+    }
+    segment
+}
+
+fn property_name(name: &str) -> String {
+    let is_identifier = name.chars().enumerate().all(|(i, c)| {
+        c == '_' || c == '$' || c.is_ascii_alphabetic() || (i > 0 && c.is_ascii_digit())
+    });
+    if is_identifier && !name.is_empty() {
+        name.to_string()
+    } else {
+        serde_json::to_string(name).unwrap_or_default()
+    }
+}
@@ -1,9 +1,14 @@
+use std::net::IpAddr;
+
 use anyhow::{bail, Result};
+use base64::{engine::general_purpose::STANDARD, Engine};
 use indexmap::indexmap;
-use serde::Deserialize;
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize, Serializer};
 use serde_json::json;
+use tracing::Instrument;
 use turbo_tasks::{
-    primitives::{JsonValueVc, StringsVc},
+    primitives::{JsonValueVc, StringVc, StringsVc},
+    trace::TraceRawVcs,
     CompletionVc, CompletionsVc, Value,
 };
 use turbo_tasks_fs::{
@@ -11,14 +16,14 @@ use turbo_tasks_fs::{
 };
 use turbopack::{evaluate_context::node_evaluate_asset_context, transition::TransitionsByNameVc};
 use turbopack_core::{
-    asset::AssetVc,
+    asset::{Asset, AssetVc},
     changed::any_content_changed,
     chunk::ChunkingContext,
     context::{AssetContext, AssetContextVc},
     environment::{EnvironmentIntention::Middleware, ServerAddrVc},
     ident::AssetIdentVc,
-    issue::IssueVc,
-    reference_type::{EcmaScriptModulesReferenceSubType, ReferenceType},
+    issue::{Issue, IssueSeverity, IssueSeverityVc, IssueVc},
+    reference_type::{EcmaScriptModulesReferenceSubType, EntryReferenceSubType, ReferenceType},
     resolve::{find_context_file, FindContextFileResult},
     source_asset::SourceAssetVc,
     virtual_asset::VirtualAssetVc,
@@ -35,14 +40,29 @@ use turbopack_node::{
 };
 
 use crate::{
+    builtin_middleware::{BuiltinMiddlewareChain, InvalidIpAllowlistIssue},
     embed_js::{next_asset, next_js_file},
-    next_config::NextConfigVc,
+    instrumentation::find_instrumentation_file,
+    metadata_routes::MetadataRoutesVc,
+    mode::NextMode,
+    next_config::{NextConfigVc, RewritesVc},
     next_edge::{
         context::{get_edge_compile_time_info, get_edge_resolve_options_context},
+        middleware_manifest::MiddlewareManifestAsset,
         transition::NextEdgeTransition,
     },
     next_import_map::get_next_build_import_map,
     next_server::context::{get_server_module_options_context, ServerContextType},
+    next_shared::dependencies::dependencies_changed,
+    request_validation::invalid_request_url_response,
+    route_handlers::{
+        resolve_route_handler_method, route_handler_allowed_methods, route_handler_methods,
+        RouteHandlerMethod, RouteHandlersVc,
+    },
+    router_experiments::route_experiment,
+    router_source::RAW_HEADERS_HEADER,
+    server_actions::{ACTION_HEADER, SERVER_ACTION_PATHNAME},
+    shutdown,
     util::{parse_config_from_source, NextSourceConfigVc},
 };
 
@@ -70,6 +90,216 @@ async fn middleware_files(page_extensions: StringsVc) -> Result<StringsVc> {
     Ok(StringsVc::cell(files))
 }
 
+/// The bytes of a header value, which aren't necessarily UTF-8, e.g. Latin-1
+/// encoded values of some proxies and auth schemes.
+///
+/// Over IPC, the value is a string with a character for each byte, which is
+/// how Node.js represents header values, so any value round-trips unchanged.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, TraceRawVcs)]
+pub struct RawHeaderValue(pub Vec<u8>);
+
+impl RawHeaderValue {
+    /// Returns the value as a string, if it's UTF-8.
+    pub fn to_str(&self) -> Option<&str> {
+        std::str::from_utf8(&self.0).ok()
+    }
+
+    /// Whether the value only contains visible ASCII characters, spaces and
+    /// tabs, which are passed through the dev server as they are.
+    pub fn is_visible_ascii(&self) -> bool {
+        self.0
+            .iter()
+            .all(|&byte| byte == b'\t' || (b' '..=b'~').contains(&byte))
+    }
+}
+
+impl From<&str> for RawHeaderValue {
+    fn from(value: &str) -> Self {
+        RawHeaderValue(value.as_bytes().to_vec())
+    }
+}
+
+impl From<String> for RawHeaderValue {
+    fn from(value: String) -> Self {
+        RawHeaderValue(value.into_bytes())
+    }
+}
+
+impl From<&[u8]> for RawHeaderValue {
+    fn from(value: &[u8]) -> Self {
+        RawHeaderValue(value.to_vec())
+    }
+}
+
+impl Serialize for RawHeaderValue {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let latin1 = self.0.iter().map(|&byte| byte as char).collect::<String>();
+        serializer.serialize_str(&latin1)
+    }
+}
+
+impl<'de> Deserialize<'de> for RawHeaderValue {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        value
+            .chars()
+            .map(|char| {
+                // Node.js rejects header values with other characters, as they
+                // can't be sent as bytes.
+                u8::try_from(char).map_err(|_| {
+                    D::Error::custom(format!(
+                        "invalid character {char:?} in a header value, only characters up to \
+                         U+00FF are allowed"
+                    ))
+                })
+            })
+            .collect::<Result<_, _>>()
+            .map(RawHeaderValue)
+    }
+}
+
+/// Headers in the order they were sent. Each value of a header sent multiple
+/// times, e.g. `Set-Cookie`, is kept as a separate entry, as these can't be
+/// joined into one value. Names are matched case-insensitively.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize, TraceRawVcs)]
+#[serde(transparent)]
+pub struct HeaderMap(Vec<(String, RawHeaderValue)>);
+
+impl HeaderMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a value after the existing values of the header.
+    pub fn append(&mut self, name: impl Into<String>, value: impl Into<RawHeaderValue>) {
+        self.0.push((name.into(), value.into()));
+    }
+
+    /// Returns the first value of the header.
+    pub fn get(&self, name: &str) -> Option<&RawHeaderValue> {
+        self.0
+            .iter()
+            .find(|(header, _)| header.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value)
+    }
+
+    /// Returns all values of the header, in the order they were sent.
+    pub fn get_all<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a RawHeaderValue> {
+        self.0
+            .iter()
+            .filter(move |(header, _)| header.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value)
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.get(name).is_some()
+    }
+
+    /// Removes all values of the header.
+    pub fn remove(&mut self, name: &str) {
+        self.0
+            .retain(|(header, _)| !header.eq_ignore_ascii_case(name));
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &RawHeaderValue)> {
+        self.0.iter().map(|(name, value)| (name.as_str(), value))
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Converts the headers to the strings of the dev server, which only
+    /// passes visible ASCII through unchanged. All values of headers with
+    /// other bytes are passed in the [RAW_HEADERS_HEADER] instead, see
+    /// [HeaderMap::encode], which the server in front of the dev server turns
+    /// back into these headers. Repeated headers stay separate entries.
+    pub fn to_strings(&self) -> Vec<(String, String)> {
+        let (raw, visible) = self.split_raw();
+        let mut strings = visible
+            .0
+            .into_iter()
+            .map(|(name, value)| {
+                // Visible ASCII is UTF-8.
+                let value = String::from_utf8(value.0).unwrap_or_default();
+                (name, value)
+            })
+            .collect::<Vec<_>>();
+        if !raw.is_empty() {
+            strings.push((RAW_HEADERS_HEADER.to_string(), raw.encode()));
+        }
+        strings
+    }
+
+    /// The inverse of [HeaderMap::to_strings], for the headers of a request
+    /// received by the dev server.
+    pub fn from_strings<'a>(strings: impl IntoIterator<Item = (&'a str, &'a str)>) -> Self {
+        let mut headers = HeaderMap::new();
+        let mut raw = None;
+        for (name, value) in strings {
+            if name.eq_ignore_ascii_case(RAW_HEADERS_HEADER) {
+                raw = HeaderMap::decode(value);
+            } else {
+                headers.append(name, value);
+            }
+        }
+        if let Some(raw) = raw {
+            headers.0.extend(raw.0);
+        }
+        headers
+    }
+
+    /// Splits the headers into those with a value which isn't visible ASCII,
+    /// and the others. All values of a header stay together, in their order.
+    pub fn split_raw(&self) -> (HeaderMap, HeaderMap) {
+        let raw_names = self
+            .0
+            .iter()
+            .filter(|(_, value)| !value.is_visible_ascii())
+            .map(|(name, _)| name.to_ascii_lowercase())
+            .collect::<Vec<_>>();
+        let (raw, visible) = self
+            .0
+            .iter()
+            .cloned()
+            .partition(|(name, _)| raw_names.contains(&name.to_ascii_lowercase()));
+        (HeaderMap(raw), HeaderMap(visible))
+    }
+
+    /// Encodes the headers as visible ASCII, for the [RAW_HEADERS_HEADER]: the
+    /// base64 of their JSON, in which each byte of a value is a character.
+    pub fn encode(&self) -> String {
+        STANDARD.encode(serde_json::to_vec(self).unwrap_or_default())
+    }
+
+    /// Decodes headers encoded by [HeaderMap::encode].
+    pub fn decode(value: &str) -> Option<Self> {
+        serde_json::from_slice(&STANDARD.decode(value.trim()).ok()?).ok()
+    }
+}
+
+impl<N: Into<String>, V: Into<RawHeaderValue>> FromIterator<(N, V)> for HeaderMap {
+    fn from_iter<I: IntoIterator<Item = (N, V)>>(iter: I) -> Self {
+        let mut headers = HeaderMap::new();
+        for (name, value) in iter {
+            headers.append(name, value);
+        }
+        headers
+    }
+}
+
+impl<N: Into<String>, V: Into<RawHeaderValue>> Extend<(N, V)> for HeaderMap {
+    fn extend<I: IntoIterator<Item = (N, V)>>(&mut self, iter: I) {
+        for (name, value) in iter {
+            self.append(name, value);
+        }
+    }
+}
+
 #[turbo_tasks::value(shared)]
 #[derive(Debug, Clone, Default)]
 #[serde(rename_all = "camelCase")]
@@ -77,7 +307,11 @@ pub struct RouterRequest {
     pub method: String,
     pub pathname: String,
     pub raw_query: String,
-    pub raw_headers: Vec<(String, String)>,
+    pub raw_headers: HeaderMap,
+    /// The address of the peer of the connection, either the client or the
+    /// closest proxy in front of the server.
+    #[turbo_tasks(trace_ignore)]
+    pub peer_addr: Option<IpAddr>,
 }
 
 #[turbo_tasks::value(shared)]
@@ -85,7 +319,7 @@ pub struct RouterRequest {
 #[serde(rename_all = "camelCase")]
 pub struct RewriteResponse {
     pub url: String,
-    pub headers: Vec<(String, String)>,
+    pub headers: HeaderMap,
 }
 
 #[turbo_tasks::value(shared)]
@@ -93,7 +327,7 @@ pub struct RewriteResponse {
 #[serde(rename_all = "camelCase")]
 pub struct MiddlewareHeadersResponse {
     pub status_code: u16,
-    pub headers: Vec<(String, String)>,
+    pub headers: HeaderMap,
 }
 
 #[turbo_tasks::value(shared)]
@@ -126,19 +360,51 @@ enum RouterIncomingMessage {
     FullMiddleware {
         data: FullMiddlewareResponse,
     },
+    /// The middleware response body exceeded the limit, and was aborted.
+    MiddlewareBodyTooLarge {
+        data: MiddlewareBodyTooLargeResponse,
+    },
     None,
     Error(StructuredError),
 }
 
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MiddlewareBodyTooLargeResponse {
+    limit: u64,
+}
+
 #[derive(Debug)]
 #[turbo_tasks::value]
 pub enum RouterResult {
     Rewrite(RewriteResponse),
+    /// The request resolved to an app route handler (`app/**/route.ts`),
+    /// which is served by rewriting to its url.
+    RouteHandler(RewriteResponse),
+    /// The request resolved to a metadata file route of the app directory,
+    /// e.g. `/sitemap.xml`.
+    MetadataRoute(RewriteResponse),
+    /// The request calls a server action, which is served by rewriting to the
+    /// server action endpoint.
+    ServerAction(RewriteResponse),
     FullMiddleware(FullMiddlewareResponse),
+    /// The request resolved to the not found page, e.g. because middleware
+    /// rewrote it to `/404`. It's served by rewriting to [NOT_FOUND_PATHNAME],
+    /// which renders the custom not found page of the app or pages directory.
+    NotFound(RewriteResponse),
     None,
     Error,
 }
 
+/// The pathname rendering the not found page.
+pub const NOT_FOUND_PATHNAME: &str = "_next/404";
+
+/// The pathname rendering the custom `500` page of the pages directory.
+pub const INTERNAL_ERROR_PATHNAME: &str = "_next/500";
+
+/// The default of `experimental.turbo.middlewareBodySizeLimit`.
+const DEFAULT_MIDDLEWARE_BODY_SIZE_LIMIT: u64 = 10 * 1024 * 1024;
+
 impl From<RouterIncomingMessage> for RouterResult {
     fn from(value: RouterIncomingMessage) -> Self {
         match value {
@@ -150,6 +416,96 @@ impl From<RouterIncomingMessage> for RouterResult {
     }
 }
 
+/// Preliminary hints for the response to a request, which servers supporting
+/// 103 Early Hints can send while the response is still being rendered.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EarlyHints {
+    /// The `Link` header values preloading or preconnecting to the assets of
+    /// the matched route, e.g. `</app.css>; rel=preload; as=style`.
+    pub links: Vec<String>,
+}
+
+impl EarlyHints {
+    pub fn is_empty(&self) -> bool {
+        self.links.is_empty()
+    }
+}
+
+impl RouterResult {
+    /// Returns the early hints for the route the request resolved to, from
+    /// the `Link` headers `headers` in next.config.js and middleware added to
+    /// it. Only links which let the browser start fetching are hinted, other
+    /// relations, e.g. `canonical`, are only meaningful with the response.
+    ///
+    /// A response of middleware is sent right away, so it has no hints.
+    pub fn early_hints(&self) -> EarlyHints {
+        let headers = match self {
+            RouterResult::Rewrite(data)
+            | RouterResult::RouteHandler(data)
+            | RouterResult::MetadataRoute(data)
+            | RouterResult::ServerAction(data)
+            | RouterResult::NotFound(data) => &data.headers,
+            RouterResult::FullMiddleware(_) | RouterResult::None | RouterResult::Error => {
+                return EarlyHints::default();
+            }
+        };
+        let links = headers
+            .get_all("link")
+            .filter_map(|value| value.to_str())
+            .flat_map(split_link_header)
+            .filter(|link| is_early_hint_link(link))
+            .map(str::to_string)
+            .collect();
+        EarlyHints { links }
+    }
+}
+
+/// Splits a `Link` header value into its links. Commas separate the links,
+/// except within the `<...>` of a URL or a quoted parameter.
+fn split_link_header(value: &str) -> impl Iterator<Item = &str> {
+    let mut links = Vec::new();
+    let mut start = 0;
+    let mut in_url = false;
+    let mut in_quotes = false;
+    for (index, char) in value.char_indices() {
+        match char {
+            '<' if !in_quotes => in_url = true,
+            '>' if !in_quotes => in_url = false,
+            '"' if !in_url => in_quotes = !in_quotes,
+            ',' if !in_url && !in_quotes => {
+                links.push(&value[start..index]);
+                start = index + 1;
+            }
+            _ => {}
+        }
+    }
+    links.push(&value[start..]);
+    links
+        .into_iter()
+        .map(str::trim)
+        .filter(|link| !link.is_empty())
+}
+
+/// Returns whether a link is a hint the browser acts on before the response,
+/// see [RouterResult::early_hints].
+fn is_early_hint_link(link: &str) -> bool {
+    link.split(';').skip(1).any(|param| {
+        let Some((name, value)) = param.split_once('=') else {
+            return false;
+        };
+        name.trim().eq_ignore_ascii_case("rel")
+            && value
+                .trim()
+                .trim_matches('"')
+                .split_ascii_whitespace()
+                .any(|rel| {
+                    ["preload", "modulepreload", "preconnect"]
+                        .iter()
+                        .any(|hint| rel.eq_ignore_ascii_case(hint))
+                })
+    })
+}
+
 #[turbo_tasks::function]
 async fn get_config(
     context: AssetContextVc,
@@ -196,16 +552,33 @@ async fn next_config_changed(
 async fn config_assets(
     context: AssetContextVc,
     project_path: FileSystemPathVc,
-    page_extensions: StringsVc,
+    next_config: NextConfigVc,
+    output_root: FileSystemPathVc,
 ) -> Result<InnerAssetsVc> {
-    let middleware_config =
-        get_config(context, project_path, middleware_files(page_extensions)).await?;
+    let span = tracing::info_span!("discover configs");
+    discover_config_assets(context, project_path, next_config, output_root)
+        .instrument(span)
+        .await
+}
+
+async fn discover_config_assets(
+    context: AssetContextVc,
+    project_path: FileSystemPathVc,
+    next_config: NextConfigVc,
+    output_root: FileSystemPathVc,
+) -> Result<InnerAssetsVc> {
+    let middleware_config = get_config(
+        context,
+        project_path,
+        middleware_files(next_config.page_extensions()),
+    )
+    .await?;
 
     // The router.ts file expects a manifest of chunks for the middleware. If there
     // is no middleware file, then we need to generate a default empty manifest
     // and we cannot process it with the next-edge transition because it
     // requires a real file for some reason.
-    let (manifest, config) = match &*middleware_config {
+    let (manifest, config, chunk_group) = match &*middleware_config {
         Some(c) => {
             let manifest = context.with_transition("next-edge").process(
                 c.as_asset(),
@@ -214,7 +587,7 @@ async fn config_assets(
                 )),
             );
             let config = parse_config_from_source(c.as_asset());
-            (manifest, config)
+            (manifest, config, Some(manifest))
         }
         None => {
             let manifest = as_es_module_asset(
@@ -227,10 +600,25 @@ async fn config_assets(
             )
             .as_asset();
             let config = NextSourceConfigVc::default();
-            (manifest, config)
+            (manifest, config, None)
         }
     };
 
+    // Emitted like `next build` does, so the output can be deployed with
+    // hosting adapters which run the middleware from the manifest.
+    let middleware_manifest: AssetVc = MiddlewareManifestAsset {
+        output_root,
+        chunk_group,
+        config,
+        next_config,
+    }
+    .cell()
+    .into();
+    middleware_manifest
+        .content()
+        .write(middleware_manifest.ident().path())
+        .await?;
+
     let config_asset = as_es_module_asset(
         VirtualAssetVc::new(
             project_path.join("middleware_config.js"),
@@ -245,9 +633,24 @@ async fn config_assets(
     )
     .as_asset();
 
+    // The router doesn't use the import map of the server contexts, so the
+    // instrumentation file is passed like the middleware.
+    let instrumentation_file =
+        find_instrumentation_file(project_path, next_config.page_extensions()).await?;
+    let instrumentation: AssetVc = match *instrumentation_file {
+        Some(file) => SourceAssetVc::new(file).into(),
+        None => VirtualAssetVc::new(
+            project_path.join("instrumentation.js"),
+            File::from("export {};").into(),
+        )
+        .into(),
+    };
+    let instrumentation = as_es_module_asset(instrumentation, context).as_asset();
+
     Ok(InnerAssetsVc::cell(indexmap! {
         "MIDDLEWARE_CHUNK_GROUP".to_string() => manifest,
         "MIDDLEWARE_CONFIG".to_string() => config_asset,
+        "INSTRUMENTATION".to_string() => instrumentation,
     }))
 }
 
@@ -274,7 +677,12 @@ fn edge_transition_map(
     next_config: NextConfigVc,
     execution_context: ExecutionContextVc,
 ) -> TransitionsByNameVc {
-    let edge_compile_time_info = get_edge_compile_time_info(server_addr, Value::new(Middleware));
+    let edge_compile_time_info = get_edge_compile_time_info(
+        server_addr,
+        Value::new(Middleware),
+        Value::new(NextMode::Development),
+        next_config,
+    );
 
     let edge_chunking_context = DevChunkingContextVc::builder(
         project_path,
@@ -325,24 +733,97 @@ pub async fn route(
     request: RouterRequestVc,
     next_config: NextConfigVc,
     server_addr: ServerAddrVc,
+    interception_rewrites: RewritesVc,
+    route_handlers: RouteHandlersVc,
+    metadata_routes: MetadataRoutesVc,
     routes_changed: CompletionVc,
 ) -> Result<RouterResultVc> {
+    let request_value = request.await?;
+    // Malformed URLs would otherwise fail deep in the evaluation of the router.
+    if let Some(response) = invalid_request_url_response(&request_value) {
+        return Ok(RouterResult::FullMiddleware(response).cell());
+    }
     let RouterRequest {
         ref method,
         ref pathname,
         ..
-    } = *request.await?;
-    IssueVc::attach_description(
+    } = *request_value;
+    // The shutdown waits for the guard, so it must be held until the routing
+    // is done.
+    let Some(_evaluation) = shutdown::begin_evaluation() else {
+        return Ok(RouterResult::FullMiddleware(FullMiddlewareResponse {
+            headers: MiddlewareHeadersResponse {
+                status_code: 503,
+                headers: HeaderMap::from_iter([("connection", "close"), ("retry-after", "1")]),
+            },
+            body: b"Server is shutting down".to_vec(),
+        })
+        .cell());
+    };
+    let result = IssueVc::attach_description(
         format!("Next.js Routing for {} {}", method, pathname),
         route_internal(
             execution_context,
             request,
             next_config,
             server_addr,
+            interception_rewrites,
+            route_handlers,
+            metadata_routes,
             routes_changed,
         ),
     )
-    .await
+    .await?;
+    if method == "OPTIONS" {
+        if let RouterResult::RouteHandler(data) = &*result.await? {
+            if let Some(response) =
+                default_options_response(execution_context, route_handlers, data).await?
+            {
+                return Ok(RouterResult::FullMiddleware(response).cell());
+            }
+        }
+    }
+    Ok(result)
+}
+
+/// Answers `OPTIONS` requests of route handlers which don't export `OPTIONS`
+/// with the methods they allow. `HEAD` requests of route handlers without
+/// `HEAD` run `GET`, see [resolve_route_handler_method].
+async fn default_options_response(
+    execution_context: ExecutionContextVc,
+    route_handlers: RouteHandlersVc,
+    data: &RewriteResponse,
+) -> Result<Option<FullMiddlewareResponse>> {
+    let pathname = data.url.split('?').next().unwrap_or_default();
+    let Some(route_handler) = *route_handlers
+        .find(pathname.strip_prefix('/').unwrap_or(pathname))
+        .await?
+    else {
+        return Ok(None);
+    };
+    let context = node_evaluate_asset_context(
+        execution_context.await?.project_path,
+        Some(get_next_build_import_map()),
+        None,
+    );
+    let route = context.process(
+        SourceAssetVc::new(route_handler.await?.route).into(),
+        Value::new(ReferenceType::Entry(EntryReferenceSubType::AppRoute)),
+    );
+    let methods = route_handler_methods(route).await?;
+    if resolve_route_handler_method(&methods, "OPTIONS") != RouteHandlerMethod::DefaultOptions {
+        return Ok(None);
+    }
+
+    let mut headers = data.headers.clone();
+    headers.append("allow", route_handler_allowed_methods(&methods).join(", "));
+    Ok(Some(FullMiddlewareResponse {
+        headers: MiddlewareHeadersResponse {
+            status_code: 204,
+            headers,
+        },
+        body: vec![],
+    }))
 }
 
 #[turbo_tasks::function]
@@ -351,8 +832,73 @@ async fn route_internal(
     request: RouterRequestVc,
     next_config: NextConfigVc,
     server_addr: ServerAddrVc,
+    interception_rewrites: RewritesVc,
+    route_handlers: RouteHandlersVc,
+    metadata_routes: MetadataRoutesVc,
+    routes_changed: CompletionVc,
+) -> Result<RouterResultVc> {
+    let request_value = request.await?;
+    let span = tracing::info_span!(
+        "route",
+        method = %request_value.method,
+        pathname = %request_value.pathname
+    );
+    route_request(
+        execution_context,
+        request,
+        next_config,
+        server_addr,
+        interception_rewrites,
+        route_handlers,
+        metadata_routes,
+        routes_changed,
+    )
+    .instrument(span)
+    .await
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn route_request(
+    execution_context: ExecutionContextVc,
+    request: RouterRequestVc,
+    next_config: NextConfigVc,
+    server_addr: ServerAddrVc,
+    interception_rewrites: RewritesVc,
+    route_handlers: RouteHandlersVc,
+    metadata_routes: MetadataRoutesVc,
     routes_changed: CompletionVc,
 ) -> Result<RouterResultVc> {
+    let request_value = request.await?;
+
+    let (builtin_middleware, invalid_ip_allowlist_entries) =
+        BuiltinMiddlewareChain::new(&*next_config.builtin_middleware().await?);
+    if !invalid_ip_allowlist_entries.is_empty() {
+        InvalidIpAllowlistIssue {
+            path: execution_context.await?.project_path.join("next.config.js"),
+            entries: invalid_ip_allowlist_entries,
+        }
+        .cell()
+        .as_issue()
+        .emit();
+    }
+    if let Some(response) = builtin_middleware.handle(&request_value) {
+        return Ok(RouterResult::FullMiddleware(response).cell());
+    }
+
+    // Experiments are bucketed without running the Next.js router, so they
+    // don't wait for middleware.
+    if !is_server_action_request(&request_value) {
+        let experiments = next_config.route_experiments().await?;
+        if let Some(data) = route_experiment(&experiments, &request_value) {
+            let pathname = data.url.split('?').next().unwrap_or_default().to_string();
+            let result =
+                resolve_app_route(route_handlers, metadata_routes, &pathname, data.clone())
+                    .await?
+                    .unwrap_or(RouterResult::Rewrite(data));
+            return Ok(result.cell());
+        }
+    }
+
     let ExecutionContext {
         project_path,
         chunking_context,
@@ -371,13 +917,31 @@ async fn route_internal(
         )),
     );
 
-    let configs = config_assets(context, project_path, next_config.page_extensions());
+    let configs = config_assets(
+        context,
+        project_path,
+        next_config,
+        chunking_context.output_root(),
+    );
     let router_asset = route_executor(context, configs);
 
     // This invalidates the router when the next config changes
     let next_config_changed = next_config_changed(context, project_path);
+    // This restarts the router when dependencies are installed, as it loads
+    // some of them, e.g. middleware dependencies, through node.js
+    let dependencies_changed = dependencies_changed(project_path);
 
-    let request = serde_json::value::to_value(&*request.await?)?;
+    let body_size_limit = next_config
+        .await?
+        .experimental
+        .turbo
+        .as_ref()
+        .and_then(|turbo| turbo.middleware_body_size_limit)
+        .unwrap_or(DEFAULT_MIDDLEWARE_BODY_SIZE_LIMIT);
+
+    let request = serde_json::value::to_value(&*request_value)?;
+    let interception_rewrites =
+        serde_json::value::to_value(&interception_rewrites.await?.before_files)?;
     let Some(dir) = to_sys_path(project_path).await? else {
         bail!("Next.js requires a disk path to check for valid routes");
     };
@@ -392,8 +956,14 @@ async fn route_internal(
         vec![
             JsonValueVc::cell(request),
             JsonValueVc::cell(dir.to_string_lossy().into()),
+            JsonValueVc::cell(interception_rewrites),
+            JsonValueVc::cell(body_size_limit.into()),
         ],
-        CompletionsVc::all(vec![next_config_changed, routes_changed]),
+        CompletionsVc::all(vec![
+            next_config_changed,
+            routes_changed,
+            dependencies_changed,
+        ]),
         /* debug */ false,
     )
     .await?;
@@ -401,7 +971,83 @@ async fn route_internal(
     match &*result {
         JavaScriptValue::Value(val) => {
             let result: RouterIncomingMessage = parse_json_rope_with_source_context(val)?;
-            Ok(RouterResult::from(result).cell())
+            if let RouterIncomingMessage::MiddlewareBodyTooLarge { data } = &result {
+                MiddlewareBodyTooLargeIssue {
+                    path: project_path,
+                    pathname: request_value.pathname.clone(),
+                    limit: data.limit,
+                }
+                .cell()
+                .as_issue()
+                .emit();
+                return Ok(RouterResult::FullMiddleware(FullMiddlewareResponse {
+                    headers: MiddlewareHeadersResponse {
+                        status_code: 500,
+                        headers: HeaderMap::from_iter([(
+                            "content-type",
+                            "text/plain; charset=utf-8",
+                        )]),
+                    },
+                    body: b"Internal Server Error".to_vec(),
+                })
+                .cell());
+            }
+            let result = match RouterResult::from(result) {
+                // Actions are called on the url of the page, the page itself
+                // isn't rendered.
+                RouterResult::None | RouterResult::Rewrite(_)
+                    if is_server_action_request(&request_value) =>
+                {
+                    let url = if request_value.raw_query.is_empty() {
+                        format!("/{SERVER_ACTION_PATHNAME}")
+                    } else {
+                        format!("/{SERVER_ACTION_PATHNAME}?{}", request_value.raw_query)
+                    };
+                    RouterResult::ServerAction(RewriteResponse {
+                        url,
+                        headers: HeaderMap::new(),
+                    })
+                }
+                RouterResult::None => {
+                    let url = if request_value.raw_query.is_empty() {
+                        request_value.pathname.clone()
+                    } else {
+                        format!("{}?{}", request_value.pathname, request_value.raw_query)
+                    };
+                    let data = RewriteResponse {
+                        url,
+                        headers: HeaderMap::new(),
+                    };
+                    resolve_app_route(
+                        route_handlers,
+                        metadata_routes,
+                        &request_value.pathname,
+                        data,
+                    )
+                    .await?
+                    .unwrap_or(RouterResult::None)
+                }
+                RouterResult::Rewrite(data) => {
+                    let (pathname, query) = data.url.split_once('?').unwrap_or((&data.url, ""));
+                    if pathname == "/404" || pathname == "/_error" {
+                        let url = if query.is_empty() {
+                            format!("/{NOT_FOUND_PATHNAME}")
+                        } else {
+                            format!("/{NOT_FOUND_PATHNAME}?{query}")
+                        };
+                        return Ok(RouterResult::NotFound(RewriteResponse {
+                            url,
+                            headers: data.headers,
+                        })
+                        .cell());
+                    }
+                    resolve_app_route(route_handlers, metadata_routes, pathname, data.clone())
+                        .await?
+                        .unwrap_or(RouterResult::Rewrite(data))
+                }
+                result => result,
+            };
+            Ok(result.cell())
         }
         JavaScriptValue::Error => Ok(RouterResult::Error.cell()),
         JavaScriptValue::Stream(_) => {
@@ -409,3 +1055,146 @@ async fn route_internal(
         }
     }
 }
+
+/// Returns whether the request calls a server action, which is a `POST` with
+/// the id of the action in the `Next-Action` header.
+fn is_server_action_request(request: &RouterRequest) -> bool {
+    request.method == "POST" && request.raw_headers.contains(ACTION_HEADER)
+}
+
+/// Returns the result for a pathname served by an app route handler or a
+/// metadata route, if any.
+async fn resolve_app_route(
+    route_handlers: RouteHandlersVc,
+    metadata_routes: MetadataRoutesVc,
+    pathname: &str,
+    data: RewriteResponse,
+) -> Result<Option<RouterResult>> {
+    let pathname = pathname.strip_prefix('/').unwrap_or(pathname);
+    Ok(if metadata_routes.find(pathname).await?.is_some() {
+        Some(RouterResult::MetadataRoute(data))
+    } else if route_handlers.find(pathname).await?.is_some() {
+        Some(RouterResult::RouteHandler(data))
+    } else {
+        None
+    })
+}
+
+#[turbo_tasks::value(shared)]
+pub struct MiddlewareBodyTooLargeIssue {
+    pub path: FileSystemPathVc,
+    pub pathname: String,
+    pub limit: u64,
+}
+
+#[turbo_tasks::value_impl]
+impl Issue for MiddlewareBodyTooLargeIssue {
+    #[turbo_tasks::function]
+    fn severity(&self) -> IssueSeverityVc {
+        IssueSeverity::Error.into()
+    }
+
+    #[turbo_tasks::function]
+    fn category(&self) -> StringVc {
+        StringVc::cell("middleware".to_string())
+    }
+
+    #[turbo_tasks::function]
+    fn context(&self) -> FileSystemPathVc {
+        self.path
+    }
+
+    #[turbo_tasks::function]
+    fn title(&self) -> StringVc {
+        StringVc::cell(format!(
+            "Middleware response body for {} exceeded the limit",
+            self.pathname
+        ))
+    }
+
+    #[turbo_tasks::function]
+    fn description(&self) -> StringVc {
+        StringVc::cell(format!(
+            "The response body produced by middleware was larger than {} bytes, so it was aborted \
+             and a 500 was sent instead. This is often caused by a middleware that streams in a \
+             loop. The limit can be changed with `experimental.turbo.middlewareBodySizeLimit`.",
+            self.limit
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{HeaderMap, RawHeaderValue};
+    use crate::router_source::RAW_HEADERS_HEADER;
+
+    #[test]
+    fn test_raw_header_value_round_trips_bytes() {
+        let value = RawHeaderValue((0x80..=0xff).collect());
+        let json = serde_json::to_string(&value).unwrap();
+        assert_eq!(json.chars().count(), 0x80 + 2);
+        assert_eq!(
+            serde_json::from_str::<RawHeaderValue>(&json).unwrap(),
+            value
+        );
+        for byte in 0x80..=0xff_u8 {
+            let value = RawHeaderValue(vec![b'a', byte]);
+            let json = serde_json::to_string(&value).unwrap();
+            assert_eq!(
+                serde_json::from_str::<RawHeaderValue>(&json).unwrap(),
+                value
+            );
+        }
+    }
+
+    #[test]
+    fn test_raw_header_value_rejects_wide_characters() {
+        for json in [r#""\u0100""#, r#""caf\u00e9 \u20ac""#, r#""\ud83d\ude00""#] {
+            assert!(
+                serde_json::from_str::<RawHeaderValue>(json).is_err(),
+                "{json}"
+            );
+        }
+        assert_eq!(
+            serde_json::from_str::<RawHeaderValue>(r#""caf\u00e9""#).unwrap(),
+            RawHeaderValue(b"caf\xe9".to_vec())
+        );
+    }
+
+    #[test]
+    fn test_header_map_strings_round_trip_bytes() {
+        let headers = HeaderMap::from_iter([
+            ("content-type", RawHeaderValue::from("text/plain")),
+            ("set-cookie", RawHeaderValue::from("a=1")),
+            ("x-latin1", RawHeaderValue(b"caf\xe9".to_vec())),
+            ("set-cookie", RawHeaderValue(b"b=\xff".to_vec())),
+        ]);
+        let strings = headers.to_strings();
+        assert_eq!(
+            strings
+                .iter()
+                .map(|(name, _)| name.as_str())
+                .collect::<Vec<_>>(),
+            ["content-type", RAW_HEADERS_HEADER]
+        );
+        assert!(strings.iter().all(|(_, value)| value.is_ascii()));
+
+        let round_tripped =
+            HeaderMap::from_strings(strings.iter().map(|(n, v)| (n.as_str(), v.as_str())));
+        assert_eq!(
+            round_tripped,
+            HeaderMap::from_iter([
+                ("content-type", RawHeaderValue::from("text/plain")),
+                ("set-cookie", RawHeaderValue::from("a=1")),
+                ("x-latin1", RawHeaderValue(b"caf\xe9".to_vec())),
+                ("set-cookie", RawHeaderValue(b"b=\xff".to_vec())),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_header_map_ignores_invalid_raw_headers() {
+        let headers = HeaderMap::from_strings([("accept", "*/*"), (RAW_HEADERS_HEADER, "%%%")]);
+        assert_eq!(headers, HeaderMap::from_iter([("accept", "*/*")]));
+    }
+}
@@ -0,0 +1,79 @@
+use turbo_tasks_hash::hash_xxh3_hash64;
+
+use crate::{
+    next_config::{ExperimentBucketBy, ExperimentVariant, RouteExperiment},
+    router::{HeaderMap, RewriteResponse, RouterRequest},
+};
+
+/// The response header telling which variant of an experiment the request was
+/// bucketed into, as `{experiment}={variant}`.
+pub const EXPERIMENT_HEADER: &str = "x-nextjs-experiment";
+
+/// Returns the value a request is bucketed by, if the request has it.
+fn bucket_key<'a>(bucket_by: &ExperimentBucketBy, request: &'a RouterRequest) -> Option<&'a str> {
+    let headers = &request.raw_headers;
+    match bucket_by {
+        ExperimentBucketBy::Header(name) => headers.get(name).and_then(|value| value.to_str()),
+        ExperimentBucketBy::Cookie(name) => headers
+            .get_all("cookie")
+            .filter_map(|value| value.to_str())
+            .flat_map(|value| value.split(';'))
+            .filter_map(|cookie| cookie.trim().split_once('='))
+            .find(|(cookie, _)| cookie == name)
+            .map(|(_, value)| value),
+    }
+}
+
+/// Picks the variant of a bucketing key. The same key always gets the same
+/// variant, as long as the variants and their weights don't change.
+fn variant_for_key<'a>(
+    experiment: &'a RouteExperiment,
+    key: &str,
+) -> Option<&'a ExperimentVariant> {
+    let weight = |variant: &ExperimentVariant| u64::from(variant.weight.unwrap_or(1));
+    let total: u64 = experiment.variants.iter().map(weight).sum();
+    if total == 0 {
+        return None;
+    }
+    let hash = hash_xxh3_hash64(format!("{}\0{}", experiment.name, key).as_bytes());
+    let mut bucket = hash % total;
+    experiment.variants.iter().find(|variant| {
+        let weight = weight(variant);
+        if bucket < weight {
+            true
+        } else {
+            bucket -= weight;
+            false
+        }
+    })
+}
+
+/// Buckets a request into a variant of the first experiment configured for its
+/// pathname, and returns the rewrite to the variant. Requests which don't have
+/// the bucketing key of the experiment aren't bucketed.
+pub fn route_experiment(
+    experiments: &[RouteExperiment],
+    request: &RouterRequest,
+) -> Option<RewriteResponse> {
+    let experiment = experiments
+        .iter()
+        .find(|experiment| experiment.source == request.pathname)?;
+    let key = bucket_key(&experiment.bucket_by, request)?;
+    let variant = variant_for_key(experiment, key)?;
+
+    let url = match (
+        request.raw_query.is_empty(),
+        variant.destination.contains('?'),
+    ) {
+        (true, _) => variant.destination.clone(),
+        (false, true) => format!("{}&{}", variant.destination, request.raw_query),
+        (false, false) => format!("{}?{}", variant.destination, request.raw_query),
+    };
+    Some(RewriteResponse {
+        url,
+        headers: HeaderMap::from_iter([(
+            EXPERIMENT_HEADER,
+            format!("{}={}", experiment.name, variant.name),
+        )]),
+    })
+}
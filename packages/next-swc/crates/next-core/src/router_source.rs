@@ -1,23 +1,38 @@
-use anyhow::{anyhow, bail, Context, Result};
+use anyhow::{anyhow, Context, Result};
 use indexmap::IndexSet;
 use turbo_tasks::{primitives::StringVc, CompletionVc, CompletionsVc, Value};
+use turbo_tasks_fs::FileSystemPathVc;
 use turbopack_core::{
     environment::ServerAddrVc,
     introspect::{Introspectable, IntrospectableChildrenVc, IntrospectableVc},
 };
 use turbopack_dev_server::source::{
-    ContentSource, ContentSourceContent, ContentSourceData, ContentSourceDataVary,
-    ContentSourceResultVc, ContentSourceVc, HeaderListVc, NeededData, ProxyResult, RewriteBuilder,
+    combined::CombinedContentSourceVc, ContentSource, ContentSourceContent, ContentSourceData,
+    ContentSourceDataVary, ContentSourceResultVc, ContentSourceVc, HeaderListVc, NeededData,
+    ProxyResult, RewriteBuilder,
 };
 use turbopack_node::execution_context::ExecutionContextVc;
 
 use crate::{
-    app_structure::OptionAppStructureVc,
+    app_structure::{get_interception_rewrites, OptionAppStructureVc},
+    metadata_routes::find_metadata_routes,
     next_config::NextConfigVc,
     pages_structure::OptionPagesStructureVc,
-    router::{route, RouterRequest, RouterResult},
+    route_handlers::get_route_handlers,
+    router::{route, HeaderMap, RouterRequest, RouterResult, INTERNAL_ERROR_PATHNAME},
 };
 
+/// The header through which the dev server passes the address of the peer of
+/// the connection. The server in front of it replaces the header sent by the
+/// client, and it's removed before routing.
+pub const PEER_ADDR_HEADER: &str = "x-next-peer-addr";
+
+/// The header through which the dev server passes the headers with values
+/// which aren't visible ASCII, as it only handles strings. The server in front
+/// of it converts these headers of requests and responses, see
+/// [HeaderMap::to_strings].
+pub const RAW_HEADERS_HEADER: &str = "x-next-raw-headers";
+
 #[turbo_tasks::value(shared)]
 pub struct NextRouterContentSource {
     /// A wrapped content source from which we will fetch assets.
@@ -25,6 +40,7 @@ pub struct NextRouterContentSource {
     execution_context: ExecutionContextVc,
     next_config: NextConfigVc,
     server_addr: ServerAddrVc,
+    server_root: FileSystemPathVc,
     app_structure: OptionAppStructureVc,
     pages_structure: OptionPagesStructureVc,
 }
@@ -37,6 +53,7 @@ impl NextRouterContentSourceVc {
         execution_context: ExecutionContextVc,
         next_config: NextConfigVc,
         server_addr: ServerAddrVc,
+        server_root: FileSystemPathVc,
         app_structure: OptionAppStructureVc,
         pages_structure: OptionPagesStructureVc,
     ) -> NextRouterContentSourceVc {
@@ -45,6 +62,7 @@ impl NextRouterContentSourceVc {
             execution_context,
             next_config,
             server_addr,
+            server_root,
             app_structure,
             pages_structure,
         }
@@ -104,15 +122,26 @@ impl ContentSource for NextRouterContentSource {
             raw_headers: Some(raw_headers),
             raw_query: Some(raw_query),
             ..
-        } = &*data else {
-            return Ok(need_data(self_vc.into(), path))
+        } = &*data
+        else {
+            return Ok(need_data(self_vc.into(), path));
         };
 
+        let peer_addr = raw_headers
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(PEER_ADDR_HEADER))
+            .and_then(|(_, value)| value.parse().ok());
         let request = RouterRequest {
             pathname: format!("/{path}"),
             method: method.clone(),
-            raw_headers: raw_headers.clone(),
+            raw_headers: HeaderMap::from_strings(
+                raw_headers
+                    .iter()
+                    .filter(|(name, _)| !name.eq_ignore_ascii_case(PEER_ADDR_HEADER))
+                    .map(|(name, value)| (name.as_str(), value.as_str())),
+            ),
             raw_query: raw_query.clone(),
+            peer_addr,
         }
         .cell();
 
@@ -121,6 +150,9 @@ impl ContentSource for NextRouterContentSource {
             request,
             this.next_config,
             this.server_addr,
+            get_interception_rewrites(this.app_structure, this.server_root),
+            get_route_handlers(this.app_structure, this.server_root),
+            find_metadata_routes(this.app_structure),
             routes_changed(this.app_structure, this.pages_structure),
         );
 
@@ -129,34 +161,67 @@ impl ContentSource for NextRouterContentSource {
             .with_context(|| anyhow!("failed to fetch /{path}{}", formated_query(raw_query)))?;
 
         Ok(match &*res {
-            RouterResult::Error => bail!(
-                "error during Next.js routing for /{path}{}",
-                formated_query(raw_query)
-            ),
+            RouterResult::Error => {
+                // The error is reported as an issue, the error page is rendered
+                // in its place: the `global-error` of the app directory, or the
+                // 500 page of the pages directory. The static fallback is only
+                // served when neither exists.
+                let inner = CombinedContentSourceVc::new(vec![
+                    this.inner,
+                    InternalErrorFallbackContentSourceVc::new().into(),
+                ]);
+                ContentSourceResultVc::exact(
+                    ContentSourceContent::Rewrite(
+                        RewriteBuilder::new(format!("/{INTERNAL_ERROR_PATHNAME}"))
+                            .content_source(inner.into())
+                            .build(),
+                    )
+                    .cell()
+                    .into(),
+                )
+            }
             RouterResult::None => this
                 .inner
                 .get(path, Value::new(ContentSourceData::default())),
-            RouterResult::Rewrite(data) => {
+            RouterResult::Rewrite(data)
+            | RouterResult::RouteHandler(data)
+            | RouterResult::MetadataRoute(data)
+            | RouterResult::ServerAction(data)
+            | RouterResult::NotFound(data) => {
                 let mut rewrite = RewriteBuilder::new(data.url.clone()).content_source(this.inner);
                 if !data.headers.is_empty() {
-                    rewrite = rewrite.response_headers(HeaderListVc::new(data.headers.clone()));
+                    rewrite =
+                        rewrite.response_headers(HeaderListVc::new(data.headers.to_strings()));
                 }
                 ContentSourceResultVc::exact(
                     ContentSourceContent::Rewrite(rewrite.build()).cell().into(),
                 )
             }
-            RouterResult::FullMiddleware(data) => ContentSourceResultVc::exact(
-                ContentSourceContent::HttpProxy(
-                    ProxyResult {
-                        status: data.headers.status_code,
-                        headers: data.headers.headers.clone(),
-                        body: data.body.clone().into(),
+            RouterResult::FullMiddleware(data) => {
+                let mut headers = data.headers.headers.clone();
+                // Responses to `HEAD` have the headers of the `GET` response,
+                // including the length of the body they don't have.
+                let body = if method == "HEAD" {
+                    if !headers.contains("content-length") {
+                        headers.append("content-length", data.body.len().to_string());
                     }
-                    .cell(),
+                    vec![]
+                } else {
+                    data.body.clone()
+                };
+                ContentSourceResultVc::exact(
+                    ContentSourceContent::HttpProxy(
+                        ProxyResult {
+                            status: data.headers.status_code,
+                            headers: headers.to_strings(),
+                            body: body.into(),
+                        }
+                        .cell(),
+                    )
+                    .cell()
+                    .into(),
                 )
-                .cell()
-                .into(),
-            ),
+            }
         })
     }
 }
@@ -190,3 +255,46 @@ impl Introspectable for NextRouterContentSource {
         Ok(IntrospectableChildrenVc::cell(children))
     }
 }
+
+/// A minimal error page, served when the project doesn't have an error page
+/// which could be rendered instead. It doesn't depend on the project, so it
+/// can't fail itself.
+#[turbo_tasks::value(shared)]
+pub struct InternalErrorFallbackContentSource;
+
+#[turbo_tasks::value_impl]
+impl InternalErrorFallbackContentSourceVc {
+    #[turbo_tasks::function]
+    pub fn new() -> Self {
+        InternalErrorFallbackContentSource.cell()
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl ContentSource for InternalErrorFallbackContentSource {
+    #[turbo_tasks::function]
+    fn get(&self, path: &str, _data: Value<ContentSourceData>) -> ContentSourceResultVc {
+        if path != INTERNAL_ERROR_PATHNAME {
+            return ContentSourceResultVc::not_found();
+        }
+        ContentSourceResultVc::exact(
+            ContentSourceContent::HttpProxy(
+                ProxyResult {
+                    status: 500,
+                    headers: vec![(
+                        "content-type".to_string(),
+                        "text/html; charset=utf-8".to_string(),
+                    )],
+                    body: INTERNAL_ERROR_FALLBACK_HTML.into(),
+                }
+                .cell(),
+            )
+            .cell()
+            .into(),
+        )
+    }
+}
+
+const INTERNAL_ERROR_FALLBACK_HTML: &str =
+    "<!DOCTYPE html><html><head><meta charset=\"utf-8\"><title>500: Internal Server \
+     Error</title></head><body><h1>500</h1><p>Internal Server Error.</p></body></html>";
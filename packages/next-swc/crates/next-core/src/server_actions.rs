@@ -0,0 +1,178 @@
+use anyhow::Result;
+use indexmap::IndexMap;
+use next_transform_server_actions::{generate_action_id, ACTION_ENTRY_MARKER};
+use turbo_tasks::{primitives::BoolVc, Value};
+use turbo_tasks_fs::{FileContent, FileSystemPathVc};
+use turbopack_core::{
+    asset::{Asset, AssetVc},
+    context::{AssetContext, AssetContextVc},
+    reference_type::{EntryReferenceSubType, ReferenceType},
+    source_asset::SourceAssetVc,
+};
+use turbopack_ecmascript::{parse::ParseResult, EcmascriptModuleAssetVc};
+
+use crate::{
+    app_structure::{AppStructureItem, AppStructureVc, LoaderTreeVc},
+    client_references::{
+        ecmascript_module_graph, get_client_references, has_directive, is_node_module,
+        server_component_modules,
+    },
+};
+
+/// The pathname of the endpoint running server actions. Requests calling an
+/// action are rewritten to it by the router.
+pub const SERVER_ACTION_PATHNAME: &str = "_next/server-action";
+
+/// The request header containing the id of the action to call.
+pub const ACTION_HEADER: &str = "next-action";
+
+/// A function marked with `"use server"`, which can be called from the client.
+#[turbo_tasks::value(shared)]
+pub struct ServerAction {
+    /// The id the client calls the action with, see [generate_action_id].
+    pub id: String,
+    /// The name of the export of the action in its module.
+    pub name: String,
+    /// The module exporting the action, compiled for server components.
+    pub module: AssetVc,
+}
+
+#[turbo_tasks::value(transparent)]
+pub struct ServerActions(Vec<ServerActionVc>);
+
+/// Returns the server actions exported by a module. The Server Actions
+/// transform lists them in a comment at the top of the module.
+#[turbo_tasks::function]
+async fn module_server_actions(module: AssetVc) -> Result<ServerActionsVc> {
+    let Some(ecmascript_asset) = EcmascriptModuleAssetVc::resolve_from(module).await? else {
+        return Ok(ServerActionsVc::cell(vec![]));
+    };
+    let ParseResult::Ok { comments, .. } = &*ecmascript_asset.parse().await? else {
+        return Ok(ServerActionsVc::cell(vec![]));
+    };
+    let names = comments.leading.iter().find_map(|entry| {
+        entry.value().iter().find_map(|comment| {
+            comment
+                .text
+                .trim()
+                .strip_prefix(ACTION_ENTRY_MARKER)
+                .map(|names| names.trim().to_string())
+        })
+    });
+    let Some(names) = names else {
+        return Ok(ServerActionsVc::cell(vec![]));
+    };
+
+    let path = module.ident().path().await?;
+    Ok(ServerActionsVc::cell(
+        names
+            .split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .map(|name| {
+                ServerAction {
+                    id: generate_action_id(&path.path, name),
+                    name: name.to_string(),
+                    module,
+                }
+                .cell()
+            })
+            .collect(),
+    ))
+}
+
+/// Returns whether the file is marked with `"use server"`.
+#[turbo_tasks::function]
+async fn is_server_action_module(path: FileSystemPathVc) -> Result<BoolVc> {
+    let FileContent::Content(file) = &*path.read().await? else {
+        return Ok(BoolVc::cell(false));
+    };
+    Ok(BoolVc::cell(
+        file.content()
+            .to_str()
+            .map_or(false, |source| has_directive(&source, "use server")),
+    ))
+}
+
+/// Returns the server actions reachable from the layouts and pages of a
+/// [LoaderTree](crate::app_structure::LoaderTree), except for packages in
+/// `node_modules`. `context` is the server components context of the app
+/// directory.
+#[turbo_tasks::function]
+async fn loader_tree_server_actions(
+    context: AssetContextVc,
+    loader_tree: LoaderTreeVc,
+) -> Result<ServerActionsVc> {
+    let mut actions = Vec::new();
+    for &module in server_component_modules(context, loader_tree).await?.iter() {
+        if !is_node_module(&module.ident().path().await?.path) {
+            actions.extend(module_server_actions(module).await?.iter().copied());
+        }
+    }
+    for &boundary in get_client_references(context, loader_tree).await?.iter() {
+        actions.extend(
+            client_imported_server_actions(context, boundary)
+                .await?
+                .iter()
+                .copied(),
+        );
+    }
+    Ok(ServerActionsVc::cell(actions))
+}
+
+/// Returns the server actions of the `"use server"` files imported by the
+/// client components graph of a client reference. The client only gets stubs
+/// calling them by id, so they're compiled for server components here, where
+/// they're run.
+#[turbo_tasks::function]
+async fn client_imported_server_actions(
+    context: AssetContextVc,
+    boundary: FileSystemPathVc,
+) -> Result<ServerActionsVc> {
+    let module = context.with_transition("next-ssr-client-module").process(
+        SourceAssetVc::new(boundary).into(),
+        Value::new(ReferenceType::Entry(
+            EntryReferenceSubType::AppClientComponent,
+        )),
+    );
+    let mut actions = Vec::new();
+    for module in ecmascript_module_graph(module).await? {
+        let path = module.ident().path();
+        if is_node_module(&path.await?.path) || !*is_server_action_module(path).await? {
+            continue;
+        }
+        let server_module = context.process(
+            SourceAssetVc::new(path).into(),
+            Value::new(ReferenceType::Undefined),
+        );
+        actions.extend(module_server_actions(server_module).await?.iter().copied());
+    }
+    Ok(ServerActionsVc::cell(actions))
+}
+
+/// Returns the server actions used by the pages of the app directory, by id.
+/// They're either imported by server components, or by client components.
+#[turbo_tasks::function]
+pub async fn get_app_server_actions(
+    app_structure: AppStructureVc,
+    context: AssetContextVc,
+) -> Result<ServerActionsVc> {
+    let app_structure = app_structure.await?;
+    let mut actions = IndexMap::new();
+    if let Some(item) = app_structure.item {
+        if let AppStructureItem::Page { loader_tree, .. } = *item.await? {
+            for &action in loader_tree_server_actions(context, loader_tree)
+                .await?
+                .iter()
+            {
+                actions.insert(action.await?.id.clone(), action);
+            }
+        }
+    }
+    for &child in app_structure.children.iter() {
+        for &action in get_app_server_actions(child, context).await?.iter() {
+            actions.insert(action.await?.id.clone(), action);
+        }
+    }
+    Ok(ServerActionsVc::cell(actions.into_values().collect()))
+}
@@ -0,0 +1,209 @@
+use anyhow::{bail, Result};
+use turbo_tasks::{TryJoinIterExt, Value};
+use turbo_tasks_env::ProcessEnvVc;
+use turbo_tasks_fs::{File, FileSystemPathVc};
+use turbopack::{transition::TransitionsByNameVc, ModuleAssetContextVc};
+use turbopack_core::{
+    asset::{Asset, AssetContentVc, AssetVc},
+    chunk::{Chunk, ChunkGroupVc, ChunkReferenceVc, ChunkingContextVc, ChunksVc},
+    compile_time_info::{CompileTimeInfo, CompileTimeInfoVc},
+    context::{AssetContext, AssetContextVc},
+    environment::{BrowserEnvironment, EnvironmentIntention, EnvironmentVc, ExecutionEnvironment},
+    ident::AssetIdentVc,
+    reference::AssetReferencesVc,
+    reference_type::{EntryReferenceSubType, ReferenceType},
+    source_asset::SourceAssetVc,
+};
+use turbopack_dev::DevChunkingContextVc;
+use turbopack_dev_server::source::{
+    asset_graph::AssetGraphContentSourceVc, ContentSourceVc, NoContentSourceVc,
+};
+use turbopack_ecmascript::{
+    chunk::EcmascriptChunkPlaceablesVc, utils::StringifyJs, EcmascriptModuleAssetVc,
+};
+use turbopack_env::ProcessEnvAssetVc;
+use turbopack_node::execution_context::ExecutionContextVc;
+
+use crate::{
+    env::env_for_js,
+    mode::NextMode,
+    next_client::context::{
+        get_client_module_options_context, get_client_resolve_options_context, next_client_defines,
+        ClientContextType,
+    },
+    next_config::NextConfigVc,
+};
+
+#[turbo_tasks::function]
+fn get_service_worker_compile_time_info(
+    browserslist_query: &str,
+    env: ProcessEnvVc,
+    next_config: NextConfigVc,
+) -> CompileTimeInfoVc {
+    CompileTimeInfo {
+        environment: EnvironmentVc::new(
+            Value::new(ExecutionEnvironment::Browser(
+                BrowserEnvironment {
+                    dom: false,
+                    web_worker: true,
+                    service_worker: true,
+                    browserslist_query: browserslist_query.to_owned(),
+                }
+                .into(),
+            )),
+            Value::new(EnvironmentIntention::Client),
+        ),
+        defines: next_client_defines(env, Value::new(NextMode::Development), next_config),
+    }
+    .cell()
+}
+
+/// Create a content source serving the service worker configured by
+/// `experimental.turbo.serviceWorker`.
+///
+/// The service worker entry is compiled for a service worker environment and
+/// served at the root of the app, e.g. `app/sw.ts` is served at `/sw.js`, so
+/// its scope covers the whole app.
+#[turbo_tasks::function]
+pub async fn create_service_worker_source(
+    project_path: FileSystemPathVc,
+    execution_context: ExecutionContextVc,
+    server_root: FileSystemPathVc,
+    env: ProcessEnvVc,
+    browserslist_query: &str,
+    next_config: NextConfigVc,
+) -> Result<ContentSourceVc> {
+    let Some(entry) = &*next_config.service_worker_entry().await? else {
+        return Ok(NoContentSourceVc::new().into());
+    };
+    let entry_path = project_path.join(entry);
+
+    let ty = Value::new(ClientContextType::Other);
+    let compile_time_info =
+        get_service_worker_compile_time_info(browserslist_query, env, next_config);
+    let context: AssetContextVc = ModuleAssetContextVc::new(
+        TransitionsByNameVc::cell(Default::default()),
+        compile_time_info,
+        get_client_module_options_context(
+            project_path,
+            execution_context,
+            compile_time_info.environment(),
+            ty,
+            Value::new(NextMode::Development),
+            next_config,
+        ),
+        get_client_resolve_options_context(project_path, ty, next_config, execution_context),
+    )
+    .into();
+
+    let chunking_context = DevChunkingContextVc::builder(
+        project_path,
+        server_root,
+        server_root.join("/_next/static/chunks/sw"),
+        server_root.join("/_next/static/media"),
+        compile_time_info.environment(),
+    )
+    .build();
+
+    let runtime_entries = EcmascriptChunkPlaceablesVc::cell(vec![ProcessEnvAssetVc::new(
+        project_path,
+        env_for_js(env, true, next_config),
+    )
+    .as_ecmascript_chunk_placeable()]);
+
+    let entry_path_value = entry_path.await?;
+    let file_name = entry_path_value.file_name();
+    let file_stem = file_name
+        .rsplit_once('.')
+        .map_or(file_name, |(file_stem, _)| file_stem);
+    let asset = ServiceWorkerAsset {
+        path: server_root.join(&format!("{file_stem}.js")),
+        context,
+        chunking_context,
+        entry_asset: SourceAssetVc::new(entry_path).into(),
+        runtime_entries,
+    }
+    .cell();
+
+    Ok(AssetGraphContentSourceVc::new_lazy(server_root, asset.into()).into())
+}
+
+/// The service worker script. It loads the chunks of the service worker entry
+/// with `importScripts`, which has to happen synchronously during the initial
+/// evaluation of the service worker.
+#[turbo_tasks::value(shared)]
+pub struct ServiceWorkerAsset {
+    pub path: FileSystemPathVc,
+    pub context: AssetContextVc,
+    pub chunking_context: ChunkingContextVc,
+    pub entry_asset: AssetVc,
+    pub runtime_entries: EcmascriptChunkPlaceablesVc,
+}
+
+#[turbo_tasks::value_impl]
+impl ServiceWorkerAssetVc {
+    #[turbo_tasks::function]
+    async fn get_chunks(self) -> Result<ChunksVc> {
+        let this = &*self.await?;
+
+        let asset = this.context.process(
+            this.entry_asset,
+            Value::new(ReferenceType::Entry(EntryReferenceSubType::Web)),
+        );
+        let Some(asset) = EcmascriptModuleAssetVc::resolve_from(asset).await? else {
+            bail!("the service worker entry must be an ecmascript module");
+        };
+
+        let chunk_group = ChunkGroupVc::from_chunk(
+            asset.as_evaluated_chunk(this.chunking_context, Some(this.runtime_entries)),
+        );
+
+        Ok(chunk_group.chunks())
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl Asset for ServiceWorkerAsset {
+    #[turbo_tasks::function]
+    fn ident(&self) -> AssetIdentVc {
+        AssetIdentVc::from_path(self.path)
+    }
+
+    #[turbo_tasks::function]
+    async fn content(self_vc: ServiceWorkerAssetVc) -> Result<AssetContentVc> {
+        let chunks = self_vc.get_chunks().await?;
+        let dir = self_vc.await?.path.parent().await?;
+
+        let chunk_paths: Vec<_> = chunks
+            .iter()
+            .map(|chunk| {
+                let dir = dir.clone();
+                async move {
+                    Ok(dir
+                        .get_path_to(&*chunk.path().await?)
+                        .map(|path| format!("/{path}")))
+                }
+            })
+            .try_join()
+            .await?
+            .into_iter()
+            .flatten()
+            .collect();
+
+        let content = format!("importScripts(...{:#});\n", StringifyJs(&chunk_paths));
+
+        Ok(AssetContentVc::from(File::from(content)))
+    }
+
+    #[turbo_tasks::function]
+    async fn references(self_vc: ServiceWorkerAssetVc) -> Result<AssetReferencesVc> {
+        let chunks = self_vc.get_chunks().await?;
+
+        let mut references = Vec::with_capacity(chunks.len());
+        for chunk in chunks.iter() {
+            references.push(ChunkReferenceVc::new(*chunk).into());
+        }
+
+        Ok(AssetReferencesVc::cell(references))
+    }
+}
@@ -0,0 +1,87 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Mutex,
+    },
+    time::Duration,
+};
+
+use futures::future::BoxFuture;
+use once_cell::sync::Lazy;
+use tokio::sync::Notify;
+
+/// Coordinates the graceful shutdown of the server: once it begins, no new
+/// evaluations are started, and the shutdown waits for the in-flight ones
+/// before running the shutdown hooks, e.g. to flush caches.
+static SHUTDOWN: Lazy<Shutdown> = Lazy::new(Shutdown::default);
+
+type ShutdownHook = Box<dyn FnOnce() -> BoxFuture<'static, ()> + Send>;
+
+#[derive(Default)]
+struct Shutdown {
+    shutting_down: AtomicBool,
+    in_flight: AtomicUsize,
+    drained: Notify,
+    hooks: Mutex<Vec<ShutdownHook>>,
+}
+
+/// An in-flight evaluation, which the shutdown waits for until it's dropped.
+pub struct EvaluationGuard(());
+
+impl Drop for EvaluationGuard {
+    fn drop(&mut self) {
+        if SHUTDOWN.in_flight.fetch_sub(1, Ordering::AcqRel) == 1 {
+            SHUTDOWN.drained.notify_waiters();
+        }
+    }
+}
+
+/// Registers an in-flight evaluation. Returns `None` once the shutdown has
+/// begun, in which case the evaluation must not be started.
+pub fn begin_evaluation() -> Option<EvaluationGuard> {
+    SHUTDOWN.in_flight.fetch_add(1, Ordering::AcqRel);
+    let guard = EvaluationGuard(());
+    if SHUTDOWN.shutting_down.load(Ordering::Acquire) {
+        return None;
+    }
+    Some(guard)
+}
+
+pub fn is_shutting_down() -> bool {
+    SHUTDOWN.shutting_down.load(Ordering::Acquire)
+}
+
+/// Registers a hook which runs once the in-flight evaluations are drained, or
+/// the shutdown deadline has passed.
+pub fn on_shutdown<F>(hook: F)
+where
+    F: FnOnce() -> BoxFuture<'static, ()> + Send + 'static,
+{
+    SHUTDOWN.hooks.lock().unwrap().push(Box::new(hook));
+}
+
+/// Stops accepting new evaluations, waits up to `deadline` for the in-flight
+/// ones, and runs the shutdown hooks. Returns whether all in-flight
+/// evaluations finished in time.
+pub async fn shutdown(deadline: Duration) -> bool {
+    SHUTDOWN.shutting_down.store(true, Ordering::Release);
+    let drained = tokio::time::timeout(deadline, async {
+        loop {
+            // The notification must be registered before checking, so a drop in
+            // between isn't missed.
+            let notified = SHUTDOWN.drained.notified();
+            if SHUTDOWN.in_flight.load(Ordering::Acquire) == 0 {
+                return;
+            }
+            notified.await;
+        }
+    })
+    .await
+    .is_ok();
+
+    let hooks = std::mem::take(&mut *SHUTDOWN.hooks.lock().unwrap());
+    for hook in hooks {
+        hook().await;
+    }
+    drained
+}
@@ -0,0 +1,95 @@
+use anyhow::Result;
+use indexmap::IndexSet;
+use turbo_tasks::{
+    graph::{GraphTraversal, NonDeterministic},
+    primitives::StringsVc,
+    Value,
+};
+use turbo_tasks_fs::{DirectoryContent, DirectoryEntry, FileSystemEntryType, FileSystemPathVc};
+use turbopack::evaluate_context::node_evaluate_asset_context;
+use turbopack_core::{
+    asset::{Asset, AssetVc},
+    context::AssetContext,
+    reference::all_referenced_assets,
+    reference_type::{EntryReferenceSubType, ReferenceType},
+    source_asset::SourceAssetVc,
+};
+
+use crate::next_import_map::get_next_package;
+
+/// The server of Next.js, which the standalone output starts.
+const NEXT_SERVER: &str = "dist/server/next-server.js";
+
+/// Returns the files the server output in `server_dir` loads at runtime, and
+/// the server of Next.js with its dependencies, as paths relative to the root
+/// of the file system of `project_path`. Like node-file-trace, the modules are
+/// resolved as Node.js would, following `require`s and imports, and native
+/// addons and files read with `fs` are included where they can be determined
+/// statically.
+#[turbo_tasks::function]
+pub async fn trace_server_files(
+    project_path: FileSystemPathVc,
+    server_dir: FileSystemPathVc,
+) -> Result<StringsVc> {
+    let context = node_evaluate_asset_context(project_path, None, None);
+    let mut entries = Vec::new();
+    for path in server_output_files(server_dir).await? {
+        entries.push(context.process(
+            SourceAssetVc::new(path).into(),
+            Value::new(ReferenceType::Entry(EntryReferenceSubType::Undefined)),
+        ));
+    }
+    entries.push(context.process(
+        SourceAssetVc::new(get_next_package(project_path).join(NEXT_SERVER)).into(),
+        Value::new(ReferenceType::Entry(EntryReferenceSubType::Undefined)),
+    ));
+
+    let assets: IndexSet<AssetVc> =
+        GraphTraversal::<NonDeterministic<_>>::visit(entries, get_referenced_assets)
+            .await
+            .completed()?
+            .into_iter()
+            .collect();
+
+    let mut files = IndexSet::new();
+    for asset in assets {
+        let path = asset.ident().path();
+        // Assets without a file, e.g. the ones generated for `node:` builtins,
+        // have no content on disk.
+        if *path.get_type().await? == FileSystemEntryType::File {
+            files.insert(path.await?.path.clone());
+        }
+    }
+    files.sort();
+    Ok(StringsVc::cell(files.into_iter().collect()))
+}
+
+async fn get_referenced_assets(asset: AssetVc) -> Result<Vec<AssetVc>> {
+    Ok(all_referenced_assets(asset)
+        .await?
+        .iter()
+        .copied()
+        .collect())
+}
+
+/// Returns the JavaScript files in the server output directory and its
+/// subdirectories.
+async fn server_output_files(dir: FileSystemPathVc) -> Result<Vec<FileSystemPathVc>> {
+    let mut files = Vec::new();
+    let mut dirs = vec![dir];
+    while let Some(dir) = dirs.pop() {
+        let DirectoryContent::Entries(entries) = &*dir.read_dir().await? else {
+            continue;
+        };
+        for (name, entry) in entries.iter() {
+            match entry {
+                DirectoryEntry::File(file) if name.ends_with(".js") || name.ends_with(".mjs") => {
+                    files.push(*file)
+                }
+                DirectoryEntry::Directory(dir) => dirs.push(*dir),
+                _ => {}
+            }
+        }
+    }
+    Ok(files)
+}
@@ -0,0 +1,415 @@
+use std::collections::HashMap;
+
+use anyhow::{bail, Result};
+use indexmap::indexmap;
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use tracing::Instrument;
+use turbo_tasks::{
+    primitives::{JsonValueVc, OptionStringVc, StringVc},
+    trace::TraceRawVcs,
+    Value,
+};
+use turbo_tasks_env::ProcessEnvVc;
+use turbo_tasks_fs::{json::parse_json_rope_with_source_context, FileSystem, FileSystemPathVc};
+use turbopack_core::{
+    asset::{Asset, AssetVc},
+    changed::any_content_changed,
+    context::{AssetContext, AssetContextVc},
+    environment::ServerAddrVc,
+    issue::{Issue, IssueSeverity, IssueSeverityVc, IssueVc},
+    reference_type::{EntryReferenceSubType, ReferenceType},
+    server_fs::ServerFileSystemVc,
+    source_asset::SourceAssetVc,
+};
+use turbopack_ecmascript::{
+    EcmascriptInputTransform, EcmascriptInputTransformsVc, EcmascriptModuleAssetType,
+    EcmascriptModuleAssetVc, InnerAssetsVc,
+};
+use turbopack_node::{
+    evaluate::{evaluate, JavaScriptValue},
+    execution_context::{ExecutionContext, ExecutionContextVc},
+};
+
+use crate::{
+    app_structure::{find_app_structure, AppStructureItem},
+    embed_js::next_asset,
+    entry_compilation::compile_entries,
+    html_post_processing::{get_html_post_processors, post_process_html},
+    mode::NextMode,
+    next_config::NextConfigVc,
+    next_server::context::ServerContextType,
+    pages_structure::{find_pages_structure, PagesStructureItem},
+    route_list::{has_export, server_context},
+    static_generation::generate_static_pages,
+    util::pathname_for_path,
+};
+
+/// A page of the static export, see [export_static_pages].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TraceRawVcs)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportedPage {
+    pub pathname: String,
+    /// The path of the HTML file in the export directory, see
+    /// [export_file_path].
+    pub file: String,
+    pub html: String,
+}
+
+#[turbo_tasks::value(transparent)]
+pub struct ExportedPages(Vec<ExportedPage>);
+
+/// Renders the pages of the app for `output: 'export'`, which writes them as
+/// HTML files that any static file server can serve.
+///
+/// Pages are rendered with `getStaticProps`, and dynamic pages for every path
+/// of `getStaticPaths`. Routes which need a server, e.g. API routes and pages
+/// with `getServerSideProps`, are reported with an
+/// [ExportUnsupportedRoutesIssue] and skipped.
+#[turbo_tasks::function]
+pub async fn export_static_pages(
+    project_path: FileSystemPathVc,
+    execution_context: ExecutionContextVc,
+    env: ProcessEnvVc,
+    server_addr: ServerAddrVc,
+    next_config: NextConfigVc,
+) -> Result<ExportedPagesVc> {
+    let server_root = ServerFileSystemVc::new().as_file_system().root();
+    let mut unsupported_routes = Vec::new();
+    // The modules of the pages to render, by pathname.
+    let mut pages = HashMap::new();
+    let mut context = None;
+
+    if let Some(pages_structure) =
+        *find_pages_structure(project_path, server_root, next_config).await?
+    {
+        let pages_context = server_context(
+            project_path,
+            execution_context,
+            Value::new(ServerContextType::Pages {
+                pages_dir: pages_structure.directory(),
+            }),
+            env,
+            server_addr,
+            Value::new(NextMode::Build),
+            next_config,
+        );
+        context = Some(pages_context);
+        let mut entries = Vec::new();
+        let mut queue = vec![pages_structure];
+        while let Some(structure) = queue.pop() {
+            let structure = structure.await?;
+            queue.extend(structure.children.iter().copied());
+            for item in structure.items.iter() {
+                entries.push(match *item.await? {
+                    PagesStructureItem::Page { url, page, .. } => (url, page, false),
+                    PagesStructureItem::Api { url, api, .. } => (url, api, true),
+                });
+            }
+        }
+        // Every page with either its module or the reason it isn't exported.
+        let compiled = compile_entries(
+            entries,
+            &*next_config.entry_compilation_options().await?,
+            |(url, file, is_api)| async move {
+                let pathname = format!(
+                    "/{}",
+                    pathname_for_path(server_root, url, true, false).await?
+                );
+                if is_api {
+                    return Ok((pathname, Err("API routes run on the server")));
+                }
+                let module = pages_context.process(
+                    SourceAssetVc::new(file).into(),
+                    Value::new(ReferenceType::Entry(EntryReferenceSubType::Page)),
+                );
+                let module = if has_export(module, "getServerSideProps").await? {
+                    Err("`getServerSideProps` runs on every request")
+                } else if pathname.contains('[') && !has_export(module, "getStaticPaths").await? {
+                    Err("dynamic pages need `getStaticPaths` to know their paths")
+                } else {
+                    Ok(module)
+                };
+                Ok((pathname, module))
+            },
+        )
+        .await?;
+        for (pathname, module) in compiled {
+            match module {
+                Ok(module) => {
+                    pages.insert(pathname, module);
+                }
+                Err(reason) => unsupported_routes.push((pathname, reason.to_string())),
+            }
+        }
+    }
+
+    if let Some(app_structure) = *find_app_structure(project_path, server_root, next_config).await?
+    {
+        let mut queue = vec![app_structure];
+        while let Some(structure) = queue.pop() {
+            let structure = structure.await?;
+            queue.extend(structure.children.iter().copied());
+            let Some(item) = structure.item else {
+                continue;
+            };
+            let url = match *item.await? {
+                AppStructureItem::Page { url, .. } | AppStructureItem::Route { url, .. } => url,
+            };
+            unsupported_routes.push((
+                format!(
+                    "/{}",
+                    pathname_for_path(server_root, url, false, false).await?
+                ),
+                "the app directory isn't exported by Turbopack yet".to_string(),
+            ));
+        }
+    }
+
+    if !unsupported_routes.is_empty() {
+        unsupported_routes.sort();
+        ExportUnsupportedRoutesIssue {
+            path: project_path.join("next.config.js"),
+            routes: unsupported_routes,
+        }
+        .cell()
+        .as_issue()
+        .emit();
+    }
+
+    let Some(context) = context else {
+        return Ok(ExportedPagesVc::cell(vec![]));
+    };
+    let trailing_slash = *next_config.trailing_slash().await?;
+    let options = next_config.static_generation_options().await?;
+    let pages = &pages;
+    let rendered = generate_static_pages(
+        pages.keys().cloned().collect(),
+        &options,
+        |pathname| async move {
+            Ok(export_page(
+                context,
+                execution_context,
+                pages[&pathname],
+                &pathname,
+                false,
+            )
+            .await?
+            .clone_value())
+        },
+    )
+    .await?;
+
+    let processors = get_html_post_processors(execution_context, next_config);
+    let mut exported = Vec::new();
+    for RenderedPage { pathname, html, .. } in
+        rendered.into_iter().flat_map(|page| page.content.pages)
+    {
+        let html = post_process_html(processors, &pathname, html).await?;
+        exported.push(ExportedPage {
+            file: export_file_path(&pathname, trailing_slash),
+            pathname,
+            html,
+        });
+    }
+    exported.sort_by(|a, b| a.pathname.cmp(&b.pathname));
+    Ok(ExportedPagesVc::cell(exported))
+}
+
+/// How a dynamic page handles the paths its `getStaticPaths` doesn't return.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TraceRawVcs)]
+pub(crate) enum StaticPathsFallback {
+    /// `fallback: false`, the paths respond with a 404.
+    NotFound,
+    /// `fallback: true`, the fallback version of the page is served while the
+    /// path is rendered in the background.
+    Page,
+    /// `fallback: 'blocking'`, the paths are rendered on their first request.
+    Blocking,
+}
+
+/// A path of a page rendered by [export_page].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TraceRawVcs)]
+pub(crate) struct RenderedPage {
+    pub pathname: String,
+    pub html: String,
+    /// The `revalidate` returned by `getStaticProps`, `None` without ISR.
+    pub revalidate: Option<u32>,
+}
+
+#[turbo_tasks::value(shared)]
+#[derive(Debug, Clone)]
+pub(crate) struct RenderedPages {
+    /// `None` when the page doesn't export `getStaticPaths`.
+    pub fallback: Option<StaticPathsFallback>,
+    pub pages: Vec<RenderedPage>,
+    /// The paths for which `getStaticProps` returns `notFound: true`.
+    pub not_found: Vec<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ExportPageResult {
+    fallback: JsonValue,
+    pages: Vec<ExportPageResultPage>,
+    not_found: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct ExportPageResultPage {
+    pathname: String,
+    html: String,
+    revalidate: JsonValue,
+}
+
+/// Renders a page of the pages directory in node.js, for every path of its
+/// `getStaticPaths`. Unless `allow_fallback` is set, a `fallback` other than
+/// `false` fails the rendering, as it needs a server.
+#[turbo_tasks::function]
+pub(crate) async fn export_page(
+    context: AssetContextVc,
+    execution_context: ExecutionContextVc,
+    page: AssetVc,
+    pathname: &str,
+    allow_fallback: bool,
+) -> Result<RenderedPagesVc> {
+    let span = tracing::info_span!("evaluate", layer = "export", pathname);
+    evaluate_export(context, execution_context, page, pathname, allow_fallback)
+        .instrument(span)
+        .await
+}
+
+async fn evaluate_export(
+    context: AssetContextVc,
+    execution_context: ExecutionContextVc,
+    page: AssetVc,
+    pathname: &str,
+    allow_fallback: bool,
+) -> Result<RenderedPagesVc> {
+    let ExecutionContext {
+        project_path,
+        chunking_context,
+        env,
+    } = *execution_context.await?;
+
+    let entry = EcmascriptModuleAssetVc::new_with_inner_assets(
+        next_asset("entry/export-page.tsx"),
+        context,
+        Value::new(EcmascriptModuleAssetType::Typescript),
+        EcmascriptInputTransformsVc::cell(vec![
+            EcmascriptInputTransform::TypeScript {
+                use_define_for_class_fields: false,
+            },
+            EcmascriptInputTransform::React {
+                refresh: false,
+                import_source: OptionStringVc::cell(None),
+                runtime: OptionStringVc::cell(None),
+            },
+        ]),
+        context.compile_time_info(),
+        InnerAssetsVc::cell(indexmap! {
+            "INNER".to_string() => page,
+        }),
+    );
+
+    let result = evaluate(
+        entry.into(),
+        project_path,
+        env,
+        page.ident(),
+        context,
+        chunking_context.with_layer("export"),
+        None,
+        vec![
+            JsonValueVc::cell(pathname.into()),
+            JsonValueVc::cell(allow_fallback.into()),
+        ],
+        // This invalidates the export when anything referenced by the page
+        // changes
+        any_content_changed(entry.into()),
+        /* debug */ false,
+    )
+    .await?;
+    let JavaScriptValue::Value(val) = &*result else {
+        bail!("Failed to export the page {pathname}");
+    };
+    let result: ExportPageResult = parse_json_rope_with_source_context(val)?;
+    let fallback = match result.fallback {
+        JsonValue::Null => None,
+        JsonValue::Bool(false) => Some(StaticPathsFallback::NotFound),
+        JsonValue::Bool(true) => Some(StaticPathsFallback::Page),
+        _ => Some(StaticPathsFallback::Blocking),
+    };
+    Ok(RenderedPages {
+        fallback,
+        pages: result
+            .pages
+            .into_iter()
+            .map(|page| RenderedPage {
+                pathname: page.pathname,
+                html: page.html,
+                revalidate: page.revalidate.as_u64().map(|seconds| seconds as u32),
+            })
+            .collect(),
+        not_found: result.not_found,
+    }
+    .cell())
+}
+
+/// Returns the path of the HTML file of a pathname in the export directory,
+/// e.g. `about.html`, or `about/index.html` with `trailingSlash`. The not found
+/// page is always `404.html`, where static hosts look for it.
+pub fn export_file_path(pathname: &str, trailing_slash: bool) -> String {
+    let path = pathname.trim_start_matches('/');
+    if path.is_empty() {
+        "index.html".to_string()
+    } else if trailing_slash && path != "404" {
+        format!("{path}/index.html")
+    } else {
+        format!("{path}.html")
+    }
+}
+
+/// Routes which can't be exported by `output: 'export'`, as they need a
+/// server.
+#[turbo_tasks::value(shared)]
+pub struct ExportUnsupportedRoutesIssue {
+    pub path: FileSystemPathVc,
+    /// The pathnames of the routes, with the reason they can't be exported.
+    pub routes: Vec<(String, String)>,
+}
+
+#[turbo_tasks::value_impl]
+impl Issue for ExportUnsupportedRoutesIssue {
+    #[turbo_tasks::function]
+    fn severity(&self) -> IssueSeverityVc {
+        IssueSeverity::Error.into()
+    }
+
+    #[turbo_tasks::function]
+    fn category(&self) -> StringVc {
+        StringVc::cell("export".to_string())
+    }
+
+    #[turbo_tasks::function]
+    fn context(&self) -> FileSystemPathVc {
+        self.path
+    }
+
+    #[turbo_tasks::function]
+    fn title(&self) -> StringVc {
+        StringVc::cell("Routes can't be exported with `output: 'export'`".to_string())
+    }
+
+    #[turbo_tasks::function]
+    fn description(&self) -> StringVc {
+        StringVc::cell(format!(
+            "A static export is served without a server, which these routes need:\n\n{}",
+            self.routes
+                .iter()
+                .map(|(pathname, reason)| format!("- {pathname}: {reason}"))
+                .collect::<Vec<_>>()
+                .join("\n")
+        ))
+    }
+}
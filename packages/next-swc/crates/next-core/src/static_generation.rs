@@ -0,0 +1,127 @@
+use std::{collections::HashMap, future::Future, time::Duration};
+
+use anyhow::{anyhow, bail, Result};
+use futures::{stream, StreamExt};
+use turbo_tasks::util::FormatDuration;
+
+use crate::build_progress::BuildProgress;
+
+/// How static pages are generated, from `experimental.cpus` and
+/// `staticPageGenerationTimeout` of the next.config.js.
+#[turbo_tasks::value(shared)]
+#[derive(Clone, Copy, Debug)]
+pub struct StaticGenerationOptions {
+    /// The maximum number of pages rendered at the same time.
+    pub concurrency: usize,
+    /// The maximum number of seconds rendering a single page may take.
+    pub timeout_secs: f64,
+}
+
+impl StaticGenerationOptions {
+    /// Defaults to one render per CPU but one, like `next build`.
+    pub fn default_concurrency() -> usize {
+        std::thread::available_parallelism()
+            .map_or(1, |cpus| cpus.get().saturating_sub(1))
+            .max(1)
+    }
+
+    pub fn timeout(&self) -> Duration {
+        Duration::from_secs_f64(self.timeout_secs)
+    }
+}
+
+/// A page rendered by [generate_static_pages].
+pub struct StaticPage<T> {
+    pub pathname: String,
+    pub content: T,
+}
+
+/// Renders the pages at `pathnames` with at most `options.concurrency` renders
+/// in flight, so the node.js render pool is kept busy without queueing every
+/// page of a large site at once. Rendering a page fails when it takes longer
+/// than the timeout. All pages are rendered even when some fail, and the
+/// failures are reported together. The pages are returned sorted by pathname.
+pub async fn generate_static_pages<T, F, Fut>(
+    pathnames: Vec<String>,
+    options: &StaticGenerationOptions,
+    render: F,
+) -> Result<Vec<StaticPage<T>>>
+where
+    F: Fn(String) -> Fut,
+    Fut: Future<Output = Result<T>>,
+{
+    let timeout = options.timeout();
+    let results = stream::iter(pathnames)
+        .map(|pathname| {
+            let render = render(pathname.clone());
+            async move {
+                let result = match tokio::time::timeout(timeout, render).await {
+                    Ok(result) => result,
+                    Err(_) => Err(anyhow!("timed out after {}", FormatDuration(timeout))),
+                };
+                (pathname, result)
+            }
+        })
+        .buffer_unordered(options.concurrency.max(1))
+        .collect::<Vec<_>>()
+        .await;
+
+    let mut pages = Vec::new();
+    let mut failures = Vec::new();
+    for (pathname, result) in results {
+        match result {
+            Ok(content) => pages.push(StaticPage { pathname, content }),
+            Err(err) => failures.push(format!("- {pathname}: {err:#}")),
+        }
+    }
+    if !failures.is_empty() {
+        failures.sort();
+        bail!(
+            "Failed to generate {} static page(s):\n{}",
+            failures.len(),
+            failures.join("\n")
+        );
+    }
+    pages.sort_by(|a, b| a.pathname.cmp(&b.pathname));
+    Ok(pages)
+}
+
+/// A page to render with [generate_static_pages_resumable].
+pub struct StaticPageInput {
+    pub pathname: String,
+    /// A hash of the inputs of the page, e.g. from
+    /// [crate::inputs_manifest::fingerprint_inputs].
+    pub fingerprint: String,
+}
+
+/// Like [generate_static_pages], but skips the pages which an earlier build
+/// already completed with the same fingerprint, and records every page it
+/// completes in `progress`. Only the rendered pages are returned, the others
+/// are still in the output of the earlier build.
+pub async fn generate_static_pages_resumable<T, F, Fut>(
+    pages: Vec<StaticPageInput>,
+    options: &StaticGenerationOptions,
+    progress: &BuildProgress,
+    render: F,
+) -> Result<Vec<StaticPage<T>>>
+where
+    F: Fn(String) -> Fut,
+    Fut: Future<Output = Result<T>>,
+{
+    let mut fingerprints = HashMap::new();
+    let mut pathnames = Vec::new();
+    for page in pages {
+        if !progress.is_completed(&page.pathname, &page.fingerprint) {
+            pathnames.push(page.pathname.clone());
+            fingerprints.insert(page.pathname, page.fingerprint);
+        }
+    }
+    let fingerprints = &fingerprints;
+    let render = &render;
+    generate_static_pages(pathnames, options, |pathname| async move {
+        let content = render(pathname.clone()).await?;
+        progress.complete(&pathname, &fingerprints[&pathname])?;
+        Ok(content)
+    })
+    .await
+}
@@ -0,0 +1,207 @@
+use std::fmt::Write;
+
+use anyhow::{bail, Result};
+use indexmap::IndexMap;
+use serde::Deserialize;
+use tracing::Instrument;
+use turbo_tasks::{primitives::OptionStringVc, Value, ValueToString};
+use turbo_tasks_fs::{
+    json::parse_json_rope_with_source_context, rope::RopeBuilder, File, FileContent,
+};
+use turbopack_core::{
+    changed::any_content_changed,
+    context::{AssetContext, AssetContextVc},
+    ident::AssetIdentVc,
+    virtual_asset::VirtualAssetVc,
+};
+use turbopack_ecmascript::{
+    utils::StringifyJs, EcmascriptInputTransform, EcmascriptInputTransformsVc,
+    EcmascriptModuleAssetType, EcmascriptModuleAssetVc,
+};
+use turbopack_node::{
+    evaluate::{evaluate, JavaScriptValue},
+    execution_context::{ExecutionContext, ExecutionContextVc},
+    route_matcher::Param,
+};
+
+use crate::{app_structure::LoaderTreeVc, embed_js::next_js_file};
+
+/// The params of a dynamic page which are known at build time.
+#[turbo_tasks::value(shared)]
+pub struct StaticParams {
+    /// The params returned by `generateStaticParams`, which are prerendered.
+    /// `None` when no segment exports `generateStaticParams`.
+    pub params: Option<Vec<IndexMap<String, Param>>>,
+    /// Whether params which aren't returned by `generateStaticParams` are
+    /// rendered on demand. Otherwise they respond with a 404.
+    pub dynamic_params: bool,
+}
+
+impl StaticParams {
+    /// Returns whether the page is rendered for the params of a request.
+    pub fn allows(&self, params: &IndexMap<String, Param>) -> bool {
+        match &self.params {
+            Some(static_params) if !self.dynamic_params => static_params
+                .iter()
+                .any(|static_params| same_params(static_params, params)),
+            _ => true,
+        }
+    }
+}
+
+/// Compares params, treating a missing optional catch-all param like an empty
+/// one.
+fn same_params(a: &IndexMap<String, Param>, b: &IndexMap<String, Param>) -> bool {
+    fn is_set(param: &Param) -> bool {
+        !matches!(param, Param::Multi(segments) if segments.is_empty())
+    }
+    a.iter()
+        .filter(|(_, param)| is_set(param))
+        .all(|(name, param)| b.get(name) == Some(param))
+        && b.iter()
+            .filter(|(_, param)| is_set(param))
+            .all(|(name, _)| a.contains_key(name))
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ParamValue {
+    Single(String),
+    Multi(Vec<String>),
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct StaticParamsResult {
+    params: Option<Vec<IndexMap<String, ParamValue>>>,
+    dynamic_params: bool,
+}
+
+/// Evaluates `generateStaticParams` of the layouts and the page of the
+/// [LoaderTree](crate::app_structure::LoaderTree) in node.js. `context` is the
+/// server components context of the app directory.
+#[turbo_tasks::function]
+pub async fn get_static_params(
+    context: AssetContextVc,
+    execution_context: ExecutionContextVc,
+    loader_tree: LoaderTreeVc,
+) -> Result<StaticParamsVc> {
+    let span = tracing::info_span!("evaluate", layer = "static_params");
+    evaluate_static_params(context, execution_context, loader_tree)
+        .instrument(span)
+        .await
+}
+
+async fn evaluate_static_params(
+    context: AssetContextVc,
+    execution_context: ExecutionContextVc,
+    loader_tree: LoaderTreeVc,
+) -> Result<StaticParamsVc> {
+    let ExecutionContext {
+        project_path,
+        chunking_context,
+        env,
+    } = *execution_context.await?;
+
+    // The page is rendered in the `children` of every segment.
+    let mut modules = Vec::new();
+    let mut tree = Some(loader_tree);
+    while let Some(current) = tree {
+        let current = current.await?;
+        let components = current.components.await?;
+        modules.extend(components.layout);
+        modules.extend(components.page);
+        tree = current.parallel_routes.get("children").copied();
+    }
+    let Some(&page) = modules.last() else {
+        bail!("The loader tree doesn't contain a page");
+    };
+
+    let path = page.parent();
+    let path_value = path.await?;
+    let mut result = RopeBuilder::default();
+    let mut segments = Vec::new();
+    for (index, module) in modules.iter().enumerate() {
+        let Some(p) = path_value.get_relative_path_to(&*module.await?) else {
+            bail!(
+                "There is no relative path to {} from {}",
+                module.to_string().await?,
+                path.to_string().await?
+            );
+        };
+        writeln!(
+            result,
+            "import * as SEGMENT_{index} from {};",
+            StringifyJs(&p)
+        )?;
+        segments.push(format!("SEGMENT_{index}"));
+    }
+    writeln!(result, "const SEGMENTS = [{}];\n", segments.join(", "))?;
+    if let FileContent::Content(base_file) = &*next_js_file("entry/app/static-params.ts").await? {
+        result += base_file.content()
+    }
+    let entry = VirtualAssetVc::new(
+        path.join("static-params.ts"),
+        File::from(result.build()).into(),
+    );
+    let entry = EcmascriptModuleAssetVc::new(
+        entry.into(),
+        context,
+        Value::new(EcmascriptModuleAssetType::Typescript),
+        EcmascriptInputTransformsVc::cell(vec![
+            EcmascriptInputTransform::React {
+                refresh: false,
+                import_source: OptionStringVc::cell(None),
+                runtime: OptionStringVc::cell(None),
+            },
+            EcmascriptInputTransform::TypeScript {
+                use_define_for_class_fields: false,
+            },
+        ]),
+        context.compile_time_info(),
+    );
+
+    let result = evaluate(
+        entry.into(),
+        project_path,
+        env,
+        AssetIdentVc::from_path(page),
+        context,
+        chunking_context.with_layer("static_params"),
+        None,
+        vec![],
+        // This invalidates the execution when anything referenced by the
+        // segments changes
+        any_content_changed(entry.into()),
+        /* debug */ false,
+    )
+    .await?;
+    let JavaScriptValue::Value(val) = &*result else {
+        bail!(
+            "Failed to evaluate generateStaticParams of {}",
+            page.to_string().await?
+        );
+    };
+    let result: StaticParamsResult = parse_json_rope_with_source_context(val)?;
+    Ok(StaticParams {
+        params: result.params.map(|params| {
+            params
+                .into_iter()
+                .map(|params| {
+                    params
+                        .into_iter()
+                        .map(|(name, value)| {
+                            let param = match value {
+                                ParamValue::Single(value) => Param::Single(value),
+                                ParamValue::Multi(values) => Param::Multi(values),
+                            };
+                            (name, param)
+                        })
+                        .collect()
+                })
+                .collect()
+        }),
+        dynamic_params: result.dynamic_params,
+    }
+    .cell())
+}
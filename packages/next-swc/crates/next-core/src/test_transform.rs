@@ -0,0 +1,206 @@
+use indexmap::IndexMap;
+use serde::Deserialize;
+use serde_json::{json, Value as JsonValue};
+
+use crate::{
+    mode::NextMode,
+    next_config::{DefineValue, ModeDefinesConfig},
+    next_shared::defines::{next_define_values, NextRuntime},
+};
+
+/// The options of the transform of a module for tests, which `next/jest`
+/// passes for every file it transforms.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TestTransformOptions {
+    pub filename: String,
+    /// Whether the tests run in Node.js, i.e. the `node` test environment,
+    /// instead of a browser-like environment.
+    #[serde(default)]
+    pub is_server: bool,
+    /// Whether the module is kept an ES module instead of being compiled to
+    /// CommonJS.
+    #[serde(default)]
+    pub esm: bool,
+    /// The resolved `next.config.js`.
+    #[serde(default)]
+    pub next_config: JsonValue,
+    /// The `tsconfig.json` or `jsconfig.json` of the project.
+    #[serde(default)]
+    pub js_config: JsonValue,
+    pub resolved_base_url: Option<String>,
+    pub pages_dir: Option<String>,
+    #[serde(default)]
+    pub has_server_components: bool,
+    /// The version of Node.js the tests run in, which the output targets.
+    pub node_version: Option<String>,
+}
+
+/// Returns the options of `next-swc`'s transform for a module in tests.
+///
+/// The module is compiled like Turbopack compiles it for a build: TypeScript
+/// and JSX are transformed with the same settings, styled-jsx is applied, and
+/// the compile-time defines are the ones of [NextMode::Test], with the ones of
+/// `experimental.turbo.defines.test`. The output targets Node.js and has an
+/// inline source map for Jest.
+pub fn test_transform_options(options: &TestTransformOptions) -> JsonValue {
+    let TestTransformOptions {
+        filename,
+        is_server,
+        esm,
+        next_config,
+        js_config,
+        resolved_base_url,
+        pages_dir,
+        has_server_components,
+        node_version,
+    } = options;
+    let compiler_options = &js_config["compilerOptions"];
+
+    let is_ts_file = filename.ends_with(".ts");
+    let is_typescript = is_ts_file || filename.ends_with(".tsx");
+    let decorators = compiler_options["experimentalDecorators"]
+        .as_bool()
+        .unwrap_or(false);
+    let decorator_metadata = compiler_options["emitDecoratorMetadata"]
+        .as_bool()
+        .unwrap_or(false);
+    let use_define_for_class_fields = compiler_options["useDefineForClassFields"]
+        .as_bool()
+        .unwrap_or(false);
+    let mut parser = json!({
+        "syntax": if is_typescript { "typescript" } else { "ecmascript" },
+        "dynamicImport": true,
+        "decorators": decorators,
+        "importAssertions": true,
+    });
+    // Regular TypeScript files aren't parsed as JSX, so e.g. generic arrow
+    // functions and angle-bracket type assertions aren't taken for tags.
+    parser[if is_typescript { "tsx" } else { "jsx" }] = JsonValue::Bool(!is_ts_file);
+
+    let runtime = if *is_server {
+        NextRuntime::NodeJs
+    } else {
+        NextRuntime::Browser
+    };
+    let globals = test_globals(runtime, &next_config["experimental"]["turbo"]["defines"]);
+
+    let import_source = compiler_options["jsxImportSource"].as_str().unwrap_or(
+        if next_config["compiler"]["emotion"].is_null() {
+            "react"
+        } else {
+            "@emotion/react"
+        },
+    );
+
+    let mut jsc = json!({
+        "externalHelpers": false,
+        "parser": parser,
+        "experimental": {
+            "keepImportAssertions": true,
+        },
+        "transform": {
+            "hidden": {
+                "jest": true,
+            },
+            "legacyDecorator": decorators,
+            "decoratorMetadata": decorator_metadata,
+            "useDefineForClassFields": use_define_for_class_fields,
+            "react": {
+                "importSource": import_source,
+                "runtime": "automatic",
+                "pragma": "React.createElement",
+                "pragmaFrag": "React.Fragment",
+                "throwIfNamespace": true,
+                "development": false,
+                "useBuiltins": true,
+                "refresh": false,
+            },
+            "optimizer": {
+                "simplify": false,
+                "globals": globals,
+            },
+        },
+    });
+    if let (Some(base_url), paths @ JsonValue::Object(_)) =
+        (resolved_base_url, &compiler_options["paths"])
+    {
+        jsc["baseUrl"] = JsonValue::String(base_url.clone());
+        jsc["paths"] = paths.clone();
+    }
+
+    let path = filename.replace('\\', "/");
+    let is_next_dist = ["shared/lib", "client", "pages"]
+        .iter()
+        .any(|dir| path.contains(&format!("next/dist/{dir}/")));
+
+    let mut options = json!({
+        "filename": filename,
+        "jsc": jsc,
+        "sourceMaps": "inline",
+        "module": {
+            "type": if *esm && !is_next_dist { "es6" } else { "commonjs" },
+        },
+        "removeConsole": next_config["compiler"]["removeConsole"],
+        "reactRemoveProperties": false,
+        "modularizeImports": next_config["modularizeImports"],
+        "relay": next_config["compiler"]["relay"],
+        "styledJsx": true,
+        "serverComponents": if *has_server_components {
+            json!({ "isServer": is_server })
+        } else {
+            JsonValue::Null
+        },
+        "serverActions": if *has_server_components {
+            json!({ "isServer": is_server })
+        } else {
+            JsonValue::Null
+        },
+        "disableNextSsg": true,
+        "disablePageConfig": true,
+        "pagesDir": pages_dir,
+    });
+    if let Some(node_version) = node_version {
+        options["env"] = json!({
+            "targets": {
+                "node": node_version,
+            },
+        });
+    }
+    options
+}
+
+/// Returns the `globals` of the optimizer, which inline the compile-time
+/// defines of [NextMode::Test] like Turbopack does.
+fn test_globals(runtime: NextRuntime, config_defines: &JsonValue) -> JsonValue {
+    let mut defines = serde_json::from_value::<ModeDefinesConfig>(config_defines.clone())
+        .map(|config| config.values(NextMode::Test))
+        .unwrap_or_default();
+    // The defines of Next.js take precedence, like in builds.
+    defines.extend(next_define_values(runtime, NextMode::Test));
+
+    let mut envs = IndexMap::new();
+    let mut vars = IndexMap::new();
+    for (name, value) in defines {
+        let value = match value {
+            DefineValue::Bool(value) => value.to_string(),
+            DefineValue::String(value) => JsonValue::String(value).to_string(),
+        };
+        match name.as_slice() {
+            [process, env, name] if process == "process" && env == "env" => {
+                envs.insert(name.clone(), value);
+            }
+            _ => {
+                vars.insert(name.join("."), value);
+            }
+        }
+    }
+
+    json!({
+        "typeofs": {
+            "window": runtime.typeof_window(),
+        },
+        "envs": envs,
+        "vars": vars,
+    })
+}
@@ -0,0 +1,207 @@
+use anyhow::Result;
+use indexmap::IndexMap;
+use serde_json::{json, Value as JsonValue};
+use turbo_tasks::{primitives::JsonValueVc, Value};
+use turbo_tasks_env::ProcessEnvVc;
+use turbo_tasks_fs::{FileSystem, FileSystemPathVc};
+use turbopack::resolve_options;
+use turbopack_core::{
+    compile_time_info::{CompileTimeDefineValue, CompileTimeDefinesVc},
+    server_fs::ServerFileSystemVc,
+};
+use turbopack_node::execution_context::ExecutionContextVc;
+
+use crate::{
+    mode::NextMode,
+    next_client::context::{
+        get_client_resolve_options_context, next_client_defines, ClientContextType,
+    },
+    next_config::NextConfigVc,
+    next_edge::context::{get_edge_resolve_options_context, next_edge_defines},
+    next_server::context::{
+        get_server_resolve_options_context, next_server_defines, ServerContextType,
+    },
+    pages_structure::find_pages_structure,
+    typescript::get_tsconfig_paths,
+};
+
+/// A context of Next.js whose configuration [get_transform_config] exports.
+#[turbo_tasks::value(serialization = "auto_for_input")]
+#[derive(Debug, Copy, Clone, Hash, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum TransformConfigContext {
+    /// The modules of pages in the browser.
+    Client,
+    /// The modules of pages rendered in Node.js.
+    Server,
+    /// The modules of pages rendered in the edge runtime.
+    Edge,
+}
+
+/// Returns the resolve and transform configuration Turbopack applies to the
+/// modules of a context, in a format independent of Turbopack, so other
+/// bundlers, e.g. the one of Storybook, can resolve and compile the modules of
+/// the app the same way:
+///
+/// * `resolve.alias`: the aliases of `experimental.turbo.resolveAlias` with the
+///   conditions of the context applied, and the `paths` of the `tsconfig.json`,
+///   in the format of webpack's `resolve.alias`. Paths are relative to the
+///   project directory, and `*` is a wildcard.
+/// * `resolve.extensions`, `resolve.conditions`, `resolve.browser` and
+///   `resolve.module`: how requests are resolved to files and packages.
+/// * `defines`: the compile-time defines, keyed by the expression they replace,
+///   e.g. `process.env.NODE_ENV`.
+///
+/// The aliases Next.js adds to its own modules aren't part of it, as they
+/// point to modules which only exist in Turbopack.
+#[turbo_tasks::function]
+pub async fn get_transform_config(
+    project_path: FileSystemPathVc,
+    execution_context: ExecutionContextVc,
+    env: ProcessEnvVc,
+    context: Value<TransformConfigContext>,
+    mode: Value<NextMode>,
+    next_config: NextConfigVc,
+) -> Result<JsonValueVc> {
+    let server_root = ServerFileSystemVc::new().as_file_system().root();
+    let pages_dir = match *find_pages_structure(project_path, server_root, next_config).await? {
+        Some(pages_structure) => pages_structure.directory(),
+        None => project_path.join("pages"),
+    };
+
+    let context = context.into_value();
+    let (resolve_options_context, defines, alias_condition) = match context {
+        TransformConfigContext::Client => (
+            get_client_resolve_options_context(
+                project_path,
+                Value::new(ClientContextType::Pages { pages_dir }),
+                next_config,
+                execution_context,
+            ),
+            next_client_defines(env, mode, next_config),
+            "browser",
+        ),
+        TransformConfigContext::Server => (
+            get_server_resolve_options_context(
+                project_path,
+                Value::new(ServerContextType::Pages { pages_dir }),
+                next_config,
+                execution_context,
+            ),
+            next_server_defines(mode, next_config),
+            "server",
+        ),
+        TransformConfigContext::Edge => (
+            get_edge_resolve_options_context(
+                project_path,
+                Value::new(ServerContextType::Pages { pages_dir }),
+                next_config,
+                execution_context,
+            ),
+            next_edge_defines(mode, next_config),
+            "server",
+        ),
+    };
+    let resolve_options_context_value = resolve_options_context.await?;
+    let extensions = resolve_options(project_path, resolve_options_context)
+        .await?
+        .extensions
+        .clone();
+
+    let mut alias = IndexMap::new();
+    let config = next_config.await?;
+    if let Some(resolve_alias) = config
+        .experimental
+        .turbo
+        .as_ref()
+        .and_then(|turbo| turbo.resolve_alias.as_ref())
+    {
+        for (pattern, value) in resolve_alias {
+            if let Some(value) = apply_alias_conditions(value, alias_condition) {
+                alias.insert(pattern.clone(), value);
+            }
+        }
+    }
+    alias.extend(tsconfig_paths_aliases(project_path).await?);
+
+    Ok(JsonValueVc::cell(json!({
+        "context": context,
+        "resolve": {
+            "alias": alias,
+            "extensions": extensions,
+            "conditions": resolve_options_context_value.custom_conditions,
+            "browser": resolve_options_context_value.browser,
+            "module": resolve_options_context_value.module,
+        },
+        "defines": defines_to_json(defines).await?,
+    })))
+}
+
+/// Applies the conditions of a context to the value of an alias of
+/// `experimental.turbo.resolveAlias`, which can be an object keyed by
+/// conditions like the `exports` field of a `package.json`. Returns `None`
+/// when no condition matches.
+fn apply_alias_conditions(value: &JsonValue, condition: &str) -> Option<JsonValue> {
+    match value {
+        JsonValue::String(_) | JsonValue::Bool(false) => Some(value.clone()),
+        JsonValue::Array(alternatives) => {
+            let alternatives: Vec<_> = alternatives
+                .iter()
+                .filter_map(|value| apply_alias_conditions(value, condition))
+                .collect();
+            (!alternatives.is_empty()).then_some(JsonValue::Array(alternatives))
+        }
+        JsonValue::Object(conditions) => conditions
+            .iter()
+            .filter(|(key, _)| *key == condition || *key == "default")
+            .find_map(|(_, value)| apply_alias_conditions(value, condition)),
+        _ => None,
+    }
+}
+
+/// Returns the `paths` of the project's tsconfig or jsconfig as aliases,
+/// relative to the project directory.
+async fn tsconfig_paths_aliases(
+    project_path: FileSystemPathVc,
+) -> Result<IndexMap<String, JsonValue>> {
+    let tsconfig_paths = get_tsconfig_paths(project_path).await?;
+    let mut aliases = IndexMap::new();
+    let Some(paths_base) = tsconfig_paths.paths_base else {
+        return Ok(aliases);
+    };
+    // Paths outside of the project can't be expressed relative to it.
+    let project_path_value = project_path.await?;
+    let paths_base = paths_base.await?;
+    let Some(paths_base) = project_path_value.get_path_to(&paths_base) else {
+        return Ok(aliases);
+    };
+    let prefix = if paths_base.is_empty() {
+        "./".to_string()
+    } else {
+        format!("./{paths_base}/")
+    };
+    for (pattern, substitutions) in &tsconfig_paths.paths {
+        let substitutions = substitutions
+            .iter()
+            .map(|substitution| {
+                JsonValue::String(format!("{prefix}{}", substitution.trim_start_matches("./")))
+            })
+            .collect();
+        aliases.insert(pattern.clone(), JsonValue::Array(substitutions));
+    }
+    Ok(aliases)
+}
+
+async fn defines_to_json(defines: CompileTimeDefinesVc) -> Result<IndexMap<String, JsonValue>> {
+    Ok(defines
+        .await?
+        .iter()
+        .map(|(name, value)| {
+            let value = match value {
+                CompileTimeDefineValue::Bool(value) => JsonValue::Bool(*value),
+                CompileTimeDefineValue::String(value) => JsonValue::String(value.clone()),
+            };
+            (name.join("."), value)
+        })
+        .collect())
+}
@@ -1,40 +1,54 @@
 use anyhow::Result;
+use serde_json::Value as JsonValue;
 use turbo_tasks_fs::FileSystemPathVc;
-use turbopack::module_options::{TypescriptTransformOptions, TypescriptTransformOptionsVc};
+use turbopack::module_options::{
+    DecoratorsKind, DecoratorsOptions, DecoratorsOptionsVc, TypescriptTransformOptions,
+    TypescriptTransformOptionsVc,
+};
 use turbopack_core::{
+    asset::{Asset, AssetVc},
     resolve::{find_context_file, node::node_cjs_resolve_options, FindContextFileResult},
     source_asset::SourceAssetVc,
 };
 use turbopack_ecmascript::typescript::resolve::{read_from_tsconfigs, read_tsconfigs, tsconfig};
 
-// Get the transform options for specifically for the typescript's runtime
-// outputs
-#[turbo_tasks::function]
-pub async fn get_typescript_transform_options(
+/// Reads the `compilerOptions` value computed by `read` from the project's
+/// tsconfig or jsconfig (following `extends`), if there is one. `read` also
+/// receives the config file the options are read from.
+async fn read_tsconfig_compiler_option<T>(
     project_path: FileSystemPathVc,
-) -> Result<TypescriptTransformOptionsVc> {
+    read: impl Fn(&JsonValue, AssetVc) -> Option<T>,
+) -> Result<Option<T>> {
     let tsconfig = find_context_file(project_path, tsconfig());
     let tsconfig = match *tsconfig.await? {
-        FindContextFileResult::Found(path, _) => Some(
+        FindContextFileResult::Found(path, _) => {
             read_tsconfigs(
                 path.read(),
                 SourceAssetVc::new(path).into(),
                 node_cjs_resolve_options(path.root()),
             )
-            .await?,
-        ),
-        FindContextFileResult::NotFound(_) => None,
+            .await?
+        }
+        FindContextFileResult::NotFound(_) => return Ok(None),
     };
 
-    let use_define_for_class_fields = if let Some(tsconfig) = tsconfig {
-        read_from_tsconfigs(&tsconfig, |json, _| {
-            json["compilerOptions"]["useDefineForClassFields"].as_bool()
-        })
-        .await?
-        .unwrap_or(false)
-    } else {
-        false
-    };
+    read_from_tsconfigs(&tsconfig, |json, source| {
+        read(&json["compilerOptions"], source)
+    })
+    .await
+}
+
+// Get the transform options for specifically for the typescript's runtime
+// outputs
+#[turbo_tasks::function]
+pub async fn get_typescript_transform_options(
+    project_path: FileSystemPathVc,
+) -> Result<TypescriptTransformOptionsVc> {
+    let use_define_for_class_fields = read_tsconfig_compiler_option(project_path, |options, _| {
+        options["useDefineForClassFields"].as_bool()
+    })
+    .await?
+    .unwrap_or(false);
 
     let ts_transform_options = TypescriptTransformOptions {
         use_define_for_class_fields,
@@ -42,3 +56,94 @@ pub async fn get_typescript_transform_options(
 
     Ok(ts_transform_options.cell())
 }
+
+/// Get the decorators transform options from the `experimentalDecorators` and
+/// `emitDecoratorMetadata` tsconfig options. Decorators are left untouched
+/// when `experimentalDecorators` is not enabled.
+///
+/// Each option is resolved on its own, so an option inherited through
+/// `extends` applies even when the extending config sets another one.
+#[turbo_tasks::function]
+pub async fn get_decorators_transform_options(
+    project_path: FileSystemPathVc,
+) -> Result<DecoratorsOptionsVc> {
+    let experimental_decorators = read_tsconfig_compiler_option(project_path, |options, _| {
+        options["experimentalDecorators"].as_bool()
+    })
+    .await?
+    .unwrap_or(false);
+    if !experimental_decorators {
+        return Ok(DecoratorsOptions::default().cell());
+    }
+
+    let emit_decorators_metadata = read_tsconfig_compiler_option(project_path, |options, _| {
+        options["emitDecoratorMetadata"].as_bool()
+    })
+    .await?
+    .unwrap_or(false);
+    let use_define_for_class_fields = read_tsconfig_compiler_option(project_path, |options, _| {
+        options["useDefineForClassFields"].as_bool()
+    })
+    .await?
+    .unwrap_or(false);
+
+    Ok(DecoratorsOptions {
+        decorators_kind: Some(DecoratorsKind::Legacy),
+        emit_decorators_metadata,
+        use_define_for_class_fields,
+        ..Default::default()
+    }
+    .cell())
+}
+
+/// The `baseUrl` and `paths` options of the project's tsconfig or jsconfig.
+#[turbo_tasks::value(shared)]
+pub struct TsConfigPaths {
+    /// The directory non-relative module requests are resolved from.
+    pub base_url: Option<FileSystemPathVc>,
+    /// The directory `paths` substitutions are resolved from. This is the
+    /// `baseUrl` when set, and the directory of the config defining `paths`
+    /// otherwise.
+    pub paths_base: Option<FileSystemPathVc>,
+    /// The `paths` patterns with their substitutions, in declaration order.
+    pub paths: Vec<(String, Vec<String>)>,
+}
+
+#[turbo_tasks::function]
+pub async fn get_tsconfig_paths(project_path: FileSystemPathVc) -> Result<TsConfigPathsVc> {
+    let base_url = read_tsconfig_compiler_option(project_path, |options, source| {
+        let base_url = options["baseUrl"].as_str()?;
+        Some(source.ident().path().parent().join(base_url))
+    })
+    .await?;
+
+    let paths = read_tsconfig_compiler_option(project_path, |options, source| {
+        let paths = options["paths"].as_object()?;
+        let paths = paths
+            .iter()
+            .map(|(pattern, substitutions)| {
+                let substitutions = substitutions
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|s| s.as_str().map(|s| s.to_string()))
+                    .collect();
+                (pattern.clone(), substitutions)
+            })
+            .collect::<Vec<_>>();
+        Some((source.ident().path().parent(), paths))
+    })
+    .await?;
+
+    let (paths_base, paths) = match paths {
+        Some((config_dir, paths)) => (Some(base_url.unwrap_or(config_dir)), paths),
+        None => (None, vec![]),
+    };
+
+    Ok(TsConfigPaths {
+        base_url,
+        paths_base,
+        paths,
+    }
+    .cell())
+}
@@ -1,6 +1,8 @@
 use anyhow::{anyhow, bail, Context, Result};
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
-use swc_core::ecma::ast::Program;
+use swc_core::ecma::ast::{
+    Expr, ExprOrSpread, ParenExpr, Program, TsAsExpr, TsConstAssertion, TsSatisfiesExpr,
+};
 use turbo_tasks::{primitives::StringVc, trace::TraceRawVcs, Value, ValueToString};
 use turbo_tasks_fs::{json::parse_json_rope_with_source_context, FileContent, FileSystemPathVc};
 use turbopack::condition::ContextCondition;
@@ -15,7 +17,7 @@ use turbopack_core::{
     },
 };
 use turbopack_ecmascript::{
-    analyzer::{JsValue, ObjectPart},
+    analyzer::{ConstantValue, JsValue, ObjectPart},
     parse::ParseResult,
     EcmascriptModuleAssetVc,
 };
@@ -92,6 +94,47 @@ pub enum NextRuntime {
     Edge,
 }
 
+#[derive(PartialEq, Eq, Clone, Copy, Debug, TraceRawVcs, Serialize, Deserialize)]
+pub enum NextSegmentDynamic {
+    Auto,
+    ForceDynamic,
+    Error,
+    ForceStatic,
+}
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug, TraceRawVcs, Serialize, Deserialize)]
+pub enum NextSegmentFetchCache {
+    Auto,
+    DefaultCache,
+    OnlyCache,
+    ForceCache,
+    DefaultNoStore,
+    OnlyNoStore,
+    ForceNoStore,
+}
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug, TraceRawVcs, Serialize, Deserialize)]
+pub enum NextRevalidate {
+    /// `revalidate = false`, the segment is cached indefinitely.
+    Never,
+    /// Revalidate at most every n seconds.
+    After(u32),
+}
+
+/// The route segment config exports of a page, layout or route file. Exports
+/// which are missing or invalid are `None`.
+#[turbo_tasks::value]
+#[derive(Default)]
+pub struct NextSegmentConfig {
+    pub dynamic: Option<NextSegmentDynamic>,
+    pub dynamic_params: Option<bool>,
+    pub revalidate: Option<NextRevalidate>,
+    pub fetch_cache: Option<NextSegmentFetchCache>,
+    pub runtime: Option<NextRuntime>,
+    pub preferred_region: Option<Vec<String>>,
+    pub max_duration: Option<u32>,
+}
+
 #[turbo_tasks::value]
 #[derive(Default)]
 pub struct NextSourceConfig {
@@ -99,6 +142,34 @@ pub struct NextSourceConfig {
 
     /// Middleware router matchers
     pub matcher: Option<Vec<String>>,
+
+    /// The `api` property of the config export of an API route.
+    pub api: Option<NextApiConfig>,
+}
+
+/// A size limit of an API route, see [NextApiConfig].
+#[derive(Default, PartialEq, Eq, Clone, Copy, Debug, TraceRawVcs, Serialize, Deserialize)]
+pub enum NextApiSizeLimit {
+    /// The limit is turned off with `false`.
+    Disabled,
+    /// The default limit of Next.js.
+    #[default]
+    Default,
+    /// A limit in bytes.
+    Bytes(u64),
+}
+
+/// The `config.api` export of an API route, which configures how the request
+/// and response bodies are handled by the API resolver.
+#[derive(Default, PartialEq, Eq, Clone, Debug, TraceRawVcs, Serialize, Deserialize)]
+pub struct NextApiConfig {
+    /// `bodyParser`. When disabled, the handler reads the body stream itself.
+    pub body_parser: NextApiSizeLimit,
+    /// `responseLimit`, above which a warning is logged.
+    pub response_limit: NextApiSizeLimit,
+    /// `externalResolver`, which silences the warning about unresolved
+    /// requests.
+    pub external_resolver: bool,
 }
 
 #[turbo_tasks::value_impl]
@@ -153,65 +224,257 @@ impl Issue for NextSourceConfigParsingIssue {
     }
 }
 
+/// Parses the `config` export of a route or middleware file. The `runtime`
+/// segment config export (`export const runtime = "edge"`) takes precedence
+/// over `config.runtime`.
 #[turbo_tasks::function]
 pub async fn parse_config_from_source(module_asset: AssetVc) -> Result<NextSourceConfigVc> {
-    if let Some(ecmascript_asset) = EcmascriptModuleAssetVc::resolve_from(module_asset).await? {
-        if let ParseResult::Ok {
-            program: Program::Module(module),
-            eval_context,
-            ..
-        } = &*ecmascript_asset.parse().await?
-        {
-            for item in &module.body {
-                if let Some(decl) = item
-                    .as_module_decl()
-                    .and_then(|mod_decl| mod_decl.as_export_decl())
-                    .and_then(|export_decl| export_decl.decl.as_var())
-                {
-                    for decl in &decl.decls {
-                        if decl
-                            .name
-                            .as_ident()
-                            .map(|ident| &*ident.sym == "config")
-                            .unwrap_or_default()
-                        {
-                            if let Some(init) = decl.init.as_ref() {
-                                let value = eval_context.eval(init);
-                                return Ok(parse_config_from_js_value(module_asset, &value).cell());
-                            } else {
-                                NextSourceConfigParsingIssue {
-                                    ident: module_asset.ident(),
-                                    detail: StringVc::cell(
-                                        "The exported config object must contain an variable \
-                                         initializer."
-                                            .to_string(),
-                                    ),
-                                }
-                                .cell()
-                                .as_issue()
-                                .emit()
-                            }
-                        }
+    let mut config = NextSourceConfig::default();
+    for (_, _, value) in exported_values(module_asset, &["config"]).await? {
+        config = parse_config_from_js_value(module_asset, &value);
+    }
+    if let Some(runtime) = parse_segment_config_from_source(module_asset)
+        .await?
+        .runtime
+    {
+        config.runtime = runtime;
+    }
+    Ok(config.cell())
+}
+
+/// Parses the route segment config exports of a page, layout or route file,
+/// e.g. `export const revalidate = 60`.
+///
+/// Only literal values are supported, as they need to be known at compile
+/// time.
+#[turbo_tasks::function]
+pub async fn parse_segment_config_from_source(
+    module_asset: AssetVc,
+) -> Result<NextSegmentConfigVc> {
+    let mut config = NextSegmentConfig::default();
+    for (name, expr, value) in exported_values(
+        module_asset,
+        &[
+            "dynamic",
+            "dynamicParams",
+            "revalidate",
+            "fetchCache",
+            "runtime",
+            "preferredRegion",
+            "maxDuration",
+        ],
+    )
+    .await?
+    {
+        if !is_literal(&expr) {
+            invalid_config(
+                module_asset,
+                &format!(
+                    "The `{name}` export must be a literal value, computed expressions are not \
+                     supported."
+                ),
+                &value,
+            );
+            continue;
+        }
+        let invalid = |detail: &str| {
+            invalid_config(
+                module_asset,
+                &format!("The `{name}` export {detail}"),
+                &value,
+            )
+        };
+        match &*name {
+            "dynamic" => {
+                config.dynamic = match value.as_str() {
+                    Some("auto") => Some(NextSegmentDynamic::Auto),
+                    Some("force-dynamic") => Some(NextSegmentDynamic::ForceDynamic),
+                    Some("error") => Some(NextSegmentDynamic::Error),
+                    Some("force-static") => Some(NextSegmentDynamic::ForceStatic),
+                    _ => {
+                        invalid(
+                            "must be one of \"auto\", \"force-dynamic\", \"error\" or \
+                             \"force-static\".",
+                        );
+                        None
                     }
                 }
             }
+            "dynamicParams" => {
+                config.dynamic_params = match &value {
+                    JsValue::Constant(ConstantValue::True) => Some(true),
+                    JsValue::Constant(ConstantValue::False) => Some(false),
+                    _ => {
+                        invalid("must be a boolean.");
+                        None
+                    }
+                }
+            }
+            "revalidate" => {
+                config.revalidate = match &value {
+                    JsValue::Constant(ConstantValue::False) => Some(NextRevalidate::Never),
+                    JsValue::Constant(ConstantValue::Num(seconds)) if seconds.0 >= 0.0 => {
+                        Some(NextRevalidate::After(seconds.0 as u32))
+                    }
+                    _ => {
+                        invalid("must be `false` or a non-negative number of seconds.");
+                        None
+                    }
+                }
+            }
+            "fetchCache" => {
+                config.fetch_cache = match value.as_str() {
+                    Some("auto") => Some(NextSegmentFetchCache::Auto),
+                    Some("default-cache") => Some(NextSegmentFetchCache::DefaultCache),
+                    Some("only-cache") => Some(NextSegmentFetchCache::OnlyCache),
+                    Some("force-cache") => Some(NextSegmentFetchCache::ForceCache),
+                    Some("default-no-store") => Some(NextSegmentFetchCache::DefaultNoStore),
+                    Some("only-no-store") => Some(NextSegmentFetchCache::OnlyNoStore),
+                    Some("force-no-store") => Some(NextSegmentFetchCache::ForceNoStore),
+                    _ => {
+                        invalid("must be a valid fetch cache option, e.g. \"force-cache\".");
+                        None
+                    }
+                }
+            }
+            "runtime" => {
+                config.runtime = parse_runtime_from_js_value(module_asset, &value);
+            }
+            "preferredRegion" => {
+                config.preferred_region = match &value {
+                    JsValue::Constant(region) => {
+                        region.as_str().map(|region| vec![region.to_string()])
+                    }
+                    JsValue::Array { items, .. } => items
+                        .iter()
+                        .map(|item| item.as_str().map(|region| region.to_string()))
+                        .collect(),
+                    _ => None,
+                };
+                if config.preferred_region.is_none() {
+                    invalid("must be a string or an array of strings.");
+                }
+            }
+            "maxDuration" => {
+                config.max_duration = match &value {
+                    JsValue::Constant(ConstantValue::Num(seconds)) if seconds.0 > 0.0 => {
+                        Some(seconds.0 as u32)
+                    }
+                    _ => {
+                        invalid("must be a positive number of seconds.");
+                        None
+                    }
+                }
+            }
+            _ => unreachable!(),
         }
     }
-    Ok(NextSourceConfigVc::default())
+    Ok(config.cell())
 }
 
-fn parse_config_from_js_value(module_asset: AssetVc, value: &JsValue) -> NextSourceConfig {
-    let mut config = NextSourceConfig::default();
-    let invalid_config = |detail: &str, value: &JsValue| {
-        let (explainer, hints) = value.explain(2, 0);
-        NextSourceConfigParsingIssue {
-            ident: module_asset.ident(),
-            detail: StringVc::cell(format!("{detail} Got {explainer}.{hints}")),
+/// Returns the name, initializer and evaluated value of the exported variable
+/// declarations with one of the given names.
+async fn exported_values(
+    module_asset: AssetVc,
+    names: &[&str],
+) -> Result<Vec<(String, Expr, JsValue)>> {
+    let mut values = vec![];
+    let Some(ecmascript_asset) = EcmascriptModuleAssetVc::resolve_from(module_asset).await? else {
+        return Ok(values);
+    };
+    let ParseResult::Ok {
+        program: Program::Module(module),
+        eval_context,
+        ..
+    } = &*ecmascript_asset.parse().await?
+    else {
+        return Ok(values);
+    };
+    for item in &module.body {
+        let Some(decl) = item
+            .as_module_decl()
+            .and_then(|mod_decl| mod_decl.as_export_decl())
+            .and_then(|export_decl| export_decl.decl.as_var())
+        else {
+            continue;
+        };
+        for decl in &decl.decls {
+            let Some(name) = decl.name.as_ident().map(|ident| &*ident.sym) else {
+                continue;
+            };
+            if !names.contains(&name) {
+                continue;
+            }
+            let Some(init) = decl.init.as_ref() else {
+                NextSourceConfigParsingIssue {
+                    ident: module_asset.ident(),
+                    detail: StringVc::cell(format!(
+                        "The exported {name} must contain an variable initializer."
+                    )),
+                }
+                .cell()
+                .as_issue()
+                .emit();
+                continue;
+            };
+            values.push((name.to_string(), (**init).clone(), eval_context.eval(init)));
         }
-        .cell()
-        .as_issue()
-        .emit()
+    }
+    Ok(values)
+}
+
+fn is_literal(expr: &Expr) -> bool {
+    match expr {
+        Expr::Lit(_) => true,
+        Expr::Paren(ParenExpr { expr, .. })
+        | Expr::TsConstAssertion(TsConstAssertion { expr, .. })
+        | Expr::TsAs(TsAsExpr { expr, .. })
+        | Expr::TsSatisfies(TsSatisfiesExpr { expr, .. }) => is_literal(expr),
+        Expr::Array(array) => array.elems.iter().all(
+            |elem| matches!(elem, Some(ExprOrSpread { spread: None, expr }) if is_literal(expr)),
+        ),
+        _ => false,
+    }
+}
+
+fn invalid_config(module_asset: AssetVc, detail: &str, value: &JsValue) {
+    let (explainer, hints) = value.explain(2, 0);
+    NextSourceConfigParsingIssue {
+        ident: module_asset.ident(),
+        detail: StringVc::cell(format!("{detail} Got {explainer}.{hints}")),
+    }
+    .cell()
+    .as_issue()
+    .emit()
+}
+
+fn parse_runtime_from_js_value(module_asset: AssetVc, value: &JsValue) -> Option<NextRuntime> {
+    let JsValue::Constant(runtime) = value else {
+        invalid_config(
+            module_asset,
+            "The runtime property must be a constant string.",
+            value,
+        );
+        return None;
     };
+    match runtime.as_str()? {
+        "edge" | "experimental-edge" => Some(NextRuntime::Edge),
+        "nodejs" => Some(NextRuntime::NodeJs),
+        _ => {
+            invalid_config(
+                module_asset,
+                "The runtime property must be either \"nodejs\" or \"edge\".",
+                value,
+            );
+            None
+        }
+    }
+}
+
+fn parse_config_from_js_value(module_asset: AssetVc, value: &JsValue) -> NextSourceConfig {
+    let mut config = NextSourceConfig::default();
+    let invalid_config =
+        |detail: &str, value: &JsValue| invalid_config(module_asset, detail, value);
     if let JsValue::Object { parts, .. } = value {
         for part in parts {
             match part {
@@ -222,31 +485,14 @@ fn parse_config_from_js_value(module_asset: AssetVc, value: &JsValue) -> NextSou
                 ObjectPart::KeyValue(key, value) => {
                     if let Some(key) = key.as_str() {
                         if key == "runtime" {
-                            if let JsValue::Constant(runtime) = value {
-                                if let Some(runtime) = runtime.as_str() {
-                                    match runtime {
-                                        "edge" | "experimental-edge" => {
-                                            config.runtime = NextRuntime::Edge;
-                                        }
-                                        "nodejs" => {
-                                            config.runtime = NextRuntime::NodeJs;
-                                        }
-                                        _ => {
-                                            invalid_config(
-                                                "The runtime property must be either \"nodejs\" \
-                                                 or \"edge\".",
-                                                value,
-                                            );
-                                        }
-                                    }
-                                }
-                            } else {
-                                invalid_config(
-                                    "The runtime property must be a constant string.",
-                                    value,
-                                );
+                            if let Some(runtime) = parse_runtime_from_js_value(module_asset, value)
+                            {
+                                config.runtime = runtime;
                             }
                         }
+                        if key == "api" {
+                            config.api = Some(parse_api_config_from_js_value(module_asset, value));
+                        }
                         if key == "matcher" {
                             let mut matchers = vec![];
                             match value {
@@ -300,6 +546,116 @@ fn parse_config_from_js_value(module_asset: AssetVc, value: &JsValue) -> NextSou
     config
 }
 
+fn parse_api_config_from_js_value(module_asset: AssetVc, value: &JsValue) -> NextApiConfig {
+    let mut config = NextApiConfig::default();
+    let invalid_config =
+        |detail: &str, value: &JsValue| invalid_config(module_asset, detail, value);
+    let JsValue::Object { parts, .. } = value else {
+        invalid_config("The api property must be an object literal.", value);
+        return config;
+    };
+    for part in parts {
+        let ObjectPart::KeyValue(key, value) = part else {
+            invalid_config(
+                "Spread properties are not supported in the api config.",
+                value,
+            );
+            continue;
+        };
+        match key.as_str() {
+            Some("bodyParser") => {
+                config.body_parser = match value {
+                    JsValue::Constant(ConstantValue::False) => NextApiSizeLimit::Disabled,
+                    JsValue::Constant(ConstantValue::True) => NextApiSizeLimit::Default,
+                    JsValue::Object { parts, .. } => {
+                        let mut limit = NextApiSizeLimit::Default;
+                        for part in parts {
+                            match part {
+                                ObjectPart::KeyValue(key, value)
+                                    if key.as_str() == Some("sizeLimit") =>
+                                {
+                                    if let Some(bytes) = parse_size_limit(value) {
+                                        limit = NextApiSizeLimit::Bytes(bytes);
+                                    } else {
+                                        invalid_config(
+                                            "The bodyParser.sizeLimit property must be a number \
+                                             of bytes or a size like \"1mb\".",
+                                            value,
+                                        );
+                                    }
+                                }
+                                _ => {}
+                            }
+                        }
+                        limit
+                    }
+                    _ => {
+                        invalid_config(
+                            "The bodyParser property must be a boolean or an object literal.",
+                            value,
+                        );
+                        NextApiSizeLimit::Default
+                    }
+                }
+            }
+            Some("responseLimit") => {
+                config.response_limit = match value {
+                    JsValue::Constant(ConstantValue::False) => NextApiSizeLimit::Disabled,
+                    JsValue::Constant(ConstantValue::True) => NextApiSizeLimit::Default,
+                    _ => match parse_size_limit(value) {
+                        Some(bytes) => NextApiSizeLimit::Bytes(bytes),
+                        None => {
+                            invalid_config(
+                                "The responseLimit property must be a boolean, a number of bytes \
+                                 or a size like \"4mb\".",
+                                value,
+                            );
+                            NextApiSizeLimit::Default
+                        }
+                    },
+                }
+            }
+            Some("externalResolver") => match value {
+                JsValue::Constant(ConstantValue::True) => config.external_resolver = true,
+                JsValue::Constant(ConstantValue::False) => config.external_resolver = false,
+                _ => invalid_config("The externalResolver property must be a boolean.", value),
+            },
+            Some(_) => {}
+            None => invalid_config("The api config must not contain non-constant keys.", key),
+        }
+    }
+    config
+}
+
+/// Parses a size limit like the `bytes` package used by Next.js does, from a
+/// number of bytes or a string like `"1mb"` or `"500 KB"`.
+fn parse_size_limit(value: &JsValue) -> Option<u64> {
+    match value {
+        JsValue::Constant(ConstantValue::Num(bytes)) if bytes.0 >= 0.0 => Some(bytes.0 as u64),
+        JsValue::Constant(constant) => parse_size(constant.as_str()?),
+        _ => None,
+    }
+}
+
+fn parse_size(size: &str) -> Option<u64> {
+    let size = size.trim().to_ascii_lowercase();
+    let unit_start = size
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(size.len());
+    let (number, unit) = size.split_at(unit_start);
+    let number: f64 = number.parse().ok()?;
+    let exponent = match unit.trim() {
+        "" | "b" => 0,
+        "kb" => 1,
+        "mb" => 2,
+        "gb" => 3,
+        "tb" => 4,
+        "pb" => 5,
+        _ => return None,
+    };
+    Some((number * 1024f64.powi(exponent)).floor() as u64)
+}
+
 pub async fn load_next_json<T: DeserializeOwned>(
     context: FileSystemPathVc,
     path: &str,
@@ -342,3 +698,121 @@ pub async fn load_next_json<T: DeserializeOwned>(
 
     Ok(result)
 }
+
+/// Converts a path pattern in the syntax of `path-to-regexp`, e.g.
+/// `/blog/:slug*`, into the source of a regular expression. Like
+/// `path-to-regexp`, a `/` in front of a parameter is part of it, so optional
+/// parameters also match the path without the `/`. Parameters without a
+/// pattern match anything but the characters of `delimiter`.
+pub fn path_to_regexp(source: &str, delimiter: &str) -> Result<String> {
+    let default_pattern = format!("[^{}]+?", escape(delimiter));
+
+    let mut regexp = String::new();
+    let mut literal = String::new();
+    let mut chars = source.chars().peekable();
+    while let Some(c) = chars.next() {
+        let pattern = match c {
+            '\\' => {
+                if let Some(c) = chars.next() {
+                    literal.push(c);
+                }
+                continue;
+            }
+            ':' => {
+                let mut name = String::new();
+                while let Some(&c) = chars.peek() {
+                    if !(c.is_ascii_alphanumeric() || c == '_') {
+                        break;
+                    }
+                    name.push(c);
+                    chars.next();
+                }
+                if name.is_empty() {
+                    bail!("missing parameter name in {source}");
+                }
+                if chars.peek() == Some(&'(') {
+                    chars.next();
+                    group_pattern(&mut chars, source)?
+                } else {
+                    default_pattern.clone()
+                }
+            }
+            '(' => group_pattern(&mut chars, source)?,
+            _ => {
+                literal.push(c);
+                continue;
+            }
+        };
+        let prefix = if literal.ends_with('/') {
+            literal.pop();
+            "\\/"
+        } else {
+            ""
+        };
+        regexp.push_str(&escape(&literal));
+        literal.clear();
+        let modifier = match chars.peek() {
+            Some(&c @ ('?' | '*' | '+')) => {
+                chars.next();
+                Some(c)
+            }
+            _ => None,
+        };
+        regexp.push_str(&match modifier {
+            None => format!("{prefix}({pattern})"),
+            Some('?') => format!("(?:{prefix}({pattern}))?"),
+            Some(modifier) => format!(
+                "(?:{prefix}((?:{pattern})(?:{prefix}(?:{pattern}))*)){}",
+                if modifier == '*' { "?" } else { "" }
+            ),
+        });
+    }
+    regexp.push_str(&escape(&literal));
+    Ok(regexp)
+}
+
+/// Reads the pattern of a group up to its closing `)`, after the opening `(`.
+fn group_pattern(
+    chars: &mut std::iter::Peekable<std::str::Chars<'_>>,
+    source: &str,
+) -> Result<String> {
+    let mut pattern = String::new();
+    let mut depth = 1;
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                pattern.push(c);
+                if let Some(c) = chars.next() {
+                    pattern.push(c);
+                }
+                continue;
+            }
+            '(' => depth += 1,
+            ')' => {
+                depth -= 1;
+                if depth == 0 {
+                    if pattern.is_empty() {
+                        bail!("empty group in {source}");
+                    }
+                    return Ok(pattern);
+                }
+            }
+            _ => {}
+        }
+        pattern.push(c);
+    }
+    bail!("unbalanced group in {source}")
+}
+
+/// Escapes the characters of a literal part of a path pattern, like
+/// `escapeString` of `path-to-regexp`.
+fn escape(literal: &str) -> String {
+    let mut escaped = String::with_capacity(literal.len());
+    for c in literal.chars() {
+        if ".+*?=^!:${}()[]|/\\".contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
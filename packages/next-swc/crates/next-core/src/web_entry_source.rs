@@ -15,6 +15,7 @@ use turbopack_dev_server::{
 use turbopack_node::execution_context::ExecutionContextVc;
 
 use crate::{
+    mode::NextMode,
     next_client::context::{
         get_client_asset_context, get_client_chunking_context, get_client_compile_time_info,
         get_client_runtime_entries, ClientContextType,
@@ -34,12 +35,18 @@ pub async fn create_web_entry_source(
     next_config: NextConfigVc,
 ) -> Result<ContentSourceVc> {
     let ty = Value::new(ClientContextType::Other);
-    let compile_time_info = get_client_compile_time_info(browserslist_query);
+    let compile_time_info = get_client_compile_time_info(
+        browserslist_query,
+        env,
+        Value::new(NextMode::Development),
+        next_config,
+    );
     let context = get_client_asset_context(
         project_path,
         execution_context,
         compile_time_info,
         ty,
+        Value::new(NextMode::Development),
         next_config,
     );
     let chunking_context = get_client_chunking_context(
@@ -47,8 +54,16 @@ pub async fn create_web_entry_source(
         server_root,
         compile_time_info.environment(),
         ty,
+        Value::new(NextMode::Development),
+    );
+    let entries = get_client_runtime_entries(
+        project_path,
+        env,
+        ty,
+        Value::new(NextMode::Development),
+        next_config,
+        execution_context,
     );
-    let entries = get_client_runtime_entries(project_path, env, ty, next_config, execution_context);
 
     let runtime_entries = entries.resolve_entries(context);
 
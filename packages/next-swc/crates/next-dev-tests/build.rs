@@ -6,4 +6,5 @@ fn main() {
     // Unfortunately, we can't have the build.rs file operate differently on
     // each file, so the entire next-dev crate needs to be rebuilt.
     rerun_if_glob("tests/integration/*/*", "tests/integration");
+    rerun_if_glob("tests/routing/*", "tests/routing");
 }
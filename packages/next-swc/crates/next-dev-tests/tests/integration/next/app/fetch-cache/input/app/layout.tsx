@@ -0,0 +1,7 @@
+export default function RootLayout({ children }: { children: any }) {
+  return (
+    <html>
+      <body>{children}</body>
+    </html>
+  );
+}
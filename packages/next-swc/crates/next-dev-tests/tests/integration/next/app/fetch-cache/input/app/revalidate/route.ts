@@ -0,0 +1,6 @@
+import { revalidateTag } from "next/cache";
+
+export async function POST() {
+  await revalidateTag("random");
+  return new Response("revalidated");
+}
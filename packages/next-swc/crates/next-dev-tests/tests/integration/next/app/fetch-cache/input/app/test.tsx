@@ -0,0 +1,28 @@
+"use client";
+
+import { useEffect } from "react";
+
+export default function Test() {
+  useEffect(() => {
+    import("@turbo/pack-test-harness").then(() => {
+      it("should serve fetches from the cache", async () => {
+        const first = await (await fetch("/cached")).text();
+        const second = await (await fetch("/cached")).text();
+        expect(second).toEqual(first);
+      }, 20000);
+      it("should not cache fetches with no-store", async () => {
+        const first = await (await fetch("/uncached")).text();
+        const second = await (await fetch("/uncached")).text();
+        expect(second).not.toEqual(first);
+      }, 20000);
+      it("should fetch again after revalidateTag()", async () => {
+        const before = await (await fetch("/cached")).text();
+        const res = await fetch("/revalidate", { method: "POST" });
+        expect(await res.text()).toEqual("revalidated");
+        const after = await (await fetch("/cached")).text();
+        expect(after).not.toEqual(before);
+      }, 20000);
+    });
+    return () => {};
+  }, []);
+}
@@ -0,0 +1,8 @@
+import { type NextRequest } from "next/server";
+
+export async function GET(request: NextRequest) {
+  const res = await fetch(new URL("/random", request.url), {
+    cache: "no-store",
+  });
+  return new Response(await res.text());
+}
@@ -0,0 +1,54 @@
+import { useEffect } from "react";
+
+export default function Index() {
+  useEffect(() => {
+    // Only run on client
+    import("@turbo/pack-test-harness").then(runTests);
+  });
+
+  return "index";
+}
+
+function runTests() {
+  it("should pass the asset prefix without trailing slash to the client", () => {
+    expect(window.__NEXT_DATA__.assetPrefix).toBe("/cdn");
+  });
+
+  it("should prefix the chunk URLs", () => {
+    const scripts = Array.from(document.querySelectorAll("script[src]"))
+      .map((script) => script.getAttribute("src"))
+      .filter((src) => src.includes("/_next/"));
+    expect(scripts.length).toBeGreaterThan(0);
+    for (const src of scripts) {
+      expect(src).toMatch(/^\/cdn\/_next\//);
+    }
+  });
+
+  it("should serve the chunks with the prefix", async () => {
+    const src = document
+      .querySelector('script[src*="/_next/"]')
+      .getAttribute("src");
+    const res = await fetch(src);
+    expect(res.status).toBe(200);
+  });
+
+  it("should load the worker chunks with the prefix", async () => {
+    const worker = new Worker(new URL("../workers/echo.js", import.meta.url));
+    expect(await echo(worker, "classic")).toBe("echo: classic");
+  });
+
+  it("should keep the type of module workers", async () => {
+    const worker = new Worker(new URL("../workers/echo.js", import.meta.url), {
+      type: "module",
+    });
+    expect(await echo(worker, "module")).toBe("echo: module");
+  });
+}
+
+function echo(worker, message) {
+  return new Promise((resolve, reject) => {
+    worker.onmessage = (event) => resolve(event.data);
+    worker.onerror = reject;
+    worker.postMessage(message);
+  });
+}
@@ -0,0 +1,6 @@
+export function register() {
+  globalThis.instrumentation = {
+    registered: (globalThis.instrumentation?.registered ?? 0) + 1,
+    runtime: process.env.NEXT_RUNTIME,
+  };
+}
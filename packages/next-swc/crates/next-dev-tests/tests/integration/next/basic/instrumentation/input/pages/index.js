@@ -0,0 +1,19 @@
+import { useEffect } from "react";
+
+export default function Index() {
+  useEffect(() => {
+    // Only run on client
+    import("@turbo/pack-test-harness").then(runTests);
+  });
+
+  return "index";
+}
+
+function runTests() {
+  it("should call register() once on startup", async () => {
+    for (let i = 0; i < 3; i++) {
+      const res = await fetch("/api/instrumentation");
+      expect(await res.json()).toEqual({ registered: 1, runtime: "nodejs" });
+    }
+  });
+}
@@ -0,0 +1,8 @@
+it("replaces the defines of the development mode from next.config.js experimental.turbo.defines", () => {
+  expect(process.env.API_URL).toBe("http://localhost:4000");
+  expect(__DEV_TOOLS__).toBe(true);
+});
+
+it("keeps process.env.NODE_ENV matching the mode", () => {
+  expect(process.env.NODE_ENV).toBe("development");
+});
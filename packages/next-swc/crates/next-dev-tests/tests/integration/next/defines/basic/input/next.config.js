@@ -0,0 +1,19 @@
+module.exports = {
+  experimental: {
+    turbo: {
+      defines: {
+        development: {
+          "process.env.API_URL": "http://localhost:4000",
+          __DEV_TOOLS__: true,
+        },
+        production: {
+          "process.env.API_URL": "https://api.example.com",
+          __DEV_TOOLS__: false,
+        },
+        test: {
+          "process.env.NODE_ENV": "production",
+        },
+      },
+    },
+  },
+};
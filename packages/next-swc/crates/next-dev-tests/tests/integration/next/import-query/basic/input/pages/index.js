@@ -0,0 +1,38 @@
+import { useEffect } from "react";
+import text from "../assets/hello.txt?raw";
+import textUrl from "../assets/hello.txt?url";
+import binaryText from "../assets/data.bin?raw";
+import binaryUrl from "../assets/data.bin?url";
+
+export default function Page() {
+  useEffect(() => {
+    // Only run on client
+    import("@turbo/pack-test-harness").then(runTests);
+  });
+
+  return null;
+}
+
+function runTests() {
+  it("imports the contents of a file with ?raw", () => {
+    expect(text).toBe('Hello "raw" text\n');
+  });
+
+  it("emits the file imported with ?url", async () => {
+    const response = await fetch(textUrl);
+    expect(response.ok).toBe(true);
+    expect(await response.text()).toBe('Hello "raw" text\n');
+  });
+
+  it("replaces invalid UTF-8 of binary files imported with ?raw", () => {
+    expect(binaryText.startsWith("�PNG")).toBe(true);
+  });
+
+  it("emits binary files imported with ?url unchanged", async () => {
+    const response = await fetch(binaryUrl);
+    const bytes = new Uint8Array(await response.arrayBuffer());
+    expect(Array.from(bytes)).toEqual([
+      0x89, 0x50, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a, 0xff, 0xfe, 0x00, 0x01,
+    ]);
+  });
+}
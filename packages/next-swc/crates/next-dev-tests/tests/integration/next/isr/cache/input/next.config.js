@@ -0,0 +1,8 @@
+/** @type {import('next').NextConfig} */
+module.exports = {
+  experimental: {
+    turbo: {
+      isrCache: true,
+    },
+  },
+};
@@ -0,0 +1,4 @@
+export default async function handler(req, res) {
+  await res.revalidate("/time");
+  res.json({ revalidated: true });
+}
@@ -0,0 +1,45 @@
+import { useEffect } from "react";
+
+export default function Index() {
+  useEffect(() => {
+    // Only run on client
+    import("@turbo/pack-test-harness").then(runTests);
+  });
+
+  return "index";
+}
+
+function runTests() {
+  it("should serve pages with getStaticProps from the ISR cache", async () => {
+    const first = await fetch("/time");
+    const firstHtml = await first.text();
+
+    const second = await fetch("/time");
+    expect(second.headers.get("x-nextjs-cache")).toBe("HIT");
+    expect(await second.text()).toBe(firstHtml);
+  });
+
+  it("should not replay the Server-Timing of the cached render", async () => {
+    await fetch("/time");
+    const cached = await fetch("/time");
+    expect(cached.headers.get("x-nextjs-cache")).toBe("HIT");
+    const timings = cached.headers.get("server-timing") ?? "";
+    expect(timings.match(/(^|, )render;dur=/g)).toHaveLength(1);
+  });
+
+  it("should render pages again after res.revalidate()", async () => {
+    const before = await (await fetch("/time")).text();
+
+    const res = await fetch("/api/revalidate");
+    expect(await res.json()).toEqual({ revalidated: true });
+
+    const after = await fetch("/time");
+    expect(after.headers.get("x-nextjs-cache")).toBe("MISS");
+    expect(await after.text()).not.toBe(before);
+  });
+
+  it("should not cache pages without getStaticProps", async () => {
+    const res = await fetch("/");
+    expect(res.headers.get("x-nextjs-cache")).toBeNull();
+  });
+}
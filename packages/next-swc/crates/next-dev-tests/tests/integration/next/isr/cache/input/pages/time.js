@@ -0,0 +1,10 @@
+export default function Time({ renderedAt }) {
+  return <div id="rendered-at">{renderedAt}</div>;
+}
+
+export function getStaticProps() {
+  return {
+    props: { renderedAt: Date.now() },
+    revalidate: 3600,
+  };
+}
@@ -0,0 +1,36 @@
+import { useEffect } from "react";
+
+export default function Index() {
+  useEffect(() => {
+    // Only run on client
+    import("@turbo/pack-test-harness").then(runTests);
+  });
+
+  return "index";
+}
+
+function runTests() {
+  it("should preload the chunks of the page with a Link header", async () => {
+    const res = await fetch("/");
+    const link = res.headers.get("link");
+    expect(link).toMatch(
+      /<\/_next\/static\/chunks\/[^>]+\.js>; rel=preload; as=script/
+    );
+  });
+
+  it("should preload the chunks which the page loads", async () => {
+    const res = await fetch("/");
+    const hrefs = Array.from(
+      res.headers.get("link").matchAll(/<([^>]+)>; rel=preload; as=script/g),
+      (match) => match[1]
+    );
+    // The script URLs can have a cache-busting query in development.
+    const scripts = Array.from(
+      document.querySelectorAll("script[src]"),
+      (script) => script.getAttribute("src").split("?")[0]
+    );
+    for (const href of hrefs) {
+      expect(scripts).toContain(href);
+    }
+  });
+}
@@ -0,0 +1,9 @@
+module.exports = {
+  experimental: {
+    turbo: {
+      remotes: {
+        remote: "remoteApp@/remote/entry.js",
+      },
+    },
+  },
+};
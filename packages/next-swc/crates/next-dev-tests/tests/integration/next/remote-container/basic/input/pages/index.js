@@ -0,0 +1,42 @@
+import { useEffect } from "react";
+
+export async function getServerSideProps() {
+  const greeting = await import("remote/greeting");
+  return { props: { greeting: greeting.greet("server") } };
+}
+
+export default function Page({ greeting }) {
+  useEffect(() => {
+    // Only run on client
+    import("@turbo/pack-test-harness").then(runTests);
+  });
+
+  return <div id="ssr">{greeting}</div>;
+}
+
+function runTests() {
+  it("loads modules of remote containers on the server", () => {
+    expect(document.getElementById("ssr").textContent).toBe("Hello server");
+  });
+
+  it("resolves the import of a remote module to the exposed module", async () => {
+    const greeting = await import("remote/greeting");
+    expect(greeting.default).toBe("Hello from the remote");
+    expect(greeting.greet("client")).toBe("Hello client");
+  });
+
+  it("loads the container and its modules once", async () => {
+    const first = await import("remote/greeting");
+    const second = await import("remote/greeting");
+    expect(first).toBe(second);
+    expect(
+      document.querySelectorAll('script[src="/remote/entry.js"]').length
+    ).toBe(1);
+  });
+
+  it("rejects modules which the container doesn't expose", async () => {
+    await expect(import("remote/missing")).rejects.toThrow(
+      "Module ./missing does not exist in container."
+    );
+  });
+}
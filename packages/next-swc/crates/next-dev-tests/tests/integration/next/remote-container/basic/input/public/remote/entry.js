@@ -0,0 +1,22 @@
+// A precompiled container, registering itself like a webpack container with
+// `library: { type: "var", name: "remoteApp" }`.
+var remoteApp = {
+  init: function (shareScope) {
+    remoteApp.shareScope = shareScope;
+  },
+  get: function (module) {
+    if (module === "./greeting") {
+      return Promise.resolve(function () {
+        return {
+          default: "Hello from the remote",
+          greet: function (name) {
+            return "Hello " + name;
+          },
+        };
+      });
+    }
+    return Promise.reject(
+      new Error("Module " + module + " does not exist in container.")
+    );
+  },
+};
@@ -0,0 +1,32 @@
+/** @type {import('next').NextConfig} */
+module.exports = {
+  async rewrites() {
+    return [
+      {
+        source: "/rewrite-query",
+        has: [{ type: "query", key: "section", value: "(?<section>[a-z]+)" }],
+        destination: "/target?from=:section",
+      },
+      {
+        source: "/rewrite-cookie",
+        has: [{ type: "cookie", key: "flavor" }],
+        destination: "/target?from=:flavor",
+      },
+      {
+        source: "/rewrite-header",
+        has: [{ type: "header", key: "x-team", value: "team-(?<team>.+)" }],
+        destination: "/target?from=:team",
+      },
+    ];
+  },
+  async redirects() {
+    return [
+      {
+        source: "/redirect-query",
+        has: [{ type: "query", key: "to", value: "(?<slug>[a-z]+)" }],
+        destination: "/target?from=:slug",
+        permanent: false,
+      },
+    ];
+  },
+};
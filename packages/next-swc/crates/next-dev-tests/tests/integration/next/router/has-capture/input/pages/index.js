@@ -0,0 +1,36 @@
+import { useEffect } from "react";
+
+export default function Index() {
+  useEffect(() => {
+    // Only run on client
+    import("@turbo/pack-test-harness").then(runTests);
+  });
+
+  return "index";
+}
+
+function runTests() {
+  it("should interpolate a named group of a query condition", async () => {
+    const res = await fetch("/rewrite-query?section=docs");
+    expect(await res.text()).toContain('<div id="from">docs</div>');
+  });
+
+  it("should interpolate the value of a cookie condition", async () => {
+    document.cookie = "flavor=vanilla; path=/";
+    const res = await fetch("/rewrite-cookie");
+    expect(await res.text()).toContain('<div id="from">vanilla</div>');
+  });
+
+  it("should interpolate a named group of a header condition", async () => {
+    const res = await fetch("/rewrite-header", {
+      headers: { "x-team": "team-turbo" },
+    });
+    expect(await res.text()).toContain('<div id="from">turbo</div>');
+  });
+
+  it("should interpolate a named group into a redirect", async () => {
+    const res = await fetch("/redirect-query?to=blog");
+    expect(new URL(res.url).pathname).toBe("/target");
+    expect(new URL(res.url).searchParams.get("from")).toBe("blog");
+  });
+}
@@ -0,0 +1,7 @@
+export default function Target({ from }) {
+  return <div id="from">{from}</div>;
+}
+
+export function getServerSideProps({ query }) {
+  return { props: { from: query.from ?? null } };
+}
@@ -0,0 +1,22 @@
+/** @type {import('next').NextConfig} */
+module.exports = {
+  i18n: {
+    locales: ["en", "nl"],
+    defaultLocale: "en",
+  },
+  async headers() {
+    return [
+      {
+        // This matches `/` since `en` is the default locale.
+        source: "/en",
+        headers: [{ key: "x-locale-false", value: "default" }],
+        locale: false,
+      },
+      {
+        source: "/nl/with-locale-manual",
+        headers: [{ key: "x-locale-false", value: "manual" }],
+        locale: false,
+      },
+    ];
+  },
+};
@@ -0,0 +1,27 @@
+import { useEffect } from "react";
+
+export default function Index() {
+  useEffect(() => {
+    // Only run on client
+    import("@turbo/pack-test-harness").then(runTests);
+  });
+
+  return "index";
+}
+
+function runTests() {
+  it("should match the default locale of a pathname without locale", async () => {
+    const res = await fetch("/");
+    expect(res.headers.get("x-locale-false")).toBe("default");
+  });
+
+  it("should match a locale-prefixed source literally", async () => {
+    const res = await fetch("/nl/with-locale-manual");
+    expect(res.headers.get("x-locale-false")).toBe("manual");
+  });
+
+  it("should not match other locales", async () => {
+    const res = await fetch("/nl");
+    expect(res.headers.get("x-locale-false")).toBeNull();
+  });
+}
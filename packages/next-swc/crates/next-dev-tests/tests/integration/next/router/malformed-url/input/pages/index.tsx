@@ -0,0 +1,34 @@
+import { useEffect } from "react";
+
+export default function Home() {
+  useEffect(() => {
+    // Only run on client
+    import("@turbo/pack-test-harness").then(runTests);
+  });
+
+  return <div>Test</div>;
+}
+
+function runTests() {
+  describe("malformed request URLs", () => {
+    it("should reject an invalid percent-encoding with 400", async () => {
+      const response = await fetch("/%E0%A4%A");
+      expect(response.status).toBe(400);
+    });
+
+    it("should reject an encoded null byte with 400", async () => {
+      const response = await fetch("/%00");
+      expect(response.status).toBe(400);
+    });
+
+    it("should reject an overlong URL with 414", async () => {
+      const response = await fetch(`/?q=${"a".repeat(9000)}`);
+      expect(response.status).toBe(414);
+    });
+
+    it("should still serve valid encoded pathnames", async () => {
+      const response = await fetch("/?q=%E2%9C%93");
+      expect(response.status).toBe(200);
+    });
+  });
+}
@@ -0,0 +1,12 @@
+import { NextResponse } from "next/server";
+
+export function middleware() {
+  const response = NextResponse.next();
+  response.headers.append("set-cookie", "first=1; Path=/");
+  response.headers.append("set-cookie", "second=2; Path=/");
+  response.headers.append("link", "</a.css>; rel=preload; as=style");
+  response.headers.append("link", "</b.css>; rel=preload; as=style");
+  response.headers.append("x-multi", "a");
+  response.headers.append("x-multi", "b");
+  return response;
+}
@@ -0,0 +1,27 @@
+import { useEffect } from "react";
+
+export default function Home() {
+  useEffect(() => {
+    // Only run on client
+    import("@turbo/pack-test-harness").then(runTests);
+  });
+
+  return <div>Test</div>;
+}
+
+function runTests() {
+  describe("multi-valued headers", () => {
+    it("should keep each set-cookie header", () => {
+      expect(document.cookie).toContain("first=1");
+      expect(document.cookie).toContain("second=2");
+    });
+
+    it("should keep the order of repeated headers", async () => {
+      const response = await fetch(location.href);
+      expect(response.headers.get("x-multi")).toBe("a, b");
+      expect(response.headers.get("link")).toBe(
+        "</a.css>; rel=preload; as=style, </b.css>; rel=preload; as=style"
+      );
+    });
+  });
+}
@@ -0,0 +1,369 @@
+#![feature(min_specialization)]
+#![cfg(test)]
+
+//! Runs the routing fixtures in `tests/routing` against the Next.js router of
+//! `next-core`. Each fixture is a Next.js app in `input` with the redirects,
+//! rewrites, headers and middleware under test, and a `cases.json` with the
+//! requests and their expected results, in the format of the routing tests of
+//! Next.js:
+//!
+//! ```json
+//! {
+//!   "cases": [
+//!     {
+//!       "name": "redirects with params",
+//!       "request": { "pathname": "/blog/hello" },
+//!       "expected": { "type": "redirect", "statusCode": 307, "location": "/posts/hello" }
+//!     }
+//!   ]
+//! }
+//! ```
+//!
+//! `upstream-custom-routes` is the `test/integration/custom-routes` app of
+//! Next.js, with the cases converted from the assertions of its tests.
+//!
+//! The results are written to `routing/<fixture>/conformance.md` in the
+//! temporary directory of the build, which is printed by the test. Cases which
+//! are known to differ are marked with `"knownGap": "<reason>"`, and only fail
+//! the test once they pass, so the mark is removed.
+
+use std::{
+    collections::BTreeMap,
+    fmt::Write,
+    fs,
+    net::{Ipv4Addr, SocketAddr},
+    path::{Path, PathBuf, MAIN_SEPARATOR},
+};
+
+use anyhow::{Context, Result};
+use dunce::canonicalize;
+use next_core::{
+    env::load_env,
+    metadata_routes::MetadataRoutesVc,
+    next_config::{load_next_config, Rewrites},
+    route_handlers::RouteHandlersVc,
+    router::{route, EarlyHints, HeaderMap, RouterRequest, RouterResult},
+};
+use serde::{Deserialize, Serialize};
+use turbo_tasks::{CompletionVc, TurboTasks};
+use turbo_tasks_fs::{DiskFileSystemVc, FileSystemVc};
+use turbo_tasks_memory::MemoryBackend;
+use turbopack::evaluate_context::node_build_environment;
+use turbopack_core::environment::ServerAddr;
+use turbopack_dev::DevChunkingContextVc;
+use turbopack_node::execution_context::ExecutionContextVc;
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RoutingFixture {
+    cases: Vec<RoutingCase>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RoutingCase {
+    name: String,
+    request: RoutingRequest,
+    expected: RoutingOutcome,
+    /// Headers the response must have, in addition to the expected outcome.
+    #[serde(default)]
+    headers: BTreeMap<String, String>,
+    /// The links the router must hint before the response, see
+    /// [RouterResult::early_hints].
+    early_hints: Option<Vec<String>>,
+    /// Why the router doesn't match Next.js for this case yet.
+    known_gap: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RoutingRequest {
+    #[serde(default = "default_method")]
+    method: String,
+    pathname: String,
+    #[serde(default)]
+    query: String,
+    #[serde(default)]
+    headers: BTreeMap<String, String>,
+}
+
+fn default_method() -> String {
+    "GET".to_string()
+}
+
+/// The result of routing a request, independent of how the router responds
+/// with it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+enum RoutingOutcome {
+    /// The request is served from `url`, which is the requested URL when
+    /// nothing rewrote it.
+    Rewrite {
+        url: String,
+    },
+    #[serde(rename_all = "camelCase")]
+    Redirect {
+        status_code: u16,
+        location: String,
+    },
+    /// The router responded itself, e.g. from a middleware.
+    #[serde(rename_all = "camelCase")]
+    Response {
+        status_code: u16,
+    },
+    NotFound,
+    None,
+    Error,
+}
+
+impl RoutingOutcome {
+    /// Compares rewrite URLs and redirect locations by pathname and query
+    /// parameters, as the order of the parameters depends on how the
+    /// destination was interpolated.
+    fn normalized(&self) -> Self {
+        match self {
+            RoutingOutcome::Rewrite { url } => RoutingOutcome::Rewrite {
+                url: normalize_url(url),
+            },
+            RoutingOutcome::Redirect {
+                status_code,
+                location,
+            } => RoutingOutcome::Redirect {
+                status_code: *status_code,
+                location: normalize_url(location),
+            },
+            outcome => outcome.clone(),
+        }
+    }
+}
+
+fn normalize_url(url: &str) -> String {
+    let (pathname, query) = url.split_once('?').unwrap_or((url, ""));
+    let mut params = query
+        .split('&')
+        .filter(|p| !p.is_empty())
+        .collect::<Vec<_>>();
+    if params.is_empty() {
+        return pathname.to_string();
+    }
+    params.sort_unstable();
+    format!("{pathname}?{}", params.join("&"))
+}
+
+/// The outcome of a request, the headers of the response and its early hints.
+type RoutedRequest = (RoutingOutcome, HeaderMap, EarlyHints);
+
+#[testing::fixture("tests/routing/*")]
+fn routing(resource: PathBuf) {
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+    runtime.block_on(run_fixture(resource)).unwrap();
+}
+
+async fn run_fixture(resource: PathBuf) -> Result<()> {
+    next_dev::register();
+
+    let resource = canonicalize(resource)?;
+    let fixture: RoutingFixture = serde_json::from_slice(
+        &fs::read(resource.join("cases.json")).context("reading cases.json")?,
+    )
+    .context("parsing cases.json")?;
+
+    let package_root = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let workspace_root = canonicalize(package_root)?
+        .ancestors()
+        .nth(4)
+        .context("the workspace root is missing")?
+        .to_path_buf();
+
+    let results = route_cases(&workspace_root, &resource.join("input"), &fixture.cases).await?;
+
+    let mut report = String::new();
+    let passed = fixture
+        .cases
+        .iter()
+        .zip(results.iter())
+        .filter(|(case, result)| passes(case, result))
+        .count();
+    writeln!(
+        report,
+        "# Routing conformance\n\n{passed} of {} cases match Next.js.\n",
+        fixture.cases.len()
+    )?;
+    let mut failures = Vec::new();
+    for (case, result) in fixture.cases.iter().zip(results.iter()) {
+        let passes = passes(case, result);
+        let status = match (passes, &case.known_gap) {
+            (true, None) => "pass".to_string(),
+            (false, Some(reason)) => format!("known gap: {reason}"),
+            (false, None) => {
+                failures.push(format!("{} doesn't match Next.js", case.name));
+                "FAIL".to_string()
+            }
+            (true, Some(_)) => {
+                failures.push(format!(
+                    "{} matches Next.js now, remove its `knownGap`",
+                    case.name
+                ));
+                "pass, but marked as a known gap".to_string()
+            }
+        };
+        writeln!(report, "- **{}**: {status}", case.name)?;
+        if !passes {
+            writeln!(
+                report,
+                "  - expected: `{}`",
+                serde_json::to_string(&case.expected)?
+            )?;
+            writeln!(report, "  - actual: `{}`", describe(result)?)?;
+        }
+    }
+    // The fixtures are checked in, so the report goes to the build directory.
+    let report_dir = Path::new(env!("CARGO_TARGET_TMPDIR"))
+        .join("routing")
+        .join(resource.file_name().context("the fixture has no name")?);
+    fs::create_dir_all(&report_dir)?;
+    let report_path = report_dir.join("conformance.md");
+    fs::write(&report_path, report)?;
+    println!("Routing conformance report: {}", report_path.display());
+
+    assert!(
+        failures.is_empty(),
+        "Routing conformance of {} changed:\n{}",
+        resource.display(),
+        failures.join("\n")
+    );
+    Ok(())
+}
+
+fn passes(case: &RoutingCase, (outcome, headers, early_hints): &RoutedRequest) -> bool {
+    case.expected.normalized() == outcome.normalized()
+        && case
+            .headers
+            .iter()
+            .all(|(name, value)| headers.get(name).and_then(|v| v.to_str()) == Some(value.as_str()))
+        && case
+            .early_hints
+            .as_ref()
+            .map_or(true, |links| *links == early_hints.links)
+}
+
+fn describe((outcome, headers, early_hints): &RoutedRequest) -> Result<String> {
+    let headers = headers.iter().collect::<BTreeMap<_, _>>();
+    let mut description = format!(
+        "{}, headers: {}",
+        serde_json::to_string(outcome)?,
+        serde_json::to_string(&headers)?
+    );
+    if !early_hints.is_empty() {
+        write!(
+            description,
+            ", early hints: {}",
+            serde_json::to_string(&early_hints.links)?
+        )?;
+    }
+    Ok(description)
+}
+
+/// Routes the requests of the cases, in order, with the router of the app in
+/// `project_dir`.
+async fn route_cases(
+    workspace_root: &Path,
+    project_dir: &Path,
+    cases: &[RoutingCase],
+) -> Result<Vec<RoutedRequest>> {
+    let root_dir = workspace_root
+        .to_str()
+        .context("the workspace root is not valid UTF-8")?
+        .to_string();
+    let project_relative = project_dir
+        .strip_prefix(workspace_root)?
+        .to_str()
+        .context("the fixture path is not valid UTF-8")?
+        .replace(MAIN_SEPARATOR, "/");
+    let requests = cases
+        .iter()
+        .map(|case| RouterRequest {
+            method: case.request.method.clone(),
+            pathname: case.request.pathname.clone(),
+            raw_query: case.request.query.clone(),
+            raw_headers: case
+                .request
+                .headers
+                .iter()
+                .map(|(name, value)| (name.as_str(), value.as_str()))
+                .collect(),
+            peer_addr: Some(Ipv4Addr::LOCALHOST.into()),
+        })
+        .collect::<Vec<_>>();
+
+    let tt = TurboTasks::new(MemoryBackend::default());
+    tt.run_once(async move {
+        let fs: FileSystemVc = DiskFileSystemVc::new("project".to_string(), root_dir).into();
+        let project_path = fs.root().join(&project_relative);
+        let env = load_env(project_path);
+        let build_output_root = project_path.join(".next/build");
+        let chunking_context = DevChunkingContextVc::builder(
+            project_path,
+            build_output_root,
+            build_output_root.join("chunks"),
+            build_output_root.join("assets"),
+            node_build_environment(),
+        )
+        .build();
+        let execution_context = ExecutionContextVc::new(project_path, chunking_context, env);
+        let next_config = load_next_config(execution_context.with_layer("next_config"));
+        let server_addr = ServerAddr::new(SocketAddr::new(Ipv4Addr::LOCALHOST.into(), 3000)).cell();
+
+        let mut results = Vec::with_capacity(requests.len());
+        for request in requests {
+            let result = route(
+                execution_context,
+                request.cell(),
+                next_config,
+                server_addr,
+                Rewrites::default().cell(),
+                RouteHandlersVc::cell(vec![]),
+                MetadataRoutesVc::cell(vec![]),
+                CompletionVc::immutable(),
+            )
+            .strongly_consistent()
+            .await?;
+            results.push(routed_request(&result));
+        }
+        Ok(results)
+    })
+    .await
+}
+
+fn routed_request(result: &RouterResult) -> RoutedRequest {
+    let (outcome, headers) = match result {
+        RouterResult::Rewrite(data)
+        | RouterResult::RouteHandler(data)
+        | RouterResult::MetadataRoute(data)
+        | RouterResult::ServerAction(data) => (
+            RoutingOutcome::Rewrite {
+                url: data.url.clone(),
+            },
+            data.headers.clone(),
+        ),
+        RouterResult::FullMiddleware(data) => {
+            let status_code = data.headers.status_code;
+            let headers = data.headers.headers.clone();
+            let outcome = match headers.get("location") {
+                Some(location) if (300..400).contains(&status_code) => RoutingOutcome::Redirect {
+                    status_code,
+                    location: String::from_utf8_lossy(&location.0).into_owned(),
+                },
+                _ => RoutingOutcome::Response { status_code },
+            };
+            (outcome, headers)
+        }
+        RouterResult::NotFound(data) => (RoutingOutcome::NotFound, data.headers.clone()),
+        RouterResult::None => (RoutingOutcome::None, HeaderMap::new()),
+        RouterResult::Error => (RoutingOutcome::Error, HeaderMap::new()),
+    };
+    (outcome, headers, result.early_hints())
+}
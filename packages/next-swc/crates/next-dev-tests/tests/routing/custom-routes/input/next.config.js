@@ -0,0 +1,60 @@
+/** @type {import('next').NextConfig} */
+module.exports = {
+  async headers() {
+    return [
+      {
+        source: "/",
+        headers: [{ key: "x-custom-header", value: "index" }],
+      },
+      {
+        source: "/blog/:slug",
+        headers: [{ key: "x-slug", value: ":slug" }],
+      },
+      {
+        source: "/docs/:path*",
+        headers: [
+          {
+            key: "Link",
+            value: [
+              "</fonts/inter.woff2>; rel=preload; as=font; crossorigin",
+              '</docs/feed.xml>; rel="alternate"',
+              "<https://cdn.example.com>; rel=preconnect",
+            ].join(", "),
+          },
+        ],
+      },
+    ];
+  },
+  async redirects() {
+    return [
+      {
+        source: "/old",
+        destination: "/new",
+        permanent: true,
+      },
+      {
+        source: "/blog/:slug",
+        destination: "/posts/:slug",
+        permanent: false,
+      },
+      {
+        source: "/external",
+        destination: "https://example.vercel.sh/",
+        statusCode: 301,
+      },
+    ];
+  },
+  async rewrites() {
+    return [
+      {
+        source: "/docs/:path*",
+        destination: "/documentation/:path*",
+      },
+      {
+        source: "/search",
+        has: [{ type: "query", key: "q", value: "(?<term>.+)" }],
+        destination: "/results/:term",
+      },
+    ];
+  },
+};
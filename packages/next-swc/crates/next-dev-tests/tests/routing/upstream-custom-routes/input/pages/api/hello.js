@@ -0,0 +1 @@
+export default async (req, res) => res.json({ query: req.query })
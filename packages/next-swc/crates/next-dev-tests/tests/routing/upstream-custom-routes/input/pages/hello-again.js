@@ -0,0 +1,10 @@
+import Link from 'next/link'
+
+export default () => (
+  <>
+    <h3 id="hello-again">Hello again</h3>
+    <Link href="/nav" id="to-nav">
+      to nav
+    </Link>
+  </>
+)
@@ -0,0 +1,3 @@
+const Page = () => 'multi-rewrites'
+
+export default Page
@@ -0,0 +1,3 @@
+export default function Page() {
+  return <p>this page is overridden by a beforeFiles rewrite</p>
+}
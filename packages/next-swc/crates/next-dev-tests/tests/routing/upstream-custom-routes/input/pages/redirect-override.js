@@ -0,0 +1 @@
+export default () => 'got to the page'
@@ -0,0 +1,12 @@
+import { NextResponse } from "next/server";
+import type { NextRequest } from "next/server";
+
+// Keep in sync with STREAM_BODY_SIZE in mod.rs
+const STREAM_BODY = "x".repeat(1024 * 1024);
+
+export function middleware(request: NextRequest) {
+  if (request.nextUrl.pathname === "/stream") {
+    return new NextResponse(STREAM_BODY);
+  }
+  return NextResponse.rewrite(new URL("/rewritten", request.url));
+}
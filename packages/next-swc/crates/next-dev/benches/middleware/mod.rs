@@ -0,0 +1,193 @@
+use std::{
+    fs,
+    net::{Ipv4Addr, SocketAddr},
+    path::Path,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use anyhow::{bail, Context, Result};
+use criterion::{Criterion, Throughput};
+use next_core::{
+    env::load_env,
+    metadata_routes::MetadataRoutesVc,
+    next_config::{load_next_config, Rewrites},
+    route_handlers::RouteHandlersVc,
+    router::{route, HeaderMap, RouterRequest, RouterResult},
+};
+use tempfile::TempDir;
+use tokio::runtime::Runtime;
+use turbo_tasks::{CompletionVc, TurboTasks};
+use turbo_tasks_fs::{DiskFileSystemVc, FileSystemVc};
+use turbo_tasks_memory::MemoryBackend;
+use turbopack::evaluate_context::node_build_environment;
+use turbopack_core::environment::ServerAddr;
+use turbopack_dev::DevChunkingContextVc;
+use turbopack_node::execution_context::ExecutionContextVc;
+
+use crate::util::npm::{self, NpmPackage};
+
+/// The size of the body the fixture middleware responds with on `/stream`.
+const STREAM_BODY_SIZE: usize = 1024 * 1024;
+
+/// Benchmarks the Next.js router with an edge middleware, through the same
+/// IPC path to the node.js router process the dev server uses:
+///
+/// - **cold:** compiling the router and the middleware, starting the router
+///   process and routing the first request.
+/// - **warm:** routing a request to the already running router process.
+/// - **stream:** receiving a 1 MiB middleware response body.
+pub fn bench_middleware(c: &mut Criterion) {
+    next_dev::register();
+
+    let runtime = Runtime::new().unwrap();
+    let app = prepare_app().unwrap();
+    let dir = app.path();
+
+    let mut g = c.benchmark_group("bench_middleware");
+    g.sample_size(10);
+    g.measurement_time(Duration::from_secs(30));
+
+    g.bench_function("cold", |b| {
+        b.to_async(&runtime).iter_custom(|iters| async move {
+            let mut duration = Duration::ZERO;
+            for _ in 0..iters {
+                let tt = TurboTasks::new(MemoryBackend::new(usize::MAX));
+                let start = Instant::now();
+                route_request(&tt, dir, "/", String::new()).await.unwrap();
+                duration += start.elapsed();
+            }
+            duration
+        });
+    });
+
+    let tt = TurboTasks::new(MemoryBackend::new(usize::MAX));
+    runtime
+        .block_on(route_request(&tt, dir, "/", String::new()))
+        .unwrap();
+
+    g.bench_function("warm", |b| {
+        let tt = &tt;
+        let mut request = 0;
+        b.to_async(&runtime).iter_custom(|iters| {
+            // Every request needs a unique query, otherwise the result is
+            // cached and the router isn't called again.
+            let first_request = request;
+            request += iters;
+            async move {
+                let start = Instant::now();
+                for i in first_request..first_request + iters {
+                    route_request(tt, dir, "/", format!("request={i}"))
+                        .await
+                        .unwrap();
+                }
+                start.elapsed()
+            }
+        });
+    });
+
+    g.throughput(Throughput::Bytes(STREAM_BODY_SIZE as u64));
+    g.bench_function("stream", |b| {
+        let tt = &tt;
+        let mut request = 0;
+        b.to_async(&runtime).iter_custom(|iters| {
+            let first_request = request;
+            request += iters;
+            async move {
+                let start = Instant::now();
+                for i in first_request..first_request + iters {
+                    let body_size = route_request(tt, dir, "/stream", format!("request={i}"))
+                        .await
+                        .unwrap();
+                    assert_eq!(body_size, STREAM_BODY_SIZE);
+                }
+                start.elapsed()
+            }
+        });
+    });
+
+    g.finish();
+}
+
+/// Creates an app with the fixture middleware and installs Next.js.
+fn prepare_app() -> Result<TempDir> {
+    let app = tempfile::tempdir()?;
+    fs::create_dir(app.path().join("pages"))?;
+    fs::write(
+        app.path().join("pages/index.js"),
+        "export default function Page() { return null; }\n",
+    )?;
+    fs::write(
+        app.path().join("middleware.ts"),
+        include_bytes!("middleware.ts"),
+    )?;
+    npm::install(
+        app.path(),
+        &[
+            NpmPackage::new("next", "13.2.4-canary.7"),
+            NpmPackage::new("react", "^18.2.0"),
+            NpmPackage::new("react-dom", "^18.2.0"),
+        ],
+    )
+    .context("failed to install from npm")?;
+    Ok(app)
+}
+
+/// Routes a request and returns the size of the middleware response body, or
+/// 0 when the middleware rewrote the request.
+async fn route_request(
+    tt: &Arc<TurboTasks<MemoryBackend>>,
+    dir: &Path,
+    pathname: &str,
+    raw_query: String,
+) -> Result<usize> {
+    let dir = dir
+        .to_str()
+        .context("the app directory is not valid UTF-8")?
+        .to_string();
+    let pathname = pathname.to_string();
+    tt.run_once(async move {
+        let fs: FileSystemVc = DiskFileSystemVc::new("project".to_string(), dir).into();
+        let project_path = fs.root();
+        let env = load_env(project_path);
+        let build_output_root = project_path.join(".next/build");
+        let chunking_context = DevChunkingContextVc::builder(
+            project_path,
+            build_output_root,
+            build_output_root.join("chunks"),
+            build_output_root.join("assets"),
+            node_build_environment(),
+        )
+        .build();
+        let execution_context = ExecutionContextVc::new(project_path, chunking_context, env);
+        let next_config = load_next_config(execution_context.with_layer("next_config"));
+        let server_addr = ServerAddr::new(SocketAddr::new(Ipv4Addr::LOCALHOST.into(), 3000)).cell();
+
+        let request = RouterRequest {
+            method: "GET".to_string(),
+            pathname,
+            raw_query,
+            raw_headers: HeaderMap::new(),
+            peer_addr: Some(Ipv4Addr::LOCALHOST.into()),
+        }
+        .cell();
+        let result = route(
+            execution_context,
+            request,
+            next_config,
+            server_addr,
+            Rewrites::default().cell(),
+            RouteHandlersVc::cell(vec![]),
+            MetadataRoutesVc::cell(vec![]),
+            CompletionVc::immutable(),
+        )
+        .strongly_consistent()
+        .await?;
+        match &*result {
+            RouterResult::FullMiddleware(data) => Ok(data.body.len()),
+            RouterResult::Rewrite(_) => Ok(0),
+            result => bail!("unexpected router result {result:?}"),
+        }
+    })
+    .await
+}
@@ -29,10 +29,11 @@ use util::{
     AsyncBencherExtension, PreparedApp, BINDING_NAME,
 };
 
-use self::{bundlers::RenderType, util::resume_on_error};
+use self::{bundlers::RenderType, middleware::bench_middleware, util::resume_on_error};
 use crate::{bundlers::Bundler, util::PageGuard};
 
 mod bundlers;
+mod middleware;
 mod util;
 
 const MAX_UPDATE_TIMEOUT: Duration = Duration::from_secs(60);
@@ -489,6 +490,6 @@ fn get_module_counts() -> Vec<usize> {
 criterion_group!(
     name = benches;
     config = Criterion::default();
-    targets = bench_startup, bench_hydration, bench_startup_cached, bench_hydration_cached, bench_hmr_to_eval, bench_hmr_to_commit
+    targets = bench_startup, bench_hydration, bench_startup_cached, bench_hydration_cached, bench_hmr_to_eval, bench_hmr_to_commit, bench_middleware
 );
 criterion_main!(benches);
@@ -79,11 +79,20 @@ pub struct DevServerOptions {
     pub full_stats: bool,
 
     /// Enable experimental garbage collection with the provided memory limit in
-    /// MB.
+    /// MB. `next dev --turbo` passes `experimental.turbo.memoryLimit`.
+    ///
+    /// The garbage collection of the `MemoryBackend` is the only eviction,
+    /// next-core doesn't evict module or chunk results itself.
     #[cfg_attr(feature = "cli", clap(long))]
     #[cfg_attr(feature = "serializable", serde(default))]
     pub memory_limit: Option<usize>,
 
+    /// How long to wait in seconds for in-flight requests on SIGTERM or
+    /// Ctrl-C before exiting. Defaults to 10 seconds.
+    #[cfg_attr(feature = "cli", clap(long))]
+    #[cfg_attr(feature = "serializable", serde(default))]
+    pub shutdown_timeout: Option<u64>,
+
     // ==
     // = Inherited options from next-dev, need revisit later.
     // ==
@@ -2,6 +2,9 @@
 #![feature(min_specialization)]
 
 pub mod devserver_options;
+#[cfg(feature = "otlp")]
+mod otlp;
+mod proxy;
 mod turbo_tasks_viz;
 
 use std::{
@@ -9,8 +12,8 @@ use std::{
     env::current_dir,
     future::{join, Future},
     io::{stdout, Write},
-    net::{IpAddr, SocketAddr},
-    path::{PathBuf, MAIN_SEPARATOR},
+    net::{IpAddr, Ipv4Addr, SocketAddr, TcpListener},
+    path::{Path, PathBuf, MAIN_SEPARATOR},
     sync::Arc,
     time::{Duration, Instant},
 };
@@ -20,10 +23,26 @@ use anyhow::{Context, Result};
 use devserver_options::DevServerOptions;
 use dunce::canonicalize;
 use next_core::{
-    app_structure::find_app_structure, create_app_source, create_page_source,
-    create_web_entry_source, env::load_env, manifest::DevManifestContentSource,
-    next_config::load_next_config, next_image::NextImageContentSourceVc,
-    pages_structure::find_pages_structure, router_source::NextRouterContentSourceVc,
+    app_structure::find_app_structure,
+    browserslist,
+    compile_hints::{CompileHintsContentSource, CompileScheduler},
+    create_app_source, create_page_source, create_service_worker_source, create_web_entry_source,
+    env::load_env,
+    health_source::{HealthContentSourceVc, ServerStatus},
+    issues_source::IssuesContentSource,
+    manifest::DevManifestContentSource,
+    mode::NextMode,
+    next_config::load_next_config,
+    next_image::{
+        cache::{ImageCache, DEFAULT_IMAGE_CACHE_MAX_SIZE},
+        NextImageContentSourceVc,
+    },
+    pages_structure::find_pages_structure,
+    project_structure::validate_project_structure,
+    route_list::{route_list, RouteListContentSource},
+    route_types::write_route_types,
+    router_source::NextRouterContentSourceVc,
+    shutdown,
     source_map::NextSourceMapTraceContentSourceVc,
 };
 use owo_colors::OwoColorize;
@@ -50,7 +69,7 @@ use turbopack_dev_server::{
         source_maps::SourceMapContentSourceVc, static_assets::StaticAssetsContentSourceVc,
         ContentSourceVc,
     },
-    DevServer, DevServerBuilder,
+    DevServer,
 };
 use turbopack_node::execution_context::ExecutionContextVc;
 
@@ -69,7 +88,7 @@ pub struct NextDevServerBuilder {
     hostname: Option<IpAddr>,
     issue_reporter: Option<Box<dyn IssueReporterProvider>>,
     port: Option<u16>,
-    browserslist_query: String,
+    browserslist_query: Option<String>,
     log_level: IssueSeverity,
     show_all: bool,
     log_detail: bool,
@@ -91,9 +110,7 @@ impl NextDevServerBuilder {
             hostname: None,
             issue_reporter: None,
             port: None,
-            browserslist_query: "last 1 Chrome versions, last 1 Firefox versions, last 1 Safari \
-                                 versions, last 1 Edge versions"
-                .to_owned(),
+            browserslist_query: None,
             log_level: IssueSeverity::Warning,
             show_all: false,
             log_detail: false,
@@ -121,8 +138,9 @@ impl NextDevServerBuilder {
         self
     }
 
+    /// Overrides the browserslist config of the project.
     pub fn browserslist_query(mut self, browserslist_query: String) -> NextDevServerBuilder {
-        self.browserslist_query = browserslist_query;
+        self.browserslist_query = Some(browserslist_query);
         self
     }
 
@@ -155,42 +173,31 @@ impl NextDevServerBuilder {
     }
 
     /// Attempts to find an open port to bind.
-    fn find_port(&self, host: IpAddr, port: u16, max_attempts: u16) -> Result<DevServerBuilder> {
+    fn find_port(&self, host: IpAddr, port: u16, max_attempts: u16) -> Result<TcpListener> {
         // max_attempts of 1 means we loop 0 times.
         let max_attempts = max_attempts - 1;
         let mut attempts = 0;
         loop {
             let current_port = port + attempts;
             let addr = SocketAddr::new(host, current_port);
-            let listen_result = DevServer::listen(addr);
-
-            if let Err(e) = &listen_result {
-                if self.allow_retry && attempts < max_attempts {
-                    // Returned error from `listen` is not `std::io::Error` but `anyhow::Error`,
-                    // so we need to access its source to check if it is
-                    // `std::io::ErrorKind::AddrInUse`.
-                    let should_retry = e
-                        .source()
-                        .and_then(|e| {
-                            e.downcast_ref::<std::io::Error>()
-                                .map(|e| e.kind() == std::io::ErrorKind::AddrInUse)
-                        })
-                        .unwrap_or(false);
-
-                    if should_retry {
-                        println!(
-                            "{} - Port {} is in use, trying {} instead",
-                            "warn ".yellow(),
-                            current_port,
-                            current_port + 1
-                        );
-                        attempts += 1;
-                        continue;
-                    }
+            match TcpListener::bind(addr) {
+                Err(e)
+                    if self.allow_retry
+                        && attempts < max_attempts
+                        && e.kind() == std::io::ErrorKind::AddrInUse =>
+                {
+                    println!(
+                        "{} - Port {} is in use, trying {} instead",
+                        "warn ".yellow(),
+                        current_port,
+                        current_port + 1
+                    );
+                    attempts += 1;
+                }
+                result => {
+                    return result.with_context(|| format!("failed to bind {addr}"));
                 }
             }
-
-            return listen_result;
         }
     }
 
@@ -198,7 +205,12 @@ impl NextDevServerBuilder {
         let port = self.port.context("port must be set")?;
         let host = self.hostname.context("hostname must be set")?;
 
-        let server = self.find_port(host, port, 10)?;
+        let listener = self.find_port(host, port, 10)?;
+        let addr = listener.local_addr()?;
+        // The dev server only listens on the loopback interface, requests are
+        // forwarded to it with the peer address of their connection and their
+        // headers, see `proxy.rs`.
+        let server = DevServer::listen(SocketAddr::new(Ipv4Addr::LOCALHOST.into(), 0))?;
 
         let turbo_tasks = self.turbo_tasks;
         let project_dir = self.project_dir;
@@ -215,8 +227,14 @@ impl NextDevServerBuilder {
             log_level: self.log_level,
         });
         let entry_requests = Arc::new(self.entry_requests);
-        let server_addr = Arc::new(server.addr);
+        let server_addr = Arc::new(addr);
         let tasks = turbo_tasks.clone();
+        let server_status = Arc::new(ServerStatus::default());
+        let compile_scheduler = Arc::new(CompileScheduler::default());
+        tokio::spawn(track_server_status(
+            turbo_tasks.clone(),
+            server_status.clone(),
+        ));
         let issue_provider = self.issue_reporter.unwrap_or_else(|| {
             // Initialize a ConsoleUi reporter if no custom reporter was provided
             Box::new(move || ConsoleUiVc::new(log_options.clone().into()).into())
@@ -231,11 +249,43 @@ impl NextDevServerBuilder {
                 turbo_tasks.clone().into(),
                 browserslist_query.clone(),
                 server_addr.clone().into(),
+                server_status.clone().into(),
+                compile_scheduler.clone().into(),
             )
         };
 
         let issue_reporter_arc = Arc::new(move || issue_provider.get_issue_reporter());
-        Ok(server.serve(tasks, source, issue_reporter_arc))
+        let server = server.serve(tasks, source, issue_reporter_arc);
+        let proxy = proxy::serve_proxy(listener, server.addr);
+        Ok(DevServer {
+            addr,
+            future: Box::pin(async move {
+                futures::future::try_join(server.future, proxy).await?;
+                Ok(())
+            }),
+        })
+    }
+}
+
+/// Reports the server as compiling while tasks are in progress. Answering a
+/// request is a task too, so the server only counts as compiling when tasks
+/// are in progress on consecutive samples.
+async fn track_server_status(
+    turbo_tasks: Arc<TurboTasks<MemoryBackend>>,
+    server_status: Arc<ServerStatus>,
+) {
+    const SAMPLE_INTERVAL: Duration = Duration::from_millis(100);
+    const BUSY_SAMPLES: u32 = 2;
+
+    let mut busy_samples = 0;
+    loop {
+        tokio::time::sleep(SAMPLE_INTERVAL).await;
+        if turbo_tasks.get_in_progress_count() > 0 {
+            busy_samples += 1;
+        } else {
+            busy_samples = 0;
+        }
+        server_status.set_compiling(busy_samples >= BUSY_SAMPLES);
     }
 }
 
@@ -261,8 +311,10 @@ async fn source(
     entry_requests: TransientInstance<Vec<EntryRequest>>,
     eager_compile: bool,
     turbo_tasks: TransientInstance<TurboTasks<MemoryBackend>>,
-    browserslist_query: String,
+    browserslist_query: Option<String>,
     server_addr: TransientInstance<SocketAddr>,
+    server_status: TransientInstance<ServerStatus>,
+    compile_scheduler: TransientInstance<CompileScheduler>,
 ) -> Result<ContentSourceVc> {
     let output_fs = output_fs(&project_dir);
     let fs = project_fs(&root_dir);
@@ -274,6 +326,12 @@ async fn source(
     let project_path = fs.root().join(&project_relative);
 
     let env = load_env(project_path);
+    let browserslist_query = match browserslist_query {
+        Some(browserslist_query) => browserslist_query,
+        None => browserslist::browserslist_query(project_path, Value::new(NextMode::Development))
+            .await?
+            .clone_value(),
+    };
     let build_output_root = output_fs.root().join(".next/build");
 
     let build_chunking_context = DevChunkingContextVc::builder(
@@ -314,6 +372,8 @@ async fn source(
         &browserslist_query,
         next_config,
     );
+    // Reports problems with the project structure before any route is compiled.
+    validate_project_structure(project_path, next_config).await?;
     let pages_structure = find_pages_structure(project_path, dev_server_root, next_config);
     let page_source = create_page_source(
         pages_structure,
@@ -327,6 +387,8 @@ async fn source(
         server_addr,
     );
     let app_structure = find_app_structure(project_path, dev_server_root, next_config);
+    // Keeps the route types up to date while the routes change.
+    write_route_types(project_path, app_structure, next_config).await?;
     let app_source = create_app_source(
         app_structure,
         project_path,
@@ -338,6 +400,14 @@ async fn source(
         next_config,
         server_addr,
     );
+    let service_worker_source = create_service_worker_source(
+        project_path,
+        execution_context,
+        dev_server_root,
+        env,
+        &browserslist_query,
+        next_config,
+    );
     let viz = turbo_tasks_viz::TurboTasksSource {
         turbo_tasks: turbo_tasks.into(),
     }
@@ -354,6 +424,7 @@ async fn source(
     let main_source = CombinedContentSourceVc::new(vec![
         manifest_source,
         static_source,
+        service_worker_source,
         app_source,
         page_source,
         web_source,
@@ -366,31 +437,100 @@ async fn source(
     let main_source = main_source.into();
     let source_maps = SourceMapContentSourceVc::new(main_source).into();
     let source_map_trace = NextSourceMapTraceContentSourceVc::new(main_source).into();
-    let img_source = NextImageContentSourceVc::new(
-        CombinedContentSourceVc::new(vec![static_source, page_source]).into(),
-    )
+    let image_cache = match ImageCache::new(
+        Path::new(&project_dir).join(".next/cache/images"),
+        DEFAULT_IMAGE_CACHE_MAX_SIZE,
+    ) {
+        Ok(cache) => Some(Arc::new(cache)),
+        Err(err) => {
+            println!(
+                "{} - images are optimized without a cache: {err:#}",
+                "warn ".yellow()
+            );
+            None
+        }
+    };
+    let img_source =
+        NextImageContentSourceVc::new(project_path, next_config, server_addr, image_cache).into();
+    let route_list_source = RouteListContentSource {
+        project_path,
+        execution_context,
+        server_root: dev_server_root,
+        env,
+        server_addr,
+        next_config,
+        app_structure,
+        pages_structure,
+    }
+    .cell()
+    .into();
+    let compile_hints_source = CompileHintsContentSource {
+        project_path,
+        execution_context,
+        server_root: dev_server_root,
+        env,
+        server_addr,
+        next_config,
+        app_structure,
+        pages_structure,
+        scheduler: compile_scheduler.into(),
+    }
+    .cell()
+    .into();
+    let issues_source = IssuesContentSource {
+        source: main_source,
+        route_list: route_list(
+            project_path,
+            execution_context,
+            dev_server_root,
+            env,
+            server_addr,
+            next_config,
+            app_structure,
+            pages_structure,
+        ),
+    }
+    .cell()
     .into();
     let router_source = NextRouterContentSourceVc::new(
         main_source,
         execution_context,
         next_config,
         server_addr,
+        dev_server_root,
         app_structure,
         pages_structure,
     )
     .into();
+    let mut routes = vec![];
+    if let Some(health_check) = &*next_config.health_check().await? {
+        let path = health_check.path.as_deref().unwrap_or("/healthz");
+        let readiness_path = health_check.readiness_path.as_deref().unwrap_or("/readyz");
+        routes.push((
+            path.trim_start_matches('/').to_string(),
+            HealthContentSourceVc::health().into(),
+        ));
+        routes.push((
+            readiness_path.trim_start_matches('/').to_string(),
+            HealthContentSourceVc::readiness(server_status.into()).into(),
+        ));
+    }
+    routes.extend([
+        ("__turbopack__/".to_string(), introspect),
+        ("__turbo_tasks__/".to_string(), viz),
+        (
+            "__nextjs_original-stack-frame".to_string(),
+            source_map_trace,
+        ),
+        ("__nextjs_routes".to_string(), route_list_source),
+        ("__nextjs_issues".to_string(), issues_source),
+        ("__nextjs_compile_hints".to_string(), compile_hints_source),
+        // TODO: Load path from next.config.js
+        ("_next/image".to_string(), img_source),
+        ("__turbopack_sourcemap__/".to_string(), source_maps),
+    ]);
     let source = RouterContentSource {
-        routes: vec![
-            ("__turbopack__/".to_string(), introspect),
-            ("__turbo_tasks__/".to_string(), viz),
-            (
-                "__nextjs_original-stack-frame".to_string(),
-                source_map_trace,
-            ),
-            // TODO: Load path from next.config.js
-            ("_next/image".to_string(), img_source),
-            ("__turbopack_sourcemap__/".to_string(), source_maps),
-        ],
+        routes,
         fallback: router_source,
     }
     .cell()
@@ -410,6 +550,8 @@ pub async fn start_server(options: &DevServerOptions) -> Result<()> {
 
     #[cfg(feature = "tokio_console")]
     console_subscriber::init();
+    #[cfg(feature = "otlp")]
+    otlp::init_otlp_tracing().context("failed to export traces over OTLP")?;
     register();
 
     let dir = options
@@ -443,6 +585,7 @@ pub async fn start_server(options: &DevServerOptions) -> Result<()> {
     tt.set_stats_type(stats_type);
 
     let tt_clone = tt.clone();
+    let tt_shutdown = tt.clone();
 
     #[allow(unused_mut)]
     let mut server = NextDevServerBuilder::new(tt, dir, root_dir)
@@ -565,11 +708,57 @@ pub async fn start_server(options: &DevServerOptions) -> Result<()> {
         }
     };
 
-    join!(stats_future, async { server.future.await.unwrap() }).await;
+    let shutdown_timeout = Duration::from_secs(
+        options
+            .shutdown_timeout
+            .unwrap_or(DEFAULT_SHUTDOWN_TIMEOUT_SECS),
+    );
+    tokio::select! {
+        _ = join!(stats_future, async { server.future.await.unwrap() }) => {}
+        _ = shutdown_signal() => {
+            println!("{} - shutting down", "event".purple());
+            if !shutdown::shutdown(shutdown_timeout).await {
+                println!(
+                    "{} - in-flight requests didn't finish within {}s",
+                    "warn ".yellow(),
+                    shutdown_timeout.as_secs()
+                );
+            }
+            // Stopping the tasks drops the Node.js pools, which terminates their
+            // workers.
+            tt_shutdown.stop_and_wait().await;
+        }
+    }
 
     Ok(())
 }
 
+const DEFAULT_SHUTDOWN_TIMEOUT_SECS: u64 = 10;
+
+/// Resolves on Ctrl-C, or on SIGTERM, which is how containers are stopped.
+async fn shutdown_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        match signal(SignalKind::terminate()) {
+            Ok(mut sigterm) => {
+                tokio::select! {
+                    _ = tokio::signal::ctrl_c() => {}
+                    _ = sigterm.recv() => {}
+                }
+            }
+            Err(_) => {
+                let _ = tokio::signal::ctrl_c().await;
+            }
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = tokio::signal::ctrl_c().await;
+    }
+}
+
 #[cfg(feature = "profile")]
 // When profiling, exits the process when no new updates have been received for
 // a given timeout and there are no more tasks in progress.
@@ -0,0 +1,49 @@
+use anyhow::Result;
+use futures::FutureExt;
+use next_core::shutdown::on_shutdown;
+use opentelemetry::{
+    runtime::Tokio,
+    sdk::{trace, Resource},
+    KeyValue,
+};
+use opentelemetry_otlp::WithExportConfig;
+use tracing_subscriber::{filter::LevelFilter, prelude::*, util::SubscriberInitExt, Layer};
+
+/// Exports the tracing spans of the dev server to an OpenTelemetry collector
+/// when `OTEL_EXPORTER_OTLP_ENDPOINT` is set, e.g. to
+/// `http://localhost:4317`. The spans of routing, evaluating and processing
+/// modules show where the time of a request is spent.
+///
+/// Nothing is exported when a tracing subscriber is already installed, e.g.
+/// the one writing a trace file. Spans are exported in batches, the last one
+/// is flushed when the dev server shuts down.
+pub fn init_otlp_tracing() -> Result<()> {
+    if std::env::var_os("OTEL_EXPORTER_OTLP_ENDPOINT").is_none() {
+        return Ok(());
+    }
+    let tracer = opentelemetry_otlp::new_pipeline()
+        .tracing()
+        .with_exporter(opentelemetry_otlp::new_exporter().tonic().with_env())
+        .with_trace_config(
+            trace::config()
+                .with_resource(Resource::new([KeyValue::new("service.name", "next-dev")])),
+        )
+        .install_batch(Tokio)?;
+    on_shutdown(|| {
+        // Shutting down the provider blocks until the pending spans are
+        // exported by the runtime, so it can't block one of its workers.
+        async {
+            let _ =
+                tokio::task::spawn_blocking(opentelemetry::global::shutdown_tracer_provider).await;
+        }
+        .boxed()
+    });
+    let _ = tracing_subscriber::registry()
+        .with(
+            tracing_opentelemetry::layer()
+                .with_tracer(tracer)
+                .with_filter(LevelFilter::INFO),
+        )
+        .try_init();
+    Ok(())
+}
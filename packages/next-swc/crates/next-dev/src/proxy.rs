@@ -0,0 +1,104 @@
+use std::{convert::Infallible, net::SocketAddr};
+
+use anyhow::Result;
+use hyper::{
+    client::HttpConnector,
+    header::{HeaderName, HeaderValue, UPGRADE},
+    server::conn::AddrStream,
+    service::{make_service_fn, service_fn},
+    Body, Client, Request, Response, Server, StatusCode,
+};
+use next_core::{
+    router::{HeaderMap, RawHeaderValue},
+    router_source::{PEER_ADDR_HEADER, RAW_HEADERS_HEADER},
+};
+
+/// Serves the public address of the dev server, forwarding every request to
+/// the dev server listening on `target`.
+///
+/// The dev server neither exposes the connection of a request nor handles
+/// header values which aren't visible ASCII, so requests get the peer address
+/// of their connection in the [PEER_ADDR_HEADER], and such header values of
+/// requests and responses pass it in the [RAW_HEADERS_HEADER].
+pub async fn serve_proxy(listener: std::net::TcpListener, target: SocketAddr) -> Result<()> {
+    listener.set_nonblocking(true)?;
+    let client = Client::new();
+    let make_service = make_service_fn(move |connection: &AddrStream| {
+        let peer_addr = connection.remote_addr();
+        let client = client.clone();
+        async move {
+            Ok::<_, Infallible>(service_fn(move |request| {
+                forward(client.clone(), target, peer_addr, request)
+            }))
+        }
+    });
+    Server::from_tcp(listener)?.serve(make_service).await?;
+    Ok(())
+}
+
+async fn forward(
+    client: Client<HttpConnector>,
+    target: SocketAddr,
+    peer_addr: SocketAddr,
+    mut request: Request<Body>,
+) -> Result<Response<Body>> {
+    let path_and_query = request
+        .uri()
+        .path_and_query()
+        .map_or("/", |path_and_query| path_and_query.as_str());
+    *request.uri_mut() = format!("http://{target}{path_and_query}").parse()?;
+
+    // Replaces the headers sent by the client, which can't be trusted.
+    let headers = request.headers_mut();
+    headers.remove(RAW_HEADERS_HEADER);
+    headers.insert(
+        PEER_ADDR_HEADER,
+        HeaderValue::from_str(&peer_addr.ip().to_string())?,
+    );
+    let (raw, _) = headers
+        .iter()
+        .map(|(name, value)| (name.as_str(), RawHeaderValue::from(value.as_bytes())))
+        .collect::<HeaderMap>()
+        .split_raw();
+    if !raw.is_empty() {
+        for (name, _) in raw.iter() {
+            headers.remove(name);
+        }
+        headers.insert(RAW_HEADERS_HEADER, HeaderValue::from_str(&raw.encode())?);
+    }
+
+    // HMR uses WebSockets, the connections are joined once both sides agreed
+    // to upgrade them.
+    let request_upgrade = request
+        .headers()
+        .contains_key(UPGRADE)
+        .then(|| hyper::upgrade::on(&mut request));
+    let mut response = client.request(request).await?;
+    if let Some(request_upgrade) = request_upgrade {
+        if response.status() == StatusCode::SWITCHING_PROTOCOLS {
+            let response_upgrade = hyper::upgrade::on(&mut response);
+            tokio::spawn(async move {
+                if let (Ok(mut client), Ok(mut server)) =
+                    futures::join!(request_upgrade, response_upgrade)
+                {
+                    let _ = tokio::io::copy_bidirectional(&mut client, &mut server).await;
+                }
+            });
+        }
+    }
+
+    let headers = response.headers_mut();
+    if let Some(raw) = headers.remove(RAW_HEADERS_HEADER) {
+        let raw = raw.to_str().ok().and_then(HeaderMap::decode);
+        for (name, value) in raw.iter().flat_map(|raw| raw.iter()) {
+            // Values with control characters can't be sent.
+            if let (Ok(name), Ok(value)) = (
+                HeaderName::from_bytes(name.as_bytes()),
+                HeaderValue::from_bytes(&value.0),
+            ) {
+                headers.append(name, value);
+            }
+        }
+    }
+    Ok(response)
+}
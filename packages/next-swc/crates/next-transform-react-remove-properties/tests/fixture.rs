@@ -0,0 +1,43 @@
+use std::path::PathBuf;
+
+use next_transform_react_remove_properties::{remove_properties, Config, Options};
+use swc_core::ecma::{
+    parser::{EsConfig, Syntax},
+    transforms::testing::test_fixture,
+};
+use testing::fixture;
+
+fn syntax() -> Syntax {
+    Syntax::Es(EsConfig {
+        jsx: true,
+        ..Default::default()
+    })
+}
+
+#[fixture("tests/fixture/react-remove-properties/default/**/input.js")]
+fn react_remove_properties_default_fixture(input: PathBuf) {
+    let output = input.parent().unwrap().join("output.js");
+    test_fixture(
+        syntax(),
+        &|_tr| remove_properties(Config::All(true)),
+        &input,
+        &output,
+        Default::default(),
+    );
+}
+
+#[fixture("tests/fixture/react-remove-properties/custom/**/input.js")]
+fn react_remove_properties_custom_fixture(input: PathBuf) {
+    let output = input.parent().unwrap().join("output.js");
+    test_fixture(
+        syntax(),
+        &|_tr| {
+            remove_properties(Config::WithOptions(Options {
+                properties: vec!["^data-custom$".into()],
+            }))
+        },
+        &input,
+        &output,
+        Default::default(),
+    );
+}
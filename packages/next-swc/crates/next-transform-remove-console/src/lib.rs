@@ -1,6 +1,6 @@
 use serde::Deserialize;
 
-use next_binding::swc::core::{
+use swc_core::{
     common::{collections::AHashSet, DUMMY_SP},
     ecma::ast::*,
     ecma::atoms::JsWord,
@@ -9,6 +9,8 @@ use next_binding::swc::core::{
 
 use crate::top_level_binding_collector::collect_top_level_decls;
 
+mod top_level_binding_collector;
+
 #[derive(Clone, Debug, Deserialize)]
 #[serde(untagged)]
 pub enum Config {
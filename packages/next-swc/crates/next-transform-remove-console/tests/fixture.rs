@@ -0,0 +1,27 @@
+use std::path::PathBuf;
+
+use next_transform_remove_console::{remove_console, Config};
+use swc_core::ecma::{
+    parser::{EsConfig, Syntax},
+    transforms::testing::test_fixture,
+};
+use testing::fixture;
+
+fn syntax() -> Syntax {
+    Syntax::Es(EsConfig {
+        jsx: true,
+        ..Default::default()
+    })
+}
+
+#[fixture("tests/fixture/remove-console/**/input.js")]
+fn remove_console_fixture(input: PathBuf) {
+    let output = input.parent().unwrap().join("output.js");
+    test_fixture(
+        syntax(),
+        &|_tr| remove_console(Config::All(true)),
+        &input,
+        &output,
+        Default::default(),
+    );
+}
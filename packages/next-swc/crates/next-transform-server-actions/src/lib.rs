@@ -1,7 +1,7 @@
-use std::convert::{TryFrom, TryInto};
-
 use hex::encode as hex_encode;
-use next_binding::swc::core::{
+use serde::Deserialize;
+use sha1::{Digest, Sha1};
+use swc_core::{
     common::{
         comments::{Comment, CommentKind, Comments},
         errors::HANDLER,
@@ -15,8 +15,6 @@ use next_binding::swc::core::{
         visit::{as_folder, noop_visit_mut_type, Fold, VisitMut, VisitMutWith},
     },
 };
-use serde::Deserialize;
-use sha1::{Digest, Sha1};
 
 #[derive(Clone, Debug, Deserialize)]
 #[serde(deny_unknown_fields, rename_all = "camelCase")]
@@ -24,6 +22,20 @@ pub struct Config {
     pub is_server: bool,
 }
 
+/// The leading comment of a module containing actions, followed by the
+/// comma-separated export names of the actions.
+pub const ACTION_ENTRY_MARKER: &str = "__next_internal_action_entry_do_not_use__";
+
+/// Returns the id of an action, the hex encoded sha1 of
+/// `{file_name}:{export_name}`.
+pub fn generate_action_id(file_name: &str, export_name: &str) -> String {
+    let mut hasher = Sha1::new();
+    hasher.update(file_name.as_bytes());
+    hasher.update(b":");
+    hasher.update(export_name.as_bytes());
+    hex_encode(hasher.finalize())
+}
+
 pub fn server_actions<C: Comments>(
     file_name: &FileName,
     config: Config,
@@ -1135,7 +1147,7 @@ impl<C: Comments> VisitMut for ServerActions<C> {
                     kind: CommentKind::Block,
                     // Append a list of exported actions.
                     text: format!(
-                        " __next_internal_action_entry_do_not_use__ {} ",
+                        " {ACTION_ENTRY_MARKER} {} ",
                         if self.in_action_file {
                             self.exported_idents
                                 .iter()
@@ -1249,14 +1261,11 @@ fn annotate_ident_as_action(
 
     // Attach a checksum to the action using sha1:
     // myAction.$$id = sha1('file_name' + ':' + 'export_name');
-    let mut hasher = Sha1::new();
-    hasher.update(file_name.as_bytes());
-    hasher.update(b":");
-    hasher.update(export_name.as_bytes());
-    let result = hasher.finalize();
-
-    // Convert result to hex string
-    annotations.push(annotate(&ident, "$$id", hex_encode(result).into()));
+    annotations.push(annotate(
+        &ident,
+        "$$id",
+        generate_action_id(&file_name, &export_name).into(),
+    ));
 
     // myAction.$$bound = [];
     annotations.push(annotate(
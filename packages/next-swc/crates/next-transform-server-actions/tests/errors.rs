@@ -0,0 +1,45 @@
+use std::path::PathBuf;
+
+use next_transform_server_actions::{server_actions, Config};
+use swc_core::{
+    common::{chain, FileName, Mark},
+    ecma::{
+        parser::{EsConfig, Syntax},
+        transforms::{
+            base::resolver,
+            testing::{test_fixture, FixtureTestConfig},
+        },
+    },
+};
+use testing::fixture;
+
+fn syntax() -> Syntax {
+    Syntax::Es(EsConfig {
+        jsx: true,
+        ..Default::default()
+    })
+}
+
+#[fixture("tests/errors/**/input.js")]
+fn server_actions_errors(input: PathBuf) {
+    let output = input.parent().unwrap().join("output.js");
+    test_fixture(
+        syntax(),
+        &|tr| {
+            chain!(
+                resolver(Mark::new(), Mark::new(), false),
+                server_actions(
+                    &FileName::Real("/app/item.js".into()),
+                    Config { is_server: true },
+                    tr.comments.as_ref().clone(),
+                )
+            )
+        },
+        &input,
+        &output,
+        FixtureTestConfig {
+            allow_error: true,
+            ..Default::default()
+        },
+    );
+}
@@ -0,0 +1,48 @@
+use std::path::PathBuf;
+
+use next_transform_server_actions::{server_actions, Config};
+use swc_core::{
+    common::{chain, FileName, Mark},
+    ecma::{
+        parser::{EsConfig, Syntax},
+        transforms::{base::resolver, testing::test_fixture},
+    },
+};
+use testing::fixture;
+
+fn syntax() -> Syntax {
+    Syntax::Es(EsConfig {
+        jsx: true,
+        ..Default::default()
+    })
+}
+
+#[fixture("tests/fixture/server/**/input.js")]
+fn server_actions_server_fixture(input: PathBuf) {
+    server_actions_fixture_run(input, true);
+}
+
+#[fixture("tests/fixture/client/**/input.js")]
+fn server_actions_client_fixture(input: PathBuf) {
+    server_actions_fixture_run(input, false);
+}
+
+fn server_actions_fixture_run(input: PathBuf, is_server: bool) {
+    let output = input.parent().unwrap().join("output.js");
+    test_fixture(
+        syntax(),
+        &|tr| {
+            chain!(
+                resolver(Mark::new(), Mark::new(), false),
+                server_actions(
+                    &FileName::Real("/app/item.js".into()),
+                    Config { is_server },
+                    tr.comments.as_ref().clone(),
+                )
+            )
+        },
+        &input,
+        &output,
+        Default::default(),
+    );
+}
@@ -0,0 +1,19 @@
+import { getSession } from "@acme/auth";
+import db from "../lib/db";
+import { formatDate } from "../lib/format";
+
+export async function getServerSideProps({ req }) {
+  const session = await getSession(req);
+  const posts = await db.posts.findMany({ where: { author: session.userId } });
+  return { props: { posts } };
+}
+
+export default function Dashboard({ posts }) {
+  return (
+    <ul>
+      {posts.map((post) => (
+        <li key={post.id}>{formatDate(post.createdAt)}</li>
+      ))}
+    </ul>
+  );
+}
@@ -0,0 +1,15 @@
+import { getSession } from "@acme/auth";
+import db from "../lib/db";
+export async function getServerSideProps({ req }) {
+  const session = await getSession(req);
+  const posts = await db.posts.findMany({
+    where: {
+      author: session.userId,
+    },
+  });
+  return {
+    props: {
+      posts,
+    },
+  };
+}
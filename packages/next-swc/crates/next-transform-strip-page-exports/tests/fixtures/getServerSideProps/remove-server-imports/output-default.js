@@ -0,0 +1,17 @@
+import { formatDate } from "../lib/format";
+export var __N_SSP = true;
+export default function Dashboard({ posts }) {
+  return __jsx(
+    "ul",
+    null,
+    posts.map((post) =>
+      __jsx(
+        "li",
+        {
+          key: post.id,
+        },
+        formatDate(post.createdAt)
+      )
+    )
+  );
+}
@@ -0,0 +1,30 @@
+import fs from "fs";
+import path from "path";
+import Link from "next/link";
+
+const postsDirectory = path.join(process.cwd(), "posts");
+
+export async function getStaticPaths() {
+  const slugs = fs.readdirSync(postsDirectory);
+  return {
+    paths: slugs.map((slug) => ({ params: { slug } })),
+    fallback: false,
+  };
+}
+
+export async function getStaticProps({ params }) {
+  const content = fs.readFileSync(
+    path.join(postsDirectory, params.slug),
+    "utf8"
+  );
+  return { props: { content } };
+}
+
+export default function Post({ content }) {
+  return (
+    <article>
+      <Link href="/">Home</Link>
+      {content}
+    </article>
+  );
+}
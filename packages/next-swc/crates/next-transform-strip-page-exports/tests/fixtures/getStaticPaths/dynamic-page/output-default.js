@@ -0,0 +1,16 @@
+import Link from "next/link";
+export var __N_SSG = true;
+export default function Post({ content }) {
+  return __jsx(
+    "article",
+    null,
+    __jsx(
+      Link,
+      {
+        href: "/",
+      },
+      "Home"
+    ),
+    content
+  );
+}
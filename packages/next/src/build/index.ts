@@ -1026,7 +1026,12 @@ export default async function build(
 
       async function turbopackBuild() {
         const turboNextBuildStart = process.hrtime()
-        await binding.turbo.nextBuild(NextBuildContext)
+        await binding.turbo.nextBuild({
+          ...NextBuildContext,
+          standalone: config.output === 'standalone',
+          staticExport: config.output === 'export',
+          memoryLimit: config.experimental.turbo?.memoryLimit,
+        })
         const [duration] = process.hrtime(turboNextBuildStart)
         return { duration, turbotraceContext: null }
       }
@@ -2237,7 +2242,8 @@ export default async function build(
         )
       )
 
-      if (config.output === 'standalone') {
+      // Turbopack traces and copies the files of the standalone output itself.
+      if (config.output === 'standalone' && !turboNextBuild) {
         await nextBuildSpan
           .traceChild('copy-traced-files')
           .traceAsyncFn(async () => {
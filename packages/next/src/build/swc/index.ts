@@ -380,6 +380,10 @@ function loadNative(isCustomTurbopack = false) {
         )
       },
 
+      transformForTestSync(src: string, options: any) {
+        return bindings.transformForTestSync(src, toBuffer(options))
+      },
+
       minify(src: string, options: any) {
         return bindings.minify(toBuffer(src), toBuffer(options ?? {}))
       },
@@ -471,9 +475,10 @@ function loadNative(isCustomTurbopack = false) {
             require(__INTERNAL_CUSTOM_TURBOPACK_BINDINGS).startDev(devOptions)
           }
         },
-        nextBuild: (options: unknown) => {
-          return bindings.nextBuild(options)
-        },
+        nextBuild: async (options: unknown) =>
+          JSON.parse(await bindings.nextBuild(options)),
+        exportTransformConfig: async (options: unknown) =>
+          JSON.parse(await bindings.exportTransformConfig(toBuffer(options))),
         startTrace: (options = {}, turboTasks: unknown) =>
           bindings.runTurboTracing(
             toBuffer({ exact: true, ...options }),
@@ -514,6 +519,40 @@ export function transformSync(src: string, options?: any): any {
   return bindings.transformSync(src, options)
 }
 
+/**
+ * Transforms a module for tests with the pipeline Turbopack uses for builds,
+ * see `getJestSWCOptions` for the options.
+ */
+export function transformForTestSync(src: string, options: any): any {
+  let bindings = loadBindingsSync()
+  if (!bindings.transformForTestSync) {
+    throw new Error(
+      'The test transform of Turbopack is not supported by the wasm build of next-swc'
+    )
+  }
+  return bindings.transformForTestSync(src, options)
+}
+
+/**
+ * Returns the resolve and transform configuration Turbopack applies to the
+ * modules of a context of the project, e.g. its aliases, resolve extensions
+ * and compile-time defines, for tools bundling the app without Turbopack like
+ * Storybook.
+ */
+export async function exportTransformConfig(options: {
+  dir: string
+  context: 'client' | 'server' | 'edge'
+  dev?: boolean
+}): Promise<any> {
+  let bindings = await loadBindings()
+  if (!bindings.turbo.exportTransformConfig) {
+    throw new Error(
+      'Exporting the transform config of Turbopack is not supported by the wasm build of next-swc'
+    )
+  }
+  return bindings.turbo.exportTransformConfig(options)
+}
+
 export async function minify(src: string, options: any): Promise<string> {
   let bindings = await loadBindings()
   return bindings.minify(src, options)
@@ -27,7 +27,7 @@ DEALINGS IN THE SOFTWARE.
 */
 
 import vm from 'vm'
-import { transformSync } from './index'
+import { transformForTestSync, transformSync } from './index'
 import { getJestSWCOptions } from './options'
 
 // Jest use the `vm` [Module API](https://nodejs.org/api/vm.html#vm_class_vm_module) for ESM.
@@ -56,7 +56,7 @@ module.exports = {
     process(src: any, filename: any, jestOptions: any) {
       const jestConfig = getJestConfig(jestOptions)
 
-      let swcTransformOpts = getJestSWCOptions({
+      const options = {
         // When target is node it's similar to the server option set in SWC.
         isServer:
           jestConfig.testEnvironment && jestConfig.testEnvironment === 'node',
@@ -69,7 +69,19 @@ module.exports = {
         esm:
           isSupportEsm &&
           isEsm(Boolean(inputOptions.isEsmProject), filename, jestConfig),
-      })
+      }
+
+      // With Turbopack, modules are transformed like they are for builds,
+      // e.g. with the defines of `experimental.turbo.defines.test`.
+      if (inputOptions.nextConfig?.experimental?.turbo) {
+        return transformForTestSync(src, {
+          ...options,
+          isServer: Boolean(options.isServer),
+          nodeVersion: process.versions.node,
+        })
+      }
+
+      let swcTransformOpts = getJestSWCOptions(options)
 
       return transformSync(src, { ...swcTransformOpts, filename })
     },
@@ -471,6 +471,7 @@ If you cannot make the changes above, but still want to try out\nNext.js v13 wit
     let server = bindings.turbo.startDev({
       ...devServerOptions,
       showAll: args['--show-all'] ?? false,
+      memoryLimit: rawNextConfig.experimental?.turbo?.memoryLimit,
       root:
         args['--root'] ??
         (turboJson
@@ -33,6 +33,9 @@ const configSchema = {
     compiler: {
       additionalProperties: false,
       properties: {
+        cssMinify: {
+          type: 'boolean',
+        },
         emotion: {
           oneOf: [
             {
@@ -443,9 +446,54 @@ const configSchema = {
             loaders: {
               type: 'object',
             },
+            rules: {
+              type: 'object',
+            },
             resolveAlias: {
               type: 'object',
             },
+            remotes: {
+              type: 'object',
+              additionalProperties: {
+                type: 'string',
+              },
+            },
+            dataModules: {
+              type: 'object',
+              additionalProperties: {
+                type: 'string',
+                enum: ['json', 'yaml', 'toml'],
+              },
+            },
+            serviceWorker: {
+              minLength: 1,
+              type: 'string',
+            },
+            mediaInlineLimit: {
+              minimum: 0,
+              type: 'number',
+            },
+            edgeNodeCompat: {
+              enum: ['shims', 'strict'],
+              type: 'string',
+            },
+            optimizeDeps: {
+              type: 'array',
+              items: {
+                type: 'string',
+              },
+            },
+            isrCache: {
+              type: 'boolean',
+            },
+            memoryLimit: {
+              minimum: 0,
+              type: 'integer',
+            },
+            entryConcurrency: {
+              minimum: 1,
+              type: 'integer',
+            },
           },
         },
         instrumentationHook: {
@@ -65,12 +65,14 @@ type TurboLoaderItem =
 interface ExperimentalTurboOptions {
   /**
    * (`next --turbo` only) A mapping of aliased imports to modules to load in their place.
+   * Aliases can be conditional on `browser` or `server`, and an alias to `false`
+   * resolves to an empty module.
    *
    * @see [Resolve Alias](https://nextjs.org/docs/api-reference/next.config.js/resolve-alias)
    */
   resolveAlias?: Record<
     string,
-    string | string[] | Record<string, string | string[]>
+    false | string | string[] | Record<string, false | string | string[]>
   >
 
   /**
@@ -79,6 +81,88 @@ interface ExperimentalTurboOptions {
    * @see [Turbopack Loaders](https://nextjs.org/docs/api-reference/next.config.js/turbopack-loaders)
    */
   loaders?: Record<string, TurboLoaderItem[]>
+
+  /**
+   * (`next --turbo` only) Webpack loaders to run on files matching an extension glob
+   * (e.g. `'*.svg'`), like `loaders`. The output is processed as JavaScript, so `as`
+   * can only be `'*.js'` (the default).
+   */
+  rules?: Record<
+    string,
+    TurboLoaderItem[] | { loaders: TurboLoaderItem[]; as?: string }
+  >
+
+  /**
+   * (`next --turbo` only, experimental) Precompiled remote containers to load at runtime
+   * in the browser and the Node.js server, keyed by the specifier prefix they are imported
+   * with. Values are the container entry URL, or `global@url` when the container registers
+   * a different global. The server reads containers served from `public` from disk, other
+   * URLs must be absolute for it. Remote modules are loaded asynchronously:
+   * `await import("remote/module")` resolves to the exposed module.
+   */
+  remotes?: Record<string, string>
+
+  /**
+   * (`next --turbo` only) Data file formats to import as JSON modules, keyed by file
+   * extension (e.g. `'.yaml'`). Defaults to handling `.yaml`, `.yml` and `.toml`.
+   */
+  dataModules?: Record<string, 'json' | 'yaml' | 'toml'>
+
+  /**
+   * (`next --turbo` only) The service worker entry, relative to the project directory
+   * (e.g. `'app/sw.ts'`). It is compiled for the service worker environment and served
+   * at the root of the app (e.g. `/sw.js`).
+   */
+  serviceWorker?: string
+
+  /**
+   * (`next --turbo` only) Audio and video files smaller than this many bytes are
+   * inlined as data URLs when imported. Defaults to 8 KiB.
+   */
+  mediaInlineLimit?: number
+
+  /**
+   * (`next --turbo` only) How Node.js modules are handled in the Edge Runtime.
+   * With `shims` (the default), `buffer`, `async_hooks` and `util` are mapped
+   * to edge-safe shims. Other Node.js modules are reported as unsupported.
+   */
+  edgeNodeCompat?: 'shims' | 'strict'
+
+  /**
+   * (`next --turbo` only) CommonJS packages which are pre-bundled into a single
+   * module for the client in development, to reduce the number of modules
+   * processed and loaded for dependency-heavy packages. The dependencies of
+   * the package are resolved like the other client modules, e.g. with the
+   * `browser` field of their `package.json`. Pre-bundled packages are cached in
+   * `.next/cache/turbopack/deps`.
+   */
+  optimizeDeps?: string[]
+
+  /**
+   * (`next --turbo` only) Caches the pages with `getStaticProps` or a
+   * `revalidate` segment config in development like `next start` does. Cached
+   * pages are served until they are revalidated, stale ones while they are
+   * rendered again in the background. The cache is stored in
+   * `.next/cache/isr`.
+   */
+  isrCache?: boolean
+
+  /**
+   * (`next --turbo` only) Enables the experimental garbage collection of the
+   * compiler with this memory limit in MB. It's passed to the compiler of
+   * `next dev` and `next build` when it starts. Defaults to no limit.
+   *
+   * This is the only memory control: Next.js doesn't keep an LRU of module or
+   * chunk results, so pages compiled during a session stay in memory unless
+   * the garbage collection of the compiler drops them.
+   */
+  memoryLimit?: number
+
+  /**
+   * (`next --turbo` only) The maximum number of route entries `next build`
+   * compiles at the same time. Defaults to the number of CPUs.
+   */
+  entryConcurrency?: number
 }
 
 export interface WebpackConfigContext {
@@ -511,6 +595,12 @@ export interface NextConfig extends Record<string, any> {
    * @see [Supported Compiler Options](https://nextjs.org/docs/advanced-features/compiler#supported-features)
    */
   compiler?: {
+    /**
+     * Minify the CSS of production builds with Turbopack.
+     *
+     * @default true
+     */
+    cssMinify?: boolean
     reactRemoveProperties?:
       | boolean
       | {
@@ -7,11 +7,13 @@ import { RouteMatch } from '../future/route-matches/route-match'
 import type { PageChecker, Route } from '../router'
 import { getMiddlewareMatchers } from '../../build/analysis/get-page-static-info'
 import { getMiddlewareRouteMatcher } from '../../shared/lib/router/utils/middleware-route-matcher'
+import { getNextPathnameInfo } from '../../shared/lib/router/utils/get-next-pathname-info'
 import { join } from 'path'
 
 type MiddlewareConfig = {
   matcher: string[]
   files: string[]
+  wasm?: { name: string; filePath: string }[]
 }
 type RouteResult =
   | {
@@ -107,7 +109,10 @@ export async function makeResolver(
             name: 'middleware',
             paths: middleware.files.map((file) => join(process.cwd(), file)),
             env: [],
-            wasm: [],
+            wasm: (middleware.wasm ?? []).map((binding) => ({
+              name: binding.name,
+              filePath: join(process.cwd(), binding.filePath),
+            })),
             assets: [],
           }
         }
@@ -125,6 +130,17 @@ export async function makeResolver(
 
   const routeResults = new WeakMap<any, string>()
   const routes = devServer.generateRoutes()
+
+  if (nextConfig.i18n) {
+    // Like rewrites, headers and redirects are matched against the pathname
+    // with its locale, which is the default locale when the pathname has none.
+    // `loadCustomRoutes` prefixes their sources with the locales, except for
+    // the ones with `locale: false`, which match the locale-prefixed pathname
+    // literally.
+    for (const route of [...routes.headers, ...routes.redirects]) {
+      route.matchesLocale = true
+    }
+  }
   // @ts-expect-error protected
   const catchAllMiddleware = devServer.generateCatchAllMiddlewareRoute(true)
 
@@ -142,7 +158,7 @@ export async function makeResolver(
       fn: async (req, _res, _params, parsedUrl) => {
         // clean up internal query values
         for (const key of Object.keys(parsedUrl.query || {})) {
-          if (key.startsWith('_next')) {
+          if (key.startsWith('_next') || key === '__nextLocale') {
             delete parsedUrl.query[key]
           }
         }
@@ -183,6 +199,26 @@ export async function makeResolver(
     devServer.attachRequestMeta(req, parsedUrl)
     ;(req as any)._initUrl = req.url
 
+    // An `assetPrefix` which is a path is served by this server, so the
+    // prefix is removed from the `_next` assets requested with it.
+    const assetPrefix = (nextConfig.assetPrefix || '').replace(/\/$/, '')
+    if (
+      assetPrefix.startsWith('/') &&
+      parsedUrl.pathname?.startsWith(`${assetPrefix}/_next/`)
+    ) {
+      parsedUrl.pathname = parsedUrl.pathname.slice(assetPrefix.length)
+    }
+
+    if (nextConfig.i18n) {
+      // The router adds this locale to the pathnames without one, like the
+      // server does after detecting the locale of a request.
+      const { locale } = getNextPathnameInfo(parsedUrl.pathname!, {
+        nextConfig,
+        parseData: false,
+      })
+      parsedUrl.query.__nextLocale = locale || nextConfig.i18n.defaultLocale
+    }
+
     await router.execute(req, res, parsedUrl)
 
     if (!res.originalResponse.headersSent) {